@@ -0,0 +1,45 @@
+use criterion::criterion_main;
+
+mod bench {
+    use criterion::{criterion_group, Criterion};
+    use multi_party_ecdsa::protocols::two_party_ecdsa::dkls::*;
+
+    pub fn bench_full_keygen_party_one_two(c: &mut Criterion) {
+        c.bench_function("keygen", move |b| {
+            b.iter(|| {
+                let (party_one_first_message, comm_witness, ec_key_pair_party1) =
+                    party_one::KeyGenFirstMsg::create_commitments();
+                let (party_two_first_message, ec_key_pair_party2, base_ot_sender) =
+                    party_two::KeyGenFirstMsg::create();
+                let (party_one_second_message, ot_sender) =
+                    party_one::KeyGenSecondMsg::verify_and_decommit(
+                        comm_witness,
+                        &party_two_first_message,
+                    )
+                    .expect("failed to verify and decommit");
+                let (_party_two_second_message, ot_receiver) =
+                    party_two::KeyGenSecondMsg::verify_commitments_and_dlog_proof(
+                        &party_one_first_message,
+                        &party_one_second_message,
+                        &base_ot_sender,
+                    )
+                    .expect("failed to verify commitments and DLog proof");
+
+                let _party_one_private = party_one::Party1Private::set_private_key(
+                    &ec_key_pair_party1,
+                    &party_two_first_message.public_share,
+                    ot_sender,
+                );
+                let _party_two_private =
+                    party_two::Party2Private::set_private_key(&ec_key_pair_party2, ot_receiver);
+            })
+        });
+    }
+
+    criterion_group! {
+    name = keygen;
+    config = Criterion::default().sample_size(10);
+    targets =self::bench_full_keygen_party_one_two}
+}
+
+criterion_main!(bench::keygen);
@@ -0,0 +1,66 @@
+use criterion::criterion_main;
+
+mod bench {
+    use criterion::{criterion_group, Criterion};
+    use curv::BigInt;
+    use multi_party_ecdsa::protocols::two_party_ecdsa::dkls::*;
+
+    pub fn bench_full_sign_party_one_two(c: &mut Criterion) {
+        // keygen is carried out once, signing reuses its base OTs
+        let (party_one_first_message, comm_witness, ec_key_pair_party1) =
+            party_one::KeyGenFirstMsg::create_commitments();
+        let (party_two_first_message, ec_key_pair_party2, base_ot_sender) =
+            party_two::KeyGenFirstMsg::create();
+        let (party_one_second_message, ot_sender) =
+            party_one::KeyGenSecondMsg::verify_and_decommit(comm_witness, &party_two_first_message)
+                .expect("failed to verify and decommit");
+        let (_party_two_second_message, ot_receiver) =
+            party_two::KeyGenSecondMsg::verify_commitments_and_dlog_proof(
+                &party_one_first_message,
+                &party_one_second_message,
+                &base_ot_sender,
+            )
+            .expect("failed to verify commitments and DLog proof");
+        let party1_private = party_one::Party1Private::set_private_key(
+            &ec_key_pair_party1,
+            &party_two_first_message.public_share,
+            ot_sender,
+        );
+        let party2_private =
+            party_two::Party2Private::set_private_key(&ec_key_pair_party2, ot_receiver);
+
+        c.bench_function("sign", move |b| {
+            b.iter(|| {
+                let (eph_party_two_first_message, eph_ec_key_pair_party2) =
+                    party_two::EphKeyGenFirstMsg::create(&party2_private);
+                let (eph_party_one_first_message, eph_ec_key_pair_party1) =
+                    party_one::EphKeyGenFirstMsg::create(
+                        &party1_private,
+                        &eph_party_two_first_message,
+                    )
+                    .expect("OT extension check failed");
+                let message = BigInt::from(1234);
+                let partial_sig = party_two::PartialSig::compute(
+                    eph_ec_key_pair_party2,
+                    &eph_party_one_first_message,
+                    &message,
+                )
+                .expect("invalid party one message");
+                party_one::compute_signature(
+                    &party1_private,
+                    eph_ec_key_pair_party1,
+                    &partial_sig,
+                    &message,
+                )
+                .expect("Invalid signature")
+            })
+        });
+    }
+
+    criterion_group! {
+    name = sign;
+    config = Criterion::default().sample_size(10);
+    targets =self::bench_full_sign_party_one_two}
+}
+
+criterion_main!(bench::sign);
@@ -0,0 +1,65 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use structopt::StructOpt;
+
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point};
+use multi_party_ecdsa::identity::{IdentityKey, Roster};
+
+/// Manages identity keys of parties attending gg20 keygen and signing
+#[derive(Debug, StructOpt)]
+enum Cli {
+    /// Generates a new identity key and prints its public key
+    Generate {
+        #[structopt(short, long)]
+        output: PathBuf,
+    },
+    /// Builds a roster from public keys of all parties, in order of their keygen indexes
+    Roster {
+        #[structopt(short, long)]
+        output: PathBuf,
+        /// Hex-encoded public keys printed by `generate`
+        public_keys: Vec<String>,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    match Cli::from_args() {
+        Cli::Generate { output } => {
+            let identity = IdentityKey::generate();
+            let serialized = serde_json::to_vec_pretty(&identity).context("serialize identity")?;
+            write_new(output, &serialized).await?;
+            println!("{}", hex::encode(&*identity.public_key().to_bytes(true)));
+        }
+        Cli::Roster {
+            output,
+            public_keys,
+        } => {
+            let public_keys = public_keys
+                .iter()
+                .map(|key| {
+                    let bytes = hex::decode(key).context("public key is not a hex string")?;
+                    Point::<Secp256k1>::from_bytes(&bytes).context("invalid public key")
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let roster = Roster::new(public_keys).context("invalid roster")?;
+            let serialized = serde_json::to_vec_pretty(&roster).context("serialize roster")?;
+            write_new(output, &serialized).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn write_new(path: PathBuf, content: &[u8]) -> Result<()> {
+    let mut file = tokio::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .await
+        .context("cannot create output file")?;
+    tokio::io::copy(&mut &*content, &mut file)
+        .await
+        .context("save output to file")?;
+    Ok(())
+}
@@ -1,13 +1,17 @@
 use anyhow::{anyhow, Context, Result};
 use futures::StreamExt;
+use std::convert::TryFrom;
 use std::path::PathBuf;
+use std::sync::Arc;
 use structopt::StructOpt;
 
+use multi_party_ecdsa::identity::{self, IdentityKey, Roster};
 use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::Keygen;
+use multi_party_ecdsa::secure_channel;
 use round_based::async_runtime::AsyncProtocol;
 
 mod gg20_sm_client;
-use gg20_sm_client::join_computation;
+use gg20_sm_client::join_computation_as;
 
 #[derive(Debug, StructOpt)]
 struct Cli {
@@ -18,17 +22,35 @@ struct Cli {
     #[structopt(short, long)]
     output: PathBuf,
 
+    /// Identity key of this party generated by `gg20_identity generate`
     #[structopt(short, long)]
-    index: u16,
+    identity: PathBuf,
+    /// Identity public keys of all parties built by `gg20_identity roster`, defines number of
+    /// parties and their indexes
+    #[structopt(long)]
+    roster: PathBuf,
+
     #[structopt(short, long)]
     threshold: u16,
-    #[structopt(short, long)]
-    number_of_parties: u16,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let args: Cli = Cli::from_args();
+    let identity = tokio::fs::read(args.identity)
+        .await
+        .context("cannot read identity key")?;
+    let identity: IdentityKey = serde_json::from_slice(&identity).context("parse identity key")?;
+    let roster = tokio::fs::read(args.roster)
+        .await
+        .context("cannot read roster")?;
+    let roster: Roster = serde_json::from_slice(&roster).context("parse roster")?;
+    let i = roster
+        .index_of(&identity.public_key())
+        .context("identity key is not in the roster")?;
+    let n = u16::try_from(roster.len()).context("roster is too large")?;
+    let (identity, roster) = (Arc::new(identity), Arc::new(roster));
+
     let mut output_file = tokio::fs::OpenOptions::new()
         .write(true)
         .create_new(true)
@@ -36,15 +58,50 @@ async fn main() -> Result<()> {
         .await
         .context("cannot create output file")?;
 
-    let (_i, incoming, outgoing) = join_computation(args.address, &args.room)
+    // Establish pairwise keys, so secret shares are not revealed to the relay and other parties.
+    // Handshake is signed by identity keys, so the relay can't substitute ephemeral keys.
+    let handshake_room = format!("{}-handshake", args.room);
+    let (_i, incoming, outgoing) = join_computation_as(args.address.clone(), &handshake_room, i)
+        .await
+        .context("join handshake")?;
+    let incoming =
+        identity::verify_incoming(roster.clone(), handshake_room.as_bytes().to_vec(), incoming);
+    let outgoing = identity::sign_outgoing(
+        identity.clone(),
+        handshake_room.as_bytes().to_vec(),
+        outgoing,
+    );
+    tokio::pin!(incoming);
+    tokio::pin!(outgoing);
+    // Room name only labels the context, session id is derived from fresh ephemeral keys of all
+    // parties, so it's different in every run even if the room is reused
+    let keys = secure_channel::handshake::<_, _, anyhow::Error>(
+        i,
+        n,
+        handshake_room.as_bytes(),
+        incoming,
+        outgoing,
+    )
+    .await
+    .context("handshake")?;
+    let session_id = keys.session_id().to_vec();
+    println!("Session id: {}", hex::encode(&session_id));
+    let keys = Arc::new(keys);
+
+    // Protocol messages are encrypted (if P2P) and then signed within the session
+    let (i, incoming, outgoing) = join_computation_as(args.address, &args.room, i)
         .await
         .context("join computation")?;
+    let incoming = identity::verify_incoming(roster.clone(), session_id.clone(), incoming);
+    let outgoing = identity::sign_outgoing(identity, session_id, outgoing);
+    let incoming = secure_channel::wrap_incoming(keys.clone(), incoming);
+    let outgoing = secure_channel::wrap_outgoing(keys, outgoing);
 
     let incoming = incoming.fuse();
     tokio::pin!(incoming);
     tokio::pin!(outgoing);
 
-    let keygen = Keygen::new(args.index, args.threshold, args.number_of_parties)?;
+    let keygen: Keygen = Keygen::with_roster(i, args.threshold, n, (*roster).clone())?;
     let output = AsyncProtocol::new(keygen, incoming, outgoing)
         .run()
         .await
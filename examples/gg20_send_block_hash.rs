@@ -2,28 +2,29 @@ use anyhow::{anyhow, Context, Result};
 use structopt::StructOpt;
 
 mod gg20_sm_client;
-use gg20_sm_client::{join_computation, Claims};
+use gg20_sm_client::join_computation;
 
 use std::path::PathBuf;
 
 use dotenv::dotenv;
 
+use multi_party_ecdsa::attestation::{self, Claims, EthereumRpc, JsonRpcTransport, Validator};
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::jwt::{self, UnsignedToken};
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
 use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::sign::{
     OfflineStage, SignManual,
 };
+use multi_party_ecdsa::transport::{http, mux, Transport};
 use round_based::async_runtime::AsyncProtocol;
 use round_based::Msg;
 
-use curv::arithmetic::Converter;
-use curv::BigInt;
+use curv::elliptic::curves::Secp256k1;
 
 use futures::{SinkExt, StreamExt, TryStreamExt};
 
-use jsonwebtoken::{Validation, Algorithm, decode, DecodingKey};
-
 #[derive(StructOpt, Debug)]
 struct Cli {
-    #[structopt(short, long, default_value= "http://localhost:8000/")]
+    #[structopt(short, long, default_value = "http://localhost:8000/")]
     address: surf::Url,
     #[structopt(short, long)]
     submission: surf::Url,
@@ -35,39 +36,81 @@ struct Cli {
     parties: Vec<u16>,
 }
 
+struct HttpNode {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl JsonRpcTransport for HttpNode {
+    fn send(&self, request: &str) -> Result<String, attestation::Error> {
+        self.client
+            .post(&self.url)
+            .body(request.to_owned())
+            .send()
+            .and_then(|mut response| response.text())
+            .map_err(|e| attestation::Error::Transport(Box::new(e)))
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenv().ok();
     let args: Cli = Cli::from_args();
 
+    let local_share = tokio::fs::read(args.local_share.clone())
+        .await
+        .context("cannot read local share")?;
+    let local_share: LocalKey<Secp256k1> =
+        serde_json::from_slice(&local_share).context("parse local share")?;
+    let public_key = local_share.public_key();
+
+    let rpc = std::env::var("ETHEREUM_RPC").context("ETHEREUM_RPC is not set")?;
+    let chain = std::env::var("CHAIN").unwrap_or_else(|_| "ethereum".to_owned());
+    let data_source = EthereumRpc::new(
+        chain,
+        HttpNode {
+            client: reqwest::Client::new(),
+            url: rpc,
+        },
+    );
+
+    // Claims to be attested are proposed in a separate room
     let (_i, incoming, _outgoing) =
-        join_computation::<String>(args.address.clone(), &format!("{}-jwt", args.room))
+        join_computation::<Claims>(args.address.clone(), &format!("{}-claims", args.room))
             .await
-            .context("join offline computation")?;
+            .context("join claims room")?;
 
     tokio::pin!(incoming);
 
+    // Every request is signed in its own pair of sessions multiplexed over one connection
+    let connection = http::join(args.address.clone(), &format!("{}-mux", args.room))
+        .await
+        .context("join signing room")?;
+    let (mux, driver) = mux::new(connection, mux::Config::default());
+    tokio::spawn(async move {
+        if let Err(err) = driver.await {
+            eprintln!("Signing connection is lost: {}", err);
+        }
+    });
+
     let mut stream_index = 0;
     let number_of_parties = args.parties.len();
-    
-    let key = std::env::var("JWT_SECRET").unwrap();
-    
-    let client = reqwest::Client::new();
 
-    while let Some(jwt) = incoming.next().await {
-        let token = jwt.unwrap().body;
-        println!("JWT token: {:?}", token);
+    let client = reqwest::Client::new();
 
-        let validation = Validation::new(Algorithm::HS256);
-        let token_data = decode::<Claims>(&token, &DecodingKey::from_secret(key.as_bytes()) , &validation).unwrap();
-        println!("Decoded token: {:?}", token_data);
+    while let Some(claims) = incoming.next().await {
+        let info = claims.context("receive claims")?.body;
+        println!("Received claims: {:?}", info);
 
-        let info = token_data.claims;
+        // Never propose claims to the committee that we couldn't confirm ourselves
+        if let Err(err) = data_source.validate(&info) {
+            eprintln!("Skipping claims {:?}: {}", info, err);
+            continue;
+        }
 
-        let (i, _, outgoing) =
-            join_computation(args.address.clone(), &args.room)
-                .await
-                .context("join computation")?;
+        let (i, _, outgoing) = join_computation(args.address.clone(), &args.room)
+            .await
+            .context("join computation")?;
 
         tokio::pin!(outgoing);
 
@@ -79,41 +122,26 @@ async fn main() -> Result<()> {
             })
             .await?;
 
-        let local_share = tokio::fs::read(args.local_share.clone())
-            .await
-            .context("cannot read local share")?;
-        let local_share = serde_json::from_slice(&local_share).context("parse local share")?;
-
-        let (i, incoming, outgoing) =
-            join_computation(args.address.clone(), &format!("{}-{}-offline", args.room, stream_index))
-                .await
-                .context("join offline computation")?;
+        let (incoming, outgoing) = mux.session(format!("{}-offline", stream_index))?.split();
 
-        let incoming = incoming.fuse();
-        tokio::pin!(incoming);
-        tokio::pin!(outgoing);
-
-        let signing = OfflineStage::new(i, args.parties.clone(), local_share)?;
-        let completed_offline_stage = AsyncProtocol::new(signing, incoming, outgoing)
+        let signing =
+            OfflineStage::new(mux.party_index(), args.parties.clone(), local_share.clone())?;
+        let completed_offline_stage = AsyncProtocol::new(signing, incoming.fuse(), outgoing)
             .run()
             .await
             .map_err(|e| anyhow!("protocol execution terminated with error: {}", e))?;
 
-        let (_i, incoming, _outgoing) = join_computation(args.address.clone(), &format!("{}-{}-online", args.room, stream_index))
-            .await
-            .context("join online computation")?;
+        let (incoming, _outgoing) = mux.session(format!("{}-online", stream_index))?.split();
 
         stream_index += 1;
 
-        tokio::pin!(incoming);
-
-        let (signing, _partial_signature) = SignManual::new(
-            BigInt::from_bytes(&bincode::serialize(&info).unwrap()),
-            completed_offline_stage,
-        )?;
+        // Committee issues ES256K token carrying the claims
+        let token = UnsignedToken::new(&public_key, &info)?;
+        let (signing, _partial_signature) =
+            SignManual::new(token.message(), completed_offline_stage)?;
 
         let partial_signatures: Vec<_> = incoming
-            .take(number_of_parties-1)
+            .take(number_of_parties - 1)
             .map_ok(|msg| msg.body)
             .try_collect()
             .await?;
@@ -122,16 +150,16 @@ async fn main() -> Result<()> {
             .complete(&partial_signatures)
             .context("online stage failed")?;
 
-        let r = BigInt::from_bytes(signature.r.to_bytes().as_ref()).to_str_radix(16);
-        let s = BigInt::from_bytes(signature.s.to_bytes().as_ref()).to_str_radix(16);
-        let v = signature.recid;
+        let token = token.complete(&signature);
+        jwt::verify::<Claims>(&token, &public_key).context("verify issued token")?;
 
-        client.post(&args.submission.to_string())
-            .body(format!(r#"{{"r": {}, "s": {}, "v": {}}}"#, r, s, v))
-            .send()?;
+        client
+            .post(args.submission.as_str())
+            .body(token.clone())
+            .send()
+            .context("submit token")?;
 
-        let signature = serde_json::to_string(&signature).context("serialize signature")?;
-        println!("Signature: {}", signature);
+        println!("Issued token: {}", token);
     }
 
     Ok(())
@@ -1,29 +1,28 @@
 use anyhow::{anyhow, Context, Result};
 use curv::elliptic::curves::Secp256k1;
+use multi_party_ecdsa::attestation::{
+    self, prepare_offline_stage, Claims, ClaimsOfflineStage, EthereumRpc, JsonRpcTransport,
+};
 use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+use multi_party_ecdsa::transport::mux::{self, Mux};
+use multi_party_ecdsa::transport::{http, Transport};
 use structopt::StructOpt;
 
 use dotenv::dotenv;
-use regex::Regex;
 
-use futures::{SinkExt, StreamExt, TryStreamExt};
-use curv::arithmetic::Converter;
-use curv::BigInt;
+use futures::{SinkExt, StreamExt};
 
 use std::path::PathBuf;
 
 mod gg20_sm_client;
-use gg20_sm_client::{join_computation, Claims};
+use gg20_sm_client::join_computation;
 
-use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::sign::{
-    OfflineStage, SignManual,
-};
 use round_based::async_runtime::AsyncProtocol;
 use round_based::Msg;
 
 #[derive(StructOpt, Debug)]
 struct Cli {
-    #[structopt(short, long, default_value= "http://localhost:8000/")]
+    #[structopt(short, long, default_value = "http://localhost:8000/")]
     address: surf::Url,
     #[structopt(short, long, default_value = "block-hashes")]
     room: String,
@@ -33,6 +32,21 @@ struct Cli {
     parties: Vec<u16>,
 }
 
+struct HttpNode {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl JsonRpcTransport for HttpNode {
+    fn send(&self, request: &str) -> Result<String, attestation::Error> {
+        self.client
+            .post(&self.url)
+            .body(request.to_owned())
+            .send()
+            .and_then(|mut response| response.text())
+            .map_err(|e| attestation::Error::Transport(Box::new(e)))
+    }
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -42,89 +56,108 @@ async fn main() -> Result<()> {
     let local_share = tokio::fs::read(args.local_share)
         .await
         .context("cannot read local share")?;
-    let local_share: LocalKey<Secp256k1> = serde_json::from_slice(&local_share).context("parse local share")?;
+    let local_share: LocalKey<Secp256k1> =
+        serde_json::from_slice(&local_share).context("parse local share")?;
 
-    let (_i, incoming, _outgoing) =
-        join_computation::<Claims>(args.address.clone(), &args.room)
-            .await
-            .context("join computation")?;
+    let (_i, incoming, _outgoing) = join_computation::<Claims>(args.address.clone(), &args.room)
+        .await
+        .context("join computation")?;
 
     tokio::pin!(incoming);
 
+    // Every request is signed in its own pair of sessions multiplexed over one connection,
+    // so signatures don't need rooms of their own and may run in parallel
+    let connection = http::join(args.address.clone(), &format!("{}-mux", args.room))
+        .await
+        .context("join signing room")?;
+    let (mux, driver) = mux::new(connection, mux::Config::default());
+    tokio::spawn(async move {
+        if let Err(err) = driver.await {
+            eprintln!("Signing connection is lost: {}", err);
+        }
+    });
+
     let mut stream_index = 0;
 
-    let client = reqwest::Client::new();
+    let rpc = std::env::var("ETHEREUM_RPC").context("ETHEREUM_RPC is not set")?;
+    let chain = std::env::var("CHAIN").unwrap_or_else(|_| "ethereum".to_owned());
+    let data_source = EthereumRpc::new(
+        chain,
+        HttpNode {
+            client: reqwest::Client::new(),
+            url: rpc,
+        },
+    );
 
     // fetch all the blocks info
     while let Some(block_info) = incoming.next().await {
-        let data_to_sign = block_info.unwrap();
+        let data_to_sign = block_info.context("receive claims")?;
         println!("Received to sign: {:?}", data_to_sign);
 
-        let block_number = &data_to_sign.body.blocknumber;
-        let expected_hash = &data_to_sign.body.parent_hash;
-
-        let rpc = std::env::var("ETHEREUM_RPC").unwrap();
-
-        let res = client.post(&rpc)
-            .body(format!(r#"{{"jsonrpc":"2.0","method":"eth_getBlockByNumber","params":["{}", true],"id":1}}"#, block_number))
-            .send()?
-            .text()?;
-
-        let re = Regex::new(r#""parentHash":"([^"]+)"#).unwrap();
-        let captures = re.captures(&res).unwrap();
-        let hash = &captures[1];
-
-        // Verifying hash
-        assert_eq!(expected_hash, hash, "Invalid hash");
-
-        //let sender = data_to_sign.sender;
-        
-        let (i, incoming, outgoing) =
-            join_computation(args.address.clone(), &format!("{}-{}-offline", args.room, stream_index))
-                .await
-                .context("join offline computation")?;
+        // Sessions are numbered by requests, so skipped ones must be counted too
+        let current_index = stream_index;
+        stream_index += 1;
 
-        let incoming = incoming.fuse();
-        tokio::pin!(incoming);
-        tokio::pin!(outgoing);
+        // Refuses to sign unless claims match our own view of the chain. Check goes before
+        // joining the sessions, so we never take part in signing data we couldn't confirm, and
+        // offline stage can only sign the claims that were checked.
+        let offline = match prepare_offline_stage(
+            &data_source,
+            &data_to_sign.body,
+            mux.party_index(),
+            args.parties.clone(),
+            local_share.clone(),
+        ) {
+            Ok(offline) => offline,
+            Err(err) => {
+                eprintln!("Refusing to sign {:?}: {}", data_to_sign.body, err);
+                // Other parties don't need to wait for us
+                mux.cancel(&format!("{}-offline", current_index)).await?;
+                continue;
+            }
+        };
+
+        let mux = mux.clone();
+        tokio::spawn(async move {
+            if let Err(err) = sign(mux, offline, current_index).await {
+                eprintln!("Signing request {} failed: {}", current_index, err);
+            }
+        });
+    }
 
-        println!("1------------------ Before offline ");
+    Ok(())
+}
 
-        let signing = OfflineStage::new(i, args.parties.clone(), local_share.clone())?;
-        let completed_offline_stage = AsyncProtocol::new(signing, incoming, outgoing)
-            .run()
-            .await
-            .map_err(|e| anyhow!("protocol execution terminated with error: {}", e))?;
+async fn sign(mux: Mux, offline: ClaimsOfflineStage, request: usize) -> Result<()> {
+    let i = mux.party_index();
+    let (incoming, outgoing) = mux.session(format!("{}-offline", request))?.split();
 
-        println!("2------------------ Offline completed ");
+    println!("1------------------ Before offline ");
 
-        let (i, _incoming, outgoing) = join_computation(args.address.clone(), &format!("{}-{}-online", args.room, stream_index))
-            .await
-            .context("join online computation")?;
+    let completed_offline_stage = AsyncProtocol::new(offline, incoming.fuse(), outgoing)
+        .run()
+        .await
+        .map_err(|e| anyhow!("protocol execution terminated with error: {}", e))?;
 
-        stream_index += 1;
+    println!("2------------------ Offline completed ");
 
-        tokio::pin!(outgoing);
+    let (_incoming, mut outgoing) = mux.session(format!("{}-online", request))?.split();
 
-        let (_signing, partial_signature) = SignManual::new(
-            BigInt::from_bytes(&bincode::serialize(&data_to_sign.body).unwrap()),
-            completed_offline_stage,
-        )?;
+    // Committee issues ES256K token carrying the claims
+    let (_token, _signing, partial_signature) = completed_offline_stage.sign_token()?;
 
-        println!("3------------------ Partial signature completed, sending to master node");
+    println!("3------------------ Partial signature completed, sending to master node");
 
-        outgoing
-            .send(Msg {
-                sender: i,
-                // TODO: receiver to master node
-                //receiver: Some(sender),
-                receiver: None,
-                body: partial_signature.clone(),
-            })
-            .await?;
+    outgoing
+        .send(Msg {
+            sender: i,
+            // TODO: receiver to master node
+            //receiver: Some(sender),
+            receiver: None,
+            body: partial_signature.clone(),
+        })
+        .await?;
 
-        println!("{:?} sent partial_signature {:?}", i, partial_signature);
-    }
-    
+    println!("{:?} sent partial_signature {:?}", i, partial_signature);
     Ok(())
 }
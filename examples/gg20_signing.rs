@@ -1,20 +1,26 @@
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use anyhow::{anyhow, Context, Result};
 use futures::{SinkExt, StreamExt, TryStreamExt};
 use structopt::StructOpt;
 
 use curv::arithmetic::Converter;
+use curv::elliptic::curves::secp256_k1::Secp256k1;
 use curv::BigInt;
 
-use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::sign::{
-    OfflineStage, SignManual,
+use multi_party_ecdsa::identity::{self, IdentityKey};
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::echo_broadcast::EchoBroadcast;
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::negotiation::{
+    self, Negotiation,
 };
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::sign::SignManual;
 use round_based::async_runtime::AsyncProtocol;
 use round_based::Msg;
 
 mod gg20_sm_client;
-use gg20_sm_client::join_computation;
+use gg20_sm_client::{join_computation, join_computation_as};
 
 #[derive(Debug, StructOpt)]
 struct Cli {
@@ -24,9 +30,13 @@ struct Cli {
     room: String,
     #[structopt(short, long)]
     local_share: PathBuf,
+    /// Identity key this party used at keygen
+    #[structopt(short, long)]
+    identity: PathBuf,
 
-    #[structopt(short, long, use_delimiter(true))]
-    parties: Vec<u16>,
+    /// Number of parties attending signing
+    #[structopt(short, long)]
+    number_of_parties: u16,
     #[structopt(short, long)]
     data_to_sign: String,
 }
@@ -37,27 +47,70 @@ async fn main() -> Result<()> {
     let local_share = tokio::fs::read(args.local_share)
         .await
         .context("cannot read local share")?;
-    let local_share = serde_json::from_slice(&local_share).context("parse local share")?;
-    let number_of_parties = args.parties.len();
+    let local_share: LocalKey<Secp256k1> =
+        serde_json::from_slice(&local_share).context("parse local share")?;
+    let identity = tokio::fs::read(args.identity)
+        .await
+        .context("cannot read identity key")?;
+    let identity: IdentityKey = serde_json::from_slice(&identity).context("parse identity key")?;
+    let n = args.number_of_parties;
 
+    // Agree on signing indexes: parties join in arbitrary order, so the index issued by
+    // the room has nothing to do with the index party had at keygen
     let (i, incoming, outgoing) =
-        join_computation(args.address.clone(), &format!("{}-offline", args.room))
+        join_computation(args.address.clone(), &format!("{}-negotiation", args.room))
             .await
-            .context("join offline computation")?;
+            .context("join negotiation")?;
+    let incoming = incoming.fuse();
+    tokio::pin!(incoming);
+    tokio::pin!(outgoing);
+
+    let negotiation =
+        Negotiation::with_identity(i, n, &local_share, args.room.as_bytes(), &identity)?;
+    let negotiation = EchoBroadcast::new(negotiation)?;
+    let agreement = AsyncProtocol::new(negotiation, incoming, outgoing)
+        .run()
+        .await
+        .map_err(|e| anyhow!("negotiation terminated with error: {}", e))?;
+    let agreement = Arc::new(agreement);
+    // Messages are signed with signing indexes, so they're verified against identities of
+    // the agreed committee
+    let roster = local_share
+        .roster
+        .as_ref()
+        .context("local share has no roster")?
+        .select(agreement.s_l())?;
+    let (identity, roster) = (Arc::new(identity), Arc::new(roster));
+
+    let offline_room = format!("{}-offline", args.room);
+    let (_i, incoming, outgoing) = join_computation_as(args.address.clone(), &offline_room, i)
+        .await
+        .context("join offline computation")?;
+    let incoming = negotiation::remap_incoming(agreement.clone(), incoming);
+    let outgoing = negotiation::remap_outgoing(agreement.clone(), outgoing);
+    let incoming =
+        identity::verify_incoming(roster.clone(), offline_room.as_bytes().to_vec(), incoming);
+    let outgoing =
+        identity::sign_outgoing(identity.clone(), offline_room.as_bytes().to_vec(), outgoing);
 
     let incoming = incoming.fuse();
     tokio::pin!(incoming);
     tokio::pin!(outgoing);
 
-    let signing = OfflineStage::new(i, args.parties, local_share)?;
+    let signing = agreement.offline_stage(local_share)?;
     let completed_offline_stage = AsyncProtocol::new(signing, incoming, outgoing)
         .run()
         .await
         .map_err(|e| anyhow!("protocol execution terminated with error: {}", e))?;
 
-    let (i, incoming, outgoing) = join_computation(args.address, &format!("{}-online", args.room))
+    let online_room = format!("{}-online", args.room);
+    let (_i, incoming, outgoing) = join_computation_as(args.address, &online_room, i)
         .await
         .context("join online computation")?;
+    let incoming = negotiation::remap_incoming(agreement.clone(), incoming);
+    let outgoing = negotiation::remap_outgoing(agreement.clone(), outgoing);
+    let incoming = identity::verify_incoming(roster, online_room.as_bytes().to_vec(), incoming);
+    let outgoing = identity::sign_outgoing(identity, online_room.as_bytes().to_vec(), outgoing);
 
     tokio::pin!(incoming);
     tokio::pin!(outgoing);
@@ -69,14 +122,14 @@ async fn main() -> Result<()> {
 
     outgoing
         .send(Msg {
-            sender: i,
+            sender: agreement.party_index(),
             receiver: None,
             body: partial_signature,
         })
         .await?;
 
     let partial_signatures: Vec<_> = incoming
-        .take(number_of_parties - 1)
+        .take(usize::from(n) - 1)
         .map_ok(|msg| msg.body)
         .try_collect()
         .await?;
@@ -1,8 +1,10 @@
 use std::collections::hash_map::{Entry, HashMap};
+use std::collections::HashSet;
 use std::sync::{
     atomic::{AtomicU16, Ordering},
-    Arc,
+    Arc, Mutex,
 };
+use std::time::{Duration, Instant};
 
 use futures::Stream;
 use rocket::data::ToByteUnit;
@@ -44,21 +46,30 @@ async fn issue_idx(db: &State<Db>, room_id: &str) -> Json<IssuedUniqueIdx> {
 }
 
 #[rocket::post("/rooms/<room_id>/broadcast", data = "<message>")]
-async fn broadcast(db: &State<Db>, room_id: &str, message: String) -> Status {
+async fn broadcast(db: &State<Db>, room_id: &str, key: IdempotencyKey, message: String) -> Status {
     let room = db.get_room_or_create_empty(room_id).await;
-    room.publish(message).await;
+    room.publish(message, key.0).await;
     Status::Ok
 }
 
+/// Room that has no subscribers and saw no activity for that long is evicted
+///
+/// Clients may all be between reconnects at the same time, so a room must outlive its
+/// subscribers long enough for them to resume with `Last-Event-ID`.
+const ROOM_IDLE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
 struct Db {
     rooms: RwLock<HashMap<String, Arc<Room>>>,
 }
 
 struct Room {
     messages: RwLock<Vec<String>>,
+    /// Idempotency keys of messages published to this room, dropped along with the room
+    published_keys: RwLock<HashSet<String>>,
     message_appeared: Notify,
     subscribers: AtomicU16,
     next_idx: AtomicU16,
+    last_activity: Mutex<Instant>,
 }
 
 impl Db {
@@ -71,20 +82,20 @@ impl Db {
     pub async fn get_room_or_create_empty(&self, room_id: &str) -> Arc<Room> {
         let rooms = self.rooms.read().await;
         if let Some(room) = rooms.get(room_id) {
-            // If no one is watching this room - we need to clean it up first
-            if !room.is_abandoned() {
-                return room.clone();
-            }
+            room.touch();
+            return room.clone();
         }
         drop(rooms);
 
         let mut rooms = self.rooms.write().await;
+        // Evict idle rooms along with their messages and idempotency keys, so memory isn't
+        // taken by rooms nobody uses anymore
+        let now = Instant::now();
+        rooms.retain(|id, room| id == room_id || !room.is_idle(now));
         match rooms.entry(room_id.to_owned()) {
-            Entry::Occupied(entry) if !entry.get().is_abandoned() => entry.get().clone(),
             Entry::Occupied(entry) => {
-                let room = Arc::new(Room::empty());
-                *entry.into_mut() = room.clone();
-                room
+                entry.get().touch();
+                entry.get().clone()
             }
             Entry::Vacant(entry) => entry.insert(Arc::new(Room::empty())).clone(),
         }
@@ -95,14 +106,22 @@ impl Room {
     pub fn empty() -> Self {
         Self {
             messages: RwLock::new(vec![]),
+            published_keys: RwLock::new(HashSet::new()),
             message_appeared: Notify::new(),
             subscribers: AtomicU16::new(0),
             next_idx: AtomicU16::new(1),
+            last_activity: Mutex::new(Instant::now()),
         }
     }
 
-    pub async fn publish(self: &Arc<Self>, message: String) {
+    pub async fn publish(self: &Arc<Self>, message: String, key: Option<String>) {
         let mut messages = self.messages.write().await;
+        if let Some(key) = key {
+            // Message is retransmitted by a client that didn't get a response
+            if !self.published_keys.write().await.insert(key) {
+                return;
+            }
+        }
         messages.push(message);
         self.message_appeared.notify_waiters();
     }
@@ -115,8 +134,15 @@ impl Room {
         }
     }
 
-    pub fn is_abandoned(&self) -> bool {
+    /// Room has no subscribers and nothing happened in it for [ROOM_IDLE_TIMEOUT]
+    pub fn is_idle(&self, now: Instant) -> bool {
         self.subscribers.load(Ordering::SeqCst) == 0
+            && now.saturating_duration_since(*self.last_activity.lock().unwrap())
+                > ROOM_IDLE_TIMEOUT
+    }
+
+    fn touch(&self) {
+        *self.last_activity.lock().unwrap() = Instant::now();
     }
 
     pub fn issue_unique_idx(&self) -> u16 {
@@ -147,6 +173,8 @@ impl Subscription {
 
 impl Drop for Subscription {
     fn drop(&mut self) {
+        // Idle timeout starts from the moment the last subscriber left
+        self.room.touch();
         self.room.subscribers.fetch_sub(1, Ordering::SeqCst);
     }
 }
@@ -166,13 +194,26 @@ impl<'r> FromRequest<'r> for LastEventId {
         match header {
             Some(Ok(last_seen_msg)) => Outcome::Success(LastEventId(Some(last_seen_msg))),
             Some(Err(_parse_err)) => {
-                Outcome::Failure((Status::BadRequest, "last seen msg id is not valid"))
+                Outcome::Error((Status::BadRequest, "last seen msg id is not valid"))
             }
             None => Outcome::Success(LastEventId(None)),
         }
     }
 }
 
+/// Represents a header Idempotency-Key
+struct IdempotencyKey(Option<String>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IdempotencyKey {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let key = request.headers().get_one("Idempotency-Key");
+        Outcome::Success(IdempotencyKey(key.map(String::from)))
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 struct IssuedUniqueIdx {
     unique_idx: u16,
@@ -0,0 +1,73 @@
+use serde_json::{json, Value};
+
+use super::{parse_hash, DataSource, Error, Result};
+
+/// Transport that delivers JSON-RPC requests to a node
+///
+/// Library doesn't depend on any HTTP client, so transport is provided by the caller.
+pub trait JsonRpcTransport {
+    /// Sends serialized request, returns raw response body
+    fn send(&self, request: &str) -> Result<String>;
+}
+
+/// Data source backed by Ethereum JSON-RPC node
+pub struct EthereumRpc<T> {
+    chain: String,
+    transport: T,
+}
+
+impl<T: JsonRpcTransport> EthereumRpc<T> {
+    /// Constructs data source for `chain`, requests are sent via `transport`
+    pub fn new(chain: impl Into<String>, transport: T) -> Self {
+        Self {
+            chain: chain.into(),
+            transport,
+        }
+    }
+
+    /// Transport used to reach the node
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+            "id": 1,
+        });
+        let response = self.transport.send(&request.to_string())?;
+        let mut response: Value =
+            serde_json::from_str(&response).map_err(|e| Error::MalformedResponse(e.to_string()))?;
+
+        if let Some(error) = response.get("error") {
+            return Err(Error::Rpc(error.to_string()));
+        }
+        match response.get_mut("result") {
+            Some(result) => Ok(result.take()),
+            None => Err(Error::MalformedResponse("missing result".to_owned())),
+        }
+    }
+}
+
+impl<T: JsonRpcTransport> DataSource for EthereumRpc<T> {
+    fn chain(&self) -> &str {
+        &self.chain
+    }
+
+    fn parent_hash(&self, block_number: u64) -> Result<[u8; 32]> {
+        let block = self.call(
+            "eth_getBlockByNumber",
+            json!([format!("{:#x}", block_number), false]),
+        )?;
+        if block.is_null() {
+            return Err(Error::UnknownBlock(block_number));
+        }
+        let parent_hash = block
+            .get("parentHash")
+            .and_then(Value::as_str)
+            .ok_or_else(|| Error::MalformedResponse("missing parentHash".to_owned()))?;
+        parse_hash(parent_hash)
+    }
+}
@@ -0,0 +1,37 @@
+use std::collections::HashMap;
+
+use super::{DataSource, Error, Result};
+
+/// Local in-memory data source, useful for tests and demos
+#[derive(Debug, Clone, Default)]
+pub struct MockDataSource {
+    chain: String,
+    parent_hashes: HashMap<u64, [u8; 32]>,
+}
+
+impl MockDataSource {
+    pub fn new(chain: impl Into<String>) -> Self {
+        Self {
+            chain: chain.into(),
+            parent_hashes: HashMap::new(),
+        }
+    }
+
+    /// Sets parent hash of block `block_number`
+    pub fn insert(&mut self, block_number: u64, parent_hash: [u8; 32]) {
+        self.parent_hashes.insert(block_number, parent_hash);
+    }
+}
+
+impl DataSource for MockDataSource {
+    fn chain(&self) -> &str {
+        &self.chain
+    }
+
+    fn parent_hash(&self, block_number: u64) -> Result<[u8; 32]> {
+        self.parent_hashes
+            .get(&block_number)
+            .copied()
+            .ok_or(Error::UnknownBlock(block_number))
+    }
+}
@@ -0,0 +1,356 @@
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+//! # Threshold oracle attestations
+//!
+//! Committee attests facts about external data (e.g. parent hash of a block) by threshold signing
+//! them with GG20. A single attestation goes as follows:
+//!
+//! 1. Coordinator proposes [Claims] to the committee
+//! 2. Every party independently checks claims against its own [DataSource] (see [Validator])
+//!    before it joins signing, so honest party never signs data it couldn't confirm.
+//!    [prepare_offline_stage] does the check and constructs [ClaimsOfflineStage] in one go
+//! 3. Parties sign [Claims::message] (hash of [canonical encoding](Claims::canonical_encoding))
+//!    via [ClaimsSigning::sign]. Completed offline stage is bound to the validated claims, so it
+//!    can't be used to sign anything else
+//! 4. Resulting signature is delivered via [Submitter]
+//!
+//! Alternatively, committee can issue claims as ES256K JWT: at step 3 parties sign a token
+//! carrying the claims via [ClaimsSigning::sign_token], and the completed token is delivered
+//! instead of raw signature.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use curv::arithmetic::traits::*;
+use curv::BigInt;
+use round_based::{Msg, StateMachine};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::protocols::multi_party_ecdsa::gg_2020::jwt::{self, UnsignedToken};
+use crate::protocols::multi_party_ecdsa::gg_2020::party_i::SignatureRecid;
+use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::sign::{
+    self, CompletedOfflineStage, OfflineProtocolMessage, OfflineStage, PartialSignature, SignError,
+    SignManual,
+};
+use curv::elliptic::curves::secp256_k1::Secp256k1;
+
+mod ethereum;
+mod mock;
+
+pub use ethereum::{EthereumRpc, JsonRpcTransport};
+pub use mock::MockDataSource;
+
+/// Domain separator prepended to canonical encoding of claims
+pub const DOMAIN_SEPARATOR: &[u8] = b"multi-party-ecdsa/attestation/v1";
+
+/// Claims attested by committee
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Claims {
+    pub chain: String,
+    pub parent_hash: String,
+    pub blocknumber: String,
+    pub exp: usize,
+}
+
+impl Claims {
+    /// Block number, parsed from hex quantity (e.g. `0x1b4`)
+    pub fn block_number(&self) -> Result<u64> {
+        parse_quantity(&self.blocknumber)
+    }
+
+    /// Parent hash, parsed from 32 bytes hex string
+    pub fn parent_hash(&self) -> Result<[u8; 32]> {
+        parse_hash(&self.parent_hash)
+    }
+
+    /// Canonical encoding of claims
+    ///
+    /// Unlike serde-based encodings, it doesn't depend on textual representation of numbers and
+    /// hashes (`0x01b4` and `0x1B4` encode the same way), so every party derives the same message
+    /// as long as it agrees on the claims. Encoding is:
+    ///
+    /// ```text
+    /// DOMAIN_SEPARATOR || len(chain) as u32 BE || chain || block_number as u64 BE
+    ///     || parent_hash (32 bytes) || exp as u64 BE
+    /// ```
+    pub fn canonical_encoding(&self) -> Result<Vec<u8>> {
+        let chain_len = u32::try_from(self.chain.len()).map_err(|_| Error::ChainNameTooLong)?;
+        let mut encoded = Vec::with_capacity(DOMAIN_SEPARATOR.len() + self.chain.len() + 52);
+        encoded.extend_from_slice(DOMAIN_SEPARATOR);
+        encoded.extend_from_slice(&chain_len.to_be_bytes());
+        encoded.extend_from_slice(self.chain.as_bytes());
+        encoded.extend_from_slice(&self.block_number()?.to_be_bytes());
+        encoded.extend_from_slice(&self.parent_hash()?);
+        encoded.extend_from_slice(&(self.exp as u64).to_be_bytes());
+        Ok(encoded)
+    }
+
+    /// Message to be signed by committee: SHA-256 hash of [canonical encoding](Self::canonical_encoding)
+    pub fn message(&self) -> Result<BigInt> {
+        Ok(BigInt::from_bytes(&Sha256::digest(
+            &self.canonical_encoding()?,
+        )))
+    }
+}
+
+/// Source of data that claims are checked against
+pub trait DataSource {
+    /// Name of the chain the source serves (matched against [Claims::chain])
+    fn chain(&self) -> &str;
+    /// Returns parent hash of block with given number
+    fn parent_hash(&self, block_number: u64) -> Result<[u8; 32]>;
+}
+
+/// Per-party check that must pass before party agrees to sign claims
+pub trait Validator {
+    fn validate(&self, claims: &Claims) -> Result<()>;
+}
+
+/// Any data source validates claims by comparing them with data it serves
+///
+/// Claims that expired (`exp` is not in the future) are rejected.
+impl<D: DataSource> Validator for D {
+    fn validate(&self, claims: &Claims) -> Result<()> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::ZERO)
+            .as_secs();
+        if claims.exp as u64 <= now {
+            return Err(Error::Expired { exp: claims.exp });
+        }
+        if claims.chain != self.chain() {
+            return Err(Error::ChainMismatch {
+                expected: self.chain().to_owned(),
+                actual: claims.chain.clone(),
+            });
+        }
+        let expected = self.parent_hash(claims.block_number()?)?;
+        if claims.parent_hash()? != expected {
+            return Err(Error::ParentHashMismatch);
+        }
+        Ok(())
+    }
+}
+
+/// Validates claims and constructs offline stage of signing them
+///
+/// Takes the same arguments as [OfflineStage::new], but refuses to proceed unless `validator`
+/// accepts the claims.
+pub fn prepare_offline_stage<V: Validator + ?Sized>(
+    validator: &V,
+    claims: &Claims,
+    i: u16,
+    s_l: Vec<u16>,
+    local_key: LocalKey<Secp256k1>,
+) -> Result<ClaimsOfflineStage> {
+    validator.validate(claims)?;
+    // Make sure claims can be encoded before running the protocol
+    claims.message()?;
+    Ok(ClaimsOfflineStage {
+        offline: OfflineStage::new(i, s_l, local_key).map_err(Error::OfflineStage)?,
+        claims: claims.clone(),
+    })
+}
+
+/// [OfflineStage] bound to validated claims
+///
+/// Runs exactly as [OfflineStage], but completed offline stage is wrapped into [ClaimsSigning]
+/// that only signs the claims it was [prepared](prepare_offline_stage) for.
+pub struct ClaimsOfflineStage {
+    offline: OfflineStage,
+    claims: Claims,
+}
+
+impl StateMachine for ClaimsOfflineStage {
+    type MessageBody = OfflineProtocolMessage;
+    type Err = sign::Error;
+    type Output = ClaimsSigning;
+
+    fn handle_incoming(&mut self, msg: Msg<Self::MessageBody>) -> Result<(), Self::Err> {
+        self.offline.handle_incoming(msg)
+    }
+
+    fn message_queue(&mut self) -> &mut Vec<Msg<Self::MessageBody>> {
+        self.offline.message_queue()
+    }
+
+    fn wants_to_proceed(&self) -> bool {
+        self.offline.wants_to_proceed()
+    }
+
+    fn proceed(&mut self) -> Result<(), Self::Err> {
+        self.offline.proceed()
+    }
+
+    fn round_timeout(&self) -> Option<Duration> {
+        self.offline.round_timeout()
+    }
+
+    fn round_timeout_reached(&mut self) -> Self::Err {
+        self.offline.round_timeout_reached()
+    }
+
+    fn is_finished(&self) -> bool {
+        self.offline.is_finished()
+    }
+
+    fn pick_output(&mut self) -> Option<Result<Self::Output, Self::Err>> {
+        let claims = &self.claims;
+        self.offline.pick_output().map(|result| {
+            result.map(|completed| ClaimsSigning {
+                completed,
+                claims: claims.clone(),
+            })
+        })
+    }
+
+    fn current_round(&self) -> u16 {
+        self.offline.current_round()
+    }
+
+    fn total_rounds(&self) -> Option<u16> {
+        self.offline.total_rounds()
+    }
+
+    fn party_ind(&self) -> u16 {
+        self.offline.party_ind()
+    }
+
+    fn parties(&self) -> u16 {
+        self.offline.parties()
+    }
+}
+
+impl fmt::Debug for ClaimsOfflineStage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{{Attestation of {:?}: {:?}}}",
+            self.claims, self.offline
+        )
+    }
+}
+
+/// Completed offline stage that can only sign the claims it was prepared for
+pub struct ClaimsSigning {
+    completed: CompletedOfflineStage,
+    claims: Claims,
+}
+
+impl ClaimsSigning {
+    /// Claims that were validated before offline stage
+    pub fn claims(&self) -> &Claims {
+        &self.claims
+    }
+
+    /// Signs [Claims::message], returns local signing state and partial signature to broadcast
+    pub fn sign(self) -> Result<(SignManual, PartialSignature)> {
+        let message = self.claims.message()?;
+        SignManual::new(message, self.completed).map_err(Error::Sign)
+    }
+
+    /// Signs ES256K token carrying the claims, returns the token along with local signing state
+    /// and partial signature to broadcast
+    pub fn sign_token(self) -> Result<(UnsignedToken, SignManual, PartialSignature)> {
+        let token =
+            UnsignedToken::new(self.completed.public_key(), &self.claims).map_err(Error::Token)?;
+        let (signing, partial) =
+            SignManual::new(token.message(), self.completed).map_err(Error::Sign)?;
+        Ok((token, signing, partial))
+    }
+}
+
+/// Signature in a form expected by consumers of attestations
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Submission {
+    pub r: BigInt,
+    pub s: BigInt,
+    /// Recovery id (`0` or `1` for all practical purposes)
+    pub v: u8,
+}
+
+impl From<&SignatureRecid> for Submission {
+    fn from(signature: &SignatureRecid) -> Self {
+        Self {
+            r: signature.r.to_bigint(),
+            s: signature.s.to_bigint(),
+            v: signature.recid,
+        }
+    }
+}
+
+/// Delivers signed attestations to its consumer
+pub trait Submitter {
+    fn submit(&self, claims: &Claims, submission: &Submission) -> Result<()>;
+}
+
+fn parse_quantity(quantity: &str) -> Result<u64> {
+    let digits = quantity
+        .strip_prefix("0x")
+        .ok_or_else(|| Error::InvalidQuantity(quantity.to_owned()))?;
+    u64::from_str_radix(digits, 16).map_err(|_| Error::InvalidQuantity(quantity.to_owned()))
+}
+
+fn parse_hash(hash: &str) -> Result<[u8; 32]> {
+    let digits = hash
+        .strip_prefix("0x")
+        .ok_or_else(|| Error::InvalidHash(hash.to_owned()))?;
+    let mut bytes = [0u8; 32];
+    hex::decode_to_slice(digits, &mut bytes).map_err(|_| Error::InvalidHash(hash.to_owned()))?;
+    Ok(bytes)
+}
+
+type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("invalid hex quantity: {0:?}")]
+    InvalidQuantity(String),
+    #[error("invalid 32 bytes hash: {0:?}")]
+    InvalidHash(String),
+    #[error("chain name is too long")]
+    ChainNameTooLong,
+    #[error("claims are about chain {actual:?}, but data source serves {expected:?}")]
+    ChainMismatch { expected: String, actual: String },
+    #[error("block {0} is unknown to data source")]
+    UnknownBlock(u64),
+    #[error("parent hash doesn't match data source")]
+    ParentHashMismatch,
+    #[error("claims expired at {exp}")]
+    Expired { exp: usize },
+    #[error("json-rpc transport: {0}")]
+    Transport(#[source] Box<dyn std::error::Error + Send + Sync>),
+    #[error("json-rpc: malformed response: {0}")]
+    MalformedResponse(String),
+    #[error("json-rpc: node returned error: {0}")]
+    Rpc(String),
+    #[error("construct offline stage: {0}")]
+    OfflineStage(sign::Error),
+    #[error("sign claims: {0}")]
+    Sign(SignError),
+    #[error("issue token: {0}")]
+    Token(#[source] jwt::Error),
+    #[error("submit attestation: {0}")]
+    Submit(#[source] Box<dyn std::error::Error + Send + Sync>),
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,196 @@
+use std::cell::RefCell;
+
+use round_based::dev::Simulation;
+
+use crate::protocols::multi_party_ecdsa::gg_2020::party_i::verify;
+use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+
+use super::*;
+
+const PARENT_HASH: &str = "0x88e96d4537bea4d9c05d12549907b32561d3bf31f45aae734cdc119f13406cb6";
+
+fn claims() -> Claims {
+    Claims {
+        chain: "ethereum".to_owned(),
+        parent_hash: PARENT_HASH.to_owned(),
+        blocknumber: "0x1b4".to_owned(),
+        exp: 4_000_000_000,
+    }
+}
+
+fn data_source() -> MockDataSource {
+    let mut source = MockDataSource::new("ethereum");
+    source.insert(0x1b4, claims().parent_hash().unwrap());
+    source
+}
+
+struct FakeNode {
+    response: String,
+    requests: RefCell<Vec<String>>,
+}
+
+impl JsonRpcTransport for FakeNode {
+    fn send(&self, request: &str) -> Result<String> {
+        self.requests.borrow_mut().push(request.to_owned());
+        Ok(self.response.clone())
+    }
+}
+
+#[test]
+fn canonical_encoding_ignores_textual_representation() {
+    let claims = claims();
+    let same_claims = Claims {
+        parent_hash: PARENT_HASH.to_uppercase().replacen("0X", "0x", 1),
+        blocknumber: "0x01B4".to_owned(),
+        ..claims.clone()
+    };
+    assert_eq!(
+        claims.canonical_encoding().unwrap(),
+        same_claims.canonical_encoding().unwrap()
+    );
+    assert_eq!(claims.message().unwrap(), same_claims.message().unwrap());
+
+    let other_claims = Claims {
+        blocknumber: "0x1b5".to_owned(),
+        ..claims.clone()
+    };
+    assert_ne!(claims.message().unwrap(), other_claims.message().unwrap());
+
+    let malformed = Claims {
+        blocknumber: "436".to_owned(),
+        ..claims
+    };
+    assert!(matches!(
+        malformed.canonical_encoding(),
+        Err(Error::InvalidQuantity(_))
+    ));
+}
+
+#[test]
+fn data_source_validates_claims() {
+    let source = data_source();
+    source.validate(&claims()).unwrap();
+
+    let wrong_hash = Claims {
+        parent_hash: format!("0x{}", "00".repeat(32)),
+        ..claims()
+    };
+    assert!(matches!(
+        source.validate(&wrong_hash),
+        Err(Error::ParentHashMismatch)
+    ));
+
+    let wrong_chain = Claims {
+        chain: "goerli".to_owned(),
+        ..claims()
+    };
+    assert!(matches!(
+        source.validate(&wrong_chain),
+        Err(Error::ChainMismatch { .. })
+    ));
+
+    let unknown_block = Claims {
+        blocknumber: "0x1b5".to_owned(),
+        ..claims()
+    };
+    assert!(matches!(
+        source.validate(&unknown_block),
+        Err(Error::UnknownBlock(0x1b5))
+    ));
+
+    let expired = Claims {
+        exp: 1_700_000_000,
+        ..claims()
+    };
+    assert!(matches!(
+        source.validate(&expired),
+        Err(Error::Expired { exp: 1_700_000_000 })
+    ));
+}
+
+#[test]
+fn prepared_offline_stage_signs_validated_claims() {
+    let keys = simulate_keygen(1, 3);
+    let source = data_source();
+    let s_l = vec![1, 3];
+
+    let expired = Claims {
+        exp: 1_700_000_000,
+        ..claims()
+    };
+    assert!(matches!(
+        prepare_offline_stage(&source, &expired, 1, s_l.clone(), keys[0].clone()),
+        Err(Error::Expired { .. })
+    ));
+
+    let mut simulation = Simulation::new();
+    for (i, &j) in (1..).zip(&s_l) {
+        let key = keys[usize::from(j - 1)].clone();
+        simulation
+            .add_party(prepare_offline_stage(&source, &claims(), i, s_l.clone(), key).unwrap());
+    }
+    let (parties, partials): (Vec<_>, Vec<_>) = simulation
+        .run()
+        .unwrap()
+        .into_iter()
+        .map(|signing| {
+            assert_eq!(signing.claims(), &claims());
+            signing.sign().unwrap()
+        })
+        .unzip();
+    let signature = parties
+        .into_iter()
+        .next()
+        .unwrap()
+        .complete(&partials[1..])
+        .unwrap();
+    verify(
+        &signature,
+        &keys[0].public_key(),
+        &claims().message().unwrap(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn ethereum_rpc_fetches_parent_hash() {
+    let node = FakeNode {
+        response: format!(
+            r#"{{"jsonrpc":"2.0","id":1,"result":{{"number":"0x1b4","parentHash":"{}"}}}}"#,
+            PARENT_HASH
+        ),
+        requests: RefCell::new(vec![]),
+    };
+    let source = EthereumRpc::new("ethereum", node);
+    source.validate(&claims()).unwrap();
+
+    let request: serde_json::Value =
+        serde_json::from_str(&source.transport().requests.borrow()[0]).unwrap();
+    assert_eq!(request["method"], "eth_getBlockByNumber");
+    assert_eq!(request["params"][0], "0x1b4");
+}
+
+#[test]
+fn ethereum_rpc_reports_errors() {
+    let unknown = EthereumRpc::new(
+        "ethereum",
+        FakeNode {
+            response: r#"{"jsonrpc":"2.0","id":1,"result":null}"#.to_owned(),
+            requests: RefCell::new(vec![]),
+        },
+    );
+    assert!(matches!(
+        unknown.parent_hash(0x1b4),
+        Err(Error::UnknownBlock(0x1b4))
+    ));
+
+    let failing = EthereumRpc::new(
+        "ethereum",
+        FakeNode {
+            response: r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32000,"message":"boom"}}"#
+                .to_owned(),
+            requests: RefCell::new(vec![]),
+        },
+    );
+    assert!(matches!(failing.parent_hash(0x1b4), Err(Error::Rpc(_))));
+}
@@ -0,0 +1,83 @@
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use structopt::StructOpt;
+
+use multi_party_ecdsa::relay::{self, Config, Relay};
+
+/// Relay server of HTTP/SSE rooms
+#[derive(Debug, StructOpt)]
+struct Cli {
+    #[structopt(long, default_value = "127.0.0.1")]
+    address: IpAddr,
+    #[structopt(short, long, default_value = "8000")]
+    port: u16,
+
+    /// Room is removed once it saw no activity for this many seconds
+    #[structopt(long, default_value = "3600")]
+    room_ttl: u64,
+    /// Capacity of rooms whose capacity isn't declared by parties
+    #[structopt(long, default_value = "16")]
+    default_capacity: u16,
+    /// Largest capacity parties may declare
+    #[structopt(long, default_value = "256")]
+    max_capacity: u16,
+    /// Room accepts at most `capacity * max_messages_per_party` messages
+    #[structopt(long, default_value = "1000")]
+    max_messages_per_party: usize,
+    /// Size limit of a single message, in bytes
+    #[structopt(long, default_value = "1048576")]
+    max_message_size: usize,
+    /// Limit of total size of messages in a room, in bytes
+    #[structopt(long, default_value = "16777216")]
+    max_room_size: usize,
+    /// Maximum number of rooms held at the same time
+    #[structopt(long, default_value = "128")]
+    max_rooms: usize,
+
+    /// Directory rooms are logged to, so they're restored after restart
+    #[structopt(long)]
+    log_dir: Option<PathBuf>,
+    /// File with accepted bearer tokens, one per line. Authentication is disabled if not set
+    #[structopt(long)]
+    tokens: Option<PathBuf>,
+}
+
+#[rocket::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Cli = Cli::from_args();
+
+    let tokens = match &args.tokens {
+        Some(path) => std::fs::read_to_string(path)?
+            .lines()
+            .map(str::trim)
+            .filter(|token| !token.is_empty())
+            .map(String::from)
+            .collect(),
+        None => {
+            eprintln!("Warning: tokens file isn't provided, authentication is disabled");
+            vec![]
+        }
+    };
+    if args.tokens.is_some() && tokens.is_empty() {
+        return Err("tokens file doesn't contain any token".into());
+    }
+
+    let relay = Relay::open(Config {
+        room_ttl: Duration::from_secs(args.room_ttl),
+        default_capacity: args.default_capacity,
+        max_capacity: args.max_capacity,
+        max_messages_per_party: args.max_messages_per_party,
+        max_message_size: args.max_message_size,
+        max_room_size: args.max_room_size,
+        max_rooms: args.max_rooms,
+        log_dir: args.log_dir,
+        tokens,
+    })?;
+    let figment = rocket::Config::figment()
+        .merge(("address", args.address))
+        .merge(("port", args.port));
+    relay::server(figment, relay).launch().await?;
+    Ok(())
+}
@@ -0,0 +1,292 @@
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+//! # Party identities and signed protocol messages
+//!
+//! `round_based` identifies parties only by index, and a relay has no way to tell who actually
+//! sent a message. This module binds indexes to long-term identity keys:
+//! * Every party holds an [IdentityKey] — a secp256k1 keypair that outlives protocol executions
+//! * Committee is described by a [Roster]: party with index `i` is identified by `i`-th public key
+//!   in the roster. Roster is fixed at [keygen](crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::Keygen::with_roster)
+//!   and stored in `LocalKey`
+//! * Outgoing messages are signed by [sign_outgoing], incoming messages are checked against the
+//!   roster by [verify_incoming] before they reach the state machine
+//!
+//! Signature covers session id, sender and receiver indexes, and serialized message body, so
+//! a message can't be replayed in another session, attributed to another party, or redirected.
+//!
+//! Wrapping [secure channel](crate::secure_channel) handshake streams with these functions
+//! authenticates ephemeral keys, and makes the channel secure against an active relay.
+
+use std::sync::Arc;
+
+use curv::arithmetic::traits::*;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use curv::BigInt;
+use futures::{future, Sink, SinkExt, Stream, StreamExt};
+use round_based::Msg;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::utilities::verification::{self, Mode, SignatureRecid};
+
+#[cfg(test)]
+mod test;
+
+/// Domain separator of signed message digest
+const DOMAIN: &[u8] = b"multi-party-ecdsa/identity/v1";
+/// Domain separator of roster hash
+const ROSTER_DOMAIN: &[u8] = b"multi-party-ecdsa/identity/v1/roster";
+
+/// Long-term identity keypair of a party
+#[derive(Clone, Serialize, Deserialize)]
+pub struct IdentityKey {
+    secret: Scalar<Secp256k1>,
+}
+
+impl IdentityKey {
+    /// Generates a new random identity key
+    pub fn generate() -> Self {
+        Self {
+            secret: Scalar::random(),
+        }
+    }
+
+    /// Public key identifying this party in a [Roster]
+    pub fn public_key(&self) -> Point<Secp256k1> {
+        Point::generator() * &self.secret
+    }
+
+    /// Signs a message sent by party `msg.sender` in session `session_id`
+    pub fn sign_msg<M: Serialize>(
+        &self,
+        session_id: &[u8],
+        msg: Msg<M>,
+    ) -> Result<Msg<SignedMessage>> {
+        let payload = serde_json::to_string(&msg.body).map_err(Error::Serialize)?;
+        let digest = message_digest(session_id, msg.sender, msg.receiver, &payload);
+        let signature = self.sign(&digest)?;
+        Ok(Msg {
+            sender: msg.sender,
+            receiver: msg.receiver,
+            body: SignedMessage { payload, signature },
+        })
+    }
+
+    /// ECDSA signature of `message`, normalized to low-S
+    #[allow(non_snake_case)]
+    fn sign(&self, message: &BigInt) -> Result<SignatureRecid> {
+        let k = Scalar::<Secp256k1>::random();
+        let R = Point::generator() * &k;
+        let r = R
+            .x_coord()
+            .map(|x| Scalar::<Secp256k1>::from(&x))
+            .ok_or(Error::Sign)?;
+        let k_inv = k.invert().ok_or(Error::Sign)?;
+        let s = k_inv * (Scalar::<Secp256k1>::from(message) + &r * &self.secret);
+        let (s, recid) = verification::normalize_s(&R, s).map_err(|_| Error::Sign)?;
+        Ok(SignatureRecid { r, s, recid })
+    }
+}
+
+/// Identity public keys of the committee
+///
+/// Party with index `i` is identified by `i`-th key (indexes start from 1).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Roster(Vec<Point<Secp256k1>>);
+
+impl Roster {
+    /// Constructs a roster, `keys[i-1]` is the identity of party `i`
+    ///
+    /// Returns error if roster is empty, contains the point at infinity or the same key twice.
+    pub fn new(keys: Vec<Point<Secp256k1>>) -> Result<Self> {
+        if keys.is_empty() {
+            return Err(Error::EmptyRoster);
+        }
+        for (i, key) in (1..).zip(&keys) {
+            if key.is_zero() {
+                return Err(Error::InvalidIdentityKey { party: i });
+            }
+            if keys[..usize::from(i - 1)].contains(key) {
+                return Err(Error::DuplicatedIdentityKey { party: i });
+            }
+        }
+        Ok(Self(keys))
+    }
+
+    /// Number of parties in the roster
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if roster has no parties (can only happen if it was deserialized)
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Identity public key of party `i`
+    pub fn public_key(&self, i: u16) -> Option<&Point<Secp256k1>> {
+        usize::from(i).checked_sub(1).and_then(|i| self.0.get(i))
+    }
+
+    /// Index of party identified by `public_key`
+    pub fn index_of(&self, public_key: &Point<Secp256k1>) -> Option<u16> {
+        (1..)
+            .zip(&self.0)
+            .find(|(_, k)| *k == public_key)
+            .map(|(i, _)| i)
+    }
+
+    /// Hash of the roster
+    ///
+    /// Two rosters have the same hash only if they list the same keys in the same order.
+    pub fn hash(&self) -> [u8; 32] {
+        let mut hash = Sha256::new()
+            .chain(ROSTER_DOMAIN)
+            .chain((self.0.len() as u64).to_be_bytes());
+        for key in &self.0 {
+            hash.update(&*key.to_bytes(true));
+        }
+        hash.finalize().into()
+    }
+
+    /// Roster of signing committee
+    ///
+    /// Signing parties are indexed from 1 to `s_l.len()`, party `j` holds the key share of
+    /// keygen party `s_l[j-1]` (the same `s_l` as given to
+    /// [OfflineStage](crate::protocols::multi_party_ecdsa::gg_2020::state_machine::sign::OfflineStage)).
+    pub fn select(&self, s_l: &[u16]) -> Result<Roster> {
+        s_l.iter()
+            .map(|&i| {
+                self.public_key(i)
+                    .cloned()
+                    .ok_or(Error::UnknownParty { party: i })
+            })
+            .collect::<Result<Vec<_>>>()
+            .and_then(Roster::new)
+    }
+
+    /// Checks that `msg` is signed by its sender in session `session_id`, and returns the message
+    /// body
+    pub fn verify_msg<M: DeserializeOwned>(
+        &self,
+        session_id: &[u8],
+        msg: Msg<SignedMessage>,
+    ) -> Result<Msg<M>> {
+        let sender = msg.sender;
+        let public_key = self
+            .public_key(sender)
+            .ok_or(Error::UnknownParty { party: sender })?;
+        let digest = message_digest(session_id, sender, msg.receiver, &msg.body.payload);
+        let signature = &msg.body.signature;
+        verification::verify(
+            &signature.r,
+            &signature.s,
+            public_key,
+            &digest,
+            Mode::Strict,
+        )
+        .map_err(|_| Error::InvalidSignature { sender })?;
+
+        let body =
+            serde_json::from_str(&msg.body.payload).map_err(|_| Error::Deserialize { sender })?;
+        Ok(Msg {
+            sender,
+            receiver: msg.receiver,
+            body,
+        })
+    }
+}
+
+/// Serialized message body signed by the sender
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignedMessage {
+    pub payload: String,
+    pub signature: SignatureRecid,
+}
+
+fn message_digest(session_id: &[u8], sender: u16, receiver: Option<u16>, payload: &str) -> BigInt {
+    let receiver = match receiver {
+        Some(j) => [&[1u8][..], &j.to_be_bytes()].concat(),
+        None => vec![0],
+    };
+    let digest = Sha256::new()
+        .chain(DOMAIN)
+        .chain((session_id.len() as u64).to_be_bytes())
+        .chain(session_id)
+        .chain(sender.to_be_bytes())
+        .chain(receiver)
+        .chain(payload)
+        .finalize();
+    BigInt::from_bytes(&digest)
+}
+
+/// Wraps outgoing sink: signs every message with `identity` key
+pub fn sign_outgoing<M, O, E>(
+    identity: Arc<IdentityKey>,
+    session_id: Vec<u8>,
+    outgoing: O,
+) -> impl Sink<Msg<M>, Error = E>
+where
+    M: Serialize,
+    O: Sink<Msg<SignedMessage>, Error = E>,
+    E: From<Error>,
+{
+    outgoing.with(move |msg: Msg<M>| {
+        future::ready(identity.sign_msg(&session_id, msg).map_err(E::from))
+    })
+}
+
+/// Wraps incoming stream: checks every message against the `roster`
+///
+/// Message that isn't signed by its sender is yielded as an error.
+pub fn verify_incoming<M, I, E>(
+    roster: Arc<Roster>,
+    session_id: Vec<u8>,
+    incoming: I,
+) -> impl Stream<Item = Result<Msg<M>, E>>
+where
+    M: DeserializeOwned,
+    I: Stream<Item = Result<Msg<SignedMessage>, E>>,
+    E: From<Error>,
+{
+    incoming
+        .map(move |msg| msg.and_then(|msg| roster.verify_msg(&session_id, msg).map_err(E::from)))
+}
+
+type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("roster is empty")]
+    EmptyRoster,
+    #[error("identity key of party {party} is the point at infinity")]
+    InvalidIdentityKey { party: u16 },
+    #[error("identity key of party {party} appears in roster twice")]
+    DuplicatedIdentityKey { party: u16 },
+    #[error("party {party} is not in roster")]
+    UnknownParty { party: u16 },
+    #[error("serialize message: {0}")]
+    Serialize(#[source] serde_json::Error),
+    #[error("couldn't sign message")]
+    Sign,
+    #[error("message from party {sender} has invalid signature")]
+    InvalidSignature { sender: u16 },
+    #[error("couldn't deserialize message from party {sender}")]
+    Deserialize { sender: u16 },
+}
@@ -0,0 +1,156 @@
+use curv::elliptic::curves::secp256_k1::Secp256k1;
+use futures::channel::mpsc;
+use futures::executor::block_on;
+use round_based::dev::Simulation;
+
+use super::*;
+use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::{self, Keygen};
+
+fn committee(n: usize) -> (Vec<IdentityKey>, Roster) {
+    let keys = (0..n).map(|_| IdentityKey::generate()).collect::<Vec<_>>();
+    let roster = Roster::new(keys.iter().map(IdentityKey::public_key).collect()).unwrap();
+    (keys, roster)
+}
+
+fn msg(sender: u16, receiver: Option<u16>) -> Msg<u32> {
+    Msg {
+        sender,
+        receiver,
+        body: 42,
+    }
+}
+
+#[test]
+fn signed_message_is_bound_to_session_sender_and_receiver() {
+    let (keys, roster) = committee(3);
+    let signed = keys[0].sign_msg(b"session", msg(1, Some(2))).unwrap();
+    let verified: Msg<u32> = roster.verify_msg(b"session", signed.clone()).unwrap();
+    assert_eq!(verified, msg(1, Some(2)));
+
+    let replayed = roster.verify_msg::<u32>(b"another session", signed.clone());
+    assert!(matches!(
+        replayed,
+        Err(Error::InvalidSignature { sender: 1 })
+    ));
+
+    let impersonated = Msg {
+        sender: 3,
+        ..signed.clone()
+    };
+    assert!(matches!(
+        roster.verify_msg::<u32>(b"session", impersonated),
+        Err(Error::InvalidSignature { sender: 3 })
+    ));
+
+    let redirected = Msg {
+        receiver: Some(3),
+        ..signed.clone()
+    };
+    assert!(matches!(
+        roster.verify_msg::<u32>(b"session", redirected),
+        Err(Error::InvalidSignature { sender: 1 })
+    ));
+
+    let mut tampered = signed;
+    tampered.body.payload = "43".to_owned();
+    assert!(matches!(
+        roster.verify_msg::<u32>(b"session", tampered),
+        Err(Error::InvalidSignature { sender: 1 })
+    ));
+
+    let outsider = IdentityKey::generate()
+        .sign_msg(b"session", msg(4, None))
+        .unwrap();
+    assert!(matches!(
+        roster.verify_msg::<u32>(b"session", outsider),
+        Err(Error::UnknownParty { party: 4 })
+    ));
+}
+
+#[test]
+fn roster_rejects_duplicates_and_selects_signers() {
+    let (keys, roster) = committee(3);
+    let duplicated = vec![
+        keys[0].public_key(),
+        keys[1].public_key(),
+        keys[0].public_key(),
+    ];
+    assert!(matches!(
+        Roster::new(duplicated),
+        Err(Error::DuplicatedIdentityKey { party: 3 })
+    ));
+    assert!(matches!(Roster::new(vec![]), Err(Error::EmptyRoster)));
+
+    let signers = roster.select(&[1, 3]).unwrap();
+    assert_eq!(signers.len(), 2);
+    assert_eq!(signers.public_key(2), Some(&keys[2].public_key()));
+    assert_eq!(roster.index_of(&keys[2].public_key()), Some(3));
+    assert!(matches!(
+        roster.select(&[1, 4]),
+        Err(Error::UnknownParty { party: 4 })
+    ));
+}
+
+#[test]
+fn wrapped_streams_reject_forged_messages() {
+    let (keys, roster) = committee(2);
+    let (tx, rx) = mpsc::unbounded::<Msg<SignedMessage>>();
+
+    let mut outgoing = Box::pin(sign_outgoing::<u32, _, Error>(
+        Arc::new(keys.into_iter().next().unwrap()),
+        b"session".to_vec(),
+        tx.clone().sink_map_err(|_| Error::Sign),
+    ));
+    let mut incoming = Box::pin(verify_incoming::<u32, _, Error>(
+        Arc::new(roster),
+        b"session".to_vec(),
+        rx.map(Ok),
+    ));
+
+    block_on(outgoing.send(msg(1, None))).unwrap();
+    // Relay claims the message comes from party 1
+    let forged = IdentityKey::generate()
+        .sign_msg(b"session", msg(1, None))
+        .unwrap();
+    tx.unbounded_send(forged).unwrap();
+
+    let received = block_on(incoming.next()).unwrap().unwrap();
+    assert_eq!(received, msg(1, None));
+    let forged = block_on(incoming.next()).unwrap();
+    assert!(matches!(forged, Err(Error::InvalidSignature { sender: 1 })));
+}
+
+#[test]
+fn keygen_stores_roster() {
+    let (_keys, roster) = committee(3);
+    assert!(matches!(
+        Keygen::<Secp256k1>::with_roster(1, 1, 2, roster.clone()),
+        Err(keygen::Error::RosterSizeMismatch)
+    ));
+
+    let mut simulation = Simulation::<Keygen>::new();
+    for i in 1..=3 {
+        simulation.add_party(Keygen::with_roster(i, 1, 3, roster.clone()).unwrap());
+    }
+    let local_keys = simulation.run().unwrap();
+    assert!(local_keys
+        .iter()
+        .all(|k| k.roster.as_ref() == Some(&roster)));
+}
+
+#[test]
+fn keygen_aborts_if_rosters_differ() {
+    let (_keys, roster) = committee(3);
+    let (_keys, another_roster) = committee(3);
+
+    let mut simulation = Simulation::<Keygen>::new();
+    simulation.add_party(Keygen::with_roster(1, 1, 3, roster.clone()).unwrap());
+    simulation.add_party(Keygen::with_roster(2, 1, 3, roster).unwrap());
+    simulation.add_party(Keygen::with_roster(3, 1, 3, another_roster).unwrap());
+    assert!(matches!(
+        simulation.run(),
+        Err(keygen::Error::ProceedRound(
+            keygen::ProceedError::Round1VerifyRoster(_)
+        ))
+    ));
+}
@@ -18,7 +18,14 @@
 #![allow(clippy::too_many_arguments)]
 #![allow(clippy::type_complexity)]
 
+pub mod attestation;
+pub mod identity;
 pub mod protocols;
+#[cfg(feature = "relay")]
+pub mod relay;
+pub mod secure_channel;
+#[cfg(feature = "transport")]
+pub mod transport;
 pub mod utilities;
 #[derive(Copy, PartialEq, Eq, Clone, Debug)]
 pub enum Error {
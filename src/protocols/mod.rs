@@ -15,4 +15,7 @@
 */
 
 pub mod multi_party_ecdsa;
+pub mod multi_party_eddsa;
+pub mod threshold_ecdh;
+pub mod threshold_schnorr;
 pub mod two_party_ecdsa;
@@ -0,0 +1,31 @@
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+//! UC Non-Interactive, Proactive, Threshold ECDSA with Identifiable Aborts
+//!
+//! Implementation of [CGGMP21](https://eprint.iacr.org/2021/060): threshold key generation,
+//! auxiliary info generation, presigning with identifiable abort and one-round signing, all
+//! available in [state_machine] module.
+//!
+//! Deviations from the paper:
+//! * Πenc, Πaff-g and Πlog* are instantiated with [MtA range proofs](crate::utilities::mta::range_proofs)
+//!   and [PDL with slack](crate::utilities::zk_pdl_with_slack) proofs, hashed together with the
+//!   execution id, prover and verifier indexes.
+//! * Culprits at presigning are identified by an extra round revealing nonces and MtA masks,
+//!   instead of Πdec proofs.
+
+pub mod state_machine;
+pub mod zk;
@@ -0,0 +1,515 @@
+//! CGGMP21 auxiliary info generation
+//!
+//! Every party generates Paillier key and ring-Pedersen parameters `(N, s, t)` over the same
+//! modulus. Parties commit to their parameters and random `rho_i` at round 1 and reveal them
+//! along with Πprm proof at round 2. At round 3 every party proves with Πmod that its modulus is
+//! a Paillier-Blum modulus, and with Πfac that it has no small factors (Πfac is proven against
+//! ring-Pedersen parameters of every other party). Proofs are bound to `rho = ⊕ rho_i`.
+//!
+//! Safe primes generation takes a while, so primes are generated before the protocol starts,
+//! see [PregeneratedPrimes].
+
+use std::fmt;
+use std::mem::replace;
+use std::time::Duration;
+
+use curv::arithmetic::traits::*;
+use curv::BigInt;
+use paillier::keygen::PrimeSampable;
+use round_based::containers::{
+    push::{Push, PushExt},
+    *,
+};
+use round_based::{IsCritical, Msg, StateMachine};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+mod rounds;
+
+use private::InternalError;
+pub use rounds::{AuxInfo, PartyAux, ProceedError};
+use rounds::{Commitment, Decommitment, ModulusProofs};
+use rounds::{Round0, Round1, Round2, Round3};
+
+/// Bit length of every of primes constituting Paillier modulus
+pub const PRIME_BITS: usize = 1024;
+
+/// Pair of safe primes used to derive Paillier key and ring-Pedersen parameters
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PregeneratedPrimes {
+    p: BigInt,
+    q: BigInt,
+}
+
+impl PregeneratedPrimes {
+    /// Generates two random safe primes of [PRIME_BITS] length
+    ///
+    /// It's a slow operation, it may take a minute.
+    pub fn generate() -> Self {
+        Self {
+            p: BigInt::sample_safe_prime(PRIME_BITS),
+            q: BigInt::sample_safe_prime(PRIME_BITS),
+        }
+    }
+
+    /// Takes two safe primes that were generated in advance
+    ///
+    /// Returns [Error::InvalidPrimes] if `p` or `q` is not a safe prime of [PRIME_BITS] length,
+    /// or if they are equal.
+    pub fn new(p: BigInt, q: BigInt) -> Result<Self> {
+        let is_safe_prime = |p: &BigInt| {
+            p.bit_length() == PRIME_BITS
+                && p.is_probable_prime(30)
+                && ((p - BigInt::one()) >> 1).is_probable_prime(30)
+        };
+        if p == q || !is_safe_prime(&p) || !is_safe_prime(&q) {
+            return Err(Error::InvalidPrimes);
+        }
+        Ok(Self { p, q })
+    }
+}
+
+impl fmt::Debug for PregeneratedPrimes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("PregeneratedPrimes { .. }")
+    }
+}
+
+/// Auxiliary info generation state machine
+///
+/// Successfully completed protocol produces [AuxInfo]. Along with [LocalKey](super::keygen::LocalKey),
+/// it's used in [presigning](super::presign).
+pub struct AuxInfoGen {
+    round: R,
+
+    msgs1: Option<Store<BroadcastMsgs<Commitment>>>,
+    msgs2: Option<Store<BroadcastMsgs<Decommitment>>>,
+    msgs3: Option<Store<BroadcastMsgs<ModulusProofs>>>,
+
+    msgs_queue: Vec<Msg<ProtocolMessage>>,
+
+    party_i: u16,
+    party_n: u16,
+}
+
+impl AuxInfoGen {
+    /// Constructs a party of aux info generation protocol
+    ///
+    /// Takes party index `i` (in range `[1; n]`), total number of parties `n`, safe primes that
+    /// will constitute party's Paillier modulus, and execution id that must be unique and the
+    /// same for all parties.
+    ///
+    /// Returns error if:
+    /// * `n` is less than 2, returns [Error::TooFewParties]
+    /// * `i` is not in range `[1; n]`, returns [Error::InvalidPartyIndex]
+    pub fn new(i: u16, n: u16, primes: PregeneratedPrimes, execution_id: &[u8]) -> Result<Self> {
+        if n < 2 {
+            return Err(Error::TooFewParties);
+        }
+        if i == 0 || i > n {
+            return Err(Error::InvalidPartyIndex);
+        }
+        let mut state = Self {
+            round: R::Round0(Round0 {
+                primes,
+                i,
+                n,
+                execution_id: execution_id.to_vec(),
+            }),
+
+            msgs1: Some(Round1::expects_messages(i, n)),
+            msgs2: Some(Round2::expects_messages(i, n)),
+            msgs3: Some(Round3::expects_messages(i, n)),
+
+            msgs_queue: vec![],
+
+            party_i: i,
+            party_n: n,
+        };
+
+        state.proceed_round(false)?;
+        Ok(state)
+    }
+
+    fn gmap_queue<'a, T, F>(&'a mut self, mut f: F) -> impl Push<Msg<T>> + 'a
+    where
+        F: FnMut(T) -> M + 'a,
+    {
+        (&mut self.msgs_queue).gmap(move |m: Msg<T>| m.map_body(|m| ProtocolMessage(f(m))))
+    }
+
+    /// Proceeds round state if it received enough messages and if it's cheap to compute or
+    /// `may_block == true`
+    fn proceed_round(&mut self, may_block: bool) -> Result<()> {
+        let store1_wants_more = self.msgs1.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store2_wants_more = self.msgs2.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store3_wants_more = self.msgs3.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+
+        let next_state: R;
+        let try_again: bool = match replace(&mut self.round, R::Gone) {
+            R::Round0(round) if !round.is_expensive() || may_block => {
+                next_state = round
+                    .proceed(self.gmap_queue(M::Round1))
+                    .map(R::Round1)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round0(_) => {
+                next_state = s;
+                false
+            }
+            R::Round1(round) if !store1_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs1.take().ok_or(InternalError::StoreGone)?;
+                let msgs = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?;
+                next_state = round
+                    .proceed(msgs, self.gmap_queue(M::Round2))
+                    .map(R::Round2)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round1(_) => {
+                next_state = s;
+                false
+            }
+            R::Round2(round) if !store2_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs2.take().ok_or(InternalError::StoreGone)?;
+                let msgs = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?;
+                next_state = round
+                    .proceed(msgs, self.gmap_queue(M::Round3))
+                    .map(R::Round3)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round2(_) => {
+                next_state = s;
+                false
+            }
+            R::Round3(round) if !store3_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs3.take().ok_or(InternalError::StoreGone)?;
+                let msgs = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?;
+                next_state = round
+                    .proceed(msgs)
+                    .map(|aux| R::Final(Box::new(aux)))
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round3(_) => {
+                next_state = s;
+                false
+            }
+            s @ R::Final(_) | s @ R::Gone => {
+                next_state = s;
+                false
+            }
+        };
+
+        self.round = next_state;
+        if try_again {
+            self.proceed_round(may_block)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl StateMachine for AuxInfoGen {
+    type MessageBody = ProtocolMessage;
+    type Err = Error;
+    type Output = AuxInfo;
+
+    fn handle_incoming(&mut self, msg: Msg<Self::MessageBody>) -> Result<()> {
+        let current_round = self.current_round();
+
+        match msg.body {
+            ProtocolMessage(M::Round1(m)) => {
+                let store = self
+                    .msgs1
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 1,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+            ProtocolMessage(M::Round2(m)) => {
+                let store = self
+                    .msgs2
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 2,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+            ProtocolMessage(M::Round3(m)) => {
+                let store = self
+                    .msgs3
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 3,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+        }
+        self.proceed_round(false)
+    }
+
+    fn message_queue(&mut self) -> &mut Vec<Msg<Self::MessageBody>> {
+        &mut self.msgs_queue
+    }
+
+    fn wants_to_proceed(&self) -> bool {
+        let store1_wants_more = self.msgs1.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store2_wants_more = self.msgs2.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store3_wants_more = self.msgs3.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+
+        match &self.round {
+            R::Round0(_) => true,
+            R::Round1(_) => !store1_wants_more,
+            R::Round2(_) => !store2_wants_more,
+            R::Round3(_) => !store3_wants_more,
+            R::Final(_) | R::Gone => false,
+        }
+    }
+
+    fn proceed(&mut self) -> Result<()> {
+        self.proceed_round(true)
+    }
+
+    fn round_timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    fn round_timeout_reached(&mut self) -> Self::Err {
+        panic!("no timeout was set")
+    }
+
+    fn is_finished(&self) -> bool {
+        matches!(self.round, R::Final(_))
+    }
+
+    fn pick_output(&mut self) -> Option<Result<Self::Output>> {
+        match self.round {
+            R::Final(_) => (),
+            R::Gone => return Some(Err(Error::DoublePickOutput)),
+            _ => return None,
+        }
+
+        match replace(&mut self.round, R::Gone) {
+            R::Final(result) => Some(Ok(*result)),
+            _ => unreachable!("guaranteed by match expression above"),
+        }
+    }
+
+    fn current_round(&self) -> u16 {
+        match &self.round {
+            R::Round0(_) => 0,
+            R::Round1(_) => 1,
+            R::Round2(_) => 2,
+            R::Round3(_) => 3,
+            R::Final(_) | R::Gone => 4,
+        }
+    }
+
+    fn total_rounds(&self) -> Option<u16> {
+        Some(3)
+    }
+
+    fn party_ind(&self) -> u16 {
+        self.party_i
+    }
+
+    fn parties(&self) -> u16 {
+        self.party_n
+    }
+}
+
+impl fmt::Debug for AuxInfoGen {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{{CGGMP21 AuxInfoGen at round={} queue=[len={}]}}",
+            self.current_round(),
+            self.msgs_queue.len()
+        )
+    }
+}
+
+// Rounds
+
+enum R {
+    Round0(Round0),
+    Round1(Round1),
+    Round2(Round2),
+    Round3(Round3),
+    Final(Box<AuxInfo>),
+    Gone,
+}
+
+// Messages
+
+/// Protocol message which parties send on wire
+///
+/// Hides actual messages structure so it could be changed without breaking semver policy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtocolMessage(M);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+enum M {
+    Round1(Commitment),
+    Round2(Decommitment),
+    Round3(ModulusProofs),
+}
+
+// Error
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Error type of aux info generation protocol
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// Round proceeding resulted in error
+    #[error("proceed round: {0}")]
+    ProceedRound(#[source] ProceedError),
+
+    /// Too few parties (`n < 2`)
+    #[error("at least 2 parties are required for aux info generation")]
+    TooFewParties,
+    /// Party index `i` is not in range `[1; n]`
+    #[error("party index is not in range [1; n]")]
+    InvalidPartyIndex,
+    /// Given primes are not safe primes of [PRIME_BITS] length
+    #[error("primes are not distinct safe primes of {} bits", PRIME_BITS)]
+    InvalidPrimes,
+
+    /// Received message didn't pass pre-validation
+    #[error("received message didn't pass pre-validation: {0}")]
+    HandleMessage(#[source] StoreErr),
+    /// Received message which we didn't expect to receive now (e.g. message from previous round)
+    #[error(
+        "didn't expect to receive message from round {msg_round} (being at round {current_round})"
+    )]
+    ReceivedOutOfOrderMessage { current_round: u16, msg_round: u16 },
+    /// [AuxInfoGen::pick_output] called twice
+    #[error("pick_output called twice")]
+    DoublePickOutput,
+
+    /// Some internal assertions were failed, which is a bug
+    #[doc(hidden)]
+    #[error("internal error: {0:?}")]
+    InternalError(InternalError),
+}
+
+impl IsCritical for Error {
+    fn is_critical(&self) -> bool {
+        !matches!(
+            self,
+            Error::HandleMessage(_) | Error::ReceivedOutOfOrderMessage { .. }
+        )
+    }
+}
+
+impl From<InternalError> for Error {
+    fn from(err: InternalError) -> Self {
+        Self::InternalError(err)
+    }
+}
+
+mod private {
+    #[derive(Debug)]
+    #[non_exhaustive]
+    pub enum InternalError {
+        /// [Messages store](super::MessageStore) reported that it received all messages it wanted to receive,
+        /// but refused to return message container
+        RetrieveRoundMessages(super::StoreErr),
+        #[doc(hidden)]
+        StoreGone,
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use round_based::dev::Simulation;
+
+    use super::*;
+
+    /// Safe primes generated in advance, as generating them takes most of the test time
+    const SAFE_PRIMES: [&str; 8] = [
+        "d7a28e468753a9d558ee2a5d0b19c1e38316f9b2f421670cb509a9cab5d8c1d9fc9acb8f0d06bcf175d84f06ffb186add9ef85c217ae35abcef431bebec36d4f89582c0876c72b9282be852a3ccc49483a9587875d1441f66d13799d2368783ab1d27828c73158f4925f0fe8d9dbae916c7cb100e345f339995a11e69f9ce37f",
+        "e32d4ca2b4330dacd5bfcfdd232f5e60cfe8dd987bfd9bddbd060907ddad46d121dfca7787e82613705ac81846915505415d7e3ed664757daca8c235be609bc49264710fc56dee0ff857d5243e924a64f624533eaa79b3882c73a90ab2104ccafd1653b4037696832bca514a75cecaa9d68004a74f65e3f53b364387a03d941f",
+        "b4ff552ea14f377ab9746ab70313f232ff4e9b623ffbc54402581363e2c177f62c5133335739790662fab38f8bff4c603f3b2c2b7c9375a26a3099ded4628d85e076c961fa0a1f1c8cdb77c532798f4748b3331c33f157f777c8b226f6bd48701e2feb18b5cde4b6da152a9f78bc50009c7968979dbba5251437e0a031b108ef",
+        "cc2627d682771fa89840ce40ba4c2319a0b7ee9ff6244c30e61dd2a44b828160112b9c5acc7b80c80d38ea1f536690a98cb1baad6aeab51cfadabfb7ff19e26770879f2a5408b4de52cc51699b2640b09b9544710b63c241115a341048c6c8cc97f886684525c552e646f99716b2d5e08b99105f3a862c0320a8c3074b92dbd7",
+        "90b25be4dd39f472888e22520c1a5b6c619669423e93ae6c90d327a5898d8ab01291e7445f90ff8ef08e188fd8b31e95083ca6f12e63b85cedc589c85e6ccd1fe85d475cb951d283dbb9f9a01bc2bc2c2fb1759b3be2e3918572afac14cd50948de621f4c496515f1bc74fb138ca3bcd455af7ff5b40c68826e06ddbf08cbb1b",
+        "d235ee2d202b446e2f4d2499407f03d38b71eeed0f3a5bfdaf607dd590b101b1aeb32ddb6874b81ec71a7161527f4e28641ba12a9d23689d3dab26f7c2ab19c10894829a9653bf5968fe5287eb11f7a9de9d8ecf3bca6e19ef47d86c453ec30f7fb459d262191dc252fbdffca8494deabe06f1878aa532a3881ce5b12c362327",
+        "f292be6c422cc1e99835aaf986ce301597b01a36e6719f07116528746ad991538e678a9062e0247968e352a88021766af46becf39020a7328717b045c2d1f91dd0c9950557650b46368874ea6248b3b21434dc7f588a8734990163ba1b763ceb02a0abadccfd367fddb9f80f5d9f1326e88af13ca98065f3723f295dc4f159a3",
+        "e3297c41a6dd7fc7c5b0b51d57b284b91f3a6f4a61cedb248fdb49f880dabe426daa8c9ef05ae0229b0d9fa40d51c085d0df5bd724f0311e2c5f3550ffb1e5ab7219cdb5b1821e2a2b5589c8716eb1147f53a9549b63116c1ae77f6c9d1c320fd2215a38c214728b6d2f8e3db6de9cfbfb87cfe63d4c9bf1013036c73653429f",
+    ];
+
+    pub fn pregenerated_primes(i: u16) -> PregeneratedPrimes {
+        let i = usize::from(i - 1) * 2;
+        let prime = |k: usize| BigInt::from_hex(SAFE_PRIMES[k % SAFE_PRIMES.len()]).unwrap();
+        PregeneratedPrimes::new(prime(i), prime(i + 1)).unwrap()
+    }
+
+    pub fn simulate_aux_info(n: u16) -> Vec<AuxInfo> {
+        let mut simulation = Simulation::new();
+        for i in 1..=n {
+            simulation.add_party(AuxInfoGen::new(i, n, pregenerated_primes(i), b"aux").unwrap());
+        }
+        let aux = simulation.run().unwrap();
+
+        for (i, aux_i) in (1..).zip(&aux) {
+            assert_eq!(aux_i.i, i);
+            assert_eq!(aux_i.parties, aux[0].parties);
+            let party = &aux_i.parties[usize::from(i - 1)];
+            assert_eq!(party.modulus, &aux_i.paillier_dk.p * &aux_i.paillier_dk.q);
+        }
+        aux
+    }
+
+    #[test]
+    fn simulate_aux_info_n3() {
+        simulate_aux_info(3);
+    }
+
+    #[test]
+    fn rejects_primes_that_are_not_safe() {
+        let p = BigInt::from_hex(SAFE_PRIMES[0]).unwrap();
+        let q = BigInt::from_hex(SAFE_PRIMES[1]).unwrap();
+        assert!(PregeneratedPrimes::new(p.clone(), q.clone()).is_ok());
+        assert!(matches!(
+            PregeneratedPrimes::new(p.clone(), p.clone()),
+            Err(Error::InvalidPrimes)
+        ));
+        assert!(matches!(
+            PregeneratedPrimes::new(p, q + 2),
+            Err(Error::InvalidPrimes)
+        ));
+    }
+}
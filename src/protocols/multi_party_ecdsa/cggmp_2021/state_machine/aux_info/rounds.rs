@@ -0,0 +1,410 @@
+use curv::arithmetic::traits::*;
+use curv::BigInt;
+use paillier::{DecryptionKey, EncryptionKey, Keypair};
+use round_based::containers::push::Push;
+use round_based::containers::{self, BroadcastMsgs, Store};
+use round_based::Msg;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use zk_paillier::zkproofs::DLogStatement;
+
+use super::super::super::zk::no_small_factor::NoSmallFactorProof;
+use super::super::super::zk::paillier_blum::PaillierBlumProof;
+use super::super::super::zk::ring_pedersen::RingPedersenProof;
+use super::super::{proof_index, random_bytes, xor_all, Transcript};
+use super::PregeneratedPrimes;
+
+/// Minimal bit length of Paillier modulus accepted from other parties
+const MIN_MODULUS_BITS: usize = 2047;
+
+/// Commitment to party's auxiliary info, sent at round 1
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Commitment(pub [u8; 32]);
+
+/// Party's auxiliary info, revealed at round 2
+///
+/// Ring-Pedersen proof is bound to the party and the execution id, so it's not committed to.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Decommitment {
+    pub modulus: BigInt,
+    pub s: BigInt,
+    pub t: BigInt,
+    pub prm_proof: RingPedersenProof,
+    pub rho: [u8; 32],
+    pub blind: [u8; 32],
+}
+
+impl Decommitment {
+    fn commit(&self, execution_id: &[u8], i: u16) -> Commitment {
+        Commitment(
+            Transcript::new(b"cggmp21/aux-info/commitment")
+                .bytes(execution_id)
+                .u16(i)
+                .bigint(&self.modulus)
+                .bigint(&self.s)
+                .bigint(&self.t)
+                .bytes(&self.rho)
+                .bytes(&self.blind)
+                .finish(),
+        )
+    }
+}
+
+/// Proofs that party's modulus is a Paillier-Blum modulus (Πmod) and has no small factors
+/// (Πfac), sent at round 3
+///
+/// Πfac is proven against ring-Pedersen parameters of the verifier, so there's a Πfac proof for
+/// every other party.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ModulusProofs {
+    pub mod_proof: PaillierBlumProof,
+    pub fac_proofs: Vec<NoSmallFactorProof>,
+}
+
+pub struct Round0 {
+    pub primes: PregeneratedPrimes,
+    pub i: u16,
+    pub n: u16,
+    pub execution_id: Vec<u8>,
+}
+
+impl Round0 {
+    pub fn proceed<O>(self, mut output: O) -> Result<Round1>
+    where
+        O: Push<Msg<Commitment>>,
+    {
+        let PregeneratedPrimes { p, q } = self.primes;
+        let modulus = &p * &q;
+        let phi = (&p - BigInt::one()) * (&q - BigInt::one());
+
+        let r = BigInt::sample_below(&modulus);
+        let t = BigInt::mod_mul(&r, &r, &modulus);
+        let lambda = BigInt::sample_below(&phi);
+        let s = BigInt::mod_pow(&t, &lambda, &modulus);
+        let prm_proof = RingPedersenProof::prove(
+            &modulus,
+            &s,
+            &t,
+            &lambda,
+            &phi,
+            &prm_context(&self.execution_id, self.i),
+        );
+
+        let decommitment = Decommitment {
+            modulus,
+            s,
+            t,
+            prm_proof,
+            rho: random_bytes(),
+            blind: random_bytes(),
+        };
+        let commitment = decommitment.commit(&self.execution_id, self.i);
+        output.push(Msg {
+            sender: self.i,
+            receiver: None,
+            body: commitment.clone(),
+        });
+
+        Ok(Round1 {
+            commitment,
+            decommitment,
+            keypair: Keypair { p, q },
+            i: self.i,
+            n: self.n,
+            execution_id: self.execution_id,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+}
+
+pub struct Round1 {
+    commitment: Commitment,
+    decommitment: Decommitment,
+    keypair: Keypair,
+
+    i: u16,
+    n: u16,
+    execution_id: Vec<u8>,
+}
+
+impl Round1 {
+    pub fn proceed<O>(self, input: BroadcastMsgs<Commitment>, mut output: O) -> Result<Round2>
+    where
+        O: Push<Msg<Decommitment>>,
+    {
+        output.push(Msg {
+            sender: self.i,
+            receiver: None,
+            body: self.decommitment.clone(),
+        });
+        Ok(Round2 {
+            commitments: input.into_vec_including_me(self.commitment),
+            decommitment: self.decommitment,
+            keypair: self.keypair,
+            i: self.i,
+            n: self.n,
+            execution_id: self.execution_id,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        false
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<Commitment>> {
+        containers::BroadcastMsgsStore::new(i, n)
+    }
+}
+
+pub struct Round2 {
+    commitments: Vec<Commitment>,
+    decommitment: Decommitment,
+    keypair: Keypair,
+
+    i: u16,
+    n: u16,
+    execution_id: Vec<u8>,
+}
+
+impl Round2 {
+    pub fn proceed<O>(self, input: BroadcastMsgs<Decommitment>, mut output: O) -> Result<Round3>
+    where
+        O: Push<Msg<ModulusProofs>>,
+    {
+        let decommitments = input.into_vec_including_me(self.decommitment);
+
+        let (execution_id, i) = (&self.execution_id, self.i);
+        let culprits = (1..)
+            .zip(decommitments.iter().zip(&self.commitments))
+            .filter(|(j, (decommitment, commitment))| {
+                decommitment.commit(execution_id, *j).0 != commitment.0
+            })
+            .map(|(j, _)| j)
+            .collect::<Vec<u16>>();
+        if !culprits.is_empty() {
+            return Err(ProceedError::Round2InvalidDecommitment { culprits });
+        }
+
+        let culprits = (1..)
+            .zip(&decommitments)
+            .filter(|(j, _)| *j != i)
+            .filter(|(j, d)| {
+                d.modulus.bit_length() < MIN_MODULUS_BITS
+                    || d.prm_proof
+                        .verify(&d.modulus, &d.s, &d.t, &prm_context(execution_id, *j))
+                        .is_err()
+            })
+            .map(|(j, _)| j)
+            .collect::<Vec<u16>>();
+        if !culprits.is_empty() {
+            return Err(ProceedError::Round2InvalidRingPedersenParams { culprits });
+        }
+
+        let rho = xor_all(decommitments.iter().map(|d| &d.rho));
+        let (own, keypair) = (&decommitments[usize::from(i - 1)], &self.keypair);
+        let mod_proof = PaillierBlumProof::prove(
+            &own.modulus,
+            &keypair.p,
+            &keypair.q,
+            &mod_context(execution_id, i, &rho),
+        );
+        let fac_proofs = (1..)
+            .zip(&decommitments)
+            .filter(|(j, _)| *j != i)
+            .map(|(j, d)| {
+                NoSmallFactorProof::prove(
+                    &own.modulus,
+                    &keypair.p,
+                    &keypair.q,
+                    &d.modulus,
+                    &d.s,
+                    &d.t,
+                    &fac_context(execution_id, i, j, &rho),
+                )
+            })
+            .collect();
+        let proof = ModulusProofs {
+            mod_proof,
+            fac_proofs,
+        };
+        output.push(Msg {
+            sender: self.i,
+            receiver: None,
+            body: proof.clone(),
+        });
+
+        Ok(Round3 {
+            own_proof: proof,
+            decommitments,
+            rho,
+            keypair: self.keypair,
+            i: self.i,
+            n: self.n,
+            execution_id: self.execution_id,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<Decommitment>> {
+        containers::BroadcastMsgsStore::new(i, n)
+    }
+}
+
+pub struct Round3 {
+    own_proof: ModulusProofs,
+    decommitments: Vec<Decommitment>,
+    rho: [u8; 32],
+    keypair: Keypair,
+
+    i: u16,
+    n: u16,
+    execution_id: Vec<u8>,
+}
+
+impl Round3 {
+    pub fn proceed(self, input: BroadcastMsgs<ModulusProofs>) -> Result<AuxInfo> {
+        let proofs = input.into_vec_including_me(self.own_proof);
+
+        let (execution_id, rho, i, n) = (&self.execution_id, &self.rho, self.i, self.n);
+        let culprits = (1..)
+            .zip(proofs.iter().zip(&self.decommitments))
+            .filter(|(j, _)| *j != i)
+            .filter(|(j, (proof, d))| {
+                proof
+                    .mod_proof
+                    .verify(&d.modulus, &mod_context(execution_id, *j, rho))
+                    .is_err()
+            })
+            .map(|(j, _)| j)
+            .collect::<Vec<u16>>();
+        if !culprits.is_empty() {
+            return Err(ProceedError::Round3InvalidPaillierBlumProof { culprits });
+        }
+
+        let own = &self.decommitments[usize::from(i - 1)];
+        let culprits = (1..)
+            .zip(proofs.iter().zip(&self.decommitments))
+            .filter(|(j, _)| *j != i)
+            .filter(|(j, (proof, d))| {
+                proof.fac_proofs.len() != usize::from(n - 1)
+                    || proof.fac_proofs[proof_index(*j, i)]
+                        .verify(
+                            &d.modulus,
+                            &own.modulus,
+                            &own.s,
+                            &own.t,
+                            &fac_context(execution_id, *j, i, rho),
+                        )
+                        .is_err()
+            })
+            .map(|(j, _)| j)
+            .collect::<Vec<u16>>();
+        if !culprits.is_empty() {
+            return Err(ProceedError::Round3InvalidNoSmallFactorProof { culprits });
+        }
+
+        let (_, paillier_dk) = self.keypair.keys();
+        Ok(AuxInfo {
+            paillier_dk,
+            parties: self
+                .decommitments
+                .into_iter()
+                .map(|d| PartyAux {
+                    modulus: d.modulus,
+                    s: d.s,
+                    t: d.t,
+                })
+                .collect(),
+            i: self.i,
+            n: self.n,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<ModulusProofs>> {
+        containers::BroadcastMsgsStore::new(i, n)
+    }
+}
+
+fn prm_context(execution_id: &[u8], i: u16) -> [u8; 32] {
+    Transcript::new(b"cggmp21/aux-info/prm")
+        .bytes(execution_id)
+        .u16(i)
+        .finish()
+}
+
+fn mod_context(execution_id: &[u8], i: u16, rho: &[u8; 32]) -> [u8; 32] {
+    Transcript::new(b"cggmp21/aux-info/mod")
+        .bytes(execution_id)
+        .u16(i)
+        .bytes(rho)
+        .finish()
+}
+
+fn fac_context(execution_id: &[u8], prover: u16, verifier: u16, rho: &[u8; 32]) -> [u8; 32] {
+    Transcript::new(b"cggmp21/aux-info/fac")
+        .bytes(execution_id)
+        .u16(prover)
+        .u16(verifier)
+        .bytes(rho)
+        .finish()
+}
+
+/// Auxiliary info obtained by party after [aux info generation](super::AuxInfoGen) is completed
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AuxInfo {
+    /// Party's Paillier decryption key
+    pub paillier_dk: DecryptionKey,
+    /// Public auxiliary info of all the parties
+    pub parties: Vec<PartyAux>,
+    pub i: u16,
+    pub n: u16,
+}
+
+/// Public auxiliary info of a party: Paillier modulus `N` and ring-Pedersen parameters `s`, `t`
+/// defined over the same modulus
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PartyAux {
+    pub modulus: BigInt,
+    pub s: BigInt,
+    pub t: BigInt,
+}
+
+impl PartyAux {
+    /// Party's Paillier encryption key
+    pub fn ek(&self) -> EncryptionKey {
+        EncryptionKey::from(&self.modulus)
+    }
+
+    /// Party's ring-Pedersen parameters in the form accepted by range proofs
+    pub fn dlog_statement(&self) -> DLogStatement {
+        DLogStatement {
+            N: self.modulus.clone(),
+            g: self.s.clone(),
+            ni: self.t.clone(),
+        }
+    }
+}
+
+// Errors
+
+type Result<T> = std::result::Result<T, ProceedError>;
+
+/// Proceeding protocol error
+///
+/// Subset of [aux info generation errors](enum@super::Error) that can occur at protocol
+/// proceeding (i.e. after every message was received and pre-validated). Every variant lists
+/// indexes of parties who misbehaved.
+#[derive(Debug, Error)]
+pub enum ProceedError {
+    #[error("round 2: invalid decommitment: parties {culprits:?}")]
+    Round2InvalidDecommitment { culprits: Vec<u16> },
+    #[error("round 2: invalid modulus or ring-pedersen parameters: parties {culprits:?}")]
+    Round2InvalidRingPedersenParams { culprits: Vec<u16> },
+    #[error("round 3: invalid paillier-blum modulus proof: parties {culprits:?}")]
+    Round3InvalidPaillierBlumProof { culprits: Vec<u16> },
+    #[error("round 3: invalid no small factor proof: parties {culprits:?}")]
+    Round3InvalidNoSmallFactorProof { culprits: Vec<u16> },
+}
@@ -0,0 +1,400 @@
+use curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use round_based::containers::push::Push;
+use round_based::containers::{self, BroadcastMsgs, P2PMsgs, Store};
+use round_based::Msg;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::super::{random_bytes, xor_all, Transcript};
+
+/// Commitment to party's contribution, sent at round 1
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Commitment(pub [u8; 32]);
+
+/// Party's contribution, revealed at round 2
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Decommitment {
+    pub vss: VerifiableSS<Secp256k1>,
+    pub schnorr_commitment: Point<Secp256k1>,
+    pub rid: [u8; 32],
+    pub blind: [u8; 32],
+}
+
+impl Decommitment {
+    fn commit(&self, execution_id: &[u8], i: u16) -> Commitment {
+        let transcript = Transcript::new(b"cggmp21/keygen/commitment")
+            .bytes(execution_id)
+            .u16(i)
+            .u16(self.vss.parameters.threshold)
+            .u16(self.vss.parameters.share_count);
+        let transcript = self
+            .vss
+            .commitments
+            .iter()
+            .fold(transcript, |t, point| t.point(point));
+        Commitment(
+            transcript
+                .point(&self.schnorr_commitment)
+                .bytes(&self.rid)
+                .bytes(&self.blind)
+                .finish(),
+        )
+    }
+}
+
+/// Secret share sent to a party at round 3
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Share(pub Scalar<Secp256k1>);
+
+/// Proof of knowledge of party's secret share, sent at round 4
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SchnorrProof(pub Scalar<Secp256k1>);
+
+pub struct Round0 {
+    pub i: u16,
+    pub t: u16,
+    pub n: u16,
+    pub execution_id: Vec<u8>,
+}
+
+impl Round0 {
+    pub fn proceed<O>(self, mut output: O) -> Result<Round1>
+    where
+        O: Push<Msg<Commitment>>,
+    {
+        let secret = Scalar::random();
+        let (vss, shares) = VerifiableSS::share(self.t, self.n, &secret);
+        let shares = (0..usize::from(self.n))
+            .map(|j| shares[j].clone())
+            .collect();
+
+        let schnorr_nonce = Scalar::random();
+        let decommitment = Decommitment {
+            vss,
+            schnorr_commitment: Point::generator() * &schnorr_nonce,
+            rid: random_bytes(),
+            blind: random_bytes(),
+        };
+        let commitment = decommitment.commit(&self.execution_id, self.i);
+
+        output.push(Msg {
+            sender: self.i,
+            receiver: None,
+            body: commitment.clone(),
+        });
+        Ok(Round1 {
+            commitment,
+            decommitment,
+            shares,
+            schnorr_nonce,
+            i: self.i,
+            t: self.t,
+            n: self.n,
+            execution_id: self.execution_id,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        false
+    }
+}
+
+pub struct Round1 {
+    commitment: Commitment,
+    decommitment: Decommitment,
+    shares: Vec<Scalar<Secp256k1>>,
+    schnorr_nonce: Scalar<Secp256k1>,
+
+    i: u16,
+    t: u16,
+    n: u16,
+    execution_id: Vec<u8>,
+}
+
+impl Round1 {
+    pub fn proceed<O>(self, input: BroadcastMsgs<Commitment>, mut output: O) -> Result<Round2>
+    where
+        O: Push<Msg<Decommitment>>,
+    {
+        output.push(Msg {
+            sender: self.i,
+            receiver: None,
+            body: self.decommitment.clone(),
+        });
+        Ok(Round2 {
+            commitments: input.into_vec_including_me(self.commitment),
+            decommitment: self.decommitment,
+            shares: self.shares,
+            schnorr_nonce: self.schnorr_nonce,
+            i: self.i,
+            t: self.t,
+            n: self.n,
+            execution_id: self.execution_id,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        false
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<Commitment>> {
+        containers::BroadcastMsgsStore::new(i, n)
+    }
+}
+
+pub struct Round2 {
+    commitments: Vec<Commitment>,
+    decommitment: Decommitment,
+    shares: Vec<Scalar<Secp256k1>>,
+    schnorr_nonce: Scalar<Secp256k1>,
+
+    i: u16,
+    t: u16,
+    n: u16,
+    execution_id: Vec<u8>,
+}
+
+impl Round2 {
+    pub fn proceed<O>(self, input: BroadcastMsgs<Decommitment>, mut output: O) -> Result<Round3>
+    where
+        O: Push<Msg<Share>>,
+    {
+        let decommitments = input.into_vec_including_me(self.decommitment);
+
+        let (execution_id, t, n) = (&self.execution_id, self.t, self.n);
+        let culprits = (1..)
+            .zip(decommitments.iter().zip(&self.commitments))
+            .filter(|(j, (decommitment, commitment))| {
+                decommitment.commit(execution_id, *j).0 != commitment.0
+                    || decommitment.vss.parameters.threshold != t
+                    || decommitment.vss.parameters.share_count != n
+                    || decommitment.vss.commitments.len() != usize::from(t) + 1
+            })
+            .map(|(j, _)| j)
+            .collect::<Vec<u16>>();
+        if !culprits.is_empty() {
+            return Err(ProceedError::Round2InvalidDecommitment { culprits });
+        }
+
+        for (j, share) in (1..).zip(&self.shares) {
+            if j == self.i {
+                continue;
+            }
+            output.push(Msg {
+                sender: self.i,
+                receiver: Some(j),
+                body: Share(share.clone()),
+            });
+        }
+
+        let rid = xor_all(decommitments.iter().map(|d| &d.rid));
+        Ok(Round3 {
+            own_share: self.shares[usize::from(self.i - 1)].clone(),
+            decommitments,
+            rid,
+            schnorr_nonce: self.schnorr_nonce,
+            i: self.i,
+            t: self.t,
+            n: self.n,
+            execution_id: self.execution_id,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        false
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<Decommitment>> {
+        containers::BroadcastMsgsStore::new(i, n)
+    }
+}
+
+pub struct Round3 {
+    own_share: Scalar<Secp256k1>,
+    decommitments: Vec<Decommitment>,
+    rid: [u8; 32],
+    schnorr_nonce: Scalar<Secp256k1>,
+
+    i: u16,
+    t: u16,
+    n: u16,
+    execution_id: Vec<u8>,
+}
+
+impl Round3 {
+    pub fn proceed<O>(self, input: P2PMsgs<Share>, mut output: O) -> Result<Round4>
+    where
+        O: Push<Msg<SchnorrProof>>,
+    {
+        let shares = input.into_vec_including_me(Share(self.own_share));
+
+        let i = self.i;
+        let culprits = (1..)
+            .zip(shares.iter().zip(&self.decommitments))
+            .filter(|(_, (share, decommitment))| {
+                decommitment.vss.validate_share(&share.0, i).is_err()
+            })
+            .map(|(j, _)| j)
+            .collect::<Vec<u16>>();
+        if !culprits.is_empty() {
+            return Err(ProceedError::Round3InvalidShare { culprits });
+        }
+
+        let x = shares
+            .iter()
+            .fold(Scalar::zero(), |acc, share| acc + &share.0);
+        let decommitments = &self.decommitments;
+        let public_shares = (1..=self.n)
+            .map(|j| {
+                decommitments
+                    .iter()
+                    .map(|d| d.vss.get_point_commitment(j))
+                    .fold(Point::zero(), |acc, point| acc + point)
+            })
+            .collect::<Vec<_>>();
+        let y = self
+            .decommitments
+            .iter()
+            .fold(Point::zero(), |acc, d| acc + &d.vss.commitments[0]);
+
+        let own = &self.decommitments[usize::from(self.i - 1)];
+        let challenge = schnorr_challenge(
+            &self.execution_id,
+            self.i,
+            &self.rid,
+            &public_shares[usize::from(self.i - 1)],
+            &own.schnorr_commitment,
+        );
+        let proof = SchnorrProof(&self.schnorr_nonce + challenge * &x);
+        output.push(Msg {
+            sender: self.i,
+            receiver: None,
+            body: proof.clone(),
+        });
+
+        Ok(Round4 {
+            own_proof: proof,
+            schnorr_commitments: self
+                .decommitments
+                .into_iter()
+                .map(|d| d.schnorr_commitment)
+                .collect(),
+            rid: self.rid,
+            x,
+            public_shares,
+            y,
+            i: self.i,
+            t: self.t,
+            n: self.n,
+            execution_id: self.execution_id,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<P2PMsgs<Share>> {
+        containers::P2PMsgsStore::new(i, n)
+    }
+}
+
+pub struct Round4 {
+    own_proof: SchnorrProof,
+    schnorr_commitments: Vec<Point<Secp256k1>>,
+    rid: [u8; 32],
+    x: Scalar<Secp256k1>,
+    public_shares: Vec<Point<Secp256k1>>,
+    y: Point<Secp256k1>,
+
+    i: u16,
+    t: u16,
+    n: u16,
+    execution_id: Vec<u8>,
+}
+
+impl Round4 {
+    pub fn proceed(self, input: BroadcastMsgs<SchnorrProof>) -> Result<LocalKey> {
+        let proofs = input.into_vec_including_me(self.own_proof);
+
+        let (execution_id, rid, public_shares) =
+            (&self.execution_id, &self.rid, &self.public_shares);
+        let culprits = (1..)
+            .zip(proofs.iter().zip(&self.schnorr_commitments))
+            .filter(|(j, (proof, commitment))| {
+                let public_share = &public_shares[usize::from(*j - 1)];
+                let challenge = schnorr_challenge(execution_id, *j, rid, public_share, commitment);
+                Point::generator() * &proof.0 != *commitment + public_share * challenge
+            })
+            .map(|(j, _)| j)
+            .collect::<Vec<u16>>();
+        if !culprits.is_empty() {
+            return Err(ProceedError::Round4InvalidSchnorrProof { culprits });
+        }
+
+        Ok(LocalKey {
+            x: self.x,
+            public_shares: self.public_shares,
+            y: self.y,
+            i: self.i,
+            t: self.t,
+            n: self.n,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<SchnorrProof>> {
+        containers::BroadcastMsgsStore::new(i, n)
+    }
+}
+
+fn schnorr_challenge(
+    execution_id: &[u8],
+    j: u16,
+    rid: &[u8; 32],
+    public_share: &Point<Secp256k1>,
+    commitment: &Point<Secp256k1>,
+) -> Scalar<Secp256k1> {
+    Transcript::new(b"cggmp21/keygen/schnorr")
+        .bytes(execution_id)
+        .u16(j)
+        .bytes(rid)
+        .point(public_share)
+        .point(commitment)
+        .finish_scalar()
+}
+
+/// Local secret obtained by party after [keygen](super::Keygen) protocol is completed
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LocalKey {
+    /// Party's secret share `x_i`
+    pub x: Scalar<Secp256k1>,
+    /// Public shares `X_j = x_j * G` of all the parties
+    pub public_shares: Vec<Point<Secp256k1>>,
+    /// Shared public key
+    pub y: Point<Secp256k1>,
+    pub i: u16,
+    pub t: u16,
+    pub n: u16,
+}
+
+impl LocalKey {
+    /// Public key of secret shared between parties
+    pub fn public_key(&self) -> Point<Secp256k1> {
+        self.y.clone()
+    }
+}
+
+// Errors
+
+type Result<T> = std::result::Result<T, ProceedError>;
+
+/// Proceeding protocol error
+///
+/// Subset of [keygen errors](enum@super::Error) that can occur at protocol proceeding (i.e. after
+/// every message was received and pre-validated). Every variant lists indexes of parties who
+/// misbehaved.
+#[derive(Debug, Error)]
+pub enum ProceedError {
+    #[error("round 2: invalid decommitment: parties {culprits:?}")]
+    Round2InvalidDecommitment { culprits: Vec<u16> },
+    #[error("round 3: invalid secret share: parties {culprits:?}")]
+    Round3InvalidShare { culprits: Vec<u16> },
+    #[error("round 4: invalid schnorr proof: parties {culprits:?}")]
+    Round4InvalidSchnorrProof { culprits: Vec<u16> },
+}
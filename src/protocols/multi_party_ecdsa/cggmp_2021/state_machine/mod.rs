@@ -0,0 +1,77 @@
+//! CGGMP21 protocols implemented as [round_based] state machines
+//!
+//! Parties carry out [keygen] once to share a secret key, and [aux_info] to generate Paillier
+//! keys and ring-Pedersen parameters (aux info generation doesn't depend on the key, so it can be
+//! carried out before or after keygen). Then any `t+1` parties can run [presign] protocol ahead
+//! of time, and [sign] a message in one round once it's known.
+//!
+//! Keygen, aux info generation and presigning take an execution id. It's hashed into commitments and proofs,
+//! so messages of one protocol execution can't be replayed in another one. Execution id must be
+//! unique for every execution and agreed on by all the parties.
+
+use curv::arithmetic::traits::*;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use curv::BigInt;
+use sha2::{Digest, Sha256};
+
+pub mod aux_info;
+pub mod keygen;
+pub mod presign;
+pub mod sign;
+
+/// Hash of length-prefixed values, used to derive commitments and Fiat-Shamir challenges
+pub(crate) struct Transcript(Sha256);
+
+impl Transcript {
+    pub fn new(domain: &[u8]) -> Self {
+        Self(Sha256::new()).bytes(domain)
+    }
+
+    pub fn bytes(mut self, bytes: &[u8]) -> Self {
+        self.0.update((bytes.len() as u64).to_be_bytes());
+        self.0.update(bytes);
+        self
+    }
+
+    pub fn u16(self, x: u16) -> Self {
+        self.bytes(&x.to_be_bytes())
+    }
+
+    pub fn bigint(self, x: &BigInt) -> Self {
+        self.bytes(&x.to_bytes())
+    }
+
+    pub fn point(self, point: &Point<Secp256k1>) -> Self {
+        self.bytes(&point.to_bytes(true))
+    }
+
+    pub fn finish(self) -> [u8; 32] {
+        self.0.finalize().into()
+    }
+
+    pub fn finish_scalar(self) -> Scalar<Secp256k1> {
+        Scalar::from(&BigInt::from_bytes(&self.finish()))
+    }
+}
+
+/// Index of a proof meant for `receiver` in the list of proofs produced by `sender`
+///
+/// Sender produces a proof for every other party in ascending order of their indexes.
+pub(crate) fn proof_index(sender: u16, receiver: u16) -> usize {
+    usize::from(receiver - 1) - usize::from(receiver > sender)
+}
+
+/// XOR of 32-byte strings, used in coin flipping
+pub(crate) fn xor_all<'a>(values: impl IntoIterator<Item = &'a [u8; 32]>) -> [u8; 32] {
+    values.into_iter().fold([0u8; 32], |mut acc, value| {
+        acc.iter_mut().zip(value).for_each(|(acc, x)| *acc ^= x);
+        acc
+    })
+}
+
+pub(crate) fn random_bytes() -> [u8; 32] {
+    let bytes = BigInt::sample(256).to_bytes();
+    let mut out = [0u8; 32];
+    out[32 - bytes.len()..].copy_from_slice(&bytes);
+    out
+}
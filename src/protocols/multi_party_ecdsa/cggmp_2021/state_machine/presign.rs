@@ -0,0 +1,663 @@
+//! CGGMP21 presigning with identifiable abort
+//!
+//! Any `t+1` parties holding [LocalKey] and [AuxInfo] can carry out presigning before the
+//! message to sign is known. Every party `i` samples nonces `k_i`, `γ_i` and broadcasts their
+//! Paillier encryptions `K_i`, `G_i` (round 1). Then every pair of parties runs two MtA protocols
+//! over `K_j`: with `γ_i` and with `w_i` (round 2), so parties obtain additive shares of
+//! `δ = k * γ` and `χ = k * x`. At round 3 parties reveal `δ_i` and `Δ_i = k_i * Γ`, every
+//! message is accompanied with zero-knowledge proofs that bind it to the previous ones.
+//!
+//! If `δ * G != Σ Δ_j`, parties run an additional round revealing their nonces `k_i`, `γ_i` and
+//! MtA masks, which identifies parties who misbehaved. Nonces are discarded after abort, and
+//! MtA with `w_i` isn't revealed, so it doesn't leak anything about the key.
+//!
+//! Successfully completed presigning outputs [Presignature] that can be used to
+//! [sign](super::sign) exactly one message.
+
+use std::fmt;
+use std::mem::replace;
+use std::time::Duration;
+
+use curv::cryptographic_primitives::secret_sharing::feldman_vss::{
+    ShamirSecretSharing, VerifiableSS,
+};
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point};
+use round_based::containers::{
+    push::{Push, PushExt},
+    *,
+};
+use round_based::{IsCritical, Msg, StateMachine};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::aux_info::AuxInfo;
+use super::keygen::LocalKey;
+
+mod rounds;
+
+use private::InternalError;
+use rounds::{DeltaShare, EncryptedNonces, MtaMessages, Reveal, Setup};
+pub use rounds::{Presignature, ProceedError};
+use rounds::{Round0, Round1, Round2, Round3, Round3Output, Round4};
+
+/// Presigning protocol state machine
+///
+/// Successfully completed presigning produces [Presignature].
+pub struct Presign {
+    round: R,
+
+    msgs1: Option<Store<BroadcastMsgs<EncryptedNonces>>>,
+    msgs2: Option<Store<BroadcastMsgs<MtaMessages>>>,
+    msgs3: Option<Store<BroadcastMsgs<DeltaShare>>>,
+    msgs4: Option<Store<BroadcastMsgs<Reveal>>>,
+
+    msgs_queue: Vec<Msg<ProtocolMessage>>,
+
+    party_i: u16,
+    party_n: u16,
+}
+
+impl Presign {
+    /// Constructs a party of presigning protocol
+    ///
+    /// Takes party index `i` (in range `[1; |s_l|]`), list `s_l` of keygen indexes of the parties
+    /// taking part in presigning, party's [LocalKey], [AuxInfo], and execution id that must be
+    /// unique and the same for all parties. `s_l[i-1]` must be equal to index of the party at
+    /// keygen.
+    ///
+    /// Returns error if:
+    /// * `s_l` has less than `t+1` parties, returns [Error::TooFewParties]
+    /// * `s_l` contains duplicates or indexes that are not in range `[1; n]`, returns
+    ///   [Error::InvalidSignersList]
+    /// * `i` is not in range `[1; |s_l|]` or `s_l[i-1]` doesn't match index in local key, returns
+    ///   [Error::InvalidPartyIndex]
+    /// * aux info was obtained by another party or for another number of parties, returns
+    ///   [Error::MismatchedAuxInfo]
+    pub fn new(
+        i: u16,
+        s_l: Vec<u16>,
+        local_key: LocalKey,
+        aux_info: AuxInfo,
+        execution_id: &[u8],
+    ) -> Result<Self> {
+        if s_l.len() <= usize::from(local_key.t) {
+            return Err(Error::TooFewParties);
+        }
+        let mut sorted = s_l.clone();
+        sorted.sort_unstable();
+        sorted.dedup();
+        if sorted.len() != s_l.len() || s_l.iter().any(|&j| j == 0 || j > local_key.n) {
+            return Err(Error::InvalidSignersList);
+        }
+        let n = s_l.len() as u16;
+        if i == 0 || i > n || s_l[usize::from(i - 1)] != local_key.i {
+            return Err(Error::InvalidPartyIndex);
+        }
+        if aux_info.i != local_key.i || aux_info.n != local_key.n {
+            return Err(Error::MismatchedAuxInfo);
+        }
+
+        let params = ShamirSecretSharing {
+            threshold: local_key.t,
+            share_count: local_key.n,
+        };
+        let s_l0 = s_l.iter().map(|j| j - 1).collect::<Vec<_>>();
+        let public_w = s_l
+            .iter()
+            .map(|&j| {
+                let lambda =
+                    VerifiableSS::<Secp256k1>::map_share_to_new_params(&params, j - 1, &s_l0);
+                &local_key.public_shares[usize::from(j - 1)] * lambda
+            })
+            .collect::<Vec<Point<Secp256k1>>>();
+        let lambda =
+            VerifiableSS::<Secp256k1>::map_share_to_new_params(&params, local_key.i - 1, &s_l0);
+
+        let setup = Setup {
+            i,
+            n,
+            w: &local_key.x * lambda,
+            public_w,
+            public_key: local_key.y,
+            parties: s_l
+                .iter()
+                .map(|&j| aux_info.parties[usize::from(j - 1)].clone())
+                .collect(),
+            dk: aux_info.paillier_dk,
+            execution_id: execution_id.to_vec(),
+        };
+
+        let mut state = Self {
+            round: R::Round0(Round0 { setup }),
+
+            msgs1: Some(Round1::expects_messages(i, n)),
+            msgs2: Some(Round2::expects_messages(i, n)),
+            msgs3: Some(Round3::expects_messages(i, n)),
+            msgs4: Some(Round4::expects_messages(i, n)),
+
+            msgs_queue: vec![],
+
+            party_i: i,
+            party_n: n,
+        };
+
+        state.proceed_round(false)?;
+        Ok(state)
+    }
+
+    fn gmap_queue<'a, T, F>(&'a mut self, mut f: F) -> impl Push<Msg<T>> + 'a
+    where
+        F: FnMut(T) -> M + 'a,
+    {
+        (&mut self.msgs_queue).gmap(move |m: Msg<T>| m.map_body(|m| ProtocolMessage(f(m))))
+    }
+
+    /// Proceeds round state if it received enough messages and if it's cheap to compute or
+    /// `may_block == true`
+    fn proceed_round(&mut self, may_block: bool) -> Result<()> {
+        let store1_wants_more = self.msgs1.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store2_wants_more = self.msgs2.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store3_wants_more = self.msgs3.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store4_wants_more = self.msgs4.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+
+        let next_state: R;
+        let try_again: bool = match replace(&mut self.round, R::Gone) {
+            R::Round0(round) if !round.is_expensive() || may_block => {
+                next_state = round
+                    .proceed(self.gmap_queue(M::Round1))
+                    .map(R::Round1)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round0(_) => {
+                next_state = s;
+                false
+            }
+            R::Round1(round) if !store1_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs1.take().ok_or(InternalError::StoreGone)?;
+                let msgs = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?;
+                next_state = round
+                    .proceed(msgs, self.gmap_queue(M::Round2))
+                    .map(R::Round2)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round1(_) => {
+                next_state = s;
+                false
+            }
+            R::Round2(round) if !store2_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs2.take().ok_or(InternalError::StoreGone)?;
+                let msgs = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?;
+                next_state = round
+                    .proceed(msgs, self.gmap_queue(M::Round3))
+                    .map(R::Round3)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round2(_) => {
+                next_state = s;
+                false
+            }
+            R::Round3(round) if !store3_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs3.take().ok_or(InternalError::StoreGone)?;
+                let msgs = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?;
+                next_state = match round
+                    .proceed(msgs, self.gmap_queue(M::Round4))
+                    .map_err(Error::ProceedRound)?
+                {
+                    Round3Output::Presignature(presignature) => R::Final(presignature),
+                    Round3Output::Blame(round) => R::Round4(round),
+                };
+                true
+            }
+            s @ R::Round3(_) => {
+                next_state = s;
+                false
+            }
+            R::Round4(round) if !store4_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs4.take().ok_or(InternalError::StoreGone)?;
+                let msgs = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?;
+                return Err(Error::ProceedRound(round.proceed(msgs)));
+            }
+            s @ R::Round4(_) => {
+                next_state = s;
+                false
+            }
+            s @ R::Final(_) | s @ R::Gone => {
+                next_state = s;
+                false
+            }
+        };
+
+        self.round = next_state;
+        if try_again {
+            self.proceed_round(may_block)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl StateMachine for Presign {
+    type MessageBody = ProtocolMessage;
+    type Err = Error;
+    type Output = Presignature;
+
+    fn handle_incoming(&mut self, msg: Msg<Self::MessageBody>) -> Result<()> {
+        let current_round = self.current_round();
+
+        match msg.body {
+            ProtocolMessage(M::Round1(m)) => {
+                let store = self
+                    .msgs1
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 1,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+            ProtocolMessage(M::Round2(m)) => {
+                let store = self
+                    .msgs2
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 2,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+            ProtocolMessage(M::Round3(m)) => {
+                let store = self
+                    .msgs3
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 3,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+            ProtocolMessage(M::Round4(m)) => {
+                let store = self
+                    .msgs4
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 4,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+        }
+        self.proceed_round(false)
+    }
+
+    fn message_queue(&mut self) -> &mut Vec<Msg<Self::MessageBody>> {
+        &mut self.msgs_queue
+    }
+
+    fn wants_to_proceed(&self) -> bool {
+        let store1_wants_more = self.msgs1.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store2_wants_more = self.msgs2.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store3_wants_more = self.msgs3.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store4_wants_more = self.msgs4.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+
+        match &self.round {
+            R::Round0(_) => true,
+            R::Round1(_) => !store1_wants_more,
+            R::Round2(_) => !store2_wants_more,
+            R::Round3(_) => !store3_wants_more,
+            R::Round4(_) => !store4_wants_more,
+            R::Final(_) | R::Gone => false,
+        }
+    }
+
+    fn proceed(&mut self) -> Result<()> {
+        self.proceed_round(true)
+    }
+
+    fn round_timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    fn round_timeout_reached(&mut self) -> Self::Err {
+        panic!("no timeout was set")
+    }
+
+    fn is_finished(&self) -> bool {
+        matches!(self.round, R::Final(_))
+    }
+
+    fn pick_output(&mut self) -> Option<Result<Self::Output>> {
+        match self.round {
+            R::Final(_) => (),
+            R::Gone => return Some(Err(Error::DoublePickOutput)),
+            _ => return None,
+        }
+
+        match replace(&mut self.round, R::Gone) {
+            R::Final(result) => Some(Ok(result)),
+            _ => unreachable!("guaranteed by match expression above"),
+        }
+    }
+
+    fn current_round(&self) -> u16 {
+        match &self.round {
+            R::Round0(_) => 0,
+            R::Round1(_) => 1,
+            R::Round2(_) => 2,
+            R::Round3(_) => 3,
+            R::Round4(_) => 4,
+            R::Final(_) | R::Gone => 5,
+        }
+    }
+
+    /// Presigning takes 3 rounds if every party behaves honestly, and one more round otherwise,
+    /// so total number of rounds is unknown in advance
+    fn total_rounds(&self) -> Option<u16> {
+        None
+    }
+
+    fn party_ind(&self) -> u16 {
+        self.party_i
+    }
+
+    fn parties(&self) -> u16 {
+        self.party_n
+    }
+}
+
+impl fmt::Debug for Presign {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{{CGGMP21 Presign at round={} queue=[len={}]}}",
+            self.current_round(),
+            self.msgs_queue.len()
+        )
+    }
+}
+
+// Rounds
+
+enum R {
+    Round0(Round0),
+    Round1(Round1),
+    Round2(Round2),
+    Round3(Round3),
+    Round4(Round4),
+    Final(Presignature),
+    Gone,
+}
+
+// Messages
+
+/// Protocol message which parties send on wire
+///
+/// Hides actual messages structure so it could be changed without breaking semver policy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtocolMessage(M);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+enum M {
+    Round1(EncryptedNonces),
+    Round2(MtaMessages),
+    Round3(DeltaShare),
+    Round4(Reveal),
+}
+
+// Error
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Error type of presigning protocol
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// Round proceeding resulted in error
+    #[error("proceed round: {0}")]
+    ProceedRound(#[source] ProceedError),
+
+    /// Less than `t+1` parties take part in presigning
+    #[error("at least t+1 parties are required for presigning")]
+    TooFewParties,
+    /// List of signers contains duplicates or indexes that are not in range `[1; n]`
+    #[error("list of signers is invalid")]
+    InvalidSignersList,
+    /// Party index `i` is not in range `[1; |s_l|]` or doesn't match local key
+    #[error("party index is not in range [1; |s_l|] or doesn't match local key")]
+    InvalidPartyIndex,
+    /// Aux info doesn't match local key
+    #[error("aux info was generated by another party or for another number of parties")]
+    MismatchedAuxInfo,
+
+    /// Received message didn't pass pre-validation
+    #[error("received message didn't pass pre-validation: {0}")]
+    HandleMessage(#[source] StoreErr),
+    /// Received message which we didn't expect to receive now (e.g. message from previous round)
+    #[error(
+        "didn't expect to receive message from round {msg_round} (being at round {current_round})"
+    )]
+    ReceivedOutOfOrderMessage { current_round: u16, msg_round: u16 },
+    /// [Presign::pick_output] called twice
+    #[error("pick_output called twice")]
+    DoublePickOutput,
+
+    /// Some internal assertions were failed, which is a bug
+    #[doc(hidden)]
+    #[error("internal error: {0:?}")]
+    InternalError(InternalError),
+}
+
+impl IsCritical for Error {
+    fn is_critical(&self) -> bool {
+        !matches!(
+            self,
+            Error::HandleMessage(_) | Error::ReceivedOutOfOrderMessage { .. }
+        )
+    }
+}
+
+impl From<InternalError> for Error {
+    fn from(err: InternalError) -> Self {
+        Self::InternalError(err)
+    }
+}
+
+mod private {
+    #[derive(Debug)]
+    #[non_exhaustive]
+    pub enum InternalError {
+        /// [Messages store](super::MessageStore) reported that it received all messages it wanted to receive,
+        /// but refused to return message container
+        RetrieveRoundMessages(super::StoreErr),
+        #[doc(hidden)]
+        StoreGone,
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use round_based::dev::Simulation;
+
+    use super::super::aux_info::test::simulate_aux_info;
+    use super::super::keygen::test::simulate_keygen;
+    use super::*;
+
+    pub fn simulate_presign(keys: &[LocalKey], aux: &[AuxInfo], s_l: &[u16]) -> Vec<Presignature> {
+        let mut simulation = Simulation::new();
+        for (i, &j) in (1..).zip(s_l) {
+            let j = usize::from(j - 1);
+            simulation.add_party(
+                Presign::new(i, s_l.to_vec(), keys[j].clone(), aux[j].clone(), b"presign").unwrap(),
+            );
+        }
+        let presignatures = simulation.run().unwrap();
+
+        for p in &presignatures {
+            assert_eq!(p.r, presignatures[0].r);
+        }
+        presignatures
+    }
+
+    #[test]
+    fn simulate_presign_t1_n3() {
+        let keys = simulate_keygen(1, 3);
+        let aux = simulate_aux_info(3);
+        simulate_presign(&keys, &aux, &[1, 3]);
+    }
+
+    /// Runs presigning delivering messages via `tamper` that can modify message for particular
+    /// receiver, returns result of every party that finished before the protocol got stuck
+    fn run_tampered(
+        keys: &[LocalKey],
+        aux: &[AuxInfo],
+        s_l: &[u16],
+        tamper: impl Fn(u16, &mut Msg<ProtocolMessage>),
+    ) -> Vec<Option<Result<Presignature>>> {
+        let mut parties = (1..)
+            .zip(s_l)
+            .map(|(i, &j)| {
+                let j = usize::from(j - 1);
+                Presign::new(i, s_l.to_vec(), keys[j].clone(), aux[j].clone(), b"presign").map(Some)
+            })
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let mut results = (0..parties.len()).map(|_| None).collect::<Vec<_>>();
+
+        loop {
+            let mut msgs = vec![];
+            let mut proceeded = false;
+            for (party, result) in parties.iter_mut().zip(&mut results) {
+                let p = match party {
+                    Some(p) => p,
+                    None => continue,
+                };
+                if p.wants_to_proceed() {
+                    proceeded = true;
+                    if let Err(err) = p.proceed() {
+                        *result = Some(Err(err));
+                        *party = None;
+                        continue;
+                    }
+                }
+                msgs.append(p.message_queue());
+                if let Some(output) = p.pick_output() {
+                    *result = Some(output);
+                    *party = None;
+                }
+            }
+            if msgs.is_empty() && !proceeded {
+                return results;
+            }
+            for msg in msgs {
+                for (receiver, party) in (1..).zip(parties.iter_mut()) {
+                    let p = match party {
+                        Some(p) if receiver != msg.sender => p,
+                        _ => continue,
+                    };
+                    let mut msg = msg.clone();
+                    tamper(receiver, &mut msg);
+                    p.handle_incoming(msg).unwrap();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn identifies_party_sending_invalid_mta() {
+        let keys = simulate_keygen(1, 3);
+        let aux = simulate_aux_info(3);
+        let results = run_tampered(&keys, &aux, &[1, 2, 3], |_, msg| {
+            if let ProtocolMessage(M::Round2(m)) = &mut msg.body {
+                if msg.sender == 2 {
+                    m.mta[0].d += 1;
+                }
+            }
+        });
+        assert!(matches!(
+            &results[0],
+            Some(Err(Error::ProceedRound(ProceedError::Round2InvalidMtaProof { culprits })))
+                if culprits == &[2]
+        ));
+    }
+
+    #[test]
+    fn identifies_party_sending_inconsistent_delta() {
+        let keys = simulate_keygen(1, 3);
+        let aux = simulate_aux_info(3);
+        // Party 2 is seen as cheater by parties 1 and 3, party 1 is seen as cheater by party 2
+        let results = run_tampered(&keys, &aux, &[1, 2, 3], |receiver, msg| {
+            if let ProtocolMessage(M::Round3(m)) = &mut msg.body {
+                if msg.sender == 2 || (msg.sender, receiver) == (1, 2) {
+                    m.delta = &m.delta + curv::elliptic::curves::Scalar::from(1);
+                }
+            }
+        });
+        for (result, expected) in results.iter().zip(&[2, 1, 2]) {
+            assert!(
+                matches!(
+                    result,
+                    Some(Err(Error::ProceedRound(ProceedError::Round4InconsistentDelta { culprits })))
+                        if culprits == &[*expected]
+                ),
+                "{:?}",
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_signers_list() {
+        let keys = simulate_keygen(1, 3);
+        let aux = simulate_aux_info(3);
+        assert!(matches!(
+            Presign::new(1, vec![1], keys[0].clone(), aux[0].clone(), b"presign"),
+            Err(Error::TooFewParties)
+        ));
+        assert!(matches!(
+            Presign::new(1, vec![1, 1], keys[0].clone(), aux[0].clone(), b"presign"),
+            Err(Error::InvalidSignersList)
+        ));
+        assert!(matches!(
+            Presign::new(1, vec![2, 3], keys[0].clone(), aux[0].clone(), b"presign"),
+            Err(Error::InvalidPartyIndex)
+        ));
+        assert!(matches!(
+            Presign::new(1, vec![1, 2], keys[0].clone(), aux[1].clone(), b"presign"),
+            Err(Error::MismatchedAuxInfo)
+        ));
+    }
+}
@@ -0,0 +1,692 @@
+use curv::arithmetic::traits::*;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use curv::BigInt;
+use paillier::{
+    Add, Decrypt, DecryptionKey, EncryptWithChosenRandomness, EncryptionKey, Mul, Paillier,
+    Randomness, RawCiphertext, RawPlaintext,
+};
+use round_based::containers::push::Push;
+use round_based::containers::{self, BroadcastMsgs, Store};
+use round_based::Msg;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use zk_paillier::zkproofs::DLogStatement;
+
+use crate::utilities::mta::range_proofs::{AliceProof, BobProofExt, SampleFromMultiplicativeGroup};
+use crate::utilities::mta::MessageA;
+use crate::utilities::zk_pdl_with_slack::{PDLwSlackProof, PDLwSlackStatement, PDLwSlackWitness};
+
+use super::super::aux_info::PartyAux;
+use super::super::{proof_index, Transcript};
+
+/// Encrypted nonces `K_i = enc_i(k_i)` and `G_i = enc_i(γ_i)`, sent at round 1
+///
+/// `K_i` comes with Πenc range proofs, one per every other party.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EncryptedNonces {
+    pub k: MessageA,
+    pub g: BigInt,
+}
+
+/// `Γ_i = γ_i * G` and MtA messages for every other party, sent at round 2
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MtaMessages {
+    pub gamma: Point<Secp256k1>,
+    pub mta: Vec<MtaMessage>,
+}
+
+/// MtA messages for party `j`
+///
+/// Carries `D_{j,i} = γ_i * K_j + enc_j(β'_{i,j})`, `D̂_{j,i} = w_i * K_j + enc_j(β̂'_{i,j})` with
+/// Πaff-g proofs binding them to `Γ_i` and `W_i`, and Πlog* proof that `G_i` encrypts `log Γ_i`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MtaMessage {
+    pub d: BigInt,
+    pub d_proof: BobProofExt,
+    pub d_hat: BigInt,
+    pub d_hat_proof: BobProofExt,
+    pub gamma_proof: PDLwSlackProof,
+}
+
+/// `δ_i` and `Δ_i = k_i * Γ`, sent at round 3
+///
+/// `Δ_i` comes with Πlog* proofs, one per every other party.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DeltaShare {
+    pub delta: Scalar<Secp256k1>,
+    pub big_delta: Point<Secp256k1>,
+    pub proofs: Vec<PDLwSlackProof>,
+}
+
+/// Nonces and MtA masks revealed at round 4 if `δ * G != Σ Δ_j`
+///
+/// Nonces are discarded after abort, so revealing them doesn't leak anything about the key.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Reveal {
+    pub k: Scalar<Secp256k1>,
+    pub rho: BigInt,
+    pub gamma: Scalar<Secp256k1>,
+    pub nu: BigInt,
+    /// `(β'_{i,j}, randomness)` of `D_{j,i}` for every other party `j`
+    pub betas: Vec<(BigInt, BigInt)>,
+}
+
+/// Data that doesn't change throughout the protocol
+pub struct Setup {
+    pub i: u16,
+    pub n: u16,
+    /// Party's share of the secret key, multiplied by its Lagrange coefficient
+    pub w: Scalar<Secp256k1>,
+    /// `W_j = w_j * G` of every party
+    pub public_w: Vec<Point<Secp256k1>>,
+    pub public_key: Point<Secp256k1>,
+    pub parties: Vec<PartyAux>,
+    pub dk: DecryptionKey,
+    pub execution_id: Vec<u8>,
+}
+
+impl Setup {
+    fn ek(&self, j: u16) -> EncryptionKey {
+        self.parties[usize::from(j - 1)].ek()
+    }
+
+    fn dlog_statement(&self, j: u16) -> DLogStatement {
+        self.parties[usize::from(j - 1)].dlog_statement()
+    }
+
+    fn others(&self) -> impl Iterator<Item = u16> {
+        let i = self.i;
+        (1..=self.n).filter(move |&j| j != i)
+    }
+
+    /// Context that binds a proof to the execution, the prover and the verifier
+    fn context(&self, proof: &[u8], prover: u16, verifier: u16) -> [u8; 32] {
+        Transcript::new(b"cggmp21/presign")
+            .bytes(proof)
+            .bytes(&self.execution_id)
+            .u16(prover)
+            .u16(verifier)
+            .finish()
+    }
+}
+
+pub struct Round0 {
+    pub setup: Setup,
+}
+
+impl Round0 {
+    pub fn proceed<O>(self, mut output: O) -> Result<Round1>
+    where
+        O: Push<Msg<EncryptedNonces>>,
+    {
+        let setup = self.setup;
+        let ek = setup.ek(setup.i);
+
+        let k = Scalar::random();
+        let gamma = Scalar::random();
+        let rho = BigInt::from_paillier_key(&ek);
+        let nu = BigInt::from_paillier_key(&ek);
+
+        let k_enc = encrypt(&ek, &k.to_bigint(), &rho);
+        let range_proofs = setup
+            .others()
+            .map(|j| {
+                AliceProof::generate_with_context::<Secp256k1>(
+                    &k.to_bigint(),
+                    &k_enc,
+                    &ek,
+                    &setup.dlog_statement(j),
+                    &rho,
+                    &setup.context(b"enc", setup.i, j),
+                )
+            })
+            .collect();
+        let nonces = EncryptedNonces {
+            k: MessageA {
+                c: k_enc,
+                range_proofs,
+            },
+            g: encrypt(&ek, &gamma.to_bigint(), &nu),
+        };
+        output.push(Msg {
+            sender: setup.i,
+            receiver: None,
+            body: nonces.clone(),
+        });
+
+        Ok(Round1 {
+            nonces,
+            secrets: Secrets { k, rho, gamma, nu },
+            setup,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+}
+
+/// Party's nonces and their encryption randomness
+struct Secrets {
+    k: Scalar<Secp256k1>,
+    rho: BigInt,
+    gamma: Scalar<Secp256k1>,
+    nu: BigInt,
+}
+
+pub struct Round1 {
+    nonces: EncryptedNonces,
+    secrets: Secrets,
+    setup: Setup,
+}
+
+impl Round1 {
+    pub fn proceed<O>(self, input: BroadcastMsgs<EncryptedNonces>, mut output: O) -> Result<Round2>
+    where
+        O: Push<Msg<MtaMessages>>,
+    {
+        let setup = self.setup;
+        let nonces = input.into_vec_including_me(self.nonces);
+
+        let my_dlog_statement = setup.dlog_statement(setup.i);
+        let culprits = setup
+            .others()
+            .filter(|&j| {
+                let k = &nonces[usize::from(j - 1)].k;
+                k.range_proofs.len() != usize::from(setup.n - 1)
+                    || !k.range_proofs[proof_index(j, setup.i)].verify_with_context::<Secp256k1>(
+                        &k.c,
+                        &setup.ek(j),
+                        &my_dlog_statement,
+                        &setup.context(b"enc", j, setup.i),
+                    )
+            })
+            .collect::<Vec<u16>>();
+        if !culprits.is_empty() {
+            return Err(ProceedError::Round1InvalidEncProof { culprits });
+        }
+
+        let gamma = Point::generator() * &self.secrets.gamma;
+        let my_ek = setup.ek(setup.i);
+        let gamma_witness = PDLwSlackWitness {
+            x: self.secrets.gamma.clone(),
+            r: self.secrets.nu.clone(),
+        };
+
+        let mut betas = vec![];
+        let mut beta_hats = vec![];
+        let mut reveal_betas = vec![];
+        let mut mta = vec![];
+        for j in setup.others() {
+            let ek_j = setup.ek(j);
+            let dlog_statement = setup.dlog_statement(j);
+            let k_j = &nonces[usize::from(j - 1)].k.c;
+
+            let (beta_prim, r) = (
+                BigInt::sample_below(&ek_j.n),
+                BigInt::from_paillier_key(&ek_j),
+            );
+            let d = affine(&ek_j, k_j, &self.secrets.gamma, &beta_prim, &r);
+            let d_proof = BobProofExt::generate_with_context(
+                k_j,
+                &d,
+                &self.secrets.gamma,
+                &beta_prim,
+                &ek_j,
+                &dlog_statement,
+                &Randomness::from(&r),
+                &setup.context(b"aff-g/gamma", setup.i, j),
+            );
+
+            let (beta_hat_prim, r_hat) = (
+                BigInt::sample_below(&ek_j.n),
+                BigInt::from_paillier_key(&ek_j),
+            );
+            let d_hat = affine(&ek_j, k_j, &setup.w, &beta_hat_prim, &r_hat);
+            let d_hat_proof = BobProofExt::generate_with_context(
+                k_j,
+                &d_hat,
+                &setup.w,
+                &beta_hat_prim,
+                &ek_j,
+                &dlog_statement,
+                &Randomness::from(&r_hat),
+                &setup.context(b"aff-g/w", setup.i, j),
+            );
+
+            let gamma_proof = PDLwSlackProof::prove_with_context(
+                &gamma_witness,
+                &pdl_statement(
+                    &nonces[usize::from(setup.i - 1)].g,
+                    &my_ek,
+                    &gamma,
+                    &Point::generator().to_point(),
+                    &dlog_statement,
+                ),
+                &setup.context(b"log/gamma", setup.i, j),
+            );
+
+            betas.push(-Scalar::from(&beta_prim));
+            beta_hats.push(-Scalar::from(&beta_hat_prim));
+            reveal_betas.push((beta_prim, r));
+            mta.push(MtaMessage {
+                d,
+                d_proof,
+                d_hat,
+                d_hat_proof,
+                gamma_proof,
+            });
+        }
+
+        let messages = MtaMessages { gamma, mta };
+        output.push(Msg {
+            sender: setup.i,
+            receiver: None,
+            body: messages.clone(),
+        });
+
+        Ok(Round2 {
+            messages,
+            nonces,
+            secrets: self.secrets,
+            betas,
+            beta_hats,
+            reveal_betas,
+            setup,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<EncryptedNonces>> {
+        containers::BroadcastMsgsStore::new(i, n)
+    }
+}
+
+pub struct Round2 {
+    messages: MtaMessages,
+    nonces: Vec<EncryptedNonces>,
+    secrets: Secrets,
+    betas: Vec<Scalar<Secp256k1>>,
+    beta_hats: Vec<Scalar<Secp256k1>>,
+    reveal_betas: Vec<(BigInt, BigInt)>,
+    setup: Setup,
+}
+
+impl Round2 {
+    pub fn proceed<O>(self, input: BroadcastMsgs<MtaMessages>, mut output: O) -> Result<Round3>
+    where
+        O: Push<Msg<DeltaShare>>,
+    {
+        let setup = self.setup;
+        let messages = input.into_vec_including_me(self.messages);
+
+        let i = setup.i;
+        let my_ek = setup.ek(i);
+        let my_dlog_statement = setup.dlog_statement(i);
+        let nonces = &self.nonces;
+        let my_k = &nonces[usize::from(i - 1)].k.c;
+        let culprits = setup
+            .others()
+            .filter(|&j| {
+                let from_j = &messages[usize::from(j - 1)];
+                if from_j.mta.len() != usize::from(setup.n - 1) {
+                    return true;
+                }
+                let m = &from_j.mta[proof_index(j, i)];
+                let statement = pdl_statement(
+                    &nonces[usize::from(j - 1)].g,
+                    &setup.ek(j),
+                    &from_j.gamma,
+                    &Point::generator().to_point(),
+                    &my_dlog_statement,
+                );
+                !m.d_proof.verify_with_context(
+                    my_k,
+                    &m.d,
+                    &my_ek,
+                    &my_dlog_statement,
+                    &from_j.gamma,
+                    &setup.context(b"aff-g/gamma", j, i),
+                ) || !m.d_hat_proof.verify_with_context(
+                    my_k,
+                    &m.d_hat,
+                    &my_ek,
+                    &my_dlog_statement,
+                    &setup.public_w[usize::from(j - 1)],
+                    &setup.context(b"aff-g/w", j, i),
+                ) || m
+                    .gamma_proof
+                    .verify_with_context(&statement, &setup.context(b"log/gamma", j, i))
+                    .is_err()
+            })
+            .collect::<Vec<u16>>();
+        if !culprits.is_empty() {
+            return Err(ProceedError::Round2InvalidMtaProof { culprits });
+        }
+
+        let gamma = messages.iter().fold(Point::zero(), |acc, m| acc + &m.gamma);
+        let big_delta = &gamma * &self.secrets.k;
+
+        let mut delta = &self.secrets.k * &self.secrets.gamma;
+        let mut chi = &self.secrets.k * &setup.w;
+        for (j, (beta, beta_hat)) in setup.others().zip(self.betas.iter().zip(&self.beta_hats)) {
+            let m = &messages[usize::from(j - 1)].mta[proof_index(j, i)];
+            let alpha = decrypt(&setup.dk, &m.d);
+            let alpha_hat = decrypt(&setup.dk, &m.d_hat);
+            delta = delta + Scalar::from(&alpha) + beta;
+            chi = chi + Scalar::from(&alpha_hat) + beta_hat;
+        }
+
+        let witness = PDLwSlackWitness {
+            x: self.secrets.k.clone(),
+            r: self.secrets.rho.clone(),
+        };
+        let proofs = setup
+            .others()
+            .map(|j| {
+                PDLwSlackProof::prove_with_context(
+                    &witness,
+                    &pdl_statement(my_k, &my_ek, &big_delta, &gamma, &setup.dlog_statement(j)),
+                    &setup.context(b"log/delta", i, j),
+                )
+            })
+            .collect();
+
+        let share = DeltaShare {
+            delta,
+            big_delta,
+            proofs,
+        };
+        output.push(Msg {
+            sender: i,
+            receiver: None,
+            body: share.clone(),
+        });
+
+        Ok(Round3 {
+            share,
+            gamma,
+            chi,
+            messages,
+            nonces: self.nonces,
+            secrets: self.secrets,
+            reveal_betas: self.reveal_betas,
+            setup,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<MtaMessages>> {
+        containers::BroadcastMsgsStore::new(i, n)
+    }
+}
+
+pub struct Round3 {
+    share: DeltaShare,
+    gamma: Point<Secp256k1>,
+    chi: Scalar<Secp256k1>,
+    messages: Vec<MtaMessages>,
+    nonces: Vec<EncryptedNonces>,
+    secrets: Secrets,
+    reveal_betas: Vec<(BigInt, BigInt)>,
+    setup: Setup,
+}
+
+/// Result of round 3: either presignature, or the protocol proceeds to the blame round
+#[allow(clippy::large_enum_variant)]
+pub enum Round3Output {
+    Presignature(Presignature),
+    Blame(Round4),
+}
+
+impl Round3 {
+    pub fn proceed<O>(self, input: BroadcastMsgs<DeltaShare>, mut output: O) -> Result<Round3Output>
+    where
+        O: Push<Msg<Reveal>>,
+    {
+        let setup = self.setup;
+        let shares = input.into_vec_including_me(self.share);
+
+        let my_dlog_statement = setup.dlog_statement(setup.i);
+        let (nonces, gamma) = (&self.nonces, &self.gamma);
+        let culprits = setup
+            .others()
+            .filter(|&j| {
+                let share = &shares[usize::from(j - 1)];
+                if share.proofs.len() != usize::from(setup.n - 1) {
+                    return true;
+                }
+                let statement = pdl_statement(
+                    &nonces[usize::from(j - 1)].k.c,
+                    &setup.ek(j),
+                    &share.big_delta,
+                    gamma,
+                    &my_dlog_statement,
+                );
+                share.proofs[proof_index(j, setup.i)]
+                    .verify_with_context(&statement, &setup.context(b"log/delta", j, setup.i))
+                    .is_err()
+            })
+            .collect::<Vec<u16>>();
+        if !culprits.is_empty() {
+            return Err(ProceedError::Round3InvalidLogProof { culprits });
+        }
+
+        let delta = shares
+            .iter()
+            .fold(Scalar::zero(), |acc, share| acc + &share.delta);
+        let big_delta = shares
+            .iter()
+            .fold(Point::zero(), |acc, share| acc + &share.big_delta);
+
+        if let Some(delta_inv) = delta.invert() {
+            if Point::generator() * &delta == big_delta {
+                return Ok(Round3Output::Presignature(Presignature {
+                    r: &self.gamma * delta_inv,
+                    k: self.secrets.k,
+                    chi: self.chi,
+                    public_key: setup.public_key,
+                    i: setup.i,
+                    n: setup.n,
+                }));
+            }
+        }
+
+        let reveal = Reveal {
+            k: self.secrets.k,
+            rho: self.secrets.rho,
+            gamma: self.secrets.gamma,
+            nu: self.secrets.nu,
+            betas: self.reveal_betas,
+        };
+        output.push(Msg {
+            sender: setup.i,
+            receiver: None,
+            body: reveal.clone(),
+        });
+
+        Ok(Round3Output::Blame(Round4 {
+            reveal,
+            gamma: self.gamma,
+            shares,
+            messages: self.messages,
+            nonces: self.nonces,
+            setup,
+        }))
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<DeltaShare>> {
+        containers::BroadcastMsgsStore::new(i, n)
+    }
+}
+
+pub struct Round4 {
+    reveal: Reveal,
+    gamma: Point<Secp256k1>,
+    shares: Vec<DeltaShare>,
+    messages: Vec<MtaMessages>,
+    nonces: Vec<EncryptedNonces>,
+    setup: Setup,
+}
+
+impl Round4 {
+    /// Identifies parties who caused the abort
+    ///
+    /// Every party reveals its nonces and masks of MtA it acted as Bob in, so everyone can
+    /// recompute `δ_j` and `Δ_j` of every party and find the ones that don't match.
+    pub fn proceed(self, input: BroadcastMsgs<Reveal>) -> ProceedError {
+        let reveals = input.into_vec_including_me(self.reveal);
+        let (setup, nonces, messages, shares, gamma) = (
+            &self.setup,
+            &self.nonces,
+            &self.messages,
+            &self.shares,
+            &self.gamma,
+        );
+        let others = |j: u16| (1..=setup.n).filter(move |&l| l != j);
+
+        let culprits = (1..=setup.n)
+            .filter(|&j| {
+                let reveal = &reveals[usize::from(j - 1)];
+                let nonces_j = &nonces[usize::from(j - 1)];
+                let messages_j = &messages[usize::from(j - 1)];
+                let ek_j = setup.ek(j);
+                reveal.betas.len() != usize::from(setup.n - 1)
+                    || encrypt(&ek_j, &reveal.k.to_bigint(), &reveal.rho) != nonces_j.k.c
+                    || encrypt(&ek_j, &reveal.gamma.to_bigint(), &reveal.nu) != nonces_j.g
+                    || Point::generator() * &reveal.gamma != messages_j.gamma
+                    || others(j).any(|l| {
+                        let (beta_prim, r) = &reveal.betas[proof_index(j, l)];
+                        let k_l = &nonces[usize::from(l - 1)].k.c;
+                        affine(&setup.ek(l), k_l, &reveal.gamma, beta_prim, r)
+                            != messages_j.mta[proof_index(j, l)].d
+                    })
+            })
+            .collect::<Vec<u16>>();
+        if !culprits.is_empty() {
+            return ProceedError::Round4InvalidReveal { culprits };
+        }
+
+        let culprits = (1..=setup.n)
+            .filter(|&j| {
+                let reveal = &reveals[usize::from(j - 1)];
+                let share = &shares[usize::from(j - 1)];
+                let n_j = setup.ek(j).n;
+                let delta = others(j).fold(&reveal.k * &reveal.gamma, |acc, l| {
+                    let reveal_l = &reveals[usize::from(l - 1)];
+                    let (beta_prim_lj, _) = &reveal_l.betas[proof_index(l, j)];
+                    let (beta_prim_jl, _) = &reveal.betas[proof_index(j, l)];
+                    let alpha =
+                        (reveal.k.to_bigint() * reveal_l.gamma.to_bigint() + beta_prim_lj) % &n_j;
+                    acc + Scalar::from(&alpha) - Scalar::from(beta_prim_jl)
+                });
+                delta != share.delta || gamma * &reveal.k != share.big_delta
+            })
+            .collect();
+        ProceedError::Round4InconsistentDelta { culprits }
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<Reveal>> {
+        containers::BroadcastMsgsStore::new(i, n)
+    }
+}
+
+/// Presignature obtained by party after [presigning](super::Presign) is completed
+///
+/// Presignature must be used to sign at most one message, signing two messages with the same
+/// presignature leaks the secret key.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Presignature {
+    /// `R = k^-1 * G`
+    pub r: Point<Secp256k1>,
+    /// Party's share of `k`
+    pub k: Scalar<Secp256k1>,
+    /// Party's share of `k * x`
+    pub chi: Scalar<Secp256k1>,
+    pub public_key: Point<Secp256k1>,
+    /// Index of the party in the list of signers
+    pub i: u16,
+    /// Number of signers
+    pub n: u16,
+}
+
+fn encrypt(ek: &EncryptionKey, m: &BigInt, r: &BigInt) -> BigInt {
+    Paillier::encrypt_with_chosen_randomness(ek, RawPlaintext::from(m), &Randomness::from(r))
+        .0
+        .into_owned()
+}
+
+/// Computes `x * c + enc(beta, r)`
+fn affine(
+    ek: &EncryptionKey,
+    c: &BigInt,
+    x: &Scalar<Secp256k1>,
+    beta: &BigInt,
+    r: &BigInt,
+) -> BigInt {
+    let x_c = Paillier::mul(
+        ek,
+        RawCiphertext::from(c),
+        RawPlaintext::from(x.to_bigint()),
+    );
+    let enc_beta = Paillier::encrypt_with_chosen_randomness(
+        ek,
+        RawPlaintext::from(beta),
+        &Randomness::from(r),
+    );
+    Paillier::add(ek, x_c, enc_beta).0.into_owned()
+}
+
+fn decrypt(dk: &DecryptionKey, c: &BigInt) -> BigInt {
+    Paillier::decrypt(dk, &RawCiphertext::from(c))
+        .0
+        .into_owned()
+}
+
+/// Πlog* statement: `c` encrypts discrete log of `q` in base `g`, verifier's ring-Pedersen
+/// parameters are given in `dlog_statement`
+fn pdl_statement(
+    c: &BigInt,
+    ek: &EncryptionKey,
+    q: &Point<Secp256k1>,
+    g: &Point<Secp256k1>,
+    dlog_statement: &DLogStatement,
+) -> PDLwSlackStatement {
+    PDLwSlackStatement {
+        ciphertext: c.clone(),
+        ek: ek.clone(),
+        Q: q.clone(),
+        G: g.clone(),
+        h1: dlog_statement.g.clone(),
+        h2: dlog_statement.ni.clone(),
+        N_tilde: dlog_statement.N.clone(),
+    }
+}
+
+// Errors
+
+type Result<T> = std::result::Result<T, ProceedError>;
+
+/// Proceeding protocol error
+///
+/// Subset of [presigning errors](enum@super::Error) that can occur at protocol proceeding (i.e.
+/// after every message was received and pre-validated). Every variant lists indexes of parties
+/// who misbehaved, indexes are positions in the list of signers (starting from 1).
+#[derive(Debug, Error)]
+pub enum ProceedError {
+    #[error("round 1: invalid encryption range proof: parties {culprits:?}")]
+    Round1InvalidEncProof { culprits: Vec<u16> },
+    #[error("round 2: invalid MtA proof: parties {culprits:?}")]
+    Round2InvalidMtaProof { culprits: Vec<u16> },
+    #[error("round 3: invalid Δ_i proof: parties {culprits:?}")]
+    Round3InvalidLogProof { culprits: Vec<u16> },
+    #[error("round 4: revealed nonces don't match ciphertexts: parties {culprits:?}")]
+    Round4InvalidReveal { culprits: Vec<u16> },
+    #[error("round 4: δ_i or Δ_i is inconsistent with revealed nonces: parties {culprits:?}")]
+    Round4InconsistentDelta { culprits: Vec<u16> },
+}
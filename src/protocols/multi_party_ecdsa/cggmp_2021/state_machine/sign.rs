@@ -0,0 +1,347 @@
+//! CGGMP21 one-round signing
+//!
+//! Given a [Presignature], every party computes its partial signature
+//! `σ_i = k_i * m + r * χ_i`, and the signature is `(r, Σ σ_j)`. [Sign] carries it out as a
+//! state machine, [SignManual] lets you construct messages and the final signature manually.
+//!
+//! Presignature must not be reused: signing two messages with the same presignature reveals the
+//! secret key. Both [Sign] and [SignManual] take presignature by value for that reason.
+
+use std::fmt;
+use std::time::Duration;
+
+use curv::arithmetic::traits::*;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use curv::BigInt;
+use round_based::containers::{BroadcastMsgs, BroadcastMsgsStore, MessageStore, Store, StoreErr};
+use round_based::{IsCritical, Msg, StateMachine};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::utilities::verification::{self, Mode, SignatureRecid};
+
+pub use super::presign::Presignature;
+
+/// Party's partial signature `σ_i`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartialSignature(pub Scalar<Secp256k1>);
+
+#[derive(Clone)]
+struct Signing {
+    r: Point<Secp256k1>,
+    public_key: Point<Secp256k1>,
+    message: BigInt,
+    partial: PartialSignature,
+}
+
+impl Signing {
+    fn new(presignature: Presignature, message: BigInt) -> Result<Self, SignError> {
+        let r = presignature
+            .r
+            .x_coord()
+            .ok_or(SignError::InvalidPresignature)?;
+        let r = Scalar::<Secp256k1>::from(&r);
+        let m = Scalar::<Secp256k1>::from(&message);
+        let partial = PartialSignature(&presignature.k * m + r * &presignature.chi);
+        Ok(Self {
+            r: presignature.r,
+            public_key: presignature.public_key,
+            message,
+            partial,
+        })
+    }
+
+    /// `sigs` contains partial signatures of all parties except the local one
+    fn complete(self, sigs: &[PartialSignature]) -> Result<SignatureRecid, SignError> {
+        let s = sigs
+            .iter()
+            .fold(self.partial.0.clone(), |acc, sig| acc + &sig.0);
+        let r_bn = self.r.x_coord().ok_or(SignError::InvalidPresignature)?;
+        let (s, recid) =
+            verification::normalize_s(&self.r, s).map_err(|_| SignError::InvalidSignature)?;
+        let signature = SignatureRecid {
+            r: Scalar::from(&r_bn.modulus(Scalar::<Secp256k1>::group_order())),
+            s,
+            recid,
+        };
+        verification::verify(
+            &signature.r,
+            &signature.s,
+            &self.public_key,
+            &self.message,
+            Mode::Strict,
+        )
+        .map_err(|_| SignError::InvalidSignature)?;
+        Ok(signature)
+    }
+}
+
+/// Manual one-round signing
+///
+/// ```no_run
+/// # use multi_party_ecdsa::protocols::multi_party_ecdsa::cggmp_2021::state_machine::sign::{
+/// #     PartialSignature, Presignature, SignManual,
+/// # };
+/// # use curv::arithmetic::{BigInt, Converter};
+/// # type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+/// # fn broadcast(msg: PartialSignature) -> Result<()> { panic!() }
+/// # fn wait_messages() -> Result<Vec<PartialSignature>> { panic!() }
+/// # fn main() -> Result<()> {
+/// # let presignature: Presignature = panic!();
+/// let data = BigInt::from_bytes(b"a message");
+///
+/// // Sign a message locally
+/// let (sign, msg) = SignManual::new(data, presignature)?;
+/// // Broadcast local partial signature
+/// broadcast(msg)?;
+/// // Collect partial signatures from other parties
+/// let sigs: Vec<PartialSignature> = wait_messages()?;
+/// // Complete signing
+/// let signature = sign.complete(&sigs)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct SignManual {
+    state: Signing,
+}
+
+impl SignManual {
+    pub fn new(
+        message: BigInt,
+        presignature: Presignature,
+    ) -> Result<(Self, PartialSignature), SignError> {
+        let state = Signing::new(presignature, message)?;
+        let partial = state.partial.clone();
+        Ok((Self { state }, partial))
+    }
+
+    /// `sigs` must not include partial signature produced by local party (only partial signatures produced
+    /// by other parties)
+    pub fn complete(self, sigs: &[PartialSignature]) -> Result<SignatureRecid, SignError> {
+        self.state.complete(sigs)
+    }
+}
+
+/// One-round signing state machine
+///
+/// Party index and number of parties are the same as at [presigning](super::presign::Presign).
+pub struct Sign {
+    state: Option<Signing>,
+    output: Option<SignatureRecid>,
+
+    msgs: Option<Store<BroadcastMsgs<PartialSignature>>>,
+    msgs_queue: Vec<Msg<PartialSignature>>,
+
+    party_i: u16,
+    party_n: u16,
+}
+
+impl Sign {
+    /// Constructs a party of signing protocol, immediately producing local partial signature
+    pub fn new(message: BigInt, presignature: Presignature) -> Result<Self, Error> {
+        let (i, n) = (presignature.i, presignature.n);
+        let state = Signing::new(presignature, message).map_err(Error::Sign)?;
+        let msgs_queue = vec![Msg {
+            sender: i,
+            receiver: None,
+            body: state.partial.clone(),
+        }];
+        Ok(Self {
+            state: Some(state),
+            output: None,
+            msgs: Some(BroadcastMsgsStore::new(i, n)),
+            msgs_queue,
+            party_i: i,
+            party_n: n,
+        })
+    }
+}
+
+impl StateMachine for Sign {
+    type MessageBody = PartialSignature;
+    type Err = Error;
+    type Output = SignatureRecid;
+
+    fn handle_incoming(&mut self, msg: Msg<Self::MessageBody>) -> Result<(), Error> {
+        let store = self.msgs.as_mut().ok_or(Error::ReceivedOutOfOrderMessage)?;
+        store.push_msg(msg).map_err(Error::HandleMessage)?;
+        if !store.wants_more() {
+            self.proceed()?;
+        }
+        Ok(())
+    }
+
+    fn message_queue(&mut self) -> &mut Vec<Msg<Self::MessageBody>> {
+        &mut self.msgs_queue
+    }
+
+    fn wants_to_proceed(&self) -> bool {
+        matches!(&self.msgs, Some(store) if !store.wants_more())
+    }
+
+    fn proceed(&mut self) -> Result<(), Error> {
+        if !self.wants_to_proceed() {
+            return Ok(());
+        }
+        let (store, state) = match (self.msgs.take(), self.state.take()) {
+            (Some(store), Some(state)) => (store, state),
+            _ => return Ok(()),
+        };
+        let sigs = store.finish().map_err(Error::HandleMessage)?.into_vec();
+        self.output = Some(state.complete(&sigs).map_err(Error::Sign)?);
+        Ok(())
+    }
+
+    fn round_timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    fn round_timeout_reached(&mut self) -> Self::Err {
+        panic!("no timeout was set")
+    }
+
+    fn is_finished(&self) -> bool {
+        self.output.is_some()
+    }
+
+    fn pick_output(&mut self) -> Option<Result<Self::Output, Error>> {
+        if self.msgs.is_some() {
+            return None;
+        }
+        match self.output.take() {
+            Some(signature) => Some(Ok(signature)),
+            None => Some(Err(Error::DoublePickOutput)),
+        }
+    }
+
+    fn current_round(&self) -> u16 {
+        if self.msgs.is_some() {
+            1
+        } else {
+            2
+        }
+    }
+
+    fn total_rounds(&self) -> Option<u16> {
+        Some(1)
+    }
+
+    fn party_ind(&self) -> u16 {
+        self.party_i
+    }
+
+    fn parties(&self) -> u16 {
+        self.party_n
+    }
+}
+
+impl fmt::Debug for Sign {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{{CGGMP21 Sign at round={} queue=[len={}]}}",
+            self.current_round(),
+            self.msgs_queue.len()
+        )
+    }
+}
+
+/// Error of local signing
+#[derive(Debug, Error)]
+pub enum SignError {
+    #[error("presignature is invalid")]
+    InvalidPresignature,
+    /// Resulting signature doesn't match public key, meaning that at least one of parties sent
+    /// invalid partial signature
+    #[error("resulting signature is not valid")]
+    InvalidSignature,
+}
+
+/// Error type of signing protocol
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Error {
+    #[error("signing: {0}")]
+    Sign(#[source] SignError),
+    /// Received message didn't pass pre-validation
+    #[error("received message didn't pass pre-validation: {0}")]
+    HandleMessage(#[source] StoreErr),
+    /// Received message after signing was completed
+    #[error("didn't expect to receive message: signing is completed")]
+    ReceivedOutOfOrderMessage,
+    /// [Sign::pick_output] called twice
+    #[error("pick_output called twice")]
+    DoublePickOutput,
+}
+
+impl IsCritical for Error {
+    fn is_critical(&self) -> bool {
+        !matches!(
+            self,
+            Error::HandleMessage(_) | Error::ReceivedOutOfOrderMessage
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use round_based::dev::Simulation;
+
+    use super::super::aux_info::test::simulate_aux_info;
+    use super::super::keygen::test::simulate_keygen;
+    use super::super::presign::test::simulate_presign;
+    use super::*;
+
+    #[test]
+    fn sign_with_presignatures() {
+        let keys = simulate_keygen(1, 3);
+        let aux = simulate_aux_info(3);
+        let message = BigInt::from_bytes(b"a message");
+
+        let presignatures = simulate_presign(&keys, &aux, &[3, 1]);
+        let (parties, sigs): (Vec<_>, Vec<_>) = presignatures
+            .into_iter()
+            .map(|p| SignManual::new(message.clone(), p).unwrap())
+            .unzip();
+        for (i, party) in parties.into_iter().enumerate() {
+            let mut others = sigs.clone();
+            others.remove(i);
+            let signature = party.complete(&others).unwrap();
+            let recovered = verification::recover_public_key(&signature, &message).unwrap();
+            assert_eq!(recovered, keys[0].public_key());
+        }
+
+        let presignatures = simulate_presign(&keys, &aux, &[1, 2, 3]);
+        let mut simulation = Simulation::new();
+        for presignature in presignatures {
+            simulation.add_party(Sign::new(message.clone(), presignature).unwrap());
+        }
+        for signature in simulation.run().unwrap() {
+            verification::verify(
+                &signature.r,
+                &signature.s,
+                &keys[0].public_key(),
+                &message,
+                Mode::Strict,
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn detects_invalid_partial_signature() {
+        let keys = simulate_keygen(1, 2);
+        let aux = simulate_aux_info(2);
+        let message = BigInt::from_bytes(b"a message");
+
+        let mut presignatures = simulate_presign(&keys, &aux, &[1, 2]);
+        let (party, _) = SignManual::new(message.clone(), presignatures.remove(0)).unwrap();
+        let (_, sig) = SignManual::new(message, presignatures.remove(0)).unwrap();
+        let sig = PartialSignature(sig.0 + Scalar::from(1));
+        assert!(matches!(
+            party.complete(&[sig]),
+            Err(SignError::InvalidSignature)
+        ));
+    }
+}
@@ -0,0 +1,55 @@
+//! Zero-knowledge proofs of CGGMP21 that are not covered by [utilities](crate::utilities)
+//!
+//! * [paillier_blum]: Πmod, proof that Paillier modulus is a product of two Blum primes
+//! * [ring_pedersen]: Πprm, proof that ring-Pedersen parameters are well-formed
+//! * [no_small_factor]: Πfac, proof that Paillier modulus has no small factors
+//!
+//! Πenc, Πaff-g and Πlog* proofs used at presigning are instantiated with
+//! [MtA range proofs](crate::utilities::mta::range_proofs) and
+//! [PDL with slack](crate::utilities::zk_pdl_with_slack) proofs, which prove the same statements
+//! against verifier's ring-Pedersen parameters. They take the same `context` as proofs of this
+//! module.
+//!
+//! Proofs are made non-interactive via Fiat-Shamir transform. Every proof takes a `context`:
+//! bytes that bind the proof to the protocol execution and to the prover (session id, prover
+//! index, etc.), so a proof can't be replayed in another context.
+
+use curv::arithmetic::traits::*;
+use curv::BigInt;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+pub mod no_small_factor;
+pub mod paillier_blum;
+pub mod ring_pedersen;
+
+/// Number of repetitions of the proofs with binary challenge (statistical security parameter)
+pub const M: usize = 80;
+
+/// Proof didn't pass verification
+#[derive(Debug, Error)]
+pub enum InvalidProof {
+    #[error("paillier-blum modulus proof is invalid")]
+    PaillierBlum,
+    #[error("ring-pedersen parameters proof is invalid")]
+    RingPedersen,
+    #[error("no small factor proof is invalid")]
+    NoSmallFactor,
+}
+
+/// Derives an element of `Z_n` from the hash of `seed`
+///
+/// Hash output is expanded to 128 bits more than `n` has, so the result is statistically close
+/// to uniform.
+pub(crate) fn hash_to_modulus(seed: &[u8], n: &BigInt) -> BigInt {
+    let blocks = (n.bit_length() + 128).div_ceil(256);
+    let bytes = (0..blocks as u32)
+        .flat_map(|counter| {
+            Sha256::new()
+                .chain(seed)
+                .chain(counter.to_be_bytes())
+                .finalize()
+        })
+        .collect::<Vec<u8>>();
+    BigInt::from_bytes(&bytes).modulus(n)
+}
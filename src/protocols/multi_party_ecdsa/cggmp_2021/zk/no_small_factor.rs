@@ -0,0 +1,220 @@
+//! Πfac: no small factor proof
+//!
+//! Proves knowledge of factorization `N0 = pq` with `|p|, |q| ≤ 2^(ℓ+ε) * √N0`, so neither of
+//! factors is smaller than `√N0 / 2^(ℓ+ε)`, see figure 28 of
+//! [CGGMP21](https://eprint.iacr.org/2021/060). The proof is computed against verifier's
+//! ring-Pedersen parameters `(N̂, s, t)`, so every verifier gets its own proof.
+//!
+//! `√N0` is bounded by `2^⌈|N0|/2⌉`, which only makes the ranges slightly wider.
+
+use curv::arithmetic::traits::*;
+use curv::BigInt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::InvalidProof;
+
+/// Bit length of the challenge, equals to the bit length of secp256k1 group order
+const L: usize = 256;
+/// Slack parameter, `ε = 2ℓ`
+const EPSILON: usize = 2 * L;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NoSmallFactorProof {
+    big_p: BigInt,
+    big_q: BigInt,
+    big_a: BigInt,
+    big_b: BigInt,
+    big_t: BigInt,
+    sigma: BigInt,
+    z1: BigInt,
+    z2: BigInt,
+    w1: BigInt,
+    w2: BigInt,
+    v: BigInt,
+}
+
+impl NoSmallFactorProof {
+    /// Proves that `n0 = p * q` has no small factors, `(n_hat, s, t)` are verifier's ring-Pedersen
+    /// parameters
+    pub fn prove(
+        n0: &BigInt,
+        p: &BigInt,
+        q: &BigInt,
+        n_hat: &BigInt,
+        s: &BigInt,
+        t: &BigInt,
+        context: &[u8],
+    ) -> Self {
+        let sqrt_n0 = sqrt_bound(n0);
+        let alpha = sample_pm(&(&sqrt_n0 << (L + EPSILON)));
+        let beta = sample_pm(&(&sqrt_n0 << (L + EPSILON)));
+        let mu = sample_pm(&(n_hat << L));
+        let nu = sample_pm(&(n_hat << L));
+        let sigma = sample_pm(&((n0 * n_hat) << L));
+        let r = sample_pm(&((n0 * n_hat) << (L + EPSILON)));
+        let x = sample_pm(&(n_hat << (L + EPSILON)));
+        let y = sample_pm(&(n_hat << (L + EPSILON)));
+
+        // s and t are invertible modulo n_hat, it's ensured by Πprm
+        let commit = |a: &BigInt, b: &BigInt| {
+            commitment(s, t, a, b, n_hat).expect("ring-pedersen parameters are invertible")
+        };
+        let big_p = commit(p, &mu);
+        let big_q = commit(q, &nu);
+        let big_a = commit(&alpha, &x);
+        let big_b = commit(&beta, &y);
+        let big_t = commitment(&big_q, t, &alpha, &r, n_hat)
+            .expect("Q is invertible as a product of invertible elements");
+        let sigma_hat = &sigma - &nu * p;
+
+        let e = challenge(
+            context,
+            &[
+                n0, n_hat, s, t, &big_p, &big_q, &big_a, &big_b, &big_t, &sigma,
+            ],
+        );
+
+        Self {
+            z1: alpha + &e * p,
+            z2: beta + &e * q,
+            w1: x + &e * mu,
+            w2: y + &e * nu,
+            v: r + &e * sigma_hat,
+            big_p,
+            big_q,
+            big_a,
+            big_b,
+            big_t,
+            sigma,
+        }
+    }
+
+    /// Verifies that `n0` has no small factors, `(n_hat, s, t)` are verifier's own ring-Pedersen
+    /// parameters
+    pub fn verify(
+        &self,
+        n0: &BigInt,
+        n_hat: &BigInt,
+        s: &BigInt,
+        t: &BigInt,
+        context: &[u8],
+    ) -> Result<(), InvalidProof> {
+        let bound = sqrt_bound(n0) << (L + EPSILON);
+        if self.z1.abs() > bound || self.z2.abs() > bound {
+            return Err(InvalidProof::NoSmallFactor);
+        }
+
+        let e = challenge(
+            context,
+            &[
+                n0,
+                n_hat,
+                s,
+                t,
+                &self.big_p,
+                &self.big_q,
+                &self.big_a,
+                &self.big_b,
+                &self.big_t,
+                &self.sigma,
+            ],
+        );
+        let big_r = commitment(s, t, n0, &self.sigma, n_hat);
+
+        let checks = || -> Option<bool> {
+            let lhs1 = commitment(s, t, &self.z1, &self.w1, n_hat)?;
+            let rhs1 = BigInt::mod_mul(&self.big_a, &pow(&self.big_p, &e, n_hat)?, n_hat);
+            let lhs2 = commitment(s, t, &self.z2, &self.w2, n_hat)?;
+            let rhs2 = BigInt::mod_mul(&self.big_b, &pow(&self.big_q, &e, n_hat)?, n_hat);
+            let lhs3 = commitment(&self.big_q, t, &self.z1, &self.v, n_hat)?;
+            let rhs3 = BigInt::mod_mul(&self.big_t, &pow(&big_r?, &e, n_hat)?, n_hat);
+            Some(lhs1 == rhs1 && lhs2 == rhs2 && lhs3 == rhs3)
+        };
+        match checks() {
+            Some(true) => Ok(()),
+            _ => Err(InvalidProof::NoSmallFactor),
+        }
+    }
+}
+
+/// Upper bound of `√n`
+fn sqrt_bound(n: &BigInt) -> BigInt {
+    BigInt::one() << n.bit_length().div_ceil(2)
+}
+
+/// Samples uniformly from `[-bound; bound]`
+fn sample_pm(bound: &BigInt) -> BigInt {
+    BigInt::sample_below(&(bound * 2 + 1)) - bound
+}
+
+/// Computes `x^e mod n`, `e` can be negative. Returns `None` if `x` is not invertible while `e`
+/// is negative.
+fn pow(x: &BigInt, e: &BigInt, n: &BigInt) -> Option<BigInt> {
+    if e < &BigInt::zero() {
+        let x_inv = BigInt::mod_inv(x, n)?;
+        Some(BigInt::mod_pow(&x_inv, &-e, n))
+    } else {
+        Some(BigInt::mod_pow(x, e, n))
+    }
+}
+
+/// Computes `s^a * t^b mod n`
+fn commitment(s: &BigInt, t: &BigInt, a: &BigInt, b: &BigInt, n: &BigInt) -> Option<BigInt> {
+    Some(BigInt::mod_mul(&pow(s, a, n)?, &pow(t, b, n)?, n))
+}
+
+/// Fiat-Shamir challenge `e ∈ [0; 2^ℓ)`
+fn challenge(context: &[u8], values: &[&BigInt]) -> BigInt {
+    let mut hash = Sha256::new()
+        .chain((context.len() as u64).to_be_bytes())
+        .chain(context);
+    for x in values {
+        let bytes = x.to_bytes();
+        hash.update((bytes.len() as u64).to_be_bytes());
+        hash.update(&bytes);
+    }
+    BigInt::from_bytes(&hash.finalize())
+}
+
+#[cfg(test)]
+mod test {
+    use paillier::{KeyGeneration, Paillier};
+
+    use super::*;
+
+    fn ring_pedersen() -> (BigInt, BigInt, BigInt) {
+        let keypair = Paillier::keypair_with_modulus_size(2048);
+        let n = &keypair.p * &keypair.q;
+        let phi = (&keypair.p - 1) * (&keypair.q - 1);
+        let r = BigInt::sample_below(&n);
+        let t = BigInt::mod_mul(&r, &r, &n);
+        let s = BigInt::mod_pow(&t, &BigInt::sample_below(&phi), &n);
+        (n, s, t)
+    }
+
+    #[test]
+    fn proves_no_small_factors() {
+        let (n_hat, s, t) = ring_pedersen();
+        let keypair = Paillier::keypair_with_modulus_size(2048);
+        let n0 = &keypair.p * &keypair.q;
+
+        let proof =
+            NoSmallFactorProof::prove(&n0, &keypair.p, &keypair.q, &n_hat, &s, &t, b"context");
+        proof.verify(&n0, &n_hat, &s, &t, b"context").unwrap();
+        assert!(proof
+            .verify(&n0, &n_hat, &s, &t, b"another context")
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_modulus_with_small_factor() {
+        let (n_hat, s, t) = ring_pedersen();
+        let p = BigInt::from(65537);
+        let q = BigInt::sample(2048 - 17).next_prime();
+        let n0 = &p * &q;
+
+        let proof = NoSmallFactorProof::prove(&n0, &p, &q, &n_hat, &s, &t, b"context");
+        assert!(proof.verify(&n0, &n_hat, &s, &t, b"context").is_err());
+    }
+}
@@ -0,0 +1,191 @@
+//! Πmod: Paillier-Blum modulus proof
+//!
+//! Proves that `N = pq` is a product of two primes `p ≡ q ≡ 3 (mod 4)` and `gcd(N, φ(N)) = 1`,
+//! see figure 16 of [CGGMP21](https://eprint.iacr.org/2021/060).
+
+use curv::arithmetic::traits::*;
+use curv::BigInt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::{hash_to_modulus, InvalidProof, M};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PaillierBlumProof {
+    w: BigInt,
+    rounds: Vec<ProofRound>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct ProofRound {
+    x: BigInt,
+    a: bool,
+    b: bool,
+    z: BigInt,
+}
+
+impl PaillierBlumProof {
+    /// Proves that `n = p * q` is a Paillier-Blum modulus
+    ///
+    /// `p` and `q` must be primes congruent to 3 modulo 4, otherwise produced proof is invalid.
+    pub fn prove(n: &BigInt, p: &BigInt, q: &BigInt, context: &[u8]) -> Self {
+        let one = BigInt::one();
+        let phi = (p - &one) * (q - &one);
+        let n_inv = BigInt::mod_inv(n, &phi).unwrap_or_else(BigInt::zero);
+
+        // w is a quadratic residue modulo exactly one of primes, i.e. its Jacobi symbol is -1
+        let w = loop {
+            let w = BigInt::sample_below(n);
+            if is_quadratic_residue(&w, p) != is_quadratic_residue(&w, q)
+                && !is_zero_mod(&w, p)
+                && !is_zero_mod(&w, q)
+            {
+                break w;
+            }
+        };
+        let minus_one = n - &one;
+
+        let rounds = (0..M)
+            .map(|k| {
+                let y = challenge(context, n, &w, k);
+                let z = BigInt::mod_pow(&y, &n_inv, n);
+
+                // Exactly one of (-1)^a * w^b * y is a quadratic residue modulo both primes
+                let (a, b, y_tag) = [(false, false), (true, false), (false, true), (true, true)]
+                    .iter()
+                    .map(|&(a, b)| {
+                        let mut y_tag = y.clone();
+                        if a {
+                            y_tag = BigInt::mod_mul(&y_tag, &minus_one, n);
+                        }
+                        if b {
+                            y_tag = BigInt::mod_mul(&y_tag, &w, n);
+                        }
+                        (a, b, y_tag)
+                    })
+                    .find(|(_, _, y_tag)| {
+                        is_quadratic_residue(y_tag, p) && is_quadratic_residue(y_tag, q)
+                    })
+                    .unwrap_or_else(|| (false, false, y.clone()));
+                let x = fourth_root(&y_tag, p, q);
+
+                ProofRound { x, a, b, z }
+            })
+            .collect();
+
+        Self { w, rounds }
+    }
+
+    /// Verifies that `n` is a Paillier-Blum modulus
+    pub fn verify(&self, n: &BigInt, context: &[u8]) -> Result<(), InvalidProof> {
+        let one = BigInt::one();
+        if n <= &one
+            || n.is_even()
+            || n.is_probable_prime(30)
+            || self.rounds.len() != M
+            || self.w <= BigInt::zero()
+            || &self.w >= n
+        {
+            return Err(InvalidProof::PaillierBlum);
+        }
+        let minus_one = n - &one;
+        let four = BigInt::from(4);
+
+        for (k, round) in self.rounds.iter().enumerate() {
+            let y = challenge(context, n, &self.w, k);
+            if BigInt::mod_pow(&round.z, n, n) != y {
+                return Err(InvalidProof::PaillierBlum);
+            }
+            let mut y_tag = y;
+            if round.a {
+                y_tag = BigInt::mod_mul(&y_tag, &minus_one, n);
+            }
+            if round.b {
+                y_tag = BigInt::mod_mul(&y_tag, &self.w, n);
+            }
+            if BigInt::mod_pow(&round.x, &four, n) != y_tag {
+                return Err(InvalidProof::PaillierBlum);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn challenge(context: &[u8], n: &BigInt, w: &BigInt, k: usize) -> BigInt {
+    let n_bytes = n.to_bytes();
+    let w_bytes = w.to_bytes();
+    let seed = Sha256::new()
+        .chain((context.len() as u64).to_be_bytes())
+        .chain(context)
+        .chain((n_bytes.len() as u64).to_be_bytes())
+        .chain(&n_bytes)
+        .chain((w_bytes.len() as u64).to_be_bytes())
+        .chain(&w_bytes)
+        .chain((k as u64).to_be_bytes())
+        .finalize();
+    hash_to_modulus(&seed, n)
+}
+
+fn is_zero_mod(x: &BigInt, p: &BigInt) -> bool {
+    x.modulus(p) == BigInt::zero()
+}
+
+/// Euler's criterion
+fn is_quadratic_residue(x: &BigInt, p: &BigInt) -> bool {
+    let exp = (p - BigInt::one()) >> 1;
+    BigInt::mod_pow(x, &exp, p) == BigInt::one()
+}
+
+/// Computes the fourth root of `x` modulo `p * q` which is a quadratic residue itself
+///
+/// For prime `p ≡ 3 (mod 4)`, `x^((p+1)/4)` is a square root of quadratic residue `x` that is a
+/// quadratic residue as well, so taking it twice gives the fourth root.
+fn fourth_root(x: &BigInt, p: &BigInt, q: &BigInt) -> BigInt {
+    let root = |p: &BigInt| {
+        let one = BigInt::one();
+        let e = (p + &one) >> 2;
+        let e = BigInt::mod_mul(&e, &e, &(p - &one));
+        BigInt::mod_pow(&x.modulus(p), &e, p)
+    };
+    let (x_p, x_q) = (root(p), root(q));
+    // CRT: x = x_p + p * ((x_q - x_p) * p^-1 mod q)
+    let p_inv = BigInt::mod_inv(p, q).unwrap_or_else(BigInt::zero);
+    let h = BigInt::mod_mul(&BigInt::mod_sub(&x_q, &x_p.modulus(q), q), &p_inv, q);
+    x_p + p * h
+}
+
+#[cfg(test)]
+mod test {
+    use paillier::{KeyGeneration, Paillier};
+
+    use super::*;
+
+    fn blum_primes() -> (BigInt, BigInt) {
+        let blum_prime = || loop {
+            let p = Paillier::keypair_with_modulus_size(1024).p;
+            if p.modulus(&BigInt::from(4)) == BigInt::from(3) {
+                break p;
+            }
+        };
+        (blum_prime(), blum_prime())
+    }
+
+    #[test]
+    fn proves_blum_modulus() {
+        let (p, q) = blum_primes();
+        let n = &p * &q;
+        let proof = PaillierBlumProof::prove(&n, &p, &q, b"context");
+        proof.verify(&n, b"context").unwrap();
+        assert!(proof.verify(&n, b"another context").is_err());
+    }
+
+    #[test]
+    fn rejects_modulus_with_three_factors() {
+        let (p, q) = blum_primes();
+        let r = BigInt::from(7);
+        let n = &p * &q * &r;
+        // Prover pretends that `q * r` is a prime
+        let proof = PaillierBlumProof::prove(&n, &p, &(&q * &r), b"context");
+        assert!(proof.verify(&n, b"context").is_err());
+    }
+}
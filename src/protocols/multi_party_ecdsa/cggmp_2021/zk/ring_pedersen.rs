@@ -0,0 +1,136 @@
+//! Πprm: ring-Pedersen parameters proof
+//!
+//! Proves knowledge of `λ` such that `s = t^λ mod N`, i.e. that `s` belongs to the subgroup
+//! generated by `t`, see figure 17 of [CGGMP21](https://eprint.iacr.org/2021/060).
+
+use curv::arithmetic::traits::*;
+use curv::BigInt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::{InvalidProof, M};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RingPedersenProof {
+    commitments: Vec<BigInt>,
+    responses: Vec<BigInt>,
+}
+
+impl RingPedersenProof {
+    /// Proves that `s = t^lambda mod n`, `phi` is the Euler's totient of `n`
+    pub fn prove(
+        n: &BigInt,
+        s: &BigInt,
+        t: &BigInt,
+        lambda: &BigInt,
+        phi: &BigInt,
+        context: &[u8],
+    ) -> Self {
+        let nonces = (0..M)
+            .map(|_| BigInt::sample_below(phi))
+            .collect::<Vec<_>>();
+        let commitments = nonces
+            .iter()
+            .map(|a| BigInt::mod_pow(t, a, n))
+            .collect::<Vec<_>>();
+        let challenge = challenge(context, n, s, t, &commitments);
+        let responses = nonces
+            .iter()
+            .enumerate()
+            .map(|(k, a)| {
+                if challenge_bit(&challenge, k) {
+                    BigInt::mod_add(a, lambda, phi)
+                } else {
+                    a.clone()
+                }
+            })
+            .collect();
+        Self {
+            commitments,
+            responses,
+        }
+    }
+
+    /// Verifies that `s` belongs to the subgroup of `Z*_n` generated by `t`
+    pub fn verify(
+        &self,
+        n: &BigInt,
+        s: &BigInt,
+        t: &BigInt,
+        context: &[u8],
+    ) -> Result<(), InvalidProof> {
+        let one = BigInt::one();
+        let is_invertible = |x: &BigInt| x > &BigInt::zero() && x < n && x.gcd(n) == one;
+        if self.commitments.len() != M
+            || self.responses.len() != M
+            || !is_invertible(s)
+            || !is_invertible(t)
+            || t == &one
+        {
+            return Err(InvalidProof::RingPedersen);
+        }
+
+        let challenge = challenge(context, n, s, t, &self.commitments);
+        for (k, (a, z)) in self.commitments.iter().zip(&self.responses).enumerate() {
+            let expected = if challenge_bit(&challenge, k) {
+                BigInt::mod_mul(a, s, n)
+            } else {
+                a.modulus(n)
+            };
+            if BigInt::mod_pow(t, z, n) != expected {
+                return Err(InvalidProof::RingPedersen);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn challenge(
+    context: &[u8],
+    n: &BigInt,
+    s: &BigInt,
+    t: &BigInt,
+    commitments: &[BigInt],
+) -> [u8; 32] {
+    let mut hash = Sha256::new()
+        .chain((context.len() as u64).to_be_bytes())
+        .chain(context);
+    for x in [n, s, t].iter().copied().chain(commitments) {
+        let bytes = x.to_bytes();
+        hash.update((bytes.len() as u64).to_be_bytes());
+        hash.update(&bytes);
+    }
+    hash.finalize().into()
+}
+
+fn challenge_bit(challenge: &[u8; 32], k: usize) -> bool {
+    challenge[k / 8] >> (k % 8) & 1 == 1
+}
+
+#[cfg(test)]
+mod test {
+    use paillier::{KeyGeneration, Paillier};
+
+    use super::*;
+
+    #[test]
+    fn proves_ring_pedersen_parameters() {
+        let keypair = Paillier::keypair_with_modulus_size(2048);
+        let n = &keypair.p * &keypair.q;
+        let phi = (&keypair.p - 1) * (&keypair.q - 1);
+
+        let r = BigInt::sample_below(&n);
+        let t = BigInt::mod_mul(&r, &r, &n);
+        let lambda = BigInt::sample_below(&phi);
+        let s = BigInt::mod_pow(&t, &lambda, &n);
+
+        let proof = RingPedersenProof::prove(&n, &s, &t, &lambda, &phi, b"context");
+        proof.verify(&n, &s, &t, b"context").unwrap();
+        assert!(proof.verify(&n, &s, &t, b"another context").is_err());
+
+        // s is not in the subgroup generated by t with overwhelming probability
+        let s = BigInt::sample_below(&n);
+        let proof = RingPedersenProof::prove(&n, &s, &t, &lambda, &phi, b"context");
+        assert!(proof.verify(&n, &s, &t, b"context").is_err());
+    }
+}
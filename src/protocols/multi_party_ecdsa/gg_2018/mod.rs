@@ -15,6 +15,7 @@
 */
 
 pub mod party_i;
+pub mod state_machine;
 
 #[cfg(test)]
 mod test;
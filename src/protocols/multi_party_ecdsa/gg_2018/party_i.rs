@@ -37,6 +37,7 @@ use zk_paillier::zkproofs::NiCorrectKeyProof;
 
 use serde::{Deserialize, Serialize};
 
+use crate::utilities::verification::{self, Mode};
 use crate::Error::{self, InvalidCom, InvalidKey, InvalidSS, InvalidSig};
 
 const SECURITY: usize = 256;
@@ -137,12 +138,7 @@ pub struct Phase5DDecom2 {
     pub blind_factor: BigInt,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct SignatureRecid {
-    pub r: Scalar<Secp256k1>,
-    pub s: Scalar<Secp256k1>,
-    pub recid: u8,
-}
+pub use crate::utilities::verification::SignatureRecid;
 
 impl Keys {
     pub fn create(index: u16) -> Self {
@@ -672,35 +668,15 @@ impl LocalSignature {
         }
     }
     pub fn output_signature(&self, s_vec: &[Scalar<Secp256k1>]) -> Result<SignatureRecid, Error> {
-        let mut s = &self.s_i + s_vec.iter().sum::<Scalar<Secp256k1>>();
-        let s_bn = s.to_bigint();
-
+        let s = s_vec.iter().fold(self.s_i.clone(), |acc, x| acc + x);
         let r = Scalar::<Secp256k1>::from(
             &self
                 .R
                 .x_coord()
-                .ok_or(Error::InvalidSig)?
+                .ok_or(InvalidSig)?
                 .mod_floor(Scalar::<Secp256k1>::group_order()),
         );
-        let ry: BigInt = self
-            .R
-            .y_coord()
-            .ok_or(Error::InvalidSig)?
-            .mod_floor(Scalar::<Secp256k1>::group_order());
-
-        /*
-         Calculate recovery id - it is not possible to compute the public key out of the signature
-         itself. Recovery id is used to enable extracting the public key uniquely.
-         1. id = R.y & 1
-         2. if (s > curve.q / 2) id = id ^ 1
-        */
-        let is_ry_odd = ry.test_bit(0);
-        let mut recid = if is_ry_odd { 1 } else { 0 };
-        let s_tag_bn = Scalar::<Secp256k1>::group_order() - &s_bn;
-        if s_bn > s_tag_bn {
-            s = Scalar::<Secp256k1>::from(&s_tag_bn);
-            recid ^= 1;
-        }
+        let (s, recid) = verification::normalize_s(&self.R, s)?;
         let sig = SignatureRecid { r, s, recid };
         let ver = verify(&sig, &self.y, &self.m).is_ok();
         if ver {
@@ -711,27 +687,10 @@ impl LocalSignature {
     }
 }
 
+/// Verifies signature of `message` under public key `y`
+///
+/// Both low-S and high-S signatures are accepted, call [verification::verify] with [Mode::Strict]
+/// to reject the latter.
 pub fn verify(sig: &SignatureRecid, y: &Point<Secp256k1>, message: &BigInt) -> Result<(), Error> {
-    let b = sig.s.invert().ok_or(Error::InvalidSig)?;
-    let a = Scalar::<Secp256k1>::from(message);
-    let u1 = a * &b;
-    let u2 = &sig.r * &b;
-
-    let g = Point::generator();
-    let gu1 = g * u1;
-    let yu2 = y * &u2;
-    // can be faster using shamir trick
-
-    if sig.r
-        == Scalar::<Secp256k1>::from(
-            &(gu1 + yu2)
-                .x_coord()
-                .ok_or(Error::InvalidSig)?
-                .mod_floor(Scalar::<Secp256k1>::group_order()),
-        )
-    {
-        Ok(())
-    } else {
-        Err(InvalidSig)
-    }
+    verification::verify(&sig.r, &sig.s, y, message, Mode::Lax)
 }
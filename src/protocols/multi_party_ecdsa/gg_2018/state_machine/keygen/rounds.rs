@@ -0,0 +1,347 @@
+use curv::cryptographic_primitives::proofs::sigma_dlog::DLogProof;
+use curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
+use curv::elliptic::curves::{Point, Scalar, Secp256k1};
+use paillier::EncryptionKey;
+use round_based::containers::push::Push;
+use round_based::containers::{self, BroadcastMsgs, P2PMsgs, Store};
+use round_based::Msg;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use thiserror::Error;
+
+use crate::protocols::multi_party_ecdsa::gg_2018::party_i::{
+    KeyGenBroadcastMessage1, KeyGenDecommitMessage1, Keys, Parameters, SharedKeys,
+};
+use crate::Error as PhaseError;
+
+pub struct Round0 {
+    pub party_i: u16,
+    pub t: u16,
+    pub n: u16,
+}
+
+impl Round0 {
+    pub fn proceed<O>(self, mut output: O) -> Result<Round1>
+    where
+        O: Push<Msg<KeyGenBroadcastMessage1>>,
+    {
+        let keys = Keys::create(self.party_i);
+        let (bc1, decom1) = keys.phase1_broadcast_phase3_proof_of_correct_key();
+        output.push(Msg {
+            sender: self.party_i,
+            receiver: None,
+            body: bc1.clone(),
+        });
+        Ok(Round1 {
+            keys,
+            bc1,
+            decom1,
+            party_i: self.party_i,
+            t: self.t,
+            n: self.n,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+}
+
+pub struct Round1 {
+    keys: Keys,
+    bc1: KeyGenBroadcastMessage1,
+    decom1: KeyGenDecommitMessage1,
+    party_i: u16,
+    t: u16,
+    n: u16,
+}
+
+impl Round1 {
+    pub fn proceed<O>(
+        self,
+        input: BroadcastMsgs<KeyGenBroadcastMessage1>,
+        mut output: O,
+    ) -> Result<Round2>
+    where
+        O: Push<Msg<KeyGenDecommitMessage1>>,
+    {
+        output.push(Msg {
+            sender: self.party_i,
+            receiver: None,
+            body: self.decom1.clone(),
+        });
+        Ok(Round2 {
+            keys: self.keys,
+            bc1_vec: input.into_vec_including_me(self.bc1),
+            decom1: self.decom1,
+            party_i: self.party_i,
+            t: self.t,
+            n: self.n,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        false
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<KeyGenBroadcastMessage1>> {
+        containers::BroadcastMsgsStore::new(i, n)
+    }
+}
+
+pub struct Round2 {
+    keys: Keys,
+    bc1_vec: Vec<KeyGenBroadcastMessage1>,
+    decom1: KeyGenDecommitMessage1,
+    party_i: u16,
+    t: u16,
+    n: u16,
+}
+
+impl Round2 {
+    pub fn proceed<O>(
+        self,
+        input: BroadcastMsgs<KeyGenDecommitMessage1>,
+        mut output: O,
+    ) -> Result<Round3>
+    where
+        O: Push<Msg<(VerifiableSS<Secp256k1>, Scalar<Secp256k1>)>>,
+    {
+        let params = Parameters {
+            threshold: self.t,
+            share_count: self.n,
+        };
+        let decom_vec = input.into_vec_including_me(self.decom1);
+        let (vss_scheme, secret_shares, _) = self
+            .keys
+            .phase1_verify_com_phase3_verify_correct_key_phase2_distribute(
+                &params,
+                &decom_vec,
+                &self.bc1_vec,
+            )
+            .map_err(ProceedError::Round2VerifyCommitments)?;
+
+        for (j, share) in (1..).zip(&secret_shares) {
+            if j != self.party_i {
+                output.push(Msg {
+                    sender: self.party_i,
+                    receiver: Some(j),
+                    body: (vss_scheme.clone(), share.clone()),
+                });
+            }
+        }
+
+        Ok(Round3 {
+            keys: self.keys,
+            bc1_vec: self.bc1_vec,
+            y_vec: decom_vec.into_iter().map(|d| d.y_i).collect(),
+            own_share: secret_shares[usize::from(self.party_i - 1)].clone(),
+            own_vss: vss_scheme,
+            party_i: self.party_i,
+            t: self.t,
+            n: self.n,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<KeyGenDecommitMessage1>> {
+        containers::BroadcastMsgsStore::new(i, n)
+    }
+}
+
+pub struct Round3 {
+    keys: Keys,
+    bc1_vec: Vec<KeyGenBroadcastMessage1>,
+    y_vec: Vec<Point<Secp256k1>>,
+    own_vss: VerifiableSS<Secp256k1>,
+    own_share: Scalar<Secp256k1>,
+    party_i: u16,
+    t: u16,
+    n: u16,
+}
+
+impl Round3 {
+    pub fn proceed<O>(
+        self,
+        input: P2PMsgs<(VerifiableSS<Secp256k1>, Scalar<Secp256k1>)>,
+        mut output: O,
+    ) -> Result<Round4>
+    where
+        O: Push<Msg<DLogProof<Secp256k1, Sha256>>>,
+    {
+        let params = Parameters {
+            threshold: self.t,
+            share_count: self.n,
+        };
+        let (vss_scheme_vec, secret_shares): (Vec<_>, Vec<_>) = input
+            .into_vec_including_me((self.own_vss, self.own_share))
+            .into_iter()
+            .unzip();
+
+        let (shared_keys, dlog_proof) = self
+            .keys
+            .phase2_verify_vss_construct_keypair_phase3_pok_dlog(
+                &params,
+                &self.y_vec,
+                &secret_shares,
+                &vss_scheme_vec,
+                self.party_i,
+            )
+            .map_err(ProceedError::Round3VerifyVssConstruct)?;
+
+        output.push(Msg {
+            sender: self.party_i,
+            receiver: None,
+            body: dlog_proof.clone(),
+        });
+
+        Ok(Round4 {
+            keys: self.keys,
+            bc1_vec: self.bc1_vec,
+            shared_keys,
+            vss_scheme_vec,
+            own_dlog_proof: dlog_proof,
+            party_i: self.party_i,
+            t: self.t,
+            n: self.n,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+    pub fn expects_messages(
+        i: u16,
+        n: u16,
+    ) -> Store<P2PMsgs<(VerifiableSS<Secp256k1>, Scalar<Secp256k1>)>> {
+        containers::P2PMsgsStore::new(i, n)
+    }
+}
+
+pub struct Round4 {
+    keys: Keys,
+    bc1_vec: Vec<KeyGenBroadcastMessage1>,
+    shared_keys: SharedKeys,
+    vss_scheme_vec: Vec<VerifiableSS<Secp256k1>>,
+    own_dlog_proof: DLogProof<Secp256k1, Sha256>,
+    party_i: u16,
+    t: u16,
+    n: u16,
+}
+
+impl Round4 {
+    pub fn proceed(self, input: BroadcastMsgs<DLogProof<Secp256k1, Sha256>>) -> Result<LocalKey> {
+        let dlog_proofs = input.into_vec_including_me(self.own_dlog_proof);
+        let xi_com_vec = Keys::get_commitments_to_xi(&self.vss_scheme_vec);
+        let culprits = (1..)
+            .zip(dlog_proofs.iter().zip(&xi_com_vec))
+            .filter(|(_, (proof, xi_com))| {
+                proof.pk != **xi_com || DLogProof::verify(proof).is_err()
+            })
+            .map(|(j, _)| j)
+            .collect::<Vec<u16>>();
+        if !culprits.is_empty() {
+            return Err(ProceedError::Round4InvalidDLogProof { culprits });
+        }
+
+        Ok(LocalKey {
+            y_sum: self.shared_keys.y.clone(),
+            keys: self.keys,
+            shared_keys: self.shared_keys,
+            vss_scheme_vec: self.vss_scheme_vec,
+            paillier_key_vec: self.bc1_vec.into_iter().map(|bc1| bc1.e).collect(),
+            i: self.party_i,
+            t: self.t,
+            n: self.n,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<DLogProof<Secp256k1, Sha256>>> {
+        containers::BroadcastMsgsStore::new(i, n)
+    }
+}
+
+/// Key file written by `gg18_keygen_client` example
+///
+/// Party's keys, its shared keys, its index, VSS schemes and Paillier encryption keys of every
+/// party, and joint public key.
+pub type ExampleKeyTuple = (
+    Keys,
+    SharedKeys,
+    u16,
+    Vec<VerifiableSS<Secp256k1>>,
+    Vec<EncryptionKey>,
+    Point<Secp256k1>,
+);
+
+/// Local secret obtained by party after [keygen](super::Keygen) protocol is completed
+///
+/// Holds the same data as the key file written by `gg18_keygen_client` example, with `t` and `n`
+/// taken from parameters of the VSS schemes. Use [LocalKey::from_example_tuple] to convert the
+/// key file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LocalKey {
+    /// Party's Paillier key pair and its share `u_i` of the secret key
+    pub keys: Keys,
+    /// Party's share of the secret key `x_i` and joint public key
+    pub shared_keys: SharedKeys,
+    /// Feldman VSS commitments of every party
+    pub vss_scheme_vec: Vec<VerifiableSS<Secp256k1>>,
+    /// Paillier encryption keys of every party
+    pub paillier_key_vec: Vec<EncryptionKey>,
+    /// Joint public key
+    pub y_sum: Point<Secp256k1>,
+
+    pub i: u16,
+    pub t: u16,
+    pub n: u16,
+}
+
+impl LocalKey {
+    /// Converts key file written by `gg18_keygen_client` example
+    ///
+    /// `t` and `n` are taken from parameters of the party's own VSS scheme. Returns `None` if party
+    /// index is not in range `[1; n]` or the key file holds VSS schemes of other than `n` parties.
+    pub fn from_example_tuple(key: ExampleKeyTuple) -> Option<Self> {
+        let (keys, shared_keys, i, vss_scheme_vec, paillier_key_vec, y_sum) = key;
+        let parameters = &vss_scheme_vec
+            .get(usize::from(i).checked_sub(1)?)?
+            .parameters;
+        let (t, n) = (parameters.threshold, parameters.share_count);
+        if i > n || vss_scheme_vec.len() != usize::from(n) {
+            return None;
+        }
+        Some(Self {
+            keys,
+            shared_keys,
+            vss_scheme_vec,
+            paillier_key_vec,
+            y_sum,
+            i,
+            t,
+            n,
+        })
+    }
+
+    /// Public key of secret shared between parties
+    pub fn public_key(&self) -> Point<Secp256k1> {
+        self.y_sum.clone()
+    }
+}
+
+// Errors
+
+type Result<T> = std::result::Result<T, ProceedError>;
+
+/// Proceeding protocol error
+///
+/// Subset of [keygen errors](enum@super::Error) that can occur at protocol proceeding (i.e. after
+/// every message was received and pre-validated).
+#[derive(Debug, Error)]
+pub enum ProceedError {
+    #[error("round 2: invalid decommitment or paillier key proof: {0:?}")]
+    Round2VerifyCommitments(PhaseError),
+    #[error("round 3: invalid secret share or vss commitment: {0:?}")]
+    Round3VerifyVssConstruct(PhaseError),
+    #[error("round 4: invalid proof of knowledge of x_i: parties {culprits:?}")]
+    Round4InvalidDLogProof { culprits: Vec<u16> },
+}
@@ -0,0 +1,458 @@
+//! Migration of GG18 keys to GG20
+//!
+//! GG20 signing needs every party to hold `N_tilde`, `h1`, `h2` of every other party for range
+//! proofs in MtA, which GG18 keygen doesn't generate. In this one-round protocol every party of
+//! GG18 keygen generates `N_tilde`, `h1`, `h2` and broadcasts them along with composite DLog
+//! proofs. Parties end up with GG20 [LocalKey](Gg20LocalKey) for the same public key and the same
+//! secret shares, so it can be used with GG20 [signing](crate::protocols::multi_party_ecdsa::gg_2020::state_machine::sign).
+//!
+//! All `n` parties of GG18 keygen must take part in migration. Key files written by
+//! `gg18_keygen_client` example can be converted with
+//! [LocalKey::from_example_tuple](super::keygen::LocalKey::from_example_tuple). Migration fails if
+//! Paillier key of any party is not 2048 bits long, as GG20 range proofs rely on it.
+
+use std::fmt;
+use std::mem::replace;
+use std::time::Duration;
+
+use round_based::containers::{
+    push::{Push, PushExt},
+    *,
+};
+use round_based::{IsCritical, Msg, StateMachine};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey as Gg20LocalKey;
+
+use super::keygen::LocalKey;
+
+mod rounds;
+
+use private::InternalError;
+pub use rounds::{DLogStatementMsg, ProceedError};
+use rounds::{Round0, Round1};
+
+/// Migration protocol state machine
+///
+/// Takes GG18 [LocalKey] and produces GG20 [LocalKey](Gg20LocalKey) of the same key share.
+pub struct Migrate {
+    round: R,
+
+    msgs1: Option<Store<BroadcastMsgs<DLogStatementMsg>>>,
+
+    msgs_queue: Vec<Msg<ProtocolMessage>>,
+
+    party_i: u16,
+    party_n: u16,
+}
+
+impl Migrate {
+    /// Constructs a party of migration protocol
+    ///
+    /// Party index and number of parties are taken from `local_key`. Returns
+    /// [Error::InvalidLocalKey] if `local_key` is inconsistent, e.g. it holds VSS schemes or
+    /// Paillier keys of less than `n` parties.
+    pub fn new(local_key: LocalKey) -> Result<Self> {
+        let (i, n) = (local_key.i, local_key.n);
+        if i == 0
+            || i > n
+            || local_key.vss_scheme_vec.len() != usize::from(n)
+            || local_key.paillier_key_vec.len() != usize::from(n)
+        {
+            return Err(Error::InvalidLocalKey);
+        }
+        let mut state = Self {
+            round: R::Round0(Round0 { local_key }),
+
+            msgs1: Some(Round1::expects_messages(i, n)),
+
+            msgs_queue: vec![],
+
+            party_i: i,
+            party_n: n,
+        };
+
+        state.proceed_round(false)?;
+        Ok(state)
+    }
+
+    fn gmap_queue<'a, T, F>(&'a mut self, mut f: F) -> impl Push<Msg<T>> + 'a
+    where
+        F: FnMut(T) -> M + 'a,
+    {
+        (&mut self.msgs_queue).gmap(move |m: Msg<T>| m.map_body(|m| ProtocolMessage(f(m))))
+    }
+
+    /// Proceeds round state if it received enough messages and if it's cheap to compute or
+    /// `may_block == true`
+    fn proceed_round(&mut self, may_block: bool) -> Result<()> {
+        let store1_wants_more = self.msgs1.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+
+        let next_state: R;
+        let try_again: bool = match replace(&mut self.round, R::Gone) {
+            R::Round0(round) if !round.is_expensive() || may_block => {
+                next_state = round
+                    .proceed(self.gmap_queue(M::Round1))
+                    .map(R::Round1)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round0(_) => {
+                next_state = s;
+                false
+            }
+            R::Round1(round) if !store1_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs1.take().ok_or(InternalError::StoreGone)?;
+                let msgs = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?;
+                next_state = round
+                    .proceed(msgs)
+                    .map(R::Final)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round1(_) => {
+                next_state = s;
+                false
+            }
+            s @ R::Final(_) | s @ R::Gone => {
+                next_state = s;
+                false
+            }
+        };
+
+        self.round = next_state;
+        if try_again {
+            self.proceed_round(may_block)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl StateMachine for Migrate {
+    type MessageBody = ProtocolMessage;
+    type Err = Error;
+    type Output = Gg20LocalKey;
+
+    fn handle_incoming(&mut self, msg: Msg<Self::MessageBody>) -> Result<()> {
+        let current_round = self.current_round();
+
+        match msg.body {
+            ProtocolMessage(M::Round1(m)) => {
+                let store = self
+                    .msgs1
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 1,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+        }
+        self.proceed_round(false)
+    }
+
+    fn message_queue(&mut self) -> &mut Vec<Msg<Self::MessageBody>> {
+        &mut self.msgs_queue
+    }
+
+    fn wants_to_proceed(&self) -> bool {
+        let store1_wants_more = self.msgs1.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+
+        match &self.round {
+            R::Round0(_) => true,
+            R::Round1(_) => !store1_wants_more,
+            R::Final(_) | R::Gone => false,
+        }
+    }
+
+    fn proceed(&mut self) -> Result<()> {
+        self.proceed_round(true)
+    }
+
+    fn round_timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    fn round_timeout_reached(&mut self) -> Self::Err {
+        panic!("no timeout was set")
+    }
+
+    fn is_finished(&self) -> bool {
+        matches!(self.round, R::Final(_))
+    }
+
+    fn pick_output(&mut self) -> Option<Result<Self::Output>> {
+        match self.round {
+            R::Final(_) => (),
+            R::Gone => return Some(Err(Error::DoublePickOutput)),
+            _ => return None,
+        }
+
+        match replace(&mut self.round, R::Gone) {
+            R::Final(result) => Some(Ok(result)),
+            _ => unreachable!("guaranteed by match expression above"),
+        }
+    }
+
+    fn current_round(&self) -> u16 {
+        match &self.round {
+            R::Round0(_) => 0,
+            R::Round1(_) => 1,
+            R::Final(_) | R::Gone => 2,
+        }
+    }
+
+    fn total_rounds(&self) -> Option<u16> {
+        Some(1)
+    }
+
+    fn party_ind(&self) -> u16 {
+        self.party_i
+    }
+
+    fn parties(&self) -> u16 {
+        self.party_n
+    }
+}
+
+impl fmt::Debug for Migrate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{{GG18 to GG20 Migrate at round={} queue=[len={}]}}",
+            self.current_round(),
+            self.msgs_queue.len()
+        )
+    }
+}
+
+// Rounds
+
+#[allow(clippy::large_enum_variant)]
+enum R {
+    Round0(Round0),
+    Round1(Round1),
+    Final(Gg20LocalKey),
+    Gone,
+}
+
+// Messages
+
+/// Protocol message which parties send on wire
+///
+/// Hides actual messages structure so it could be changed without breaking semver policy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtocolMessage(M);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+enum M {
+    Round1(DLogStatementMsg),
+}
+
+// Error
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Error type of migration protocol
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// Round proceeding resulted in error
+    #[error("proceed round: {0}")]
+    ProceedRound(#[source] ProceedError),
+
+    /// GG18 local key is inconsistent
+    #[error("local key is inconsistent")]
+    InvalidLocalKey,
+
+    /// Received message didn't pass pre-validation
+    #[error("received message didn't pass pre-validation: {0}")]
+    HandleMessage(#[source] StoreErr),
+    /// Received message which we didn't expect to receive now (e.g. message from previous round)
+    #[error(
+        "didn't expect to receive message from round {msg_round} (being at round {current_round})"
+    )]
+    ReceivedOutOfOrderMessage { current_round: u16, msg_round: u16 },
+    /// [Migrate::pick_output] called twice
+    #[error("pick_output called twice")]
+    DoublePickOutput,
+
+    /// Some internal assertions were failed, which is a bug
+    #[doc(hidden)]
+    #[error("internal error: {0:?}")]
+    InternalError(InternalError),
+}
+
+impl IsCritical for Error {
+    fn is_critical(&self) -> bool {
+        !matches!(
+            self,
+            Error::HandleMessage(_) | Error::ReceivedOutOfOrderMessage { .. }
+        )
+    }
+}
+
+impl From<InternalError> for Error {
+    fn from(err: InternalError) -> Self {
+        Self::InternalError(err)
+    }
+}
+
+mod private {
+    #[derive(Debug)]
+    #[non_exhaustive]
+    pub enum InternalError {
+        /// [Messages store](super::MessageStore) reported that it received all messages it wanted to receive,
+        /// but refused to return message container
+        RetrieveRoundMessages(super::StoreErr),
+        #[doc(hidden)]
+        StoreGone,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use curv::arithmetic::Converter;
+    use curv::cryptographic_primitives::hashing::{Digest, DigestExt};
+    use curv::elliptic::curves::{Point, Scalar, Secp256k1};
+    use curv::BigInt;
+    use paillier::EncryptionKey;
+    use round_based::dev::Simulation;
+    use sha2::Sha256;
+
+    use super::super::keygen::test::simulate_keygen;
+    use super::*;
+    use crate::protocols::multi_party_ecdsa::gg_2020::party_i::verify;
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::sign::{
+        OfflineStage, SignManual,
+    };
+
+    fn simulate_migrate(keys: Vec<LocalKey>) -> Vec<Gg20LocalKey> {
+        let mut simulation = Simulation::new();
+        for key in keys {
+            simulation.add_party(Migrate::new(key).unwrap());
+        }
+        simulation.run().unwrap()
+    }
+
+    #[test]
+    fn migrated_keys_sign_with_gg20() {
+        let gg18_keys = simulate_keygen(1, 3);
+        let public_key = gg18_keys[0].public_key();
+        let keys = simulate_migrate(gg18_keys.clone());
+        for (key, gg18_key) in keys.iter().zip(&gg18_keys) {
+            assert_eq!(key.public_key(), public_key);
+            assert_eq!(key.keys_linear.x_i, gg18_key.shared_keys.x_i);
+            assert_eq!(key.h1_h2_n_tilde_vec.len(), 3);
+            assert_eq!(
+                Point::generator() * &key.keys_linear.x_i,
+                key.pk_vec[usize::from(key.i - 1)]
+            );
+        }
+
+        let s_l = [1, 3];
+        let mut simulation = Simulation::new();
+        for (i, &j) in (1..).zip(&s_l) {
+            simulation.add_party(
+                OfflineStage::new(i, s_l.to_vec(), keys[usize::from(j - 1)].clone()).unwrap(),
+            );
+        }
+        let offline = simulation.run().unwrap();
+
+        let message = Sha256::new()
+            .chain_bigint(&BigInt::from_bytes(b"a message"))
+            .result_bigint();
+        let (parties, local_sigs): (Vec<_>, Vec<_>) = offline
+            .into_iter()
+            .map(|o| SignManual::new(message.clone(), o).unwrap())
+            .unzip();
+        let mut parties = parties.into_iter();
+        let signature = parties.next().unwrap().complete(&local_sigs[1..]).unwrap();
+        verify(&signature, &public_key, &message).unwrap();
+    }
+
+    #[test]
+    fn migrates_example_key_file() {
+        let gg18_keys = simulate_keygen(1, 2);
+        let keys = gg18_keys
+            .iter()
+            .map(|key| {
+                let key = LocalKey::from_example_tuple((
+                    key.keys.clone(),
+                    key.shared_keys.clone(),
+                    key.i,
+                    key.vss_scheme_vec.clone(),
+                    key.paillier_key_vec.clone(),
+                    key.y_sum.clone(),
+                ))
+                .unwrap();
+                assert_eq!((key.t, key.n), (1, 2));
+                key
+            })
+            .collect::<Vec<_>>();
+        let migrated = simulate_migrate(keys);
+        assert_eq!(migrated[0].public_key(), gg18_keys[0].public_key());
+
+        let key = &gg18_keys[0];
+        let example_tuple = |i| {
+            (
+                key.keys.clone(),
+                key.shared_keys.clone(),
+                i,
+                key.vss_scheme_vec.clone(),
+                key.paillier_key_vec.clone(),
+                key.y_sum.clone(),
+            )
+        };
+        assert!(LocalKey::from_example_tuple(example_tuple(0)).is_none());
+        assert!(LocalKey::from_example_tuple(example_tuple(3)).is_none());
+    }
+
+    #[test]
+    fn identifies_party_with_short_paillier_key() {
+        let mut gg18_keys = simulate_keygen(1, 2);
+        let short_key = EncryptionKey::from(&(BigInt::from(65537) * BigInt::from(65539)));
+        for key in &mut gg18_keys {
+            key.paillier_key_vec[1] = short_key.clone();
+        }
+        let mut parties = gg18_keys
+            .into_iter()
+            .map(|key| Migrate::new(key).unwrap())
+            .collect::<Vec<_>>();
+        parties[1].proceed().unwrap();
+        let msg = parties[1].message_queue().remove(0);
+        assert!(matches!(
+            parties[0].handle_incoming(msg).and_then(|_| parties[0].proceed()),
+            Err(Error::ProceedRound(ProceedError::Round1InvalidPaillierKey { culprits }))
+                if culprits == [2]
+        ));
+    }
+
+    #[test]
+    fn identifies_party_migrating_different_key() {
+        let gg18_keys = simulate_keygen(1, 2);
+        let mut parties = gg18_keys
+            .into_iter()
+            .map(|key| Migrate::new(key).unwrap())
+            .collect::<Vec<_>>();
+        parties[1].proceed().unwrap();
+        let mut msg = parties[1].message_queue().remove(0);
+        let ProtocolMessage(M::Round1(m)) = &mut msg.body;
+        m.public_key = Point::generator() * Scalar::<Secp256k1>::from(2);
+        assert!(matches!(
+            parties[0].handle_incoming(msg).and_then(|_| parties[0].proceed()),
+            Err(Error::ProceedRound(ProceedError::Round1PublicKeyMismatch { culprits }))
+                if culprits == [2]
+        ));
+    }
+}
@@ -0,0 +1,170 @@
+use curv::arithmetic::traits::*;
+use curv::elliptic::curves::{Point, Secp256k1};
+use round_based::containers::push::Push;
+use round_based::containers::{self, BroadcastMsgs, Store};
+use round_based::Msg;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use zk_paillier::zkproofs::{CompositeDLogProof, DLogStatement};
+
+use crate::protocols::multi_party_ecdsa::gg_2018::party_i::Keys;
+use crate::protocols::multi_party_ecdsa::gg_2020::party_i::{
+    generate_h1_h2_N_tilde, SharedKeys, PAILLIER_MAX_BIT_LENGTH, PAILLIER_MIN_BIT_LENGTH,
+};
+use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey as Gg20LocalKey;
+
+use super::super::keygen::LocalKey;
+
+/// Party's `N_tilde`, `h1`, `h2` along with proofs that `h1` and `h2` generate the same group,
+/// sent at round 1
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DLogStatementMsg {
+    /// Public key the sender migrates, must be the same for every party
+    pub public_key: Point<Secp256k1>,
+    /// Statement `h2 = h1^xhi mod N_tilde`
+    pub dlog_statement: DLogStatement,
+    pub composite_dlog_proof_base_h1: CompositeDLogProof,
+    pub composite_dlog_proof_base_h2: CompositeDLogProof,
+}
+
+pub struct Round0 {
+    pub local_key: LocalKey,
+}
+
+impl Round0 {
+    pub fn proceed<O>(self, mut output: O) -> Result<Round1>
+    where
+        O: Push<Msg<DLogStatementMsg>>,
+    {
+        let (n_tilde, h1, h2, xhi, xhi_inv) = generate_h1_h2_N_tilde();
+        let dlog_statement = DLogStatement {
+            N: n_tilde.clone(),
+            g: h1.clone(),
+            ni: h2.clone(),
+        };
+        let dlog_statement_base_h2 = DLogStatement {
+            N: n_tilde,
+            g: h2,
+            ni: h1,
+        };
+        let msg = DLogStatementMsg {
+            public_key: self.local_key.public_key(),
+            composite_dlog_proof_base_h1: CompositeDLogProof::prove(&dlog_statement, &xhi),
+            composite_dlog_proof_base_h2: CompositeDLogProof::prove(
+                &dlog_statement_base_h2,
+                &xhi_inv,
+            ),
+            dlog_statement,
+        };
+        output.push(Msg {
+            sender: self.local_key.i,
+            receiver: None,
+            body: msg.clone(),
+        });
+        Ok(Round1 {
+            local_key: self.local_key,
+            msg,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+}
+
+pub struct Round1 {
+    local_key: LocalKey,
+    msg: DLogStatementMsg,
+}
+
+impl Round1 {
+    pub fn proceed(self, input: BroadcastMsgs<DLogStatementMsg>) -> Result<Gg20LocalKey> {
+        let local_key = self.local_key;
+        let msgs = input.into_vec_including_me(self.msg);
+
+        let culprits = (1..)
+            .zip(&msgs)
+            .filter(|(_, m)| m.public_key != local_key.y_sum)
+            .map(|(j, _)| j)
+            .collect::<Vec<u16>>();
+        if !culprits.is_empty() {
+            return Err(ProceedError::Round1PublicKeyMismatch { culprits });
+        }
+
+        // GG18 keygen doesn't bound size of Paillier keys, while GG20 range proofs rely on it
+        let culprits = (1..)
+            .zip(&local_key.paillier_key_vec)
+            .filter(|(_, ek)| {
+                ek.n.bit_length() < PAILLIER_MIN_BIT_LENGTH
+                    || ek.n.bit_length() > PAILLIER_MAX_BIT_LENGTH
+            })
+            .map(|(j, _)| j)
+            .collect::<Vec<u16>>();
+        if !culprits.is_empty() {
+            return Err(ProceedError::Round1InvalidPaillierKey { culprits });
+        }
+
+        let culprits = (1..)
+            .zip(&msgs)
+            .filter(|(_, m)| {
+                let statement = &m.dlog_statement;
+                let statement_base_h2 = DLogStatement {
+                    N: statement.N.clone(),
+                    g: statement.ni.clone(),
+                    ni: statement.g.clone(),
+                };
+                statement.N.bit_length() < PAILLIER_MIN_BIT_LENGTH
+                    || statement.N.bit_length() > PAILLIER_MAX_BIT_LENGTH
+                    || m.composite_dlog_proof_base_h1.verify(statement).is_err()
+                    || m.composite_dlog_proof_base_h2
+                        .verify(&statement_base_h2)
+                        .is_err()
+            })
+            .map(|(j, _)| j)
+            .collect::<Vec<u16>>();
+        if !culprits.is_empty() {
+            return Err(ProceedError::Round1InvalidDLogProof { culprits });
+        }
+
+        Ok(Gg20LocalKey {
+            paillier_dk: local_key.keys.dk,
+            pk_vec: Keys::get_commitments_to_xi(&local_key.vss_scheme_vec),
+            keys_linear: SharedKeys {
+                y: local_key.shared_keys.y,
+                x_i: local_key.shared_keys.x_i,
+            },
+            paillier_key_vec: local_key.paillier_key_vec,
+            y_sum_s: local_key.y_sum,
+            h1_h2_n_tilde_vec: msgs.into_iter().map(|m| m.dlog_statement).collect(),
+            vss_scheme: local_key.vss_scheme_vec[usize::from(local_key.i - 1)].clone(),
+            chain_code: None,
+            roster: None,
+            i: local_key.i,
+            t: local_key.t,
+            n: local_key.n,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<DLogStatementMsg>> {
+        containers::BroadcastMsgsStore::new(i, n)
+    }
+}
+
+// Errors
+
+type Result<T> = std::result::Result<T, ProceedError>;
+
+/// Proceeding protocol error
+///
+/// Subset of [migration errors](enum@super::Error) that can occur at protocol proceeding (i.e.
+/// after every message was received and pre-validated).
+#[derive(Debug, Error)]
+pub enum ProceedError {
+    #[error("round 1: parties migrate a different public key: parties {culprits:?}")]
+    Round1PublicKeyMismatch { culprits: Vec<u16> },
+    #[error("round 1: paillier key size is out of range: parties {culprits:?}")]
+    Round1InvalidPaillierKey { culprits: Vec<u16> },
+    #[error("round 1: invalid N_tilde or composite dlog proof: parties {culprits:?}")]
+    Round1InvalidDLogProof { culprits: Vec<u16> },
+}
@@ -0,0 +1,9 @@
+//! GG18 keygen and signing as [round_based::StateMachine]s
+//!
+//! Wraps the phases of [party_i](super::party_i) into protocols that can be carried out with
+//! `AsyncProtocol` or `Simulation` in the same way as [GG20](crate::protocols::multi_party_ecdsa::gg_2020::state_machine).
+//! GG18 keys can be turned into GG20 ones with [migrate] protocol.
+
+pub mod keygen;
+pub mod migrate;
+pub mod sign;
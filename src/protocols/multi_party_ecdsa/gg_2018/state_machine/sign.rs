@@ -0,0 +1,742 @@
+//! GG18 signing
+//!
+//! Any `t+1` parties holding [LocalKey] can sign a message. At round 1 every signer commits to
+//! `g_gamma_i` and broadcasts its nonce share `k_i` encrypted under its Paillier key. At round 2
+//! signers run MtA with each other to obtain additive shares of `k * gamma` and `k * x`, and
+//! broadcast their shares `delta_i` of `k * gamma` at round 3. At round 4 they reveal `g_gamma_i`
+//! and compute `R`. Rounds 5 to 8 check consistency of local signatures `s_i` (phase 5 of the
+//! paper) before signers broadcast them at round 9.
+//!
+//! Signing doesn't identify parties at fault apart from those sending invalid MtA responses.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::mem::replace;
+use std::time::Duration;
+
+use curv::elliptic::curves::{Point, Scalar, Secp256k1};
+use curv::BigInt;
+use round_based::containers::{
+    push::{Push, PushExt},
+    *,
+};
+use round_based::{IsCritical, Msg, StateMachine};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::protocols::multi_party_ecdsa::gg_2018::party_i::{
+    Keys, Phase5Com1, Phase5Com2, Phase5DDecom2, SignBroadcastPhase1, SignDecommitPhase1,
+    SignatureRecid,
+};
+use crate::utilities::mta::{MessageA, MessageB};
+
+use super::keygen::LocalKey;
+
+mod rounds;
+
+use private::InternalError;
+pub use rounds::{Phase5Decommitment, ProceedError};
+use rounds::{
+    Round0, Round1, Round2, Round3, Round4, Round5, Round6, Round7, Round8, Round9, Setup,
+};
+
+/// Signing protocol state machine
+///
+/// Successfully completed signing produces [SignatureRecid] which is valid under
+/// [public key](LocalKey::public_key) of [LocalKey].
+pub struct Sign {
+    round: R,
+
+    msgs1: Option<Store<BroadcastMsgs<(SignBroadcastPhase1, MessageA)>>>,
+    msgs2: Option<Store<P2PMsgs<(MessageB, MessageB)>>>,
+    msgs3: Option<Store<BroadcastMsgs<Scalar<Secp256k1>>>>,
+    msgs4: Option<Store<BroadcastMsgs<SignDecommitPhase1>>>,
+    msgs5: Option<Store<BroadcastMsgs<Phase5Com1>>>,
+    msgs6: Option<Store<BroadcastMsgs<Phase5Decommitment>>>,
+    msgs7: Option<Store<BroadcastMsgs<Phase5Com2>>>,
+    msgs8: Option<Store<BroadcastMsgs<Phase5DDecom2>>>,
+    msgs9: Option<Store<BroadcastMsgs<Scalar<Secp256k1>>>>,
+
+    msgs_queue: Vec<Msg<ProtocolMessage>>,
+
+    party_i: u16,
+    party_n: u16,
+}
+
+impl Sign {
+    /// Constructs a party of signing protocol
+    ///
+    /// Takes party index `i` (in range `[1; |s_l|]`), list `s_l` of keygen indexes of the parties
+    /// taking part in signing, party's [LocalKey] and hash of the message to sign. `s_l[i-1]` must
+    /// be equal to index of the party at keygen.
+    ///
+    /// Returns error if:
+    /// * `s_l` has less than `t+1` parties, returns [Error::TooFewParties]
+    /// * `s_l` contains duplicates or indexes that are not in range `[1; n]`, returns
+    ///   [Error::InvalidSl]
+    /// * `i` is not in range `[1; |s_l|]` or `s_l[i-1]` doesn't match index in local key, returns
+    ///   [Error::InvalidPartyIndex]
+    pub fn new(i: u16, s_l: Vec<u16>, local_key: LocalKey, message: BigInt) -> Result<Self> {
+        if s_l.len() <= usize::from(local_key.t) {
+            return Err(Error::TooFewParties);
+        }
+        let n = u16::try_from(s_l.len()).map_err(|_| Error::TooManyParties { n: s_l.len() })?;
+        let mut sorted = s_l.clone();
+        sorted.sort_unstable();
+        sorted.dedup();
+        if sorted.len() != s_l.len() || s_l.iter().any(|&j| j == 0 || j > local_key.n) {
+            return Err(Error::InvalidSl);
+        }
+        if i == 0 || i > n || s_l[usize::from(i - 1)] != local_key.i {
+            return Err(Error::InvalidPartyIndex);
+        }
+
+        let s_l0 = s_l.iter().map(|j| j - 1).collect::<Vec<_>>();
+        let xi_com_vec = Keys::get_commitments_to_xi(&local_key.vss_scheme_vec);
+        let g_w_vec = s_l0
+            .iter()
+            .map(|&j| {
+                Keys::update_commitments_to_xi(
+                    &xi_com_vec[usize::from(j)],
+                    &local_key.vss_scheme_vec[usize::from(j)],
+                    j,
+                    &s_l0,
+                )
+            })
+            .collect::<Vec<Point<Secp256k1>>>();
+
+        let setup = Setup {
+            i,
+            s_l,
+            local_key,
+            g_w_vec,
+            message,
+        };
+
+        let mut state = Self {
+            round: R::Round0(Round0 { setup }),
+
+            msgs1: Some(Round1::expects_messages(i, n)),
+            msgs2: Some(Round2::expects_messages(i, n)),
+            msgs3: Some(Round3::expects_messages(i, n)),
+            msgs4: Some(Round4::expects_messages(i, n)),
+            msgs5: Some(Round5::expects_messages(i, n)),
+            msgs6: Some(Round6::expects_messages(i, n)),
+            msgs7: Some(Round7::expects_messages(i, n)),
+            msgs8: Some(Round8::expects_messages(i, n)),
+            msgs9: Some(Round9::expects_messages(i, n)),
+
+            msgs_queue: vec![],
+
+            party_i: i,
+            party_n: n,
+        };
+
+        state.proceed_round(false)?;
+        Ok(state)
+    }
+
+    fn gmap_queue<'a, T, F>(&'a mut self, mut f: F) -> impl Push<Msg<T>> + 'a
+    where
+        F: FnMut(T) -> M + 'a,
+    {
+        (&mut self.msgs_queue).gmap(move |m: Msg<T>| m.map_body(|m| ProtocolMessage(f(m))))
+    }
+
+    /// Proceeds round state if it received enough messages and if it's cheap to compute or
+    /// `may_block == true`
+    fn proceed_round(&mut self, may_block: bool) -> Result<()> {
+        let store1_wants_more = self.msgs1.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store2_wants_more = self.msgs2.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store3_wants_more = self.msgs3.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store4_wants_more = self.msgs4.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store5_wants_more = self.msgs5.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store6_wants_more = self.msgs6.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store7_wants_more = self.msgs7.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store8_wants_more = self.msgs8.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store9_wants_more = self.msgs9.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+
+        let next_state: R;
+        let try_again: bool = match replace(&mut self.round, R::Gone) {
+            R::Round0(round) if !round.is_expensive() || may_block => {
+                next_state = round
+                    .proceed(self.gmap_queue(M::Round1))
+                    .map(R::Round1)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round0(_) => {
+                next_state = s;
+                false
+            }
+            R::Round1(round) if !store1_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs1.take().ok_or(InternalError::StoreGone)?;
+                let msgs = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?;
+                next_state = round
+                    .proceed(msgs, self.gmap_queue(M::Round2))
+                    .map(R::Round2)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round1(_) => {
+                next_state = s;
+                false
+            }
+            R::Round2(round) if !store2_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs2.take().ok_or(InternalError::StoreGone)?;
+                let msgs = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?;
+                next_state = round
+                    .proceed(msgs, self.gmap_queue(M::Round3))
+                    .map(R::Round3)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round2(_) => {
+                next_state = s;
+                false
+            }
+            R::Round3(round) if !store3_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs3.take().ok_or(InternalError::StoreGone)?;
+                let msgs = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?;
+                next_state = round
+                    .proceed(msgs, self.gmap_queue(M::Round4))
+                    .map(R::Round4)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round3(_) => {
+                next_state = s;
+                false
+            }
+            R::Round4(round) if !store4_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs4.take().ok_or(InternalError::StoreGone)?;
+                let msgs = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?;
+                next_state = round
+                    .proceed(msgs, self.gmap_queue(M::Round5))
+                    .map(R::Round5)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round4(_) => {
+                next_state = s;
+                false
+            }
+            R::Round5(round) if !store5_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs5.take().ok_or(InternalError::StoreGone)?;
+                let msgs = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?;
+                next_state = round
+                    .proceed(msgs, self.gmap_queue(M::Round6))
+                    .map(R::Round6)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round5(_) => {
+                next_state = s;
+                false
+            }
+            R::Round6(round) if !store6_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs6.take().ok_or(InternalError::StoreGone)?;
+                let msgs = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?;
+                next_state = round
+                    .proceed(msgs, self.gmap_queue(M::Round7))
+                    .map(R::Round7)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round6(_) => {
+                next_state = s;
+                false
+            }
+            R::Round7(round) if !store7_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs7.take().ok_or(InternalError::StoreGone)?;
+                let msgs = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?;
+                next_state = round
+                    .proceed(msgs, self.gmap_queue(M::Round8))
+                    .map(R::Round8)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round7(_) => {
+                next_state = s;
+                false
+            }
+            R::Round8(round) if !store8_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs8.take().ok_or(InternalError::StoreGone)?;
+                let msgs = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?;
+                next_state = round
+                    .proceed(msgs, self.gmap_queue(M::Round9))
+                    .map(R::Round9)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round8(_) => {
+                next_state = s;
+                false
+            }
+            R::Round9(round) if !store9_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs9.take().ok_or(InternalError::StoreGone)?;
+                let msgs = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?;
+                next_state = round
+                    .proceed(msgs)
+                    .map(R::Final)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round9(_) => {
+                next_state = s;
+                false
+            }
+            s @ R::Final(_) | s @ R::Gone => {
+                next_state = s;
+                false
+            }
+        };
+
+        self.round = next_state;
+        if try_again {
+            self.proceed_round(may_block)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl StateMachine for Sign {
+    type MessageBody = ProtocolMessage;
+    type Err = Error;
+    type Output = SignatureRecid;
+
+    fn handle_incoming(&mut self, msg: Msg<Self::MessageBody>) -> Result<()> {
+        let current_round = self.current_round();
+
+        match msg.body {
+            ProtocolMessage(M::Round1(m)) => {
+                let store = self
+                    .msgs1
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 1,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+            ProtocolMessage(M::Round2(m)) => {
+                let store = self
+                    .msgs2
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 2,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+            ProtocolMessage(M::Round3(m)) => {
+                let store = self
+                    .msgs3
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 3,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+            ProtocolMessage(M::Round4(m)) => {
+                let store = self
+                    .msgs4
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 4,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+            ProtocolMessage(M::Round5(m)) => {
+                let store = self
+                    .msgs5
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 5,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+            ProtocolMessage(M::Round6(m)) => {
+                let store = self
+                    .msgs6
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 6,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+            ProtocolMessage(M::Round7(m)) => {
+                let store = self
+                    .msgs7
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 7,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+            ProtocolMessage(M::Round8(m)) => {
+                let store = self
+                    .msgs8
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 8,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+            ProtocolMessage(M::Round9(m)) => {
+                let store = self
+                    .msgs9
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 9,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+        }
+        self.proceed_round(false)
+    }
+
+    fn message_queue(&mut self) -> &mut Vec<Msg<Self::MessageBody>> {
+        &mut self.msgs_queue
+    }
+
+    fn wants_to_proceed(&self) -> bool {
+        let store1_wants_more = self.msgs1.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store2_wants_more = self.msgs2.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store3_wants_more = self.msgs3.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store4_wants_more = self.msgs4.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store5_wants_more = self.msgs5.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store6_wants_more = self.msgs6.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store7_wants_more = self.msgs7.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store8_wants_more = self.msgs8.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store9_wants_more = self.msgs9.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+
+        match &self.round {
+            R::Round0(_) => true,
+            R::Round1(_) => !store1_wants_more,
+            R::Round2(_) => !store2_wants_more,
+            R::Round3(_) => !store3_wants_more,
+            R::Round4(_) => !store4_wants_more,
+            R::Round5(_) => !store5_wants_more,
+            R::Round6(_) => !store6_wants_more,
+            R::Round7(_) => !store7_wants_more,
+            R::Round8(_) => !store8_wants_more,
+            R::Round9(_) => !store9_wants_more,
+            R::Final(_) | R::Gone => false,
+        }
+    }
+
+    fn proceed(&mut self) -> Result<()> {
+        self.proceed_round(true)
+    }
+
+    fn round_timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    fn round_timeout_reached(&mut self) -> Self::Err {
+        panic!("no timeout was set")
+    }
+
+    fn is_finished(&self) -> bool {
+        matches!(self.round, R::Final(_))
+    }
+
+    fn pick_output(&mut self) -> Option<Result<Self::Output>> {
+        match self.round {
+            R::Final(_) => (),
+            R::Gone => return Some(Err(Error::DoublePickOutput)),
+            _ => return None,
+        }
+
+        match replace(&mut self.round, R::Gone) {
+            R::Final(result) => Some(Ok(result)),
+            _ => unreachable!("guaranteed by match expression above"),
+        }
+    }
+
+    fn current_round(&self) -> u16 {
+        match &self.round {
+            R::Round0(_) => 0,
+            R::Round1(_) => 1,
+            R::Round2(_) => 2,
+            R::Round3(_) => 3,
+            R::Round4(_) => 4,
+            R::Round5(_) => 5,
+            R::Round6(_) => 6,
+            R::Round7(_) => 7,
+            R::Round8(_) => 8,
+            R::Round9(_) => 9,
+            R::Final(_) | R::Gone => 10,
+        }
+    }
+
+    fn total_rounds(&self) -> Option<u16> {
+        Some(9)
+    }
+
+    fn party_ind(&self) -> u16 {
+        self.party_i
+    }
+
+    fn parties(&self) -> u16 {
+        self.party_n
+    }
+}
+
+impl fmt::Debug for Sign {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{{GG18 Sign at round={} queue=[len={}]}}",
+            self.current_round(),
+            self.msgs_queue.len()
+        )
+    }
+}
+
+// Rounds
+
+enum R {
+    Round0(Round0),
+    Round1(Round1),
+    Round2(Round2),
+    Round3(Round3),
+    Round4(Round4),
+    Round5(Round5),
+    Round6(Round6),
+    Round7(Round7),
+    Round8(Round8),
+    Round9(Round9),
+    Final(SignatureRecid),
+    Gone,
+}
+
+// Messages
+
+/// Protocol message which parties send on wire
+///
+/// Hides actual messages structure so it could be changed without breaking semver policy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtocolMessage(M);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)]
+enum M {
+    Round1((SignBroadcastPhase1, MessageA)),
+    Round2((MessageB, MessageB)),
+    Round3(Scalar<Secp256k1>),
+    Round4(SignDecommitPhase1),
+    Round5(Phase5Com1),
+    Round6(Phase5Decommitment),
+    Round7(Phase5Com2),
+    Round8(Phase5DDecom2),
+    Round9(Scalar<Secp256k1>),
+}
+
+// Error
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Error type of signing protocol
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// Round proceeding resulted in error
+    #[error("proceed round: {0}")]
+    ProceedRound(#[source] ProceedError),
+
+    /// Less than `t+1` parties are taking part in signing
+    #[error("at least t+1 parties are required for signing")]
+    TooFewParties,
+    /// Number of signers doesn't fit into `u16`
+    #[error("too many parties: {n}")]
+    TooManyParties { n: usize },
+    /// `s_l` contains duplicates or indexes out of range `[1; n]`
+    #[error("list of signers is invalid")]
+    InvalidSl,
+    /// Party index `i` is not in range `[1; |s_l|]` or doesn't match local key
+    #[error("party index is not in range [1; |s_l|] or doesn't match local key")]
+    InvalidPartyIndex,
+
+    /// Received message didn't pass pre-validation
+    #[error("received message didn't pass pre-validation: {0}")]
+    HandleMessage(#[source] StoreErr),
+    /// Received message which we didn't expect to receive now (e.g. message from previous round)
+    #[error(
+        "didn't expect to receive message from round {msg_round} (being at round {current_round})"
+    )]
+    ReceivedOutOfOrderMessage { current_round: u16, msg_round: u16 },
+    /// [Sign::pick_output] called twice
+    #[error("pick_output called twice")]
+    DoublePickOutput,
+
+    /// Some internal assertions were failed, which is a bug
+    #[doc(hidden)]
+    #[error("internal error: {0:?}")]
+    InternalError(InternalError),
+}
+
+impl IsCritical for Error {
+    fn is_critical(&self) -> bool {
+        !matches!(
+            self,
+            Error::HandleMessage(_) | Error::ReceivedOutOfOrderMessage { .. }
+        )
+    }
+}
+
+impl From<InternalError> for Error {
+    fn from(err: InternalError) -> Self {
+        Self::InternalError(err)
+    }
+}
+
+mod private {
+    #[derive(Debug)]
+    #[non_exhaustive]
+    pub enum InternalError {
+        /// [Messages store](super::MessageStore) reported that it received all messages it wanted to receive,
+        /// but refused to return message container
+        RetrieveRoundMessages(super::StoreErr),
+        #[doc(hidden)]
+        StoreGone,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use curv::arithmetic::Converter;
+    use curv::cryptographic_primitives::hashing::{Digest, DigestExt};
+    use round_based::dev::Simulation;
+    use sha2::Sha256;
+
+    use super::super::keygen::test::simulate_keygen;
+    use super::*;
+    use crate::protocols::multi_party_ecdsa::gg_2018::party_i::verify;
+
+    fn simulate_sign(keys: &[LocalKey], s_l: &[u16], message: &BigInt) -> SignatureRecid {
+        let mut simulation = Simulation::new();
+        for (i, &j) in (1..).zip(s_l) {
+            simulation.add_party(
+                Sign::new(
+                    i,
+                    s_l.to_vec(),
+                    keys[usize::from(j - 1)].clone(),
+                    message.clone(),
+                )
+                .unwrap(),
+            );
+        }
+        let signatures = simulation.run().unwrap();
+        for signature in &signatures {
+            assert_eq!(signature.r, signatures[0].r);
+            assert_eq!(signature.s, signatures[0].s);
+        }
+        signatures[0].clone()
+    }
+
+    #[test]
+    fn simulate_signing_t1_n3() {
+        let keys = simulate_keygen(1, 3);
+        let message = Sha256::new()
+            .chain_bigint(&BigInt::from_bytes(b"a message"))
+            .result_bigint();
+
+        for s_l in [&[1, 3][..], &[3, 2, 1]] {
+            let signature = simulate_sign(&keys, s_l, &message);
+            verify(&signature, &keys[0].public_key(), &message).unwrap();
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_signers_list() {
+        let keys = simulate_keygen(1, 2);
+        let message = BigInt::from(1);
+        let sign = |i, s_l: Vec<u16>| Sign::new(i, s_l, keys[0].clone(), message.clone());
+        assert!(matches!(sign(1, vec![1]), Err(Error::TooFewParties)));
+        assert!(matches!(sign(1, vec![1, 1]), Err(Error::InvalidSl)));
+        assert!(matches!(sign(1, vec![1, 3]), Err(Error::InvalidSl)));
+        assert!(matches!(sign(2, vec![1, 2]), Err(Error::InvalidPartyIndex)));
+    }
+}
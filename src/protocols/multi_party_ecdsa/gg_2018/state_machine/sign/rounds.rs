@@ -0,0 +1,544 @@
+use curv::cryptographic_primitives::proofs::sigma_correct_homomorphic_elgamal_enc::HomoELGamalProof;
+use curv::cryptographic_primitives::proofs::sigma_dlog::DLogProof;
+use curv::elliptic::curves::{Point, Scalar, Secp256k1};
+use curv::BigInt;
+use round_based::containers::push::Push;
+use round_based::containers::{self, BroadcastMsgs, P2PMsgs, Store};
+use round_based::Msg;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use thiserror::Error;
+
+use crate::protocols::multi_party_ecdsa::gg_2018::party_i::{
+    LocalSignature, PartyPrivate, Phase5ADecom1, Phase5Com1, Phase5Com2, Phase5DDecom2,
+    SignBroadcastPhase1, SignDecommitPhase1, SignKeys, SignatureRecid,
+};
+use crate::utilities::mta::{MessageA, MessageB};
+use crate::Error as PhaseError;
+
+use super::super::keygen::LocalKey;
+
+/// Party's phase 5 decommitment along with proofs of its correctness, sent at round 6
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Phase5Decommitment {
+    pub decom: Phase5ADecom1,
+    pub elgamal_proof: HomoELGamalProof<Secp256k1, Sha256>,
+    pub dlog_proof_rho: DLogProof<Secp256k1, Sha256>,
+}
+
+/// Data that doesn't change throughout the protocol
+pub struct Setup {
+    pub i: u16,
+    /// Keygen indexes of the signers
+    pub s_l: Vec<u16>,
+    pub local_key: LocalKey,
+    /// `w_j * G` of every signer, where `w_j` is signer's share multiplied by its Lagrange
+    /// coefficient
+    pub g_w_vec: Vec<Point<Secp256k1>>,
+    pub message: BigInt,
+}
+
+impl Setup {
+    fn keygen_index(&self, j: u16) -> usize {
+        usize::from(self.s_l[usize::from(j - 1)] - 1)
+    }
+}
+
+pub struct Round0 {
+    pub setup: Setup,
+}
+
+impl Round0 {
+    pub fn proceed<O>(self, mut output: O) -> Result<Round1>
+    where
+        O: Push<Msg<(SignBroadcastPhase1, MessageA)>>,
+    {
+        let setup = self.setup;
+        let local_key = &setup.local_key;
+        let private =
+            PartyPrivate::set_private(local_key.keys.clone(), local_key.shared_keys.clone());
+        let s_l0 = setup.s_l.iter().map(|j| j - 1).collect::<Vec<_>>();
+        let sign_keys = SignKeys::create(
+            &private,
+            &local_key.vss_scheme_vec[usize::from(local_key.i - 1)],
+            local_key.i - 1,
+            &s_l0,
+        );
+        let (com, decommit) = sign_keys.phase1_broadcast();
+        let (m_a, _) = MessageA::a(&sign_keys.k_i, &local_key.keys.ek, &[]);
+
+        output.push(Msg {
+            sender: setup.i,
+            receiver: None,
+            body: (com.clone(), m_a.clone()),
+        });
+        Ok(Round1 {
+            setup,
+            sign_keys,
+            com,
+            decommit,
+            m_a,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+}
+
+pub struct Round1 {
+    setup: Setup,
+    sign_keys: SignKeys,
+    com: SignBroadcastPhase1,
+    decommit: SignDecommitPhase1,
+    m_a: MessageA,
+}
+
+impl Round1 {
+    pub fn proceed<O>(
+        self,
+        input: BroadcastMsgs<(SignBroadcastPhase1, MessageA)>,
+        mut output: O,
+    ) -> Result<Round2>
+    where
+        O: Push<Msg<(MessageB, MessageB)>>,
+    {
+        let setup = self.setup;
+        let (com_vec, m_a_vec): (Vec<_>, Vec<_>) = input
+            .into_vec_including_me((self.com, self.m_a))
+            .into_iter()
+            .unzip();
+
+        let mut beta_vec = vec![];
+        let mut ni_vec = vec![];
+        for (j, m_a) in (1..).zip(m_a_vec) {
+            if j == setup.i {
+                continue;
+            }
+            let ek = &setup.local_key.paillier_key_vec[setup.keygen_index(j)];
+            let (m_b_gamma, beta_gamma, _, _) =
+                MessageB::b(&self.sign_keys.gamma_i, ek, m_a.clone(), &[])
+                    .map_err(|_| ProceedError::Round1MtA)?;
+            let (m_b_w, beta_wi, _, _) = MessageB::b(&self.sign_keys.w_i, ek, m_a, &[])
+                .map_err(|_| ProceedError::Round1MtA)?;
+            output.push(Msg {
+                sender: setup.i,
+                receiver: Some(j),
+                body: (m_b_gamma, m_b_w),
+            });
+            beta_vec.push(beta_gamma);
+            ni_vec.push(beta_wi);
+        }
+
+        Ok(Round2 {
+            setup,
+            sign_keys: self.sign_keys,
+            com_vec,
+            decommit: self.decommit,
+            beta_vec,
+            ni_vec,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+    pub fn expects_messages(
+        i: u16,
+        n: u16,
+    ) -> Store<BroadcastMsgs<(SignBroadcastPhase1, MessageA)>> {
+        containers::BroadcastMsgsStore::new(i, n)
+    }
+}
+
+pub struct Round2 {
+    setup: Setup,
+    sign_keys: SignKeys,
+    com_vec: Vec<SignBroadcastPhase1>,
+    decommit: SignDecommitPhase1,
+    beta_vec: Vec<Scalar<Secp256k1>>,
+    ni_vec: Vec<Scalar<Secp256k1>>,
+}
+
+impl Round2 {
+    pub fn proceed<O>(self, input: P2PMsgs<(MessageB, MessageB)>, mut output: O) -> Result<Round3>
+    where
+        O: Push<Msg<Scalar<Secp256k1>>>,
+    {
+        let setup = self.setup;
+        let dk = &setup.local_key.keys.dk;
+        let k_i = &self.sign_keys.k_i;
+
+        let mut alpha_vec = vec![];
+        let mut miu_vec = vec![];
+        let mut b_proof_vec = vec![];
+        let mut culprits = vec![];
+        for (j, (m_b_gamma, m_b_w)) in input.into_iter_indexed() {
+            let alpha = m_b_gamma.verify_proofs_get_alpha(dk, k_i);
+            let miu = m_b_w.verify_proofs_get_alpha(dk, k_i);
+            match (alpha, miu) {
+                (Ok(alpha), Ok(miu)) if m_b_w.b_proof.pk == setup.g_w_vec[usize::from(j - 1)] => {
+                    alpha_vec.push(alpha.0);
+                    miu_vec.push(miu.0);
+                    b_proof_vec.push(m_b_gamma.b_proof);
+                }
+                _ => culprits.push(j),
+            }
+        }
+        if !culprits.is_empty() {
+            return Err(ProceedError::Round2InvalidMtAResponse { culprits });
+        }
+
+        let delta_i = self.sign_keys.phase2_delta_i(&alpha_vec, &self.beta_vec);
+        let sigma_i = self.sign_keys.phase2_sigma_i(&miu_vec, &self.ni_vec);
+        output.push(Msg {
+            sender: setup.i,
+            receiver: None,
+            body: delta_i.clone(),
+        });
+
+        // Phase 4 checks `g_gamma_j` of every signer against proof of knowledge of `gamma_j`
+        // received in MtA, our own one is proved here
+        b_proof_vec.insert(
+            usize::from(setup.i - 1),
+            DLogProof::prove(&self.sign_keys.gamma_i),
+        );
+
+        Ok(Round3 {
+            setup,
+            sign_keys: self.sign_keys,
+            com_vec: self.com_vec,
+            decommit: self.decommit,
+            b_proof_vec,
+            delta_i,
+            sigma_i,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<P2PMsgs<(MessageB, MessageB)>> {
+        containers::P2PMsgsStore::new(i, n)
+    }
+}
+
+pub struct Round3 {
+    setup: Setup,
+    sign_keys: SignKeys,
+    com_vec: Vec<SignBroadcastPhase1>,
+    decommit: SignDecommitPhase1,
+    b_proof_vec: Vec<DLogProof<Secp256k1, Sha256>>,
+    delta_i: Scalar<Secp256k1>,
+    sigma_i: Scalar<Secp256k1>,
+}
+
+impl Round3 {
+    pub fn proceed<O>(
+        self,
+        input: BroadcastMsgs<Scalar<Secp256k1>>,
+        mut output: O,
+    ) -> Result<Round4>
+    where
+        O: Push<Msg<SignDecommitPhase1>>,
+    {
+        let delta_vec = input.into_vec_including_me(self.delta_i);
+        if delta_vec.iter().sum::<Scalar<Secp256k1>>().is_zero() {
+            return Err(ProceedError::Round3ZeroDelta);
+        }
+        let delta_inv = SignKeys::phase3_reconstruct_delta(&delta_vec);
+
+        output.push(Msg {
+            sender: self.setup.i,
+            receiver: None,
+            body: self.decommit.clone(),
+        });
+
+        Ok(Round4 {
+            setup: self.setup,
+            sign_keys: self.sign_keys,
+            com_vec: self.com_vec,
+            decommit: self.decommit,
+            b_proof_vec: self.b_proof_vec,
+            delta_inv,
+            sigma_i: self.sigma_i,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        false
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<Scalar<Secp256k1>>> {
+        containers::BroadcastMsgsStore::new(i, n)
+    }
+}
+
+pub struct Round4 {
+    setup: Setup,
+    sign_keys: SignKeys,
+    com_vec: Vec<SignBroadcastPhase1>,
+    decommit: SignDecommitPhase1,
+    b_proof_vec: Vec<DLogProof<Secp256k1, Sha256>>,
+    delta_inv: Scalar<Secp256k1>,
+    sigma_i: Scalar<Secp256k1>,
+}
+
+impl Round4 {
+    pub fn proceed<O>(
+        self,
+        input: BroadcastMsgs<SignDecommitPhase1>,
+        mut output: O,
+    ) -> Result<Round5>
+    where
+        O: Push<Msg<Phase5Com1>>,
+    {
+        let setup = self.setup;
+        let decommit_vec = input.into_vec_including_me(self.decommit);
+        let b_proof_vec = self.b_proof_vec.iter().collect::<Vec<_>>();
+        let r = SignKeys::phase4(&self.delta_inv, &b_proof_vec, decommit_vec, &self.com_vec)
+            .map_err(ProceedError::Round4VerifyDecommitments)?;
+
+        let local_sig = LocalSignature::phase5_local_sig(
+            &self.sign_keys.k_i,
+            &setup.message,
+            &r,
+            &self.sigma_i,
+            &setup.local_key.y_sum,
+        );
+        let (com1, decom1, elgamal_proof, dlog_proof_rho) =
+            local_sig.phase5a_broadcast_5b_zkproof();
+        output.push(Msg {
+            sender: setup.i,
+            receiver: None,
+            body: com1.clone(),
+        });
+
+        Ok(Round5 {
+            setup,
+            local_sig,
+            com1,
+            decom1: Phase5Decommitment {
+                decom: decom1,
+                elgamal_proof,
+                dlog_proof_rho,
+            },
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<SignDecommitPhase1>> {
+        containers::BroadcastMsgsStore::new(i, n)
+    }
+}
+
+pub struct Round5 {
+    setup: Setup,
+    local_sig: LocalSignature,
+    com1: Phase5Com1,
+    decom1: Phase5Decommitment,
+}
+
+impl Round5 {
+    pub fn proceed<O>(self, input: BroadcastMsgs<Phase5Com1>, mut output: O) -> Result<Round6>
+    where
+        O: Push<Msg<Phase5Decommitment>>,
+    {
+        output.push(Msg {
+            sender: self.setup.i,
+            receiver: None,
+            body: self.decom1.clone(),
+        });
+        Ok(Round6 {
+            com1_vec: input.into_vec_including_me(self.com1),
+            setup: self.setup,
+            local_sig: self.local_sig,
+            decom1: self.decom1,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        false
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<Phase5Com1>> {
+        containers::BroadcastMsgsStore::new(i, n)
+    }
+}
+
+pub struct Round6 {
+    setup: Setup,
+    local_sig: LocalSignature,
+    com1_vec: Vec<Phase5Com1>,
+    decom1: Phase5Decommitment,
+}
+
+impl Round6 {
+    pub fn proceed<O>(
+        self,
+        input: BroadcastMsgs<Phase5Decommitment>,
+        mut output: O,
+    ) -> Result<Round7>
+    where
+        O: Push<Msg<Phase5Com2>>,
+    {
+        let own_v_i = self.decom1.decom.V_i.clone();
+        let own = usize::from(self.setup.i - 1);
+        let decom1_vec = input.into_vec_including_me(self.decom1);
+
+        // Phase 5c takes decommitments of other parties only, but proofs of knowledge of `rho_j` of
+        // every party
+        let mut com1_vec = self.com1_vec;
+        com1_vec.remove(own);
+        let (decom_vec, elgamal_proofs): (Vec<_>, Vec<_>) = decom1_vec
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != own)
+            .map(|(_, d)| (d.decom.clone(), d.elgamal_proof.clone()))
+            .unzip();
+        let dlog_proofs_rho = decom1_vec
+            .iter()
+            .map(|d| d.dlog_proof_rho.clone())
+            .collect::<Vec<_>>();
+
+        let (com2, decom2) = self
+            .local_sig
+            .phase5c(
+                &decom_vec,
+                &com1_vec,
+                &elgamal_proofs,
+                &dlog_proofs_rho,
+                &own_v_i,
+                &self.local_sig.R,
+            )
+            .map_err(ProceedError::Round6VerifyPhase5Decommitments)?;
+        output.push(Msg {
+            sender: self.setup.i,
+            receiver: None,
+            body: com2.clone(),
+        });
+
+        Ok(Round7 {
+            setup: self.setup,
+            local_sig: self.local_sig,
+            decom1_vec: decom1_vec.into_iter().map(|d| d.decom).collect(),
+            com2,
+            decom2,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<Phase5Decommitment>> {
+        containers::BroadcastMsgsStore::new(i, n)
+    }
+}
+
+pub struct Round7 {
+    setup: Setup,
+    local_sig: LocalSignature,
+    decom1_vec: Vec<Phase5ADecom1>,
+    com2: Phase5Com2,
+    decom2: Phase5DDecom2,
+}
+
+impl Round7 {
+    pub fn proceed<O>(self, input: BroadcastMsgs<Phase5Com2>, mut output: O) -> Result<Round8>
+    where
+        O: Push<Msg<Phase5DDecom2>>,
+    {
+        output.push(Msg {
+            sender: self.setup.i,
+            receiver: None,
+            body: self.decom2.clone(),
+        });
+        Ok(Round8 {
+            com2_vec: input.into_vec_including_me(self.com2),
+            setup: self.setup,
+            local_sig: self.local_sig,
+            decom1_vec: self.decom1_vec,
+            decom2: self.decom2,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        false
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<Phase5Com2>> {
+        containers::BroadcastMsgsStore::new(i, n)
+    }
+}
+
+pub struct Round8 {
+    setup: Setup,
+    local_sig: LocalSignature,
+    decom1_vec: Vec<Phase5ADecom1>,
+    com2_vec: Vec<Phase5Com2>,
+    decom2: Phase5DDecom2,
+}
+
+impl Round8 {
+    pub fn proceed<O>(self, input: BroadcastMsgs<Phase5DDecom2>, mut output: O) -> Result<Round9>
+    where
+        O: Push<Msg<Scalar<Secp256k1>>>,
+    {
+        let decom2_vec = input.into_vec_including_me(self.decom2);
+        let s_i = self
+            .local_sig
+            .phase5d(&decom2_vec, &self.com2_vec, &self.decom1_vec)
+            .map_err(ProceedError::Round8VerifyPhase5Decommitments)?;
+        output.push(Msg {
+            sender: self.setup.i,
+            receiver: None,
+            body: s_i,
+        });
+        Ok(Round9 {
+            local_sig: self.local_sig,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<Phase5DDecom2>> {
+        containers::BroadcastMsgsStore::new(i, n)
+    }
+}
+
+pub struct Round9 {
+    local_sig: LocalSignature,
+}
+
+impl Round9 {
+    pub fn proceed(self, input: BroadcastMsgs<Scalar<Secp256k1>>) -> Result<SignatureRecid> {
+        let s_vec = input.into_vec();
+        self.local_sig
+            .output_signature(&s_vec)
+            .map_err(|_| ProceedError::Round9InvalidSignature)
+    }
+    pub fn is_expensive(&self) -> bool {
+        false
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<Scalar<Secp256k1>>> {
+        containers::BroadcastMsgsStore::new(i, n)
+    }
+}
+
+// Errors
+
+type Result<T> = std::result::Result<T, ProceedError>;
+
+/// Proceeding protocol error
+///
+/// Subset of [signing errors](enum@super::Error) that can occur at protocol proceeding (i.e.
+/// after every message was received and pre-validated). Indexes of parties are positions in the
+/// list of signers (starting from 1).
+#[derive(Debug, Error)]
+pub enum ProceedError {
+    #[error("round 1: MtA with received Paillier ciphertext failed")]
+    Round1MtA,
+    #[error("round 2: invalid MtA response: parties {culprits:?}")]
+    Round2InvalidMtAResponse { culprits: Vec<u16> },
+    #[error("round 3: sum of delta_i is zero")]
+    Round3ZeroDelta,
+    #[error("round 4: invalid decommitment to g_gamma_i: {0:?}")]
+    Round4VerifyDecommitments(PhaseError),
+    #[error("round 6: invalid phase 5 decommitment or proof: {0:?}")]
+    Round6VerifyPhase5Decommitments(PhaseError),
+    #[error("round 8: invalid phase 5 decommitment or consistency check failed: {0:?}")]
+    Round8VerifyPhase5Decommitments(PhaseError),
+    #[error("round 9: resulting signature is not valid")]
+    Round9InvalidSignature,
+}
@@ -0,0 +1,297 @@
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+//! # JSON Web Tokens issued by signing committee
+//!
+//! Tokens are compact JWS signed with `ES256K` algorithm (ECDSA over secp256k1 with SHA-256,
+//! [RFC 8812]). Token header carries `kid` which is a JWK thumbprint ([RFC 7638]) of group public
+//! key, so verifier can tell which committee issued the token.
+//!
+//! ## How to issue a token
+//!
+//! 1. Every signer builds the same [UnsignedToken] out of claims and group public key
+//! 2. Signers carry out [OfflineStage] and [SignManual] over [UnsignedToken::message]
+//! 3. Whoever collects partial signatures turns the resulting signature into compact JWS via
+//!    [UnsignedToken::complete]
+//!
+//! Token is verified by [verify] against [LocalKey::public_key].
+//!
+//! Note that [verify] only checks the header and the signature. Registered claims (like `exp` or
+//! `nbf`) are not validated, it's up to caller to check them.
+//!
+//! [RFC 8812]: https://www.rfc-editor.org/rfc/rfc8812
+//! [RFC 7638]: https://www.rfc-editor.org/rfc/rfc7638
+//! [OfflineStage]: super::state_machine::sign::OfflineStage
+//! [SignManual]: super::state_machine::sign::SignManual
+//! [LocalKey::public_key]: super::state_machine::keygen::LocalKey::public_key
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use curv::arithmetic::traits::*;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use curv::BigInt;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::protocols::multi_party_ecdsa::gg_2020::party_i::SignatureRecid;
+use crate::utilities::verification::{self, Mode};
+
+/// JWS algorithm of tokens issued by committee
+pub const ALGORITHM: &str = "ES256K";
+
+/// JOSE header of token issued by committee
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Header {
+    pub alg: String,
+    pub typ: String,
+    pub kid: String,
+}
+
+impl Header {
+    /// Constructs a header of token signed by committee with group public key `public_key`
+    pub fn new(public_key: &Point<Secp256k1>) -> Self {
+        Self {
+            alg: ALGORITHM.to_owned(),
+            typ: "JWT".to_owned(),
+            kid: key_id(public_key),
+        }
+    }
+}
+
+/// Token to be signed by committee
+///
+/// Every signer must construct the same token, i.e. claims must serialize to the same JSON.
+#[derive(Clone, Debug)]
+pub struct UnsignedToken {
+    signing_input: String,
+}
+
+impl UnsignedToken {
+    /// Serializes header and claims of a token to be signed by committee with group public key
+    /// `public_key`
+    pub fn new<C: Serialize>(public_key: &Point<Secp256k1>, claims: &C) -> Result<Self> {
+        let header =
+            serde_json::to_vec(&Header::new(public_key)).map_err(Error::SerializeHeader)?;
+        let claims = serde_json::to_vec(claims).map_err(Error::SerializeClaims)?;
+        Ok(Self {
+            signing_input: format!(
+                "{}.{}",
+                URL_SAFE_NO_PAD.encode(header),
+                URL_SAFE_NO_PAD.encode(claims)
+            ),
+        })
+    }
+
+    /// JWS signing input: `BASE64URL(header) || '.' || BASE64URL(claims)`
+    pub fn signing_input(&self) -> &str {
+        &self.signing_input
+    }
+
+    /// Message to be signed by committee (SHA-256 hash of [signing input](Self::signing_input))
+    pub fn message(&self) -> BigInt {
+        BigInt::from_bytes(&Sha256::digest(self.signing_input.as_bytes()))
+    }
+
+    /// Attaches signature produced by committee, returns token in compact JWS serialization
+    pub fn complete(self, signature: &SignatureRecid) -> String {
+        let mut sig = Vec::with_capacity(64);
+        sig.extend_from_slice(&signature.r.to_bytes());
+        sig.extend_from_slice(&signature.s.to_bytes());
+        format!("{}.{}", self.signing_input, URL_SAFE_NO_PAD.encode(sig))
+    }
+}
+
+/// Verifies token issued by committee with group public key `public_key`, returns its header and
+/// claims
+///
+/// Signature must be in low-S form (see [Mode::Strict]). Every token issued via [UnsignedToken]
+/// satisfies this requirement.
+pub fn verify<C: DeserializeOwned>(
+    token: &str,
+    public_key: &Point<Secp256k1>,
+) -> Result<(Header, C)> {
+    let mut parts = token.split('.');
+    let (header_b64, claims_b64, sig_b64) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(h), Some(c), Some(s)) if parts.next().is_none() => (h, c, s),
+        _ => return Err(Error::MalformedToken),
+    };
+
+    let header = URL_SAFE_NO_PAD.decode(header_b64).map_err(Error::Base64)?;
+    let header: Header = serde_json::from_slice(&header).map_err(Error::DeserializeHeader)?;
+    if header.alg != ALGORITHM {
+        return Err(Error::UnsupportedAlgorithm(header.alg));
+    }
+    if header.kid != key_id(public_key) {
+        return Err(Error::KeyIdMismatch);
+    }
+
+    let sig = URL_SAFE_NO_PAD.decode(sig_b64).map_err(Error::Base64)?;
+    if sig.len() != 64 {
+        return Err(Error::MalformedSignature);
+    }
+    let q = Scalar::<Secp256k1>::group_order();
+    let r = BigInt::from_bytes(&sig[..32]);
+    let s = BigInt::from_bytes(&sig[32..]);
+    if &r >= q || &s >= q {
+        return Err(Error::MalformedSignature);
+    }
+
+    let signing_input = &token[..header_b64.len() + 1 + claims_b64.len()];
+    let message = BigInt::from_bytes(&Sha256::digest(signing_input.as_bytes()));
+    verification::verify(
+        &Scalar::from(&r),
+        &Scalar::from(&s),
+        public_key,
+        &message,
+        Mode::Strict,
+    )
+    .map_err(|_| Error::InvalidSignature)?;
+
+    let claims = URL_SAFE_NO_PAD.decode(claims_b64).map_err(Error::Base64)?;
+    let claims = serde_json::from_slice(&claims).map_err(Error::DeserializeClaims)?;
+    Ok((header, claims))
+}
+
+/// Key id of group public key: base64url-encoded JWK thumbprint ([RFC 7638])
+///
+/// [RFC 7638]: https://www.rfc-editor.org/rfc/rfc7638
+pub fn key_id(public_key: &Point<Secp256k1>) -> String {
+    let coord = |c: Option<BigInt>| {
+        let c = c.unwrap_or_else(BigInt::zero);
+        URL_SAFE_NO_PAD.encode(BigInt::to_bytes_array::<32>(&c).unwrap_or([0u8; 32]))
+    };
+    // Members must be in lexicographic order without whitespaces
+    let jwk = format!(
+        r#"{{"crv":"secp256k1","kty":"EC","x":"{}","y":"{}"}}"#,
+        coord(public_key.x_coord()),
+        coord(public_key.y_coord()),
+    );
+    URL_SAFE_NO_PAD.encode(Sha256::digest(jwk.as_bytes()))
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("serialize header: {0}")]
+    SerializeHeader(serde_json::Error),
+    #[error("serialize claims: {0}")]
+    SerializeClaims(serde_json::Error),
+    #[error("token must consist of three dot-separated parts")]
+    MalformedToken,
+    #[error("invalid base64url encoding: {0}")]
+    Base64(base64::DecodeError),
+    #[error("deserialize header: {0}")]
+    DeserializeHeader(serde_json::Error),
+    #[error("deserialize claims: {0}")]
+    DeserializeClaims(serde_json::Error),
+    #[error("unsupported algorithm {0:?}, expected ES256K")]
+    UnsupportedAlgorithm(String),
+    #[error("token is issued by a different key")]
+    KeyIdMismatch,
+    #[error("signature must be 64 bytes: r || s, both less than curve order")]
+    MalformedSignature,
+    #[error("invalid signature")]
+    InvalidSignature,
+}
+
+#[cfg(test)]
+mod test {
+    use round_based::dev::Simulation;
+
+    use super::*;
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::sign::{
+        OfflineStage, SignManual,
+    };
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    struct Claims {
+        chain: String,
+        blocknumber: String,
+        exp: usize,
+    }
+
+    fn issue(claims: &Claims) -> (String, Point<Secp256k1>) {
+        let local_keys = simulate_keygen(1, 2);
+        let public_key = local_keys[0].public_key();
+
+        let mut simulation = Simulation::new();
+        for (i, local_key) in (1..).zip(local_keys) {
+            simulation.add_party(OfflineStage::new(i, vec![1, 2], local_key).unwrap());
+        }
+        let offline = simulation.run().unwrap();
+
+        let token = UnsignedToken::new(&public_key, claims).unwrap();
+        let (parties, partial_sigs): (Vec<_>, Vec<_>) = offline
+            .into_iter()
+            .map(|o| SignManual::new(token.message(), o).unwrap())
+            .unzip();
+        let signature = parties
+            .into_iter()
+            .next()
+            .unwrap()
+            .complete(&partial_sigs[1..])
+            .unwrap();
+
+        (token.complete(&signature), public_key)
+    }
+
+    #[test]
+    fn issue_and_verify_token() {
+        let claims = Claims {
+            chain: "ethereum".to_owned(),
+            blocknumber: "0x10d4f".to_owned(),
+            exp: 1_700_000_000,
+        };
+        let (token, public_key) = issue(&claims);
+
+        let (header, decoded) = verify::<Claims>(&token, &public_key).unwrap();
+        assert_eq!(header, Header::new(&public_key));
+        assert_eq!(decoded, claims);
+
+        // Tampered claims
+        let parts: Vec<_> = token.split('.').collect();
+        let forged_claims = Claims {
+            exp: usize::MAX,
+            ..claims
+        };
+        let forged = format!(
+            "{}.{}.{}",
+            parts[0],
+            URL_SAFE_NO_PAD.encode(serde_json::to_vec(&forged_claims).unwrap()),
+            parts[2]
+        );
+        assert!(matches!(
+            verify::<Claims>(&forged, &public_key),
+            Err(Error::InvalidSignature)
+        ));
+
+        // Different committee
+        let other_key = Point::generator() * Scalar::<Secp256k1>::random();
+        assert!(matches!(
+            verify::<Claims>(&token, &other_key),
+            Err(Error::KeyIdMismatch)
+        ));
+
+        assert!(matches!(
+            verify::<Claims>(&parts[..2].join("."), &public_key),
+            Err(Error::MalformedToken)
+        ));
+    }
+}
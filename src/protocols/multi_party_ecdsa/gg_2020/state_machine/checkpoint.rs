@@ -0,0 +1,542 @@
+//! Encrypted checkpoints of in-progress protocols
+//!
+//! [Keygen](super::keygen::Keygen) and [OfflineStage](super::sign::OfflineStage) can be saved
+//! at any point of protocol execution as a [Checkpoint]: it captures round state (including
+//! party's secrets, e.g. Paillier keys), received messages that weren't consumed by a round yet,
+//! and outgoing messages that weren't taken from the message queue. Checkpoint is encrypted with
+//! AES-256-GCM under a [CheckpointKey], so it can be written to the disk as is. Restored party
+//! carries on from the same point, so a process restart doesn't abort the protocol.
+//!
+//! Checkpoint is the only way to persist protocol state: round state is never exposed in plain
+//! text.
+//!
+//! ## Equivocation
+//!
+//! Party restored from a stale checkpoint would compute some round messages anew, with fresh
+//! randomness. Sending them to parties that already received other messages of the same round is
+//! equivocation. To rule that out, every party keeps a [SentLog]: digests of messages it emitted
+//! at every round. Party can only be checkpointed once the log is attached to a [SentLogStore]
+//! (e.g. [SentLogFile]), and the log is saved to the store every time the party enters a round,
+//! before messages of the round are put into the message queue. Restored party loads the log from
+//! the store and refuses to emit messages that differ from logged ones, returning
+//! [Error::Equivocation].
+//!
+//! ## Example
+//! ```no_run
+//! # use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::{
+//! #     checkpoint::{CheckpointKey, SentLogFile},
+//! #     keygen::Keygen,
+//! # };
+//! # fn main() -> anyhow::Result<()> {
+//! let key = CheckpointKey::generate();
+//! let mut keygen: Keygen = Keygen::new(1, 1, 3)?;
+//! keygen.persist_sent_log(SentLogFile::new("keygen.sent"))?;
+//! // ... handle incoming messages and send outgoing ones
+//! let checkpoint = serde_json::to_vec(&keygen.checkpoint(&key)?)?;
+//!
+//! // After restart
+//! let checkpoint = serde_json::from_slice(&checkpoint)?;
+//! let keygen: Keygen = Keygen::restore(&checkpoint, &key, SentLogFile::new("keygen.sent"))?;
+//! # Ok(()) }
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use aes_gcm::aead::{Aead, NewAead, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use curv::arithmetic::traits::*;
+use curv::BigInt;
+use round_based::Msg;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use zeroize::Zeroize;
+
+/// Version of checkpoint format
+const VERSION: u16 = 1;
+
+/// Key that encrypts checkpoints
+pub struct CheckpointKey([u8; 32]);
+
+impl CheckpointKey {
+    /// Generates a new random key
+    pub fn generate() -> Self {
+        let mut key = [0u8; 32];
+        fill_random(&mut key);
+        Self(key)
+    }
+
+    pub fn from_bytes(key: [u8; 32]) -> Self {
+        Self(key)
+    }
+
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(&Key::from(self.0))
+    }
+}
+
+impl Drop for CheckpointKey {
+    fn drop(&mut self) {
+        self.0.zeroize()
+    }
+}
+
+/// Encrypted state of in-progress protocol
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Checkpoint {
+    version: u16,
+    protocol: String,
+    nonce: [u8; 12],
+    ciphertext: Vec<u8>,
+}
+
+impl Checkpoint {
+    /// Encrypts protocol state
+    pub(crate) fn seal<T: Serialize>(
+        key: &CheckpointKey,
+        protocol: &str,
+        state: &T,
+    ) -> Result<Self> {
+        let mut plaintext = serde_json::to_vec(state).map_err(Error::Serialize)?;
+        let mut nonce = [0u8; 12];
+        fill_random(&mut nonce);
+        let ciphertext = key.cipher().encrypt(
+            &Nonce::from(nonce),
+            Payload {
+                msg: &plaintext,
+                aad: &associated_data(protocol),
+            },
+        );
+        plaintext.zeroize();
+        Ok(Self {
+            version: VERSION,
+            protocol: protocol.to_owned(),
+            nonce,
+            ciphertext: ciphertext.map_err(|_| Error::Encrypt)?,
+        })
+    }
+
+    /// Decrypts protocol state
+    pub(crate) fn open<T: DeserializeOwned>(
+        &self,
+        key: &CheckpointKey,
+        protocol: &str,
+    ) -> Result<T> {
+        if self.version != VERSION {
+            return Err(Error::UnsupportedVersion {
+                version: self.version,
+            });
+        }
+        if self.protocol != protocol {
+            return Err(Error::ProtocolMismatch {
+                expected: protocol.to_owned(),
+                actual: self.protocol.clone(),
+            });
+        }
+        let mut plaintext = key
+            .cipher()
+            .decrypt(
+                &Nonce::from(self.nonce),
+                Payload {
+                    msg: &self.ciphertext,
+                    aad: &associated_data(protocol),
+                },
+            )
+            .map_err(|_| Error::Decrypt)?;
+        let state = serde_json::from_slice(&plaintext).map_err(Error::Deserialize);
+        plaintext.zeroize();
+        state
+    }
+
+    /// Name of the protocol which state is saved in the checkpoint
+    pub fn protocol(&self) -> &str {
+        &self.protocol
+    }
+}
+
+/// Digests of messages that party emitted at every round
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SentLog {
+    rounds: BTreeMap<u16, [u8; 32]>,
+}
+
+impl SentLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rounds at which party emitted messages
+    pub fn rounds(&self) -> impl Iterator<Item = u16> + '_ {
+        self.rounds.keys().copied()
+    }
+
+    /// Logs messages emitted at given round
+    ///
+    /// Returns [Error::Equivocation] if different messages were already logged at this round.
+    pub(crate) fn record<M: Serialize>(&mut self, round: u16, msgs: &[Msg<M>]) -> Result<()> {
+        let encoded = serde_json::to_vec(msgs).map_err(Error::Serialize)?;
+        let digest: [u8; 32] = Sha256::digest(&encoded).into();
+        match self.rounds.get(&round) {
+            Some(logged) if *logged != digest => Err(Error::Equivocation { round }),
+            _ => {
+                self.rounds.insert(round, digest);
+                Ok(())
+            }
+        }
+    }
+
+    /// Merges rounds logged in `other`
+    ///
+    /// Returns [Error::Equivocation] if logs disagree on messages of some round.
+    pub(crate) fn merge(&mut self, other: &SentLog) -> Result<()> {
+        for (&round, digest) in &other.rounds {
+            match self.rounds.get(&round) {
+                Some(logged) if logged != digest => return Err(Error::Equivocation { round }),
+                _ => {
+                    self.rounds.insert(round, *digest);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Durable storage of a [SentLog]
+pub trait SentLogStore: Send {
+    /// Loads the latest saved log, returns an empty log if nothing was saved yet
+    fn load(&mut self) -> io::Result<SentLog>;
+    /// Saves the log, must not return before it's durably stored
+    fn save(&mut self, log: &SentLog) -> io::Result<()>;
+}
+
+/// [SentLogStore] keeping the log in a file
+pub struct SentLogFile {
+    path: PathBuf,
+}
+
+impl SentLogFile {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl SentLogStore for SentLogFile {
+    fn load(&mut self) -> io::Result<SentLog> {
+        match fs::read(&self.path) {
+            Ok(log) => serde_json::from_slice(&log)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(SentLog::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn save(&mut self, log: &SentLog) -> io::Result<()> {
+        let log = serde_json::to_vec(log).map_err(io::Error::from)?;
+        // Write to a temporary file first, so a crash never leaves half-written log
+        let tmp = self.path.with_extension("tmp");
+        let mut f = fs::File::create(&tmp)?;
+        f.write_all(&log)?;
+        f.sync_all()?;
+        fs::rename(&tmp, &self.path)
+    }
+}
+
+/// [SentLog] of a state machine along with the store it's persisted to
+#[derive(Default)]
+pub(crate) struct Journal {
+    log: SentLog,
+    store: Option<Box<dyn SentLogStore>>,
+}
+
+impl Journal {
+    /// Loads the log from the store and merges it with the one saved in a checkpoint
+    pub fn restore(checkpointed: &SentLog, mut store: Box<dyn SentLogStore>) -> Result<Self> {
+        let mut log = store.load().map_err(Error::SentLogStore)?;
+        log.merge(checkpointed)?;
+        store.save(&log).map_err(Error::SentLogStore)?;
+        Ok(Self {
+            log,
+            store: Some(store),
+        })
+    }
+
+    pub fn log(&self) -> &SentLog {
+        &self.log
+    }
+
+    /// Attaches the store, and saves the log to it
+    pub fn persist(&mut self, mut store: Box<dyn SentLogStore>) -> Result<()> {
+        store.save(&self.log).map_err(Error::SentLogStore)?;
+        self.store = Some(store);
+        Ok(())
+    }
+
+    /// Returns [Error::SentLogNotPersisted] if the store isn't attached
+    pub fn ensure_persisted(&self) -> Result<()> {
+        match self.store {
+            Some(_) => Ok(()),
+            None => Err(Error::SentLogNotPersisted),
+        }
+    }
+
+    /// Logs messages emitted at given round, and saves the log to the store
+    ///
+    /// Log is left untouched if messages differ from the logged ones or the store fails.
+    pub fn record<M: Serialize>(&mut self, round: u16, msgs: &[Msg<M>]) -> Result<()> {
+        let mut log = self.log.clone();
+        log.record(round, msgs)?;
+        if let Some(store) = &mut self.store {
+            store.save(&log).map_err(Error::SentLogStore)?;
+        }
+        self.log = log;
+        Ok(())
+    }
+}
+
+fn associated_data(protocol: &str) -> Vec<u8> {
+    let mut aad = VERSION.to_be_bytes().to_vec();
+    aad.extend_from_slice(protocol.as_bytes());
+    aad
+}
+
+fn fill_random(out: &mut [u8]) {
+    let bytes = BigInt::sample(out.len() * 8).to_bytes();
+    let offset = out.len() - bytes.len();
+    out[offset..].copy_from_slice(&bytes);
+}
+
+type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Error {
+    #[error("serialize protocol state: {0}")]
+    Serialize(#[source] serde_json::Error),
+    #[error("deserialize protocol state: {0}")]
+    Deserialize(#[source] serde_json::Error),
+    #[error("encrypt checkpoint")]
+    Encrypt,
+    #[error("checkpoint can't be decrypted: wrong key or checkpoint is corrupted")]
+    Decrypt,
+    #[error("unsupported checkpoint version {version}")]
+    UnsupportedVersion { version: u16 },
+    #[error("checkpoint saves state of {actual}, expected {expected}")]
+    ProtocolMismatch { expected: String, actual: String },
+    #[error("party has already sent different messages at round {round}")]
+    Equivocation { round: u16 },
+    #[error("sent log is not persisted, party can't be checkpointed")]
+    SentLogNotPersisted,
+    #[error("sent log store: {0}")]
+    SentLogStore(#[source] io::Error),
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use curv::elliptic::curves::secp256_k1::Secp256k1;
+    use round_based::StateMachine;
+
+    use super::*;
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::{self, Keygen};
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::sign::{self, OfflineStage};
+
+    /// Sent log store shared by all incarnations of a party
+    #[derive(Clone, Default)]
+    struct MemoryStore(Arc<Mutex<SentLog>>);
+
+    impl SentLogStore for MemoryStore {
+        fn load(&mut self) -> io::Result<SentLog> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+        fn save(&mut self, log: &SentLog) -> io::Result<()> {
+            *self.0.lock().unwrap() = log.clone();
+            Ok(())
+        }
+    }
+
+    /// Delivers all queued messages, calls `after_delivery` every time a party received a message
+    fn exchange<SM, F>(parties: &mut [SM], mut after_delivery: F)
+    where
+        SM: StateMachine,
+        SM::MessageBody: Clone,
+        SM::Err: std::fmt::Debug,
+        F: FnMut(usize, &mut SM),
+    {
+        let msgs = parties
+            .iter_mut()
+            .flat_map(|p| p.message_queue().drain(..).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        for msg in msgs {
+            for (k, party) in parties.iter_mut().enumerate() {
+                let j = k as u16 + 1;
+                if msg.sender == j || matches!(msg.receiver, Some(r) if r != j) {
+                    continue;
+                }
+                party.handle_incoming(msg.clone()).unwrap();
+                after_delivery(k, party);
+            }
+        }
+        for party in parties.iter_mut() {
+            if party.wants_to_proceed() {
+                party.proceed().unwrap();
+            }
+        }
+    }
+
+    fn run_to_completion<SM, F>(mut parties: Vec<SM>, mut after_delivery: F) -> Vec<SM::Output>
+    where
+        SM: StateMachine,
+        SM::MessageBody: Clone,
+        SM::Err: std::fmt::Debug,
+        F: FnMut(usize, &mut SM),
+    {
+        for _ in 0..10 {
+            if parties.iter().all(|p| p.is_finished()) {
+                return parties
+                    .iter_mut()
+                    .map(|p| p.pick_output().unwrap().unwrap())
+                    .collect();
+            }
+            exchange(&mut parties, &mut after_delivery);
+        }
+        panic!("protocol didn't complete")
+    }
+
+    /// Restores the first party from a checkpoint after every message it receives
+    fn checkpoint_and_restore<SM>(
+        checkpoint: impl Fn(&SM) -> Checkpoint,
+        restore: impl Fn(&Checkpoint, &SM) -> SM,
+    ) -> impl FnMut(usize, &mut SM) {
+        move |k, party| {
+            if k == 0 {
+                let encoded = serde_json::to_vec(&checkpoint(party)).unwrap();
+                *party = restore(&serde_json::from_slice(&encoded).unwrap(), party);
+            }
+        }
+    }
+
+    #[test]
+    fn keygen_and_offline_stage_restored_after_every_message() {
+        let key = CheckpointKey::generate();
+        let store = MemoryStore::default();
+
+        let mut keygens: Vec<Keygen> = (1..=3).map(|i| Keygen::new(i, 1, 3).unwrap()).collect();
+        keygens[0].persist_sent_log(store.clone()).unwrap();
+        let local_keys = run_to_completion(
+            keygens,
+            checkpoint_and_restore(
+                |p: &Keygen| p.checkpoint(&key).unwrap(),
+                |c, _| Keygen::restore(c, &key, store.clone()).unwrap(),
+            ),
+        );
+        assert!(local_keys
+            .iter()
+            .all(|k| k.public_key() == local_keys[0].public_key()));
+
+        let store = MemoryStore::default();
+        let s_l = vec![1, 3];
+        let mut offline_stages = vec![
+            OfflineStage::new(1, s_l.clone(), local_keys[0].clone()).unwrap(),
+            OfflineStage::new(2, s_l, local_keys[2].clone()).unwrap(),
+        ];
+        offline_stages[0].persist_sent_log(store.clone()).unwrap();
+        let completed = run_to_completion(
+            offline_stages,
+            checkpoint_and_restore(
+                |p: &OfflineStage| p.checkpoint(&key).unwrap(),
+                |c, _| OfflineStage::restore(c, &key, store.clone()).unwrap(),
+            ),
+        );
+        assert_eq!(completed[0].public_key(), &local_keys[0].public_key());
+    }
+
+    #[test]
+    fn checkpoint_requires_the_same_key_and_protocol() {
+        let key = CheckpointKey::generate();
+        let mut keygen: Keygen = Keygen::new(1, 1, 2).unwrap();
+        keygen.persist_sent_log(MemoryStore::default()).unwrap();
+        let checkpoint = keygen.checkpoint(&key).unwrap();
+
+        assert!(matches!(
+            Keygen::<Secp256k1>::restore(
+                &checkpoint,
+                &CheckpointKey::generate(),
+                MemoryStore::default()
+            ),
+            Err(keygen::Error::Checkpoint(Error::Decrypt))
+        ));
+        assert!(matches!(
+            OfflineStage::<Secp256k1>::restore(&checkpoint, &key, MemoryStore::default()),
+            Err(sign::Error::Checkpoint(Error::ProtocolMismatch { .. }))
+        ));
+    }
+
+    #[test]
+    fn checkpoint_requires_persisted_sent_log() {
+        let key = CheckpointKey::generate();
+        let mut keygen: Keygen = Keygen::new(1, 1, 2).unwrap();
+        assert!(matches!(
+            keygen.checkpoint(&key),
+            Err(keygen::Error::Checkpoint(Error::SentLogNotPersisted))
+        ));
+
+        // Messages of round 1 are logged to the store by the time they're queued
+        let store = MemoryStore::default();
+        keygen.persist_sent_log(store.clone()).unwrap();
+        keygen.proceed().unwrap();
+        assert!(!keygen.message_queue().is_empty());
+        assert!(store.0.lock().unwrap().rounds().any(|r| r == 1));
+        keygen.checkpoint(&key).unwrap();
+    }
+
+    #[test]
+    fn party_restored_from_stale_checkpoint_refuses_to_equivocate() {
+        let key = CheckpointKey::generate();
+        let store = MemoryStore::default();
+        let mut parties = (1..=2)
+            .map(|i| Keygen::new(i, 1, 2).unwrap())
+            .collect::<Vec<Keygen>>();
+        parties[0].persist_sent_log(store.clone()).unwrap();
+        // Both parties proceed to round 2 (decommitment), messages of round 3 (VSS shares) are
+        // not generated yet
+        for _ in 0..2 {
+            exchange(&mut parties, |_, _| ());
+        }
+        let stale = parties[0].checkpoint(&key).unwrap();
+        let decommitment = parties[1].message_queue().clone();
+
+        exchange(&mut parties, |_, _| ());
+        // VSS shares were logged to the store before they were queued
+        assert!(store.0.lock().unwrap().rounds().any(|r| r == 3));
+
+        // Restored party receives decommitment again and computes new VSS shares
+        let mut restored = Keygen::restore(&stale, &key, store.clone()).unwrap();
+        let result = decommitment
+            .into_iter()
+            .try_for_each(|msg| restored.handle_incoming(msg))
+            .and_then(|()| restored.proceed());
+        assert!(matches!(
+            result,
+            Err(keygen::Error::Checkpoint(Error::Equivocation { round: 3 }))
+        ));
+        // Only decommitment that was queued at the moment of stale checkpoint is left
+        assert!(restored
+            .message_queue()
+            .iter()
+            .all(|m| m.receiver.is_none()));
+
+        // Party restored from up-to-date checkpoint keeps going
+        let checkpoint = parties[0].checkpoint(&key).unwrap();
+        let mut restored: Keygen = Keygen::restore(&checkpoint, &key, store).unwrap();
+        assert_eq!(restored.current_round(), 3);
+        assert_eq!(
+            restored.message_queue().len(),
+            parties[0].message_queue().len()
+        );
+    }
+}
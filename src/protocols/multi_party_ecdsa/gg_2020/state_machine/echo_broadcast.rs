@@ -0,0 +1,570 @@
+//! Echo broadcast: detects parties sending different broadcast messages to different peers
+//!
+//! GG20 assumes a reliable broadcast channel, but a relay only guarantees that every message
+//! reaches its addressee. A malicious party may send one commitment to some parties and another
+//! one to the rest, and nobody would notice. [EchoBroadcast] wraps [Keygen](super::keygen::Keygen),
+//! [OfflineStage](super::sign::OfflineStage), or any other state machine and adds an echo round
+//! after each broadcast round:
+//! 1. Messages received in a round are held back from the wrapped state machine
+//! 2. Once all broadcast messages of the round are received, party sends to everyone hashes of
+//!    the broadcast messages it has seen (including its own)
+//! 3. Once everyone's echo is received, echoed hashes are compared with party's own view. If
+//!    they match, held messages are handed over to the wrapped state machine, otherwise protocol
+//!    is aborted with [Error::Equivocation]
+//!
+//! Round tags are checked against receiver's own round: a message of the wrapped protocol may
+//! only belong to the current round or to the next one (sent by a party that's ahead), and an
+//! echo may only refer to the current round, as nobody can echo a round before receiving our
+//! own broadcast message of that round.
+//!
+//! Rounds in which party itself sends no broadcast message are considered P2P-only and passed
+//! through without echo. Echo messages themselves are not protected against equivocation, so
+//! messages should be [signed](crate::identity) to make blame undeniable.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+use round_based::{IsCritical, Msg, StateMachine};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// Domain separator of broadcast message hash
+const DOMAIN: &[u8] = b"multi-party-ecdsa/echo-broadcast/v1";
+
+/// State machine with echo broadcast, see [module level documentation](self)
+pub struct EchoBroadcast<SM: StateMachine> {
+    inner: SM,
+    rounds: BTreeMap<u16, RoundState<SM::MessageBody>>,
+    msgs_queue: Vec<Msg<EchoMessage<SM::MessageBody>>>,
+}
+
+/// Message of [EchoBroadcast] protocol
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum EchoMessage<M> {
+    /// Message of wrapped protocol sent at `round`
+    Protocol { round: u16, body: M },
+    /// Hashes of broadcast messages received at `round`, `hashes[j-1]` is hash of message from
+    /// party `j`
+    Echo { round: u16, hashes: Vec<[u8; 32]> },
+}
+
+struct RoundState<M> {
+    /// `None` until we sent our own messages of this round, then `Some(true)` if we sent a
+    /// broadcast message
+    broadcast: Option<bool>,
+    held: Vec<Msg<M>>,
+    hashes: Vec<Option<[u8; 32]>>,
+    echoes: Vec<Option<Vec<[u8; 32]>>>,
+    echo_sent: bool,
+    released: bool,
+}
+
+impl<M> RoundState<M> {
+    fn new(n: u16) -> Self {
+        Self {
+            broadcast: None,
+            held: vec![],
+            hashes: vec![None; usize::from(n)],
+            echoes: vec![None; usize::from(n)],
+            echo_sent: false,
+            released: false,
+        }
+    }
+}
+
+impl<SM> EchoBroadcast<SM>
+where
+    SM: StateMachine,
+    SM::MessageBody: Serialize,
+{
+    /// Wraps state machine `inner`
+    pub fn new(inner: SM) -> Result<Self, Error<SM::Err>> {
+        let mut state = Self {
+            inner,
+            rounds: BTreeMap::new(),
+            msgs_queue: vec![],
+        };
+        state.advance()?;
+        Ok(state)
+    }
+
+    /// Wrapped state machine
+    pub fn inner(&self) -> &SM {
+        &self.inner
+    }
+
+    fn round(&mut self, round: u16) -> &mut RoundState<SM::MessageBody> {
+        let n = self.inner.parties();
+        self.rounds
+            .entry(round)
+            .or_insert_with(|| RoundState::new(n))
+    }
+
+    /// Forwards messages sent by wrapped state machine, sends echoes and releases held messages
+    /// until nothing changes
+    fn advance(&mut self) -> Result<(), Error<SM::Err>> {
+        let (i, n) = (self.inner.party_ind(), self.inner.parties());
+        loop {
+            let sent = std::mem::take(self.inner.message_queue());
+            if !sent.is_empty() {
+                // Wrapped protocol sends messages of round `r` when it enters round `r`
+                let round = self.inner.current_round();
+                for msg in sent {
+                    if msg.receiver.is_none() {
+                        let hash = message_hash(round, msg.sender, &msg.body)?;
+                        let state = self.round(round);
+                        state.broadcast = Some(true);
+                        state.hashes[usize::from(i - 1)] = Some(hash);
+                    } else {
+                        let state = self.round(round);
+                        state.broadcast = Some(state.broadcast.unwrap_or(false));
+                    }
+                    self.msgs_queue
+                        .push(msg.map_body(|body| EchoMessage::Protocol { round, body }));
+                }
+            }
+
+            let msgs_queue = &mut self.msgs_queue;
+            let ready = self.rounds.iter_mut().find_map(|(&round, state)| {
+                if state.released {
+                    return None;
+                }
+                match state.broadcast {
+                    None => None,
+                    Some(false) => Some(round),
+                    Some(true) => {
+                        if !state.echo_sent && state.hashes.iter().all(Option::is_some) {
+                            state.echo_sent = true;
+                            let hashes = state.hashes.iter().flatten().cloned().collect();
+                            msgs_queue.push(Msg {
+                                sender: i,
+                                receiver: None,
+                                body: EchoMessage::Echo { round, hashes },
+                            });
+                        }
+                        let echoes_received = (1..=n)
+                            .filter(|&j| j != i)
+                            .all(|j| state.echoes[usize::from(j - 1)].is_some());
+                        if state.echo_sent && echoes_received {
+                            Some(round)
+                        } else {
+                            None
+                        }
+                    }
+                }
+            });
+            let round = match ready {
+                Some(round) => round,
+                None => return Ok(()),
+            };
+
+            let state = self.round(round);
+            state.released = true;
+            if state.broadcast == Some(true) {
+                check_echoes(round, i, state)?;
+            }
+            let held = std::mem::take(&mut state.held);
+            for msg in held {
+                self.inner.handle_incoming(msg).map_err(Error::Inner)?;
+            }
+        }
+    }
+}
+
+impl<SM> fmt::Debug for EchoBroadcast<SM>
+where
+    SM: StateMachine + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pending_rounds = self
+            .rounds
+            .iter()
+            .filter(|(_, state)| !state.released)
+            .map(|(&round, _)| round)
+            .collect::<Vec<_>>();
+        f.debug_struct("EchoBroadcast")
+            .field("inner", &self.inner)
+            .field("pending_rounds", &pending_rounds)
+            .field("msgs_queue", &self.msgs_queue.len())
+            .finish()
+    }
+}
+
+/// Compares party's view of broadcast `round` with echoes received from others
+fn check_echoes<M, E>(round: u16, i: u16, state: &RoundState<M>) -> Result<(), Error<E>> {
+    for (witness, echo) in (1..).zip(&state.echoes) {
+        let echo = match echo {
+            Some(echo) => echo,
+            None => continue,
+        };
+        for ((party, hash), echoed) in (1..).zip(&state.hashes).zip(echo) {
+            if hash.as_ref() == Some(echoed) {
+                continue;
+            }
+            return Err(if party == witness || party == i {
+                // Witness claims it sent (or we sent) a message different from the one we
+                // received (or sent), so it's the witness who is lying
+                Error::Equivocation {
+                    round,
+                    party: witness,
+                    witness: i,
+                }
+            } else {
+                Error::Equivocation {
+                    round,
+                    party,
+                    witness,
+                }
+            });
+        }
+    }
+    Ok(())
+}
+
+fn message_hash<M: Serialize, E>(round: u16, sender: u16, body: &M) -> Result<[u8; 32], Error<E>> {
+    let body = serde_json::to_vec(body).map_err(|e| Error::Serialize(e.to_string()))?;
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(
+        &Sha256::new()
+            .chain(DOMAIN)
+            .chain(round.to_be_bytes())
+            .chain(sender.to_be_bytes())
+            .chain(&body)
+            .finalize(),
+    );
+    Ok(hash)
+}
+
+impl<SM> StateMachine for EchoBroadcast<SM>
+where
+    SM: StateMachine,
+    SM::MessageBody: Serialize,
+{
+    type MessageBody = EchoMessage<SM::MessageBody>;
+    type Err = Error<SM::Err>;
+    type Output = SM::Output;
+
+    fn handle_incoming(&mut self, msg: Msg<Self::MessageBody>) -> Result<(), Self::Err> {
+        let (i, n) = (self.inner.party_ind(), self.inner.parties());
+        let sender = msg.sender;
+        if sender == 0 || sender > n || sender == i {
+            return Err(Error::UnknownSender { sender });
+        }
+        let current_round = self.inner.current_round();
+        match msg.body {
+            EchoMessage::Protocol { round, body } => {
+                if round != current_round && Some(round) != current_round.checked_add(1) {
+                    return Err(Error::UnexpectedRound {
+                        round,
+                        current_round,
+                        sender,
+                    });
+                }
+                let msg = Msg {
+                    sender,
+                    receiver: msg.receiver,
+                    body,
+                };
+                if msg.receiver.is_none() {
+                    let hash = message_hash(round, sender, &msg.body)?;
+                    let state = self.round(round);
+                    let slot = &mut state.hashes[usize::from(sender - 1)];
+                    if slot.is_some() {
+                        return Err(Error::DuplicatedMessage { round, sender });
+                    }
+                    *slot = Some(hash);
+                }
+                if self.round(round).released {
+                    // Message of the round that's already passed, let wrapped state machine
+                    // decide what to do with it
+                    self.inner.handle_incoming(msg).map_err(Error::Inner)?;
+                } else {
+                    self.round(round).held.push(msg);
+                }
+            }
+            EchoMessage::Echo { round, hashes } => {
+                if round != current_round || self.round(round).broadcast != Some(true) {
+                    return Err(Error::UnexpectedEcho {
+                        round,
+                        current_round,
+                        sender,
+                    });
+                }
+                if hashes.len() != usize::from(n) {
+                    return Err(Error::MalformedEcho { round, sender });
+                }
+                let state = self.round(round);
+                let slot = &mut state.echoes[usize::from(sender - 1)];
+                if slot.is_some() {
+                    return Err(Error::DuplicatedEcho { round, sender });
+                }
+                *slot = Some(hashes);
+            }
+        }
+        self.advance()
+    }
+
+    fn message_queue(&mut self) -> &mut Vec<Msg<Self::MessageBody>> {
+        &mut self.msgs_queue
+    }
+
+    fn wants_to_proceed(&self) -> bool {
+        self.inner.wants_to_proceed()
+    }
+
+    fn proceed(&mut self) -> Result<(), Self::Err> {
+        self.inner.proceed().map_err(Error::Inner)?;
+        self.advance()
+    }
+
+    fn round_timeout(&self) -> Option<Duration> {
+        self.inner.round_timeout()
+    }
+
+    fn round_timeout_reached(&mut self) -> Self::Err {
+        Error::Inner(self.inner.round_timeout_reached())
+    }
+
+    fn is_finished(&self) -> bool {
+        self.inner.is_finished()
+    }
+
+    fn pick_output(&mut self) -> Option<Result<Self::Output, Self::Err>> {
+        self.inner
+            .pick_output()
+            .map(|output| output.map_err(Error::Inner))
+    }
+
+    fn current_round(&self) -> u16 {
+        self.inner.current_round()
+    }
+
+    fn total_rounds(&self) -> Option<u16> {
+        self.inner.total_rounds()
+    }
+
+    fn party_ind(&self) -> u16 {
+        self.inner.party_ind()
+    }
+
+    fn parties(&self) -> u16 {
+        self.inner.parties()
+    }
+}
+
+/// Error type of [EchoBroadcast]
+#[derive(Debug, Error)]
+pub enum Error<E> {
+    /// Wrapped state machine returned error
+    #[error("{0}")]
+    Inner(E),
+    /// Party `party` sent different messages at broadcast `round`: message `witness` received
+    /// from it differs from what we received
+    #[error("party {party} equivocated at round {round} (witnessed by party {witness})")]
+    Equivocation {
+        round: u16,
+        party: u16,
+        witness: u16,
+    },
+    /// Message sender is not in range `[1; n]`, or it's us
+    #[error("message from unknown sender {sender}")]
+    UnknownSender { sender: u16 },
+    /// Message is tagged with a round other than current round of this party or the next one
+    #[error("party {sender} sent message of round {round} (being at round {current_round})")]
+    UnexpectedRound {
+        round: u16,
+        current_round: u16,
+        sender: u16,
+    },
+    /// Echo refers to a round other than current round of this party, or to a round in which
+    /// this party sent no broadcast message
+    #[error("party {sender} sent echo for round {round} (being at round {current_round})")]
+    UnexpectedEcho {
+        round: u16,
+        current_round: u16,
+        sender: u16,
+    },
+    /// Party sent two broadcast messages at the same round
+    #[error("party {sender} sent two broadcast messages at round {round}")]
+    DuplicatedMessage { round: u16, sender: u16 },
+    /// Party sent two echo messages for the same round
+    #[error("party {sender} sent two echo messages for round {round}")]
+    DuplicatedEcho { round: u16, sender: u16 },
+    /// Echo doesn't contain a hash for every party
+    #[error("echo of party {sender} for round {round} is malformed")]
+    MalformedEcho { round: u16, sender: u16 },
+    /// Couldn't serialize broadcast message
+    #[error("serialize message: {0}")]
+    Serialize(String),
+}
+
+impl<E: IsCritical> IsCritical for Error<E> {
+    fn is_critical(&self) -> bool {
+        match self {
+            Error::Inner(e) => e.is_critical(),
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use curv::elliptic::curves::secp256_k1::Secp256k1;
+    use curv::BigInt;
+    use round_based::dev::Simulation;
+
+    use super::*;
+    use crate::protocols::multi_party_ecdsa::gg_2020::party_i::verify;
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::{
+        Keygen, LocalKey, ProtocolMessage,
+    };
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::sign::{
+        OfflineStage, SignManual,
+    };
+
+    fn keygen_with_echo(t: u16, n: u16) -> Vec<LocalKey<Secp256k1>> {
+        let mut simulation = Simulation::new();
+        for i in 1..=n {
+            simulation.add_party(EchoBroadcast::new(Keygen::new(i, t, n).unwrap()).unwrap());
+        }
+        simulation.run().unwrap()
+    }
+
+    #[test]
+    fn keygen_and_signing_with_echo_broadcast() {
+        let local_keys = keygen_with_echo(1, 3);
+        let s_l = vec![1, 3];
+
+        let mut simulation = Simulation::new();
+        for (i, &keygen_i) in (1..).zip(&s_l) {
+            let offline = OfflineStage::new(
+                i,
+                s_l.clone(),
+                local_keys[usize::from(keygen_i - 1)].clone(),
+            );
+            simulation.add_party(EchoBroadcast::new(offline.unwrap()).unwrap());
+        }
+        let completed = simulation.run().unwrap();
+
+        let message = BigInt::from(7);
+        let (parties, partial_sigs): (Vec<_>, Vec<_>) = completed
+            .into_iter()
+            .map(|c| SignManual::new(message.clone(), c).unwrap())
+            .unzip();
+        let signature = parties
+            .into_iter()
+            .next()
+            .unwrap()
+            .complete(&partial_sigs[1..])
+            .unwrap();
+        verify(&signature, &local_keys[0].public_key(), &message).unwrap();
+    }
+
+    #[test]
+    fn round_tags_are_checked_against_own_round() {
+        let (t, n) = (1, 3);
+        let mut party = EchoBroadcast::new(Keygen::<Secp256k1>::new(1, t, n).unwrap()).unwrap();
+        let mut other = Keygen::<Secp256k1>::new(2, t, n).unwrap();
+        other.proceed().unwrap();
+        let body = other.message_queue().remove(0).body;
+
+        // Party is at round 0, so messages of round 1 may already come, but not of round 2
+        let msg = |body| Msg {
+            sender: 2,
+            receiver: None,
+            body,
+        };
+        assert!(matches!(
+            party.handle_incoming(msg(EchoMessage::Protocol {
+                round: 2,
+                body: body.clone(),
+            })),
+            Err(Error::UnexpectedRound {
+                round: 2,
+                current_round: 0,
+                sender: 2,
+            })
+        ));
+        party
+            .handle_incoming(msg(EchoMessage::Protocol { round: 1, body }))
+            .unwrap();
+
+        // Nobody can echo round 1 before party sent its round 1 message
+        assert!(matches!(
+            party.handle_incoming(msg(EchoMessage::Echo {
+                round: 1,
+                hashes: vec![[0u8; 32]; 3],
+            })),
+            Err(Error::UnexpectedEcho {
+                round: 1,
+                current_round: 0,
+                sender: 2,
+            })
+        ));
+    }
+
+    #[test]
+    fn equivocation_is_detected() {
+        let (t, n) = (1, 3);
+        let mut parties = (1..=n)
+            .map(|i| EchoBroadcast::new(Keygen::new(i, t, n).unwrap()).unwrap())
+            .collect::<Vec<_>>();
+
+        // Another round 1 message of party 1 that it's going to send to party 3
+        let mut other_keygen = Keygen::new(1, t, n).unwrap();
+        other_keygen.proceed().unwrap();
+        let other_msg: Msg<ProtocolMessage> = other_keygen.message_queue().remove(0);
+
+        let mut results = vec![Ok(()); usize::from(n)];
+        loop {
+            let mut progressed = false;
+            for party in &mut parties {
+                if party.wants_to_proceed() {
+                    let i = usize::from(party.party_ind() - 1);
+                    results[i] = results[i]
+                        .clone()
+                        .and(party.proceed().map_err(|e| e.to_string()));
+                    progressed = true;
+                }
+            }
+            let msgs = parties
+                .iter_mut()
+                .flat_map(|p| std::mem::take(p.message_queue()))
+                .collect::<Vec<_>>();
+            for msg in msgs {
+                for party in &mut parties {
+                    let j = party.party_ind();
+                    if j == msg.sender || msg.receiver.map(|r| r != j).unwrap_or(false) {
+                        continue;
+                    }
+                    let mut msg = msg.clone();
+                    if let (1, 3, EchoMessage::Protocol { round: 1, .. }) =
+                        (msg.sender, j, &msg.body)
+                    {
+                        msg.body = EchoMessage::Protocol {
+                            round: 1,
+                            body: other_msg.body.clone(),
+                        };
+                    }
+                    let i = usize::from(j - 1);
+                    if results[i].is_ok() {
+                        results[i] = party.handle_incoming(msg).map_err(|e| e.to_string());
+                    }
+                    progressed = true;
+                }
+            }
+            if !progressed {
+                break;
+            }
+        }
+
+        assert!(parties.iter().all(|p| p.current_round() == 1));
+        for result in &results[1..] {
+            let err = result.clone().unwrap_err();
+            assert!(err.starts_with("party 1 equivocated at round 1"), "{}", err);
+        }
+        assert!(results[0].is_err());
+    }
+}
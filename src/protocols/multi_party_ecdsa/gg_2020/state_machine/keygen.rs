@@ -6,7 +6,7 @@ use std::time::Duration;
 
 use curv::cryptographic_primitives::proofs::sigma_dlog::DLogProof;
 use curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
-use curv::elliptic::curves::{secp256_k1::Secp256k1, Scalar};
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Curve, Scalar};
 use round_based::containers::{
     push::{Push, PushExt},
     *,
@@ -16,11 +16,17 @@ use serde::{Deserialize, Serialize};
 use sha2::Sha256;
 use thiserror::Error;
 
+use crate::identity::Roster;
 use crate::protocols::multi_party_ecdsa::gg_2020;
 
+use super::checkpoint::{self, Checkpoint, CheckpointKey, Journal, SentLog, SentLogStore};
+
+mod derivation;
 mod rounds;
 
+pub use derivation::DeriveError;
 use private::InternalError;
+use rounds::{ChainCodeCommitment, ChainCodeDecommitment, RosterCommitment};
 pub use rounds::{LocalKey, ProceedError};
 use rounds::{Round0, Round1, Round2, Round3, Round4};
 
@@ -28,22 +34,45 @@ use rounds::{Round0, Round1, Round2, Round3, Round4};
 ///
 /// Successfully completed keygen protocol produces [LocalKey] that can be used in further
 /// [signing](super::sign) protocol.
-pub struct Keygen {
-    round: R,
-
-    msgs1: Option<Store<BroadcastMsgs<gg_2020::party_i::KeyGenBroadcastMessage1>>>,
-    msgs2: Option<Store<BroadcastMsgs<gg_2020::party_i::KeyGenDecommitMessage1>>>,
-    msgs3: Option<Store<P2PMsgs<(VerifiableSS<Secp256k1>, Scalar<Secp256k1>)>>>,
-    msgs4: Option<Store<BroadcastMsgs<DLogProof<Secp256k1, Sha256>>>>,
-
-    msgs_queue: Vec<Msg<ProtocolMessage>>,
+///
+/// Keygen is generic over the curve and defaults to secp256k1: [Keygen::new] constructs
+/// secp256k1 keygen, and [`Keygen::<Secp256r1>::new_on_curve`](Keygen::new_on_curve) generates
+/// P-256 keys, e.g. for interoperability with WebAuthn or cloud KMS. BIP32
+/// [derivation](LocalKey::derive_child) is only available on secp256k1.
+pub struct Keygen<E: Curve = Secp256k1> {
+    round: R<E>,
+
+    msgs1: Option<
+        Store<
+            BroadcastMsgs<(
+                gg_2020::party_i::KeyGenBroadcastMessage1,
+                ChainCodeCommitment,
+                RosterCommitment,
+            )>,
+        >,
+    >,
+    msgs2: Option<
+        Store<
+            BroadcastMsgs<(
+                gg_2020::party_i::KeyGenDecommitMessage1<E>,
+                ChainCodeDecommitment,
+            )>,
+        >,
+    >,
+    msgs3: Option<Store<P2PMsgs<(VerifiableSS<E>, Scalar<E>)>>>,
+    msgs4: Option<Store<BroadcastMsgs<DLogProof<E, Sha256>>>>,
+
+    msgs_queue: Vec<Msg<ProtocolMessage<E>>>,
+    /// Messages pushed to stores of rounds which didn't proceed yet
+    received: Vec<Msg<ProtocolMessage<E>>>,
+    sent: Journal,
 
     party_i: u16,
     party_n: u16,
 }
 
-impl Keygen {
-    /// Constructs a party of keygen protocol
+impl Keygen<Secp256k1> {
+    /// Constructs a party of keygen protocol over secp256k1
     ///
     /// Takes party index `i` (in range `[1; n]`), threshold value `t`, and total number of
     /// parties `n`. Party index identifies this party in the protocol, so it must be guaranteed
@@ -54,6 +83,37 @@ impl Keygen {
     /// * `t` is not in range `[1; n-1]`, returns [Error::InvalidThreshold]
     /// * `i` is not in range `[1; n]`, returns [Error::InvalidPartyIndex]
     pub fn new(i: u16, t: u16, n: u16) -> Result<Self> {
+        Self::new_on_curve(i, t, n)
+    }
+}
+
+impl<E: Curve> Keygen<E> {
+    /// Constructs a party of keygen protocol over curve `E`
+    ///
+    /// Takes the same arguments and returns the same errors as [Keygen::new].
+    pub fn new_on_curve(i: u16, t: u16, n: u16) -> Result<Self> {
+        Self::construct(i, t, n, None)
+    }
+
+    /// Constructs a party of keygen protocol run by parties with known identities
+    ///
+    /// Party `j` is identified by `roster.public_key(j)`. Roster itself is not exchanged by the
+    /// protocol (protocol messages are expected to be [signed and verified](crate::identity)
+    /// against it), but every party commits to [roster hash](Roster::hash) in round 1, and keygen
+    /// is aborted with [ProceedError::Round1VerifyRoster] if parties hold different rosters.
+    /// Roster is stored in resulting [LocalKey], so subsequent signing can be checked against the
+    /// same committee.
+    ///
+    /// Returns the same errors as [Keygen::new], and [Error::RosterSizeMismatch] if roster
+    /// doesn't contain exactly `n` parties.
+    pub fn with_roster(i: u16, t: u16, n: u16, roster: Roster) -> Result<Self> {
+        if roster.len() != usize::from(n) {
+            return Err(Error::RosterSizeMismatch);
+        }
+        Self::construct(i, t, n, Some(roster))
+    }
+
+    fn construct(i: u16, t: u16, n: u16, roster: Option<Roster>) -> Result<Self> {
         if n < 2 {
             return Err(Error::TooFewParties);
         }
@@ -64,14 +124,21 @@ impl Keygen {
             return Err(Error::InvalidPartyIndex);
         }
         let mut state = Self {
-            round: R::Round0(Round0 { party_i: i, t, n }),
-
-            msgs1: Some(Round1::expects_messages(i, n)),
+            round: R::Round0(Round0 {
+                party_i: i,
+                t,
+                n,
+                roster,
+            }),
+
+            msgs1: Some(Round1::<E>::expects_messages(i, n)),
             msgs2: Some(Round2::expects_messages(i, n)),
             msgs3: Some(Round3::expects_messages(i, n)),
             msgs4: Some(Round4::expects_messages(i, n)),
 
             msgs_queue: vec![],
+            received: vec![],
+            sent: Journal::default(),
 
             party_i: i,
             party_n: n,
@@ -81,22 +148,128 @@ impl Keygen {
         Ok(state)
     }
 
+    /// Persists [sent log](Keygen::sent_log) to the `store`
+    ///
+    /// Log is saved right away, and then every time keygen enters a round, before messages of the
+    /// round are put into the [message queue](StateMachine::message_queue). Call it before taking
+    /// any messages from the queue. Required for [checkpointing](Keygen::checkpoint).
+    pub fn persist_sent_log(&mut self, store: impl SentLogStore + 'static) -> Result<()> {
+        self.sent
+            .persist(Box::new(store))
+            .map_err(Error::Checkpoint)
+    }
+
+    /// Saves keygen state as an encrypted [checkpoint](super::checkpoint)
+    ///
+    /// Keygen can be checkpointed at any point, including in the middle of a round: received
+    /// messages that weren't consumed by a round yet are saved as well. It's fine to carry on with
+    /// this instance after taking a checkpoint.
+    ///
+    /// Returns [checkpoint::Error::SentLogNotPersisted] unless the sent log is
+    /// [persisted](Keygen::persist_sent_log).
+    pub fn checkpoint(&self, key: &CheckpointKey) -> Result<Checkpoint> {
+        self.sent.ensure_persisted().map_err(Error::Checkpoint)?;
+        let state = KeygenCheckpoint {
+            round: &self.round,
+            received: &self.received,
+            msgs_queue: &self.msgs_queue,
+            sent: self.sent.log(),
+            party_i: self.party_i,
+            party_n: self.party_n,
+        };
+        Checkpoint::seal(key, CHECKPOINT_PROTOCOL, &state).map_err(Error::Checkpoint)
+    }
+
+    /// Restores keygen from a [checkpoint](Keygen::checkpoint)
+    ///
+    /// `store` is the [sent log store](Keygen::persist_sent_log) of the party, it might be newer
+    /// than the checkpoint. Restored keygen keeps persisting the log to it, and refuses to emit
+    /// messages that differ from the ones logged in the store or in the checkpoint, see
+    /// [Error::Checkpoint].
+    pub fn restore(
+        checkpoint: &Checkpoint,
+        key: &CheckpointKey,
+        store: impl SentLogStore + 'static,
+    ) -> Result<Self> {
+        let KeygenCheckpoint {
+            round,
+            received,
+            msgs_queue,
+            sent,
+            party_i,
+            party_n,
+        }: KeygenCheckpoint<R<E>, _, SentLog> = checkpoint
+            .open(key, CHECKPOINT_PROTOCOL)
+            .map_err(Error::Checkpoint)?;
+
+        let mut state = Self::from_parts(round, msgs_queue, party_i, party_n);
+        state.sent = Journal::restore(&sent, Box::new(store)).map_err(Error::Checkpoint)?;
+        for msg in received {
+            state.handle_incoming(msg)?;
+        }
+        Ok(state)
+    }
+
+    /// Digests of messages emitted by this party at every round
+    pub fn sent_log(&self) -> &SentLog {
+        self.sent.log()
+    }
+
+    fn from_parts(round: R<E>, msgs_queue: Vec<Msg<ProtocolMessage<E>>>, i: u16, n: u16) -> Self {
+        let mut state = Self {
+            round,
+            msgs1: None,
+            msgs2: None,
+            msgs3: None,
+            msgs4: None,
+            msgs_queue,
+            received: vec![],
+            sent: Journal::default(),
+            party_i: i,
+            party_n: n,
+        };
+        let round = state.current_round();
+        state.msgs1 = Some(Round1::<E>::expects_messages(i, n)).filter(|_| round <= 1);
+        state.msgs2 = Some(Round2::expects_messages(i, n)).filter(|_| round <= 2);
+        state.msgs3 = Some(Round3::expects_messages(i, n)).filter(|_| round <= 3);
+        state.msgs4 = Some(Round4::expects_messages(i, n)).filter(|_| round <= 4);
+        state
+    }
+
     fn gmap_queue<'a, T, F>(&'a mut self, mut f: F) -> impl Push<Msg<T>> + 'a
     where
-        F: FnMut(T) -> M + 'a,
+        F: FnMut(T) -> M<E> + 'a,
     {
         (&mut self.msgs_queue).gmap(move |m: Msg<T>| m.map_body(|m| ProtocolMessage(f(m))))
     }
 
+    /// Logs messages emitted on entering current round, drops received messages of rounds that
+    /// already proceeded
+    ///
+    /// Emitted messages are taken back if they differ from the ones sent at this round before.
+    fn round_proceeded(&mut self, queued: usize) -> Result<()> {
+        let round = self.current_round();
+        self.received.retain(|msg| msg.body.round() >= round);
+        if self.msgs_queue.len() == queued {
+            return Ok(());
+        }
+        if let Err(err) = self.sent.record(round, &self.msgs_queue[queued..]) {
+            self.msgs_queue.truncate(queued);
+            return Err(Error::Checkpoint(err));
+        }
+        Ok(())
+    }
+
     /// Proceeds round state if it received enough messages and if it's cheap to compute or
     /// `may_block == true`
     fn proceed_round(&mut self, may_block: bool) -> Result<()> {
+        let queued = self.msgs_queue.len();
         let store1_wants_more = self.msgs1.as_ref().map(|s| s.wants_more()).unwrap_or(false);
         let store2_wants_more = self.msgs2.as_ref().map(|s| s.wants_more()).unwrap_or(false);
         let store3_wants_more = self.msgs3.as_ref().map(|s| s.wants_more()).unwrap_or(false);
         let store4_wants_more = self.msgs4.as_ref().map(|s| s.wants_more()).unwrap_or(false);
 
-        let next_state: R;
+        let next_state: R<E>;
         let try_again: bool = match replace(&mut self.round, R::Gone) {
             R::Round0(round) if !round.is_expensive() || may_block => {
                 next_state = round
@@ -177,6 +350,7 @@ impl Keygen {
 
         self.round = next_state;
         if try_again {
+            self.round_proceeded(queued)?;
             self.proceed_round(may_block)
         } else {
             Ok(())
@@ -184,13 +358,14 @@ impl Keygen {
     }
 }
 
-impl StateMachine for Keygen {
-    type MessageBody = ProtocolMessage;
+impl<E: Curve> StateMachine for Keygen<E> {
+    type MessageBody = ProtocolMessage<E>;
     type Err = Error;
-    type Output = LocalKey<Secp256k1>;
+    type Output = LocalKey<E>;
 
     fn handle_incoming(&mut self, msg: Msg<Self::MessageBody>) -> Result<()> {
         let current_round = self.current_round();
+        let received = msg.clone();
 
         match msg.body {
             ProtocolMessage(M::Round1(m)) => {
@@ -208,7 +383,6 @@ impl StateMachine for Keygen {
                         body: m,
                     })
                     .map_err(Error::HandleMessage)?;
-                self.proceed_round(false)
             }
             ProtocolMessage(M::Round2(m)) => {
                 let store = self
@@ -225,7 +399,6 @@ impl StateMachine for Keygen {
                         body: m,
                     })
                     .map_err(Error::HandleMessage)?;
-                self.proceed_round(false)
             }
             ProtocolMessage(M::Round3(m)) => {
                 let store = self
@@ -242,7 +415,6 @@ impl StateMachine for Keygen {
                         body: m,
                     })
                     .map_err(Error::HandleMessage)?;
-                self.proceed_round(false)
             }
             ProtocolMessage(M::Round4(m)) => {
                 let store = self
@@ -259,9 +431,10 @@ impl StateMachine for Keygen {
                         body: m,
                     })
                     .map_err(Error::HandleMessage)?;
-                self.proceed_round(false)
             }
         }
+        self.received.push(received);
+        self.proceed_round(false)
     }
 
     fn message_queue(&mut self) -> &mut Vec<Msg<Self::MessageBody>> {
@@ -337,7 +510,7 @@ impl StateMachine for Keygen {
     }
 }
 
-impl super::traits::RoundBlame for Keygen {
+impl<E: Curve> super::traits::RoundBlame for Keygen<E> {
     /// Returns number of unwilling parties and a vector of their party indexes.
     fn round_blame(&self) -> (u16, Vec<u16>) {
         let store1_blame = self.msgs1.as_ref().map(|s| s.blame()).unwrap_or_default();
@@ -357,7 +530,7 @@ impl super::traits::RoundBlame for Keygen {
     }
 }
 
-impl fmt::Debug for Keygen {
+impl<E: Curve> fmt::Debug for Keygen<E> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let current_round = match &self.round {
             R::Round0(_) => "0",
@@ -397,15 +570,36 @@ impl fmt::Debug for Keygen {
     }
 }
 
+/// Keygen state saved in a [Checkpoint]
+///
+/// Borrows the state when it's sealed, and owns it when it's opened.
+#[derive(Serialize, Deserialize)]
+struct KeygenCheckpoint<R, Q, S> {
+    round: R,
+    received: Q,
+    msgs_queue: Q,
+    sent: S,
+    party_i: u16,
+    party_n: u16,
+}
+
+const CHECKPOINT_PROTOCOL: &str = "gg20/keygen";
+
 // Rounds
 
-enum R {
+/// Round state
+///
+/// Holds party's secrets (e.g. Paillier keys) in plain text, so it's only ever serialized into an
+/// encrypted [Checkpoint], and never cloned.
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+enum R<E: Curve> {
     Round0(Round0),
-    Round1(Round1),
-    Round2(Round2),
-    Round3(Round3),
-    Round4(Round4),
-    Final(LocalKey<Secp256k1>),
+    Round1(Round1<E>),
+    Round2(Round2<E>),
+    Round3(Round3<E>),
+    Round4(Round4<E>),
+    Final(LocalKey<E>),
     Gone,
 }
 
@@ -415,14 +609,38 @@ enum R {
 ///
 /// Hides actual messages structure so it could be changed without breaking semver policy.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ProtocolMessage(M);
+#[serde(bound = "")]
+pub struct ProtocolMessage<E: Curve = Secp256k1>(M<E>);
+
+impl<E: Curve> ProtocolMessage<E> {
+    fn round(&self) -> u16 {
+        match self.0 {
+            M::Round1(_) => 1,
+            M::Round2(_) => 2,
+            M::Round3(_) => 3,
+            M::Round4(_) => 4,
+        }
+    }
+}
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-enum M {
-    Round1(gg_2020::party_i::KeyGenBroadcastMessage1),
-    Round2(gg_2020::party_i::KeyGenDecommitMessage1),
-    Round3((VerifiableSS<Secp256k1>, Scalar<Secp256k1>)),
-    Round4(DLogProof<Secp256k1, Sha256>),
+#[serde(bound = "")]
+enum M<E: Curve> {
+    Round1(
+        (
+            gg_2020::party_i::KeyGenBroadcastMessage1,
+            ChainCodeCommitment,
+            RosterCommitment,
+        ),
+    ),
+    Round2(
+        (
+            gg_2020::party_i::KeyGenDecommitMessage1<E>,
+            ChainCodeDecommitment,
+        ),
+    ),
+    Round3((VerifiableSS<E>, Scalar<E>)),
+    Round4(DLogProof<E, Sha256>),
 }
 
 // Error
@@ -446,6 +664,9 @@ pub enum Error {
     /// Party index `i` is not in range `[1; n]`
     #[error("party index is not in range [1; n]")]
     InvalidPartyIndex,
+    /// Roster given to [Keygen::with_roster] doesn't have exactly `n` parties
+    #[error("roster size doesn't match number of parties")]
+    RosterSizeMismatch,
 
     /// Received message didn't pass pre-validation
     #[error("received message didn't pass pre-validation: {0}")]
@@ -458,6 +679,10 @@ pub enum Error {
     /// [Keygen::pick_output] called twice
     #[error("pick_output called twice")]
     DoublePickOutput,
+    /// Checkpoint can't be saved or restored, or restored party was about to send messages
+    /// different from the ones it sent before ([checkpoint::Error::Equivocation])
+    #[error("checkpoint: {0}")]
+    Checkpoint(#[source] checkpoint::Error),
 
     /// Some internal assertions were failed, which is a bug
     #[doc(hidden)]
@@ -496,11 +721,15 @@ pub mod test {
     use super::*;
 
     pub fn simulate_keygen(t: u16, n: u16) -> Vec<LocalKey<Secp256k1>> {
+        simulate_keygen_on_curve(t, n)
+    }
+
+    pub fn simulate_keygen_on_curve<E: Curve>(t: u16, n: u16) -> Vec<LocalKey<E>> {
         let mut simulation = Simulation::new();
         simulation.enable_benchmarks(true);
 
         for i in 1..=n {
-            simulation.add_party(Keygen::new(i, t, n).unwrap());
+            simulation.add_party(Keygen::new_on_curve(i, t, n).unwrap());
         }
 
         let keys = simulation.run().unwrap();
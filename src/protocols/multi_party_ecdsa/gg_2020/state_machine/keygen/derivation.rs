@@ -0,0 +1,206 @@
+//! BIP32 non-hardened child key derivation
+
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use hmac::{Hmac, Mac, NewMac};
+use sha2::Sha512;
+use thiserror::Error;
+
+use super::LocalKey;
+
+/// Index of the first hardened child
+const HARDENED_OFFSET: u32 = 1 << 31;
+
+impl LocalKey<Secp256k1> {
+    /// Derives child key at non-hardened BIP32 `path` (e.g. `m/0/1` is `&[0, 1]`)
+    ///
+    /// Derivation is local, parties don't need to interact. BIP32 public derivation gives
+    /// a tweak `t` that only depends on chain code and public key, so every party shifts its
+    /// share by the same `t`: child public key is `y + tG`, every public share in `pk_vec` is
+    /// shifted by `tG`. Shares are points of a polynomial, shifting all of them by `t` shifts
+    /// the shared secret by `t` as well, so derived key can be used in
+    /// [OfflineStage](crate::protocols::multi_party_ecdsa::gg_2020::state_machine::sign::OfflineStage)
+    /// as usual. Child chain code is stored in derived key, so it can be derived further.
+    ///
+    /// `vss_scheme` is kept as is — signing only needs its parameters.
+    ///
+    /// Returns error if key has no chain code, if `path` contains hardened index, or in the
+    /// (negligibly unlikely) case BIP32 declares the child invalid.
+    pub fn derive_child(&self, path: &[u32]) -> Result<LocalKey<Secp256k1>, DeriveError> {
+        let chain_code = self.chain_code.ok_or(DeriveError::MissingChainCode)?;
+        let (tweak, chain_code) = derive_tweak(&self.y_sum_s, chain_code, path)?;
+        let tweak_point = Point::generator() * &tweak;
+
+        let mut child = self.clone();
+        child.y_sum_s = &self.y_sum_s + &tweak_point;
+        child.keys_linear.y = &self.keys_linear.y + &tweak_point;
+        child.keys_linear.x_i = &self.keys_linear.x_i + &tweak;
+        child.pk_vec = self.pk_vec.iter().map(|pk| pk + &tweak_point).collect();
+        child.chain_code = Some(chain_code);
+        Ok(child)
+    }
+}
+
+/// Computes accumulated tweak and chain code of child at `path` relative to `public_key`
+fn derive_tweak(
+    public_key: &Point<Secp256k1>,
+    mut chain_code: [u8; 32],
+    path: &[u32],
+) -> Result<(Scalar<Secp256k1>, [u8; 32]), DeriveError> {
+    let mut public_key = public_key.clone();
+    let mut tweak = Scalar::<Secp256k1>::zero();
+    for &index in path {
+        let (shift, child_chain_code) = ckd_pub(&public_key, &chain_code, index)?;
+        public_key = public_key + Point::generator() * &shift;
+        if public_key.is_zero() {
+            return Err(DeriveError::InvalidChild { index });
+        }
+        tweak = tweak + shift;
+        chain_code = child_chain_code;
+    }
+    Ok((tweak, chain_code))
+}
+
+/// BIP32 `CKDpub`: returns `IL` (child key is `K + IL·G`) and child chain code `IR`
+fn ckd_pub(
+    public_key: &Point<Secp256k1>,
+    chain_code: &[u8; 32],
+    index: u32,
+) -> Result<(Scalar<Secp256k1>, [u8; 32]), DeriveError> {
+    if index >= HARDENED_OFFSET {
+        return Err(DeriveError::HardenedIndex { index });
+    }
+    let mut hmac =
+        Hmac::<Sha512>::new_from_slice(chain_code).expect("HMAC must take a key of any length");
+    hmac.update(&public_key.to_bytes(true));
+    hmac.update(&index.to_be_bytes());
+    let i = hmac.finalize().into_bytes();
+
+    let shift = Scalar::<Secp256k1>::from_bytes(&i[..32])
+        .map_err(|_| DeriveError::InvalidChild { index })?;
+    let mut child_chain_code = [0u8; 32];
+    child_chain_code.copy_from_slice(&i[32..]);
+    Ok((shift, child_chain_code))
+}
+
+/// Error of [child key derivation](LocalKey::derive_child)
+#[derive(Debug, Error)]
+pub enum DeriveError {
+    /// Key was generated before chain code was introduced
+    #[error("local key doesn't have a chain code")]
+    MissingChainCode,
+    /// Hardened derivation requires the secret key which is not known to any party
+    #[error("hardened index {index} can't be derived from a threshold key")]
+    HardenedIndex { index: u32 },
+    /// BIP32 declares the child invalid, next index should be used instead
+    #[error("child {index} is invalid")]
+    InvalidChild { index: u32 },
+}
+
+#[cfg(test)]
+mod test {
+    use curv::arithmetic::traits::*;
+    use curv::BigInt;
+    use round_based::dev::Simulation;
+
+    use super::*;
+    use crate::protocols::multi_party_ecdsa::gg_2020::party_i::verify;
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::sign::{
+        OfflineStage, SignManual,
+    };
+
+    fn from_hex<const N: usize>(hex: &str) -> [u8; N] {
+        BigInt::from_hex(hex).unwrap().to_bytes_array().unwrap()
+    }
+
+    /// BIP32 test vector 2: `m` -> `m/0`
+    #[test]
+    fn ckd_pub_matches_bip32_test_vector() {
+        let public_key = Point::<Secp256k1>::from_bytes(&from_hex::<33>(
+            "03cbcaa9c98c877a26977d00825c956a238e8dddfbd322cce4f74b0b5bd6ace4a7",
+        ))
+        .unwrap();
+        let chain_code =
+            from_hex("60499f801b896d83179a4374aeb7822aaeaceaa0db1f85ee3e904c4defbd9689");
+
+        let (tweak, child_chain_code) = derive_tweak(&public_key, chain_code, &[0]).unwrap();
+        let child_public_key = public_key + Point::generator() * tweak;
+
+        assert_eq!(
+            child_public_key.to_bytes(true).to_vec(),
+            from_hex::<33>("02fc9e5af0ac8d9b3cecfe2a888e2117ba3d089d8585886c9c826b6b22a98d12ea")
+                .to_vec()
+        );
+        assert_eq!(
+            child_chain_code,
+            from_hex::<32>("f0909affaa7ee7abe5dd4e100598d4dc53cd709d5a5c2cac40e7412f232f7c9c")
+        );
+    }
+
+    #[test]
+    fn derived_key_signs() {
+        let local_keys = simulate_keygen(1, 3);
+        assert!(local_keys
+            .iter()
+            .all(|k| k.chain_code.is_some() && k.chain_code == local_keys[0].chain_code));
+
+        let path = [0, 1, 7];
+        let children = local_keys
+            .iter()
+            .map(|k| k.derive_child(&path).unwrap())
+            .collect::<Vec<_>>();
+        let public_key = children[0].public_key();
+        assert!(children.iter().all(|c| c.public_key() == public_key));
+        assert_ne!(public_key, local_keys[0].public_key());
+
+        // Deriving step by step gives the same child
+        let stepwise = local_keys[1]
+            .derive_child(&path[..1])
+            .unwrap()
+            .derive_child(&path[1..])
+            .unwrap();
+        assert_eq!(stepwise.public_key(), public_key);
+        assert_eq!(stepwise.chain_code, children[1].chain_code);
+
+        let s_l = [1u16, 3];
+        let mut simulation = Simulation::new();
+        for (i, &keygen_i) in (1..).zip(&s_l) {
+            simulation.add_party(
+                OfflineStage::new(i, s_l.to_vec(), children[usize::from(keygen_i - 1)].clone())
+                    .unwrap(),
+            );
+        }
+        let offline = simulation.run().unwrap();
+
+        let message = BigInt::from(42);
+        let (parties, partial_sigs): (Vec<_>, Vec<_>) = offline
+            .into_iter()
+            .map(|o| SignManual::new(message.clone(), o).unwrap())
+            .unzip();
+        let signature = parties
+            .into_iter()
+            .next()
+            .unwrap()
+            .complete(&partial_sigs[1..])
+            .unwrap();
+        verify(&signature, &public_key, &message).unwrap();
+    }
+
+    #[test]
+    fn hardened_and_legacy_keys_are_rejected() {
+        let local_key = simulate_keygen(1, 2).remove(0);
+        assert!(matches!(
+            local_key.derive_child(&[0, HARDENED_OFFSET]),
+            Err(DeriveError::HardenedIndex { .. })
+        ));
+
+        let legacy = LocalKey {
+            chain_code: None,
+            ..local_key
+        };
+        assert!(matches!(
+            legacy.derive_child(&[0]),
+            Err(DeriveError::MissingChainCode)
+        ));
+    }
+}
@@ -1,6 +1,10 @@
+use curv::arithmetic::traits::*;
+use curv::cryptographic_primitives::commitments::hash_commitment::HashCommitment;
+use curv::cryptographic_primitives::commitments::traits::Commitment;
 use curv::cryptographic_primitives::proofs::sigma_dlog::DLogProof;
 use curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
 use curv::elliptic::curves::{secp256_k1::Secp256k1, Curve, Point, Scalar};
+use curv::BigInt;
 use sha2::Sha256;
 
 use serde::{Deserialize, Serialize};
@@ -12,38 +16,92 @@ use round_based::containers::{self, BroadcastMsgs, P2PMsgs, Store};
 use round_based::Msg;
 use zk_paillier::zkproofs::DLogStatement;
 
+use crate::identity::Roster;
 use crate::protocols::multi_party_ecdsa::gg_2020::party_i::{
     KeyGenBroadcastMessage1, KeyGenDecommitMessage1, Keys,
 };
 use crate::protocols::multi_party_ecdsa::gg_2020::{self, ErrorType};
 
+const SECURITY: usize = 256;
+
+/// Commitment to party's share of chain code, sent along with round 1 message
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChainCodeCommitment(pub BigInt);
+
+/// Party's share of chain code, revealed along with round 2 message
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChainCodeDecommitment {
+    pub share: [u8; 32],
+    pub blind_factor: BigInt,
+}
+
+/// Hash of the roster that party was [constructed](super::Keygen::with_roster) with, sent along
+/// with round 1 message
+///
+/// All parties must hold the same roster (or none of them), otherwise keygen is aborted.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RosterCommitment(pub Option<[u8; 32]>);
+
+impl ChainCodeDecommitment {
+    fn commit(&self) -> ChainCodeCommitment {
+        ChainCodeCommitment(
+            HashCommitment::<Sha256>::create_commitment_with_user_defined_randomness(
+                &BigInt::from_bytes(&self.share),
+                &self.blind_factor,
+            ),
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct Round0 {
     pub party_i: u16,
     pub t: u16,
     pub n: u16,
+    pub roster: Option<Roster>,
 }
 
 impl Round0 {
-    pub fn proceed<O>(self, mut output: O) -> Result<Round1>
+    pub fn proceed<E: Curve, O>(self, mut output: O) -> Result<Round1<E>>
     where
-        O: Push<Msg<gg_2020::party_i::KeyGenBroadcastMessage1>>,
+        O: Push<
+            Msg<(
+                gg_2020::party_i::KeyGenBroadcastMessage1,
+                ChainCodeCommitment,
+                RosterCommitment,
+            )>,
+        >,
     {
         let party_keys = Keys::create(self.party_i as usize);
         let (bc1, decom1) =
             party_keys.phase1_broadcast_phase3_proof_of_correct_key_proof_of_correct_h1h2();
 
+        // Chain code is obtained by coin flipping: every party commits to random share here, and
+        // reveals it in the next round
+        let chain_code_decom = ChainCodeDecommitment {
+            share: BigInt::sample(256)
+                .to_bytes_array()
+                .expect("sampled number fits into 32 bytes"),
+            blind_factor: BigInt::sample(SECURITY),
+        };
+        let chain_code_com = chain_code_decom.commit();
+        let roster_com = RosterCommitment(self.roster.as_ref().map(Roster::hash));
+
         output.push(Msg {
             sender: self.party_i,
             receiver: None,
-            body: bc1.clone(),
+            body: (bc1.clone(), chain_code_com.clone(), roster_com),
         });
         Ok(Round1 {
             keys: party_keys,
             bc1,
             decom1,
+            chain_code_com,
+            chain_code_decom,
             party_i: self.party_i,
             t: self.t,
             n: self.n,
+            roster: self.roster,
         })
     }
     pub fn is_expensive(&self) -> bool {
@@ -51,71 +109,148 @@ impl Round0 {
     }
 }
 
-pub struct Round1 {
-    keys: Keys,
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Round1<E: Curve> {
+    keys: Keys<E>,
     bc1: KeyGenBroadcastMessage1,
-    decom1: KeyGenDecommitMessage1,
+    decom1: KeyGenDecommitMessage1<E>,
+    chain_code_com: ChainCodeCommitment,
+    chain_code_decom: ChainCodeDecommitment,
     party_i: u16,
     t: u16,
     n: u16,
+    roster: Option<Roster>,
 }
 
-impl Round1 {
+impl<E: Curve> Round1<E> {
     pub fn proceed<O>(
         self,
-        input: BroadcastMsgs<KeyGenBroadcastMessage1>,
+        input: BroadcastMsgs<(
+            KeyGenBroadcastMessage1,
+            ChainCodeCommitment,
+            RosterCommitment,
+        )>,
         mut output: O,
-    ) -> Result<Round2>
+    ) -> Result<Round2<E>>
     where
-        O: Push<Msg<gg_2020::party_i::KeyGenDecommitMessage1>>,
+        O: Push<
+            Msg<(
+                gg_2020::party_i::KeyGenDecommitMessage1<E>,
+                ChainCodeDecommitment,
+            )>,
+        >,
     {
+        let roster_com = RosterCommitment(self.roster.as_ref().map(Roster::hash));
+        let mut received_comm = vec![];
+        let mut chain_code_coms = vec![];
+        let mut bad_actors = vec![];
+        for (i, (comm, chain_code_com, their_roster_com)) in input
+            .into_vec_including_me((self.bc1, self.chain_code_com, roster_com.clone()))
+            .into_iter()
+            .enumerate()
+        {
+            if their_roster_com != roster_com {
+                bad_actors.push(i);
+            }
+            received_comm.push(comm);
+            chain_code_coms.push(chain_code_com);
+        }
+        // Don't reveal anything until everyone agreed on the committee
+        if !bad_actors.is_empty() {
+            return Err(ProceedError::Round1VerifyRoster(ErrorType {
+                error_type: "roster mismatch".to_string(),
+                bad_actors,
+            }));
+        }
+
         output.push(Msg {
             sender: self.party_i,
             receiver: None,
-            body: self.decom1.clone(),
+            body: (self.decom1.clone(), self.chain_code_decom.clone()),
         });
         Ok(Round2 {
             keys: self.keys,
-            received_comm: input.into_vec_including_me(self.bc1),
+            received_comm,
             decom: self.decom1,
+            chain_code_coms,
+            chain_code_decom: self.chain_code_decom,
 
             party_i: self.party_i,
             t: self.t,
             n: self.n,
+            roster: self.roster,
         })
     }
     pub fn is_expensive(&self) -> bool {
         false
     }
-    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<KeyGenBroadcastMessage1>> {
+    pub fn expects_messages(
+        i: u16,
+        n: u16,
+    ) -> Store<
+        BroadcastMsgs<(
+            KeyGenBroadcastMessage1,
+            ChainCodeCommitment,
+            RosterCommitment,
+        )>,
+    > {
         containers::BroadcastMsgsStore::new(i, n)
     }
 }
 
-pub struct Round2 {
-    keys: gg_2020::party_i::Keys,
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Round2<E: Curve> {
+    keys: gg_2020::party_i::Keys<E>,
     received_comm: Vec<KeyGenBroadcastMessage1>,
-    decom: KeyGenDecommitMessage1,
+    decom: KeyGenDecommitMessage1<E>,
+    chain_code_coms: Vec<ChainCodeCommitment>,
+    chain_code_decom: ChainCodeDecommitment,
 
     party_i: u16,
     t: u16,
     n: u16,
+    roster: Option<Roster>,
 }
 
-impl Round2 {
+impl<E: Curve> Round2<E> {
     pub fn proceed<O>(
         self,
-        input: BroadcastMsgs<KeyGenDecommitMessage1>,
+        input: BroadcastMsgs<(KeyGenDecommitMessage1<E>, ChainCodeDecommitment)>,
         mut output: O,
-    ) -> Result<Round3>
+    ) -> Result<Round3<E>>
     where
-        O: Push<Msg<(VerifiableSS<Secp256k1>, Scalar<Secp256k1>)>>,
+        O: Push<Msg<(VerifiableSS<E>, Scalar<E>)>>,
     {
         let params = gg_2020::party_i::Parameters {
             threshold: self.t,
             share_count: self.n,
         };
-        let received_decom = input.into_vec_including_me(self.decom);
+        let (received_decom, chain_code_decoms): (Vec<_>, Vec<_>) = input
+            .into_vec_including_me((self.decom, self.chain_code_decom))
+            .into_iter()
+            .unzip();
+
+        let bad_actors = chain_code_decoms
+            .iter()
+            .zip(&self.chain_code_coms)
+            .enumerate()
+            .filter(|(_, (decom, com))| decom.commit().0 != com.0)
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        if !bad_actors.is_empty() {
+            return Err(ProceedError::Round2VerifyChainCode(ErrorType {
+                error_type: "bad chain code decommitment".to_string(),
+                bad_actors,
+            }));
+        }
+        let chain_code = chain_code_decoms.iter().fold([0u8; 32], |mut acc, decom| {
+            acc.iter_mut()
+                .zip(&decom.share)
+                .for_each(|(acc, share)| *acc ^= share);
+            acc
+        });
 
         let vss_result = self
             .keys
@@ -146,42 +281,51 @@ impl Round2 {
 
             own_vss: vss_result.0.clone(),
             own_share: vss_result.1[usize::from(self.party_i - 1)].clone(),
+            chain_code,
 
             party_i: self.party_i,
             t: self.t,
             n: self.n,
+            roster: self.roster,
         })
     }
     pub fn is_expensive(&self) -> bool {
         true
     }
-    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<KeyGenDecommitMessage1>> {
+    pub fn expects_messages(
+        i: u16,
+        n: u16,
+    ) -> Store<BroadcastMsgs<(KeyGenDecommitMessage1<E>, ChainCodeDecommitment)>> {
         containers::BroadcastMsgsStore::new(i, n)
     }
 }
 
-pub struct Round3 {
-    keys: gg_2020::party_i::Keys,
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Round3<E: Curve> {
+    keys: gg_2020::party_i::Keys<E>,
 
-    y_vec: Vec<Point<Secp256k1>>,
+    y_vec: Vec<Point<E>>,
     bc_vec: Vec<gg_2020::party_i::KeyGenBroadcastMessage1>,
 
-    own_vss: VerifiableSS<Secp256k1>,
-    own_share: Scalar<Secp256k1>,
+    own_vss: VerifiableSS<E>,
+    own_share: Scalar<E>,
+    chain_code: [u8; 32],
 
     party_i: u16,
     t: u16,
     n: u16,
+    roster: Option<Roster>,
 }
 
-impl Round3 {
+impl<E: Curve> Round3<E> {
     pub fn proceed<O>(
         self,
-        input: P2PMsgs<(VerifiableSS<Secp256k1>, Scalar<Secp256k1>)>,
+        input: P2PMsgs<(VerifiableSS<E>, Scalar<E>)>,
         mut output: O,
-    ) -> Result<Round4>
+    ) -> Result<Round4<E>>
     where
-        O: Push<Msg<DLogProof<Secp256k1, Sha256>>>,
+        O: Push<Msg<DLogProof<E, Sha256>>>,
     {
         let params = gg_2020::party_i::Parameters {
             threshold: self.t,
@@ -216,41 +360,41 @@ impl Round3 {
             shared_keys,
             own_dlog_proof: dlog_proof,
             vss_vec: vss_schemes,
+            chain_code: self.chain_code,
 
             party_i: self.party_i,
             t: self.t,
             n: self.n,
+            roster: self.roster,
         })
     }
     pub fn is_expensive(&self) -> bool {
         true
     }
-    pub fn expects_messages(
-        i: u16,
-        n: u16,
-    ) -> Store<P2PMsgs<(VerifiableSS<Secp256k1>, Scalar<Secp256k1>)>> {
+    pub fn expects_messages(i: u16, n: u16) -> Store<P2PMsgs<(VerifiableSS<E>, Scalar<E>)>> {
         containers::P2PMsgsStore::new(i, n)
     }
 }
 
-pub struct Round4 {
-    keys: gg_2020::party_i::Keys,
-    y_vec: Vec<Point<Secp256k1>>,
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Round4<E: Curve> {
+    keys: gg_2020::party_i::Keys<E>,
+    y_vec: Vec<Point<E>>,
     bc_vec: Vec<gg_2020::party_i::KeyGenBroadcastMessage1>,
-    shared_keys: gg_2020::party_i::SharedKeys,
-    own_dlog_proof: DLogProof<Secp256k1, Sha256>,
-    vss_vec: Vec<VerifiableSS<Secp256k1>>,
+    shared_keys: gg_2020::party_i::SharedKeys<E>,
+    own_dlog_proof: DLogProof<E, Sha256>,
+    vss_vec: Vec<VerifiableSS<E>>,
+    chain_code: [u8; 32],
 
     party_i: u16,
     t: u16,
     n: u16,
+    roster: Option<Roster>,
 }
 
-impl Round4 {
-    pub fn proceed(
-        self,
-        input: BroadcastMsgs<DLogProof<Secp256k1, Sha256>>,
-    ) -> Result<LocalKey<Secp256k1>> {
+impl<E: Curve> Round4<E> {
+    pub fn proceed(self, input: BroadcastMsgs<DLogProof<E, Sha256>>) -> Result<LocalKey<E>> {
         let params = gg_2020::party_i::Parameters {
             threshold: self.t,
             share_count: self.n,
@@ -266,7 +410,7 @@ impl Round4 {
         .map_err(ProceedError::Round4VerifyDLogProof)?;
         let pk_vec = (0..params.share_count as usize)
             .map(|i| dlog_proofs[i].pk.clone())
-            .collect::<Vec<Point<Secp256k1>>>();
+            .collect::<Vec<Point<E>>>();
 
         let paillier_key_vec = (0..params.share_count)
             .map(|i| self.bc_vec[i as usize].e.clone())
@@ -290,10 +434,12 @@ impl Round4 {
             h1_h2_n_tilde_vec,
 
             vss_scheme: self.vss_vec[usize::from(self.party_i - 1)].clone(),
+            chain_code: Some(self.chain_code),
 
             i: self.party_i,
             t: self.t,
             n: self.n,
+            roster: self.roster,
         };
 
         Ok(local_key)
@@ -301,29 +447,40 @@ impl Round4 {
     pub fn is_expensive(&self) -> bool {
         true
     }
-    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<DLogProof<Secp256k1, Sha256>>> {
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<DLogProof<E, Sha256>>> {
         containers::BroadcastMsgsStore::new(i, n)
     }
 }
 
 /// Local secret obtained by party after [keygen](super::Keygen) protocol is completed
 #[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct LocalKey<E: Curve> {
+#[serde(bound = "")]
+pub struct LocalKey<E: Curve = Secp256k1> {
     pub paillier_dk: paillier::DecryptionKey,
     pub pk_vec: Vec<Point<E>>,
-    pub keys_linear: gg_2020::party_i::SharedKeys,
+    pub keys_linear: gg_2020::party_i::SharedKeys<E>,
     pub paillier_key_vec: Vec<EncryptionKey>,
     pub y_sum_s: Point<E>,
     pub h1_h2_n_tilde_vec: Vec<DLogStatement>,
     pub vss_scheme: VerifiableSS<E>,
+    /// BIP32 chain code jointly generated at keygen, used in [child key derivation](Self::derive_child)
+    ///
+    /// It's `None` for keys generated before chain code was introduced.
+    #[serde(default)]
+    pub chain_code: Option<[u8; 32]>,
+    /// Identity keys of the committee fixed at [keygen](super::Keygen::with_roster)
+    ///
+    /// It's `None` if keygen was carried out by anonymous parties.
+    #[serde(default)]
+    pub roster: Option<Roster>,
     pub i: u16,
     pub t: u16,
     pub n: u16,
 }
 
-impl LocalKey<Secp256k1> {
+impl<E: Curve> LocalKey<E> {
     /// Public key of secret shared between parties
-    pub fn public_key(&self) -> Point<Secp256k1> {
+    pub fn public_key(&self) -> Point<E> {
         self.y_sum_s.clone()
     }
 }
@@ -338,8 +495,12 @@ type Result<T> = std::result::Result<T, ProceedError>;
 /// every message was received and pre-validated).
 #[derive(Debug, Error)]
 pub enum ProceedError {
+    #[error("round 1: parties hold different rosters: {0:?}")]
+    Round1VerifyRoster(ErrorType),
     #[error("round 2: verify commitments: {0:?}")]
     Round2VerifyCommitments(ErrorType),
+    #[error("round 2: verify chain code commitments: {0:?}")]
+    Round2VerifyChainCode(ErrorType),
     #[error("round 3: verify vss construction: {0:?}")]
     Round3VerifyVssConstruct(ErrorType),
     #[error("round 4: verify dlog proof: {0:?}")]
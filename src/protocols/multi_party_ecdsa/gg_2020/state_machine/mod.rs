@@ -1,3 +1,6 @@
+pub mod checkpoint;
+pub mod echo_broadcast;
 pub mod keygen;
+pub mod negotiation;
 pub mod sign;
 pub mod traits;
@@ -0,0 +1,619 @@
+//! Negotiation of signing parties' indexes
+//!
+//! A relay issues indexes in the order parties joined the room, and they have nothing to do
+//! with indexes parties had at keygen. [OfflineStage](super::sign::OfflineStage) needs both: party
+//! index `i` and list `s_l` mapping every signing index to keygen index, and every party must
+//! get them exactly the same. [Negotiation] derives them from the room instead of a
+//! command line:
+//! 1. Every party broadcasts its keygen index. If [LocalKey] has a roster (see
+//!    [Keygen::with_roster](super::keygen::Keygen::with_roster)), the announcement is signed
+//!    by party's [identity key](crate::identity), so nobody can claim somebody else's share.
+//! 2. Parties are ordered by keygen index: `s_l` is the sorted list of announced keygen
+//!    indexes, signing index of a party is the position of its keygen index in `s_l`.
+//!
+//! The resulting [Agreement] depends only on the set of announcements, not on join order. To
+//! make sure everyone has seen the same announcements, run negotiation wrapped into
+//! [EchoBroadcast](super::echo_broadcast::EchoBroadcast).
+//!
+//! Messages of offline stage are addressed with signing indexes, but the room still delivers
+//! them by room indexes: translate them with [remap_incoming] and [remap_outgoing].
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use curv::elliptic::curves::secp256_k1::Secp256k1;
+use futures::{future, Sink, SinkExt, Stream, StreamExt};
+use round_based::containers::{self, BroadcastMsgs, MessageStore, Store, StoreErr};
+use round_based::{IsCritical, Msg, StateMachine};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::keygen::LocalKey;
+use super::sign::{self, OfflineStage};
+use crate::identity::{self, IdentityKey, Roster, SignedMessage};
+
+/// Negotiation state machine, see [module level documentation](self)
+pub struct Negotiation {
+    i: u16,
+    n: u16,
+    keygen_i: u16,
+    keygen_n: u16,
+    roster: Option<Roster>,
+    session_id: Vec<u8>,
+
+    msgs: Option<Store<BroadcastMsgs<Announcement>>>,
+    msgs_queue: Vec<Msg<Announcement>>,
+    output: Option<Agreement>,
+    output_picked: bool,
+}
+
+/// Message of [Negotiation] protocol
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Announcement {
+    keygen_index: u16,
+    /// Room index of the sender signed by its identity key, as message sent by keygen party
+    /// `keygen_index`
+    signature: Option<SignedMessage>,
+}
+
+impl Negotiation {
+    /// Constructs a party of negotiation
+    ///
+    /// Takes party index `i` in the room (in range `[1; n]`), number of parties in the room `n`
+    /// and party's local key. `session_id` must be unique for every negotiation (e.g. room id),
+    /// it's not used if local key has no roster.
+    ///
+    /// Returns error if:
+    /// * `n` is less than `t+1` or greater than number of keygen parties, returns
+    ///   [Error::TooFewParties] or [Error::TooManyParties]
+    /// * `i` is not in range `[1; n]`, returns [Error::InvalidPartyIndex]
+    /// * local key has a roster, returns [Error::IdentityKeyRequired]: everyone is going to
+    ///   require a signed announcement, use [Negotiation::with_identity]
+    pub fn new(i: u16, n: u16, local_key: &LocalKey<Secp256k1>, session_id: &[u8]) -> Result<Self> {
+        if local_key.roster.is_some() {
+            return Err(Error::IdentityKeyRequired);
+        }
+        Self::construct(i, n, local_key, session_id, None)
+    }
+
+    /// Constructs a party of negotiation that signs its announcement with `identity` key
+    ///
+    /// Returns the same errors as [Negotiation::new] (except [Error::IdentityKeyRequired]), and
+    /// [Error::IdentityKeyMismatch] if local key has no roster or `identity` isn't the key of
+    /// this party in the roster.
+    pub fn with_identity(
+        i: u16,
+        n: u16,
+        local_key: &LocalKey<Secp256k1>,
+        session_id: &[u8],
+        identity: &IdentityKey,
+    ) -> Result<Self> {
+        let expected = local_key
+            .roster
+            .as_ref()
+            .and_then(|roster| roster.public_key(local_key.i));
+        if expected != Some(&identity.public_key()) {
+            return Err(Error::IdentityKeyMismatch);
+        }
+        Self::construct(i, n, local_key, session_id, Some(identity))
+    }
+
+    fn construct(
+        i: u16,
+        n: u16,
+        local_key: &LocalKey<Secp256k1>,
+        session_id: &[u8],
+        identity: Option<&IdentityKey>,
+    ) -> Result<Self> {
+        if n < local_key.t + 1 || n < 2 {
+            return Err(Error::TooFewParties);
+        }
+        if n > local_key.n {
+            return Err(Error::TooManyParties);
+        }
+        if i == 0 || i > n {
+            return Err(Error::InvalidPartyIndex);
+        }
+
+        let signature = identity
+            .map(|identity| {
+                let msg = Msg {
+                    sender: local_key.i,
+                    receiver: None,
+                    body: i,
+                };
+                identity.sign_msg(session_id, msg).map(|msg| msg.body)
+            })
+            .transpose()
+            .map_err(Error::Identity)?;
+
+        Ok(Self {
+            i,
+            n,
+            keygen_i: local_key.i,
+            keygen_n: local_key.n,
+            roster: local_key.roster.clone(),
+            session_id: session_id.to_vec(),
+
+            msgs: Some(containers::BroadcastMsgsStore::new(i, n)),
+            msgs_queue: vec![Msg {
+                sender: i,
+                receiver: None,
+                body: Announcement {
+                    keygen_index: local_key.i,
+                    signature,
+                },
+            }],
+            output: None,
+            output_picked: false,
+        })
+    }
+
+    /// Checks announcement of party `party`, returns announced keygen index
+    fn check_announcement(&self, party: u16, announcement: &Announcement) -> Result<u16> {
+        let keygen_index = announcement.keygen_index;
+        if keygen_index == 0 || keygen_index > self.keygen_n {
+            return Err(Error::InvalidKeygenIndex {
+                party,
+                keygen_index,
+            });
+        }
+        if let Some(roster) = &self.roster {
+            let signature = announcement
+                .signature
+                .clone()
+                .ok_or(Error::UnsignedAnnouncement { party })?;
+            let msg = Msg {
+                sender: keygen_index,
+                receiver: None,
+                body: signature,
+            };
+            let signed_index = roster
+                .verify_msg::<u16>(&self.session_id, msg)
+                .map_err(|_| Error::InvalidSignature { party })?;
+            if signed_index.body != party {
+                return Err(Error::InvalidSignature { party });
+            }
+        }
+        Ok(keygen_index)
+    }
+
+    fn finish(&mut self, msgs: BroadcastMsgs<Announcement>) -> Result<()> {
+        let mut parties = vec![(self.keygen_i, self.i)];
+        for (party, announcement) in msgs.into_iter_indexed() {
+            parties.push((announcement.keygen_index, party));
+        }
+        parties.sort_unstable();
+        if let Some(w) = parties.windows(2).find(|w| w[0].0 == w[1].0) {
+            return Err(Error::DuplicatedKeygenIndex {
+                keygen_index: w[0].0,
+                parties: (w[0].1.min(w[1].1), w[0].1.max(w[1].1)),
+            });
+        }
+
+        let (s_l, room_indexes): (Vec<u16>, Vec<u16>) = parties.into_iter().unzip();
+        let position = room_indexes
+            .iter()
+            .position(|&j| j == self.i)
+            .expect("own index is in the list");
+        self.output = Some(Agreement {
+            i: u16::try_from(position + 1).expect("n fits into u16"),
+            s_l,
+            room_indexes,
+        });
+        Ok(())
+    }
+}
+
+impl StateMachine for Negotiation {
+    type MessageBody = Announcement;
+    type Err = Error;
+    type Output = Agreement;
+
+    fn handle_incoming(&mut self, msg: Msg<Self::MessageBody>) -> Result<()> {
+        self.check_announcement(msg.sender, &msg.body)?;
+        let store = self.msgs.as_mut().ok_or(Error::ReceivedOutOfOrderMessage)?;
+        store.push_msg(msg).map_err(Error::HandleMessage)?;
+        if !store.wants_more() {
+            let msgs = self
+                .msgs
+                .take()
+                .ok_or(Error::ReceivedOutOfOrderMessage)?
+                .finish()
+                .map_err(Error::HandleMessage)?;
+            self.finish(msgs)?;
+        }
+        Ok(())
+    }
+
+    fn message_queue(&mut self) -> &mut Vec<Msg<Self::MessageBody>> {
+        &mut self.msgs_queue
+    }
+
+    fn wants_to_proceed(&self) -> bool {
+        false
+    }
+
+    fn proceed(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn round_timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    fn round_timeout_reached(&mut self) -> Self::Err {
+        panic!("no timeout was set")
+    }
+
+    fn is_finished(&self) -> bool {
+        self.output.is_some()
+    }
+
+    fn pick_output(&mut self) -> Option<Result<Self::Output>> {
+        if self.output_picked {
+            return Some(Err(Error::DoublePickOutput));
+        }
+        let output = self.output.take()?;
+        self.output_picked = true;
+        Some(Ok(output))
+    }
+
+    fn current_round(&self) -> u16 {
+        if self.msgs.is_some() {
+            1
+        } else {
+            2
+        }
+    }
+
+    fn total_rounds(&self) -> Option<u16> {
+        Some(1)
+    }
+
+    fn party_ind(&self) -> u16 {
+        self.i
+    }
+
+    fn parties(&self) -> u16 {
+        self.n
+    }
+}
+
+impl fmt::Debug for Negotiation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let msgs = match self.msgs.as_ref() {
+            Some(msgs) => format!("[{}/{}]", msgs.messages_received(), msgs.messages_total()),
+            None => "[None]".into(),
+        };
+        write!(
+            f,
+            "{{Negotiation i={} keygen_i={} msgs={} queue=[len={}]}}",
+            self.i,
+            self.keygen_i,
+            msgs,
+            self.msgs_queue.len()
+        )
+    }
+}
+
+/// Outcome of negotiation: signing committee and its indexing
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Agreement {
+    i: u16,
+    s_l: Vec<u16>,
+    /// `room_indexes[j-1]` is room index of party with signing index `j`
+    room_indexes: Vec<u16>,
+}
+
+impl Agreement {
+    /// Signing index of this party
+    pub fn party_index(&self) -> u16 {
+        self.i
+    }
+
+    /// Keygen indexes of signing parties, `s_l[j-1]` is keygen index of party with signing
+    /// index `j`
+    pub fn s_l(&self) -> &[u16] {
+        &self.s_l
+    }
+
+    /// Room index of party with signing index `j`
+    pub fn room_index(&self, j: u16) -> Option<u16> {
+        usize::from(j)
+            .checked_sub(1)
+            .and_then(|j| self.room_indexes.get(j))
+            .copied()
+    }
+
+    /// Signing index of party with room index `j`
+    pub fn signing_index(&self, j: u16) -> Option<u16> {
+        (1..)
+            .zip(&self.room_indexes)
+            .find(|(_, &r)| r == j)
+            .map(|(s, _)| s)
+    }
+
+    /// Constructs offline stage of the agreed committee
+    pub fn offline_stage(
+        &self,
+        local_key: LocalKey<Secp256k1>,
+    ) -> std::result::Result<OfflineStage, sign::Error> {
+        OfflineStage::new(self.i, self.s_l.clone(), local_key)
+    }
+
+    /// Translates sender and receiver of a message from room indexes to signing indexes
+    pub fn to_signing_indexes<M>(&self, msg: Msg<M>) -> Result<Msg<M>> {
+        let sender = self
+            .signing_index(msg.sender)
+            .ok_or(Error::UnknownParty { party: msg.sender })?;
+        let receiver = msg
+            .receiver
+            .map(|j| {
+                self.signing_index(j)
+                    .ok_or(Error::UnknownParty { party: j })
+            })
+            .transpose()?;
+        Ok(Msg {
+            sender,
+            receiver,
+            body: msg.body,
+        })
+    }
+
+    /// Translates sender and receiver of a message from signing indexes to room indexes
+    pub fn to_room_indexes<M>(&self, msg: Msg<M>) -> Result<Msg<M>> {
+        let sender = self
+            .room_index(msg.sender)
+            .ok_or(Error::UnknownParty { party: msg.sender })?;
+        let receiver = msg
+            .receiver
+            .map(|j| self.room_index(j).ok_or(Error::UnknownParty { party: j }))
+            .transpose()?;
+        Ok(Msg {
+            sender,
+            receiver,
+            body: msg.body,
+        })
+    }
+}
+
+/// Wraps incoming stream: translates room indexes to signing indexes
+///
+/// Message from a party outside the agreed committee is yielded as an error.
+pub fn remap_incoming<M, I, E>(
+    agreement: Arc<Agreement>,
+    incoming: I,
+) -> impl Stream<Item = std::result::Result<Msg<M>, E>>
+where
+    I: Stream<Item = std::result::Result<Msg<M>, E>>,
+    E: From<Error>,
+{
+    incoming.map(move |msg| msg.and_then(|msg| agreement.to_signing_indexes(msg).map_err(E::from)))
+}
+
+/// Wraps outgoing sink: translates signing indexes to room indexes
+pub fn remap_outgoing<M, O, E>(
+    agreement: Arc<Agreement>,
+    outgoing: O,
+) -> impl Sink<Msg<M>, Error = E>
+where
+    O: Sink<Msg<M>, Error = E>,
+    E: From<Error>,
+{
+    outgoing.with(move |msg: Msg<M>| future::ready(agreement.to_room_indexes(msg).map_err(E::from)))
+}
+
+type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Error type of negotiation protocol
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// Fewer parties than needed to sign (`n < t+1`)
+    #[error("too few parties to sign")]
+    TooFewParties,
+    /// More parties than participated in keygen
+    #[error("more parties than participated in keygen")]
+    TooManyParties,
+    /// Party index `i` is not in range `[1; n]`
+    #[error("party index is not in range [1; n]")]
+    InvalidPartyIndex,
+    /// Local key has a roster, announcement must be signed
+    #[error("local key has a roster, identity key is required")]
+    IdentityKeyRequired,
+    /// Identity key doesn't match the roster of local key
+    #[error("identity key doesn't match the roster")]
+    IdentityKeyMismatch,
+    /// Couldn't sign the announcement
+    #[error("sign announcement: {0}")]
+    Identity(#[source] identity::Error),
+
+    /// Party announced keygen index out of range `[1; keygen_n]`
+    #[error("party {party} announced invalid keygen index {keygen_index}")]
+    InvalidKeygenIndex { party: u16, keygen_index: u16 },
+    /// Two parties announced the same keygen index
+    #[error("parties {parties:?} both announced keygen index {keygen_index}")]
+    DuplicatedKeygenIndex {
+        keygen_index: u16,
+        parties: (u16, u16),
+    },
+    /// Party didn't sign its announcement, though local key has a roster
+    #[error("party {party} didn't sign its announcement")]
+    UnsignedAnnouncement { party: u16 },
+    /// Announcement isn't signed by identity key of announced keygen index
+    #[error("announcement of party {party} has invalid signature")]
+    InvalidSignature { party: u16 },
+    /// Message is sent by or addressed to a party outside the agreed committee
+    #[error("party {party} is not in the agreed committee")]
+    UnknownParty { party: u16 },
+
+    /// Received message which we didn't expect to receive now
+    #[error("negotiation is already finished")]
+    ReceivedOutOfOrderMessage,
+    /// Received message didn't pass pre-validation
+    #[error("received message didn't pass pre-validation: {0}")]
+    HandleMessage(#[source] StoreErr),
+    /// [Negotiation::pick_output] called twice
+    #[error("pick_output called twice")]
+    DoublePickOutput,
+}
+
+impl IsCritical for Error {
+    fn is_critical(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use round_based::dev::Simulation;
+
+    use super::*;
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::echo_broadcast::EchoBroadcast;
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::Keygen;
+
+    /// `keygen_indexes[j-1]` is keygen index of party that got room index `j`
+    fn negotiate(
+        local_keys: &[LocalKey<Secp256k1>],
+        keygen_indexes: &[u16],
+        identities: Option<&[IdentityKey]>,
+    ) -> Vec<Agreement> {
+        let n = keygen_indexes.len() as u16;
+        let mut simulation = Simulation::new();
+        for (i, &keygen_i) in (1..).zip(keygen_indexes) {
+            let local_key = &local_keys[usize::from(keygen_i - 1)];
+            let party = match identities {
+                Some(identities) => Negotiation::with_identity(
+                    i,
+                    n,
+                    local_key,
+                    b"room",
+                    &identities[usize::from(keygen_i - 1)],
+                ),
+                None => Negotiation::new(i, n, local_key, b"room"),
+            };
+            simulation.add_party(EchoBroadcast::new(party.unwrap()).unwrap());
+        }
+        simulation.run().unwrap()
+    }
+
+    #[test]
+    fn agreement_doesnt_depend_on_join_order() {
+        let local_keys = simulate_keygen(1, 3);
+
+        let agreements = negotiate(&local_keys, &[3, 1], None);
+        assert!(agreements.iter().all(|a| a.s_l() == [1, 3]));
+        assert_eq!(agreements[0].party_index(), 2);
+        assert_eq!(agreements[1].party_index(), 1);
+        assert_eq!(agreements[0].room_index(1), Some(2));
+        assert_eq!(agreements[0].signing_index(1), Some(2));
+
+        // Agreed committee completes offline stage
+        let mut simulation = Simulation::new();
+        let mut parties = agreements
+            .iter()
+            .zip([3u16, 1])
+            .map(|(a, keygen_i)| {
+                let local_key = local_keys[usize::from(keygen_i - 1)].clone();
+                (a.party_index(), a.offline_stage(local_key).unwrap())
+            })
+            .collect::<Vec<_>>();
+        parties.sort_by_key(|(i, _)| *i);
+        for (_, party) in parties {
+            simulation.add_party(party);
+        }
+        simulation.run().unwrap();
+    }
+
+    #[test]
+    fn signed_announcements_are_verified() {
+        let (t, n) = (1, 3);
+        let identities = (0..n).map(|_| IdentityKey::generate()).collect::<Vec<_>>();
+        let roster = Roster::new(identities.iter().map(IdentityKey::public_key).collect()).unwrap();
+        let mut simulation = Simulation::new();
+        for i in 1..=n {
+            simulation.add_party(Keygen::with_roster(i, t, n, roster.clone()).unwrap());
+        }
+        let local_keys = simulation.run().unwrap();
+
+        let agreements = negotiate(&local_keys, &[2, 3, 1], Some(&identities));
+        assert!(agreements.iter().all(|a| a.s_l() == [1, 2, 3]));
+
+        assert!(matches!(
+            Negotiation::new(1, 2, &local_keys[0], b"room"),
+            Err(Error::IdentityKeyRequired)
+        ));
+        assert!(matches!(
+            Negotiation::with_identity(1, 2, &local_keys[0], b"room", &identities[1]),
+            Err(Error::IdentityKeyMismatch)
+        ));
+
+        // Party 2 claims keygen index 1, but can't sign for it
+        let mut party1 =
+            Negotiation::with_identity(1, 2, &local_keys[0], b"room", &identities[0]).unwrap();
+        let mut party2 =
+            Negotiation::with_identity(2, 2, &local_keys[1], b"room", &identities[1]).unwrap();
+        let mut forged = party2.message_queue().remove(0);
+        forged.body.keygen_index = 1;
+        assert!(matches!(
+            party1.handle_incoming(forged),
+            Err(Error::InvalidSignature { party: 2 })
+        ));
+
+        // Signature is bound to the room index
+        let mut party2 =
+            Negotiation::with_identity(2, 3, &local_keys[1], b"room", &identities[1]).unwrap();
+        let mut party1 =
+            Negotiation::with_identity(1, 3, &local_keys[0], b"room", &identities[0]).unwrap();
+        let mut replayed = party2.message_queue().remove(0);
+        replayed.sender = 3;
+        assert!(matches!(
+            party1.handle_incoming(replayed),
+            Err(Error::InvalidSignature { party: 3 })
+        ));
+    }
+
+    #[test]
+    fn duplicated_keygen_index_is_rejected() {
+        let local_keys = simulate_keygen(1, 3);
+        let mut party1 = Negotiation::new(1, 2, &local_keys[0], b"room").unwrap();
+        let mut party2 = Negotiation::new(2, 2, &local_keys[0], b"room").unwrap();
+        let msg = party2.message_queue().remove(0);
+        assert!(matches!(
+            party1.handle_incoming(msg),
+            Err(Error::DuplicatedKeygenIndex {
+                keygen_index: 1,
+                parties: (1, 2)
+            })
+        ));
+    }
+
+    #[test]
+    fn messages_are_remapped() {
+        let agreement = Agreement {
+            i: 1,
+            s_l: vec![1, 3],
+            room_indexes: vec![2, 1],
+        };
+        let msg = Msg {
+            sender: 1,
+            receiver: Some(2),
+            body: (),
+        };
+        let remapped = agreement.to_room_indexes(msg.clone()).unwrap();
+        assert_eq!((remapped.sender, remapped.receiver), (2, Some(1)));
+        assert_eq!(agreement.to_signing_indexes(remapped).unwrap(), msg);
+        assert!(matches!(
+            agreement.to_signing_indexes(Msg {
+                sender: 3,
+                receiver: None,
+                body: ()
+            }),
+            Err(Error::UnknownParty { party: 3 })
+        ));
+    }
+}
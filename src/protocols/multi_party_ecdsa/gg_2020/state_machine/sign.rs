@@ -29,11 +29,15 @@ use round_based::{IsCritical, Msg, StateMachine};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::utilities::adaptor::PreSignature;
 use crate::utilities::mta::MessageA;
 
 use crate::protocols::multi_party_ecdsa::gg_2020 as gg20;
-use curv::elliptic::curves::secp256_k1::Secp256k1;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Curve, Point, Scalar};
 use gg20::party_i::{SignBroadcastPhase1, SignDecommitPhase1, SignatureRecid};
+use gg20::state_machine::checkpoint::{
+    self, Checkpoint, CheckpointKey, Journal, SentLog, SentLogStore,
+};
 use gg20::state_machine::keygen::LocalKey;
 
 mod fmt;
@@ -42,29 +46,37 @@ mod rounds;
 use crate::utilities::zk_pdl_with_slack::PDLwSlackProof;
 use curv::BigInt;
 use rounds::*;
-pub use rounds::{CompletedOfflineStage, Error as ProceedError, PartialSignature};
+pub use rounds::{
+    AdaptorNonceShare, CompletedOfflineStage, Error as ProceedError, PartialPreSignature,
+    PartialSignature,
+};
 
 /// Offline Stage of GG20 signing
 ///
 /// Successfully carried out Offline Stage will produce [CompletedOfflineStage] that can
-/// be used for one-round signing multiple times.
-pub struct OfflineStage {
-    round: OfflineR,
+/// be used for one-round signing multiple times. [OfflineStage::new] takes secp256k1 [LocalKey],
+/// [OfflineStage::new_on_curve] and [OfflineStage::new_with_tweak] infer the curve from the key
+/// they're constructed with.
+pub struct OfflineStage<E: Curve = Secp256k1> {
+    round: OfflineR<E>,
 
     msgs1: Option<Store<BroadcastMsgs<(MessageA, SignBroadcastPhase1)>>>,
-    msgs2: Option<Store<P2PMsgs<(GammaI, WI)>>>,
-    msgs3: Option<Store<BroadcastMsgs<(DeltaI, TI, TIProof)>>>,
-    msgs4: Option<Store<BroadcastMsgs<SignDecommitPhase1>>>,
-    msgs5: Option<Store<BroadcastMsgs<(RDash, Vec<PDLwSlackProof>)>>>,
-    msgs6: Option<Store<BroadcastMsgs<(SI, HEGProof)>>>,
+    msgs2: Option<Store<P2PMsgs<(GammaI<E>, WI<E>)>>>,
+    msgs3: Option<Store<BroadcastMsgs<(DeltaI<E>, TI<E>, TIProof<E>)>>>,
+    msgs4: Option<Store<BroadcastMsgs<SignDecommitPhase1<E>>>>,
+    msgs5: Option<Store<BroadcastMsgs<(RDash<E>, Vec<PDLwSlackProof<E>>)>>>,
+    msgs6: Option<Store<BroadcastMsgs<(SI<E>, HEGProof<E>)>>>,
 
-    msgs_queue: MsgQueue,
+    msgs_queue: MsgQueue<E>,
+    /// Messages pushed to stores of rounds which didn't proceed yet
+    received: Vec<Msg<OfflineProtocolMessage<E>>>,
+    sent: Journal,
 
     party_i: u16,
     party_n: u16,
 }
 
-impl OfflineStage {
+impl OfflineStage<Secp256k1> {
     /// Construct a party of offline stage of threshold signing protocol
     ///
     /// Once offline stage is finished, parties can do one-round threshold signing (i.e. they only
@@ -76,6 +88,33 @@ impl OfflineStage {
     ///
     /// Returns error if given arguments are contradicting.
     pub fn new(i: u16, s_l: Vec<u16>, local_key: LocalKey<Secp256k1>) -> Result<Self> {
+        Self::new_on_curve(i, s_l, local_key)
+    }
+}
+
+impl<E: Curve> OfflineStage<E> {
+    /// Constructs a party of offline stage over the curve of `local_key`
+    ///
+    /// Takes the same arguments and returns the same errors as [OfflineStage::new].
+    pub fn new_on_curve(i: u16, s_l: Vec<u16>, local_key: LocalKey<E>) -> Result<Self> {
+        Self::new_with_tweak(i, s_l, local_key, Scalar::zero())
+    }
+
+    /// Constructs a party of offline stage that signs under tweaked public key `Q = y + tG`
+    ///
+    /// Useful for pay-to-contract commitments, Taproot output keys, per-user deposit addresses,
+    /// etc. `LocalKey` is left untouched: the tweak is applied to the party's share of `k * x`,
+    /// so [CompletedOfflineStage] (and every signature produced from it) is bound to `Q`.
+    /// All parties must use the same tweak, otherwise offline stage fails at the last round.
+    ///
+    /// Takes the same arguments as [OfflineStage::new] plus public tweak `tweak`. Returns
+    /// [Error::InvalidTweak] if tweaked public key is the point at infinity.
+    pub fn new_with_tweak(
+        i: u16,
+        s_l: Vec<u16>,
+        local_key: LocalKey<E>,
+        tweak: Scalar<E>,
+    ) -> Result<Self> {
         if s_l.len() < 2 {
             return Err(Error::TooFewParties);
         }
@@ -101,10 +140,19 @@ impl OfflineStage {
 
         let n = u16::try_from(s_l.len()).map_err(|_| Error::TooManyParties { n: s_l.len() })?;
 
-        Ok(Self {
-            round: OfflineR::R0(Round0 { i, s_l, local_key }),
+        if (&local_key.y_sum_s + Point::generator() * &tweak).is_zero() {
+            return Err(Error::InvalidTweak);
+        }
 
-            msgs1: Some(Round1::expects_messages(i, n)),
+        Ok(Self {
+            round: OfflineR::R0(Round0 {
+                i,
+                s_l,
+                local_key,
+                tweak,
+            }),
+
+            msgs1: Some(Round1::<E>::expects_messages(i, n)),
             msgs2: Some(Round2::expects_messages(i, n)),
             msgs3: Some(Round3::expects_messages(i, n)),
             msgs4: Some(Round4::expects_messages(i, n)),
@@ -112,18 +160,129 @@ impl OfflineStage {
             msgs6: Some(Round6::expects_messages(i, n)),
 
             msgs_queue: MsgQueue(vec![]),
+            received: vec![],
+            sent: Journal::default(),
 
             party_i: i,
             party_n: n,
         })
     }
 
+    /// Persists [sent log](OfflineStage::sent_log) to the `store`, see
+    /// [Keygen::persist_sent_log](super::keygen::Keygen::persist_sent_log)
+    pub fn persist_sent_log(&mut self, store: impl SentLogStore + 'static) -> Result<()> {
+        self.sent
+            .persist(Box::new(store))
+            .map_err(Error::Checkpoint)
+    }
+
+    /// Saves offline stage state as an encrypted [checkpoint](super::checkpoint)
+    ///
+    /// Works the same way as [Keygen::checkpoint](super::keygen::Keygen::checkpoint): offline
+    /// stage can be checkpointed at any point, including in the middle of a round, once its sent
+    /// log is [persisted](OfflineStage::persist_sent_log).
+    pub fn checkpoint(&self, key: &CheckpointKey) -> Result<Checkpoint> {
+        self.sent.ensure_persisted().map_err(Error::Checkpoint)?;
+        let state = OfflineStageCheckpoint {
+            round: &self.round,
+            received: &self.received,
+            msgs_queue: &self.msgs_queue.0,
+            sent: self.sent.log(),
+            party_i: self.party_i,
+            party_n: self.party_n,
+        };
+        Checkpoint::seal(key, CHECKPOINT_PROTOCOL, &state).map_err(Error::Checkpoint)
+    }
+
+    /// Restores offline stage from a [checkpoint](OfflineStage::checkpoint)
+    ///
+    /// Restored offline stage refuses to emit messages that differ from the ones logged in
+    /// `store` or in the checkpoint, see [Keygen::restore](super::keygen::Keygen::restore).
+    pub fn restore(
+        checkpoint: &Checkpoint,
+        key: &CheckpointKey,
+        store: impl SentLogStore + 'static,
+    ) -> Result<Self> {
+        let OfflineStageCheckpoint {
+            round,
+            received,
+            msgs_queue,
+            sent,
+            party_i,
+            party_n,
+        }: OfflineStageCheckpoint<OfflineR<E>, _, SentLog> = checkpoint
+            .open(key, CHECKPOINT_PROTOCOL)
+            .map_err(Error::Checkpoint)?;
+
+        let mut state = Self::from_parts(round, msgs_queue, party_i, party_n);
+        state.sent = Journal::restore(&sent, Box::new(store)).map_err(Error::Checkpoint)?;
+        for msg in received {
+            state.handle_incoming(msg)?;
+        }
+        Ok(state)
+    }
+
+    /// Digests of messages emitted by this party at every round, see
+    /// [Keygen::sent_log](super::keygen::Keygen::sent_log)
+    pub fn sent_log(&self) -> &SentLog {
+        self.sent.log()
+    }
+
+    fn from_parts(
+        round: OfflineR<E>,
+        msgs_queue: Vec<Msg<OfflineProtocolMessage<E>>>,
+        i: u16,
+        n: u16,
+    ) -> Self {
+        let mut state = Self {
+            round,
+            msgs1: None,
+            msgs2: None,
+            msgs3: None,
+            msgs4: None,
+            msgs5: None,
+            msgs6: None,
+            msgs_queue: MsgQueue(msgs_queue),
+            received: vec![],
+            sent: Journal::default(),
+            party_i: i,
+            party_n: n,
+        };
+        let round = state.current_round();
+        state.msgs1 = Some(Round1::<E>::expects_messages(i, n)).filter(|_| round <= 1);
+        state.msgs2 = Some(Round2::expects_messages(i, n)).filter(|_| round <= 2);
+        state.msgs3 = Some(Round3::expects_messages(i, n)).filter(|_| round <= 3);
+        state.msgs4 = Some(Round4::expects_messages(i, n)).filter(|_| round <= 4);
+        state.msgs5 = Some(Round5::expects_messages(i, n)).filter(|_| round <= 5);
+        state.msgs6 = Some(Round6::expects_messages(i, n)).filter(|_| round <= 6);
+        state
+    }
+
     // fn proceed_state(&mut self, may_block: bool) -> Result<()> {
     //     self.proceed_round(may_block)?;
     //     self.proceed_decommit_round(may_block)
     // }
 
+    /// Logs messages emitted on entering current round, drops received messages of rounds that
+    /// already proceeded
+    ///
+    /// Emitted messages are taken back if they differ from the ones sent at this round before.
+    fn round_proceeded(&mut self, queued: usize) -> Result<()> {
+        let round = self.current_round();
+        self.received.retain(|msg| msg.body.round() >= round);
+        if self.msgs_queue.0.len() == queued {
+            return Ok(());
+        }
+        if let Err(err) = self.sent.record(round, &self.msgs_queue.0[queued..]) {
+            self.msgs_queue.0.truncate(queued);
+            return Err(Error::Checkpoint(err));
+        }
+        Ok(())
+    }
+
     fn proceed_round(&mut self, may_block: bool) -> Result<()> {
+        let round = self.current_round();
+        let queued = self.msgs_queue.0.len();
         let store1_wants_more = self.msgs1.as_ref().map(|s| s.wants_more()).unwrap_or(false);
         let store2_wants_more = self.msgs2.as_ref().map(|s| s.wants_more()).unwrap_or(false);
         let store3_wants_more = self.msgs3.as_ref().map(|s| s.wants_more()).unwrap_or(false);
@@ -131,7 +290,7 @@ impl OfflineStage {
         let store5_wants_more = self.msgs5.as_ref().map(|s| s.wants_more()).unwrap_or(false);
         let store6_wants_more = self.msgs6.as_ref().map(|s| s.wants_more()).unwrap_or(false);
 
-        let next_state: OfflineR;
+        let next_state: OfflineR<E>;
         let try_again: bool = match replace(&mut self.round, OfflineR::Gone) {
             OfflineR::R0(round) if !round.is_expensive() || may_block => {
                 next_state = round
@@ -241,6 +400,9 @@ impl OfflineStage {
         };
 
         self.round = next_state;
+        if self.current_round() != round {
+            self.round_proceeded(queued)?;
+        }
         if try_again {
             self.proceed_round(may_block)
         } else {
@@ -249,13 +411,14 @@ impl OfflineStage {
     }
 }
 
-impl StateMachine for OfflineStage {
-    type MessageBody = OfflineProtocolMessage;
+impl<E: Curve> StateMachine for OfflineStage<E> {
+    type MessageBody = OfflineProtocolMessage<E>;
     type Err = Error;
-    type Output = CompletedOfflineStage;
+    type Output = CompletedOfflineStage<E>;
 
     fn handle_incoming(&mut self, msg: Msg<Self::MessageBody>) -> Result<(), Self::Err> {
         let current_round = self.current_round();
+        let received = msg.clone();
 
         match msg.body {
             OfflineProtocolMessage(OfflineM::M1(m)) => {
@@ -355,6 +518,7 @@ impl StateMachine for OfflineStage {
                     .map_err(Error::HandleMessage)?;
             }
         }
+        self.received.push(received);
         self.proceed_round(false)
     }
 
@@ -437,7 +601,7 @@ impl StateMachine for OfflineStage {
     }
 }
 
-impl super::traits::RoundBlame for OfflineStage {
+impl<E: Curve> super::traits::RoundBlame for OfflineStage<E> {
     /// RoundBlame returns number of unwilling parties and a vector of their party indexes.
     fn round_blame(&self) -> (u16, Vec<u16>) {
         let store1_blame = self.msgs1.as_ref().map(|s| s.blame()).unwrap_or_default();
@@ -462,39 +626,75 @@ impl super::traits::RoundBlame for OfflineStage {
     }
 }
 
+/// Offline stage state saved in a [Checkpoint]
+///
+/// Borrows the state when it's sealed, and owns it when it's opened.
+#[derive(Serialize, Deserialize)]
+struct OfflineStageCheckpoint<R, Q, S> {
+    round: R,
+    received: Q,
+    msgs_queue: Q,
+    sent: S,
+    party_i: u16,
+    party_n: u16,
+}
+
+const CHECKPOINT_PROTOCOL: &str = "gg20/offline-stage";
+
+/// Round state
+///
+/// Holds party's secrets (e.g. nonce shares) in plain text, so it's only ever serialized into an
+/// encrypted [Checkpoint], and never cloned.
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
 #[allow(clippy::large_enum_variant)]
-enum OfflineR {
-    R0(Round0),
-    R1(Round1),
-    R2(Round2),
-    R3(Round3),
-    R4(Round4),
-    R5(Round5),
-    R6(Round6),
-    Finished(CompletedOfflineStage),
+enum OfflineR<E: Curve> {
+    R0(Round0<E>),
+    R1(Round1<E>),
+    R2(Round2<E>),
+    R3(Round3<E>),
+    R4(Round4<E>),
+    R5(Round5<E>),
+    R6(Round6<E>),
+    Finished(CompletedOfflineStage<E>),
     Gone,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct OfflineProtocolMessage(OfflineM);
+#[serde(bound = "")]
+pub struct OfflineProtocolMessage<E: Curve = Secp256k1>(OfflineM<E>);
+
+impl<E: Curve> OfflineProtocolMessage<E> {
+    fn round(&self) -> u16 {
+        match self.0 {
+            OfflineM::M1(_) => 1,
+            OfflineM::M2(_) => 2,
+            OfflineM::M3(_) => 3,
+            OfflineM::M4(_) => 4,
+            OfflineM::M5(_) => 5,
+            OfflineM::M6(_) => 6,
+        }
+    }
+}
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(bound = "")]
 #[allow(clippy::large_enum_variant)]
-enum OfflineM {
+enum OfflineM<E: Curve> {
     M1((MessageA, SignBroadcastPhase1)),
-    M2((GammaI, WI)),
-    M3((DeltaI, TI, TIProof)),
-    M4(SignDecommitPhase1),
-    M5((RDash, Vec<PDLwSlackProof>)),
-    M6((SI, HEGProof)),
+    M2((GammaI<E>, WI<E>)),
+    M3((DeltaI<E>, TI<E>, TIProof<E>)),
+    M4(SignDecommitPhase1<E>),
+    M5((RDash<E>, Vec<PDLwSlackProof<E>>)),
+    M6((SI<E>, HEGProof<E>)),
 }
 
-struct MsgQueue(Vec<Msg<OfflineProtocolMessage>>);
+struct MsgQueue<E: Curve>(Vec<Msg<OfflineProtocolMessage<E>>>);
 
 macro_rules! make_pushable {
     ($($constructor:ident $t:ty),*$(,)?) => {
         $(
-        impl Push<Msg<$t>> for MsgQueue {
+        impl<E: Curve> Push<Msg<$t>> for MsgQueue<E> {
             fn push(&mut self, m: Msg<$t>) {
                 Vec::push(&mut self.0, Msg{
                     sender: m.sender,
@@ -509,11 +709,11 @@ macro_rules! make_pushable {
 
 make_pushable! {
     M1 (MessageA, SignBroadcastPhase1),
-    M2 (GammaI, WI),
-    M3 (DeltaI, TI, TIProof),
-    M4 SignDecommitPhase1,
-    M5 (RDash, Vec<PDLwSlackProof>),
-    M6 (SI, HEGProof),
+    M2 (GammaI<E>, WI<E>),
+    M3 (DeltaI<E>, TI<E>, TIProof<E>),
+    M4 SignDecommitPhase1<E>,
+    M5 (RDash<E>, Vec<PDLwSlackProof<E>>),
+    M6 (SI<E>, HEGProof<E>),
 }
 
 type Result<T, E = Error> = std::result::Result<T, E>;
@@ -534,6 +734,9 @@ pub enum Error {
     /// participated in DKG (`exist i. s_l[i] = 0 || s_l[i] > keygen_n`).
     #[error("invalid s_l")]
     InvalidSl,
+    /// Tweaked public key `y + tG` is the point at infinity
+    #[error("tweaked public key is the point at infinity")]
+    InvalidTweak,
 
     /// Round proceeding resulted in protocol error
     #[error("proceeding round: {0}")]
@@ -551,6 +754,10 @@ pub enum Error {
     /// [OfflineStage::pick_output] called twice
     #[error("pick_output called twice")]
     DoublePickOutput,
+    /// Checkpoint can't be saved or restored, or restored party was about to send messages
+    /// different from the ones it sent before ([checkpoint::Error::Equivocation])
+    #[error("checkpoint: {0}")]
+    Checkpoint(#[source] checkpoint::Error),
 
     /// A bug in protocol implementation
     #[error("offline stage protocol bug: {0}")]
@@ -580,10 +787,12 @@ impl IsCritical for Error {
             Error::TooManyParties { .. } => true,
             Error::InvalidPartyIndex => true,
             Error::InvalidSl => true,
+            Error::InvalidTweak => true,
             Error::ProceedRound(_) => true,
             Error::ReceivedOutOfOrderMessage { .. } => false,
             Error::HandleMessage(_) => false,
             Error::DoublePickOutput => true,
+            Error::Checkpoint(_) => true,
             Error::Bug(_) => true,
         }
     }
@@ -622,15 +831,15 @@ impl IsCritical for Error {
 /// # }
 /// ```
 #[derive(Clone)]
-pub struct SignManual {
-    state: Round7,
+pub struct SignManual<E: Curve = Secp256k1> {
+    state: Round7<E>,
 }
 
-impl SignManual {
+impl<E: Curve> SignManual<E> {
     pub fn new(
         message: BigInt,
-        completed_offline_stage: CompletedOfflineStage,
-    ) -> Result<(Self, PartialSignature), SignError> {
+        completed_offline_stage: CompletedOfflineStage<E>,
+    ) -> Result<(Self, PartialSignature<E>), SignError> {
         Round7::new(&message, completed_offline_stage)
             .map(|(state, m)| (Self { state }, m))
             .map_err(SignError::LocalSigning)
@@ -638,13 +847,96 @@ impl SignManual {
 
     /// `sigs` must not include partial signature produced by local party (only partial signatures produced
     /// by other parties)
-    pub fn complete(self, sigs: &[PartialSignature]) -> Result<SignatureRecid, SignError> {
+    pub fn complete(self, sigs: &[PartialSignature<E>]) -> Result<SignatureRecid<E>, SignError> {
         self.state
             .proceed_manual(sigs)
             .map_err(SignError::CompleteSigning)
     }
 }
 
+/// Variant of [SignManual] producing a pre-signature tied to adaptor point `Y`
+///
+/// Takes one more round than [SignManual]: parties exchange their shares of adaptor nonce point
+/// first, then partial pre-signatures. Resulting [PreSignature] is completed into a valid
+/// signature by whoever knows `y` (see [adaptor](crate::utilities::adaptor) module).
+///
+/// ## Example
+/// ```no_run
+/// # use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::sign::{
+/// #     AdaptorNonceShare, AdaptorSignManual, CompletedOfflineStage, PartialPreSignature,
+/// # };
+/// # use multi_party_ecdsa::utilities::adaptor::{adapt, extract_secret};
+/// # use curv::arithmetic::{BigInt, Converter};
+/// # use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+/// # type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+/// # fn broadcast<T>(msg: T) -> Result<()> { panic!() }
+/// # fn wait_messages<T>() -> Result<Vec<T>> { panic!() }
+/// # fn main() -> Result<()> {
+/// # let completed_offline_stage: CompletedOfflineStage = panic!();
+/// # let y: Scalar<Secp256k1> = panic!();
+/// let data = BigInt::from_bytes(b"a message");
+/// let adaptor_point = Point::generator() * &y;
+///
+/// let (sign, share) = AdaptorSignManual::new(data.clone(), completed_offline_stage, adaptor_point.clone())?;
+/// broadcast(share)?;
+/// // Nonce shares of other parties, ordered by party index
+/// let shares: Vec<AdaptorNonceShare> = wait_messages()?;
+/// let (sign, partial) = sign.partial_sign(&shares)?;
+/// broadcast(partial)?;
+/// let partials: Vec<PartialPreSignature> = wait_messages()?;
+/// let pre_signature = sign.complete(&partials)?;
+///
+/// // Owner of `y` completes the signature, which reveals `y` to the signers
+/// let signature = adapt(&pre_signature, &y).expect("y is a valid scalar");
+/// assert_eq!(extract_secret(&pre_signature, &signature, &adaptor_point).ok(), Some(y));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct AdaptorSignManual {
+    state: AdaptorRound7,
+}
+
+impl AdaptorSignManual {
+    pub fn new(
+        message: BigInt,
+        completed_offline_stage: CompletedOfflineStage,
+        adaptor_point: Point<Secp256k1>,
+    ) -> Result<(Self, AdaptorNonceShare), SignError> {
+        AdaptorRound7::new(&message, completed_offline_stage, adaptor_point)
+            .map(|(state, m)| (Self { state }, m))
+            .map_err(SignError::LocalSigning)
+    }
+
+    /// `shares` must be ordered by index of the parties and must not include share produced by
+    /// local party
+    pub fn partial_sign(
+        self,
+        shares: &[AdaptorNonceShare],
+    ) -> Result<(AdaptorPreSign, PartialPreSignature), SignError> {
+        self.state
+            .proceed_manual(shares)
+            .map(|(state, m)| (AdaptorPreSign { state }, m))
+            .map_err(SignError::LocalSigning)
+    }
+}
+
+/// Second stage of [AdaptorSignManual]
+#[derive(Clone)]
+pub struct AdaptorPreSign {
+    state: AdaptorRound8,
+}
+
+impl AdaptorPreSign {
+    /// `partials` must be ordered by index of the parties and must not include partial
+    /// pre-signature produced by local party
+    pub fn complete(self, partials: &[PartialPreSignature]) -> Result<PreSignature, SignError> {
+        self.state
+            .proceed_manual(partials)
+            .map_err(SignError::CompleteSigning)
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum SignError {
     #[error("signing message locally: {0}")]
@@ -661,22 +953,35 @@ mod test {
     use sha2::Sha256;
 
     use super::*;
+    use crate::utilities::adaptor;
+    use crate::utilities::verification::recover_public_key;
+    use curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
+    use curv::elliptic::curves::p256::Secp256r1;
     use gg20::party_i::verify;
-    use gg20::state_machine::keygen::test::simulate_keygen;
+    use gg20::state_machine::keygen::test::{simulate_keygen, simulate_keygen_on_curve};
 
     fn simulate_offline_stage(
         local_keys: Vec<LocalKey<Secp256k1>>,
         s_l: &[u16],
     ) -> Vec<CompletedOfflineStage> {
+        simulate_offline_stage_with_tweak(local_keys, s_l, Scalar::zero())
+    }
+
+    fn simulate_offline_stage_with_tweak<E: Curve>(
+        local_keys: Vec<LocalKey<E>>,
+        s_l: &[u16],
+        tweak: Scalar<E>,
+    ) -> Vec<CompletedOfflineStage<E>> {
         let mut simulation = Simulation::new();
         simulation.enable_benchmarks(true);
 
         for (i, &keygen_i) in (1..).zip(s_l) {
             simulation.add_party(
-                OfflineStage::new(
+                OfflineStage::new_with_tweak(
                     i,
                     s_l.to_vec(),
                     local_keys[usize::from(keygen_i - 1)].clone(),
+                    tweak.clone(),
                 )
                 .unwrap(),
             );
@@ -690,7 +995,7 @@ mod test {
         stages
     }
 
-    fn simulate_signing(offline: Vec<CompletedOfflineStage>, message: &[u8]) {
+    fn simulate_signing<E: Curve>(offline: Vec<CompletedOfflineStage<E>>, message: &[u8]) {
         let message = Sha256::new()
             .chain_bigint(&BigInt::from_bytes(message))
             .result_bigint();
@@ -761,4 +1066,126 @@ mod test {
         let offline_stage = simulate_offline_stage(local_keys, &[1, 2, 3]);
         simulate_signing(offline_stage, b"ZenGo")
     }
+
+    #[test]
+    fn simulate_signing_with_tweak_t1_n3_s2() {
+        let local_keys = simulate_keygen(1, 3);
+        let tweak = Scalar::<Secp256k1>::random();
+        let tweaked_public_key = local_keys[0].public_key() + Point::generator() * &tweak;
+
+        let offline_stage = simulate_offline_stage_with_tweak(local_keys, &[1, 3], tweak);
+        assert!(offline_stage
+            .iter()
+            .all(|o| o.public_key() == &tweaked_public_key));
+        simulate_signing(offline_stage, b"ZenGo");
+    }
+
+    #[test]
+    fn simulate_signing_p256_t1_n3_s2() {
+        let local_keys = simulate_keygen_on_curve::<Secp256r1>(1, 3);
+        let offline_stage = simulate_offline_stage_with_tweak(local_keys, &[1, 3], Scalar::zero());
+        let pk = offline_stage[0].public_key().clone();
+        let message = Sha256::new()
+            .chain_bigint(&BigInt::from_bytes(b"ZenGo"))
+            .result_bigint();
+
+        let (parties, local_sigs): (Vec<_>, Vec<_>) = offline_stage
+            .into_iter()
+            .map(|o| SignManual::new(message.clone(), o).unwrap())
+            .unzip();
+        for (i, party) in parties.into_iter().enumerate() {
+            let signature = party.complete(&except(&local_sigs, i)).unwrap();
+            assert!(verify(&signature, &pk, &message).is_ok());
+            // Recovery id must be computed against P-256 order, not secp256k1's
+            assert_eq!(recover_public_key(&signature, &message).unwrap(), pk);
+        }
+    }
+
+    fn except<T: Clone>(items: &[T], i: usize) -> Vec<T> {
+        let mut items = items.to_vec();
+        items.remove(i);
+        items
+    }
+
+    fn simulate_adaptor_signing(
+        offline: Vec<CompletedOfflineStage>,
+        message: &BigInt,
+        adaptor_point: &Point<Secp256k1>,
+    ) -> (Vec<AdaptorPreSign>, Vec<PartialPreSignature>) {
+        let (parties, shares): (Vec<_>, Vec<_>) = offline
+            .into_iter()
+            .map(|o| AdaptorSignManual::new(message.clone(), o, adaptor_point.clone()).unwrap())
+            .unzip();
+        parties
+            .into_iter()
+            .enumerate()
+            .map(|(i, p)| p.partial_sign(&except(&shares, i)).unwrap())
+            .unzip()
+    }
+
+    #[test]
+    fn adaptor_signing_t1_n3_s2() {
+        let local_keys = simulate_keygen(1, 3);
+        let offline_stage = simulate_offline_stage(local_keys, &[1, 3]);
+        let pk = offline_stage[0].public_key().clone();
+        let message = BigInt::from_bytes(b"ZenGo");
+        let y = Scalar::<Secp256k1>::random();
+        let adaptor_point = Point::generator() * &y;
+
+        let (parties, partials) = simulate_adaptor_signing(offline_stage, &message, &adaptor_point);
+        let pre_signatures = parties
+            .into_iter()
+            .enumerate()
+            .map(|(i, p)| p.complete(&except(&partials, i)).unwrap())
+            .collect::<Vec<_>>();
+
+        for pre_signature in &pre_signatures {
+            adaptor::verify_pre_signature(pre_signature, &pk, &adaptor_point, &message).unwrap();
+            let signature = adaptor::adapt(pre_signature, &y).unwrap();
+            assert!(verify(&signature, &pk, &message).is_ok());
+            let extracted = adaptor::extract_secret(pre_signature, &signature, &adaptor_point);
+            assert_eq!(extracted.unwrap(), y);
+        }
+    }
+
+    #[test]
+    fn adaptor_signing_identifies_invalid_nonce_share() {
+        let local_keys = simulate_keygen(2, 3);
+        let offline_stage = simulate_offline_stage(local_keys, &[1, 2, 3]);
+        let message = BigInt::from_bytes(b"ZenGo");
+        let adaptor_point = Point::generator() * Scalar::<Secp256k1>::random();
+
+        let (parties, mut partials) =
+            simulate_adaptor_signing(offline_stage, &message, &adaptor_point);
+        partials[1] = partials[2].clone();
+
+        match parties[0].clone().complete(&except(&partials, 0)) {
+            Err(SignError::CompleteSigning(ProceedError::AdaptorRound8InvalidProof(err))) => {
+                assert_eq!(err.bad_actors, vec![1])
+            }
+            _ => panic!("invalid nonce share isn't detected"),
+        }
+    }
+
+    #[test]
+    fn offline_stage_rejects_tweak_cancelling_public_key() {
+        let local_keys = simulate_keygen(1, 2);
+        // Find t such that y + tG = 0 by reconstructing the secret key
+        let s_l = [0, 1];
+        let x = local_keys
+            .iter()
+            .map(|k| {
+                let li = VerifiableSS::<Secp256k1>::map_share_to_new_params(
+                    &k.vss_scheme.parameters,
+                    k.i - 1,
+                    &s_l,
+                );
+                li * &k.keys_linear.x_i
+            })
+            .fold(Scalar::zero(), |acc, w| acc + w);
+        assert!(matches!(
+            OfflineStage::new_with_tweak(1, vec![1, 2], local_keys[0].clone(), -x),
+            Err(Error::InvalidTweak)
+        ));
+    }
 }
@@ -1,8 +1,9 @@
 use std::fmt;
 
+use curv::elliptic::curves::Curve;
 use round_based::containers::{BroadcastMsgsStore, MessageStore, P2PMsgsStore};
 
-impl fmt::Debug for super::OfflineStage {
+impl<E: Curve> fmt::Debug for super::OfflineStage<E> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         OfflineStageProgress::from(self).fmt(f)
     }
@@ -21,8 +22,8 @@ pub struct OfflineStageProgress {
     msgs_queue: OutgoingMessages,
 }
 
-impl From<&super::OfflineStage> for OfflineStageProgress {
-    fn from(state: &super::OfflineStage) -> Self {
+impl<E: Curve> From<&super::OfflineStage<E>> for OfflineStageProgress {
+    fn from(state: &super::OfflineStage<E>) -> Self {
         Self {
             round: match &state.round {
                 super::OfflineR::R0(_) => OfflineR::R0,
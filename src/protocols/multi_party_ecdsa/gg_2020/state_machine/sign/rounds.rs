@@ -6,7 +6,8 @@ use std::iter;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use curv::arithmetic::traits::*;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Curve, Point, Scalar};
 use curv::BigInt;
 use sha2::Sha256;
 
@@ -14,6 +15,7 @@ use round_based::containers::push::Push;
 use round_based::containers::{self, BroadcastMsgs, P2PMsgs, Store};
 use round_based::Msg;
 
+use crate::utilities::adaptor::{self, PreSignature};
 use crate::utilities::mta::{MessageA, MessageB};
 
 use crate::protocols::multi_party_ecdsa::gg_2020 as gg20;
@@ -29,26 +31,37 @@ use gg20::ErrorType;
 type Result<T, E = Error> = std::result::Result<T, E>;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(bound = "")]
 #[allow(clippy::upper_case_acronyms)]
-pub struct GWI(pub Point<Secp256k1>);
+pub struct GWI<E: Curve = Secp256k1>(pub Point<E>);
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct GammaI(pub MessageB);
+#[serde(bound = "")]
+pub struct GammaI<E: Curve = Secp256k1>(pub MessageB<E>);
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct WI(pub MessageB);
+#[serde(bound = "")]
+pub struct WI<E: Curve = Secp256k1>(pub MessageB<E>);
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct DeltaI(Scalar<Secp256k1>);
+#[serde(bound = "")]
+pub struct DeltaI<E: Curve = Secp256k1>(Scalar<E>);
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct TI(pub Point<Secp256k1>);
+#[serde(bound = "")]
+pub struct TI<E: Curve = Secp256k1>(pub Point<E>);
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct TIProof(pub PedersenProof<Secp256k1, Sha256>);
+#[serde(bound = "")]
+pub struct TIProof<E: Curve = Secp256k1>(pub PedersenProof<E, Sha256>);
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct RDash(Point<Secp256k1>);
+#[serde(bound = "")]
+pub struct RDash<E: Curve = Secp256k1>(Point<E>);
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct SI(pub Point<Secp256k1>);
+#[serde(bound = "")]
+pub struct SI<E: Curve = Secp256k1>(pub Point<E>);
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct HEGProof(pub HomoELGamalProof<Secp256k1, Sha256>);
+#[serde(bound = "")]
+pub struct HEGProof<E: Curve = Secp256k1>(pub HomoELGamalProof<E, Sha256>);
 
-pub struct Round0 {
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Round0<E: Curve = Secp256k1> {
     /// Index of this party
     ///
     /// Must be in range `[0; n)` where `n` is number of parties involved in signing.
@@ -61,11 +74,16 @@ pub struct Round0 {
     pub s_l: Vec<u16>,
 
     /// Party local secret share
-    pub local_key: LocalKey<Secp256k1>,
+    pub local_key: LocalKey<E>,
+
+    /// Additive tweak `t`: signature will be valid under public key `y + tG`
+    ///
+    /// Zero if signing under untweaked public key `y`.
+    pub tweak: Scalar<E>,
 }
 
-impl Round0 {
-    pub fn proceed<O>(self, mut output: O) -> Result<Round1>
+impl<E: Curve> Round0<E> {
+    pub fn proceed<O>(self, mut output: O) -> Result<Round1<E>>
     where
         O: Push<Msg<(MessageA, SignBroadcastPhase1)>>,
     {
@@ -98,6 +116,7 @@ impl Round0 {
             sign_keys,
             phase1_com: bc1,
             phase1_decom: decom1,
+            tweak: self.tweak,
         };
 
         Ok(round1)
@@ -108,24 +127,27 @@ impl Round0 {
     }
 }
 
-pub struct Round1 {
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Round1<E: Curve = Secp256k1> {
     i: u16,
     s_l: Vec<u16>,
-    local_key: LocalKey<Secp256k1>,
+    local_key: LocalKey<E>,
     m_a: (MessageA, BigInt),
-    sign_keys: SignKeys,
+    sign_keys: SignKeys<E>,
     phase1_com: SignBroadcastPhase1,
-    phase1_decom: SignDecommitPhase1,
+    phase1_decom: SignDecommitPhase1<E>,
+    tweak: Scalar<E>,
 }
 
-impl Round1 {
+impl<E: Curve> Round1<E> {
     pub fn proceed<O>(
         self,
         input: BroadcastMsgs<(MessageA, SignBroadcastPhase1)>,
         mut output: O,
-    ) -> Result<Round2>
+    ) -> Result<Round2<E>>
     where
-        O: Push<Msg<(GammaI, WI)>>,
+        O: Push<Msg<(GammaI<E>, WI<E>)>>,
     {
         let (m_a_vec, bc_vec): (Vec<_>, Vec<_>) = input
             .into_vec_including_me((self.m_a.0.clone(), self.phase1_com.clone()))
@@ -190,6 +212,7 @@ impl Round1 {
             bc_vec,
             m_a_vec,
             phase1_decom: self.phase1_decom,
+            tweak: self.tweak,
         })
     }
 
@@ -205,23 +228,30 @@ impl Round1 {
     }
 }
 
-pub struct Round2 {
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Round2<E: Curve = Secp256k1> {
     i: u16,
     s_l: Vec<u16>,
-    local_key: LocalKey<Secp256k1>,
-    sign_keys: SignKeys,
+    local_key: LocalKey<E>,
+    sign_keys: SignKeys<E>,
     m_a: (MessageA, BigInt),
-    beta_vec: Vec<Scalar<Secp256k1>>,
-    ni_vec: Vec<Scalar<Secp256k1>>,
+    beta_vec: Vec<Scalar<E>>,
+    ni_vec: Vec<Scalar<E>>,
     bc_vec: Vec<SignBroadcastPhase1>,
     m_a_vec: Vec<MessageA>,
-    phase1_decom: SignDecommitPhase1,
+    phase1_decom: SignDecommitPhase1<E>,
+    tweak: Scalar<E>,
 }
 
-impl Round2 {
-    pub fn proceed<O>(self, input_p2p: P2PMsgs<(GammaI, WI)>, mut output: O) -> Result<Round3>
+impl<E: Curve> Round2<E> {
+    pub fn proceed<O>(
+        self,
+        input_p2p: P2PMsgs<(GammaI<E>, WI<E>)>,
+        mut output: O,
+    ) -> Result<Round3<E>>
     where
-        O: Push<Msg<(DeltaI, TI, TIProof)>>, // TODO: unify TI and TIProof
+        O: Push<Msg<(DeltaI<E>, TI<E>, TIProof<E>)>>, // TODO: unify TI and TIProof
     {
         let (m_b_gamma_s, m_b_w_s): (Vec<_>, Vec<_>) = input_p2p
             .into_vec()
@@ -264,7 +294,10 @@ impl Round2 {
 
         let delta_i = self.sign_keys.phase2_delta_i(&alpha_vec, &self.beta_vec);
 
-        let sigma_i = self.sign_keys.phase2_sigma_i(&miu_vec, &self.ni_vec);
+        // sigma_i are additive shares of `k * x`. Signing under `x + t` requires shares of
+        // `k * (x + t)`, and `sum(t * k_i) = t * k`, so every party adds `t * k_i` to its share
+        let sigma_i = self.sign_keys.phase2_sigma_i(&miu_vec, &self.ni_vec)
+            + &self.tweak * &self.sign_keys.k_i;
         let (t_i, l_i, t_i_proof) = SignKeys::phase3_compute_t_i(&sigma_i);
         output.push(Msg {
             sender: self.i,
@@ -291,10 +324,11 @@ impl Round2 {
             sigma_i,
             t_i_proof,
             phase1_decom: self.phase1_decom,
+            tweak: self.tweak,
         })
     }
 
-    pub fn expects_messages(i: u16, n: u16) -> Store<P2PMsgs<(GammaI, WI)>> {
+    pub fn expects_messages(i: u16, n: u16) -> Store<P2PMsgs<(GammaI<E>, WI<E>)>> {
         containers::P2PMsgsStore::new(i, n)
     }
 
@@ -303,32 +337,35 @@ impl Round2 {
     }
 }
 
-pub struct Round3 {
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Round3<E: Curve = Secp256k1> {
     i: u16,
     s_l: Vec<u16>,
-    local_key: LocalKey<Secp256k1>,
-    sign_keys: SignKeys,
+    local_key: LocalKey<E>,
+    sign_keys: SignKeys<E>,
     m_a: (MessageA, BigInt),
-    mb_gamma_s: Vec<MessageB>,
+    mb_gamma_s: Vec<MessageB<E>>,
     bc_vec: Vec<SignBroadcastPhase1>,
     m_a_vec: Vec<MessageA>,
-    delta_i: Scalar<Secp256k1>,
-    t_i: Point<Secp256k1>,
-    l_i: Scalar<Secp256k1>,
-    sigma_i: Scalar<Secp256k1>,
-    t_i_proof: PedersenProof<Secp256k1, Sha256>,
-
-    phase1_decom: SignDecommitPhase1,
+    delta_i: Scalar<E>,
+    t_i: Point<E>,
+    l_i: Scalar<E>,
+    sigma_i: Scalar<E>,
+    t_i_proof: PedersenProof<E, Sha256>,
+
+    phase1_decom: SignDecommitPhase1<E>,
+    tweak: Scalar<E>,
 }
 
-impl Round3 {
+impl<E: Curve> Round3<E> {
     pub fn proceed<O>(
         self,
-        input: BroadcastMsgs<(DeltaI, TI, TIProof)>,
+        input: BroadcastMsgs<(DeltaI<E>, TI<E>, TIProof<E>)>,
         mut output: O,
-    ) -> Result<Round4>
+    ) -> Result<Round4<E>>
     where
-        O: Push<Msg<SignDecommitPhase1>>,
+        O: Push<Msg<SignDecommitPhase1<E>>>,
     {
         let (delta_vec, t_vec, t_proof_vec) = input
             .into_vec_including_me((
@@ -371,10 +408,14 @@ impl Round3 {
             phase1_decom: self.phase1_decom,
             delta_inv,
             t_vec,
+            tweak: self.tweak,
         })
     }
 
-    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<(DeltaI, TI, TIProof)>> {
+    pub fn expects_messages(
+        i: u16,
+        n: u16,
+    ) -> Store<BroadcastMsgs<(DeltaI<E>, TI<E>, TIProof<E>)>> {
         containers::BroadcastMsgsStore::new(i, n)
     }
 
@@ -383,33 +424,37 @@ impl Round3 {
     }
 }
 
-pub struct Round4 {
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Round4<E: Curve = Secp256k1> {
     i: u16,
     s_l: Vec<u16>,
-    local_key: LocalKey<Secp256k1>,
-    sign_keys: SignKeys,
+    local_key: LocalKey<E>,
+    sign_keys: SignKeys<E>,
     m_a: (MessageA, BigInt),
-    mb_gamma_s: Vec<MessageB>,
+    mb_gamma_s: Vec<MessageB<E>>,
     bc_vec: Vec<SignBroadcastPhase1>,
     m_a_vec: Vec<MessageA>,
-    t_i: Point<Secp256k1>,
-    l_i: Scalar<Secp256k1>,
-    sigma_i: Scalar<Secp256k1>,
-    delta_inv: Scalar<Secp256k1>,
-    t_vec: Vec<Point<Secp256k1>>,
-    phase1_decom: SignDecommitPhase1,
+    t_i: Point<E>,
+    l_i: Scalar<E>,
+    sigma_i: Scalar<E>,
+    delta_inv: Scalar<E>,
+    t_vec: Vec<Point<E>>,
+    phase1_decom: SignDecommitPhase1<E>,
+    tweak: Scalar<E>,
 }
 
-impl Round4 {
+impl<E: Curve> Round4<E> {
     pub fn proceed<O>(
         self,
-        decommit_round1: BroadcastMsgs<SignDecommitPhase1>,
+        decommit_round1: BroadcastMsgs<SignDecommitPhase1<E>>,
         mut output: O,
-    ) -> Result<Round5>
+    ) -> Result<Round5<E>>
     where
-        O: Push<Msg<(RDash, Vec<PDLwSlackProof>)>>,
+        O: Push<Msg<(RDash<E>, Vec<PDLwSlackProof<E>>)>>,
     {
         let decom_vec: Vec<_> = decommit_round1.into_vec_including_me(self.phase1_decom.clone());
+        let g_gamma_vec: Vec<_> = decom_vec.iter().map(|d| d.g_gamma_i.clone()).collect();
 
         let ttag = self.s_l.len();
         let b_proof_vec: Vec<_> = (0..ttag - 1).map(|i| &self.mb_gamma_s[i].b_proof).collect();
@@ -466,10 +511,13 @@ impl Round4 {
             R,
             R_dash,
             phase5_proofs_vec,
+            delta_inv: self.delta_inv,
+            g_gamma_vec,
+            tweak: self.tweak,
         })
     }
 
-    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<SignDecommitPhase1>> {
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<SignDecommitPhase1<E>>> {
         containers::BroadcastMsgsStore::new(i, n)
     }
 
@@ -478,29 +526,34 @@ impl Round4 {
     }
 }
 
-pub struct Round5 {
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Round5<E: Curve = Secp256k1> {
     i: u16,
     s_l: Vec<u16>,
-    local_key: LocalKey<Secp256k1>,
-    sign_keys: SignKeys,
-    t_vec: Vec<Point<Secp256k1>>,
+    local_key: LocalKey<E>,
+    sign_keys: SignKeys<E>,
+    t_vec: Vec<Point<E>>,
     m_a_vec: Vec<MessageA>,
-    t_i: Point<Secp256k1>,
-    l_i: Scalar<Secp256k1>,
-    sigma_i: Scalar<Secp256k1>,
-    R: Point<Secp256k1>,
-    R_dash: Point<Secp256k1>,
-    phase5_proofs_vec: Vec<PDLwSlackProof>,
+    t_i: Point<E>,
+    l_i: Scalar<E>,
+    sigma_i: Scalar<E>,
+    R: Point<E>,
+    R_dash: Point<E>,
+    phase5_proofs_vec: Vec<PDLwSlackProof<E>>,
+    delta_inv: Scalar<E>,
+    g_gamma_vec: Vec<Point<E>>,
+    tweak: Scalar<E>,
 }
 
-impl Round5 {
+impl<E: Curve> Round5<E> {
     pub fn proceed<O>(
         self,
-        input: BroadcastMsgs<(RDash, Vec<PDLwSlackProof>)>,
+        input: BroadcastMsgs<(RDash<E>, Vec<PDLwSlackProof<E>>)>,
         mut output: O,
-    ) -> Result<Round6>
+    ) -> Result<Round6<E>>
     where
-        O: Push<Msg<(SI, HEGProof)>>,
+        O: Push<Msg<(SI<E>, HEGProof<E>)>>,
     {
         let (r_dash_vec, pdl_proof_mat_inc_me): (Vec<_>, Vec<_>) = input
             .into_vec_including_me((RDash(self.R_dash), self.phase5_proofs_vec))
@@ -549,16 +602,22 @@ impl Round5 {
             s_l: self.s_l,
             protocol_output: CompletedOfflineStage {
                 i: self.i,
+                public_key: &self.local_key.y_sum_s + Point::generator() * &self.tweak,
                 local_key: self.local_key,
                 sign_keys: self.sign_keys,
                 t_vec: self.t_vec,
                 R: self.R,
                 sigma_i: self.sigma_i,
+                delta_inv: self.delta_inv,
+                g_gamma_vec: self.g_gamma_vec,
             },
         })
     }
 
-    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<(RDash, Vec<PDLwSlackProof>)>> {
+    pub fn expects_messages(
+        i: u16,
+        n: u16,
+    ) -> Store<BroadcastMsgs<(RDash<E>, Vec<PDLwSlackProof<E>>)>> {
         containers::BroadcastMsgsStore::new(i, n)
     }
 
@@ -567,19 +626,21 @@ impl Round5 {
     }
 }
 
-pub struct Round6 {
-    S_i: Point<Secp256k1>,
-    homo_elgamal_proof: HomoELGamalProof<Secp256k1, Sha256>,
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Round6<E: Curve = Secp256k1> {
+    S_i: Point<E>,
+    homo_elgamal_proof: HomoELGamalProof<E, Sha256>,
     s_l: Vec<u16>,
     /// Round 6 guards protocol output until final checks are taken the place
-    protocol_output: CompletedOfflineStage,
+    protocol_output: CompletedOfflineStage<E>,
 }
 
-impl Round6 {
+impl<E: Curve> Round6<E> {
     pub fn proceed(
         self,
-        input: BroadcastMsgs<(SI, HEGProof)>,
-    ) -> Result<CompletedOfflineStage, Error> {
+        input: BroadcastMsgs<(SI<E>, HEGProof<E>)>,
+    ) -> Result<CompletedOfflineStage<E>, Error> {
         let (S_i_vec, hegp_vec): (Vec<_>, Vec<_>) = input
             .into_vec_including_me((SI(self.S_i), HEGProof(self.homo_elgamal_proof)))
             .into_iter()
@@ -596,13 +657,13 @@ impl Round6 {
             &self.protocol_output.t_vec,
         )
         .map_err(Error::Round6VerifyProof)?;
-        LocalSignature::phase6_check_S_i_sum(&self.protocol_output.local_key.y_sum_s, &S_i_vec)
+        LocalSignature::phase6_check_S_i_sum(&self.protocol_output.public_key, &S_i_vec)
             .map_err(Error::Round6CheckSig)?;
 
         Ok(self.protocol_output)
     }
 
-    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<(SI, HEGProof)>> {
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<(SI<E>, HEGProof<E>)>> {
         containers::BroadcastMsgsStore::new(i, n)
     }
 
@@ -611,47 +672,59 @@ impl Round6 {
     }
 }
 
-#[derive(Clone)]
-pub struct CompletedOfflineStage {
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct CompletedOfflineStage<E: Curve = Secp256k1> {
     i: u16,
-    local_key: LocalKey<Secp256k1>,
-    sign_keys: SignKeys,
-    t_vec: Vec<Point<Secp256k1>>,
-    R: Point<Secp256k1>,
-    sigma_i: Scalar<Secp256k1>,
+    local_key: LocalKey<E>,
+    /// Public key the signature will be valid under (tweaked, if tweak was set)
+    public_key: Point<E>,
+    sign_keys: SignKeys<E>,
+    t_vec: Vec<Point<E>>,
+    R: Point<E>,
+    sigma_i: Scalar<E>,
+    /// `δ^-1`, such that `R = δ^-1 Σ Γ_j`
+    delta_inv: Scalar<E>,
+    /// `Γ_j = γ_j G` of every signer
+    g_gamma_vec: Vec<Point<E>>,
 }
 
-impl CompletedOfflineStage {
-    pub fn public_key(&self) -> &Point<Secp256k1> {
-        &self.local_key.y_sum_s
+impl<E: Curve> CompletedOfflineStage<E> {
+    /// Public key the resulting signature is valid under
+    ///
+    /// It's `y + tG` if offline stage was constructed [with tweak](super::OfflineStage::new_with_tweak),
+    /// or just `y` otherwise.
+    pub fn public_key(&self) -> &Point<E> {
+        &self.public_key
     }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct PartialSignature(Scalar<Secp256k1>);
+#[serde(bound = "")]
+pub struct PartialSignature<E: Curve = Secp256k1>(Scalar<E>);
 
 #[derive(Clone)]
-pub struct Round7 {
-    local_signature: LocalSignature,
+pub struct Round7<E: Curve = Secp256k1> {
+    local_signature: LocalSignature<E>,
 }
 
-impl Round7 {
+impl<E: Curve> Round7<E> {
     pub fn new(
         message: &BigInt,
-        completed_offline_stage: CompletedOfflineStage,
-    ) -> Result<(Self, PartialSignature)> {
+        completed_offline_stage: CompletedOfflineStage<E>,
+    ) -> Result<(Self, PartialSignature<E>)> {
         let local_signature = LocalSignature::phase7_local_sig(
             &completed_offline_stage.sign_keys.k_i,
             message,
             &completed_offline_stage.R,
             &completed_offline_stage.sigma_i,
-            &completed_offline_stage.local_key.y_sum_s,
+            &completed_offline_stage.public_key,
         );
         let partial = PartialSignature(local_signature.s_i.clone());
         Ok((Self { local_signature }, partial))
     }
 
-    pub fn proceed_manual(self, sigs: &[PartialSignature]) -> Result<SignatureRecid> {
+    pub fn proceed_manual(self, sigs: &[PartialSignature<E>]) -> Result<SignatureRecid<E>> {
         let sigs = sigs.iter().map(|s_i| s_i.0.clone()).collect::<Vec<_>>();
         self.local_signature
             .output_signature(&sigs)
@@ -659,6 +732,178 @@ impl Round7 {
     }
 }
 
+/// Share of adaptor nonce point `R_Y = δ^-1 Σ γ_j Y` along with commitments `ρ_i G`, `ρ_i Y` to
+/// the joint proof that `log_G R = log_Y R_Y`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AdaptorNonceShare {
+    gamma_y: Point<Secp256k1>,
+    a1: Point<Secp256k1>,
+    a2: Point<Secp256k1>,
+}
+
+/// Partial pre-signature along with response `z_i = ρ_i + e γ_i` to the joint proof
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PartialPreSignature {
+    s_i: Scalar<Secp256k1>,
+    z_i: Scalar<Secp256k1>,
+}
+
+#[derive(Clone)]
+pub struct AdaptorRound7 {
+    i: u16,
+    message: BigInt,
+    adaptor_point: Point<Secp256k1>,
+    completed_offline_stage: CompletedOfflineStage,
+    rho_i: Scalar<Secp256k1>,
+    nonce_share: AdaptorNonceShare,
+}
+
+impl AdaptorRound7 {
+    pub fn new(
+        message: &BigInt,
+        completed_offline_stage: CompletedOfflineStage,
+        adaptor_point: Point<Secp256k1>,
+    ) -> Result<(Self, AdaptorNonceShare)> {
+        if adaptor_point.is_zero() {
+            return Err(Error::AdaptorRound7(crate::Error::InvalidKey));
+        }
+        let rho_i = Scalar::<Secp256k1>::random();
+        let nonce_share = AdaptorNonceShare {
+            gamma_y: &adaptor_point * &completed_offline_stage.sign_keys.gamma_i,
+            a1: Point::generator() * &rho_i,
+            a2: &adaptor_point * &rho_i,
+        };
+        Ok((
+            Self {
+                i: completed_offline_stage.i,
+                message: message.clone(),
+                adaptor_point,
+                completed_offline_stage,
+                rho_i,
+                nonce_share: nonce_share.clone(),
+            },
+            nonce_share,
+        ))
+    }
+
+    /// `shares` must be ordered by index of the parties and not include share of local party
+    pub fn proceed_manual(
+        self,
+        shares: &[AdaptorNonceShare],
+    ) -> Result<(AdaptorRound8, PartialPreSignature)> {
+        let completed = self.completed_offline_stage;
+        let mut shares = shares.to_vec();
+        if shares.len() + 1 != completed.g_gamma_vec.len() {
+            return Err(Error::AdaptorRound7(crate::Error::InvalidSig));
+        }
+        shares.insert(usize::from(self.i - 1), self.nonce_share);
+
+        let sum = |points: &mut dyn Iterator<Item = &Point<Secp256k1>>| {
+            points.fold(Point::zero(), |acc, p| acc + p) * &completed.delta_inv
+        };
+        let R_Y = sum(&mut shares.iter().map(|s| &s.gamma_y));
+        let a1 = sum(&mut shares.iter().map(|s| &s.a1));
+        let a2 = sum(&mut shares.iter().map(|s| &s.a2));
+        let e = adaptor::challenge(&completed.R, &self.adaptor_point, &R_Y, &a1, &a2);
+        let r = Scalar::<Secp256k1>::from(
+            &R_Y.x_coord()
+                .ok_or(Error::AdaptorRound7(crate::Error::InvalidSig))?
+                .mod_floor(Scalar::<Secp256k1>::group_order()),
+        );
+
+        let partial = PartialPreSignature {
+            s_i: Scalar::from(&self.message) * &completed.sign_keys.k_i + r * &completed.sigma_i,
+            z_i: self.rho_i + &e * &completed.sign_keys.gamma_i,
+        };
+        Ok((
+            AdaptorRound8 {
+                i: self.i,
+                message: self.message,
+                adaptor_point: self.adaptor_point,
+                public_key: completed.public_key,
+                R: completed.R,
+                R_Y,
+                a1,
+                a2,
+                e,
+                delta_inv: completed.delta_inv,
+                g_gamma_vec: completed.g_gamma_vec,
+                nonce_shares: shares,
+                partial: partial.clone(),
+            },
+            partial,
+        ))
+    }
+}
+
+#[derive(Clone)]
+pub struct AdaptorRound8 {
+    i: u16,
+    message: BigInt,
+    adaptor_point: Point<Secp256k1>,
+    public_key: Point<Secp256k1>,
+    R: Point<Secp256k1>,
+    R_Y: Point<Secp256k1>,
+    a1: Point<Secp256k1>,
+    a2: Point<Secp256k1>,
+    e: Scalar<Secp256k1>,
+    delta_inv: Scalar<Secp256k1>,
+    g_gamma_vec: Vec<Point<Secp256k1>>,
+    nonce_shares: Vec<AdaptorNonceShare>,
+    partial: PartialPreSignature,
+}
+
+impl AdaptorRound8 {
+    /// `partials` must be ordered by index of the parties and not include partial pre-signature
+    /// of local party
+    pub fn proceed_manual(self, partials: &[PartialPreSignature]) -> Result<PreSignature> {
+        let mut partials = partials.to_vec();
+        if partials.len() + 1 != self.g_gamma_vec.len() {
+            return Err(Error::AdaptorRound8(crate::Error::InvalidSig));
+        }
+        partials.insert(usize::from(self.i - 1), self.partial);
+
+        let (e, adaptor_point) = (&self.e, &self.adaptor_point);
+        let bad_actors = partials
+            .iter()
+            .zip(&self.nonce_shares)
+            .zip(&self.g_gamma_vec)
+            .enumerate()
+            .filter(|(_, ((partial, share), g_gamma_j))| {
+                Point::generator() * &partial.z_i != &share.a1 + *g_gamma_j * e
+                    || adaptor_point * &partial.z_i != &share.a2 + &share.gamma_y * e
+            })
+            .map(|(j, _)| j)
+            .collect::<Vec<_>>();
+        if !bad_actors.is_empty() {
+            return Err(Error::AdaptorRound8InvalidProof(ErrorType {
+                error_type: "invalid adaptor nonce share".to_string(),
+                bad_actors,
+            }));
+        }
+
+        let (s_hat, z) = partials
+            .iter()
+            .fold((Scalar::zero(), Scalar::zero()), |(s, z), partial| {
+                (s + &partial.s_i, z + &partial.z_i)
+            });
+        let pre_signature = PreSignature {
+            R: self.R,
+            R_Y: self.R_Y,
+            s_hat,
+            proof: adaptor::proof(self.a1, self.a2, z * self.delta_inv),
+        };
+        adaptor::verify_pre_signature(
+            &pre_signature,
+            &self.public_key,
+            &self.adaptor_point,
+            &self.message,
+        )
+        .map_err(Error::AdaptorRound8)?;
+        Ok(pre_signature)
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("round 1: {0:?}")]
@@ -677,6 +922,12 @@ pub enum Error {
     Round6CheckSig(crate::Error),
     #[error("round 7: {0:?}")]
     Round7(crate::Error),
+    #[error("adaptor round 7: {0:?}")]
+    AdaptorRound7(crate::Error),
+    #[error("adaptor round 8: invalid proof: {0:?}")]
+    AdaptorRound8InvalidProof(ErrorType),
+    #[error("adaptor round 8: {0:?}")]
+    AdaptorRound8(crate::Error),
 }
 
 trait IteratorExt: Iterator {
@@ -723,9 +723,9 @@ fn check_sig(r: &Scalar<Secp256k1>, s: &Scalar<Secp256k1>, msg: &BigInt, pk: &Po
         // after curv's pk_to_key_slice return 65 bytes, this can be removed
         raw_pk.insert(0, 4u8);
         raw_pk.extend(vec![0u8; 64 - slice.len()]);
-        raw_pk.extend(slice.as_ref());
+        raw_pk.extend_from_slice(&slice);
     } else {
-        raw_pk.extend(slice.as_ref());
+        raw_pk.extend_from_slice(&slice);
     }
 
     assert_eq!(raw_pk.len(), 65);
@@ -764,7 +764,7 @@ fn test_serialize_deserialize() {
 #[test]
 fn test_small_paillier() {
     // parties shouldn't be able to choose small Paillier modulus
-    let mut k = Keys::create(0);
+    let mut k: Keys = Keys::create(0);
     // creating 2046-bit Paillier
     let (ek, dk) = Paillier::keypair_with_modulus_size(2046).keys();
     k.dk = dk;
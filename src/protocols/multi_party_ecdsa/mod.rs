@@ -14,5 +14,6 @@
     @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
 */
 
+pub mod cggmp_2021;
 pub mod gg_2018;
 pub mod gg_2020;
@@ -0,0 +1,130 @@
+//! Ed25519 signatures
+//!
+//! Points and scalars are encoded as in [RFC8032](https://datatracker.ietf.org/doc/html/rfc8032):
+//! a point is 32 bytes of its y coordinate with sign of x in the top bit, a scalar is 32 bytes
+//! little-endian.
+
+use curv::arithmetic::traits::*;
+use curv::elliptic::curves::{Ed25519, Point, Scalar};
+use curv::BigInt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha512};
+use thiserror::Error;
+
+/// Ed25519 signature `(R, s)`, `r` is encoded nonce point `R`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Signature {
+    pub r: [u8; 32],
+    pub s: Scalar<Ed25519>,
+}
+
+impl Signature {
+    /// Encodes signature as 64 bytes `R || s`
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(&self.r);
+        bytes[32..].copy_from_slice(&self.s.to_bytes());
+        bytes
+    }
+
+    /// Decodes signature from 64 bytes `R || s`
+    ///
+    /// Returns `None` if `s` is not less than group order.
+    pub fn from_bytes(bytes: &[u8; 64]) -> Option<Self> {
+        let mut r = [0u8; 32];
+        r.copy_from_slice(&bytes[..32]);
+        let s = from_le_bytes(&bytes[32..]);
+        if &s >= Scalar::<Ed25519>::group_order() {
+            return None;
+        }
+        Some(Self {
+            r,
+            s: Scalar::from(&s),
+        })
+    }
+}
+
+/// Signature didn't pass verification
+#[derive(Debug, Error)]
+pub enum VerifyError {
+    #[error("public key is not a valid Ed25519 point")]
+    InvalidPublicKey,
+    #[error("signature is invalid")]
+    InvalidSignature,
+}
+
+/// Verifies Ed25519 signature of `message` under encoded public key `public_key`
+///
+/// Checks `s * B = R + k * A` without multiplying by the cofactor. Public key and nonce point
+/// must be in the prime order subgroup.
+pub fn verify(
+    signature: &Signature,
+    public_key: &[u8; 32],
+    message: &[u8],
+) -> Result<(), VerifyError> {
+    let a = Point::<Ed25519>::from_bytes(public_key).map_err(|_| VerifyError::InvalidPublicKey)?;
+    let r =
+        Point::<Ed25519>::from_bytes(&signature.r).map_err(|_| VerifyError::InvalidSignature)?;
+    let k = challenge(&signature.r, public_key, message);
+    if Point::generator() * &signature.s != r + a * k {
+        return Err(VerifyError::InvalidSignature);
+    }
+    Ok(())
+}
+
+/// Encodes point as 32 bytes
+pub fn encode_point(point: &Point<Ed25519>) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out.copy_from_slice(&point.to_bytes(true));
+    out
+}
+
+/// Challenge `k = SHA512(R || A || M)`
+pub fn challenge(r: &[u8; 32], public_key: &[u8; 32], message: &[u8]) -> Scalar<Ed25519> {
+    hash_to_scalar(&[r, public_key, message])
+}
+
+/// `SHA512` of concatenated `chunks` interpreted as little-endian integer modulo group order
+pub fn hash_to_scalar(chunks: &[&[u8]]) -> Scalar<Ed25519> {
+    let hash = chunks
+        .iter()
+        .fold(Sha512::new(), |hash, chunk| hash.chain(chunk))
+        .finalize();
+    Scalar::from(&from_le_bytes(&hash))
+}
+
+fn from_le_bytes(bytes: &[u8]) -> BigInt {
+    let mut bytes = bytes.to_vec();
+    bytes.reverse();
+    BigInt::from_bytes(&bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn decode<const N: usize>(s: &str) -> [u8; N] {
+        let mut out = [0u8; N];
+        out.copy_from_slice(&hex::decode(s).unwrap());
+        out
+    }
+
+    #[test]
+    fn verifies_rfc8032_test_vector() {
+        // Test 1 from RFC8032, section 7.1
+        let public_key =
+            decode::<32>("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a");
+        let signature = Signature::from_bytes(&decode::<64>(
+            "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e06522490155\
+             5fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+        ))
+        .unwrap();
+
+        verify(&signature, &public_key, b"").unwrap();
+        assert_eq!(
+            Signature::from_bytes(&signature.to_bytes()),
+            Some(signature.clone())
+        );
+        assert!(verify(&signature, &public_key, b"a message").is_err());
+    }
+}
@@ -0,0 +1,38 @@
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+//! Threshold EdDSA
+//!
+//! Threshold signing producing [Ed25519](https://datatracker.ietf.org/doc/html/rfc8032)
+//! signatures, verifiable by any standard Ed25519 implementation under the joint public key.
+//!
+//! Key generation is a Feldman VSS based DKG: every party shares a random secret with
+//! `VerifiableSS<Ed25519>` and proves knowledge of it. The proof of knowledge only prevents
+//! rogue-key attacks, i.e. choosing a contribution that cancels out the others'. Commitments are
+//! not committed to beforehand, so a rushing party that sees the others' commitments first can
+//! still bias the distribution of the joint key (e.g. by aborting). This is the PedPoP DKG from the
+//! FROST paper, which is shown to be sufficient for Schnorr signing. Signing is
+//! [FROST](https://eprint.iacr.org/2020/852) two-round signing. Both protocols are [round_based]
+//! state machines with the same API shape as
+//! [GG20](crate::protocols::multi_party_ecdsa::gg_2020::state_machine): [Keygen] outputs [LocalKey]
+//! which is then given to [Sign].
+//!
+//! [Keygen]: state_machine::keygen::Keygen
+//! [LocalKey]: state_machine::keygen::LocalKey
+//! [Sign]: state_machine::sign::Sign
+
+pub mod ed25519;
+pub mod state_machine;
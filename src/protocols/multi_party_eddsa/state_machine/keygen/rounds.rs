@@ -0,0 +1,226 @@
+use curv::cryptographic_primitives::proofs::sigma_dlog::DLogProof;
+use curv::cryptographic_primitives::secret_sharing::feldman_vss::{
+    ShamirSecretSharing, VerifiableSS,
+};
+use curv::elliptic::curves::{Ed25519, Point, Scalar};
+use round_based::containers::push::Push;
+use round_based::containers::{self, BroadcastMsgs, P2PMsgs, Store};
+use round_based::Msg;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use thiserror::Error;
+
+use super::super::super::ed25519;
+
+/// Feldman VSS commitments to party's secret along with proof of knowledge of the secret, sent at
+/// round 1
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VssCommitment {
+    pub vss: VerifiableSS<Ed25519>,
+    pub proof: DLogProof<Ed25519, Sha256>,
+}
+
+/// Share of sender's secret addressed to the receiver, sent at round 2
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SecretShare(pub Scalar<Ed25519>);
+
+pub struct Round0 {
+    pub party_i: u16,
+    pub t: u16,
+    pub n: u16,
+}
+
+impl Round0 {
+    pub fn proceed<O>(self, mut output: O) -> Result<Round1>
+    where
+        O: Push<Msg<VssCommitment>>,
+    {
+        let secret = Scalar::random();
+        let (vss, shares) = VerifiableSS::share(self.t, self.n, &secret);
+        let commitment = VssCommitment {
+            vss,
+            proof: DLogProof::prove(&secret),
+        };
+        output.push(Msg {
+            sender: self.party_i,
+            receiver: None,
+            body: commitment.clone(),
+        });
+        Ok(Round1 {
+            party_i: self.party_i,
+            t: self.t,
+            n: self.n,
+            commitment,
+            shares: shares.to_vec(),
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        false
+    }
+}
+
+pub struct Round1 {
+    party_i: u16,
+    t: u16,
+    n: u16,
+    commitment: VssCommitment,
+    shares: Vec<Scalar<Ed25519>>,
+}
+
+impl Round1 {
+    pub fn proceed<O>(self, input: BroadcastMsgs<VssCommitment>, mut output: O) -> Result<Round2>
+    where
+        O: Push<Msg<SecretShare>>,
+    {
+        let parameters = ShamirSecretSharing {
+            threshold: self.t,
+            share_count: self.n,
+        };
+        let commitments = input.into_vec_including_me(self.commitment);
+        let culprits = (1..)
+            .zip(&commitments)
+            .filter(|(_, c)| {
+                c.vss.parameters != parameters
+                    || c.vss.commitments.len() != usize::from(parameters.threshold) + 1
+                    || c.vss.commitments[0] != c.proof.pk
+                    || DLogProof::verify(&c.proof).is_err()
+            })
+            .map(|(j, _)| j)
+            .collect::<Vec<u16>>();
+        if !culprits.is_empty() {
+            return Err(ProceedError::Round1InvalidCommitment { culprits });
+        }
+
+        for (j, share) in (1..).zip(&self.shares) {
+            if j != self.party_i {
+                output.push(Msg {
+                    sender: self.party_i,
+                    receiver: Some(j),
+                    body: SecretShare(share.clone()),
+                });
+            }
+        }
+
+        Ok(Round2 {
+            party_i: self.party_i,
+            t: self.t,
+            n: self.n,
+            vss_vec: commitments.into_iter().map(|c| c.vss).collect(),
+            own_share: self.shares[usize::from(self.party_i - 1)].clone(),
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<VssCommitment>> {
+        containers::BroadcastMsgsStore::new(i, n)
+    }
+}
+
+pub struct Round2 {
+    party_i: u16,
+    t: u16,
+    n: u16,
+    vss_vec: Vec<VerifiableSS<Ed25519>>,
+    own_share: Scalar<Ed25519>,
+}
+
+impl Round2 {
+    pub fn proceed(self, input: P2PMsgs<SecretShare>) -> Result<LocalKey> {
+        let Round2 {
+            party_i,
+            t,
+            n,
+            vss_vec,
+            own_share,
+        } = self;
+        let shares = input.into_vec_including_me(SecretShare(own_share));
+        let culprits = (1..)
+            .zip(shares.iter().zip(&vss_vec))
+            .filter(|(_, (share, vss))| vss.validate_share(&share.0, party_i).is_err())
+            .map(|(j, _)| j)
+            .collect::<Vec<u16>>();
+        if !culprits.is_empty() {
+            return Err(ProceedError::Round2InvalidShare { culprits });
+        }
+
+        let x_i = shares
+            .into_iter()
+            .fold(Scalar::zero(), |acc, share| acc + share.0);
+        let commitments = (0..=usize::from(t))
+            .map(|k| {
+                vss_vec
+                    .iter()
+                    .fold(Point::zero(), |acc, vss| acc + &vss.commitments[k])
+            })
+            .collect::<Vec<_>>();
+        let vss_scheme = VerifiableSS {
+            parameters: vss_vec[0].parameters.clone(),
+            commitments,
+        };
+        let pk_vec = (1..=n)
+            .map(|j| vss_scheme.get_point_commitment(j))
+            .collect::<Vec<_>>();
+
+        Ok(LocalKey {
+            y_sum_s: vss_scheme.commitments[0].clone(),
+            x_i,
+            vss_scheme,
+            pk_vec,
+            i: party_i,
+            t,
+            n,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        false
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<P2PMsgs<SecretShare>> {
+        containers::P2PMsgsStore::new(i, n)
+    }
+}
+
+/// Local secret obtained by party after [keygen](super::Keygen) protocol is completed
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LocalKey {
+    /// Party's share of the secret key
+    pub x_i: Scalar<Ed25519>,
+    /// Sum of Feldman VSS commitments of all parties, i.e. commitments to the joint polynomial
+    pub vss_scheme: VerifiableSS<Ed25519>,
+    /// `x_j * G` of every party
+    pub pk_vec: Vec<Point<Ed25519>>,
+    /// Joint public key
+    pub y_sum_s: Point<Ed25519>,
+
+    pub i: u16,
+    pub t: u16,
+    pub n: u16,
+}
+
+impl LocalKey {
+    /// Public key of secret shared between parties
+    pub fn public_key(&self) -> Point<Ed25519> {
+        self.y_sum_s.clone()
+    }
+
+    /// Public key encoded as 32 bytes, as accepted by Ed25519 verifiers
+    pub fn public_key_bytes(&self) -> [u8; 32] {
+        ed25519::encode_point(&self.y_sum_s)
+    }
+}
+
+// Errors
+
+type Result<T> = std::result::Result<T, ProceedError>;
+
+/// Proceeding protocol error
+///
+/// Subset of [keygen errors](enum@super::Error) that can occur at protocol proceeding (i.e. after
+/// every message was received and pre-validated).
+#[derive(Debug, Error)]
+pub enum ProceedError {
+    #[error("round 1: invalid vss commitment or proof of knowledge: parties {culprits:?}")]
+    Round1InvalidCommitment { culprits: Vec<u16> },
+    #[error("round 2: secret share doesn't match vss commitment: parties {culprits:?}")]
+    Round2InvalidShare { culprits: Vec<u16> },
+}
@@ -0,0 +1,483 @@
+//! FROST two-round signing
+//!
+//! Any `t+1` parties holding [LocalKey] can sign a message. At round 1 every party `i` samples
+//! nonces `d_i`, `e_i` and broadcasts commitments `D_i = d_i * G`, `E_i = e_i * G`. At round 2
+//! parties derive binding factors `ρ_j` from the message and all the commitments, compute group
+//! nonce `R = Σ (D_j + ρ_j * E_j)` and broadcast partial signatures
+//! `z_i = d_i + ρ_i * e_i + k * λ_i * x_i`, where `k = SHA512(R || A || M)` is the Ed25519
+//! challenge and `λ_i` is the Lagrange coefficient of the party. Signature is `(R, Σ z_j)`.
+//!
+//! Every partial signature is checked against commitments and public key share of its sender, so
+//! parties sending invalid partial signatures are identified.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::mem::replace;
+use std::time::Duration;
+
+use curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
+use curv::elliptic::curves::{Ed25519, Point};
+use round_based::containers::{
+    push::{Push, PushExt},
+    *,
+};
+use round_based::{IsCritical, Msg, StateMachine};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::super::ed25519::Signature;
+use super::keygen::LocalKey;
+
+mod rounds;
+
+use private::InternalError;
+pub use rounds::{NonceCommitment, PartialSignature, ProceedError};
+use rounds::{Round0, Round1, Round2, Setup};
+
+/// Signing protocol state machine
+///
+/// Successfully completed signing produces Ed25519 [Signature] which is valid under
+/// [public key](LocalKey::public_key_bytes) of [LocalKey].
+pub struct Sign {
+    round: R,
+
+    msgs1: Option<Store<BroadcastMsgs<NonceCommitment>>>,
+    msgs2: Option<Store<BroadcastMsgs<PartialSignature>>>,
+
+    msgs_queue: Vec<Msg<ProtocolMessage>>,
+
+    party_i: u16,
+    party_n: u16,
+}
+
+impl Sign {
+    /// Constructs a party of signing protocol
+    ///
+    /// Takes party index `i` (in range `[1; |s_l|]`), list `s_l` of keygen indexes of the parties
+    /// taking part in signing, party's [LocalKey] and message to sign. `s_l[i-1]` must be equal to
+    /// index of the party at keygen.
+    ///
+    /// Returns error if:
+    /// * `s_l` has less than `t+1` parties, returns [Error::TooFewParties]
+    /// * `s_l` contains duplicates or indexes that are not in range `[1; n]`, returns
+    ///   [Error::InvalidSl]
+    /// * `i` is not in range `[1; |s_l|]` or `s_l[i-1]` doesn't match index in local key, returns
+    ///   [Error::InvalidPartyIndex]
+    pub fn new(i: u16, s_l: Vec<u16>, local_key: LocalKey, message: &[u8]) -> Result<Self> {
+        if s_l.len() <= usize::from(local_key.t) {
+            return Err(Error::TooFewParties);
+        }
+        let n = u16::try_from(s_l.len()).map_err(|_| Error::TooManyParties { n: s_l.len() })?;
+        let mut sorted = s_l.clone();
+        sorted.sort_unstable();
+        sorted.dedup();
+        if sorted.len() != s_l.len() || s_l.iter().any(|&j| j == 0 || j > local_key.n) {
+            return Err(Error::InvalidSl);
+        }
+        if i == 0 || i > n || s_l[usize::from(i - 1)] != local_key.i {
+            return Err(Error::InvalidPartyIndex);
+        }
+
+        let params = &local_key.vss_scheme.parameters;
+        let s_l0 = s_l.iter().map(|j| j - 1).collect::<Vec<_>>();
+        let public_w = s_l
+            .iter()
+            .map(|&j| {
+                let lambda = VerifiableSS::<Ed25519>::map_share_to_new_params(params, j - 1, &s_l0);
+                &local_key.pk_vec[usize::from(j - 1)] * lambda
+            })
+            .collect::<Vec<Point<Ed25519>>>();
+        let lambda =
+            VerifiableSS::<Ed25519>::map_share_to_new_params(params, local_key.i - 1, &s_l0);
+
+        let setup = Setup {
+            i,
+            w: &local_key.x_i * lambda,
+            public_w,
+            public_key: local_key.y_sum_s,
+            message: message.to_vec(),
+            s_l,
+        };
+
+        let mut state = Self {
+            round: R::Round0(Round0 { setup }),
+
+            msgs1: Some(Round1::expects_messages(i, n)),
+            msgs2: Some(Round2::expects_messages(i, n)),
+
+            msgs_queue: vec![],
+
+            party_i: i,
+            party_n: n,
+        };
+
+        state.proceed_round(false)?;
+        Ok(state)
+    }
+
+    fn gmap_queue<'a, T, F>(&'a mut self, mut f: F) -> impl Push<Msg<T>> + 'a
+    where
+        F: FnMut(T) -> M + 'a,
+    {
+        (&mut self.msgs_queue).gmap(move |m: Msg<T>| m.map_body(|m| ProtocolMessage(f(m))))
+    }
+
+    /// Proceeds round state if it received enough messages and if it's cheap to compute or
+    /// `may_block == true`
+    fn proceed_round(&mut self, may_block: bool) -> Result<()> {
+        let store1_wants_more = self.msgs1.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store2_wants_more = self.msgs2.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+
+        let next_state: R;
+        let try_again: bool = match replace(&mut self.round, R::Gone) {
+            R::Round0(round) if !round.is_expensive() || may_block => {
+                next_state = round
+                    .proceed(self.gmap_queue(M::Round1))
+                    .map(R::Round1)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round0(_) => {
+                next_state = s;
+                false
+            }
+            R::Round1(round) if !store1_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs1.take().ok_or(InternalError::StoreGone)?;
+                let msgs = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?;
+                next_state = round
+                    .proceed(msgs, self.gmap_queue(M::Round2))
+                    .map(R::Round2)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round1(_) => {
+                next_state = s;
+                false
+            }
+            R::Round2(round) if !store2_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs2.take().ok_or(InternalError::StoreGone)?;
+                let msgs = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?;
+                next_state = round
+                    .proceed(msgs)
+                    .map(R::Final)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round2(_) => {
+                next_state = s;
+                false
+            }
+            s @ R::Final(_) | s @ R::Gone => {
+                next_state = s;
+                false
+            }
+        };
+
+        self.round = next_state;
+        if try_again {
+            self.proceed_round(may_block)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl StateMachine for Sign {
+    type MessageBody = ProtocolMessage;
+    type Err = Error;
+    type Output = Signature;
+
+    fn handle_incoming(&mut self, msg: Msg<Self::MessageBody>) -> Result<()> {
+        let current_round = self.current_round();
+
+        match msg.body {
+            ProtocolMessage(M::Round1(m)) => {
+                let store = self
+                    .msgs1
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 1,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+            ProtocolMessage(M::Round2(m)) => {
+                let store = self
+                    .msgs2
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 2,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+        }
+        self.proceed_round(false)
+    }
+
+    fn message_queue(&mut self) -> &mut Vec<Msg<Self::MessageBody>> {
+        &mut self.msgs_queue
+    }
+
+    fn wants_to_proceed(&self) -> bool {
+        let store1_wants_more = self.msgs1.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store2_wants_more = self.msgs2.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+
+        match &self.round {
+            R::Round0(_) => true,
+            R::Round1(_) => !store1_wants_more,
+            R::Round2(_) => !store2_wants_more,
+            R::Final(_) | R::Gone => false,
+        }
+    }
+
+    fn proceed(&mut self) -> Result<()> {
+        self.proceed_round(true)
+    }
+
+    fn round_timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    fn round_timeout_reached(&mut self) -> Self::Err {
+        panic!("no timeout was set")
+    }
+
+    fn is_finished(&self) -> bool {
+        matches!(self.round, R::Final(_))
+    }
+
+    fn pick_output(&mut self) -> Option<Result<Self::Output>> {
+        match self.round {
+            R::Final(_) => (),
+            R::Gone => return Some(Err(Error::DoublePickOutput)),
+            _ => return None,
+        }
+
+        match replace(&mut self.round, R::Gone) {
+            R::Final(result) => Some(Ok(result)),
+            _ => unreachable!("guaranteed by match expression above"),
+        }
+    }
+
+    fn current_round(&self) -> u16 {
+        match &self.round {
+            R::Round0(_) => 0,
+            R::Round1(_) => 1,
+            R::Round2(_) => 2,
+            R::Final(_) | R::Gone => 3,
+        }
+    }
+
+    fn total_rounds(&self) -> Option<u16> {
+        Some(2)
+    }
+
+    fn party_ind(&self) -> u16 {
+        self.party_i
+    }
+
+    fn parties(&self) -> u16 {
+        self.party_n
+    }
+}
+
+impl fmt::Debug for Sign {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{{EdDSA Sign at round={} queue=[len={}]}}",
+            self.current_round(),
+            self.msgs_queue.len()
+        )
+    }
+}
+
+// Rounds
+
+enum R {
+    Round0(Round0),
+    Round1(Round1),
+    Round2(Round2),
+    Final(Signature),
+    Gone,
+}
+
+// Messages
+
+/// Protocol message which parties send on wire
+///
+/// Hides actual messages structure so it could be changed without breaking semver policy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtocolMessage(M);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)]
+enum M {
+    Round1(NonceCommitment),
+    Round2(PartialSignature),
+}
+
+// Error
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Error type of signing protocol
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// Round proceeding resulted in error
+    #[error("proceed round: {0}")]
+    ProceedRound(#[source] ProceedError),
+
+    /// Less than `t+1` parties take part in signing
+    #[error("at least t+1 parties are required for signing")]
+    TooFewParties,
+    /// Number of signers doesn't fit into `u16`
+    #[error("too many parties: {n}")]
+    TooManyParties { n: usize },
+    /// List of signers contains duplicates or indexes that are not in range `[1; n]`
+    #[error("list of signers is invalid")]
+    InvalidSl,
+    /// Party index `i` is not in range `[1; |s_l|]` or doesn't match local key
+    #[error("party index is not in range [1; |s_l|] or doesn't match local key")]
+    InvalidPartyIndex,
+
+    /// Received message didn't pass pre-validation
+    #[error("received message didn't pass pre-validation: {0}")]
+    HandleMessage(#[source] StoreErr),
+    /// Received message which we didn't expect to receive now (e.g. message from previous round)
+    #[error(
+        "didn't expect to receive message from round {msg_round} (being at round {current_round})"
+    )]
+    ReceivedOutOfOrderMessage { current_round: u16, msg_round: u16 },
+    /// [Sign::pick_output] called twice
+    #[error("pick_output called twice")]
+    DoublePickOutput,
+
+    /// Some internal assertions were failed, which is a bug
+    #[doc(hidden)]
+    #[error("internal error: {0:?}")]
+    InternalError(InternalError),
+}
+
+impl IsCritical for Error {
+    fn is_critical(&self) -> bool {
+        !matches!(
+            self,
+            Error::HandleMessage(_) | Error::ReceivedOutOfOrderMessage { .. }
+        )
+    }
+}
+
+impl From<InternalError> for Error {
+    fn from(err: InternalError) -> Self {
+        Self::InternalError(err)
+    }
+}
+
+mod private {
+    #[derive(Debug)]
+    #[non_exhaustive]
+    pub enum InternalError {
+        /// [Messages store](super::MessageStore) reported that it received all messages it wanted to receive,
+        /// but refused to return message container
+        RetrieveRoundMessages(super::StoreErr),
+        #[doc(hidden)]
+        StoreGone,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use curv::elliptic::curves::Scalar;
+    use round_based::dev::Simulation;
+
+    use super::super::super::ed25519;
+    use super::super::keygen::test::simulate_keygen;
+    use super::*;
+
+    fn simulate_sign(keys: &[LocalKey], s_l: &[u16], message: &[u8]) -> Signature {
+        let mut simulation = Simulation::new();
+        for (i, &j) in (1..).zip(s_l) {
+            simulation.add_party(
+                Sign::new(i, s_l.to_vec(), keys[usize::from(j - 1)].clone(), message).unwrap(),
+            );
+        }
+        let signatures = simulation.run().unwrap();
+        for signature in &signatures {
+            assert_eq!(signature, &signatures[0]);
+        }
+        signatures[0].clone()
+    }
+
+    #[test]
+    fn simulate_signing_t1_n3() {
+        let keys = simulate_keygen(1, 3);
+        let public_key = keys[0].public_key_bytes();
+        let message = b"a message";
+
+        for s_l in [&[1, 3][..], &[2, 1, 3]] {
+            let signature = simulate_sign(&keys, s_l, message);
+            ed25519::verify(&signature, &public_key, message).unwrap();
+            let encoded = Signature::from_bytes(&signature.to_bytes()).unwrap();
+            assert_eq!(encoded, signature);
+        }
+    }
+
+    #[test]
+    fn identifies_party_sending_invalid_partial_signature() {
+        let keys = simulate_keygen(1, 3);
+        let message = b"a message";
+        let mut parties = (1..)
+            .zip(&[1, 2])
+            .map(|(i, &j)| Sign::new(i, vec![1, 2], keys[j - 1].clone(), message).unwrap())
+            .collect::<Vec<_>>();
+
+        let commitments = parties
+            .iter_mut()
+            .map(|p| p.message_queue().remove(0))
+            .collect::<Vec<_>>();
+        parties[0].handle_incoming(commitments[1].clone()).unwrap();
+        parties[1].handle_incoming(commitments[0].clone()).unwrap();
+
+        let mut partial = parties[1].message_queue().remove(0);
+        if let ProtocolMessage(M::Round2(z)) = &mut partial.body {
+            z.0 = &z.0 + Scalar::from(1);
+        }
+        assert!(matches!(
+            parties[0].handle_incoming(partial),
+            Err(Error::ProceedRound(ProceedError::Round2InvalidPartialSignature { culprits }))
+                if culprits == [2]
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_signers_list() {
+        let keys = simulate_keygen(1, 3);
+        assert!(matches!(
+            Sign::new(1, vec![1], keys[0].clone(), b""),
+            Err(Error::TooFewParties)
+        ));
+        assert!(matches!(
+            Sign::new(1, vec![2, 3], keys[0].clone(), b""),
+            Err(Error::InvalidPartyIndex)
+        ));
+    }
+}
@@ -0,0 +1,206 @@
+use curv::elliptic::curves::{Ed25519, Point, Scalar};
+use round_based::containers::push::Push;
+use round_based::containers::{self, BroadcastMsgs, Store};
+use round_based::Msg;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::super::super::ed25519::{self, Signature};
+
+/// Nonce commitments `D_i = d_i * G` and `E_i = e_i * G`, sent at round 1
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NonceCommitment {
+    pub d: Point<Ed25519>,
+    pub e: Point<Ed25519>,
+}
+
+/// Party's partial signature `z_i`, sent at round 2
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartialSignature(pub Scalar<Ed25519>);
+
+/// Data that doesn't change throughout the protocol
+pub struct Setup {
+    pub i: u16,
+    /// Keygen indexes of the signers
+    pub s_l: Vec<u16>,
+    /// Party's share of the secret key, multiplied by its Lagrange coefficient
+    pub w: Scalar<Ed25519>,
+    /// `W_j = w_j * G` of every signer
+    pub public_w: Vec<Point<Ed25519>>,
+    pub public_key: Point<Ed25519>,
+    pub message: Vec<u8>,
+}
+
+pub struct Round0 {
+    pub setup: Setup,
+}
+
+impl Round0 {
+    pub fn proceed<O>(self, mut output: O) -> Result<Round1>
+    where
+        O: Push<Msg<NonceCommitment>>,
+    {
+        let d = Scalar::random();
+        let e = Scalar::random();
+        let commitment = NonceCommitment {
+            d: Point::generator() * &d,
+            e: Point::generator() * &e,
+        };
+        output.push(Msg {
+            sender: self.setup.i,
+            receiver: None,
+            body: commitment.clone(),
+        });
+        Ok(Round1 {
+            setup: self.setup,
+            d,
+            e,
+            commitment,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        false
+    }
+}
+
+pub struct Round1 {
+    setup: Setup,
+    d: Scalar<Ed25519>,
+    e: Scalar<Ed25519>,
+    commitment: NonceCommitment,
+}
+
+impl Round1 {
+    pub fn proceed<O>(self, input: BroadcastMsgs<NonceCommitment>, mut output: O) -> Result<Round2>
+    where
+        O: Push<Msg<PartialSignature>>,
+    {
+        let setup = self.setup;
+        let commitments = input.into_vec_including_me(self.commitment);
+        let public_key = ed25519::encode_point(&setup.public_key);
+
+        let encoded_commitments = setup
+            .s_l
+            .iter()
+            .zip(&commitments)
+            .flat_map(|(j, c)| {
+                let mut bytes = j.to_be_bytes().to_vec();
+                bytes.extend_from_slice(&ed25519::encode_point(&c.d));
+                bytes.extend_from_slice(&ed25519::encode_point(&c.e));
+                bytes
+            })
+            .collect::<Vec<u8>>();
+        let message_len = (setup.message.len() as u64).to_be_bytes();
+        let nonces = setup
+            .s_l
+            .iter()
+            .zip(&commitments)
+            .map(|(j, c)| {
+                let rho = ed25519::hash_to_scalar(&[
+                    b"FROST/ed25519/rho",
+                    &public_key,
+                    &message_len,
+                    &setup.message,
+                    &encoded_commitments,
+                    &j.to_be_bytes(),
+                ]);
+                (&c.d + &c.e * &rho, rho)
+            })
+            .collect::<Vec<_>>();
+
+        let r = nonces.iter().fold(Point::zero(), |acc, (r_j, _)| acc + r_j);
+        if r.is_zero() {
+            return Err(ProceedError::Round1ZeroNonce);
+        }
+        let challenge = ed25519::challenge(&ed25519::encode_point(&r), &public_key, &setup.message);
+
+        let rho_i = &nonces[usize::from(setup.i - 1)].1;
+        let z_i = self.d + self.e * rho_i + &challenge * &setup.w;
+        let partial = PartialSignature(z_i);
+        output.push(Msg {
+            sender: setup.i,
+            receiver: None,
+            body: partial.clone(),
+        });
+
+        Ok(Round2 {
+            nonces: nonces.into_iter().map(|(r_j, _)| r_j).collect(),
+            r,
+            challenge,
+            partial,
+            setup,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        false
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<NonceCommitment>> {
+        containers::BroadcastMsgsStore::new(i, n)
+    }
+}
+
+pub struct Round2 {
+    setup: Setup,
+    /// `R_j = D_j + ρ_j * E_j` of every signer
+    nonces: Vec<Point<Ed25519>>,
+    r: Point<Ed25519>,
+    challenge: Scalar<Ed25519>,
+    partial: PartialSignature,
+}
+
+impl Round2 {
+    pub fn proceed(self, input: BroadcastMsgs<PartialSignature>) -> Result<Signature> {
+        let partials = input.into_vec_including_me(self.partial);
+        let challenge = &self.challenge;
+
+        let culprits = (1..)
+            .zip(&partials)
+            .zip(self.nonces.iter().zip(&self.setup.public_w))
+            .filter(|((_, z_j), (r_j, w_j))| Point::generator() * &z_j.0 != *r_j + *w_j * challenge)
+            .map(|((j, _), _)| j)
+            .collect::<Vec<u16>>();
+        if !culprits.is_empty() {
+            return Err(ProceedError::Round2InvalidPartialSignature { culprits });
+        }
+
+        let s = partials
+            .into_iter()
+            .fold(Scalar::zero(), |acc, z_j| acc + z_j.0);
+        let signature = Signature {
+            r: ed25519::encode_point(&self.r),
+            s,
+        };
+        ed25519::verify(
+            &signature,
+            &ed25519::encode_point(&self.setup.public_key),
+            &self.setup.message,
+        )
+        .map_err(|_| ProceedError::Round2InvalidSignature)?;
+        Ok(signature)
+    }
+    pub fn is_expensive(&self) -> bool {
+        false
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<PartialSignature>> {
+        containers::BroadcastMsgsStore::new(i, n)
+    }
+}
+
+// Errors
+
+type Result<T> = std::result::Result<T, ProceedError>;
+
+/// Proceeding protocol error
+///
+/// Subset of [signing errors](enum@super::Error) that can occur at protocol proceeding (i.e.
+/// after every message was received and pre-validated). Indexes of parties are positions in the
+/// list of signers (starting from 1).
+#[derive(Debug, Error)]
+pub enum ProceedError {
+    #[error("round 1: group nonce is the point at infinity")]
+    Round1ZeroNonce,
+    #[error("round 2: invalid partial signature: parties {culprits:?}")]
+    Round2InvalidPartialSignature { culprits: Vec<u16> },
+    #[error("round 2: resulting signature is not valid")]
+    Round2InvalidSignature,
+}
@@ -0,0 +1,24 @@
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+//! Threshold ECDH
+//!
+//! Lets any `t+1` parties holding
+//! [GG20 LocalKey](crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey)
+//! compute `y * P` for a given point `P` without reconstructing secret key `y`, e.g. to derive
+//! ECDH shared secret or to decrypt ECIES ciphertext addressed to the threshold key.
+
+pub mod state_machine;
@@ -0,0 +1,445 @@
+//! Threshold ECDH as [round_based] state machine
+//!
+//! Every party `i` out of `t+1` participants computes `S_i = x_i * P` and broadcasts it along
+//! with DLEQ proof that `S_i` and public share `X_i` (entry of `pk_vec` in local key) have the
+//! same discrete logarithm. Having received all the shares, parties verify the proofs and output
+//! `y * P = Σ λ_j * S_j`, where `λ_j` are Lagrange coefficients of the participants. Parties who
+//! sent invalid shares are identified.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::mem::replace;
+use std::time::Duration;
+
+use curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point};
+use round_based::containers::{
+    push::{Push, PushExt},
+    *,
+};
+use round_based::{IsCritical, Msg, StateMachine};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+
+mod rounds;
+
+use private::InternalError;
+pub use rounds::{EcdhShare, ProceedError};
+use rounds::{Round0, Round1, Setup};
+
+/// Threshold ECDH state machine
+///
+/// Successfully completed protocol outputs `y * P`, where `y` is the secret key shared at keygen.
+pub struct Ecdh {
+    round: R,
+
+    msgs1: Option<Store<BroadcastMsgs<EcdhShare>>>,
+
+    msgs_queue: Vec<Msg<ProtocolMessage>>,
+
+    party_i: u16,
+    party_n: u16,
+}
+
+impl Ecdh {
+    /// Constructs a party of threshold ECDH protocol
+    ///
+    /// Takes party index `i` (in range `[1; |s_l|]`), list `s_l` of keygen indexes of the
+    /// participants, party's [LocalKey] and point `P` to multiply by the secret key. `s_l[i-1]`
+    /// must be equal to index of the party at keygen.
+    ///
+    /// Returns error if:
+    /// * `s_l` has less than `t+1` parties, returns [Error::TooFewParties]
+    /// * `s_l` contains duplicates or indexes that are not in range `[1; n]`, returns
+    ///   [Error::InvalidSl]
+    /// * `i` is not in range `[1; |s_l|]` or `s_l[i-1]` doesn't match index in local key, returns
+    ///   [Error::InvalidPartyIndex]
+    /// * `P` is the point at infinity, returns [Error::InvalidPoint]
+    pub fn new(
+        i: u16,
+        s_l: Vec<u16>,
+        local_key: LocalKey<Secp256k1>,
+        point: Point<Secp256k1>,
+    ) -> Result<Self> {
+        if s_l.len() <= usize::from(local_key.t) {
+            return Err(Error::TooFewParties);
+        }
+        let n = u16::try_from(s_l.len()).map_err(|_| Error::TooManyParties { n: s_l.len() })?;
+        let mut sorted = s_l.clone();
+        sorted.sort_unstable();
+        sorted.dedup();
+        if sorted.len() != s_l.len() || s_l.iter().any(|&j| j == 0 || j > local_key.n) {
+            return Err(Error::InvalidSl);
+        }
+        if i == 0 || i > n || s_l[usize::from(i - 1)] != local_key.i {
+            return Err(Error::InvalidPartyIndex);
+        }
+        if point.is_zero() {
+            return Err(Error::InvalidPoint);
+        }
+
+        let params = &local_key.vss_scheme.parameters;
+        let s_l0 = s_l.iter().map(|j| j - 1).collect::<Vec<_>>();
+        let public_x = s_l
+            .iter()
+            .map(|&j| local_key.pk_vec[usize::from(j - 1)].clone())
+            .collect();
+        let setup = Setup {
+            i,
+            point,
+            x: local_key.keys_linear.x_i,
+            public_x,
+            lambda: s_l0
+                .iter()
+                .map(|&j| VerifiableSS::<Secp256k1>::map_share_to_new_params(params, j, &s_l0))
+                .collect(),
+        };
+
+        let mut state = Self {
+            round: R::Round0(Round0 { setup }),
+
+            msgs1: Some(Round1::expects_messages(i, n)),
+
+            msgs_queue: vec![],
+
+            party_i: i,
+            party_n: n,
+        };
+
+        state.proceed_round(false)?;
+        Ok(state)
+    }
+
+    fn gmap_queue<'a, T, F>(&'a mut self, mut f: F) -> impl Push<Msg<T>> + 'a
+    where
+        F: FnMut(T) -> M + 'a,
+    {
+        (&mut self.msgs_queue).gmap(move |m: Msg<T>| m.map_body(|m| ProtocolMessage(f(m))))
+    }
+
+    /// Proceeds round state if it received enough messages and if it's cheap to compute or
+    /// `may_block == true`
+    fn proceed_round(&mut self, may_block: bool) -> Result<()> {
+        let store1_wants_more = self.msgs1.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+
+        let next_state: R;
+        let try_again: bool = match replace(&mut self.round, R::Gone) {
+            R::Round0(round) if !round.is_expensive() || may_block => {
+                next_state = round
+                    .proceed(self.gmap_queue(M::Round1))
+                    .map(R::Round1)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round0(_) => {
+                next_state = s;
+                false
+            }
+            R::Round1(round) if !store1_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs1.take().ok_or(InternalError::StoreGone)?;
+                let msgs = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?;
+                next_state = round
+                    .proceed(msgs)
+                    .map(R::Final)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round1(_) => {
+                next_state = s;
+                false
+            }
+            s @ R::Final(_) | s @ R::Gone => {
+                next_state = s;
+                false
+            }
+        };
+
+        self.round = next_state;
+        if try_again {
+            self.proceed_round(may_block)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl StateMachine for Ecdh {
+    type MessageBody = ProtocolMessage;
+    type Err = Error;
+    type Output = Point<Secp256k1>;
+
+    fn handle_incoming(&mut self, msg: Msg<Self::MessageBody>) -> Result<()> {
+        let current_round = self.current_round();
+
+        match msg.body {
+            ProtocolMessage(M::Round1(m)) => {
+                let store = self
+                    .msgs1
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 1,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+        }
+        self.proceed_round(false)
+    }
+
+    fn message_queue(&mut self) -> &mut Vec<Msg<Self::MessageBody>> {
+        &mut self.msgs_queue
+    }
+
+    fn wants_to_proceed(&self) -> bool {
+        let store1_wants_more = self.msgs1.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+
+        match &self.round {
+            R::Round0(_) => true,
+            R::Round1(_) => !store1_wants_more,
+            R::Final(_) | R::Gone => false,
+        }
+    }
+
+    fn proceed(&mut self) -> Result<()> {
+        self.proceed_round(true)
+    }
+
+    fn round_timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    fn round_timeout_reached(&mut self) -> Self::Err {
+        panic!("no timeout was set")
+    }
+
+    fn is_finished(&self) -> bool {
+        matches!(self.round, R::Final(_))
+    }
+
+    fn pick_output(&mut self) -> Option<Result<Self::Output>> {
+        match self.round {
+            R::Final(_) => (),
+            R::Gone => return Some(Err(Error::DoublePickOutput)),
+            _ => return None,
+        }
+
+        match replace(&mut self.round, R::Gone) {
+            R::Final(result) => Some(Ok(result)),
+            _ => unreachable!("guaranteed by match expression above"),
+        }
+    }
+
+    fn current_round(&self) -> u16 {
+        match &self.round {
+            R::Round0(_) => 0,
+            R::Round1(_) => 1,
+            R::Final(_) | R::Gone => 2,
+        }
+    }
+
+    fn total_rounds(&self) -> Option<u16> {
+        Some(1)
+    }
+
+    fn party_ind(&self) -> u16 {
+        self.party_i
+    }
+
+    fn parties(&self) -> u16 {
+        self.party_n
+    }
+}
+
+impl fmt::Debug for Ecdh {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{{Threshold ECDH at round={} queue=[len={}]}}",
+            self.current_round(),
+            self.msgs_queue.len()
+        )
+    }
+}
+
+// Rounds
+
+#[allow(clippy::large_enum_variant)]
+enum R {
+    Round0(Round0),
+    Round1(Round1),
+    Final(Point<Secp256k1>),
+    Gone,
+}
+
+// Messages
+
+/// Protocol message which parties send on wire
+///
+/// Hides actual messages structure so it could be changed without breaking semver policy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtocolMessage(M);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+enum M {
+    Round1(EcdhShare),
+}
+
+// Error
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Error type of threshold ECDH protocol
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// Round proceeding resulted in error
+    #[error("proceed round: {0}")]
+    ProceedRound(#[source] ProceedError),
+
+    /// Less than `t+1` parties take part in the protocol
+    #[error("at least t+1 parties are required")]
+    TooFewParties,
+    /// Number of participants doesn't fit into `u16`
+    #[error("too many parties: {n}")]
+    TooManyParties { n: usize },
+    /// List of participants contains duplicates or indexes that are not in range `[1; n]`
+    #[error("list of participants is invalid")]
+    InvalidSl,
+    /// Party index `i` is not in range `[1; |s_l|]` or doesn't match local key
+    #[error("party index is not in range [1; |s_l|] or doesn't match local key")]
+    InvalidPartyIndex,
+    /// Given point is the point at infinity
+    #[error("point is the point at infinity")]
+    InvalidPoint,
+
+    /// Received message didn't pass pre-validation
+    #[error("received message didn't pass pre-validation: {0}")]
+    HandleMessage(#[source] StoreErr),
+    /// Received message which we didn't expect to receive now (e.g. message from previous round)
+    #[error(
+        "didn't expect to receive message from round {msg_round} (being at round {current_round})"
+    )]
+    ReceivedOutOfOrderMessage { current_round: u16, msg_round: u16 },
+    /// [Ecdh::pick_output] called twice
+    #[error("pick_output called twice")]
+    DoublePickOutput,
+
+    /// Some internal assertions were failed, which is a bug
+    #[doc(hidden)]
+    #[error("internal error: {0:?}")]
+    InternalError(InternalError),
+}
+
+impl IsCritical for Error {
+    fn is_critical(&self) -> bool {
+        !matches!(
+            self,
+            Error::HandleMessage(_) | Error::ReceivedOutOfOrderMessage { .. }
+        )
+    }
+}
+
+impl From<InternalError> for Error {
+    fn from(err: InternalError) -> Self {
+        Self::InternalError(err)
+    }
+}
+
+mod private {
+    #[derive(Debug)]
+    #[non_exhaustive]
+    pub enum InternalError {
+        /// [Messages store](super::MessageStore) reported that it received all messages it wanted to receive,
+        /// but refused to return message container
+        RetrieveRoundMessages(super::StoreErr),
+        #[doc(hidden)]
+        StoreGone,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use curv::elliptic::curves::Scalar;
+    use round_based::dev::Simulation;
+
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+
+    use super::*;
+
+    #[test]
+    fn computes_shared_secret() {
+        let keys = simulate_keygen(1, 3);
+        // Counterparty's ephemeral key
+        let k = Scalar::<Secp256k1>::random();
+        let point = Point::generator() * &k;
+
+        for s_l in [&[1, 2][..], &[3, 1], &[2, 3, 1]] {
+            let mut simulation = Simulation::new();
+            for (i, &j) in (1..).zip(s_l) {
+                simulation.add_party(
+                    Ecdh::new(
+                        i,
+                        s_l.to_vec(),
+                        keys[usize::from(j - 1)].clone(),
+                        point.clone(),
+                    )
+                    .unwrap(),
+                );
+            }
+            for shared in simulation.run().unwrap() {
+                assert_eq!(shared, keys[0].public_key() * &k);
+            }
+        }
+    }
+
+    #[test]
+    fn identifies_party_sending_invalid_share() {
+        let keys = simulate_keygen(1, 3);
+        let point = Point::generator() * Scalar::<Secp256k1>::random();
+        let mut parties = (1..)
+            .zip(&[1, 2])
+            .map(|(i, &j)| Ecdh::new(i, vec![1, 2], keys[j - 1].clone(), point.clone()).unwrap())
+            .collect::<Vec<_>>();
+
+        let mut share = parties[1].message_queue().remove(0);
+        let ProtocolMessage(M::Round1(m)) = &mut share.body;
+        m.share = &m.share + Point::generator();
+        assert!(matches!(
+            parties[0].handle_incoming(share),
+            Err(Error::ProceedRound(ProceedError::Round1InvalidProof { culprits }))
+                if culprits == [2]
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_parameters() {
+        let keys = simulate_keygen(1, 3);
+        let point = Point::generator().to_point();
+        assert!(matches!(
+            Ecdh::new(1, vec![1], keys[0].clone(), point.clone()),
+            Err(Error::TooFewParties)
+        ));
+        assert!(matches!(
+            Ecdh::new(1, vec![1, 1], keys[0].clone(), point.clone()),
+            Err(Error::InvalidSl)
+        ));
+        assert!(matches!(
+            Ecdh::new(1, vec![2, 3], keys[0].clone(), point),
+            Err(Error::InvalidPartyIndex)
+        ));
+        assert!(matches!(
+            Ecdh::new(1, vec![1, 2], keys[0].clone(), Point::zero()),
+            Err(Error::InvalidPoint)
+        ));
+    }
+}
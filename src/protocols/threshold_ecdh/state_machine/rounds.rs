@@ -0,0 +1,116 @@
+use curv::cryptographic_primitives::proofs::sigma_ec_ddh::{
+    ECDDHProof, ECDDHStatement, ECDDHWitness,
+};
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use round_based::containers::push::Push;
+use round_based::containers::{self, BroadcastMsgs, Store};
+use round_based::Msg;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use thiserror::Error;
+
+/// Party's share `S_i = x_i * P` with a proof that `log_G X_i = log_P S_i`, sent at round 1
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EcdhShare {
+    pub share: Point<Secp256k1>,
+    pub proof: ECDDHProof<Secp256k1, Sha256>,
+}
+
+/// Data that doesn't change throughout the protocol
+pub struct Setup {
+    pub i: u16,
+    pub point: Point<Secp256k1>,
+    /// Party's share of the secret key `x_i`
+    pub x: Scalar<Secp256k1>,
+    /// `X_j = x_j * G` of every party
+    pub public_x: Vec<Point<Secp256k1>>,
+    /// Lagrange coefficient `λ_j` of every party
+    pub lambda: Vec<Scalar<Secp256k1>>,
+}
+
+impl Setup {
+    fn statement(&self, j: usize, share: &Point<Secp256k1>) -> ECDDHStatement<Secp256k1> {
+        ECDDHStatement {
+            g1: Point::generator().to_point(),
+            h1: self.public_x[j].clone(),
+            g2: self.point.clone(),
+            h2: share.clone(),
+        }
+    }
+}
+
+pub struct Round0 {
+    pub setup: Setup,
+}
+
+impl Round0 {
+    pub fn proceed<O>(self, mut output: O) -> Result<Round1>
+    where
+        O: Push<Msg<EcdhShare>>,
+    {
+        let setup = self.setup;
+        let share = &setup.point * &setup.x;
+        let proof = ECDDHProof::prove(
+            &ECDDHWitness { x: setup.x.clone() },
+            &setup.statement(usize::from(setup.i - 1), &share),
+        );
+        let share = EcdhShare { share, proof };
+        output.push(Msg {
+            sender: setup.i,
+            receiver: None,
+            body: share.clone(),
+        });
+        Ok(Round1 { setup, share })
+    }
+    pub fn is_expensive(&self) -> bool {
+        false
+    }
+}
+
+pub struct Round1 {
+    setup: Setup,
+    share: EcdhShare,
+}
+
+impl Round1 {
+    pub fn proceed(self, input: BroadcastMsgs<EcdhShare>) -> Result<Point<Secp256k1>> {
+        let setup = self.setup;
+        let shares = input.into_vec_including_me(self.share);
+
+        let culprits = (1..)
+            .zip(&shares)
+            .enumerate()
+            .filter(|(j, (_, s))| s.proof.verify(&setup.statement(*j, &s.share)).is_err())
+            .map(|(_, (j, _))| j)
+            .collect::<Vec<u16>>();
+        if !culprits.is_empty() {
+            return Err(ProceedError::Round1InvalidProof { culprits });
+        }
+
+        Ok(shares
+            .iter()
+            .zip(&setup.lambda)
+            .fold(Point::zero(), |acc, (s, lambda)| acc + &s.share * lambda))
+    }
+    pub fn is_expensive(&self) -> bool {
+        false
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<EcdhShare>> {
+        containers::BroadcastMsgsStore::new(i, n)
+    }
+}
+
+// Errors
+
+type Result<T> = std::result::Result<T, ProceedError>;
+
+/// Proceeding protocol error
+///
+/// Subset of [protocol errors](enum@super::Error) that can occur at protocol proceeding (i.e.
+/// after every message was received and pre-validated). Indexes of parties are positions in the
+/// list of participants (starting from 1).
+#[derive(Debug, Error)]
+pub enum ProceedError {
+    #[error("round 1: invalid DLEQ proof: parties {culprits:?}")]
+    Round1InvalidProof { culprits: Vec<u16> },
+}
@@ -0,0 +1,190 @@
+//! BIP340 Schnorr signatures
+//!
+//! Public keys are x-only: point `P` is encoded by its x coordinate only, and stands for the
+//! point with even y coordinate. Nonce point `R` of the signature is x-only as well.
+
+use curv::arithmetic::traits::*;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use curv::BigInt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// BIP340 signature `(r, s)`, `r` is x coordinate of nonce point `R`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Signature {
+    pub r: [u8; 32],
+    pub s: Scalar<Secp256k1>,
+}
+
+impl Signature {
+    /// Encodes signature as 64 bytes `r || s`
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(&self.r);
+        bytes[32..].copy_from_slice(&to_bytes32(&self.s.to_bigint()));
+        bytes
+    }
+
+    /// Decodes signature from 64 bytes `r || s`
+    ///
+    /// Returns `None` if `s` is not less than group order.
+    pub fn from_bytes(bytes: &[u8; 64]) -> Option<Self> {
+        let mut r = [0u8; 32];
+        r.copy_from_slice(&bytes[..32]);
+        let s = BigInt::from_bytes(&bytes[32..]);
+        if &s >= Scalar::<Secp256k1>::group_order() {
+            return None;
+        }
+        Some(Self {
+            r,
+            s: Scalar::from(&s),
+        })
+    }
+}
+
+/// Signature didn't pass verification
+#[derive(Debug, Error)]
+pub enum VerifyError {
+    #[error("public key is not a valid x-only key")]
+    InvalidPublicKey,
+    #[error("signature is invalid")]
+    InvalidSignature,
+}
+
+/// Verifies BIP340 signature of `message` under x-only public key `public_key`
+pub fn verify(
+    signature: &Signature,
+    public_key: &[u8; 32],
+    message: &[u8],
+) -> Result<(), VerifyError> {
+    let p = lift_x(public_key).ok_or(VerifyError::InvalidPublicKey)?;
+    if BigInt::from_bytes(&signature.r) >= field_order() {
+        return Err(VerifyError::InvalidSignature);
+    }
+    let e = challenge(&signature.r, public_key, message);
+    let r = Point::generator() * &signature.s - p * e;
+    if r.is_zero() || !has_even_y(&r) || x_only(&r) != signature.r {
+        return Err(VerifyError::InvalidSignature);
+    }
+    Ok(())
+}
+
+/// Encodes point as x-only public key
+///
+/// The point at infinity is encoded as zeroes.
+pub fn x_only(point: &Point<Secp256k1>) -> [u8; 32] {
+    point.x_coord().map(|x| to_bytes32(&x)).unwrap_or([0u8; 32])
+}
+
+/// Checks whether y coordinate of the point is even
+pub fn has_even_y(point: &Point<Secp256k1>) -> bool {
+    point.y_coord().map(|y| y.is_even()).unwrap_or(true)
+}
+
+/// Point with given x coordinate and even y coordinate
+pub fn lift_x(x: &[u8; 32]) -> Option<Point<Secp256k1>> {
+    let mut compressed = [0u8; 33];
+    compressed[0] = 2;
+    compressed[1..].copy_from_slice(x);
+    Point::from_bytes(&compressed).ok()
+}
+
+/// Challenge `e = H_BIP0340/challenge(r || P || m)`
+pub fn challenge(r: &[u8; 32], public_key: &[u8; 32], message: &[u8]) -> Scalar<Secp256k1> {
+    let hash = tagged_hash(b"BIP0340/challenge", &[r, public_key, message]);
+    Scalar::from(&BigInt::from_bytes(&hash))
+}
+
+/// Taproot tweak `t = H_TapTweak(P || merkle_root)` of x-only internal key `P`, see
+/// [BIP341](https://github.com/bitcoin/bips/blob/master/bip-0341.mediawiki)
+///
+/// `merkle_root` is `None` for outputs without script path. Returns `None` if the hash is not
+/// less than group order.
+pub fn taproot_tweak(
+    internal_key: &[u8; 32],
+    merkle_root: Option<&[u8; 32]>,
+) -> Option<Scalar<Secp256k1>> {
+    let hash = match merkle_root {
+        Some(root) => tagged_hash(b"TapTweak", &[internal_key, root]),
+        None => tagged_hash(b"TapTweak", &[internal_key]),
+    };
+    let t = BigInt::from_bytes(&hash);
+    if &t >= Scalar::<Secp256k1>::group_order() {
+        return None;
+    }
+    Some(Scalar::from(&t))
+}
+
+/// Taproot output key `Q = P + t * G`, where `P` is `internal_key` with even y coordinate and `t`
+/// is its [taproot tweak](taproot_tweak)
+///
+/// Returns `None` if the tweak is invalid or `Q` is the point at infinity.
+pub fn taproot_output_key(
+    internal_key: &Point<Secp256k1>,
+    merkle_root: Option<&[u8; 32]>,
+) -> Option<Point<Secp256k1>> {
+    let t = taproot_tweak(&x_only(internal_key), merkle_root)?;
+    let p = if has_even_y(internal_key) {
+        internal_key.clone()
+    } else {
+        -internal_key
+    };
+    let q = p + Point::generator() * t;
+    if q.is_zero() {
+        None
+    } else {
+        Some(q)
+    }
+}
+
+/// Tagged hash `SHA256(SHA256(tag) || SHA256(tag) || x)` of concatenated `chunks`
+pub fn tagged_hash(tag: &[u8], chunks: &[&[u8]]) -> [u8; 32] {
+    let tag = Sha256::digest(tag);
+    let hash = Sha256::new().chain(tag).chain(tag);
+    chunks
+        .iter()
+        .fold(hash, |hash, chunk| hash.chain(chunk))
+        .finalize()
+        .into()
+}
+
+fn to_bytes32(x: &BigInt) -> [u8; 32] {
+    let bytes = x.to_bytes();
+    let mut out = [0u8; 32];
+    out[32 - bytes.len()..].copy_from_slice(&bytes);
+    out
+}
+
+/// Order of secp256k1 base field `p = 2^256 - 2^32 - 977`
+fn field_order() -> BigInt {
+    (BigInt::one() << 256) - (BigInt::one() << 32) - BigInt::from(977)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn decode<const N: usize>(s: &str) -> [u8; N] {
+        let mut out = [0u8; N];
+        out.copy_from_slice(&hex::decode(s).unwrap());
+        out
+    }
+
+    #[test]
+    fn verifies_bip340_test_vector() {
+        // Test vector 0 from BIP340
+        let public_key =
+            decode::<32>("F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9");
+        let signature = Signature::from_bytes(&decode::<64>(
+            "E907831F80848D1069A5371B402410364BDF1C5F8307B0084C55F1CE2DCA8215\
+             25F66A4A85EA8B71E482A74F382D2CE5EBEEE8FDB2172F477DF4900D310536C0",
+        ))
+        .unwrap();
+        let message = [0u8; 32];
+
+        verify(&signature, &public_key, &message).unwrap();
+        assert_eq!(x_only(&(Point::generator() * Scalar::from(3))), public_key);
+        assert!(verify(&signature, &public_key, &[1u8; 32]).is_err());
+    }
+}
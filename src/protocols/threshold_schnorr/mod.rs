@@ -0,0 +1,26 @@
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+//! Threshold Schnorr signatures
+//!
+//! [FROST](https://eprint.iacr.org/2020/852) two-round threshold signing producing
+//! [BIP340](https://github.com/bitcoin/bips/blob/master/bip-0340.mediawiki) signatures over
+//! secp256k1, e.g. for Taproot key path spends. Signing takes
+//! [GG20 LocalKey](crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey)
+//! as is, so shares from a single DKG can be used for both ECDSA and Schnorr signing.
+
+pub mod bip340;
+pub mod sign;
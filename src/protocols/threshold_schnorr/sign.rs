@@ -0,0 +1,630 @@
+//! FROST two-round signing
+//!
+//! Any `t+1` parties holding GG20 [LocalKey] can sign a message. At round 1 every party `i`
+//! samples nonces `d_i`, `e_i` and broadcasts commitments `D_i = d_i * G`, `E_i = e_i * G`. At
+//! round 2 parties derive binding factors `ρ_j` from the message and all the commitments, compute
+//! group nonce `R = Σ (D_j + ρ_j * E_j)` and broadcast partial signatures
+//! `z_i = d_i + ρ_i * e_i + c * λ_i * x_i`, where `c` is the BIP340 challenge and `λ_i` is the
+//! Lagrange coefficient of the party. Signature is `(R.x, Σ z_j)`.
+//!
+//! BIP340 keys and nonces are x-only, i.e. they always stand for points with even y coordinate.
+//! If public key `y` or group nonce `R` has odd y coordinate, parties negate their key shares or
+//! nonces respectively, so the key doesn't need to be tweaked at keygen.
+//!
+//! [Sign::new_with_tweak] signs under BIP341 output key `Q = P + t * G` instead, where `P` is the
+//! shared public key with even y coordinate and `t = H_TapTweak(P || merkle_root)`. Every party
+//! multiplies its share by the same parity factors, and the term `c * t` is added once to the
+//! sum of partial signatures.
+//!
+//! Nonce commitments equal to the point at infinity are rejected, and their senders are reported.
+//!
+//! Every partial signature is checked against commitments and public key share of its sender, so
+//! parties sending invalid partial signatures are identified.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::mem::replace;
+use std::time::Duration;
+
+use curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point};
+use round_based::containers::{
+    push::{Push, PushExt},
+    *,
+};
+use round_based::{IsCritical, Msg, StateMachine};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+
+use super::bip340::Signature;
+
+mod rounds;
+
+use private::InternalError;
+pub use rounds::{NonceCommitment, PartialSignature, ProceedError};
+use rounds::{OutputKey, Round0, Round1, Round2, Setup};
+
+/// Signing protocol state machine
+///
+/// Successfully completed signing produces BIP340 [Signature] which is valid under x-only
+/// public key of [LocalKey] (see [bip340::x_only](super::bip340::x_only)), or under taproot
+/// output key if constructed via [Sign::new_with_tweak].
+pub struct Sign {
+    round: R,
+
+    msgs1: Option<Store<BroadcastMsgs<NonceCommitment>>>,
+    msgs2: Option<Store<BroadcastMsgs<PartialSignature>>>,
+
+    msgs_queue: Vec<Msg<ProtocolMessage>>,
+
+    party_i: u16,
+    party_n: u16,
+}
+
+impl Sign {
+    /// Constructs a party of signing protocol
+    ///
+    /// Takes party index `i` (in range `[1; |s_l|]`), list `s_l` of keygen indexes of the parties
+    /// taking part in signing, party's [LocalKey] and message to sign. `s_l[i-1]` must be equal to
+    /// index of the party at keygen.
+    ///
+    /// Returns error if:
+    /// * `s_l` has less than `t+1` parties, returns [Error::TooFewParties]
+    /// * `s_l` contains duplicates or indexes that are not in range `[1; n]`, returns
+    ///   [Error::InvalidSl]
+    /// * `i` is not in range `[1; |s_l|]` or `s_l[i-1]` doesn't match index in local key, returns
+    ///   [Error::InvalidPartyIndex]
+    pub fn new(
+        i: u16,
+        s_l: Vec<u16>,
+        local_key: LocalKey<Secp256k1>,
+        message: &[u8],
+    ) -> Result<Self> {
+        let output_key = OutputKey::untweaked(&local_key.y_sum_s);
+        Self::with_output_key(i, s_l, local_key, message, output_key)
+    }
+
+    /// Constructs a party of signing protocol producing signature under taproot output key
+    ///
+    /// Signature is valid under x-only key
+    /// [taproot_output_key(y, merkle_root)](super::bip340::taproot_output_key), where `y` is the
+    /// public key of [LocalKey]. `merkle_root` is `None` for outputs without script path (key
+    /// path spend only). Takes the same arguments as [Sign::new].
+    ///
+    /// Returns the same errors as [Sign::new], and [Error::InvalidTweak] if the tweak is not less
+    /// than group order or output key is the point at infinity.
+    pub fn new_with_tweak(
+        i: u16,
+        s_l: Vec<u16>,
+        local_key: LocalKey<Secp256k1>,
+        message: &[u8],
+        merkle_root: Option<&[u8; 32]>,
+    ) -> Result<Self> {
+        let output_key =
+            OutputKey::taproot(&local_key.y_sum_s, merkle_root).ok_or(Error::InvalidTweak)?;
+        Self::with_output_key(i, s_l, local_key, message, output_key)
+    }
+
+    fn with_output_key(
+        i: u16,
+        s_l: Vec<u16>,
+        local_key: LocalKey<Secp256k1>,
+        message: &[u8],
+        output_key: OutputKey,
+    ) -> Result<Self> {
+        if s_l.len() <= usize::from(local_key.t) {
+            return Err(Error::TooFewParties);
+        }
+        let n = u16::try_from(s_l.len()).map_err(|_| Error::TooManyParties { n: s_l.len() })?;
+        let mut sorted = s_l.clone();
+        sorted.sort_unstable();
+        sorted.dedup();
+        if sorted.len() != s_l.len() || s_l.iter().any(|&j| j == 0 || j > local_key.n) {
+            return Err(Error::InvalidSl);
+        }
+        if i == 0 || i > n || s_l[usize::from(i - 1)] != local_key.i {
+            return Err(Error::InvalidPartyIndex);
+        }
+
+        let params = &local_key.vss_scheme.parameters;
+        let s_l0 = s_l.iter().map(|j| j - 1).collect::<Vec<_>>();
+        let public_w = s_l
+            .iter()
+            .map(|&j| {
+                let lambda =
+                    VerifiableSS::<Secp256k1>::map_share_to_new_params(params, j - 1, &s_l0);
+                &local_key.pk_vec[usize::from(j - 1)] * lambda
+            })
+            .collect::<Vec<Point<Secp256k1>>>();
+        let lambda =
+            VerifiableSS::<Secp256k1>::map_share_to_new_params(params, local_key.i - 1, &s_l0);
+
+        let setup = Setup {
+            i,
+            w: &local_key.keys_linear.x_i * lambda,
+            public_w,
+            output_key,
+            message: message.to_vec(),
+            s_l,
+        };
+
+        let mut state = Self {
+            round: R::Round0(Round0 { setup }),
+
+            msgs1: Some(Round1::expects_messages(i, n)),
+            msgs2: Some(Round2::expects_messages(i, n)),
+
+            msgs_queue: vec![],
+
+            party_i: i,
+            party_n: n,
+        };
+
+        state.proceed_round(false)?;
+        Ok(state)
+    }
+
+    fn gmap_queue<'a, T, F>(&'a mut self, mut f: F) -> impl Push<Msg<T>> + 'a
+    where
+        F: FnMut(T) -> M + 'a,
+    {
+        (&mut self.msgs_queue).gmap(move |m: Msg<T>| m.map_body(|m| ProtocolMessage(f(m))))
+    }
+
+    /// Proceeds round state if it received enough messages and if it's cheap to compute or
+    /// `may_block == true`
+    fn proceed_round(&mut self, may_block: bool) -> Result<()> {
+        let store1_wants_more = self.msgs1.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store2_wants_more = self.msgs2.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+
+        let next_state: R;
+        let try_again: bool = match replace(&mut self.round, R::Gone) {
+            R::Round0(round) if !round.is_expensive() || may_block => {
+                next_state = round
+                    .proceed(self.gmap_queue(M::Round1))
+                    .map(R::Round1)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round0(_) => {
+                next_state = s;
+                false
+            }
+            R::Round1(round) if !store1_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs1.take().ok_or(InternalError::StoreGone)?;
+                let msgs = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?;
+                next_state = round
+                    .proceed(msgs, self.gmap_queue(M::Round2))
+                    .map(R::Round2)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round1(_) => {
+                next_state = s;
+                false
+            }
+            R::Round2(round) if !store2_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs2.take().ok_or(InternalError::StoreGone)?;
+                let msgs = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?;
+                next_state = round
+                    .proceed(msgs)
+                    .map(R::Final)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round2(_) => {
+                next_state = s;
+                false
+            }
+            s @ R::Final(_) | s @ R::Gone => {
+                next_state = s;
+                false
+            }
+        };
+
+        self.round = next_state;
+        if try_again {
+            self.proceed_round(may_block)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl StateMachine for Sign {
+    type MessageBody = ProtocolMessage;
+    type Err = Error;
+    type Output = Signature;
+
+    fn handle_incoming(&mut self, msg: Msg<Self::MessageBody>) -> Result<()> {
+        let current_round = self.current_round();
+
+        match msg.body {
+            ProtocolMessage(M::Round1(m)) => {
+                let store = self
+                    .msgs1
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 1,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+            ProtocolMessage(M::Round2(m)) => {
+                let store = self
+                    .msgs2
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 2,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+        }
+        self.proceed_round(false)
+    }
+
+    fn message_queue(&mut self) -> &mut Vec<Msg<Self::MessageBody>> {
+        &mut self.msgs_queue
+    }
+
+    fn wants_to_proceed(&self) -> bool {
+        let store1_wants_more = self.msgs1.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store2_wants_more = self.msgs2.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+
+        match &self.round {
+            R::Round0(_) => true,
+            R::Round1(_) => !store1_wants_more,
+            R::Round2(_) => !store2_wants_more,
+            R::Final(_) | R::Gone => false,
+        }
+    }
+
+    fn proceed(&mut self) -> Result<()> {
+        self.proceed_round(true)
+    }
+
+    fn round_timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    fn round_timeout_reached(&mut self) -> Self::Err {
+        panic!("no timeout was set")
+    }
+
+    fn is_finished(&self) -> bool {
+        matches!(self.round, R::Final(_))
+    }
+
+    fn pick_output(&mut self) -> Option<Result<Self::Output>> {
+        match self.round {
+            R::Final(_) => (),
+            R::Gone => return Some(Err(Error::DoublePickOutput)),
+            _ => return None,
+        }
+
+        match replace(&mut self.round, R::Gone) {
+            R::Final(result) => Some(Ok(result)),
+            _ => unreachable!("guaranteed by match expression above"),
+        }
+    }
+
+    fn current_round(&self) -> u16 {
+        match &self.round {
+            R::Round0(_) => 0,
+            R::Round1(_) => 1,
+            R::Round2(_) => 2,
+            R::Final(_) | R::Gone => 3,
+        }
+    }
+
+    fn total_rounds(&self) -> Option<u16> {
+        Some(2)
+    }
+
+    fn party_ind(&self) -> u16 {
+        self.party_i
+    }
+
+    fn parties(&self) -> u16 {
+        self.party_n
+    }
+}
+
+impl fmt::Debug for Sign {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{{FROST Sign at round={} queue=[len={}]}}",
+            self.current_round(),
+            self.msgs_queue.len()
+        )
+    }
+}
+
+// Rounds
+
+enum R {
+    Round0(Round0),
+    Round1(Round1),
+    Round2(Round2),
+    Final(Signature),
+    Gone,
+}
+
+// Messages
+
+/// Protocol message which parties send on wire
+///
+/// Hides actual messages structure so it could be changed without breaking semver policy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtocolMessage(M);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+enum M {
+    Round1(NonceCommitment),
+    Round2(PartialSignature),
+}
+
+// Error
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Error type of signing protocol
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// Round proceeding resulted in error
+    #[error("proceed round: {0}")]
+    ProceedRound(#[source] ProceedError),
+
+    /// Less than `t+1` parties take part in signing
+    #[error("at least t+1 parties are required for signing")]
+    TooFewParties,
+    /// Number of signers doesn't fit into `u16`
+    #[error("too many parties: {n}")]
+    TooManyParties { n: usize },
+    /// List of signers contains duplicates or indexes that are not in range `[1; n]`
+    #[error("list of signers is invalid")]
+    InvalidSl,
+    /// Party index `i` is not in range `[1; |s_l|]` or doesn't match local key
+    #[error("party index is not in range [1; |s_l|] or doesn't match local key")]
+    InvalidPartyIndex,
+    /// Taproot tweak is not less than group order, or output key is the point at infinity
+    #[error("taproot tweak is invalid")]
+    InvalidTweak,
+
+    /// Received message didn't pass pre-validation
+    #[error("received message didn't pass pre-validation: {0}")]
+    HandleMessage(#[source] StoreErr),
+    /// Received message which we didn't expect to receive now (e.g. message from previous round)
+    #[error(
+        "didn't expect to receive message from round {msg_round} (being at round {current_round})"
+    )]
+    ReceivedOutOfOrderMessage { current_round: u16, msg_round: u16 },
+    /// [Sign::pick_output] called twice
+    #[error("pick_output called twice")]
+    DoublePickOutput,
+
+    /// Some internal assertions were failed, which is a bug
+    #[doc(hidden)]
+    #[error("internal error: {0:?}")]
+    InternalError(InternalError),
+}
+
+impl IsCritical for Error {
+    fn is_critical(&self) -> bool {
+        !matches!(
+            self,
+            Error::HandleMessage(_) | Error::ReceivedOutOfOrderMessage { .. }
+        )
+    }
+}
+
+impl From<InternalError> for Error {
+    fn from(err: InternalError) -> Self {
+        Self::InternalError(err)
+    }
+}
+
+mod private {
+    #[derive(Debug)]
+    #[non_exhaustive]
+    pub enum InternalError {
+        /// [Messages store](super::MessageStore) reported that it received all messages it wanted to receive,
+        /// but refused to return message container
+        RetrieveRoundMessages(super::StoreErr),
+        #[doc(hidden)]
+        StoreGone,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use curv::elliptic::curves::Scalar;
+    use round_based::dev::Simulation;
+
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+
+    use super::super::bip340;
+    use super::*;
+
+    fn simulate_sign(keys: &[LocalKey<Secp256k1>], s_l: &[u16], message: &[u8]) -> Signature {
+        run_simulation(s_l, |i, j| {
+            Sign::new(i, s_l.to_vec(), keys[usize::from(j - 1)].clone(), message).unwrap()
+        })
+    }
+
+    fn simulate_sign_with_tweak(
+        keys: &[LocalKey<Secp256k1>],
+        s_l: &[u16],
+        message: &[u8],
+        merkle_root: Option<&[u8; 32]>,
+    ) -> Signature {
+        run_simulation(s_l, |i, j| {
+            let key = keys[usize::from(j - 1)].clone();
+            Sign::new_with_tweak(i, s_l.to_vec(), key, message, merkle_root).unwrap()
+        })
+    }
+
+    fn run_simulation(s_l: &[u16], party: impl Fn(u16, u16) -> Sign) -> Signature {
+        let mut simulation = Simulation::new();
+        for (i, &j) in (1..).zip(s_l) {
+            simulation.add_party(party(i, j));
+        }
+        let signatures = simulation.run().unwrap();
+        for signature in &signatures {
+            assert_eq!(signature, &signatures[0]);
+        }
+        signatures[0].clone()
+    }
+
+    #[test]
+    fn sign_with_gg20_keys() {
+        let keys = simulate_keygen(1, 3);
+        let public_key = bip340::x_only(&keys[0].public_key());
+        let message = b"a message";
+
+        for s_l in [&[1, 3][..], &[2, 1, 3]] {
+            let signature = simulate_sign(&keys, s_l, message);
+            bip340::verify(&signature, &public_key, message).unwrap();
+            let encoded = Signature::from_bytes(&signature.to_bytes()).unwrap();
+            assert_eq!(encoded, signature);
+        }
+    }
+
+    #[test]
+    fn sign_with_keys_of_both_parities() {
+        let keys = simulate_keygen(1, 3);
+        let is_even = bip340::has_even_y(&keys[0].public_key());
+        // Child key with the opposite parity
+        let child_keys = (0..)
+            .map(|index| {
+                keys.iter()
+                    .map(|key| key.derive_child(&[index]).unwrap())
+                    .collect::<Vec<_>>()
+            })
+            .find(|child_keys| bip340::has_even_y(&child_keys[0].public_key()) != is_even)
+            .unwrap();
+
+        for keys in [keys, child_keys] {
+            let message = b"a message";
+            let public_key = bip340::x_only(&keys[0].public_key());
+            let signature = simulate_sign(&keys, &[3, 2], message);
+            bip340::verify(&signature, &public_key, message).unwrap();
+        }
+    }
+
+    #[test]
+    fn sign_with_taproot_tweak() {
+        let keys = simulate_keygen(1, 3);
+        let is_even = bip340::has_even_y(&keys[0].public_key());
+        // Child key with the opposite parity
+        let child_keys = (0..)
+            .map(|index| {
+                keys.iter()
+                    .map(|key| key.derive_child(&[index]).unwrap())
+                    .collect::<Vec<_>>()
+            })
+            .find(|child_keys| bip340::has_even_y(&child_keys[0].public_key()) != is_even)
+            .unwrap();
+
+        let message = b"a message";
+        for keys in [keys, child_keys] {
+            for merkle_root in [None, Some(&[7u8; 32])] {
+                let output_key =
+                    bip340::taproot_output_key(&keys[0].public_key(), merkle_root).unwrap();
+                let signature = simulate_sign_with_tweak(&keys, &[1, 3], message, merkle_root);
+                bip340::verify(&signature, &bip340::x_only(&output_key), message).unwrap();
+                assert!(bip340::verify(
+                    &signature,
+                    &bip340::x_only(&keys[0].public_key()),
+                    message
+                )
+                .is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_zero_nonce_commitment() {
+        let keys = simulate_keygen(1, 3);
+        let message = b"a message";
+        let mut parties = (1..)
+            .zip(&[1, 2])
+            .map(|(i, &j)| Sign::new(i, vec![1, 2], keys[j - 1].clone(), message).unwrap())
+            .collect::<Vec<_>>();
+
+        let mut commitment = parties[1].message_queue().remove(0);
+        if let ProtocolMessage(M::Round1(c)) = &mut commitment.body {
+            c.e = Point::zero();
+        }
+        assert!(matches!(
+            parties[0].handle_incoming(commitment),
+            Err(Error::ProceedRound(ProceedError::Round1InvalidNonceCommitment { culprits }))
+                if culprits == [2]
+        ));
+    }
+
+    #[test]
+    fn identifies_party_sending_invalid_partial_signature() {
+        let keys = simulate_keygen(1, 3);
+        let message = b"a message";
+        let mut parties = (1..)
+            .zip(&[1, 2])
+            .map(|(i, &j)| Sign::new(i, vec![1, 2], keys[j - 1].clone(), message).unwrap())
+            .collect::<Vec<_>>();
+
+        let commitments = parties
+            .iter_mut()
+            .map(|p| p.message_queue().remove(0))
+            .collect::<Vec<_>>();
+        parties[0].handle_incoming(commitments[1].clone()).unwrap();
+        parties[1].handle_incoming(commitments[0].clone()).unwrap();
+
+        let mut partial = parties[1].message_queue().remove(0);
+        if let ProtocolMessage(M::Round2(z)) = &mut partial.body {
+            z.0 = &z.0 + Scalar::from(1);
+        }
+        assert!(matches!(
+            parties[0].handle_incoming(partial),
+            Err(Error::ProceedRound(ProceedError::Round2InvalidPartialSignature { culprits }))
+                if culprits == [2]
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_signers_list() {
+        let keys = simulate_keygen(1, 3);
+        assert!(matches!(
+            Sign::new(1, vec![1], keys[0].clone(), b""),
+            Err(Error::TooFewParties)
+        ));
+        assert!(matches!(
+            Sign::new(1, vec![1, 1], keys[0].clone(), b""),
+            Err(Error::InvalidSl)
+        ));
+        assert!(matches!(
+            Sign::new(1, vec![2, 3], keys[0].clone(), b""),
+            Err(Error::InvalidPartyIndex)
+        ));
+    }
+}
@@ -0,0 +1,281 @@
+use curv::arithmetic::traits::*;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use curv::BigInt;
+use round_based::containers::push::Push;
+use round_based::containers::{self, BroadcastMsgs, Store};
+use round_based::Msg;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::super::bip340::{self, Signature};
+
+/// Nonce commitments `D_i = d_i * G` and `E_i = e_i * G`, sent at round 1
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NonceCommitment {
+    pub d: Point<Secp256k1>,
+    pub e: Point<Secp256k1>,
+}
+
+/// Party's partial signature `z_i`, sent at round 2
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartialSignature(pub Scalar<Secp256k1>);
+
+/// Data that doesn't change throughout the protocol
+pub struct Setup {
+    pub i: u16,
+    /// Keygen indexes of the signers
+    pub s_l: Vec<u16>,
+    /// Party's share of the secret key, multiplied by its Lagrange coefficient
+    pub w: Scalar<Secp256k1>,
+    /// `W_j = w_j * G` of every signer
+    pub public_w: Vec<Point<Secp256k1>>,
+    pub output_key: OutputKey,
+    pub message: Vec<u8>,
+}
+
+/// Key the signature is valid under: `Q = g * Y + t * G`, where `Y` is the shared public key
+pub struct OutputKey {
+    /// `Q`
+    pub point: Point<Secp256k1>,
+    /// `g = ±1` that makes y coordinate of `g * Y` even, or `1` if the key is not tweaked
+    pub internal_parity: Scalar<Secp256k1>,
+    /// `t`, zero if the key is not tweaked
+    pub tweak: Scalar<Secp256k1>,
+}
+
+impl OutputKey {
+    /// Shared public key `Y` as is
+    pub fn untweaked(public_key: &Point<Secp256k1>) -> Self {
+        Self {
+            point: public_key.clone(),
+            internal_parity: Scalar::from(1),
+            tweak: Scalar::zero(),
+        }
+    }
+
+    /// BIP341 output key of internal key `Y`, returns `None` if the tweak is invalid
+    pub fn taproot(public_key: &Point<Secp256k1>, merkle_root: Option<&[u8; 32]>) -> Option<Self> {
+        let internal_key = bip340::x_only(public_key);
+        Some(Self {
+            point: bip340::taproot_output_key(public_key, merkle_root)?,
+            internal_parity: parity(public_key),
+            tweak: bip340::taproot_tweak(&internal_key, merkle_root)?,
+        })
+    }
+}
+
+pub struct Round0 {
+    pub setup: Setup,
+}
+
+impl Round0 {
+    pub fn proceed<O>(self, mut output: O) -> Result<Round1>
+    where
+        O: Push<Msg<NonceCommitment>>,
+    {
+        let d = Scalar::random();
+        let e = Scalar::random();
+        let commitment = NonceCommitment {
+            d: Point::generator() * &d,
+            e: Point::generator() * &e,
+        };
+        output.push(Msg {
+            sender: self.setup.i,
+            receiver: None,
+            body: commitment.clone(),
+        });
+        Ok(Round1 {
+            setup: self.setup,
+            d,
+            e,
+            commitment,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        false
+    }
+}
+
+pub struct Round1 {
+    setup: Setup,
+    d: Scalar<Secp256k1>,
+    e: Scalar<Secp256k1>,
+    commitment: NonceCommitment,
+}
+
+impl Round1 {
+    pub fn proceed<O>(self, input: BroadcastMsgs<NonceCommitment>, mut output: O) -> Result<Round2>
+    where
+        O: Push<Msg<PartialSignature>>,
+    {
+        let setup = self.setup;
+        let commitments = input.into_vec_including_me(self.commitment);
+
+        let culprits = (1..)
+            .zip(&commitments)
+            .filter(|(_, c)| c.d.is_zero() || c.e.is_zero())
+            .map(|(j, _)| j)
+            .collect::<Vec<u16>>();
+        if !culprits.is_empty() {
+            return Err(ProceedError::Round1InvalidNonceCommitment { culprits });
+        }
+
+        let output_key = &setup.output_key;
+        let public_key_x = bip340::x_only(&output_key.point);
+
+        let encoded_commitments = setup
+            .s_l
+            .iter()
+            .zip(&commitments)
+            .flat_map(|(j, c)| {
+                let mut bytes = j.to_be_bytes().to_vec();
+                bytes.extend_from_slice(&c.d.to_bytes(true));
+                bytes.extend_from_slice(&c.e.to_bytes(true));
+                bytes
+            })
+            .collect::<Vec<u8>>();
+        let message_len = (setup.message.len() as u64).to_be_bytes();
+        let nonces = setup
+            .s_l
+            .iter()
+            .zip(&commitments)
+            .map(|(j, c)| {
+                let rho = bip340::tagged_hash(
+                    b"FROST/secp256k1/rho",
+                    &[
+                        &public_key_x,
+                        &message_len,
+                        &setup.message,
+                        &encoded_commitments,
+                        &j.to_be_bytes(),
+                    ],
+                );
+                let rho = Scalar::<Secp256k1>::from(&BigInt::from_bytes(&rho));
+                (&c.d + &c.e * &rho, rho)
+            })
+            .collect::<Vec<_>>();
+
+        let r = nonces.iter().fold(Point::zero(), |acc, (r_j, _)| acc + r_j);
+        if r.is_zero() {
+            return Err(ProceedError::Round1ZeroNonce);
+        }
+        let nonce_parity = parity(&r);
+        let output_parity = parity(&output_key.point);
+        let key_parity = &output_parity * &output_key.internal_parity;
+        let tweak = output_parity * &output_key.tweak;
+        let challenge = bip340::challenge(&bip340::x_only(&r), &public_key_x, &setup.message);
+
+        let rho_i = &nonces[usize::from(setup.i - 1)].1;
+        let z_i = &nonce_parity * (self.d + self.e * rho_i) + &challenge * &key_parity * &setup.w;
+        let partial = PartialSignature(z_i);
+        output.push(Msg {
+            sender: setup.i,
+            receiver: None,
+            body: partial.clone(),
+        });
+
+        Ok(Round2 {
+            nonces: nonces.into_iter().map(|(r_j, _)| r_j).collect(),
+            r,
+            nonce_parity,
+            key_parity,
+            challenge,
+            tweak,
+            partial,
+            setup,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        false
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<NonceCommitment>> {
+        containers::BroadcastMsgsStore::new(i, n)
+    }
+}
+
+pub struct Round2 {
+    setup: Setup,
+    /// `R_j = D_j + ρ_j * E_j` of every signer
+    nonces: Vec<Point<Secp256k1>>,
+    r: Point<Secp256k1>,
+    nonce_parity: Scalar<Secp256k1>,
+    key_parity: Scalar<Secp256k1>,
+    challenge: Scalar<Secp256k1>,
+    /// Output key tweak, negated if output key has odd y coordinate
+    tweak: Scalar<Secp256k1>,
+    partial: PartialSignature,
+}
+
+impl Round2 {
+    pub fn proceed(self, input: BroadcastMsgs<PartialSignature>) -> Result<Signature> {
+        let partials = input.into_vec_including_me(self.partial);
+        let nonce_parity = &self.nonce_parity;
+        let challenge = &self.challenge * &self.key_parity;
+
+        let culprits = (1..)
+            .zip(&partials)
+            .zip(self.nonces.iter().zip(&self.setup.public_w))
+            .filter(|((_, z_j), (r_j, w_j))| {
+                Point::generator() * &z_j.0 != *r_j * nonce_parity + *w_j * &challenge
+            })
+            .map(|((j, _), _)| j)
+            .collect::<Vec<u16>>();
+        if !culprits.is_empty() {
+            return Err(ProceedError::Round2InvalidPartialSignature { culprits });
+        }
+
+        let s = partials
+            .into_iter()
+            .fold(&self.challenge * &self.tweak, |acc, z_j| acc + z_j.0);
+        let signature = Signature {
+            r: bip340::x_only(&self.r),
+            s,
+        };
+        bip340::verify(
+            &signature,
+            &bip340::x_only(&self.setup.output_key.point),
+            &self.setup.message,
+        )
+        .map_err(|_| ProceedError::Round2InvalidSignature)?;
+        Ok(signature)
+    }
+    pub fn is_expensive(&self) -> bool {
+        false
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<PartialSignature>> {
+        containers::BroadcastMsgsStore::new(i, n)
+    }
+}
+
+/// `1` if point has even y coordinate, `-1` otherwise
+///
+/// BIP340 implicitly negates public key and nonce point having odd y, so parties negate their
+/// shares accordingly.
+fn parity(point: &Point<Secp256k1>) -> Scalar<Secp256k1> {
+    if bip340::has_even_y(point) {
+        Scalar::from(1)
+    } else {
+        -Scalar::from(1)
+    }
+}
+
+// Errors
+
+type Result<T> = std::result::Result<T, ProceedError>;
+
+/// Proceeding protocol error
+///
+/// Subset of [signing errors](enum@super::Error) that can occur at protocol proceeding (i.e.
+/// after every message was received and pre-validated). Indexes of parties are positions in the
+/// list of signers (starting from 1).
+#[derive(Debug, Error)]
+pub enum ProceedError {
+    #[error("round 1: nonce commitment is the point at infinity: parties {culprits:?}")]
+    Round1InvalidNonceCommitment { culprits: Vec<u16> },
+    #[error("round 1: group nonce is the point at infinity")]
+    Round1ZeroNonce,
+    #[error("round 2: invalid partial signature: parties {culprits:?}")]
+    Round2InvalidPartialSignature { culprits: Vec<u16> },
+    #[error("round 2: resulting signature is not valid")]
+    Round2InvalidSignature,
+}
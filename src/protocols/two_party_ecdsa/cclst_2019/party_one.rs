@@ -30,11 +30,11 @@ use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
 use curv::BigInt;
 use serde::{Deserialize, Serialize};
 use sha2::Sha256;
-use subtle::ConstantTimeEq;
 
 use super::party_two::EphKeyGenFirstMsg as Party2EphKeyGenFirstMessage;
 use super::party_two::EphKeyGenSecondMsg as Party2EphKeyGenSecondMessage;
 use super::SECURITY_BITS;
+use crate::utilities::verification::{self, Mode};
 use crate::Error::{self, InvalidSig};
 
 //****************** Begin: Party One structs ******************//
@@ -396,24 +396,13 @@ pub fn verify(
     pubkey: &Point<Secp256k1>,
     message: &BigInt,
 ) -> Result<(), Error> {
-    let s_fe = Scalar::<Secp256k1>::from(&signature.s);
-    let rx_fe = Scalar::<Secp256k1>::from(&signature.r);
-
-    let s_inv_fe = s_fe.invert().ok_or(Error::InvalidSig)?;
-    let e_fe: Scalar<Secp256k1> =
-        Scalar::<Secp256k1>::from(&message.mod_floor(Scalar::<Secp256k1>::group_order()));
-    let u1 = Point::generator() * e_fe * &s_inv_fe;
-    let u2 = pubkey * rx_fe * &s_inv_fe;
-
-    // second condition is against malleability
-    let rx_bytes = &BigInt::to_bytes(&signature.r)[..];
-    let u1_plus_u2_bytes = &BigInt::to_bytes(&(u1 + u2).x_coord().unwrap())[..];
-
-    if rx_bytes.ct_eq(u1_plus_u2_bytes).unwrap_u8() == 1
-        && signature.s < Scalar::<Secp256k1>::group_order() - signature.s.clone()
-    {
-        Ok(())
-    } else {
-        Err(InvalidSig)
+    let q = Scalar::<Secp256k1>::group_order();
+    if &signature.r >= q || &signature.s >= q {
+        return Err(InvalidSig);
     }
+    let r = Scalar::<Secp256k1>::from(&signature.r);
+    let s = Scalar::<Secp256k1>::from(&signature.s);
+    let message = message.mod_floor(q);
+
+    verification::verify(&r, &s, pubkey, &message, Mode::Strict)
 }
@@ -0,0 +1,66 @@
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+//! Two-party ECDSA with OT-based multiplication
+//!
+//! Follows [DKLs18](https://eprint.iacr.org/2018/499.pdf): instead of Paillier encryption, the
+//! parties obtain additive shares of `1/k` and `x/k` by running two multiplications built on
+//! correlated oblivious transfer. Keygen carries out κ base OTs once, and every signing extends
+//! them to as many OTs as the multiplications need using [KOS15](https://eprint.iacr.org/2015/546.pdf)
+//! OT extension, which only costs hashing.
+//!
+//! As in [lindell_2017](super::lindell_2017), the secret key is shared multiplicatively:
+//! public key is `x1 * x2 * G`. Party one acts as multiplication (and OT extension) sender, party
+//! two acts as receiver. Signing takes three messages:
+//! 1. Party two sends its nonce `D = k2 * G` along with OT extension message, see
+//!    [party_two::EphKeyGenFirstMsg]
+//! 2. Party one derives `R` from `D` and its own nonce, and responds with its multiplication
+//!    messages, see [party_one::EphKeyGenFirstMsg]
+//! 3. Party two sends its share of `s`, see [party_two::PartialSig], party one adds it up and
+//!    verifies the signature, see [party_one::compute_signature]
+//!
+//! Party two encodes its multiplication inputs with [randomized encoding](mul), so party one
+//! cheating at OT extension can't learn anything from whether signing aborted or not. Unlike
+//! DKLs18, party two doesn't check consistency of party one's multiplication inputs with `R`:
+//! inconsistent inputs only make signature invalid, and it's party one who obtains and checks
+//! the signature.
+
+use curv::arithmetic::traits::*;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use curv::BigInt;
+use sha2::{Digest, Sha256};
+
+const KAPPA: usize = 256;
+const STAT_SECURITY: usize = 80;
+
+pub mod mul;
+pub mod ot;
+pub mod party_one;
+pub mod party_two;
+
+/// `H(R')`, party one's nonce is `k1 = H(R') + k1'` where `R' = k1' * D`
+///
+/// Hashing prevents party one from choosing `R` after seeing party two's nonce.
+fn nonce_tweak(r_prime: &Point<Secp256k1>) -> Scalar<Secp256k1> {
+    let hash = Sha256::new()
+        .chain(b"DKLs/nonce")
+        .chain(&*r_prime.to_bytes(true))
+        .finalize();
+    Scalar::from(&BigInt::from_bytes(&hash))
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,130 @@
+//! OT-based multiplication
+//!
+//! Sender with input `α` and receiver with input `β` obtain additive shares of `α * β`.
+//! Receiver encodes `β` as `ξ = κ + 2s` choice bits `ω` such that `β = Σ g_i * ω_i`, where gadget
+//! vector `g` consists of powers of two `2^0, ..., 2^{κ-1}` followed by `2s` public pseudorandom
+//! scalars. The last `2s` bits are sampled at random and the first κ bits encode what's left of
+//! `β`, so any `2s` choice bits are (statistically close to) uniformly random. That prevents
+//! sender from learning `β` by cheating in OT and watching whether the protocol aborts.
+//!
+//! For every OT `i` sender takes `a_i = H(k_{i,0})` and sends correction
+//! `τ_i = a_i + α - H(k_{i,1})`. Receiver computes `b_i = H(k_{i,ω_i}) + ω_i * τ_i`, which is
+//! `a_i + ω_i * α`. Then `-Σ g_i * a_i` and `Σ g_i * b_i` are additive shares of `α * β`.
+
+use curv::arithmetic::traits::*;
+use curv::cryptographic_primitives::proofs::ProofError;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Scalar};
+use curv::BigInt;
+use sha2::{Digest, Sha256};
+
+use super::ot::Block;
+use super::{KAPPA, STAT_SECURITY};
+
+/// Number of OTs consumed by a single multiplication
+pub const XI: usize = KAPPA + 2 * STAT_SECURITY;
+
+/// Encodes receiver's input `β` as `ξ` choice bits
+pub fn encode(beta: &Scalar<Secp256k1>) -> Vec<bool> {
+    let gadget = gadget();
+    let random_bits = (0..XI - KAPPA)
+        .map(|_| BigInt::sample(1).is_one())
+        .collect::<Vec<_>>();
+    let rest = gadget[KAPPA..]
+        .iter()
+        .zip(&random_bits)
+        .filter(|(_, &bit)| bit)
+        .fold(beta.clone(), |rest, (g_i, _)| rest - g_i)
+        .to_bigint();
+    (0..KAPPA)
+        .map(|i| rest.test_bit(i))
+        .chain(random_bits)
+        .collect()
+}
+
+/// Computes sender's share of `α * β` and corrections `τ_i` to be sent to receiver
+///
+/// Takes pair of keys of every OT.
+pub fn sender(
+    alpha: &Scalar<Secp256k1>,
+    keys: &[(Block, Block)],
+) -> (Scalar<Secp256k1>, Vec<Scalar<Secp256k1>>) {
+    assert_eq!(keys.len(), XI);
+    let (share, corrections) = gadget().iter().zip(keys).fold(
+        (Scalar::zero(), Vec::with_capacity(XI)),
+        |(share, mut corrections), (g_i, (key0, key1))| {
+            let a_i = to_scalar(key0);
+            corrections.push(&a_i + alpha - to_scalar(key1));
+            (share - g_i * a_i, corrections)
+        },
+    );
+    (share, corrections)
+}
+
+/// Computes receiver's share of `α * β`
+///
+/// Takes encoded input, chosen key of every OT and corrections received from sender. Returns
+/// error if number of corrections is invalid.
+pub fn receiver(
+    choices: &[bool],
+    keys: &[Block],
+    corrections: &[Scalar<Secp256k1>],
+) -> Result<Scalar<Secp256k1>, ProofError> {
+    if choices.len() != XI || keys.len() != XI || corrections.len() != XI {
+        return Err(ProofError);
+    }
+    Ok(gadget()
+        .iter()
+        .zip(choices.iter().zip(keys).zip(corrections))
+        .fold(Scalar::zero(), |share, (g_i, ((&choice, key), tau_i))| {
+            let mut b_i = to_scalar(key);
+            if choice {
+                b_i = b_i + tau_i;
+            }
+            share + g_i * b_i
+        }))
+}
+
+/// Gadget vector `g`
+fn gadget() -> Vec<Scalar<Secp256k1>> {
+    let two = Scalar::<Secp256k1>::from(2);
+    let powers = std::iter::successors(Some(Scalar::from(1)), |g: &Scalar<Secp256k1>| {
+        Some(g * &two)
+    })
+    .take(KAPPA);
+    let random = (0u64..(XI - KAPPA) as u64).map(|j| {
+        let hash = Sha256::new()
+            .chain(b"DKLs/gadget")
+            .chain(j.to_be_bytes())
+            .finalize();
+        Scalar::from(&BigInt::from_bytes(&hash))
+    });
+    powers.chain(random).collect()
+}
+
+fn to_scalar(key: &Block) -> Scalar<Secp256k1> {
+    Scalar::from(&BigInt::from_bytes(key))
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::ot::random_block;
+    use super::*;
+
+    #[test]
+    fn shares_sum_up_to_product() {
+        let (alpha, beta) = (Scalar::random(), Scalar::random());
+        let choices = encode(&beta);
+        let keys = (0..XI)
+            .map(|_| (random_block(), random_block()))
+            .collect::<Vec<_>>();
+        let chosen = keys
+            .iter()
+            .zip(&choices)
+            .map(|((key0, key1), &choice)| if choice { *key1 } else { *key0 })
+            .collect::<Vec<_>>();
+
+        let (share_a, corrections) = sender(&alpha, &keys);
+        let share_b = receiver(&choices, &chosen, &corrections).unwrap();
+        assert_eq!(share_a + share_b, alpha * beta);
+    }
+}
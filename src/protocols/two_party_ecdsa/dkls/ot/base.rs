@@ -0,0 +1,125 @@
+//! Base OT
+//!
+//! "Simplest OT" of [Chou and Orlandi](https://eprint.iacr.org/2015/267.pdf), carrying out many
+//! random OTs at once. Sender publishes `A = a * G` with a proof of knowledge of `a`. For every
+//! choice bit `c_j` receiver publishes `B_j = b_j * G + c_j * A` and takes `H(b_j * A)` as its
+//! seed, while sender computes both `H(a * B_j)` and `H(a * (B_j - A))`.
+
+use curv::cryptographic_primitives::proofs::sigma_dlog::DLogProof;
+use curv::cryptographic_primitives::proofs::ProofError;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use super::{hash, Block};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SenderMsg {
+    pub a: Point<Secp256k1>,
+    pub d_log_proof: DLogProof<Secp256k1, Sha256>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReceiverMsg {
+    pub b: Vec<Point<Secp256k1>>,
+}
+
+/// Sender of base OTs
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Sender {
+    a: Scalar<Secp256k1>,
+    public_a: Point<Secp256k1>,
+}
+
+impl Sender {
+    pub fn new() -> (Sender, SenderMsg) {
+        let a = Scalar::<Secp256k1>::random();
+        let d_log_proof = DLogProof::prove(&a);
+        let public_a = d_log_proof.pk.clone();
+        (
+            Sender { a, public_a },
+            SenderMsg {
+                a: d_log_proof.pk.clone(),
+                d_log_proof,
+            },
+        )
+    }
+
+    /// Computes both seeds of every OT
+    ///
+    /// Returns error if receiver didn't send exactly `expected` choices.
+    pub fn seeds(
+        &self,
+        receiver_msg: &ReceiverMsg,
+        expected: usize,
+    ) -> Result<Vec<(Block, Block)>, ProofError> {
+        if receiver_msg.b.len() != expected {
+            return Err(ProofError);
+        }
+        Ok((0u64..)
+            .zip(&receiver_msg.b)
+            .map(|(j, b)| {
+                let seed0 = seed(j, &self.public_a, b, &(b * &self.a));
+                let seed1 = seed(j, &self.public_a, b, &((b - &self.public_a) * &self.a));
+                (seed0, seed1)
+            })
+            .collect())
+    }
+}
+
+/// Chooses one seed of every OT according to `choices`
+///
+/// Returns error if sender's proof of knowledge is invalid.
+pub fn receive(
+    sender_msg: &SenderMsg,
+    choices: &[bool],
+) -> Result<(ReceiverMsg, Vec<Block>), ProofError> {
+    if sender_msg.a != sender_msg.d_log_proof.pk || sender_msg.a.is_zero() {
+        return Err(ProofError);
+    }
+    DLogProof::verify(&sender_msg.d_log_proof)?;
+
+    let (b, seeds) = (0u64..)
+        .zip(choices)
+        .map(|(j, &choice)| {
+            let b = Scalar::<Secp256k1>::random();
+            let mut public_b = Point::generator() * &b;
+            if choice {
+                public_b = public_b + &sender_msg.a;
+            }
+            let seed = seed(j, &sender_msg.a, &public_b, &(&sender_msg.a * &b));
+            (public_b, seed)
+        })
+        .unzip();
+    Ok((ReceiverMsg { b }, seeds))
+}
+
+fn seed(j: u64, a: &Point<Secp256k1>, b: &Point<Secp256k1>, shared: &Point<Secp256k1>) -> Block {
+    hash(
+        b"DKLs/base-OT",
+        &[
+            &j.to_be_bytes(),
+            &a.to_bytes(true),
+            &b.to_bytes(true),
+            &shared.to_bytes(true),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn receiver_gets_chosen_seeds() {
+        let choices = [true, false, false, true];
+        let (sender, sender_msg) = Sender::new();
+        let (receiver_msg, chosen) = receive(&sender_msg, &choices).unwrap();
+        let seeds = sender.seeds(&receiver_msg, choices.len()).unwrap();
+
+        for ((choice, chosen), (seed0, seed1)) in choices.iter().zip(chosen).zip(seeds) {
+            assert_ne!(seed0, seed1);
+            assert_eq!(chosen, if *choice { seed1 } else { seed0 });
+        }
+    }
+}
@@ -0,0 +1,342 @@
+//! OT extension
+//!
+//! [KOS15](https://eprint.iacr.org/2015/546.pdf) extension of κ base OTs with roles reversed:
+//! extension receiver knows both seeds of every base OT, extension sender knows one seed of every
+//! base OT chosen by its secret κ-bit string `Δ`.
+//!
+//! Receiver expands the seeds into columns `t^j = G(k_{j,0})` and sends
+//! `u^j = t^j ⊕ G(k_{j,1}) ⊕ ω`, where `ω` is a vector of its choice bits. Sender computes
+//! `q^j = G(k_{j,Δ_j}) ⊕ Δ_j * u^j`, so rows of the matrices satisfy `q_i = t_i ⊕ ω_i * Δ`. Receiver
+//! proves that it used the same `ω` in every column by revealing random linear combinations of
+//! the rows over GF(2^κ), with κ+s random choice bits appended to hide `ω`.
+//!
+//! Rows are hashed into OT keys: sender obtains `H(q_i)` and `H(q_i ⊕ Δ)`, receiver obtains
+//! `H(t_i)` which is equal to one of them chosen by `ω_i`. Keys are salted, so rows can't be
+//! correlated with rows of another extension.
+
+use curv::cryptographic_primitives::proofs::ProofError;
+use serde::{Deserialize, Serialize};
+
+use super::super::{KAPPA, STAT_SECURITY};
+use super::{hash, random_block, Block};
+
+/// Message sent by extension receiver
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExtensionMsg {
+    pub session_id: Block,
+    /// Columns `u^j`, bits are packed in little-endian order
+    pub u: Vec<Vec<u8>>,
+    /// `x = Σ ω_i * χ_i`
+    pub x: Block,
+    /// `t = Σ t_i * χ_i`
+    pub t: Block,
+}
+
+/// Extension sender, holds `Δ` and base OT seeds chosen by it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExtensionSender {
+    delta: Block,
+    seeds: Vec<Block>,
+}
+
+/// Extension receiver, holds both seeds of every base OT
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExtensionReceiver {
+    seeds: Vec<(Block, Block)>,
+}
+
+/// Rows `q_i` obtained by extension sender
+pub struct SenderRows {
+    delta: Block,
+    rows: Vec<Block>,
+}
+
+/// Rows `t_i` obtained by extension receiver
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReceiverRows {
+    rows: Vec<Block>,
+}
+
+impl ExtensionSender {
+    /// Constructs extension sender from `Δ` and seeds of base OTs chosen by bits of `Δ`
+    pub fn new(delta: Block, seeds: Vec<Block>) -> Self {
+        assert_eq!(seeds.len(), KAPPA);
+        Self { delta, seeds }
+    }
+
+    /// Bits of `Δ` which must be used as choices of base OTs
+    pub fn delta_bits(delta: &Block) -> Vec<bool> {
+        (0..KAPPA).map(|j| get_bit(delta, j)).collect()
+    }
+
+    /// Obtains `count` rows from receiver's message
+    ///
+    /// Returns error if message is malformed or receiver's consistency check doesn't pass.
+    pub fn extend(&self, count: usize, msg: &ExtensionMsg) -> Result<SenderRows, ProofError> {
+        let padded = count + KAPPA + STAT_SECURITY;
+        let len = padded.div_ceil(8);
+        if msg.u.len() != KAPPA || msg.u.iter().any(|u| u.len() != len) {
+            return Err(ProofError);
+        }
+
+        let columns = self
+            .seeds
+            .iter()
+            .zip(&msg.u)
+            .enumerate()
+            .map(|(j, (seed, u))| {
+                let mut q = prg(seed, &msg.session_id, len);
+                if get_bit(&self.delta, j) {
+                    xor_assign(&mut q, u);
+                }
+                q
+            })
+            .collect::<Vec<_>>();
+        let rows = transpose(&columns, padded);
+
+        let q = rows
+            .iter()
+            .zip(challenges(msg))
+            .fold([0u8; 32], |acc, (q_i, chi_i)| {
+                xor(&acc, &gf_mul(q_i, &chi_i))
+            });
+        if q != xor(&msg.t, &gf_mul(&msg.x, &self.delta)) {
+            return Err(ProofError);
+        }
+
+        Ok(SenderRows {
+            delta: self.delta,
+            rows: rows[..count].to_vec(),
+        })
+    }
+}
+
+impl ExtensionReceiver {
+    /// Constructs extension receiver from both seeds of every base OT
+    pub fn new(seeds: Vec<(Block, Block)>) -> Self {
+        assert_eq!(seeds.len(), KAPPA);
+        Self { seeds }
+    }
+
+    /// Extends base OTs to `choices.len()` OTs
+    pub fn extend(&self, choices: &[bool]) -> (ExtensionMsg, ReceiverRows) {
+        let count = choices.len();
+        let padded = count + KAPPA + STAT_SECURITY;
+        let len = padded.div_ceil(8);
+        let session_id = random_block();
+
+        let mut omega = vec![0u8; len];
+        for (i, &choice) in choices.iter().enumerate() {
+            set_bit(&mut omega, i, choice);
+        }
+        let padding = (0..(KAPPA + STAT_SECURITY).div_ceil(256))
+            .map(|_| random_block())
+            .collect::<Vec<_>>()
+            .concat();
+        for i in count..padded {
+            set_bit(&mut omega, i, get_bit(&padding, i - count));
+        }
+
+        let (t, u): (Vec<_>, Vec<_>) = self
+            .seeds
+            .iter()
+            .map(|(seed0, seed1)| {
+                let t = prg(seed0, &session_id, len);
+                let mut u = prg(seed1, &session_id, len);
+                xor_assign(&mut u, &t);
+                xor_assign(&mut u, &omega);
+                (t, u)
+            })
+            .unzip();
+        let rows = transpose(&t, padded);
+
+        let mut msg = ExtensionMsg {
+            session_id,
+            u,
+            x: [0u8; 32],
+            t: [0u8; 32],
+        };
+        for (i, (t_i, chi_i)) in rows.iter().zip(challenges(&msg)).enumerate() {
+            if get_bit(&omega, i) {
+                msg.x = xor(&msg.x, &chi_i);
+            }
+            msg.t = xor(&msg.t, &gf_mul(t_i, &chi_i));
+        }
+
+        let rows = ReceiverRows {
+            rows: rows[..count].to_vec(),
+        };
+        (msg, rows)
+    }
+}
+
+impl SenderRows {
+    /// Pair of keys `(H(q_i), H(q_i ⊕ Δ))` of every OT
+    pub fn keys(&self, salt: &Block) -> Vec<(Block, Block)> {
+        (0u64..)
+            .zip(&self.rows)
+            .map(|(i, q_i)| (key(salt, i, q_i), key(salt, i, &xor(q_i, &self.delta))))
+            .collect()
+    }
+}
+
+impl ReceiverRows {
+    /// Key `H(t_i)` of every OT
+    pub fn keys(&self, salt: &Block) -> Vec<Block> {
+        (0u64..)
+            .zip(&self.rows)
+            .map(|(i, t_i)| key(salt, i, t_i))
+            .collect()
+    }
+}
+
+fn key(salt: &Block, i: u64, row: &Block) -> Block {
+    hash(b"DKLs/OT-extension/key", &[salt, &i.to_be_bytes(), row])
+}
+
+/// Expands seed into `len` pseudorandom bytes
+fn prg(seed: &Block, session_id: &Block, len: usize) -> Vec<u8> {
+    let mut out = (0u64..)
+        .map(|counter| {
+            hash(
+                b"DKLs/OT-extension/prg",
+                &[seed, session_id, &counter.to_be_bytes()],
+            )
+        })
+        .take(len.div_ceil(32))
+        .collect::<Vec<_>>()
+        .concat();
+    out.truncate(len);
+    out
+}
+
+/// Challenges `χ_i` of consistency check, derived from receiver's message
+fn challenges(msg: &ExtensionMsg) -> impl Iterator<Item = Block> {
+    let u = msg.u.concat();
+    let seed = hash(b"DKLs/OT-extension/challenge", &[&msg.session_id, &u]);
+    let count = msg.u.first().map(|u| u.len() * 8).unwrap_or(0);
+    (0u64..count as u64).map(move |i| hash(b"DKLs/OT-extension/chi", &[&seed, &i.to_be_bytes()]))
+}
+
+/// Transposes κ columns of `count` bits into `count` rows of κ bits
+fn transpose(columns: &[Vec<u8>], count: usize) -> Vec<Block> {
+    (0..count)
+        .map(|i| {
+            let mut row = [0u8; 32];
+            for (j, column) in columns.iter().enumerate() {
+                set_bit(&mut row, j, get_bit(column, i));
+            }
+            row
+        })
+        .collect()
+}
+
+fn get_bit(bytes: &[u8], i: usize) -> bool {
+    bytes[i / 8] >> (i % 8) & 1 == 1
+}
+
+fn set_bit(bytes: &mut [u8], i: usize, bit: bool) {
+    if bit {
+        bytes[i / 8] |= 1 << (i % 8);
+    } else {
+        bytes[i / 8] &= !(1 << (i % 8));
+    }
+}
+
+fn xor(a: &Block, b: &Block) -> Block {
+    let mut out = *a;
+    xor_assign(&mut out, b);
+    out
+}
+
+fn xor_assign(a: &mut [u8], b: &[u8]) {
+    a.iter_mut().zip(b).for_each(|(a, b)| *a ^= b);
+}
+
+/// Multiplication in GF(2^256) modulo `x^256 + x^10 + x^5 + x^2 + 1`
+fn gf_mul(a: &Block, b: &Block) -> Block {
+    let limbs = |x: &Block| {
+        let mut limbs = [0u64; 4];
+        for (limb, chunk) in limbs.iter_mut().zip(x.chunks(8)) {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(chunk);
+            *limb = u64::from_le_bytes(bytes);
+        }
+        limbs
+    };
+    let (a, b) = (limbs(a), limbs(b));
+
+    let mut r = [0u64; 4];
+    for i in (0..256).rev() {
+        let carry = r[3] >> 63;
+        r[3] = (r[3] << 1) | (r[2] >> 63);
+        r[2] = (r[2] << 1) | (r[1] >> 63);
+        r[1] = (r[1] << 1) | (r[0] >> 63);
+        r[0] = (r[0] << 1) ^ (carry * 0x425);
+        if b[i / 64] >> (i % 64) & 1 == 1 {
+            r.iter_mut().zip(&a).for_each(|(r, a)| *r ^= a);
+        }
+    }
+
+    let mut out = [0u8; 32];
+    for (chunk, limb) in out.chunks_mut(8).zip(&r) {
+        chunk.copy_from_slice(&limb.to_le_bytes());
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::base;
+    use super::*;
+
+    fn setup() -> (ExtensionSender, ExtensionReceiver) {
+        let delta = random_block();
+        let (sender, sender_msg) = base::Sender::new();
+        let (receiver_msg, chosen) =
+            base::receive(&sender_msg, &ExtensionSender::delta_bits(&delta)).unwrap();
+        let seeds = sender.seeds(&receiver_msg, KAPPA).unwrap();
+        (
+            ExtensionSender::new(delta, chosen),
+            ExtensionReceiver::new(seeds),
+        )
+    }
+
+    #[test]
+    fn receiver_gets_chosen_keys() {
+        let (sender, receiver) = setup();
+        let choices = (0..300).map(|i| i % 3 == 0).collect::<Vec<_>>();
+        let salt = random_block();
+
+        let (msg, receiver_rows) = receiver.extend(&choices);
+        let sender_keys = sender.extend(choices.len(), &msg).unwrap().keys(&salt);
+        let receiver_keys = receiver_rows.keys(&salt);
+
+        for ((choice, key), (key0, key1)) in choices.iter().zip(receiver_keys).zip(sender_keys) {
+            assert_ne!(key0, key1);
+            assert_eq!(key, if *choice { key1 } else { key0 });
+        }
+    }
+
+    #[test]
+    fn detects_inconsistent_choices() {
+        let (sender, receiver) = setup();
+        let choices = vec![false; 100];
+        let (mut msg, _) = receiver.extend(&choices);
+        // Receiver flips its choice in a single column
+        msg.u[7][0] ^= 1;
+        assert!(sender.extend(choices.len(), &msg).is_err());
+    }
+
+    #[test]
+    fn gf_mul_is_distributive() {
+        let (a, b, c) = (random_block(), random_block(), random_block());
+        assert_eq!(
+            gf_mul(&a, &xor(&b, &c)),
+            xor(&gf_mul(&a, &b), &gf_mul(&a, &c))
+        );
+        let mut one = [0u8; 32];
+        one[0] = 1;
+        assert_eq!(gf_mul(&a, &one), a);
+    }
+}
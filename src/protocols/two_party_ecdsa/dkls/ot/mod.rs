@@ -0,0 +1,34 @@
+//! Oblivious transfer
+//!
+//! [base] OT is carried out once at keygen, it gives the receiver one of two random seeds and the
+//! sender both of them. Then [extension] turns κ base OTs into any number of random OTs at every
+//! signing.
+
+use curv::arithmetic::traits::*;
+use curv::BigInt;
+use sha2::{Digest, Sha256};
+
+pub mod base;
+pub mod extension;
+
+/// κ-bit string: a seed, OT key or a row of OT extension matrix
+pub type Block = [u8; 32];
+
+/// Hashes concatenated `chunks` prefixed with domain separation tag into a [Block]
+fn hash(tag: &[u8], chunks: &[&[u8]]) -> Block {
+    let hash = Sha256::new()
+        .chain((tag.len() as u64).to_be_bytes())
+        .chain(tag);
+    chunks
+        .iter()
+        .fold(hash, |hash, chunk| hash.chain(chunk))
+        .finalize()
+        .into()
+}
+
+pub(crate) fn random_block() -> Block {
+    let bytes = BigInt::sample(256).to_bytes();
+    let mut block = [0u8; 32];
+    block[32 - bytes.len()..].copy_from_slice(&bytes);
+    block
+}
@@ -0,0 +1,260 @@
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+use curv::arithmetic::traits::*;
+use curv::cryptographic_primitives::commitments::hash_commitment::HashCommitment;
+use curv::cryptographic_primitives::commitments::traits::Commitment;
+use curv::cryptographic_primitives::proofs::sigma_dlog::*;
+use curv::cryptographic_primitives::proofs::ProofError;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use curv::BigInt;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use super::mul::{self, XI};
+use super::ot::base;
+use super::ot::extension::ExtensionSender;
+use super::ot::{random_block, Block};
+use super::party_two::EphKeyGenFirstMsg as Party2EphKeyGenFirstMsg;
+use super::party_two::KeyGenFirstMsg as Party2KeyGenFirstMsg;
+use super::party_two::PartialSig as Party2PartialSig;
+use super::{nonce_tweak, KAPPA};
+
+use crate::utilities::verification::{self, Mode, SignatureRecid};
+use crate::Error;
+
+//****************** Begin: Party One structs ******************//
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EcKeyPair {
+    pub public_share: Point<Secp256k1>,
+    secret_share: Scalar<Secp256k1>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CommWitness {
+    pub pk_commitment_blind_factor: BigInt,
+    pub zk_pok_blind_factor: BigInt,
+    pub public_share: Point<Secp256k1>,
+    pub d_log_proof: DLogProof<Secp256k1, Sha256>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeyGenFirstMsg {
+    pub pk_commitment: BigInt,
+    pub zk_pok_commitment: BigInt,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KeyGenSecondMsg {
+    pub comm_witness: CommWitness,
+    pub base_ot: base::ReceiverMsg,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Party1Private {
+    x1: Scalar<Secp256k1>,
+    public_key: Point<Secp256k1>,
+    ot: ExtensionSender,
+}
+
+/// Party one's nonce and shares of `1/k` and `x/k`
+///
+/// Must be used to sign exactly one message.
+#[derive(Serialize, Deserialize)]
+pub struct EphEcKeyPair {
+    pub r: Point<Secp256k1>,
+    k_inv_share: Scalar<Secp256k1>,
+    x_k_inv_share: Scalar<Secp256k1>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EphKeyGenFirstMsg {
+    /// `R' = k1' * D`, nonce is `R = H(R') * D + R'`
+    pub r_prime: Point<Secp256k1>,
+    /// Salt of OT extension keys
+    pub salt: Block,
+    /// Multiplication corrections for `1/k` and `x/k`
+    pub corrections: Vec<Scalar<Secp256k1>>,
+}
+
+//****************** End: Party One structs ******************//
+
+impl KeyGenFirstMsg {
+    pub fn create_commitments() -> (KeyGenFirstMsg, CommWitness, EcKeyPair) {
+        Self::create_commitments_with_fixed_secret_share(Scalar::random())
+    }
+
+    pub fn create_commitments_with_fixed_secret_share(
+        secret_share: Scalar<Secp256k1>,
+    ) -> (KeyGenFirstMsg, CommWitness, EcKeyPair) {
+        let public_share = Point::generator() * &secret_share;
+        let d_log_proof = DLogProof::<Secp256k1, Sha256>::prove(&secret_share);
+
+        let pk_commitment_blind_factor = BigInt::sample(KAPPA);
+        let pk_commitment =
+            HashCommitment::<Sha256>::create_commitment_with_user_defined_randomness(
+                &BigInt::from_bytes(public_share.to_bytes(true).as_ref()),
+                &pk_commitment_blind_factor,
+            );
+
+        let zk_pok_blind_factor = BigInt::sample(KAPPA);
+        let zk_pok_commitment =
+            HashCommitment::<Sha256>::create_commitment_with_user_defined_randomness(
+                &BigInt::from_bytes(d_log_proof.pk_t_rand_commitment.to_bytes(true).as_ref()),
+                &zk_pok_blind_factor,
+            );
+
+        (
+            KeyGenFirstMsg {
+                pk_commitment,
+                zk_pok_commitment,
+            },
+            CommWitness {
+                pk_commitment_blind_factor,
+                zk_pok_blind_factor,
+                public_share: public_share.clone(),
+                d_log_proof,
+            },
+            EcKeyPair {
+                public_share,
+                secret_share,
+            },
+        )
+    }
+}
+
+impl KeyGenSecondMsg {
+    /// Verifies party two's proof of knowledge of its share, and carries out base OTs as
+    /// receiver
+    ///
+    /// Returns decommitment along with OT extension sender, which is needed to
+    /// [set private key](Party1Private::set_private_key).
+    pub fn verify_and_decommit(
+        comm_witness: CommWitness,
+        party_two_first_message: &Party2KeyGenFirstMsg,
+    ) -> Result<(KeyGenSecondMsg, ExtensionSender), ProofError> {
+        if party_two_first_message.public_share != party_two_first_message.d_log_proof.pk {
+            return Err(ProofError);
+        }
+        DLogProof::verify(&party_two_first_message.d_log_proof)?;
+
+        let delta = random_block();
+        let (base_ot, seeds) = base::receive(
+            &party_two_first_message.base_ot,
+            &ExtensionSender::delta_bits(&delta),
+        )?;
+        Ok((
+            KeyGenSecondMsg {
+                comm_witness,
+                base_ot,
+            },
+            ExtensionSender::new(delta, seeds),
+        ))
+    }
+}
+
+pub fn compute_pubkey(
+    party_one_private: &Party1Private,
+    other_share_public_share: &Point<Secp256k1>,
+) -> Point<Secp256k1> {
+    other_share_public_share * &party_one_private.x1
+}
+
+impl Party1Private {
+    pub fn set_private_key(
+        ec_key: &EcKeyPair,
+        other_share_public_share: &Point<Secp256k1>,
+        ot: ExtensionSender,
+    ) -> Party1Private {
+        Party1Private {
+            x1: ec_key.secret_share.clone(),
+            public_key: other_share_public_share * &ec_key.secret_share,
+            ot,
+        }
+    }
+}
+
+impl EphKeyGenFirstMsg {
+    /// Derives nonce `R` from party two's `D`, and runs multiplications with inputs `1/k1` and
+    /// `x1/k1` as sender
+    ///
+    /// Returns error if party two's proof of knowledge or OT extension consistency check doesn't
+    /// pass.
+    pub fn create(
+        party_one_private: &Party1Private,
+        party_two_first_message: &Party2EphKeyGenFirstMsg,
+    ) -> Result<(EphKeyGenFirstMsg, EphEcKeyPair), ProofError> {
+        let d = &party_two_first_message.public_share;
+        if d != &party_two_first_message.d_log_proof.pk || d.is_zero() {
+            return Err(ProofError);
+        }
+        DLogProof::verify(&party_two_first_message.d_log_proof)?;
+        let rows = party_one_private
+            .ot
+            .extend(2 * XI, &party_two_first_message.ot)?;
+
+        let k_prime = Scalar::<Secp256k1>::random();
+        let r_prime = d * &k_prime;
+        let k1 = nonce_tweak(&r_prime) + k_prime;
+        let r = d * &k1;
+        let k1_inv = k1.invert().ok_or(ProofError)?;
+
+        let salt = random_block();
+        let keys = rows.keys(&salt);
+        let (k_inv_share, mut corrections) = mul::sender(&k1_inv, &keys[..XI]);
+        let (x_k_inv_share, x_corrections) =
+            mul::sender(&(&party_one_private.x1 * &k1_inv), &keys[XI..]);
+        corrections.extend(x_corrections);
+
+        Ok((
+            EphKeyGenFirstMsg {
+                r_prime,
+                salt,
+                corrections,
+            },
+            EphEcKeyPair {
+                r,
+                k_inv_share,
+                x_k_inv_share,
+            },
+        ))
+    }
+}
+
+/// Completes signature from party two's partial signature, and verifies it
+///
+/// Takes ephemeral key pair by value, as it must not be used to sign another message.
+pub fn compute_signature(
+    party_one_private: &Party1Private,
+    ephemeral_local_share: EphEcKeyPair,
+    partial_sig: &Party2PartialSig,
+    message: &BigInt,
+) -> Result<SignatureRecid, Error> {
+    let r = ephemeral_local_share.r;
+    let rx = Scalar::<Secp256k1>::from(&r.x_coord().ok_or(Error::InvalidSig)?);
+    let m = Scalar::<Secp256k1>::from(message);
+    let s = m * ephemeral_local_share.k_inv_share
+        + &rx * ephemeral_local_share.x_k_inv_share
+        + &partial_sig.s2;
+    let (s, recid) = verification::normalize_s(&r, s)?;
+    verification::verify(
+        &rx,
+        &s,
+        &party_one_private.public_key,
+        message,
+        Mode::Strict,
+    )?;
+    Ok(SignatureRecid { r: rx, s, recid })
+}
@@ -0,0 +1,230 @@
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+use curv::arithmetic::traits::*;
+use curv::cryptographic_primitives::commitments::hash_commitment::HashCommitment;
+use curv::cryptographic_primitives::commitments::traits::Commitment;
+use curv::cryptographic_primitives::proofs::sigma_dlog::*;
+use curv::cryptographic_primitives::proofs::ProofError;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use curv::BigInt;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use super::mul::{self, XI};
+use super::nonce_tweak;
+use super::ot::base;
+use super::ot::extension::{ExtensionMsg, ExtensionReceiver, ReceiverRows};
+use super::party_one::EphKeyGenFirstMsg as Party1EphKeyGenFirstMsg;
+use super::party_one::KeyGenFirstMsg as Party1KeyGenFirstMessage;
+use super::party_one::KeyGenSecondMsg as Party1KeyGenSecondMessage;
+use super::KAPPA;
+
+//****************** Begin: Party Two structs ******************//
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EcKeyPair {
+    pub public_share: Point<Secp256k1>,
+    secret_share: Scalar<Secp256k1>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeyGenFirstMsg {
+    pub d_log_proof: DLogProof<Secp256k1, Sha256>,
+    pub public_share: Point<Secp256k1>,
+    pub base_ot: base::SenderMsg,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KeyGenSecondMsg {}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Party2Private {
+    x2: Scalar<Secp256k1>,
+    ot: ExtensionReceiver,
+}
+
+/// Party two's nonce along with its encoded multiplication inputs and OT extension rows
+///
+/// Must be used to sign exactly one message.
+#[derive(Serialize, Deserialize)]
+pub struct EphEcKeyPair {
+    pub public_share: Point<Secp256k1>,
+    choices: Vec<bool>,
+    rows: ReceiverRows,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EphKeyGenFirstMsg {
+    pub d_log_proof: DLogProof<Secp256k1, Sha256>,
+    /// `D = k2 * G`
+    pub public_share: Point<Secp256k1>,
+    pub ot: ExtensionMsg,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PartialSig {
+    pub s2: Scalar<Secp256k1>,
+}
+
+//****************** End: Party Two structs ******************//
+
+impl KeyGenFirstMsg {
+    /// Samples secret share and starts base OTs as sender
+    ///
+    /// Base OT sender must be kept until [party one's decommitment](KeyGenSecondMsg::verify_commitments_and_dlog_proof)
+    /// is received.
+    pub fn create() -> (KeyGenFirstMsg, EcKeyPair, base::Sender) {
+        Self::create_with_fixed_secret_share(Scalar::random())
+    }
+
+    pub fn create_with_fixed_secret_share(
+        secret_share: Scalar<Secp256k1>,
+    ) -> (KeyGenFirstMsg, EcKeyPair, base::Sender) {
+        let public_share = Point::generator() * &secret_share;
+        let d_log_proof = DLogProof::prove(&secret_share);
+        let (base_ot_sender, base_ot) = base::Sender::new();
+        (
+            KeyGenFirstMsg {
+                d_log_proof,
+                public_share: public_share.clone(),
+                base_ot,
+            },
+            EcKeyPair {
+                public_share,
+                secret_share,
+            },
+            base_ot_sender,
+        )
+    }
+}
+
+impl KeyGenSecondMsg {
+    /// Verifies party one's decommitment and proof of knowledge of its share, and completes base
+    /// OTs
+    ///
+    /// Returns OT extension receiver, which is needed to [set private key](Party2Private::set_private_key).
+    pub fn verify_commitments_and_dlog_proof(
+        party_one_first_message: &Party1KeyGenFirstMessage,
+        party_one_second_message: &Party1KeyGenSecondMessage,
+        base_ot_sender: &base::Sender,
+    ) -> Result<(KeyGenSecondMsg, ExtensionReceiver), ProofError> {
+        let comm_witness = &party_one_second_message.comm_witness;
+        let pk_commitment =
+            HashCommitment::<Sha256>::create_commitment_with_user_defined_randomness(
+                &BigInt::from_bytes(comm_witness.public_share.to_bytes(true).as_ref()),
+                &comm_witness.pk_commitment_blind_factor,
+            );
+        let zk_pok_commitment =
+            HashCommitment::<Sha256>::create_commitment_with_user_defined_randomness(
+                &BigInt::from_bytes(
+                    comm_witness
+                        .d_log_proof
+                        .pk_t_rand_commitment
+                        .to_bytes(true)
+                        .as_ref(),
+                ),
+                &comm_witness.zk_pok_blind_factor,
+            );
+        if pk_commitment != party_one_first_message.pk_commitment
+            || zk_pok_commitment != party_one_first_message.zk_pok_commitment
+            || comm_witness.public_share != comm_witness.d_log_proof.pk
+        {
+            return Err(ProofError);
+        }
+        DLogProof::verify(&comm_witness.d_log_proof)?;
+
+        let seeds = base_ot_sender.seeds(&party_one_second_message.base_ot, KAPPA)?;
+        Ok((KeyGenSecondMsg {}, ExtensionReceiver::new(seeds)))
+    }
+}
+
+pub fn compute_pubkey(
+    local_share: &EcKeyPair,
+    other_share_public_share: &Point<Secp256k1>,
+) -> Point<Secp256k1> {
+    other_share_public_share * &local_share.secret_share
+}
+
+impl Party2Private {
+    pub fn set_private_key(ec_key: &EcKeyPair, ot: ExtensionReceiver) -> Party2Private {
+        Party2Private {
+            x2: ec_key.secret_share.clone(),
+            ot,
+        }
+    }
+}
+
+impl EphKeyGenFirstMsg {
+    /// Samples nonce `k2` and extends OTs with choices encoding `1/k2` and `x2/k2`
+    pub fn create(party_two_private: &Party2Private) -> (EphKeyGenFirstMsg, EphEcKeyPair) {
+        let k2 = Scalar::<Secp256k1>::random();
+        let k2_inv = k2.invert().expect("nonce is not zero");
+        let d_log_proof = DLogProof::prove(&k2);
+        let public_share = d_log_proof.pk.clone();
+
+        let mut choices = mul::encode(&k2_inv);
+        choices.extend(mul::encode(&(&party_two_private.x2 * &k2_inv)));
+        let (ot, rows) = party_two_private.ot.extend(&choices);
+
+        (
+            EphKeyGenFirstMsg {
+                d_log_proof,
+                public_share: public_share.clone(),
+                ot,
+            },
+            EphEcKeyPair {
+                public_share,
+                choices,
+                rows,
+            },
+        )
+    }
+}
+
+impl PartialSig {
+    /// Completes multiplications and computes party two's share of `s`
+    ///
+    /// Takes ephemeral key pair by value, as it must not be used to sign another message.
+    /// Returns error if party one's message is malformed.
+    pub fn compute(
+        ephemeral_local_share: EphEcKeyPair,
+        party_one_first_message: &Party1EphKeyGenFirstMsg,
+        message: &BigInt,
+    ) -> Result<PartialSig, ProofError> {
+        let r_prime = &party_one_first_message.r_prime;
+        if r_prime.is_zero() {
+            return Err(ProofError);
+        }
+        let r = &ephemeral_local_share.public_share * nonce_tweak(r_prime) + r_prime;
+        let rx = Scalar::<Secp256k1>::from(&r.x_coord().ok_or(ProofError)?);
+
+        let corrections = &party_one_first_message.corrections;
+        if corrections.len() != 2 * XI {
+            return Err(ProofError);
+        }
+        let keys = ephemeral_local_share
+            .rows
+            .keys(&party_one_first_message.salt);
+        let choices = &ephemeral_local_share.choices;
+        let k_inv_share = mul::receiver(&choices[..XI], &keys[..XI], &corrections[..XI])?;
+        let x_k_inv_share = mul::receiver(&choices[XI..], &keys[XI..], &corrections[XI..])?;
+
+        let m = Scalar::<Secp256k1>::from(message);
+        Ok(PartialSig {
+            s2: m * k_inv_share + rx * x_k_inv_share,
+        })
+    }
+}
@@ -0,0 +1,106 @@
+// For integration tests, please add your tests in /tests instead
+
+use crate::protocols::two_party_ecdsa::dkls::{party_one, party_two};
+use crate::utilities::verification::{self, Mode};
+use curv::arithmetic::traits::*;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use curv::BigInt;
+
+fn keygen() -> (
+    party_one::Party1Private,
+    party_two::Party2Private,
+    Point<Secp256k1>,
+) {
+    let (party_one_first_message, comm_witness, ec_key_pair_party1) =
+        party_one::KeyGenFirstMsg::create_commitments();
+    let (party_two_first_message, ec_key_pair_party2, base_ot_sender) =
+        party_two::KeyGenFirstMsg::create();
+    let (party_one_second_message, ot_sender) =
+        party_one::KeyGenSecondMsg::verify_and_decommit(comm_witness, &party_two_first_message)
+            .expect("failed to verify and decommit");
+    let (_party_two_second_message, ot_receiver) =
+        party_two::KeyGenSecondMsg::verify_commitments_and_dlog_proof(
+            &party_one_first_message,
+            &party_one_second_message,
+            &base_ot_sender,
+        )
+        .expect("failed to verify commitments and DLog proof");
+
+    let party1_private = party_one::Party1Private::set_private_key(
+        &ec_key_pair_party1,
+        &party_two_first_message.public_share,
+        ot_sender,
+    );
+    let party2_private =
+        party_two::Party2Private::set_private_key(&ec_key_pair_party2, ot_receiver);
+
+    let pubkey = party_one::compute_pubkey(&party1_private, &party_two_first_message.public_share);
+    assert_eq!(
+        pubkey,
+        party_two::compute_pubkey(
+            &ec_key_pair_party2,
+            &party_one_second_message.comm_witness.public_share
+        )
+    );
+    (party1_private, party2_private, pubkey)
+}
+
+#[test]
+fn test_two_party_sign() {
+    let (party1_private, party2_private, pubkey) = keygen();
+
+    for message in [BigInt::from(1234), BigInt::sample(256)] {
+        let (eph_party_two_first_message, eph_ec_key_pair_party2) =
+            party_two::EphKeyGenFirstMsg::create(&party2_private);
+        let (eph_party_one_first_message, eph_ec_key_pair_party1) =
+            party_one::EphKeyGenFirstMsg::create(&party1_private, &eph_party_two_first_message)
+                .expect("OT extension check failed");
+        let partial_sig = party_two::PartialSig::compute(
+            eph_ec_key_pair_party2,
+            &eph_party_one_first_message,
+            &message,
+        )
+        .expect("invalid party one message");
+        let signature = party_one::compute_signature(
+            &party1_private,
+            eph_ec_key_pair_party1,
+            &partial_sig,
+            &message,
+        )
+        .expect("invalid signature");
+
+        verification::verify(&signature.r, &signature.s, &pubkey, &message, Mode::Strict)
+            .expect("invalid signature");
+        assert_eq!(
+            verification::recover_public_key(&signature, &message).unwrap(),
+            pubkey
+        );
+    }
+}
+
+#[test]
+fn test_invalid_partial_sig() {
+    let (party1_private, party2_private, _pubkey) = keygen();
+    let message = BigInt::from(1234);
+
+    let (eph_party_two_first_message, eph_ec_key_pair_party2) =
+        party_two::EphKeyGenFirstMsg::create(&party2_private);
+    let (eph_party_one_first_message, eph_ec_key_pair_party1) =
+        party_one::EphKeyGenFirstMsg::create(&party1_private, &eph_party_two_first_message)
+            .expect("OT extension check failed");
+    let mut partial_sig = party_two::PartialSig::compute(
+        eph_ec_key_pair_party2,
+        &eph_party_one_first_message,
+        &message,
+    )
+    .expect("invalid party one message");
+    partial_sig.s2 = partial_sig.s2 + Scalar::from(1);
+
+    assert!(party_one::compute_signature(
+        &party1_private,
+        eph_ec_key_pair_party1,
+        &partial_sig,
+        &message,
+    )
+    .is_err());
+}
@@ -31,14 +31,16 @@ use paillier::{Decrypt, EncryptWithChosenRandomness, KeyGeneration};
 use paillier::{DecryptionKey, EncryptionKey, Randomness, RawCiphertext, RawPlaintext};
 use serde::{Deserialize, Serialize};
 use sha2::Sha256;
-use subtle::ConstantTimeEq;
 use zk_paillier::zkproofs::NiCorrectKeyProof;
 
 use super::party_two::EphKeyGenFirstMsg as Party2EphKeyGenFirstMessage;
 use super::party_two::EphKeyGenSecondMsg as Party2EphKeyGenSecondMessage;
+use super::party_two::PartialPreSig;
 use super::SECURITY_BITS;
 
+use crate::utilities::adaptor::{self, PreSignature};
 use crate::utilities::mta::MessageB;
+use crate::utilities::verification::{self, Mode};
 use crate::Error;
 
 use crate::utilities::zk_pdl_with_slack::PDLwSlackProof;
@@ -67,7 +69,7 @@ pub struct KeyGenFirstMsg {
     pub zk_pok_commitment: BigInt,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct KeyGenSecondMsg {
     pub comm_witness: CommWitness,
 }
@@ -80,7 +82,7 @@ pub struct PaillierKeyPair {
     randomness: BigInt,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SignatureRecid {
     pub s: BigInt,
     pub r: BigInt,
@@ -122,7 +124,7 @@ pub struct EphEcKeyPair {
     secret_share: Scalar<Secp256k1>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EphKeyGenFirstMsg {
     pub d_log_proof: ECDDHProof<Secp256k1, Sha256>,
     pub public_share: Point<Secp256k1>,
@@ -132,6 +134,14 @@ pub struct EphKeyGenFirstMsg {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EphKeyGenSecondMsg {}
 
+/// Party one's share `k1 * Y` of adaptor nonce point, sent along with [EphKeyGenFirstMsg] when
+/// signing produces a pre-signature
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EphAdaptorMsg {
+    pub k1_y: Point<Secp256k1>,
+    pub d_log_proof: ECDDHProof<Secp256k1, Sha256>, // log_G R1 = log_Y k1_y
+}
+
 //****************** End: Party One structs ******************//
 
 impl KeyGenFirstMsg {
@@ -482,6 +492,26 @@ impl EphKeyGenSecondMsg {
     }
 }
 
+impl EphAdaptorMsg {
+    pub fn create(
+        ephemeral_local_share: &EphEcKeyPair,
+        adaptor_point: &Point<Secp256k1>,
+    ) -> EphAdaptorMsg {
+        let k1_y = adaptor_point * &ephemeral_local_share.secret_share;
+        let w = ECDDHWitness {
+            x: ephemeral_local_share.secret_share.clone(),
+        };
+        let delta = ECDDHStatement {
+            g1: Point::generator().to_point(),
+            h1: ephemeral_local_share.public_share.clone(),
+            g2: adaptor_point.clone(),
+            h2: k1_y.clone(),
+        };
+        let d_log_proof = ECDDHProof::prove(&w, &delta);
+        EphAdaptorMsg { k1_y, d_log_proof }
+    }
+}
+
 impl Signature {
     pub fn compute(
         party_one_private: &Party1Private,
@@ -521,19 +551,18 @@ impl Signature {
         partial_sig_c3: &BigInt,
         ephemeral_local_share: &EphEcKeyPair,
         ephemeral_other_public_share: &Point<Secp256k1>,
-    ) -> SignatureRecid {
+    ) -> Result<SignatureRecid, Error> {
         //compute r = k2* R1
         let r = ephemeral_other_public_share * &ephemeral_local_share.secret_share;
 
         let rx = r
             .x_coord()
-            .unwrap()
-            .mod_floor(Scalar::<Secp256k1>::group_order());
-        let ry = r
-            .y_coord()
-            .unwrap()
+            .ok_or(Error::InvalidSig)?
             .mod_floor(Scalar::<Secp256k1>::group_order());
-        let k1_inv = ephemeral_local_share.secret_share.invert().unwrap();
+        let k1_inv = ephemeral_local_share
+            .secret_share
+            .invert()
+            .ok_or(Error::InvalidSig)?;
 
         let s_tag = Paillier::decrypt(
             &party_one_private.paillier_priv,
@@ -542,25 +571,69 @@ impl Signature {
         .0;
         let s_tag_fe = Scalar::<Secp256k1>::from(s_tag.as_ref());
         let s_tag_tag = s_tag_fe * k1_inv;
-        let s_tag_tag_bn = s_tag_tag.to_bigint();
-        let s = cmp::min(
-            s_tag_tag_bn.clone(),
-            Scalar::<Secp256k1>::group_order() - &s_tag_tag_bn,
-        );
+        let (s, recid) = verification::normalize_s(&r, s_tag_tag)?;
 
-        /*
-         Calculate recovery id - it is not possible to compute the public key out of the signature
-         itself. Recovery id is used to enable extracting the public key uniquely.
-         1. id = R.y & 1
-         2. if (s > curve.q / 2) id = id ^ 1
-        */
-        let is_ry_odd = ry.test_bit(0);
-        let mut recid = if is_ry_odd { 1 } else { 0 };
-        if s_tag_tag_bn > Scalar::<Secp256k1>::group_order() - &s_tag_tag_bn {
-            recid ^= 1;
+        Ok(SignatureRecid {
+            s: s.to_bigint(),
+            r: rx,
+            recid,
+        })
+    }
+}
+
+impl Signature {
+    /// Computes pre-signature tied to `adaptor_point` from party two's [PartialPreSig]
+    ///
+    /// Party two proves that `R_Y = k2 * k1_y` with respect to nonce point `R = k2 * R1`, party
+    /// one turns that into a proof that `log_G R = log_Y R_Y` by multiplying response by `k1`.
+    /// Resulting pre-signature is verified before it's returned.
+    pub fn compute_pre_signature(
+        party_one_private: &Party1Private,
+        partial_pre_sig: &PartialPreSig,
+        ephemeral_local_share: &EphEcKeyPair,
+        ephemeral_other_public_share: &Point<Secp256k1>,
+        adaptor_point: &Point<Secp256k1>,
+        pubkey: &Point<Secp256k1>,
+        message: &BigInt,
+    ) -> Result<PreSignature, Error> {
+        let k1 = &ephemeral_local_share.secret_share;
+        let r = ephemeral_other_public_share * k1;
+        let k1_y = adaptor_point * k1;
+
+        let e = adaptor::challenge(
+            &r,
+            adaptor_point,
+            &partial_pre_sig.r_y,
+            &partial_pre_sig.a1,
+            &partial_pre_sig.a2,
+        );
+        if &ephemeral_local_share.public_share * &partial_pre_sig.z2
+            != &partial_pre_sig.a1 + &r * &e
+            || k1_y * &partial_pre_sig.z2 != &partial_pre_sig.a2 + &partial_pre_sig.r_y * &e
+        {
+            return Err(Error::InvalidSig);
         }
 
-        SignatureRecid { s, r: rx, recid }
+        let s_tag = Paillier::decrypt(
+            &party_one_private.paillier_priv,
+            &RawCiphertext::from(&partial_pre_sig.c3),
+        )
+        .0;
+        let s_hat =
+            Scalar::<Secp256k1>::from(s_tag.as_ref()) * k1.invert().ok_or(Error::InvalidSig)?;
+
+        let pre_signature = PreSignature {
+            R: r,
+            R_Y: partial_pre_sig.r_y.clone(),
+            s_hat,
+            proof: adaptor::proof(
+                partial_pre_sig.a1.clone(),
+                partial_pre_sig.a2.clone(),
+                &partial_pre_sig.z2 * k1,
+            ),
+        };
+        adaptor::verify_pre_signature(&pre_signature, pubkey, adaptor_point, message)?;
+        Ok(pre_signature)
     }
 }
 
@@ -569,26 +642,15 @@ pub fn verify(
     pubkey: &Point<Secp256k1>,
     message: &BigInt,
 ) -> Result<(), Error> {
-    let s_fe = Scalar::<Secp256k1>::from(&signature.s);
-    let rx_fe = Scalar::<Secp256k1>::from(&signature.r);
-
-    let s_inv_fe = s_fe.invert().unwrap();
-    let e_fe: Scalar<Secp256k1> =
-        Scalar::<Secp256k1>::from(&message.mod_floor(Scalar::<Secp256k1>::group_order()));
-    let u1 = Point::generator() * e_fe * &s_inv_fe;
-    let u2 = &*pubkey * rx_fe * &s_inv_fe;
-
-    // second condition is against malleability
-    let rx_bytes = &BigInt::to_bytes(&signature.r)[..];
-    let u1_plus_u2_bytes = &BigInt::to_bytes(&(u1 + u2).x_coord().unwrap())[..];
-
-    if rx_bytes.ct_eq(u1_plus_u2_bytes).unwrap_u8() == 1
-        && signature.s < Scalar::<Secp256k1>::group_order() - signature.s.clone()
-    {
-        Ok(())
-    } else {
-        Err(Error::InvalidSig)
+    let q = Scalar::<Secp256k1>::group_order();
+    if &signature.r >= q || &signature.s >= q {
+        return Err(Error::InvalidSig);
     }
+    let r = Scalar::<Secp256k1>::from(&signature.r);
+    let s = Scalar::<Secp256k1>::from(&signature.s);
+    let message = message.mod_floor(q);
+
+    verification::verify(&r, &s, pubkey, &message, Mode::Strict)
 }
 
 pub fn generate_h1_h2_n_tilde() -> (BigInt, BigInt, BigInt, BigInt) {
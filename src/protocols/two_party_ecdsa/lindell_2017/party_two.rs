@@ -32,10 +32,12 @@ use serde::{Deserialize, Serialize};
 use sha2::Sha256;
 use zk_paillier::zkproofs::{IncorrectProof, NiCorrectKeyProof};
 
+use super::party_one::EphAdaptorMsg as Party1EphAdaptorMsg;
 use super::party_one::EphKeyGenFirstMsg as Party1EphKeyGenFirstMsg;
 use super::party_one::KeyGenFirstMsg as Party1KeyGenFirstMessage;
 use super::party_one::KeyGenSecondMsg as Party1KeyGenSecondMessage;
 use super::SECURITY_BITS;
+use crate::utilities::adaptor;
 use crate::utilities::mta::{MessageA, MessageB};
 
 use crate::utilities::zk_pdl_with_slack::PDLwSlackProof;
@@ -67,18 +69,29 @@ pub struct KeyGenFirstMsg {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct KeyGenSecondMsg {}
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PaillierPublic {
     pub ek: EncryptionKey,
     pub encrypted_secret_share: BigInt,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PartialSig {
     pub c3: BigInt,
 }
 
-#[derive(Serialize, Deserialize)]
+/// Partial pre-signature: encrypted `k2^-1 (m + r x)` with `r` taken from adaptor nonce point
+/// `R_Y`, along with party two's part of the proof that `log_G R = log_Y R_Y`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PartialPreSig {
+    pub c3: BigInt,
+    pub r_y: Point<Secp256k1>,
+    pub a1: Point<Secp256k1>,
+    pub a2: Point<Secp256k1>,
+    pub z2: Scalar<Secp256k1>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Party2Private {
     x2: Scalar<Secp256k1>,
 }
@@ -130,7 +143,7 @@ pub struct EphKeyGenFirstMsg {
     pub zk_pok_commitment: BigInt,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EphKeyGenSecondMsg {
     pub comm_witness: EphCommWitness,
 }
@@ -396,10 +409,27 @@ impl PartialSig {
         ephemeral_other_public_share: &Point<Secp256k1>,
         message: &BigInt,
     ) -> PartialSig {
-        let q = Scalar::<Secp256k1>::group_order();
         //compute r = k2* R1
         let r = ephemeral_other_public_share * &ephemeral_local_share.secret_share;
+        Self::compute_with_r(
+            ek,
+            encrypted_secret_share,
+            local_share,
+            ephemeral_local_share,
+            &r,
+            message,
+        )
+    }
 
+    fn compute_with_r(
+        ek: &EncryptionKey,
+        encrypted_secret_share: &BigInt,
+        local_share: &Party2Private,
+        ephemeral_local_share: &EphEcKeyPair,
+        r: &Point<Secp256k1>,
+        message: &BigInt,
+    ) -> PartialSig {
+        let q = Scalar::<Secp256k1>::group_order();
         let rx = r.x_coord().unwrap().mod_floor(q);
         let rho = BigInt::sample_below(&q.pow(2));
         let k2_inv = BigInt::mod_inv(&ephemeral_local_share.secret_share.to_bigint(), q).unwrap();
@@ -422,3 +452,57 @@ impl PartialSig {
         }
     }
 }
+
+impl PartialPreSig {
+    /// Computes partial pre-signature tied to `adaptor_point`
+    ///
+    /// Verifies that party one's share `k1 * Y` is consistent with its ephemeral public share.
+    pub fn compute(
+        ek: &EncryptionKey,
+        encrypted_secret_share: &BigInt,
+        local_share: &Party2Private,
+        ephemeral_local_share: &EphEcKeyPair,
+        ephemeral_other_public_share: &Point<Secp256k1>,
+        party_one_adaptor_message: &Party1EphAdaptorMsg,
+        adaptor_point: &Point<Secp256k1>,
+        message: &BigInt,
+    ) -> Result<PartialPreSig, ProofError> {
+        let delta = ECDDHStatement {
+            g1: Point::generator().to_point(),
+            h1: ephemeral_other_public_share.clone(),
+            g2: adaptor_point.clone(),
+            h2: party_one_adaptor_message.k1_y.clone(),
+        };
+        party_one_adaptor_message.d_log_proof.verify(&delta)?;
+
+        let k2 = &ephemeral_local_share.secret_share;
+        let r = ephemeral_other_public_share * k2;
+        let r_y = &party_one_adaptor_message.k1_y * k2;
+        if r_y.is_zero() {
+            return Err(ProofError);
+        }
+
+        // Proof of log_R1 R = log_{k1 Y} R_Y = k2, which party one multiplies by k1
+        let rho = Scalar::<Secp256k1>::random();
+        let a1 = ephemeral_other_public_share * &rho;
+        let a2 = &party_one_adaptor_message.k1_y * &rho;
+        let e = adaptor::challenge(&r, adaptor_point, &r_y, &a1, &a2);
+        let z2 = rho + e * k2;
+
+        let partial_sig = PartialSig::compute_with_r(
+            ek,
+            encrypted_secret_share,
+            local_share,
+            ephemeral_local_share,
+            &r_y,
+            message,
+        );
+        Ok(PartialPreSig {
+            c3: partial_sig.c3,
+            r_y,
+            a1,
+            a2,
+            z2,
+        })
+    }
+}
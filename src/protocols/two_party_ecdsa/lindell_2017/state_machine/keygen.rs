@@ -0,0 +1,436 @@
+//! Lindell 2017 two-party key generation
+//!
+//! At round 1 party one commits to its public share `Q1 = x1 * G`. At round 2 party two replies
+//! with `Q2 = x2 * G` and a proof of knowledge of `x2`. At round 3 party one decommits `Q1`, and
+//! sends its Paillier encryption key along with encryption of `x1` and proofs that both are well
+//! formed. Party one completes the protocol after sending the last message.
+
+use std::fmt;
+use std::mem::replace;
+use std::time::Duration;
+
+use round_based::containers::{
+    push::{Push, PushExt},
+    *,
+};
+use round_based::{IsCritical, Msg, StateMachine};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::protocols::two_party_ecdsa::lindell_2017::{party_one, party_two};
+
+mod rounds;
+
+use private::InternalError;
+pub use rounds::{
+    LocalKey, PaillierKeyError, PaillierKeyProof, Party1Decommitment, Party1Key, Party2Key,
+    ProceedError,
+};
+use rounds::{Round0, Round1, Round2, Round3};
+
+/// Keygen protocol state machine
+///
+/// Successfully completed keygen protocol produces [LocalKey] that can be used in further
+/// [signing](super::sign) and [rotation](super::rotation) protocols.
+pub struct Keygen {
+    round: R,
+
+    msgs1: Option<Store<P2PMsgs<party_one::KeyGenFirstMsg>>>,
+    msgs2: Option<Store<P2PMsgs<party_two::KeyGenFirstMsg>>>,
+    msgs3: Option<Store<P2PMsgs<Party1Decommitment>>>,
+
+    msgs_queue: Vec<Msg<ProtocolMessage>>,
+
+    party_i: u16,
+}
+
+impl Keygen {
+    /// Constructs a party of keygen protocol
+    ///
+    /// Takes party index `i` which determines party's role: `1` for party one (the one holding
+    /// Paillier key), `2` for party two.
+    ///
+    /// Returns [Error::InvalidPartyIndex] if `i` is neither `1` nor `2`.
+    pub fn new(i: u16) -> Result<Self> {
+        let round = match i {
+            1 => R::Round0(Round0),
+            2 => R::Round1(Round1),
+            _ => return Err(Error::InvalidPartyIndex),
+        };
+        let mut state = Self {
+            round,
+
+            msgs1: (i == 2).then(|| Round1::expects_messages(i)),
+            msgs2: (i == 1).then(|| Round2::expects_messages(i)),
+            msgs3: (i == 2).then(|| Round3::expects_messages(i)),
+
+            msgs_queue: vec![],
+
+            party_i: i,
+        };
+
+        state.proceed_round(false)?;
+        Ok(state)
+    }
+
+    fn gmap_queue<'a, T, F>(&'a mut self, f: F) -> impl Push<Msg<T>> + 'a
+    where
+        F: Fn(T) -> ProtocolMessage + 'a,
+    {
+        (&mut self.msgs_queue).gmap(move |m: Msg<T>| m.map_body(&f))
+    }
+
+    /// Proceeds round state if it received enough messages and if it's cheap to compute or
+    /// `may_block == true`
+    fn proceed_round(&mut self, may_block: bool) -> Result<()> {
+        let store1_wants_more = self.msgs1.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store2_wants_more = self.msgs2.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store3_wants_more = self.msgs3.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+
+        let next_state: R;
+        let try_again: bool = match replace(&mut self.round, R::Gone) {
+            R::Round0(round) if !round.is_expensive() || may_block => {
+                next_state = round
+                    .proceed(self.gmap_queue(ProtocolMessage::Party1Commitment))
+                    .map(R::Round2)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round0(_) => {
+                next_state = s;
+                false
+            }
+            R::Round1(round) if !store1_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs1.take().ok_or(InternalError::StoreGone)?;
+                let msg = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?
+                    .into_vec()
+                    .remove(0);
+                next_state = round
+                    .proceed(msg, self.gmap_queue(ProtocolMessage::Party2PublicShare))
+                    .map(R::Round3)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round1(_) => {
+                next_state = s;
+                false
+            }
+            R::Round2(round) if !store2_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs2.take().ok_or(InternalError::StoreGone)?;
+                let msg = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?
+                    .into_vec()
+                    .remove(0);
+                next_state = round
+                    .proceed(msg, self.gmap_queue(ProtocolMessage::Party1Decommitment))
+                    .map(R::Final)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round2(_) => {
+                next_state = s;
+                false
+            }
+            R::Round3(round) if !store3_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs3.take().ok_or(InternalError::StoreGone)?;
+                let msg = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?
+                    .into_vec()
+                    .remove(0);
+                next_state = round
+                    .proceed(msg)
+                    .map(R::Final)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round3(_) => {
+                next_state = s;
+                false
+            }
+            s @ R::Final(_) | s @ R::Gone => {
+                next_state = s;
+                false
+            }
+        };
+
+        self.round = next_state;
+        if try_again {
+            self.proceed_round(may_block)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl StateMachine for Keygen {
+    type MessageBody = ProtocolMessage;
+    type Err = Error;
+    type Output = LocalKey;
+
+    fn handle_incoming(&mut self, msg: Msg<Self::MessageBody>) -> Result<()> {
+        let current_round = self.current_round();
+
+        match msg.body {
+            ProtocolMessage::Party1Commitment(m) => {
+                let store = self
+                    .msgs1
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 1,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+            ProtocolMessage::Party2PublicShare(m) => {
+                let store = self
+                    .msgs2
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 2,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+            ProtocolMessage::Party1Decommitment(m) => {
+                let store = self
+                    .msgs3
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 3,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+        }
+        self.proceed_round(false)
+    }
+
+    fn message_queue(&mut self) -> &mut Vec<Msg<Self::MessageBody>> {
+        &mut self.msgs_queue
+    }
+
+    fn wants_to_proceed(&self) -> bool {
+        let store1_wants_more = self.msgs1.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store2_wants_more = self.msgs2.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store3_wants_more = self.msgs3.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+
+        match &self.round {
+            R::Round0(_) => true,
+            R::Round1(_) => !store1_wants_more,
+            R::Round2(_) => !store2_wants_more,
+            R::Round3(_) => !store3_wants_more,
+            R::Final(_) | R::Gone => false,
+        }
+    }
+
+    fn proceed(&mut self) -> Result<()> {
+        self.proceed_round(true)
+    }
+
+    fn round_timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    fn round_timeout_reached(&mut self) -> Self::Err {
+        panic!("no timeout was set")
+    }
+
+    fn is_finished(&self) -> bool {
+        matches!(self.round, R::Final(_))
+    }
+
+    fn pick_output(&mut self) -> Option<Result<Self::Output>> {
+        match self.round {
+            R::Final(_) => (),
+            R::Gone => return Some(Err(Error::DoublePickOutput)),
+            _ => return None,
+        }
+
+        match replace(&mut self.round, R::Gone) {
+            R::Final(result) => Some(Ok(result)),
+            _ => unreachable!("guaranteed by match expression above"),
+        }
+    }
+
+    fn current_round(&self) -> u16 {
+        match &self.round {
+            R::Round0(_) => 0,
+            R::Round1(_) => 1,
+            R::Round2(_) => 2,
+            R::Round3(_) => 3,
+            R::Final(_) | R::Gone => 4,
+        }
+    }
+
+    fn total_rounds(&self) -> Option<u16> {
+        Some(3)
+    }
+
+    fn party_ind(&self) -> u16 {
+        self.party_i
+    }
+
+    fn parties(&self) -> u16 {
+        2
+    }
+}
+
+impl fmt::Debug for Keygen {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{{Lindell17 Keygen party={} at round={} queue=[len={}]}}",
+            self.party_i,
+            self.current_round(),
+            self.msgs_queue.len()
+        )
+    }
+}
+
+// Rounds
+
+#[allow(clippy::large_enum_variant)]
+enum R {
+    Round0(Round0),
+    Round1(Round1),
+    Round2(Round2),
+    Round3(Round3),
+    Final(LocalKey),
+    Gone,
+}
+
+// Messages
+
+/// Protocol message which parties send on wire
+///
+/// Variant `k` is sent at round `k`, party one and party two take turns sending messages.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)]
+pub enum ProtocolMessage {
+    /// Party one's commitment to `Q1`
+    Party1Commitment(party_one::KeyGenFirstMsg),
+    /// Party two's public share `Q2` and proof of knowledge of `x2`
+    Party2PublicShare(party_two::KeyGenFirstMsg),
+    /// Party one's decommitment and Paillier key
+    Party1Decommitment(Party1Decommitment),
+}
+
+// Error
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Error type of lindell17 keygen protocol
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// Round proceeding resulted in error
+    #[error("proceed round: {0}")]
+    ProceedRound(#[source] ProceedError),
+
+    /// Party index `i` is neither `1` nor `2`
+    #[error("party index is neither 1 nor 2")]
+    InvalidPartyIndex,
+
+    /// Received message didn't pass pre-validation
+    #[error("received message didn't pass pre-validation: {0}")]
+    HandleMessage(#[source] StoreErr),
+    /// Received message which we didn't expect to receive now (e.g. message from previous round
+    /// or message that this party sends)
+    #[error(
+        "didn't expect to receive message from round {msg_round} (being at round {current_round})"
+    )]
+    ReceivedOutOfOrderMessage { current_round: u16, msg_round: u16 },
+    /// [Keygen::pick_output] called twice
+    #[error("pick_output called twice")]
+    DoublePickOutput,
+
+    /// Some internal assertions were failed, which is a bug
+    #[doc(hidden)]
+    #[error("internal error: {0:?}")]
+    InternalError(InternalError),
+}
+
+impl IsCritical for Error {
+    fn is_critical(&self) -> bool {
+        !matches!(
+            self,
+            Error::HandleMessage(_) | Error::ReceivedOutOfOrderMessage { .. }
+        )
+    }
+}
+
+impl From<InternalError> for Error {
+    fn from(err: InternalError) -> Self {
+        Self::InternalError(err)
+    }
+}
+
+mod private {
+    #[derive(Debug)]
+    #[non_exhaustive]
+    pub enum InternalError {
+        /// [Messages store](super::MessageStore) reported that it received all messages it wanted to receive,
+        /// but refused to return message container
+        RetrieveRoundMessages(super::StoreErr),
+        #[doc(hidden)]
+        StoreGone,
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use curv::elliptic::curves::Point;
+    use round_based::dev::Simulation;
+
+    use super::*;
+
+    pub fn simulate_keygen() -> (Party1Key, Party2Key) {
+        let mut simulation = Simulation::new();
+        simulation.add_party(Keygen::new(1).unwrap());
+        simulation.add_party(Keygen::new(2).unwrap());
+        let mut keys = simulation.run().unwrap().into_iter();
+        match (keys.next(), keys.next()) {
+            (Some(LocalKey::PartyOne(party_one)), Some(LocalKey::PartyTwo(party_two))) => {
+                (party_one, party_two)
+            }
+            _ => panic!("unexpected keygen output"),
+        }
+    }
+
+    #[test]
+    fn simulate_keygen_two_parties() {
+        let (party_one, party_two) = simulate_keygen();
+        assert_eq!(party_one.public_key, party_two.public_key);
+        assert_eq!(party_one.public_share, party_two.other_public_share);
+        assert_eq!(party_two.public_share, party_one.other_public_share);
+        assert_ne!(party_one.public_key, Point::zero());
+    }
+
+    #[test]
+    fn rejects_invalid_party_index() {
+        assert!(matches!(Keygen::new(0), Err(Error::InvalidPartyIndex)));
+        assert!(matches!(Keygen::new(3), Err(Error::InvalidPartyIndex)));
+    }
+}
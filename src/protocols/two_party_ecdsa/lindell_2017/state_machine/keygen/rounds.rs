@@ -0,0 +1,282 @@
+use curv::elliptic::curves::{Point, Secp256k1};
+use curv::BigInt;
+use paillier::EncryptionKey;
+use round_based::containers::push::Push;
+use round_based::containers::{self, P2PMsgs, Store};
+use round_based::Msg;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use zk_paillier::zkproofs::{CompositeDLogProof, NiCorrectKeyProof};
+
+use crate::protocols::two_party_ecdsa::lindell_2017::{party_one, party_two};
+use crate::utilities::zk_pdl_with_slack::{PDLwSlackProof, PDLwSlackStatement};
+
+pub struct Round0;
+
+impl Round0 {
+    pub fn proceed<O>(self, mut output: O) -> Result<Round2>
+    where
+        O: Push<Msg<party_one::KeyGenFirstMsg>>,
+    {
+        let (first_msg, comm_witness, ec_key_pair) =
+            party_one::KeyGenFirstMsg::create_commitments();
+        output.push(Msg {
+            sender: 1,
+            receiver: Some(2),
+            body: first_msg,
+        });
+        Ok(Round2 {
+            comm_witness,
+            ec_key_pair,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        false
+    }
+}
+
+pub struct Round1;
+
+impl Round1 {
+    pub fn proceed<O>(self, input: party_one::KeyGenFirstMsg, mut output: O) -> Result<Round3>
+    where
+        O: Push<Msg<party_two::KeyGenFirstMsg>>,
+    {
+        let (first_msg, ec_key_pair) = party_two::KeyGenFirstMsg::create();
+        output.push(Msg {
+            sender: 2,
+            receiver: Some(1),
+            body: first_msg,
+        });
+        Ok(Round3 {
+            party_one_first_msg: input,
+            ec_key_pair,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        false
+    }
+    pub fn expects_messages(i: u16) -> Store<P2PMsgs<party_one::KeyGenFirstMsg>> {
+        containers::P2PMsgsStore::new(i, 2)
+    }
+}
+
+pub struct Round2 {
+    comm_witness: party_one::CommWitness,
+    ec_key_pair: party_one::EcKeyPair,
+}
+
+impl Round2 {
+    pub fn proceed<O>(self, input: party_two::KeyGenFirstMsg, mut output: O) -> Result<LocalKey>
+    where
+        O: Push<Msg<Party1Decommitment>>,
+    {
+        if input.d_log_proof.pk != input.public_share {
+            return Err(ProceedError::Round2InvalidPublicShare);
+        }
+        let decommitment =
+            party_one::KeyGenSecondMsg::verify_and_decommit(self.comm_witness, &input.d_log_proof)
+                .map_err(|_| ProceedError::Round2InvalidPublicShare)?;
+
+        let paillier_key_pair =
+            party_one::PaillierKeyPair::generate_keypair_and_encrypted_share(&self.ec_key_pair);
+        let private =
+            party_one::Party1Private::set_private_key(&self.ec_key_pair, &paillier_key_pair);
+        let correct_key_proof =
+            party_one::PaillierKeyPair::generate_ni_proof_correct_key(&paillier_key_pair);
+        let (pdl_statement, pdl_proof, composite_dlog_proof) =
+            party_one::PaillierKeyPair::pdl_proof(&private, &paillier_key_pair);
+
+        output.push(Msg {
+            sender: 1,
+            receiver: Some(2),
+            body: Party1Decommitment {
+                decommitment,
+                paillier_key: PaillierKeyProof {
+                    ek: paillier_key_pair.ek.clone(),
+                    encrypted_share: paillier_key_pair.encrypted_share.clone(),
+                    correct_key_proof,
+                    pdl_statement,
+                    pdl_proof,
+                    composite_dlog_proof,
+                },
+            },
+        });
+
+        let public_key = party_one::compute_pubkey(&private, &input.public_share);
+        Ok(LocalKey::PartyOne(Party1Key {
+            private,
+            public_share: self.ec_key_pair.public_share,
+            other_public_share: input.public_share,
+            public_key,
+        }))
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+    pub fn expects_messages(i: u16) -> Store<P2PMsgs<party_two::KeyGenFirstMsg>> {
+        containers::P2PMsgsStore::new(i, 2)
+    }
+}
+
+pub struct Round3 {
+    party_one_first_msg: party_one::KeyGenFirstMsg,
+    ec_key_pair: party_two::EcKeyPair,
+}
+
+impl Round3 {
+    pub fn proceed(self, input: Party1Decommitment) -> Result<LocalKey> {
+        party_two::KeyGenSecondMsg::verify_commitments_and_dlog_proof(
+            &self.party_one_first_msg,
+            &input.decommitment,
+        )
+        .map_err(|_| ProceedError::Round3InvalidDecommitment)?;
+
+        let other_public_share = input.decommitment.comm_witness.public_share;
+        let paillier_public = input
+            .paillier_key
+            .verify(&other_public_share)
+            .map_err(ProceedError::Round3InvalidPaillierKey)?;
+
+        let public_key = party_two::compute_pubkey(&self.ec_key_pair, &other_public_share);
+        Ok(LocalKey::PartyTwo(Party2Key {
+            private: party_two::Party2Private::set_private_key(&self.ec_key_pair),
+            paillier_public,
+            public_share: self.ec_key_pair.public_share,
+            other_public_share,
+            public_key,
+        }))
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+    pub fn expects_messages(i: u16) -> Store<P2PMsgs<Party1Decommitment>> {
+        containers::P2PMsgsStore::new(i, 2)
+    }
+}
+
+/// Party one's decommitment to its public share `Q1` along with its Paillier key, sent at round 3
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Party1Decommitment {
+    pub decommitment: party_one::KeyGenSecondMsg,
+    pub paillier_key: PaillierKeyProof,
+}
+
+/// Party one's Paillier encryption key and encryption of its secret share `x1`, along with proofs
+/// that the key is well formed and that ciphertext encrypts discrete log of `Q1`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PaillierKeyProof {
+    pub ek: EncryptionKey,
+    pub encrypted_share: BigInt,
+    pub correct_key_proof: NiCorrectKeyProof,
+    pub pdl_statement: PDLwSlackStatement,
+    pub pdl_proof: PDLwSlackProof,
+    pub composite_dlog_proof: CompositeDLogProof,
+}
+
+impl PaillierKeyProof {
+    /// Verifies proofs against party one's public share `q1`, returns party one's Paillier
+    /// public key and encrypted secret share
+    pub fn verify(
+        self,
+        q1: &Point<Secp256k1>,
+    ) -> std::result::Result<party_two::PaillierPublic, PaillierKeyError> {
+        party_two::PaillierPublic::verify_ni_proof_correct_key(self.correct_key_proof, &self.ek)
+            .map_err(|_| PaillierKeyError::InvalidCorrectKeyProof)?;
+        let paillier_public = party_two::PaillierPublic {
+            ek: self.ek,
+            encrypted_secret_share: self.encrypted_share,
+        };
+        party_two::PaillierPublic::pdl_verify(
+            &self.composite_dlog_proof,
+            &self.pdl_statement,
+            &self.pdl_proof,
+            &paillier_public,
+            q1,
+        )
+        .map_err(|_| PaillierKeyError::InvalidPdlProof)?;
+        Ok(paillier_public)
+    }
+}
+
+/// Local secret obtained by party after [keygen](super::Keygen) protocol is completed
+///
+/// Party one and party two hold different kinds of secrets, so the key is tagged with the role
+/// it was generated for.
+#[derive(Clone, Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)]
+pub enum LocalKey {
+    PartyOne(Party1Key),
+    PartyTwo(Party2Key),
+}
+
+impl LocalKey {
+    /// Joint public key `Q = x1 * x2 * G`
+    pub fn public_key(&self) -> &Point<Secp256k1> {
+        match self {
+            LocalKey::PartyOne(key) => &key.public_key,
+            LocalKey::PartyTwo(key) => &key.public_key,
+        }
+    }
+
+    /// Index of party the key belongs to: `1` for party one, `2` for party two
+    pub fn party_ind(&self) -> u16 {
+        match self {
+            LocalKey::PartyOne(_) => 1,
+            LocalKey::PartyTwo(_) => 2,
+        }
+    }
+}
+
+/// Party one's share of the key
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Party1Key {
+    /// Secret share `x1` and Paillier decryption key
+    pub private: party_one::Party1Private,
+    /// `Q1 = x1 * G`
+    pub public_share: Point<Secp256k1>,
+    /// `Q2 = x2 * G`
+    pub other_public_share: Point<Secp256k1>,
+    pub public_key: Point<Secp256k1>,
+}
+
+/// Party two's share of the key
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Party2Key {
+    /// Secret share `x2`
+    pub private: party_two::Party2Private,
+    /// Party one's Paillier encryption key and encrypted share `x1`
+    pub paillier_public: party_two::PaillierPublic,
+    /// `Q2 = x2 * G`
+    pub public_share: Point<Secp256k1>,
+    /// `Q1 = x1 * G`
+    pub other_public_share: Point<Secp256k1>,
+    pub public_key: Point<Secp256k1>,
+}
+
+// Errors
+
+type Result<T> = std::result::Result<T, ProceedError>;
+
+/// Proceeding protocol error
+///
+/// Subset of [keygen errors](enum@super::Error) that can occur at protocol proceeding (i.e. after
+/// every message was received and pre-validated).
+#[derive(Debug, Error)]
+pub enum ProceedError {
+    #[error("round 2: party two's proof of knowledge of x2 is invalid")]
+    Round2InvalidPublicShare,
+    #[error("round 3: party one's decommitment or proof of knowledge of x1 is invalid")]
+    Round3InvalidDecommitment,
+    #[error("round 3: {0}")]
+    Round3InvalidPaillierKey(#[source] PaillierKeyError),
+}
+
+/// Party one's [Paillier key](PaillierKeyProof) didn't pass verification
+#[derive(Debug, Error)]
+pub enum PaillierKeyError {
+    #[error("proof of correctness of paillier key is invalid")]
+    InvalidCorrectKeyProof,
+    #[error("pdl proof is invalid")]
+    InvalidPdlProof,
+}
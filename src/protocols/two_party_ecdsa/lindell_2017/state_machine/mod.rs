@@ -0,0 +1,10 @@
+//! Lindell 2017 keygen, signing and rotation as [round_based::StateMachine]s
+//!
+//! Chains [party_one](super::party_one) and [party_two](super::party_two) messages into
+//! protocols that can be carried out with `AsyncProtocol` or `Simulation` in the same way as
+//! [GG20](crate::protocols::multi_party_ecdsa::gg_2020::state_machine). Party one has index `1`
+//! and party two has index `2`.
+
+pub mod keygen;
+pub mod rotation;
+pub mod sign;
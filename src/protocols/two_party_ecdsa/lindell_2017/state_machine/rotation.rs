@@ -0,0 +1,512 @@
+//! Lindell 2017 key rotation
+//!
+//! Parties refresh their secret shares without changing joint public key. At rounds 1 to 3 they
+//! run a coin flip to agree on random factor `r`. Along with decommitting its seed at round 3,
+//! party one sends new Paillier key and encryption of `r * x1` with proofs that both are well
+//! formed. Party two updates its share to `r^-1 * x2` and, at round 4, confirms rotation by
+//! sending its new public share, which party one checks against its own view of the coin flip.
+//!
+//! Party one outputs new key only after receiving the confirmation, i.e. once party two has
+//! verified the refresh and switched to the new key. Party two outputs new key after sending
+//! the confirmation, so if the confirmation isn't delivered, party one is left with the old key
+//! only. Party two should therefore keep its old key until the new one is used in signing.
+
+use std::fmt;
+use std::mem::replace;
+use std::time::Duration;
+
+use curv::cryptographic_primitives::twoparty::coin_flip_optimal_rounds::{
+    Party1FirstMessage, Party2FirstMessage,
+};
+use curv::elliptic::curves::Secp256k1;
+use round_based::containers::{
+    push::{Push, PushExt},
+    *,
+};
+use round_based::{IsCritical, Msg, StateMachine};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use thiserror::Error;
+
+use super::keygen::LocalKey;
+
+mod rounds;
+
+use private::InternalError;
+pub use rounds::{Party1Refresh, Party2Confirmation, ProceedError};
+use rounds::{Round0, Round1, Round2, Round3, Round4};
+
+/// Rotation protocol state machine
+///
+/// Successfully completed rotation protocol produces new [LocalKey] for the same public key.
+pub struct Rotation {
+    round: R,
+
+    msgs1: Option<Store<P2PMsgs<Party1FirstMessage<Secp256k1, Sha256>>>>,
+    msgs2: Option<Store<P2PMsgs<Party2FirstMessage<Secp256k1>>>>,
+    msgs3: Option<Store<P2PMsgs<Party1Refresh>>>,
+    msgs4: Option<Store<P2PMsgs<Party2Confirmation>>>,
+
+    msgs_queue: Vec<Msg<ProtocolMessage>>,
+
+    party_i: u16,
+}
+
+impl Rotation {
+    /// Constructs a party of rotation protocol
+    ///
+    /// Party's role is taken from [LocalKey] obtained after [keygen](super::keygen) or previous
+    /// rotation.
+    pub fn new(local_key: LocalKey) -> Result<Self> {
+        let i = local_key.party_ind();
+        let round = match local_key {
+            LocalKey::PartyOne(key) => R::Round0(Round0 { key }),
+            LocalKey::PartyTwo(key) => R::Round1(Round1 { key }),
+        };
+        let mut state = Self {
+            round,
+
+            msgs1: (i == 2).then(|| Round1::expects_messages(i)),
+            msgs2: (i == 1).then(|| Round2::expects_messages(i)),
+            msgs3: (i == 2).then(|| Round3::expects_messages(i)),
+            msgs4: (i == 1).then(|| Round4::expects_messages(i)),
+
+            msgs_queue: vec![],
+
+            party_i: i,
+        };
+
+        state.proceed_round(false)?;
+        Ok(state)
+    }
+
+    fn gmap_queue<'a, T, F>(&'a mut self, f: F) -> impl Push<Msg<T>> + 'a
+    where
+        F: Fn(T) -> ProtocolMessage + 'a,
+    {
+        (&mut self.msgs_queue).gmap(move |m: Msg<T>| m.map_body(&f))
+    }
+
+    /// Proceeds round state if it received enough messages and if it's cheap to compute or
+    /// `may_block == true`
+    fn proceed_round(&mut self, may_block: bool) -> Result<()> {
+        let store1_wants_more = self.msgs1.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store2_wants_more = self.msgs2.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store3_wants_more = self.msgs3.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store4_wants_more = self.msgs4.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+
+        let next_state: R;
+        let try_again: bool = match replace(&mut self.round, R::Gone) {
+            R::Round0(round) if !round.is_expensive() || may_block => {
+                next_state = round
+                    .proceed(self.gmap_queue(ProtocolMessage::Party1SeedCommitment))
+                    .map(R::Round2)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round0(_) => {
+                next_state = s;
+                false
+            }
+            R::Round1(round) if !store1_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs1.take().ok_or(InternalError::StoreGone)?;
+                let msg = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?
+                    .into_vec()
+                    .remove(0);
+                next_state = round
+                    .proceed(msg, self.gmap_queue(ProtocolMessage::Party2Seed))
+                    .map(R::Round3)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round1(_) => {
+                next_state = s;
+                false
+            }
+            R::Round2(round) if !store2_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs2.take().ok_or(InternalError::StoreGone)?;
+                let msg = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?
+                    .into_vec()
+                    .remove(0);
+                next_state = round
+                    .proceed(msg, self.gmap_queue(ProtocolMessage::Party1Refresh))
+                    .map(R::Round4)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round2(_) => {
+                next_state = s;
+                false
+            }
+            R::Round3(round) if !store3_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs3.take().ok_or(InternalError::StoreGone)?;
+                let msg = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?
+                    .into_vec()
+                    .remove(0);
+                next_state = round
+                    .proceed(msg, self.gmap_queue(ProtocolMessage::Party2Confirmation))
+                    .map(R::Final)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round3(_) => {
+                next_state = s;
+                false
+            }
+            R::Round4(round) if !store4_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs4.take().ok_or(InternalError::StoreGone)?;
+                let msg = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?
+                    .into_vec()
+                    .remove(0);
+                next_state = round
+                    .proceed(msg)
+                    .map(R::Final)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round4(_) => {
+                next_state = s;
+                false
+            }
+            s @ R::Final(_) | s @ R::Gone => {
+                next_state = s;
+                false
+            }
+        };
+
+        self.round = next_state;
+        if try_again {
+            self.proceed_round(may_block)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl StateMachine for Rotation {
+    type MessageBody = ProtocolMessage;
+    type Err = Error;
+    type Output = LocalKey;
+
+    fn handle_incoming(&mut self, msg: Msg<Self::MessageBody>) -> Result<()> {
+        let current_round = self.current_round();
+
+        match msg.body {
+            ProtocolMessage::Party1SeedCommitment(m) => {
+                let store = self
+                    .msgs1
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 1,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+            ProtocolMessage::Party2Seed(m) => {
+                let store = self
+                    .msgs2
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 2,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+            ProtocolMessage::Party1Refresh(m) => {
+                let store = self
+                    .msgs3
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 3,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+            ProtocolMessage::Party2Confirmation(m) => {
+                let store = self
+                    .msgs4
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 4,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+        }
+        self.proceed_round(false)
+    }
+
+    fn message_queue(&mut self) -> &mut Vec<Msg<Self::MessageBody>> {
+        &mut self.msgs_queue
+    }
+
+    fn wants_to_proceed(&self) -> bool {
+        let store1_wants_more = self.msgs1.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store2_wants_more = self.msgs2.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store3_wants_more = self.msgs3.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store4_wants_more = self.msgs4.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+
+        match &self.round {
+            R::Round0(_) => true,
+            R::Round1(_) => !store1_wants_more,
+            R::Round2(_) => !store2_wants_more,
+            R::Round3(_) => !store3_wants_more,
+            R::Round4(_) => !store4_wants_more,
+            R::Final(_) | R::Gone => false,
+        }
+    }
+
+    fn proceed(&mut self) -> Result<()> {
+        self.proceed_round(true)
+    }
+
+    fn round_timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    fn round_timeout_reached(&mut self) -> Self::Err {
+        panic!("no timeout was set")
+    }
+
+    fn is_finished(&self) -> bool {
+        matches!(self.round, R::Final(_))
+    }
+
+    fn pick_output(&mut self) -> Option<Result<Self::Output>> {
+        match self.round {
+            R::Final(_) => (),
+            R::Gone => return Some(Err(Error::DoublePickOutput)),
+            _ => return None,
+        }
+
+        match replace(&mut self.round, R::Gone) {
+            R::Final(result) => Some(Ok(result)),
+            _ => unreachable!("guaranteed by match expression above"),
+        }
+    }
+
+    fn current_round(&self) -> u16 {
+        match &self.round {
+            R::Round0(_) => 0,
+            R::Round1(_) => 1,
+            R::Round2(_) => 2,
+            R::Round3(_) => 3,
+            R::Round4(_) => 4,
+            R::Final(_) | R::Gone => 5,
+        }
+    }
+
+    fn total_rounds(&self) -> Option<u16> {
+        Some(4)
+    }
+
+    fn party_ind(&self) -> u16 {
+        self.party_i
+    }
+
+    fn parties(&self) -> u16 {
+        2
+    }
+}
+
+impl fmt::Debug for Rotation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{{Lindell17 Rotation party={} at round={} queue=[len={}]}}",
+            self.party_i,
+            self.current_round(),
+            self.msgs_queue.len()
+        )
+    }
+}
+
+// Rounds
+
+#[allow(clippy::large_enum_variant)]
+enum R {
+    Round0(Round0),
+    Round1(Round1),
+    Round2(Round2),
+    Round3(Round3),
+    Round4(Round4),
+    Final(LocalKey),
+    Gone,
+}
+
+// Messages
+
+/// Protocol message which parties send on wire
+///
+/// Variant `k` is sent at round `k`, party one and party two take turns sending messages.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)]
+pub enum ProtocolMessage {
+    /// Party one's commitment to its coin flip seed
+    Party1SeedCommitment(Party1FirstMessage<Secp256k1, Sha256>),
+    /// Party two's coin flip seed
+    Party2Seed(Party2FirstMessage<Secp256k1>),
+    /// Party one's seed decommitment and new Paillier key
+    Party1Refresh(Party1Refresh),
+    /// Party two's refreshed public share
+    Party2Confirmation(Party2Confirmation),
+}
+
+// Error
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Error type of lindell17 rotation protocol
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// Round proceeding resulted in error
+    #[error("proceed round: {0}")]
+    ProceedRound(#[source] ProceedError),
+
+    /// Received message didn't pass pre-validation
+    #[error("received message didn't pass pre-validation: {0}")]
+    HandleMessage(#[source] StoreErr),
+    /// Received message which we didn't expect to receive now (e.g. message from previous round
+    /// or message that this party sends)
+    #[error(
+        "didn't expect to receive message from round {msg_round} (being at round {current_round})"
+    )]
+    ReceivedOutOfOrderMessage { current_round: u16, msg_round: u16 },
+    /// [Rotation::pick_output] called twice
+    #[error("pick_output called twice")]
+    DoublePickOutput,
+
+    /// Some internal assertions were failed, which is a bug
+    #[doc(hidden)]
+    #[error("internal error: {0:?}")]
+    InternalError(InternalError),
+}
+
+impl IsCritical for Error {
+    fn is_critical(&self) -> bool {
+        !matches!(
+            self,
+            Error::HandleMessage(_) | Error::ReceivedOutOfOrderMessage { .. }
+        )
+    }
+}
+
+impl From<InternalError> for Error {
+    fn from(err: InternalError) -> Self {
+        Self::InternalError(err)
+    }
+}
+
+mod private {
+    #[derive(Debug)]
+    #[non_exhaustive]
+    pub enum InternalError {
+        /// [Messages store](super::MessageStore) reported that it received all messages it wanted to receive,
+        /// but refused to return message container
+        RetrieveRoundMessages(super::StoreErr),
+        #[doc(hidden)]
+        StoreGone,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use curv::arithmetic::Converter;
+    use curv::BigInt;
+    use round_based::dev::Simulation;
+
+    use super::super::keygen::test::simulate_keygen;
+    use super::super::keygen::{Party1Key, Party2Key};
+    use super::super::sign::test::{simulate_sign, verify};
+    use super::*;
+
+    fn simulate_rotation(party_one: &Party1Key, party_two: &Party2Key) -> (Party1Key, Party2Key) {
+        let mut simulation = Simulation::new();
+        simulation.add_party(Rotation::new(LocalKey::PartyOne(party_one.clone())).unwrap());
+        simulation.add_party(Rotation::new(LocalKey::PartyTwo(party_two.clone())).unwrap());
+        let mut keys = simulation.run().unwrap().into_iter();
+        match (keys.next(), keys.next()) {
+            (Some(LocalKey::PartyOne(party_one)), Some(LocalKey::PartyTwo(party_two))) => {
+                (party_one, party_two)
+            }
+            _ => panic!("unexpected rotation output"),
+        }
+    }
+
+    #[test]
+    fn simulate_rotation_then_signing() {
+        let (party_one, party_two) = simulate_keygen();
+        let (new_party_one, new_party_two) = simulate_rotation(&party_one, &party_two);
+
+        assert_eq!(new_party_one.public_key, party_one.public_key);
+        assert_eq!(new_party_two.public_key, party_two.public_key);
+        assert_ne!(new_party_one.public_share, party_one.public_share);
+        assert_eq!(new_party_one.public_share, new_party_two.other_public_share);
+        assert_eq!(new_party_two.public_share, new_party_one.other_public_share);
+
+        let message = BigInt::from_bytes(b"a message");
+        let signature = simulate_sign(&new_party_one, &new_party_two, &message);
+        verify(&signature, &party_one.public_key, &message);
+    }
+
+    #[test]
+    fn party_one_waits_for_confirmation() {
+        let (party_one, party_two) = simulate_keygen();
+        let mut one = Rotation::new(LocalKey::PartyOne(party_one.clone())).unwrap();
+        let mut two = Rotation::new(LocalKey::PartyTwo(party_two)).unwrap();
+
+        let commitment = one.message_queue().remove(0);
+        two.handle_incoming(commitment).unwrap();
+        let seed = two.message_queue().remove(0);
+        one.handle_incoming(seed).unwrap();
+        one.proceed().unwrap();
+        let refresh = one.message_queue().remove(0);
+        two.handle_incoming(refresh).unwrap();
+        two.proceed().unwrap();
+        assert!(two.is_finished());
+        assert!(!one.is_finished());
+        assert_eq!(one.current_round(), 4);
+
+        let mut confirmation = two.message_queue().remove(0);
+        if let ProtocolMessage::Party2Confirmation(c) = &mut confirmation.body {
+            c.public_share = party_one.other_public_share.clone();
+        }
+        assert!(matches!(
+            one.handle_incoming(confirmation),
+            Err(Error::ProceedRound(ProceedError::Round4PublicShareMismatch))
+        ));
+    }
+}
@@ -0,0 +1,249 @@
+use curv::cryptographic_primitives::proofs::sigma_valid_pedersen::PedersenProof;
+use curv::cryptographic_primitives::proofs::sigma_valid_pedersen_blind::PedersenBlindingProof;
+use curv::cryptographic_primitives::twoparty::coin_flip_optimal_rounds::{
+    self as coin_flip, Party1FirstMessage, Party1SecondMessage, Party2FirstMessage,
+};
+use curv::elliptic::curves::{Point, Scalar, Secp256k1};
+use round_based::containers::push::Push;
+use round_based::containers::{self, P2PMsgs, Store};
+use round_based::Msg;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use thiserror::Error;
+
+use crate::protocols::two_party_ecdsa::lindell_2017::party_one::Party1Private;
+use crate::protocols::two_party_ecdsa::lindell_2017::party_two::{PaillierPublic, Party2Private};
+use crate::protocols::two_party_ecdsa::lindell_2017::state_machine::keygen::{
+    LocalKey, PaillierKeyError, PaillierKeyProof, Party1Key, Party2Key,
+};
+
+pub struct Round0 {
+    pub key: Party1Key,
+}
+
+impl Round0 {
+    pub fn proceed<O>(self, mut output: O) -> Result<Round2>
+    where
+        O: Push<Msg<Party1FirstMessage<Secp256k1, Sha256>>>,
+    {
+        let (first_msg, seed, blinding) = Party1FirstMessage::<Secp256k1, Sha256>::commit();
+        output.push(Msg {
+            sender: 1,
+            receiver: Some(2),
+            body: first_msg,
+        });
+        Ok(Round2 {
+            key: self.key,
+            seed,
+            blinding,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        false
+    }
+}
+
+pub struct Round1 {
+    pub key: Party2Key,
+}
+
+impl Round1 {
+    pub fn proceed<O>(
+        self,
+        input: Party1FirstMessage<Secp256k1, Sha256>,
+        mut output: O,
+    ) -> Result<Round3>
+    where
+        O: Push<Msg<Party2FirstMessage<Secp256k1>>>,
+    {
+        PedersenProof::verify(&input.proof)
+            .map_err(|_| ProceedError::Round1InvalidSeedCommitment)?;
+        let second_msg = Party2FirstMessage::share(&input.proof);
+        output.push(Msg {
+            sender: 2,
+            receiver: Some(1),
+            body: second_msg.clone(),
+        });
+        Ok(Round3 {
+            key: self.key,
+            party_one_first_msg: input,
+            party_two_first_msg: second_msg,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        false
+    }
+    pub fn expects_messages(i: u16) -> Store<P2PMsgs<Party1FirstMessage<Secp256k1, Sha256>>> {
+        containers::P2PMsgsStore::new(i, 2)
+    }
+}
+
+pub struct Round2 {
+    key: Party1Key,
+    seed: Scalar<Secp256k1>,
+    blinding: Scalar<Secp256k1>,
+}
+
+impl Round2 {
+    pub fn proceed<O>(self, input: Party2FirstMessage<Secp256k1>, mut output: O) -> Result<Round4>
+    where
+        O: Push<Msg<Party1Refresh>>,
+    {
+        let (reveal, factor) = Party1SecondMessage::<Secp256k1, Sha256>::reveal(
+            &input.seed,
+            &self.seed,
+            &self.blinding,
+        );
+        let factor_inv = factor.invert().ok_or(ProceedError::ZeroFactor)?;
+
+        let (
+            ek,
+            encrypted_share,
+            private,
+            correct_key_proof,
+            pdl_statement,
+            pdl_proof,
+            composite_dlog_proof,
+        ) = Party1Private::refresh_private_key(&self.key.private, &factor.to_bigint());
+        output.push(Msg {
+            sender: 1,
+            receiver: Some(2),
+            body: Party1Refresh {
+                reveal,
+                paillier_key: PaillierKeyProof {
+                    ek,
+                    encrypted_share,
+                    correct_key_proof,
+                    pdl_statement,
+                    pdl_proof,
+                    composite_dlog_proof,
+                },
+            },
+        });
+
+        Ok(Round4 {
+            new_key: Party1Key {
+                private,
+                public_share: self.key.public_share * &factor,
+                other_public_share: self.key.other_public_share * &factor_inv,
+                public_key: self.key.public_key,
+            },
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+    pub fn expects_messages(i: u16) -> Store<P2PMsgs<Party2FirstMessage<Secp256k1>>> {
+        containers::P2PMsgsStore::new(i, 2)
+    }
+}
+
+pub struct Round3 {
+    key: Party2Key,
+    party_one_first_msg: Party1FirstMessage<Secp256k1, Sha256>,
+    party_two_first_msg: Party2FirstMessage<Secp256k1>,
+}
+
+impl Round3 {
+    pub fn proceed<O>(self, input: Party1Refresh, mut output: O) -> Result<LocalKey>
+    where
+        O: Push<Msg<Party2Confirmation>>,
+    {
+        PedersenBlindingProof::verify(&input.reveal.proof)
+            .map_err(|_| ProceedError::Round3InvalidSeedDecommitment)?;
+        if input.reveal.proof.com != self.party_one_first_msg.proof.com {
+            return Err(ProceedError::Round3InvalidSeedDecommitment);
+        }
+        let factor = coin_flip::finalize(
+            &input.reveal.proof,
+            &self.party_two_first_msg.seed,
+            &self.party_one_first_msg.proof.com,
+        );
+        let factor_inv = factor.invert().ok_or(ProceedError::ZeroFactor)?;
+
+        let other_public_share = self.key.other_public_share * &factor;
+        let paillier_public: PaillierPublic = input
+            .paillier_key
+            .verify(&other_public_share)
+            .map_err(ProceedError::Round3InvalidPaillierKey)?;
+
+        let public_share = self.key.public_share * &factor_inv;
+        output.push(Msg {
+            sender: 2,
+            receiver: Some(1),
+            body: Party2Confirmation {
+                public_share: public_share.clone(),
+            },
+        });
+
+        Ok(LocalKey::PartyTwo(Party2Key {
+            private: Party2Private::update_private_key(&self.key.private, &factor_inv.to_bigint()),
+            paillier_public,
+            public_share,
+            other_public_share,
+            public_key: self.key.public_key,
+        }))
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+    pub fn expects_messages(i: u16) -> Store<P2PMsgs<Party1Refresh>> {
+        containers::P2PMsgsStore::new(i, 2)
+    }
+}
+
+pub struct Round4 {
+    new_key: Party1Key,
+}
+
+impl Round4 {
+    pub fn proceed(self, input: Party2Confirmation) -> Result<LocalKey> {
+        if input.public_share != self.new_key.other_public_share {
+            return Err(ProceedError::Round4PublicShareMismatch);
+        }
+        Ok(LocalKey::PartyOne(self.new_key))
+    }
+    pub fn is_expensive(&self) -> bool {
+        false
+    }
+    pub fn expects_messages(i: u16) -> Store<P2PMsgs<Party2Confirmation>> {
+        containers::P2PMsgsStore::new(i, 2)
+    }
+}
+
+/// Party one's seed decommitment along with its new Paillier key and encryption of refreshed
+/// secret share `r * x1`, sent at round 3
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Party1Refresh {
+    pub reveal: Party1SecondMessage<Secp256k1, Sha256>,
+    pub paillier_key: PaillierKeyProof,
+}
+
+/// Party two's refreshed public share `r^-1 * x2 * G`, sent at round 4 once party two verified
+/// party one's refresh and updated its key
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Party2Confirmation {
+    pub public_share: Point<Secp256k1>,
+}
+
+// Errors
+
+type Result<T> = std::result::Result<T, ProceedError>;
+
+/// Proceeding protocol error
+///
+/// Subset of [rotation errors](enum@super::Error) that can occur at protocol proceeding (i.e.
+/// after every message was received and pre-validated).
+#[derive(Debug, Error)]
+pub enum ProceedError {
+    #[error("round 1: party one's commitment to its seed is invalid")]
+    Round1InvalidSeedCommitment,
+    #[error("round 3: party one's seed decommitment is invalid")]
+    Round3InvalidSeedDecommitment,
+    #[error("round 3: {0}")]
+    Round3InvalidPaillierKey(#[source] PaillierKeyError),
+    #[error("round 4: party two's refreshed public share doesn't match the coin flip")]
+    Round4PublicShareMismatch,
+    #[error("coin flip resulted in zero factor")]
+    ZeroFactor,
+}
@@ -0,0 +1,498 @@
+//! Lindell 2017 two-party signing
+//!
+//! At round 1 party two commits to its nonce share `R2 = k2 * G`. At round 2 party one sends its
+//! nonce share `R1 = k1 * G` with a proof of knowledge of `k1`. At round 3 party two decommits
+//! `R2`, and sends partial signature `k2^-1 (m + r x2 x1)` homomorphically computed under party
+//! one's Paillier key. At round 4 party one decrypts it, completes the signature and sends it
+//! to party two. Both parties verify the signature before outputting it.
+
+use std::fmt;
+use std::mem::replace;
+use std::time::Duration;
+
+use curv::BigInt;
+use round_based::containers::{
+    push::{Push, PushExt},
+    *,
+};
+use round_based::{IsCritical, Msg, StateMachine};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::protocols::two_party_ecdsa::lindell_2017::party_one::{self, SignatureRecid};
+use crate::protocols::two_party_ecdsa::lindell_2017::party_two;
+
+use super::keygen::LocalKey;
+
+mod rounds;
+
+use private::InternalError;
+pub use rounds::{PartialSignature, ProceedError};
+use rounds::{Round0, Round1, Round2, Round3, Round4};
+
+/// Signing protocol state machine
+///
+/// Successfully completed signing protocol produces [SignatureRecid].
+pub struct Sign {
+    round: R,
+
+    msgs1: Option<Store<P2PMsgs<party_two::EphKeyGenFirstMsg>>>,
+    msgs2: Option<Store<P2PMsgs<party_one::EphKeyGenFirstMsg>>>,
+    msgs3: Option<Store<P2PMsgs<PartialSignature>>>,
+    msgs4: Option<Store<P2PMsgs<SignatureRecid>>>,
+
+    msgs_queue: Vec<Msg<ProtocolMessage>>,
+
+    party_i: u16,
+}
+
+impl Sign {
+    /// Constructs a party of signing protocol
+    ///
+    /// Party's role is taken from [LocalKey] obtained after [keygen](super::keygen). Both parties
+    /// must sign the same `message`, which is a hash of the data being signed.
+    pub fn new(local_key: LocalKey, message: BigInt) -> Result<Self> {
+        let i = local_key.party_ind();
+        let round = match local_key {
+            LocalKey::PartyOne(key) => R::Round1(Round1 { key, message }),
+            LocalKey::PartyTwo(key) => R::Round0(Round0 { key, message }),
+        };
+        let mut state = Self {
+            round,
+
+            msgs1: (i == 1).then(|| Round1::expects_messages(i)),
+            msgs2: (i == 2).then(|| Round2::expects_messages(i)),
+            msgs3: (i == 1).then(|| Round3::expects_messages(i)),
+            msgs4: (i == 2).then(|| Round4::expects_messages(i)),
+
+            msgs_queue: vec![],
+
+            party_i: i,
+        };
+
+        state.proceed_round(false)?;
+        Ok(state)
+    }
+
+    fn gmap_queue<'a, T, F>(&'a mut self, f: F) -> impl Push<Msg<T>> + 'a
+    where
+        F: Fn(T) -> ProtocolMessage + 'a,
+    {
+        (&mut self.msgs_queue).gmap(move |m: Msg<T>| m.map_body(&f))
+    }
+
+    /// Proceeds round state if it received enough messages and if it's cheap to compute or
+    /// `may_block == true`
+    fn proceed_round(&mut self, may_block: bool) -> Result<()> {
+        let store1_wants_more = self.msgs1.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store2_wants_more = self.msgs2.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store3_wants_more = self.msgs3.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store4_wants_more = self.msgs4.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+
+        let next_state: R;
+        let try_again: bool = match replace(&mut self.round, R::Gone) {
+            R::Round0(round) if !round.is_expensive() || may_block => {
+                next_state = round
+                    .proceed(self.gmap_queue(ProtocolMessage::Party2NonceCommitment))
+                    .map(R::Round2)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round0(_) => {
+                next_state = s;
+                false
+            }
+            R::Round1(round) if !store1_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs1.take().ok_or(InternalError::StoreGone)?;
+                let msg = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?
+                    .into_vec()
+                    .remove(0);
+                next_state = round
+                    .proceed(msg, self.gmap_queue(ProtocolMessage::Party1Nonce))
+                    .map(R::Round3)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round1(_) => {
+                next_state = s;
+                false
+            }
+            R::Round2(round) if !store2_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs2.take().ok_or(InternalError::StoreGone)?;
+                let msg = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?
+                    .into_vec()
+                    .remove(0);
+                next_state = round
+                    .proceed(
+                        msg,
+                        self.gmap_queue(ProtocolMessage::Party2PartialSignature),
+                    )
+                    .map(R::Round4)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round2(_) => {
+                next_state = s;
+                false
+            }
+            R::Round3(round) if !store3_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs3.take().ok_or(InternalError::StoreGone)?;
+                let msg = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?
+                    .into_vec()
+                    .remove(0);
+                next_state = round
+                    .proceed(msg, self.gmap_queue(ProtocolMessage::Party1Signature))
+                    .map(R::Final)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round3(_) => {
+                next_state = s;
+                false
+            }
+            R::Round4(round) if !store4_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs4.take().ok_or(InternalError::StoreGone)?;
+                let msg = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?
+                    .into_vec()
+                    .remove(0);
+                next_state = round
+                    .proceed(msg)
+                    .map(R::Final)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round4(_) => {
+                next_state = s;
+                false
+            }
+            s @ R::Final(_) | s @ R::Gone => {
+                next_state = s;
+                false
+            }
+        };
+
+        self.round = next_state;
+        if try_again {
+            self.proceed_round(may_block)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl StateMachine for Sign {
+    type MessageBody = ProtocolMessage;
+    type Err = Error;
+    type Output = SignatureRecid;
+
+    fn handle_incoming(&mut self, msg: Msg<Self::MessageBody>) -> Result<()> {
+        let current_round = self.current_round();
+
+        match msg.body {
+            ProtocolMessage::Party2NonceCommitment(m) => {
+                let store = self
+                    .msgs1
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 1,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+            ProtocolMessage::Party1Nonce(m) => {
+                let store = self
+                    .msgs2
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 2,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+            ProtocolMessage::Party2PartialSignature(m) => {
+                let store = self
+                    .msgs3
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 3,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+            ProtocolMessage::Party1Signature(m) => {
+                let store = self
+                    .msgs4
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 4,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+        }
+        self.proceed_round(false)
+    }
+
+    fn message_queue(&mut self) -> &mut Vec<Msg<Self::MessageBody>> {
+        &mut self.msgs_queue
+    }
+
+    fn wants_to_proceed(&self) -> bool {
+        let store1_wants_more = self.msgs1.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store2_wants_more = self.msgs2.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store3_wants_more = self.msgs3.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store4_wants_more = self.msgs4.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+
+        match &self.round {
+            R::Round0(_) => true,
+            R::Round1(_) => !store1_wants_more,
+            R::Round2(_) => !store2_wants_more,
+            R::Round3(_) => !store3_wants_more,
+            R::Round4(_) => !store4_wants_more,
+            R::Final(_) | R::Gone => false,
+        }
+    }
+
+    fn proceed(&mut self) -> Result<()> {
+        self.proceed_round(true)
+    }
+
+    fn round_timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    fn round_timeout_reached(&mut self) -> Self::Err {
+        panic!("no timeout was set")
+    }
+
+    fn is_finished(&self) -> bool {
+        matches!(self.round, R::Final(_))
+    }
+
+    fn pick_output(&mut self) -> Option<Result<Self::Output>> {
+        match self.round {
+            R::Final(_) => (),
+            R::Gone => return Some(Err(Error::DoublePickOutput)),
+            _ => return None,
+        }
+
+        match replace(&mut self.round, R::Gone) {
+            R::Final(result) => Some(Ok(result)),
+            _ => unreachable!("guaranteed by match expression above"),
+        }
+    }
+
+    fn current_round(&self) -> u16 {
+        match &self.round {
+            R::Round0(_) => 0,
+            R::Round1(_) => 1,
+            R::Round2(_) => 2,
+            R::Round3(_) => 3,
+            R::Round4(_) => 4,
+            R::Final(_) | R::Gone => 5,
+        }
+    }
+
+    fn total_rounds(&self) -> Option<u16> {
+        Some(4)
+    }
+
+    fn party_ind(&self) -> u16 {
+        self.party_i
+    }
+
+    fn parties(&self) -> u16 {
+        2
+    }
+}
+
+impl fmt::Debug for Sign {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{{Lindell17 Sign party={} at round={} queue=[len={}]}}",
+            self.party_i,
+            self.current_round(),
+            self.msgs_queue.len()
+        )
+    }
+}
+
+// Rounds
+
+#[allow(clippy::large_enum_variant)]
+enum R {
+    Round0(Round0),
+    Round1(Round1),
+    Round2(Round2),
+    Round3(Round3),
+    Round4(Round4),
+    Final(SignatureRecid),
+    Gone,
+}
+
+// Messages
+
+/// Protocol message which parties send on wire
+///
+/// Variant `k` is sent at round `k`, party one and party two take turns sending messages.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)]
+pub enum ProtocolMessage {
+    /// Party two's commitment to `R2`
+    Party2NonceCommitment(party_two::EphKeyGenFirstMsg),
+    /// Party one's nonce share `R1` and proof of knowledge of `k1`
+    Party1Nonce(party_one::EphKeyGenFirstMsg),
+    /// Party two's decommitment and encrypted partial signature
+    Party2PartialSignature(PartialSignature),
+    /// Resulting signature
+    Party1Signature(SignatureRecid),
+}
+
+// Error
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Error type of lindell17 sign protocol
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// Round proceeding resulted in error
+    #[error("proceed round: {0}")]
+    ProceedRound(#[source] ProceedError),
+
+    /// Received message didn't pass pre-validation
+    #[error("received message didn't pass pre-validation: {0}")]
+    HandleMessage(#[source] StoreErr),
+    /// Received message which we didn't expect to receive now (e.g. message from previous round
+    /// or message that this party sends)
+    #[error(
+        "didn't expect to receive message from round {msg_round} (being at round {current_round})"
+    )]
+    ReceivedOutOfOrderMessage { current_round: u16, msg_round: u16 },
+    /// [Sign::pick_output] called twice
+    #[error("pick_output called twice")]
+    DoublePickOutput,
+
+    /// Some internal assertions were failed, which is a bug
+    #[doc(hidden)]
+    #[error("internal error: {0:?}")]
+    InternalError(InternalError),
+}
+
+impl IsCritical for Error {
+    fn is_critical(&self) -> bool {
+        !matches!(
+            self,
+            Error::HandleMessage(_) | Error::ReceivedOutOfOrderMessage { .. }
+        )
+    }
+}
+
+impl From<InternalError> for Error {
+    fn from(err: InternalError) -> Self {
+        Self::InternalError(err)
+    }
+}
+
+mod private {
+    #[derive(Debug)]
+    #[non_exhaustive]
+    pub enum InternalError {
+        /// [Messages store](super::MessageStore) reported that it received all messages it wanted to receive,
+        /// but refused to return message container
+        RetrieveRoundMessages(super::StoreErr),
+        #[doc(hidden)]
+        StoreGone,
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use curv::arithmetic::Converter;
+    use curv::cryptographic_primitives::hashing::{Digest, DigestExt};
+    use curv::elliptic::curves::{Point, Secp256k1};
+    use round_based::dev::Simulation;
+    use sha2::Sha256;
+
+    use super::super::keygen::test::simulate_keygen;
+    use super::super::keygen::{Party1Key, Party2Key};
+    use super::*;
+
+    pub fn simulate_sign(
+        party_one: &Party1Key,
+        party_two: &Party2Key,
+        message: &BigInt,
+    ) -> SignatureRecid {
+        let mut simulation = Simulation::new();
+        simulation
+            .add_party(Sign::new(LocalKey::PartyOne(party_one.clone()), message.clone()).unwrap());
+        simulation
+            .add_party(Sign::new(LocalKey::PartyTwo(party_two.clone()), message.clone()).unwrap());
+        let signatures = simulation.run().unwrap();
+        assert_eq!(signatures[0].r, signatures[1].r);
+        assert_eq!(signatures[0].s, signatures[1].s);
+        signatures[0].clone()
+    }
+
+    pub fn verify(signature: &SignatureRecid, public_key: &Point<Secp256k1>, message: &BigInt) {
+        let signature = party_one::Signature {
+            r: signature.r.clone(),
+            s: signature.s.clone(),
+        };
+        party_one::verify(&signature, public_key, message).unwrap();
+    }
+
+    #[test]
+    fn simulate_signing() {
+        let (party_one, party_two) = simulate_keygen();
+        let message = Sha256::new()
+            .chain_bigint(&BigInt::from_bytes(b"a message"))
+            .result_bigint();
+
+        let signature = simulate_sign(&party_one, &party_two, &message);
+        verify(&signature, &party_one.public_key, &message);
+    }
+
+    #[test]
+    fn rejects_different_messages() {
+        let (party_one, party_two) = simulate_keygen();
+
+        let mut simulation = Simulation::new();
+        simulation.add_party(Sign::new(LocalKey::PartyOne(party_one), BigInt::from(1)).unwrap());
+        simulation.add_party(Sign::new(LocalKey::PartyTwo(party_two), BigInt::from(2)).unwrap());
+        assert!(simulation.run().is_err());
+    }
+}
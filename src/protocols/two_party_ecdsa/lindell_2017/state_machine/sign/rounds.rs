@@ -0,0 +1,221 @@
+use curv::elliptic::curves::{Point, Secp256k1};
+use curv::BigInt;
+use round_based::containers::push::Push;
+use round_based::containers::{self, P2PMsgs, Store};
+use round_based::Msg;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::protocols::two_party_ecdsa::lindell_2017::state_machine::keygen::{
+    Party1Key, Party2Key,
+};
+use crate::protocols::two_party_ecdsa::lindell_2017::{party_one, party_two};
+use crate::Error as PhaseError;
+
+pub struct Round0 {
+    pub key: Party2Key,
+    pub message: BigInt,
+}
+
+impl Round0 {
+    pub fn proceed<O>(self, mut output: O) -> Result<Round2>
+    where
+        O: Push<Msg<party_two::EphKeyGenFirstMsg>>,
+    {
+        let (eph_first_msg, eph_comm_witness, eph_ec_key_pair) =
+            party_two::EphKeyGenFirstMsg::create_commitments();
+        output.push(Msg {
+            sender: 2,
+            receiver: Some(1),
+            body: eph_first_msg,
+        });
+        Ok(Round2 {
+            key: self.key,
+            message: self.message,
+            eph_comm_witness,
+            eph_ec_key_pair,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        false
+    }
+}
+
+pub struct Round1 {
+    pub key: Party1Key,
+    pub message: BigInt,
+}
+
+impl Round1 {
+    pub fn proceed<O>(self, input: party_two::EphKeyGenFirstMsg, mut output: O) -> Result<Round3>
+    where
+        O: Push<Msg<party_one::EphKeyGenFirstMsg>>,
+    {
+        let (eph_first_msg, eph_ec_key_pair) = party_one::EphKeyGenFirstMsg::create();
+        output.push(Msg {
+            sender: 1,
+            receiver: Some(2),
+            body: eph_first_msg,
+        });
+        Ok(Round3 {
+            key: self.key,
+            message: self.message,
+            party_two_eph_first_msg: input,
+            eph_ec_key_pair,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        false
+    }
+    pub fn expects_messages(i: u16) -> Store<P2PMsgs<party_two::EphKeyGenFirstMsg>> {
+        containers::P2PMsgsStore::new(i, 2)
+    }
+}
+
+pub struct Round2 {
+    key: Party2Key,
+    message: BigInt,
+    eph_comm_witness: party_two::EphCommWitness,
+    eph_ec_key_pair: party_two::EphEcKeyPair,
+}
+
+impl Round2 {
+    pub fn proceed<O>(self, input: party_one::EphKeyGenFirstMsg, mut output: O) -> Result<Round4>
+    where
+        O: Push<Msg<PartialSignature>>,
+    {
+        let decommitment =
+            party_two::EphKeyGenSecondMsg::verify_and_decommit(self.eph_comm_witness, &input)
+                .map_err(|_| ProceedError::Round2InvalidNonceProof)?;
+        let partial_sig = party_two::PartialSig::compute(
+            &self.key.paillier_public.ek,
+            &self.key.paillier_public.encrypted_secret_share,
+            &self.key.private,
+            &self.eph_ec_key_pair,
+            &input.public_share,
+            &self.message,
+        );
+        output.push(Msg {
+            sender: 2,
+            receiver: Some(1),
+            body: PartialSignature {
+                decommitment,
+                partial_sig,
+            },
+        });
+        Ok(Round4 {
+            key: self.key,
+            message: self.message,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+    pub fn expects_messages(i: u16) -> Store<P2PMsgs<party_one::EphKeyGenFirstMsg>> {
+        containers::P2PMsgsStore::new(i, 2)
+    }
+}
+
+pub struct Round3 {
+    key: Party1Key,
+    message: BigInt,
+    party_two_eph_first_msg: party_two::EphKeyGenFirstMsg,
+    eph_ec_key_pair: party_one::EphEcKeyPair,
+}
+
+impl Round3 {
+    pub fn proceed<O>(
+        self,
+        input: PartialSignature,
+        mut output: O,
+    ) -> Result<party_one::SignatureRecid>
+    where
+        O: Push<Msg<party_one::SignatureRecid>>,
+    {
+        party_one::EphKeyGenSecondMsg::verify_commitments_and_dlog_proof(
+            &self.party_two_eph_first_msg,
+            &input.decommitment,
+        )
+        .map_err(|_| ProceedError::Round3InvalidNonceDecommitment)?;
+        let signature = party_one::Signature::compute_with_recid(
+            &self.key.private,
+            &input.partial_sig.c3,
+            &self.eph_ec_key_pair,
+            &input.decommitment.comm_witness.public_share,
+        )
+        .map_err(|_| ProceedError::Round3InvalidSignature)?;
+        verify(&signature, &self.key.public_key, &self.message)
+            .map_err(|_| ProceedError::Round3InvalidSignature)?;
+        output.push(Msg {
+            sender: 1,
+            receiver: Some(2),
+            body: signature.clone(),
+        });
+        Ok(signature)
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+    pub fn expects_messages(i: u16) -> Store<P2PMsgs<PartialSignature>> {
+        containers::P2PMsgsStore::new(i, 2)
+    }
+}
+
+pub struct Round4 {
+    key: Party2Key,
+    message: BigInt,
+}
+
+impl Round4 {
+    pub fn proceed(self, input: party_one::SignatureRecid) -> Result<party_one::SignatureRecid> {
+        verify(&input, &self.key.public_key, &self.message)
+            .map_err(|_| ProceedError::Round4InvalidSignature)?;
+        Ok(input)
+    }
+    pub fn is_expensive(&self) -> bool {
+        false
+    }
+    pub fn expects_messages(i: u16) -> Store<P2PMsgs<party_one::SignatureRecid>> {
+        containers::P2PMsgsStore::new(i, 2)
+    }
+}
+
+fn verify(
+    signature: &party_one::SignatureRecid,
+    public_key: &Point<Secp256k1>,
+    message: &BigInt,
+) -> std::result::Result<(), PhaseError> {
+    let signature = party_one::Signature {
+        r: signature.r.clone(),
+        s: signature.s.clone(),
+    };
+    party_one::verify(&signature, public_key, message)
+}
+
+/// Party two's decommitment to its nonce share `R2` along with encrypted partial signature, sent at
+/// round 3
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartialSignature {
+    pub decommitment: party_two::EphKeyGenSecondMsg,
+    pub partial_sig: party_two::PartialSig,
+}
+
+// Errors
+
+type Result<T> = std::result::Result<T, ProceedError>;
+
+/// Proceeding protocol error
+///
+/// Subset of [signing errors](enum@super::Error) that can occur at protocol proceeding (i.e. after
+/// every message was received and pre-validated).
+#[derive(Debug, Error)]
+pub enum ProceedError {
+    #[error("round 2: party one's proof of knowledge of k1 is invalid")]
+    Round2InvalidNonceProof,
+    #[error("round 3: party two's nonce decommitment or proof of knowledge of k2 is invalid")]
+    Round3InvalidNonceDecommitment,
+    #[error("round 3: resulting signature is not valid")]
+    Round3InvalidSignature,
+    #[error("round 4: party one sent invalid signature")]
+    Round4InvalidSignature,
+}
@@ -1,8 +1,10 @@
 // For integration tests, please add your tests in /tests instead
 
 use crate::protocols::two_party_ecdsa::lindell_2017::{party_one, party_two};
+use crate::utilities::adaptor;
+use crate::utilities::verification::{verify, Mode};
 use curv::arithmetic::traits::Samplable;
-use curv::elliptic::curves::{secp256_k1::Secp256k1, Scalar};
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
 use curv::BigInt;
 
 #[test]
@@ -135,3 +137,68 @@ fn test_two_party_sign() {
         party_one::compute_pubkey(&party1_private, &party_two_private_share_gen.public_share);
     party_one::verify(&signature, &pubkey, &message).expect("Invalid signature")
 }
+
+#[test]
+fn test_two_party_adaptor_sign() {
+    let (_party_one_private_share_gen, _comm_witness, ec_key_pair_party1) =
+        party_one::KeyGenFirstMsg::create_commitments();
+    let (party_two_private_share_gen, ec_key_pair_party2) = party_two::KeyGenFirstMsg::create();
+    let keypair =
+        party_one::PaillierKeyPair::generate_keypair_and_encrypted_share(&ec_key_pair_party1);
+    let party1_private = party_one::Party1Private::set_private_key(&ec_key_pair_party1, &keypair);
+    let party2_private = party_two::Party2Private::set_private_key(&ec_key_pair_party2);
+    let pubkey =
+        party_one::compute_pubkey(&party1_private, &party_two_private_share_gen.public_share);
+
+    let y = Scalar::<Secp256k1>::random();
+    let adaptor_point = Point::generator() * &y;
+
+    let (eph_party_two_first_message, eph_comm_witness, eph_ec_key_pair_party2) =
+        party_two::EphKeyGenFirstMsg::create_commitments();
+    let (eph_party_one_first_message, eph_ec_key_pair_party1) =
+        party_one::EphKeyGenFirstMsg::create();
+    let eph_party_one_adaptor_message =
+        party_one::EphAdaptorMsg::create(&eph_ec_key_pair_party1, &adaptor_point);
+    let eph_party_two_second_message = party_two::EphKeyGenSecondMsg::verify_and_decommit(
+        eph_comm_witness,
+        &eph_party_one_first_message,
+    )
+    .expect("party1 DLog proof failed");
+    let _eph_party_one_second_message =
+        party_one::EphKeyGenSecondMsg::verify_commitments_and_dlog_proof(
+            &eph_party_two_first_message,
+            &eph_party_two_second_message,
+        )
+        .expect("failed to verify commitments and DLog proof");
+
+    let message = BigInt::from(1234);
+    let partial_pre_sig = party_two::PartialPreSig::compute(
+        &keypair.ek,
+        &keypair.encrypted_share,
+        &party2_private,
+        &eph_ec_key_pair_party2,
+        &eph_party_one_first_message.public_share,
+        &eph_party_one_adaptor_message,
+        &adaptor_point,
+        &message,
+    )
+    .expect("party1 adaptor share is invalid");
+    let pre_signature = party_one::Signature::compute_pre_signature(
+        &party1_private,
+        &partial_pre_sig,
+        &eph_ec_key_pair_party1,
+        &eph_party_two_second_message.comm_witness.public_share,
+        &adaptor_point,
+        &pubkey,
+        &message,
+    )
+    .expect("invalid pre-signature");
+    adaptor::verify_pre_signature(&pre_signature, &pubkey, &adaptor_point, &message).unwrap();
+
+    let signature = adaptor::adapt(&pre_signature, &y).unwrap();
+    verify(&signature.r, &signature.s, &pubkey, &message, Mode::Strict).expect("Invalid signature");
+    assert_eq!(
+        adaptor::extract_secret(&pre_signature, &signature, &adaptor_point).unwrap(),
+        y
+    );
+}
@@ -18,6 +18,9 @@
 
 pub mod lindell_2017;
 
+// Secure Two-party Threshold ECDSA from ECDSA Assumptions (https://eprint.iacr.org/2018/499.pdf)
+pub mod dkls;
+
 // Two-Party ECDSA from Hash Proof Systems and
 //Efficient Instantiations (https://eprint.iacr.org/2019/503.pdf)
 #[cfg(feature = "cclst")]
@@ -0,0 +1,170 @@
+//! On-disk log of rooms
+//!
+//! Every room is logged to its own file in the log directory, named after hex-encoded room id.
+//! The file is a sequence of JSON [entries](Entry), one per line. A crash in the middle of
+//! appending an entry leaves an incomplete last line, it's discarded on restore.
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+
+use super::Error;
+
+const EXTENSION: &str = "log";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Entry<'a> {
+    Created {
+        room_id: Cow<'a, str>,
+        capacity: u16,
+    },
+    IssuedIdx {
+        idx: u16,
+    },
+    Message {
+        message: Cow<'a, str>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        key: Option<Cow<'a, str>>,
+    },
+}
+
+/// Log of a single room
+pub struct RoomLog {
+    path: PathBuf,
+    file: tokio::fs::File,
+}
+
+/// Room state recovered from the log
+pub struct RestoredRoom {
+    pub room_id: String,
+    pub capacity: u16,
+    pub issued: u16,
+    pub messages: Vec<String>,
+    pub keys: HashSet<String>,
+    pub log: RoomLog,
+}
+
+impl RoomLog {
+    /// Creates a log of a new room, overwriting the log left by expired room with the same id
+    pub async fn create(dir: &Path, room_id: &str, capacity: u16) -> io::Result<Self> {
+        let path = dir.join(file_name(room_id));
+        let file = tokio::fs::File::create(&path).await?;
+        let mut log = Self { path, file };
+        log.append(&Entry::Created {
+            room_id: Cow::Borrowed(room_id),
+            capacity,
+        })
+        .await?;
+        Ok(log)
+    }
+
+    /// Appends an entry, returns once it's written to the disk
+    pub async fn append(&mut self, entry: &Entry<'_>) -> io::Result<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        self.file.write_all(&line).await?;
+        self.file.sync_data().await
+    }
+
+    /// Deletes the log
+    pub async fn remove(self) -> io::Result<()> {
+        drop(self.file);
+        tokio::fs::remove_file(&self.path).await
+    }
+}
+
+/// Restores every room logged in `dir`
+pub fn restore(dir: &Path) -> Result<Vec<RestoredRoom>, Error> {
+    let io_error = |path: &Path| {
+        let path = path.to_owned();
+        move |source| Error::Io { path, source }
+    };
+
+    fs::create_dir_all(dir).map_err(io_error(dir))?;
+    let mut rooms = vec![];
+    for entry in fs::read_dir(dir).map_err(io_error(dir))? {
+        let path = entry.map_err(io_error(dir))?.path();
+        if path.extension().map(|ext| ext != EXTENSION).unwrap_or(true) {
+            continue;
+        }
+        let content = fs::read(&path).map_err(io_error(&path))?;
+        let complete_len = content
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map(|pos| pos + 1)
+            .unwrap_or(0);
+        if complete_len == 0 {
+            // Relay crashed while creating the room, nothing to restore
+            fs::remove_file(&path).map_err(io_error(&path))?;
+            continue;
+        }
+        let (room_id, capacity, issued, messages, keys) =
+            parse(&content[..complete_len]).map_err(|line| Error::CorruptedLog {
+                path: path.clone(),
+                line,
+            })?;
+
+        let file = fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .map_err(io_error(&path))?;
+        if complete_len < content.len() {
+            let len = u64::try_from(complete_len).expect("log size fits into u64");
+            file.set_len(len).map_err(io_error(&path))?;
+        }
+        rooms.push(RestoredRoom {
+            room_id,
+            capacity,
+            issued,
+            messages,
+            keys,
+            log: RoomLog {
+                path,
+                file: tokio::fs::File::from_std(file),
+            },
+        })
+    }
+    Ok(rooms)
+}
+
+/// Parses complete lines of the log, returns number of the first malformed line on error
+#[allow(clippy::type_complexity)]
+fn parse(content: &[u8]) -> Result<(String, u16, u16, Vec<String>, HashSet<String>), usize> {
+    let mut entries = vec![];
+    for (line, i) in content.split(|&b| b == b'\n').zip(1..) {
+        if !line.is_empty() {
+            entries.push((serde_json::from_slice::<Entry<'_>>(line).map_err(|_| i)?, i));
+        }
+    }
+    let mut entries = entries.into_iter();
+
+    let (room_id, capacity) = match entries.next() {
+        Some((Entry::Created { room_id, capacity }, _)) => (room_id.into_owned(), capacity),
+        _ => return Err(1),
+    };
+    let mut issued = 0;
+    let mut messages = vec![];
+    let mut keys = HashSet::new();
+    for (entry, i) in entries {
+        match entry {
+            Entry::IssuedIdx { idx } if idx == issued + 1 && idx <= capacity => issued = idx,
+            Entry::Message { message, key } => {
+                messages.push(message.into_owned());
+                keys.extend(key.map(Cow::into_owned));
+            }
+            _ => return Err(i),
+        }
+    }
+    Ok((room_id, capacity, issued, messages, keys))
+}
+
+fn file_name(room_id: &str) -> String {
+    format!("{}.{}", hex::encode(room_id), EXTENSION)
+}
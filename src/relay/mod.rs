@@ -0,0 +1,463 @@
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+//! # Relay server of HTTP/SSE rooms
+//!
+//! Serves the same API as `gg20_sm_manager` example, so it works with `gg20_sm_client` example
+//! and with [transport::http](crate::transport) client:
+//! * `POST rooms/{room}/issue_unique_idx` returns `{"unique_idx": N}`, a unique number in the room
+//! * `POST rooms/{room}/broadcast` takes a message and forwards it to every subscriber. If
+//!   request has `Idempotency-Key` header, message is ignored when a message with the same key
+//!   has already been published, so clients may safely retry broadcasts
+//! * `GET rooms/{room}/subscribe` is an SSE stream of messages sent to the room. Events are
+//!   numbered from 0, a client that lost connection resumes the stream by sending id of the last
+//!   received event in `Last-Event-ID` header
+//!
+//! Unlike the example, relay is meant to be exposed to the network:
+//! * Every room has a capacity: it issues at most `capacity` unique indexes and accepts at most
+//!   `capacity * max_messages_per_party` messages of total size up to [Config::max_room_size].
+//!   Capacity is declared by parties in `parties` query parameter of any request to the room
+//!   (e.g. `rooms/{room}/subscribe?parties=3`), the first request fixes it, requests declaring
+//!   different capacity are rejected with `409 Conflict`. Rooms created by requests that don't
+//!   declare capacity get [Config::default_capacity].
+//! * Relay holds at most [Config::max_rooms] rooms, requests that would create another one are
+//!   rejected with `503 Service Unavailable` until some room expires. Memory taken by messages is
+//!   bounded by `max_rooms * max_room_size`.
+//! * Room is removed once it saw no activity (index issued or message published) for
+//!   [Config::room_ttl]. Its subscriptions end, subsequent requests create a new room.
+//! * If [Config::log_dir] is set, rooms are logged to disk and restored when relay restarts.
+//! * If [Config::tokens] isn't empty, every request must carry one of them in
+//!   `Authorization: Bearer <token>` header.
+//!
+//! Capacity bounds resources taken by a room, it doesn't restrict who may write to it. Relay
+//! doesn't know which client holds which index: any client that passes authentication may
+//! subscribe to any room and broadcast messages with any `sender`, whether or not it was issued
+//! an index, and an index is issued to whoever asks first. Bearer tokens only keep strangers out
+//! of the relay. Parties must authenticate each other end to end, e.g. by signing messages with
+//! [identity keys](crate::identity), and treat the relay as an untrusted transport.
+//!
+//! Relay doesn't terminate TLS. Put it behind a reverse proxy that does, the proxy may also
+//! authenticate parties by client certificates.
+//!
+//! `relay` binary (requires `relay` feature) runs the server, see `relay --help`.
+
+use std::collections::hash_map::{Entry, HashMap};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::Stream;
+use rocket::data::{Limits as DataLimits, ToByteUnit};
+use rocket::fairing::AdHoc;
+use rocket::figment::Figment;
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::response::stream::{stream, Event, EventStream};
+use rocket::serde::json::Json;
+use rocket::{Build, Rocket, Shutdown, State};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use subtle::{Choice, ConstantTimeEq};
+use thiserror::Error;
+use tokio::sync::RwLock;
+
+use self::log::RoomLog;
+use self::room::{Limits, Room, RoomError};
+
+mod log;
+mod room;
+
+#[cfg(test)]
+mod test;
+
+/// Longest room id accepted by relay
+pub const MAX_ROOM_ID_LEN: usize = 100;
+/// Longest idempotency key accepted by relay
+pub const MAX_IDEMPOTENCY_KEY_LEN: usize = 64;
+
+/// Relay configuration
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Room is removed once it saw no activity for this long
+    pub room_ttl: Duration,
+    /// Capacity of rooms whose capacity isn't declared by parties
+    pub default_capacity: u16,
+    /// Largest capacity parties may declare
+    pub max_capacity: u16,
+    /// Room accepts at most `capacity * max_messages_per_party` messages
+    pub max_messages_per_party: usize,
+    /// Size limit of a single message, in bytes
+    pub max_message_size: usize,
+    /// Limit of total size of messages in a room, in bytes
+    pub max_room_size: usize,
+    /// Maximum number of rooms held at the same time
+    pub max_rooms: usize,
+    /// Directory rooms are logged to, rooms are kept in memory only if it's `None`
+    pub log_dir: Option<PathBuf>,
+    /// Accepted bearer tokens, authentication is disabled if it's empty
+    pub tokens: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            room_ttl: Duration::from_secs(60 * 60),
+            default_capacity: 16,
+            max_capacity: 256,
+            max_messages_per_party: 1000,
+            max_message_size: 1024 * 1024,
+            max_room_size: 16 * 1024 * 1024,
+            max_rooms: 128,
+            log_dir: None,
+            tokens: vec![],
+        }
+    }
+}
+
+/// Error of restoring relay state
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("i/o error at {path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("log {path} is corrupted at line {line}")]
+    CorruptedLog { path: PathBuf, line: usize },
+    #[error("invalid config: {0}")]
+    InvalidConfig(&'static str),
+}
+
+/// State of the relay: rooms and configuration
+pub struct Relay {
+    config: Config,
+    tokens: Vec<[u8; 32]>,
+    rooms: Arc<RwLock<HashMap<String, Arc<Room>>>>,
+}
+
+impl Relay {
+    /// Constructs relay, restoring rooms from [Config::log_dir] if it's set
+    pub fn open(config: Config) -> Result<Self, Error> {
+        if config.default_capacity == 0 || config.default_capacity > config.max_capacity {
+            return Err(Error::InvalidConfig(
+                "default capacity must be within [1; max_capacity]",
+            ));
+        }
+        if config.room_ttl == Duration::ZERO {
+            return Err(Error::InvalidConfig("room ttl must be positive"));
+        }
+        if config.max_rooms == 0 {
+            return Err(Error::InvalidConfig("max rooms must be positive"));
+        }
+
+        let mut rooms = HashMap::new();
+        if let Some(dir) = &config.log_dir {
+            for restored in log::restore(dir)? {
+                let room = Room::restore(
+                    restored.capacity,
+                    room_limits(&config, restored.capacity),
+                    restored.issued,
+                    restored.messages,
+                    restored.keys,
+                    Some(restored.log),
+                );
+                rooms.insert(restored.room_id, Arc::new(room));
+            }
+        }
+
+        let tokens = config
+            .tokens
+            .iter()
+            .map(|token| Sha256::digest(token.as_bytes()).into())
+            .collect();
+        Ok(Self {
+            config,
+            tokens,
+            rooms: Arc::new(RwLock::new(rooms)),
+        })
+    }
+
+    /// Removes rooms that saw no activity for [Config::room_ttl]
+    ///
+    /// Server built by [server] calls it periodically.
+    pub async fn remove_expired_rooms(&self) {
+        remove_expired_rooms(&self.rooms, self.config.room_ttl).await
+    }
+
+    fn is_authorized(&self, authorization: Option<&str>) -> bool {
+        if self.tokens.is_empty() {
+            return true;
+        }
+        let token = match authorization.and_then(|header| header.strip_prefix("Bearer ")) {
+            Some(token) => Sha256::digest(token.as_bytes()),
+            None => return false,
+        };
+        self.tokens
+            .iter()
+            .fold(Choice::from(0), |matched, expected| {
+                matched | expected.ct_eq(token.as_ref())
+            })
+            .into()
+    }
+
+    async fn room(&self, room_id: &str, parties: Option<u16>) -> Result<Arc<Room>, Status> {
+        if room_id.is_empty() || room_id.len() > MAX_ROOM_ID_LEN {
+            return Err(Status::BadRequest);
+        }
+        if let Some(parties) = parties {
+            if parties == 0 || parties > self.config.max_capacity {
+                return Err(Status::BadRequest);
+            }
+        }
+
+        let existing = self.rooms.read().await.get(room_id).cloned();
+        let room = match existing {
+            Some(room) if !room.is_expired(self.config.room_ttl).await => room,
+            _ => self.create_room(room_id, parties).await?,
+        };
+        match parties {
+            Some(parties) if parties != room.capacity() => Err(Status::Conflict),
+            _ => Ok(room),
+        }
+    }
+
+    /// Creates a room unless it's been created concurrently, replaces expired room
+    ///
+    /// Returns `503 Service Unavailable` if relay already holds [Config::max_rooms] rooms.
+    async fn create_room(&self, room_id: &str, parties: Option<u16>) -> Result<Arc<Room>, Status> {
+        let mut rooms = self.rooms.write().await;
+        if !rooms.contains_key(room_id) && rooms.len() >= self.config.max_rooms {
+            return Err(Status::ServiceUnavailable);
+        }
+        let entry = rooms.entry(room_id.to_owned());
+        if let Entry::Occupied(entry) = &entry {
+            let room = entry.get();
+            if !room.is_expired(self.config.room_ttl).await {
+                return Ok(room.clone());
+            }
+            // Log of a new room overwrites the old one anyway
+            let _ = room.close().await;
+        }
+
+        let capacity = parties.unwrap_or(self.config.default_capacity);
+        let log = match &self.config.log_dir {
+            Some(dir) => Some(RoomLog::create(dir, room_id, capacity).await.map_err(|e| {
+                rocket::error!("cannot create room log: {}", e);
+                Status::InternalServerError
+            })?),
+            None => None,
+        };
+        let room = Arc::new(Room::new(
+            capacity,
+            room_limits(&self.config, capacity),
+            log,
+        ));
+        match entry {
+            Entry::Occupied(entry) => *entry.into_mut() = room.clone(),
+            Entry::Vacant(entry) => {
+                entry.insert(room.clone());
+            }
+        }
+        Ok(room)
+    }
+}
+
+fn room_limits(config: &Config, capacity: u16) -> Limits {
+    Limits {
+        messages: usize::from(capacity).saturating_mul(config.max_messages_per_party),
+        size: config.max_room_size,
+    }
+}
+
+async fn remove_expired_rooms(rooms: &RwLock<HashMap<String, Arc<Room>>>, ttl: Duration) {
+    let mut rooms = rooms.write().await;
+    let mut expired = vec![];
+    for (room_id, room) in rooms.iter() {
+        if room.is_expired(ttl).await {
+            expired.push(room_id.clone());
+        }
+    }
+    for room_id in expired {
+        if let Some(room) = rooms.remove(&room_id) {
+            if let Err(e) = room.close().await {
+                rocket::error!("cannot remove log of expired room: {}", e);
+            }
+        }
+    }
+}
+
+/// Builds relay server
+///
+/// Takes Rocket configuration (address, port, etc.), sets limits on size of incoming messages
+/// and attaches a task removing expired rooms.
+pub fn server(figment: Figment, relay: Relay) -> Rocket<Build> {
+    let figment = figment.merge((
+        "limits",
+        DataLimits::new().limit("string", relay.config.max_message_size.bytes()),
+    ));
+    let rooms = relay.rooms.clone();
+    let ttl = relay.config.room_ttl;
+    rocket::custom(figment)
+        .mount("/", rocket::routes![subscribe, issue_idx, broadcast])
+        .manage(relay)
+        .attach(AdHoc::on_liftoff("Expired rooms cleanup", move |rocket| {
+            let mut shutdown = rocket.shutdown();
+            Box::pin(async move {
+                tokio::spawn(async move {
+                    loop {
+                        tokio::select! {
+                            _ = tokio::time::sleep(ttl / 2) => (),
+                            _ = &mut shutdown => return,
+                        }
+                        remove_expired_rooms(&rooms, ttl).await;
+                    }
+                });
+            })
+        }))
+}
+
+#[rocket::get("/rooms/<room_id>/subscribe?<parties>")]
+async fn subscribe(
+    _auth: Authorized,
+    relay: &State<Relay>,
+    mut shutdown: Shutdown,
+    last_seen_msg: LastEventId,
+    room_id: &str,
+    parties: Option<u16>,
+) -> Result<EventStream<impl Stream<Item = Event>>, Status> {
+    let room = relay.room(room_id, parties).await?;
+    let mut subscription = room.subscribe(last_seen_msg.0);
+    Ok(EventStream::from(stream! {
+        loop {
+            let message = tokio::select! {
+                message = subscription.next() => message,
+                _ = &mut shutdown => return,
+            };
+            let (id, msg) = match message {
+                Some(message) => message,
+                None => return,
+            };
+            yield Event::data(msg)
+                .event("new-message")
+                .id(id.to_string())
+        }
+    }))
+}
+
+#[rocket::post("/rooms/<room_id>/issue_unique_idx?<parties>")]
+async fn issue_idx(
+    _auth: Authorized,
+    relay: &State<Relay>,
+    room_id: &str,
+    parties: Option<u16>,
+) -> Result<Json<IssuedUniqueIdx>, Status> {
+    let room = relay.room(room_id, parties).await?;
+    let idx = room.issue_unique_idx().await.map_err(room_error_status)?;
+    Ok(Json::from(IssuedUniqueIdx { unique_idx: idx }))
+}
+
+#[rocket::post("/rooms/<room_id>/broadcast?<parties>", data = "<message>")]
+async fn broadcast(
+    _auth: Authorized,
+    relay: &State<Relay>,
+    room_id: &str,
+    parties: Option<u16>,
+    key: IdempotencyKey,
+    message: String,
+) -> Result<Status, Status> {
+    let room = relay.room(room_id, parties).await?;
+    room.publish(message, key.0)
+        .await
+        .map_err(room_error_status)?;
+    Ok(Status::Ok)
+}
+
+fn room_error_status(error: RoomError) -> Status {
+    match error {
+        RoomError::Full => Status::Forbidden,
+        RoomError::LimitExceeded => Status::InsufficientStorage,
+        RoomError::Closed => Status::Gone,
+        RoomError::Log(e) => {
+            rocket::error!("cannot write to room log: {}", e);
+            Status::InternalServerError
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct IssuedUniqueIdx {
+    unique_idx: u16,
+}
+
+/// Guard that rejects requests not carrying a valid bearer token
+struct Authorized;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Authorized {
+    type Error = &'static str;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let relay = match request.rocket().state::<Relay>() {
+            Some(relay) => relay,
+            None => return Outcome::Error((Status::InternalServerError, "relay isn't managed")),
+        };
+        if relay.is_authorized(request.headers().get_one("Authorization")) {
+            Outcome::Success(Authorized)
+        } else {
+            Outcome::Error((Status::Unauthorized, "missing or invalid bearer token"))
+        }
+    }
+}
+
+/// Represents a header Last-Event-ID
+struct LastEventId(Option<u64>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for LastEventId {
+    type Error = &'static str;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let header = request
+            .headers()
+            .get_one("Last-Event-ID")
+            .map(|id| id.parse::<u64>());
+        match header {
+            Some(Ok(last_seen_msg)) => Outcome::Success(LastEventId(Some(last_seen_msg))),
+            Some(Err(_parse_err)) => {
+                Outcome::Error((Status::BadRequest, "last seen msg id is not valid"))
+            }
+            None => Outcome::Success(LastEventId(None)),
+        }
+    }
+}
+
+/// Represents a header Idempotency-Key
+struct IdempotencyKey(Option<String>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IdempotencyKey {
+    type Error = &'static str;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match request.headers().get_one("Idempotency-Key") {
+            Some(key) if key.is_empty() || key.len() > MAX_IDEMPOTENCY_KEY_LEN => {
+                Outcome::Error((Status::BadRequest, "idempotency key is not valid"))
+            }
+            key => Outcome::Success(IdempotencyKey(key.map(String::from))),
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::io;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::sync::{Notify, RwLock};
+
+use super::log::{Entry, RoomLog};
+
+/// Limits applied to a room
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    /// Maximum number of messages
+    pub messages: usize,
+    /// Maximum total size of messages, in bytes
+    pub size: usize,
+}
+
+pub struct Room {
+    capacity: u16,
+    limits: Limits,
+    state: RwLock<State>,
+    message_appeared: Notify,
+}
+
+struct State {
+    messages: Vec<String>,
+    /// Idempotency keys of published messages
+    keys: HashSet<String>,
+    size: usize,
+    issued: u16,
+    last_activity: Instant,
+    closed: bool,
+    log: Option<RoomLog>,
+}
+
+#[derive(Debug)]
+pub enum RoomError {
+    /// Every party index has been issued
+    Full,
+    /// Room reached its limits
+    LimitExceeded,
+    /// Room has expired
+    Closed,
+    /// Couldn't write to the log
+    Log(io::Error),
+}
+
+impl Room {
+    pub fn new(capacity: u16, limits: Limits, log: Option<RoomLog>) -> Self {
+        Self::restore(capacity, limits, 0, vec![], HashSet::new(), log)
+    }
+
+    pub fn restore(
+        capacity: u16,
+        limits: Limits,
+        issued: u16,
+        messages: Vec<String>,
+        keys: HashSet<String>,
+        log: Option<RoomLog>,
+    ) -> Self {
+        Self {
+            capacity,
+            limits,
+            state: RwLock::new(State {
+                size: messages.iter().map(String::len).sum(),
+                messages,
+                keys,
+                issued,
+                last_activity: Instant::now(),
+                closed: false,
+                log,
+            }),
+            message_appeared: Notify::new(),
+        }
+    }
+
+    pub fn capacity(&self) -> u16 {
+        self.capacity
+    }
+
+    pub async fn issue_unique_idx(&self) -> Result<u16, RoomError> {
+        let mut state = self.state.write().await;
+        if state.closed {
+            return Err(RoomError::Closed);
+        }
+        if state.issued == self.capacity {
+            return Err(RoomError::Full);
+        }
+        let idx = state.issued + 1;
+        if let Some(log) = &mut state.log {
+            log.append(&Entry::IssuedIdx { idx })
+                .await
+                .map_err(RoomError::Log)?;
+        }
+        state.issued = idx;
+        state.last_activity = Instant::now();
+        Ok(idx)
+    }
+
+    /// Publishes a message, unless a message with the same idempotency `key` was published
+    pub async fn publish(&self, message: String, key: Option<String>) -> Result<(), RoomError> {
+        let mut state = self.state.write().await;
+        if state.closed {
+            return Err(RoomError::Closed);
+        }
+        if key.as_ref().map(|key| state.keys.contains(key)) == Some(true) {
+            return Ok(());
+        }
+        if state.messages.len() >= self.limits.messages
+            || state.size + message.len() > self.limits.size
+        {
+            return Err(RoomError::LimitExceeded);
+        }
+        if let Some(log) = &mut state.log {
+            log.append(&Entry::Message {
+                message: Cow::Borrowed(&message),
+                key: key.as_deref().map(Cow::Borrowed),
+            })
+            .await
+            .map_err(RoomError::Log)?;
+        }
+        state.size += message.len();
+        state.messages.push(message);
+        state.keys.extend(key);
+        state.last_activity = Instant::now();
+        self.message_appeared.notify_waiters();
+        Ok(())
+    }
+
+    /// Subscribes to messages following the message with id `last_seen_msg`, or to all messages
+    /// if it's `None`
+    pub fn subscribe(self: Arc<Self>, last_seen_msg: Option<u64>) -> Subscription {
+        Subscription {
+            room: self,
+            next_event: last_seen_msg.map(|i| i.saturating_add(1)).unwrap_or(0),
+        }
+    }
+
+    pub async fn is_expired(&self, ttl: Duration) -> bool {
+        self.state.read().await.last_activity.elapsed() >= ttl
+    }
+
+    /// Closes the room: ends all subscriptions and deletes the log
+    pub async fn close(&self) -> io::Result<()> {
+        let mut state = self.state.write().await;
+        state.closed = true;
+        self.message_appeared.notify_waiters();
+        match state.log.take() {
+            Some(log) => log.remove().await,
+            None => Ok(()),
+        }
+    }
+}
+
+pub struct Subscription {
+    room: Arc<Room>,
+    next_event: u64,
+}
+
+impl Subscription {
+    /// Waits for the next message, returns `None` once the room is closed
+    pub async fn next(&mut self) -> Option<(u64, String)> {
+        loop {
+            let state = self.room.state.read().await;
+            let next_msg = usize::try_from(self.next_event)
+                .ok()
+                .and_then(|i| state.messages.get(i));
+            if let Some(msg) = next_msg {
+                let event_id = self.next_event;
+                self.next_event = event_id + 1;
+                return Some((event_id, msg.clone()));
+            }
+            if state.closed {
+                return None;
+            }
+            let notification = self.room.message_appeared.notified();
+            drop(state);
+            notification.await;
+        }
+    }
+}
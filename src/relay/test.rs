@@ -0,0 +1,417 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use rocket::config::LogLevel;
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::{Client, LocalResponse};
+use tokio::io::AsyncReadExt;
+
+use super::*;
+
+fn figment() -> Figment {
+    rocket::Config::figment().merge(("log_level", LogLevel::Off))
+}
+
+async fn client(config: Config) -> Client {
+    let relay = Relay::open(config).unwrap();
+    Client::tracked(server(figment(), relay)).await.unwrap()
+}
+
+async fn issue_idx(client: &Client, uri: &str) -> Result<u16, Status> {
+    let response = client.post(uri).dispatch().await;
+    if response.status() != Status::Ok {
+        return Err(response.status());
+    }
+    let issued = response.into_json::<IssuedUniqueIdx>().await.unwrap();
+    Ok(issued.unique_idx)
+}
+
+/// Reads `n` events from SSE stream, returns their ids and data
+async fn read_events(response: &mut LocalResponse<'_>, n: usize) -> Vec<(u64, String)> {
+    let mut received = String::new();
+    let mut buf = [0u8; 1024];
+    while received.matches("\n\n").count() < n {
+        let len = response.read(&mut buf).await.unwrap();
+        assert_ne!(len, 0, "stream ended");
+        received.push_str(std::str::from_utf8(&buf[..len]).unwrap());
+    }
+    received
+        .split_terminator("\n\n")
+        .map(|event| {
+            let field = |name: &str| {
+                event
+                    .lines()
+                    .find_map(|line| line.strip_prefix(name))
+                    .unwrap()
+                    .to_owned()
+            };
+            (field("id:").parse().unwrap(), field("data:"))
+        })
+        .collect()
+}
+
+fn temp_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("relay-test-{}", rand::random::<u64>()))
+}
+
+#[tokio::test]
+async fn room_issues_indexes_up_to_its_capacity() {
+    let client = client(Config::default()).await;
+
+    assert_eq!(
+        issue_idx(&client, "/rooms/a/issue_unique_idx?parties=2").await,
+        Ok(1)
+    );
+    assert_eq!(issue_idx(&client, "/rooms/a/issue_unique_idx").await, Ok(2));
+    assert_eq!(
+        issue_idx(&client, "/rooms/a/issue_unique_idx?parties=2").await,
+        Err(Status::Forbidden)
+    );
+    assert_eq!(
+        issue_idx(&client, "/rooms/a/issue_unique_idx?parties=3").await,
+        Err(Status::Conflict)
+    );
+    assert_eq!(
+        issue_idx(&client, "/rooms/b/issue_unique_idx?parties=257").await,
+        Err(Status::BadRequest)
+    );
+    // Rooms that don't declare capacity get the default one
+    for i in 1..=16 {
+        assert_eq!(issue_idx(&client, "/rooms/c/issue_unique_idx").await, Ok(i));
+    }
+    assert_eq!(
+        issue_idx(&client, "/rooms/c/issue_unique_idx").await,
+        Err(Status::Forbidden)
+    );
+}
+
+#[tokio::test]
+async fn room_rejects_messages_beyond_its_limits() {
+    let client = client(Config {
+        max_messages_per_party: 2,
+        max_room_size: 10,
+        ..Config::default()
+    })
+    .await;
+
+    let broadcast = |room: &'static str, message: &'static str| {
+        let client = &client;
+        async move {
+            client
+                .post(format!("/rooms/{}/broadcast?parties=2", room))
+                .body(message)
+                .dispatch()
+                .await
+                .status()
+        }
+    };
+    for _ in 0..4 {
+        assert_eq!(broadcast("a", "1").await, Status::Ok);
+    }
+    assert_eq!(broadcast("a", "1").await, Status::InsufficientStorage);
+
+    assert_eq!(broadcast("b", "123456").await, Status::Ok);
+    assert_eq!(broadcast("b", "123456").await, Status::InsufficientStorage);
+}
+
+#[tokio::test]
+async fn requests_without_valid_token_are_rejected() {
+    let client = client(Config {
+        tokens: vec!["token1".into(), "token2".into()],
+        ..Config::default()
+    })
+    .await;
+
+    let request = |authorization: Option<&'static str>| {
+        let mut request = client.post("/rooms/a/issue_unique_idx");
+        if let Some(authorization) = authorization {
+            request.add_header(Header::new("Authorization", authorization));
+        }
+        async move { request.dispatch().await.status() }
+    };
+    assert_eq!(request(None).await, Status::Unauthorized);
+    assert_eq!(request(Some("token1")).await, Status::Unauthorized);
+    assert_eq!(request(Some("Bearer token3")).await, Status::Unauthorized);
+    assert_eq!(request(Some("Bearer token1")).await, Status::Ok);
+    assert_eq!(request(Some("Bearer token2")).await, Status::Ok);
+
+    let response = client.get("/rooms/a/subscribe").dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+}
+
+#[tokio::test]
+async fn subscription_resumes_after_last_event_id() {
+    let client = client(Config::default()).await;
+    for message in ["a", "b", "c"] {
+        let response = client
+            .post("/rooms/r/broadcast")
+            .body(message)
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+    }
+
+    let mut response = client.get("/rooms/r/subscribe").dispatch().await;
+    assert_eq!(
+        read_events(&mut response, 3).await,
+        [(0, "a".into()), (1, "b".into()), (2, "c".into())]
+    );
+
+    let mut response = client
+        .get("/rooms/r/subscribe")
+        .header(Header::new("Last-Event-ID", "1"))
+        .dispatch()
+        .await;
+    assert_eq!(read_events(&mut response, 1).await, [(2, "c".into())]);
+
+    // Event ids are u64, they don't wrap around at u16::MAX
+    let response = client
+        .get("/rooms/r/subscribe")
+        .header(Header::new("Last-Event-ID", "70000"))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let response = client
+        .get("/rooms/r/subscribe")
+        .header(Header::new("Last-Event-ID", "-1"))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::BadRequest);
+}
+
+#[tokio::test]
+async fn rooms_are_restored_from_log() {
+    let log_dir = temp_dir();
+    let config = Config {
+        log_dir: Some(log_dir.clone()),
+        ..Config::default()
+    };
+
+    let client1 = client(config.clone()).await;
+    assert_eq!(
+        issue_idx(&client1, "/rooms/a/issue_unique_idx?parties=3").await,
+        Ok(1)
+    );
+    for message in ["multi\nline", "second"] {
+        let response = client1
+            .post("/rooms/a/broadcast")
+            .body(message)
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+    }
+    drop(client1);
+
+    let client2 = client(config).await;
+    assert_eq!(
+        issue_idx(&client2, "/rooms/a/issue_unique_idx?parties=4").await,
+        Err(Status::Conflict)
+    );
+    assert_eq!(
+        issue_idx(&client2, "/rooms/a/issue_unique_idx").await,
+        Ok(2)
+    );
+    let mut response = client2.get("/rooms/a/subscribe").dispatch().await;
+    let events = read_events(&mut response, 2).await;
+    assert_eq!(events[1], (1, "second".into()));
+
+    std::fs::remove_dir_all(log_dir).unwrap();
+}
+
+#[tokio::test]
+async fn incomplete_log_entry_is_discarded() {
+    let log_dir = temp_dir();
+    let config = Config {
+        log_dir: Some(log_dir.clone()),
+        ..Config::default()
+    };
+
+    let client1 = client(config.clone()).await;
+    assert_eq!(
+        issue_idx(&client1, "/rooms/a/issue_unique_idx").await,
+        Ok(1)
+    );
+    drop(client1);
+
+    // Relay crashed while logging the second index
+    let log = std::fs::read_dir(&log_dir)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    let mut content = std::fs::read(log.path()).unwrap();
+    content.extend_from_slice(br#"{"type":"issued_idx","#);
+    std::fs::write(log.path(), content).unwrap();
+
+    let client2 = client(config.clone()).await;
+    assert_eq!(
+        issue_idx(&client2, "/rooms/a/issue_unique_idx").await,
+        Ok(2)
+    );
+    drop(client2);
+    let client3 = client(config).await;
+    assert_eq!(
+        issue_idx(&client3, "/rooms/a/issue_unique_idx").await,
+        Ok(3)
+    );
+
+    std::fs::remove_dir_all(log_dir).unwrap();
+}
+
+#[tokio::test]
+async fn expired_rooms_are_removed() {
+    let log_dir = temp_dir();
+    let client = client(Config {
+        room_ttl: Duration::from_millis(100),
+        log_dir: Some(log_dir.clone()),
+        ..Config::default()
+    })
+    .await;
+
+    assert_eq!(issue_idx(&client, "/rooms/a/issue_unique_idx").await, Ok(1));
+    let mut response = client.get("/rooms/a/subscribe").dispatch().await;
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    client
+        .rocket()
+        .state::<Relay>()
+        .unwrap()
+        .remove_expired_rooms()
+        .await;
+
+    // Subscription of expired room ends
+    let mut rest = vec![];
+    response.read_to_end(&mut rest).await.unwrap();
+    assert!(rest.is_empty());
+    assert_eq!(std::fs::read_dir(&log_dir).unwrap().count(), 0);
+
+    // Room id can be reused
+    assert_eq!(issue_idx(&client, "/rooms/a/issue_unique_idx").await, Ok(1));
+
+    std::fs::remove_dir_all(log_dir).unwrap();
+}
+
+#[cfg(feature = "transport-http")]
+#[tokio::test]
+async fn keygen_over_relay() {
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::Keygen;
+    use crate::transport::{http, Transport};
+    use futures::StreamExt;
+    use round_based::AsyncProtocol;
+
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let relay = Relay::open(Config {
+        tokens: vec!["secret".into()],
+        ..Config::default()
+    })
+    .unwrap();
+    let server = server(figment().merge(("port", port)), relay)
+        .ignite()
+        .await
+        .unwrap();
+    let shutdown = server.shutdown();
+    let server = tokio::spawn(server.launch());
+
+    let address: surf::Url = format!("http://127.0.0.1:{}/", port).parse().unwrap();
+    let unauthorized = http::join(address.clone(), "keygen").await;
+    assert!(unauthorized.is_err());
+
+    let mut parties = vec![];
+    for _ in 0..3 {
+        let client = http::RoomClient::new(address.clone(), "keygen")
+            .unwrap()
+            .with_bearer_token("secret")
+            .with_parties(3);
+        let transport = http::join_with(client).await.unwrap();
+        let i = Transport::<()>::party_index(&transport);
+        let (incoming, outgoing) = transport.split();
+        parties.push(tokio::spawn(async move {
+            AsyncProtocol::new(Keygen::new(i, 1, 3).unwrap(), incoming.fuse(), outgoing)
+                .run()
+                .await
+                .map_err(|e| e.to_string())
+        }));
+    }
+    let mut public_keys = vec![];
+    for party in parties {
+        public_keys.push(party.await.unwrap().unwrap().public_key());
+    }
+    assert!(public_keys.iter().all(|pk| *pk == public_keys[0]));
+
+    shutdown.notify();
+    server.await.unwrap().unwrap();
+}
+
+async fn broadcast(client: &Client, message: &'static str, key: &'static str) -> Status {
+    let request = client
+        .post("/rooms/a/broadcast")
+        .header(Header::new("Idempotency-Key", key))
+        .body(message);
+    request.dispatch().await.status()
+}
+
+#[tokio::test]
+async fn broadcast_is_idempotent() {
+    let log_dir = temp_dir();
+    let config = Config {
+        log_dir: Some(log_dir.clone()),
+        ..Config::default()
+    };
+    let client1 = client(config.clone()).await;
+    assert_eq!(broadcast(&client1, "first", "key1").await, Status::Ok);
+    assert_eq!(broadcast(&client1, "first", "key1").await, Status::Ok);
+    drop(client1);
+
+    // Keys are restored along with messages
+    let client2 = client(config).await;
+    assert_eq!(broadcast(&client2, "first", "key1").await, Status::Ok);
+    assert_eq!(broadcast(&client2, "second", "key2").await, Status::Ok);
+    assert_eq!(broadcast(&client2, "second", "").await, Status::BadRequest);
+
+    let mut response = client2.get("/rooms/a/subscribe").dispatch().await;
+    assert_eq!(
+        read_events(&mut response, 2).await,
+        [(0, "first".into()), (1, "second".into())]
+    );
+    // There's no third message
+    let mut response = client2
+        .get("/rooms/a/subscribe")
+        .header(Header::new("Last-Event-ID", "1"))
+        .dispatch()
+        .await;
+    let third = read_events(&mut response, 1);
+    assert!(tokio::time::timeout(Duration::from_millis(100), third)
+        .await
+        .is_err());
+
+    std::fs::remove_dir_all(log_dir).unwrap();
+}
+
+#[tokio::test]
+async fn relay_holds_limited_number_of_rooms() {
+    let client = client(Config {
+        max_rooms: 2,
+        ..Config::default()
+    })
+    .await;
+
+    assert_eq!(issue_idx(&client, "/rooms/a/issue_unique_idx").await, Ok(1));
+    assert_eq!(issue_idx(&client, "/rooms/b/issue_unique_idx").await, Ok(1));
+    assert_eq!(
+        issue_idx(&client, "/rooms/c/issue_unique_idx").await,
+        Err(Status::ServiceUnavailable)
+    );
+    // Existing rooms are still served
+    assert_eq!(issue_idx(&client, "/rooms/a/issue_unique_idx").await, Ok(2));
+
+    assert!(matches!(
+        Relay::open(Config {
+            max_rooms: 0,
+            ..Config::default()
+        }),
+        Err(Error::InvalidConfig(_))
+    ));
+}
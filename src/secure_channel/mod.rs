@@ -0,0 +1,477 @@
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+//! # Encrypted point-to-point channels
+//!
+//! Protocols send secret data (e.g. VSS shares at keygen) in P2P messages. When messages are
+//! delivered via a relay that broadcasts everything to everyone, P2P messages must be encrypted.
+//! This module wraps any `round_based` incoming stream / outgoing sink, so that:
+//! * P2P messages are encrypted with AES-256-GCM under a key shared by sender and receiver only.
+//!   Sender and receiver indexes along with message sequence number are authenticated as
+//!   associated data, so a P2P message can't be redirected to another party, attributed to
+//!   another sender, or replayed
+//! * Broadcast messages are sent as is
+//! * Received P2P message that is not encrypted is rejected
+//! * Received P2P message addressed to another party is skipped
+//!
+//! Pairwise keys are obtained via [handshake]: every party broadcasts an ephemeral public key,
+//! and every pair of parties derives a key from ephemeral ECDH bound to both parties' indexes
+//! and to [session id](ChannelKeys::session_id). Session id is a hash of the context passed to
+//! handshake and ephemeral keys of all parties, so it's fresh for every execution even if the
+//! context is reused.
+//!
+//! **Handshake is not authenticated by itself.** Channels protect against passive adversaries
+//! (e.g. honest-but-curious relay), but a relay that substitutes ephemeral keys can read and
+//! forge every P2P message. Sign handshake messages with parties' [identity keys](crate::identity)
+//! ([sign_outgoing](crate::identity::sign_outgoing) /
+//! [verify_incoming](crate::identity::verify_incoming)) to rule that out.
+//!
+//! ## Example
+//! ```no_run
+//! # use std::sync::Arc;
+//! # use futures::Sink;
+//! # use round_based::{Msg, async_runtime::AsyncProtocol};
+//! # use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::{Keygen, ProtocolMessage};
+//! # use multi_party_ecdsa::secure_channel::{self, Envelope, Handshake};
+//! # async fn join<M>() -> (futures::stream::Pending<Result<Msg<M>, anyhow::Error>>, Box<dyn Sink<Msg<M>, Error = anyhow::Error> + Unpin>) { unimplemented!() }
+//! # async fn run() -> anyhow::Result<()> {
+//! let (i, t, n) = (1, 1, 3);
+//! let (incoming, outgoing) = join::<Handshake>().await;
+//! let keys = secure_channel::handshake(i, n, b"keygen-session-1", incoming, outgoing).await?;
+//! let keys = Arc::new(keys);
+//!
+//! let (incoming, outgoing) = join::<Envelope<ProtocolMessage>>().await;
+//! let incoming = secure_channel::wrap_incoming(keys.clone(), incoming);
+//! let outgoing = secure_channel::wrap_outgoing(keys, outgoing);
+//! # use futures::StreamExt;
+//! let local_key = AsyncProtocol::new(Keygen::new(i, t, n)?, incoming.fuse(), outgoing)
+//!     .run()
+//!     .await
+//!     .map_err(|e| anyhow::anyhow!("keygen failed: {}", e))?;
+//! # Ok(()) }
+//! ```
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use aes_gcm::aead::{Aead, NewAead, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use curv::arithmetic::traits::*;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use futures::future;
+use futures::{Sink, SinkExt, Stream, StreamExt, TryStreamExt};
+use round_based::Msg;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use zeroize::Zeroize;
+
+/// Domain separator of pairwise key derivation
+const KDF_DOMAIN: &[u8] = b"multi-party-ecdsa/secure-channel/v1";
+/// Domain separator of session id derivation
+const SESSION_DOMAIN: &[u8] = b"multi-party-ecdsa/secure-channel/v1/session";
+
+/// Message that announces party's ephemeral public key
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Handshake {
+    pub session_id: Vec<u8>,
+    pub ephemeral_key: Point<Secp256k1>,
+}
+
+/// Wire format of messages sent through secure channel
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Envelope<M> {
+    /// Broadcast message, sent as is
+    Broadcast(M),
+    /// Encrypted P2P message
+    Sealed(SealedMessage),
+}
+
+/// Encrypted P2P message
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SealedMessage {
+    pub nonce: [u8; 12],
+    pub ciphertext: Vec<u8>,
+}
+
+/// Ephemeral secret of a single session
+pub struct EphemeralKey {
+    secret: Scalar<Secp256k1>,
+    public: Point<Secp256k1>,
+}
+
+impl EphemeralKey {
+    pub fn generate() -> Self {
+        let secret = Scalar::<Secp256k1>::random();
+        let public = Point::generator() * &secret;
+        Self { secret, public }
+    }
+
+    /// Handshake message to be broadcast to other parties
+    pub fn handshake(&self, session_id: &[u8]) -> Handshake {
+        Handshake {
+            session_id: session_id.to_vec(),
+            ephemeral_key: self.public.clone(),
+        }
+    }
+
+    /// Derives pairwise keys from handshakes received from all other parties
+    ///
+    /// `i` is index of this party in range `[1; n]`. `handshakes` must contain exactly one message
+    /// from every party except this one.
+    ///
+    /// Handshakes are taken as is: authenticity of their senders must be checked by the caller
+    /// (see [module level documentation](self)).
+    pub fn into_channel_keys(
+        self,
+        i: u16,
+        n: u16,
+        session_id: &[u8],
+        handshakes: Vec<Msg<Handshake>>,
+    ) -> Result<ChannelKeys> {
+        if i == 0 || i > n {
+            return Err(Error::InvalidPartyIndex);
+        }
+        let mut peers: Vec<Option<Point<Secp256k1>>> = vec![None; usize::from(n)];
+        for msg in handshakes {
+            let j = msg.sender;
+            if j == 0 || j > n || j == i {
+                return Err(Error::UnexpectedHandshake { sender: j });
+            }
+            if msg.body.session_id != session_id {
+                return Err(Error::SessionMismatch { sender: j });
+            }
+            if msg.body.ephemeral_key.is_zero() {
+                return Err(Error::InvalidEphemeralKey { sender: j });
+            }
+            let slot = &mut peers[usize::from(j - 1)];
+            if slot.is_some() {
+                return Err(Error::UnexpectedHandshake { sender: j });
+            }
+            *slot = Some(msg.body.ephemeral_key);
+        }
+
+        peers[usize::from(i - 1)] = Some(self.public.clone());
+        let peers = (1..=n)
+            .zip(peers)
+            .map(|(j, peer)| peer.ok_or(Error::MissingHandshake { party: j }))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut hash = Sha256::new()
+            .chain(SESSION_DOMAIN)
+            .chain((session_id.len() as u64).to_be_bytes())
+            .chain(session_id)
+            .chain(n.to_be_bytes());
+        for peer in &peers {
+            hash.update(&*peer.to_bytes(true));
+        }
+        let session_id: [u8; 32] = hash.finalize().into();
+
+        let keys = (1..=n)
+            .zip(&peers)
+            .map(|(j, peer)| {
+                if j == i {
+                    [0u8; 32]
+                } else {
+                    self.derive_key(i, &self.public, j, peer, &session_id)
+                }
+            })
+            .collect();
+
+        Ok(ChannelKeys {
+            i,
+            session_id,
+            keys,
+            counter: AtomicU64::new(0),
+            received: (0..n).map(|_| AtomicU64::new(0)).collect(),
+        })
+    }
+
+    fn derive_key(
+        &self,
+        i: u16,
+        pk_i: &Point<Secp256k1>,
+        j: u16,
+        pk_j: &Point<Secp256k1>,
+        session_id: &[u8],
+    ) -> [u8; 32] {
+        let shared = pk_j * &self.secret;
+        let shared_x = shared
+            .x_coord()
+            .and_then(|x| x.to_bytes_array::<32>())
+            .unwrap_or([0u8; 32]);
+        let ((lo, pk_lo), (hi, pk_hi)) = if i < j {
+            ((i, pk_i), (j, pk_j))
+        } else {
+            ((j, pk_j), (i, pk_i))
+        };
+
+        let mut key = [0u8; 32];
+        key.copy_from_slice(
+            &Sha256::new()
+                .chain(KDF_DOMAIN)
+                .chain((session_id.len() as u64).to_be_bytes())
+                .chain(session_id)
+                .chain(lo.to_be_bytes())
+                .chain(hi.to_be_bytes())
+                .chain(&*pk_lo.to_bytes(true))
+                .chain(&*pk_hi.to_bytes(true))
+                .chain(shared_x)
+                .finalize(),
+        );
+        key
+    }
+}
+
+/// Pairwise keys shared by this party with every other party
+///
+/// Should be wrapped into `Arc` and shared by every stream/sink of the session: nonces are
+/// derived from a counter kept here, so they never repeat as long as the keys are not copied.
+pub struct ChannelKeys {
+    i: u16,
+    session_id: [u8; 32],
+    /// `keys[j - 1]` is a key shared with party `j`
+    keys: Vec<[u8; 32]>,
+    counter: AtomicU64,
+    /// `received[j - 1]` is the least sequence number that party `j` may use in its next message
+    received: Vec<AtomicU64>,
+}
+
+impl ChannelKeys {
+    /// Index of this party
+    pub fn party_index(&self) -> u16 {
+        self.i
+    }
+
+    /// Id of the session derived at handshake
+    ///
+    /// It's the same for all parties that saw the same handshake messages, and it differs
+    /// between executions. Can be used to bind other messages of the session, e.g. signed by
+    /// [identity keys](crate::identity).
+    pub fn session_id(&self) -> &[u8; 32] {
+        &self.session_id
+    }
+
+    /// Encrypts P2P message to be sent to party `receiver`
+    pub fn seal<M: Serialize>(&self, receiver: u16, msg: &M) -> Result<SealedMessage> {
+        let cipher = self.cipher(receiver)?;
+        let plaintext = serde_json::to_vec(msg).map_err(Error::Serialize)?;
+
+        let counter = self.counter.fetch_add(1, Ordering::SeqCst);
+        let mut nonce = [0u8; 12];
+        nonce[..2].copy_from_slice(&self.i.to_be_bytes());
+        nonce[2..4].copy_from_slice(&receiver.to_be_bytes());
+        nonce[4..].copy_from_slice(&counter.to_be_bytes());
+
+        let ciphertext = cipher
+            .encrypt(
+                &Nonce::from(nonce),
+                Payload {
+                    msg: &plaintext,
+                    aad: &associated_data(self.i, receiver, counter),
+                },
+            )
+            .map_err(|_| Error::Encrypt)?;
+        Ok(SealedMessage { nonce, ciphertext })
+    }
+
+    /// Decrypts P2P message received from party `sender`
+    ///
+    /// Sequence numbers of messages received from the same sender must strictly increase, so
+    /// every message can be opened only once.
+    pub fn open<M: DeserializeOwned>(&self, sender: u16, msg: &SealedMessage) -> Result<M> {
+        let cipher = self.cipher(sender)?;
+        let mut counter = [0u8; 8];
+        counter.copy_from_slice(&msg.nonce[4..]);
+        let counter = u64::from_be_bytes(counter);
+        let plaintext = cipher
+            .decrypt(
+                &Nonce::from(msg.nonce),
+                Payload {
+                    msg: &msg.ciphertext,
+                    aad: &associated_data(sender, self.i, counter),
+                },
+            )
+            .map_err(|_| Error::Decrypt { sender })?;
+
+        // Message is authentic, so it's safe to move the window
+        let least_expected =
+            self.received[usize::from(sender - 1)].fetch_max(counter + 1, Ordering::SeqCst);
+        if counter < least_expected {
+            return Err(Error::Replay { sender });
+        }
+        serde_json::from_slice(&plaintext).map_err(|_| Error::Deserialize { sender })
+    }
+
+    /// Turns outgoing message into its wire form, encrypts it if it's a P2P message
+    pub fn seal_msg<M: Serialize>(&self, msg: Msg<M>) -> Result<Msg<Envelope<M>>> {
+        let body = match msg.receiver {
+            Some(receiver) => Envelope::Sealed(self.seal(receiver, &msg.body)?),
+            None => Envelope::Broadcast(msg.body),
+        };
+        Ok(Msg {
+            sender: msg.sender,
+            receiver: msg.receiver,
+            body,
+        })
+    }
+
+    /// Restores message from its wire form
+    ///
+    /// Returns `None` if message is a P2P message addressed to someone else (relay may deliver
+    /// every message to every party), and error if P2P message is not encrypted.
+    pub fn open_msg<M: DeserializeOwned>(&self, msg: Msg<Envelope<M>>) -> Result<Option<Msg<M>>> {
+        let body = match (msg.receiver, msg.body) {
+            (None, Envelope::Broadcast(body)) => body,
+            (Some(receiver), Envelope::Sealed(sealed)) if receiver == self.i => {
+                self.open(msg.sender, &sealed)?
+            }
+            (Some(_), Envelope::Sealed(_)) => return Ok(None),
+            (Some(_), Envelope::Broadcast(_)) => {
+                return Err(Error::UnencryptedP2PMessage { sender: msg.sender })
+            }
+            (None, Envelope::Sealed(_)) => {
+                return Err(Error::EncryptedBroadcastMessage { sender: msg.sender })
+            }
+        };
+        Ok(Some(Msg {
+            sender: msg.sender,
+            receiver: msg.receiver,
+            body,
+        }))
+    }
+
+    fn cipher(&self, party: u16) -> Result<Aes256Gcm> {
+        if party == self.i || party == 0 || usize::from(party) > self.keys.len() {
+            return Err(Error::UnknownParty { party });
+        }
+        let key = &self.keys[usize::from(party - 1)];
+        Ok(Aes256Gcm::new(&Key::from(*key)))
+    }
+}
+
+impl Drop for ChannelKeys {
+    fn drop(&mut self) {
+        self.keys.iter_mut().for_each(|k| k.zeroize())
+    }
+}
+
+fn associated_data(sender: u16, receiver: u16, counter: u64) -> [u8; 12] {
+    let mut aad = [0u8; 12];
+    aad[..2].copy_from_slice(&sender.to_be_bytes());
+    aad[2..4].copy_from_slice(&receiver.to_be_bytes());
+    aad[4..].copy_from_slice(&counter.to_be_bytes());
+    aad
+}
+
+/// Carries out handshake: broadcasts ephemeral key, waits for keys of all other parties
+///
+/// Messages sent by this party (if relay echoes them back) are ignored. `session_id` labels the
+/// context (e.g. room name), it's fine to reuse it: actual [session id](ChannelKeys::session_id)
+/// is derived from fresh ephemeral keys.
+///
+/// Handshake messages are not authenticated unless `incoming` and `outgoing` are wrapped with
+/// [verify_incoming](crate::identity::verify_incoming) and
+/// [sign_outgoing](crate::identity::sign_outgoing). Without that, channels are only secure
+/// against a passive relay.
+pub async fn handshake<I, O, E>(
+    i: u16,
+    n: u16,
+    session_id: &[u8],
+    incoming: I,
+    mut outgoing: O,
+) -> Result<ChannelKeys, E>
+where
+    I: Stream<Item = Result<Msg<Handshake>, E>> + Unpin,
+    O: Sink<Msg<Handshake>, Error = E> + Unpin,
+    E: From<Error>,
+{
+    let ephemeral = EphemeralKey::generate();
+    outgoing
+        .send(Msg {
+            sender: i,
+            receiver: None,
+            body: ephemeral.handshake(session_id),
+        })
+        .await?;
+
+    let handshakes = incoming
+        .try_filter(|msg| future::ready(msg.sender != i))
+        .take(usize::from(n.saturating_sub(1)))
+        .try_collect::<Vec<_>>()
+        .await?;
+    Ok(ephemeral.into_channel_keys(i, n, session_id, handshakes)?)
+}
+
+/// Wraps incoming stream: decrypts P2P messages, rejects unencrypted ones, skips ones addressed
+/// to other parties
+pub fn wrap_incoming<M, I, E>(
+    keys: Arc<ChannelKeys>,
+    incoming: I,
+) -> impl Stream<Item = Result<Msg<M>, E>>
+where
+    M: DeserializeOwned,
+    I: Stream<Item = Result<Msg<Envelope<M>>, E>>,
+    E: From<Error>,
+{
+    incoming.try_filter_map(move |msg| future::ready(keys.open_msg(msg).map_err(E::from)))
+}
+
+/// Wraps outgoing sink: encrypts P2P messages
+pub fn wrap_outgoing<M, O, E>(keys: Arc<ChannelKeys>, outgoing: O) -> impl Sink<Msg<M>, Error = E>
+where
+    M: Serialize,
+    O: Sink<Msg<Envelope<M>>, Error = E>,
+    E: From<Error>,
+{
+    outgoing.with(move |msg: Msg<M>| future::ready(keys.seal_msg(msg).map_err(E::from)))
+}
+
+type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("party index is not in range [1; n]")]
+    InvalidPartyIndex,
+    #[error("unexpected handshake from party {sender}")]
+    UnexpectedHandshake { sender: u16 },
+    #[error("party {party} didn't send a handshake")]
+    MissingHandshake { party: u16 },
+    #[error("party {sender} sent handshake for another session")]
+    SessionMismatch { sender: u16 },
+    #[error("party {sender} sent invalid ephemeral key")]
+    InvalidEphemeralKey { sender: u16 },
+    #[error("no channel with party {party}")]
+    UnknownParty { party: u16 },
+    #[error("serialize message: {0}")]
+    Serialize(serde_json::Error),
+    #[error("encryption failed")]
+    Encrypt,
+    #[error("couldn't decrypt message from party {sender}")]
+    Decrypt { sender: u16 },
+    #[error("couldn't deserialize decrypted message from party {sender}")]
+    Deserialize { sender: u16 },
+    #[error("party {sender} sent P2P message without encryption")]
+    UnencryptedP2PMessage { sender: u16 },
+    #[error("party {sender} sent encrypted broadcast message")]
+    EncryptedBroadcastMessage { sender: u16 },
+    #[error("party {sender} replayed a message")]
+    Replay { sender: u16 },
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,254 @@
+use std::sync::{Arc, Mutex};
+
+use futures::channel::mpsc;
+use futures::{SinkExt, StreamExt};
+use round_based::async_runtime::AsyncProtocol;
+use round_based::Msg;
+
+use super::*;
+use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::{
+    Keygen, ProtocolMessage,
+};
+
+fn channel_keys(n: u16, session_id: &[u8]) -> Vec<ChannelKeys> {
+    let ephemeral = (0..n).map(|_| EphemeralKey::generate()).collect::<Vec<_>>();
+    let handshakes = (1..)
+        .zip(&ephemeral)
+        .map(|(j, e)| Msg {
+            sender: j,
+            receiver: None,
+            body: e.handshake(session_id),
+        })
+        .collect::<Vec<_>>();
+    (1..)
+        .zip(ephemeral)
+        .map(|(i, e)| {
+            let others = handshakes
+                .iter()
+                .filter(|m| m.sender != i)
+                .cloned()
+                .collect();
+            e.into_channel_keys(i, n, session_id, others).unwrap()
+        })
+        .collect()
+}
+
+/// Relay that delivers every message to every party (like the one in examples), and keeps
+/// a log of everything it has seen. P2P messages addressed to other parties are delivered too,
+/// it's up to the parties to skip them
+struct Relay<T> {
+    outgoing: Vec<mpsc::UnboundedSender<Msg<T>>>,
+    incoming: Vec<mpsc::UnboundedReceiver<Msg<T>>>,
+    log: Arc<Mutex<Vec<Msg<T>>>>,
+}
+
+impl<T: Clone + Send + 'static> Relay<T> {
+    fn start(n: u16) -> Self {
+        let (hub_tx, mut hub_rx) = mpsc::unbounded::<Msg<T>>();
+        let (party_txs, incoming): (Vec<_>, Vec<_>) = (0..n).map(|_| mpsc::unbounded()).unzip();
+        let log = Arc::new(Mutex::new(vec![]));
+        let hub_log = log.clone();
+        tokio::spawn(async move {
+            while let Some(msg) = hub_rx.next().await {
+                hub_log.lock().unwrap().push(msg.clone());
+                for tx in &party_txs {
+                    let _ = tx.unbounded_send(msg.clone());
+                }
+            }
+        });
+        Self {
+            outgoing: (0..n).map(|_| hub_tx.clone()).collect(),
+            incoming,
+            log,
+        }
+    }
+
+    /// Incoming stream and outgoing sink of party `i`
+    fn join(
+        &mut self,
+        i: u16,
+    ) -> (
+        impl Stream<Item = Result<Msg<T>, anyhow::Error>> + Unpin,
+        impl Sink<Msg<T>, Error = anyhow::Error> + Unpin,
+    ) {
+        let incoming = self
+            .incoming
+            .remove(0)
+            .filter(move |msg| future::ready(msg.sender != i))
+            .map(Ok);
+        let outgoing = self.outgoing.remove(0).sink_map_err(anyhow::Error::from);
+        (incoming, outgoing)
+    }
+}
+
+#[test]
+fn sealed_message_is_bound_to_sender_and_receiver() {
+    let keys = channel_keys(3, b"session");
+    let secret = Scalar::<Secp256k1>::random();
+
+    let sealed = keys[0].seal(2, &secret).unwrap();
+    let opened: Scalar<Secp256k1> = keys[1].open(1, &sealed).unwrap();
+    assert_eq!(opened, secret);
+
+    // Third party doesn't share the key
+    assert!(matches!(
+        keys[2].open::<Scalar<Secp256k1>>(1, &sealed),
+        Err(Error::Decrypt { sender: 1 })
+    ));
+    // Message can't be attributed to another sender
+    assert!(matches!(
+        keys[1].open::<Scalar<Secp256k1>>(3, &sealed),
+        Err(Error::Decrypt { sender: 3 })
+    ));
+    // Tampered ciphertext is rejected
+    let mut tampered = sealed.clone();
+    tampered.ciphertext[0] ^= 1;
+    assert!(matches!(
+        keys[1].open::<Scalar<Secp256k1>>(1, &tampered),
+        Err(Error::Decrypt { sender: 1 })
+    ));
+
+    // Nonces never repeat
+    let sealed2 = keys[0].seal(2, &secret).unwrap();
+    assert_ne!(sealed.nonce, sealed2.nonce);
+    assert_ne!(sealed.ciphertext, sealed2.ciphertext);
+}
+
+#[test]
+fn sealed_message_cant_be_replayed() {
+    let keys = channel_keys(2, b"session");
+
+    let first = keys[0].seal(2, &1u32).unwrap();
+    let second = keys[0].seal(2, &2u32).unwrap();
+    assert_eq!(keys[1].open::<u32>(1, &second).unwrap(), 2);
+
+    // Neither the same message, nor an older one can be opened again
+    assert!(matches!(
+        keys[1].open::<u32>(1, &second),
+        Err(Error::Replay { sender: 1 })
+    ));
+    assert!(matches!(
+        keys[1].open::<u32>(1, &first),
+        Err(Error::Replay { sender: 1 })
+    ));
+
+    // Sequence number is authenticated
+    let mut forged = first;
+    forged.nonce[11] ^= 0x10;
+    assert!(matches!(
+        keys[1].open::<u32>(1, &forged),
+        Err(Error::Decrypt { sender: 1 })
+    ));
+}
+
+#[test]
+fn session_id_is_fresh_for_every_handshake() {
+    let run1 = channel_keys(3, b"session");
+    let run2 = channel_keys(3, b"session");
+    assert!(run1.iter().all(|k| k.session_id() == run1[0].session_id()));
+    assert_ne!(run1[0].session_id(), run2[0].session_id());
+}
+
+#[test]
+fn keys_are_bound_to_session() {
+    let ephemeral = [EphemeralKey::generate(), EphemeralKey::generate()];
+    let handshake = Msg {
+        sender: 2,
+        receiver: None,
+        body: ephemeral[1].handshake(b"another session"),
+    };
+    let [e1, _] = ephemeral;
+    assert!(matches!(
+        e1.into_channel_keys(1, 2, b"session", vec![handshake]),
+        Err(Error::SessionMismatch { sender: 2 })
+    ));
+}
+
+#[test]
+fn unencrypted_p2p_message_is_rejected() {
+    let keys = channel_keys(2, b"session");
+    let msg = Msg {
+        sender: 1,
+        receiver: Some(2),
+        body: Envelope::Broadcast(42u32),
+    };
+    assert!(matches!(
+        keys[1].open_msg(msg),
+        Err(Error::UnencryptedP2PMessage { sender: 1 })
+    ));
+
+    let msg = keys[0]
+        .seal_msg(Msg {
+            sender: 1,
+            receiver: Some(2),
+            body: 42u32,
+        })
+        .unwrap();
+    assert_eq!(keys[1].open_msg(msg).unwrap().unwrap().body, 42);
+}
+
+#[test]
+fn p2p_message_addressed_to_another_party_is_skipped() {
+    let keys = channel_keys(3, b"session");
+    let msg = keys[0]
+        .seal_msg(Msg {
+            sender: 1,
+            receiver: Some(2),
+            body: 42u32,
+        })
+        .unwrap();
+    assert!(keys[2].open_msg(msg).unwrap().is_none());
+}
+
+#[tokio::test]
+async fn keygen_over_secure_channels_doesnt_leak_shares() {
+    let (t, n) = (1, 3);
+    let session_id = b"keygen-over-secure-channels";
+
+    let mut relay = Relay::<Handshake>::start(n);
+    let handshakes = (1..=n)
+        .map(|i| {
+            let (incoming, outgoing) = relay.join(i);
+            tokio::spawn(async move {
+                handshake::<_, _, anyhow::Error>(i, n, session_id, incoming, outgoing)
+                    .await
+                    .unwrap()
+            })
+        })
+        .collect::<Vec<_>>();
+    let mut keys = vec![];
+    for h in handshakes {
+        keys.push(Arc::new(h.await.unwrap()));
+    }
+
+    let mut relay = Relay::<Envelope<ProtocolMessage>>::start(n);
+    let parties = (1..=n)
+        .zip(keys)
+        .map(|(i, keys)| {
+            let (incoming, outgoing) = relay.join(i);
+            let incoming = wrap_incoming(keys.clone(), incoming).fuse();
+            let outgoing = wrap_outgoing(keys, outgoing);
+            tokio::spawn(async move {
+                AsyncProtocol::new(Keygen::new(i, t, n).unwrap(), incoming, outgoing)
+                    .run()
+                    .await
+                    .map_err(|e| e.to_string())
+            })
+        })
+        .collect::<Vec<_>>();
+    let mut local_keys = vec![];
+    for p in parties {
+        local_keys.push(p.await.unwrap().unwrap());
+    }
+    assert!(local_keys
+        .iter()
+        .all(|k| k.public_key() == local_keys[0].public_key()));
+
+    let log = relay.log.lock().unwrap();
+    let p2p = log
+        .iter()
+        .filter(|m| m.receiver.is_some())
+        .collect::<Vec<_>>();
+    assert_eq!(p2p.len(), usize::from(n * (n - 1)));
+    assert!(p2p.iter().all(|m| matches!(m.body, Envelope::Sealed(_))));
+}
@@ -0,0 +1,393 @@
+//! Air-gapped transport: messages are carried in bundle files
+//!
+//! A party that is kept offline can't hold a connection, and its peers may be hours apart. Here
+//! every round is exchanged as a set of files in a directory:
+//! * When party `i` enters round `r`, it writes its messages of the round into one bundle per
+//!   receiver: file `round-{r}-from-{i}-to-{j}.bundle` holds broadcast messages and P2P messages
+//!   addressed to party `j`
+//! * Once bundles from every other party addressed to `i` appear in the directory, they are
+//!   handed to the state machine, and the party proceeds to the next round
+//!
+//! How the files get from one directory to another (USB drive, QR codes, ...) is up to the
+//! operator. Note that bundles carry P2P messages in plain text, so a bundle must only be
+//! delivered to its receiver.
+//!
+//! Every bundle is checksummed with SHA-256, so corrupted files are detected. If parties have
+//! [identity keys](crate::identity), bundles are also signed by the sender and verified against
+//! the roster, see [BundleDir::with_identity].
+//!
+//! [step] drives a state machine as far as bundles in the directory allow. Between calls,
+//! [Keygen](crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::Keygen::checkpoint)
+//! and [OfflineStage](crate::protocols::multi_party_ecdsa::gg_2020::state_machine::sign::OfflineStage::checkpoint)
+//! can be saved as encrypted [checkpoints](crate::protocols::multi_party_ecdsa::gg_2020::state_machine::checkpoint),
+//! so the process doesn't need to keep running.
+//!
+//! ## Example
+//! ```no_run
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::{
+//!     checkpoint::{CheckpointKey, SentLogFile},
+//!     keygen::Keygen,
+//! };
+//! use multi_party_ecdsa::transport::bundle::{self, BundleDir, Progress};
+//!
+//! let key = CheckpointKey::from_bytes(*b"checkpoint key kept on the token");
+//! let dir = BundleDir::new("/media/usb/keygen", "keygen-2021-10-01", 1, 3);
+//! let mut keygen: Keygen = match std::fs::read("keygen.checkpoint") {
+//!     Ok(checkpoint) => Keygen::restore(
+//!         &serde_json::from_slice(&checkpoint)?,
+//!         &key,
+//!         SentLogFile::new("keygen.sent"),
+//!     )?,
+//!     Err(_) => {
+//!         let mut keygen: Keygen = Keygen::new(1, 1, 3)?;
+//!         keygen.persist_sent_log(SentLogFile::new("keygen.sent"))?;
+//!         keygen
+//!     }
+//! };
+//! match bundle::step(&mut keygen, &dir)? {
+//!     Progress::Waiting { round } => {
+//!         let checkpoint = keygen.checkpoint(&key)?;
+//!         std::fs::write("keygen.checkpoint", serde_json::to_vec(&checkpoint)?)?;
+//!         println!("bundles of round {} are written, waiting for other parties", round);
+//!     }
+//!     Progress::Finished(local_key) => {
+//!         std::fs::write("local-key.json", serde_json::to_vec(&local_key)?)?;
+//!     }
+//! }
+//! # Ok(()) }
+//! ```
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use round_based::{Msg, StateMachine};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::identity::{IdentityKey, Roster, SignedMessage};
+use crate::protocols::multi_party_ecdsa::gg_2020::party_i::SignatureRecid;
+
+/// Format version of bundle files
+const VERSION: u16 = 1;
+
+/// Directory bundles are exchanged through
+pub struct BundleDir {
+    dir: PathBuf,
+    session_id: String,
+    i: u16,
+    n: u16,
+    identity: Option<(IdentityKey, Roster)>,
+}
+
+/// Bundle file
+#[derive(Serialize, Deserialize)]
+struct BundleFile {
+    version: u16,
+    sender: u16,
+    /// Serialized [Bundle]
+    payload: String,
+    /// Hex-encoded SHA-256 of the payload
+    checksum: String,
+    signature: Option<SignatureRecid>,
+}
+
+/// Messages of a round sent by `sender` to `receiver`, `T` is `Msg<M>`
+#[derive(Serialize, Deserialize)]
+struct Bundle<T> {
+    session_id: String,
+    round: u16,
+    sender: u16,
+    receiver: u16,
+    messages: Vec<T>,
+}
+
+impl BundleDir {
+    /// Constructs bundle directory of party `i` out of `n` parties
+    ///
+    /// Bundles are bound to `session_id`, so bundles of another protocol execution are rejected.
+    pub fn new(dir: impl Into<PathBuf>, session_id: impl Into<String>, i: u16, n: u16) -> Self {
+        Self {
+            dir: dir.into(),
+            session_id: session_id.into(),
+            i,
+            n,
+            identity: None,
+        }
+    }
+
+    /// Signs written bundles with `identity` key, and only accepts bundles signed by the key of
+    /// their sender in the `roster`
+    pub fn with_identity(self, identity: IdentityKey, roster: Roster) -> Self {
+        Self {
+            identity: Some((identity, roster)),
+            ..self
+        }
+    }
+
+    /// Writes outgoing messages of round `round`, one bundle per receiver
+    ///
+    /// Fails with [Error::Equivocation] if a bundle of this round was already written with
+    /// different messages: sending two versions of the same round may leak secrets.
+    pub fn write_round<M: Serialize>(&self, round: u16, messages: &[Msg<M>]) -> Result<()> {
+        if self.i == 0 || self.i > self.n {
+            return Err(Error::InvalidPartyIndex(self.i));
+        }
+        fs::create_dir_all(&self.dir).map_err(|e| self.io_error(&self.dir, e))?;
+        for j in (1..=self.n).filter(|&j| j != self.i) {
+            let messages = messages
+                .iter()
+                .filter(|msg| msg.receiver.is_none() || msg.receiver == Some(j))
+                .collect::<Vec<_>>();
+            let bundle = Bundle {
+                session_id: self.session_id.clone(),
+                round,
+                sender: self.i,
+                receiver: j,
+                messages,
+            };
+            self.write_bundle(&self.path(round, self.i, j), &bundle)?;
+        }
+        Ok(())
+    }
+
+    /// Reads messages of round `round` addressed to this party
+    ///
+    /// Returns `None` if bundles of some parties are not in the directory yet.
+    pub fn read_round<M: DeserializeOwned>(&self, round: u16) -> Result<Option<Vec<Msg<M>>>> {
+        let mut messages = vec![];
+        for j in (1..=self.n).filter(|&j| j != self.i) {
+            let path = self.path(round, j, self.i);
+            let file = match fs::read(&path) {
+                Ok(file) => file,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+                Err(e) => return Err(self.io_error(&path, e)),
+            };
+            let bundle = self.open_bundle::<Msg<M>>(&path, &file, j)?;
+            if bundle.session_id != self.session_id
+                || bundle.round != round
+                || bundle.sender != j
+                || bundle.receiver != self.i
+            {
+                return Err(Error::MisplacedBundle { path });
+            }
+            for msg in &bundle.messages {
+                let misaddressed = msg.receiver.is_some() && msg.receiver != Some(self.i);
+                if msg.sender != j || misaddressed {
+                    return Err(Error::MisplacedBundle { path });
+                }
+            }
+            messages.extend(bundle.messages);
+        }
+        Ok(Some(messages))
+    }
+
+    fn path(&self, round: u16, sender: u16, receiver: u16) -> PathBuf {
+        self.dir.join(format!(
+            "round-{}-from-{}-to-{}.bundle",
+            round, sender, receiver
+        ))
+    }
+
+    fn write_bundle<M: Serialize>(&self, path: &Path, bundle: &Bundle<&Msg<M>>) -> Result<()> {
+        let (payload, signature) = match &self.identity {
+            Some((identity, _)) => {
+                let msg = Msg {
+                    sender: self.i,
+                    receiver: None,
+                    body: bundle,
+                };
+                let signed = identity
+                    .sign_msg(self.session_id.as_bytes(), msg)
+                    .map_err(Error::Sign)?;
+                (signed.body.payload, Some(signed.body.signature))
+            }
+            None => (
+                serde_json::to_string(bundle).map_err(Error::Serialize)?,
+                None,
+            ),
+        };
+
+        match fs::read(path) {
+            Ok(existing) => {
+                let existing = serde_json::from_slice::<BundleFile>(&existing)
+                    .map_err(|_| Error::Corrupted { path: path.into() })?;
+                if existing.payload != payload {
+                    return Err(Error::Equivocation { path: path.into() });
+                }
+                return Ok(());
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => (),
+            Err(e) => return Err(self.io_error(path, e)),
+        }
+
+        let file = BundleFile {
+            version: VERSION,
+            sender: self.i,
+            checksum: hex::encode(Sha256::digest(payload.as_bytes())),
+            payload,
+            signature,
+        };
+        let file = serde_json::to_vec(&file).map_err(Error::Serialize)?;
+
+        // Write to a temporary file first, so a reader never observes half-written bundle
+        let tmp = path.with_extension("tmp");
+        let write = || -> io::Result<()> {
+            let mut f = fs::File::create(&tmp)?;
+            f.write_all(&file)?;
+            f.sync_all()?;
+            fs::rename(&tmp, path)
+        };
+        write().map_err(|e| self.io_error(path, e))
+    }
+
+    fn open_bundle<T: DeserializeOwned>(
+        &self,
+        path: &Path,
+        file: &[u8],
+        sender: u16,
+    ) -> Result<Bundle<T>> {
+        let corrupted = || Error::Corrupted { path: path.into() };
+        let file = serde_json::from_slice::<BundleFile>(file).map_err(|_| corrupted())?;
+        if file.version != VERSION {
+            return Err(Error::UnsupportedVersion {
+                path: path.into(),
+                version: file.version,
+            });
+        }
+        if hex::encode(Sha256::digest(file.payload.as_bytes())) != file.checksum {
+            return Err(corrupted());
+        }
+        if file.sender != sender {
+            return Err(Error::MisplacedBundle { path: path.into() });
+        }
+
+        match (&self.identity, file.signature) {
+            (Some((_, roster)), Some(signature)) => {
+                let msg = Msg {
+                    sender,
+                    receiver: None,
+                    body: SignedMessage {
+                        payload: file.payload,
+                        signature,
+                    },
+                };
+                roster
+                    .verify_msg(self.session_id.as_bytes(), msg)
+                    .map(|msg| msg.body)
+                    .map_err(|_| Error::InvalidSignature { path: path.into() })
+            }
+            (Some(_), None) => Err(Error::InvalidSignature { path: path.into() }),
+            (None, _) => serde_json::from_str(&file.payload).map_err(|_| corrupted()),
+        }
+    }
+
+    fn io_error(&self, path: &Path, source: io::Error) -> Error {
+        Error::Io {
+            path: path.into(),
+            source,
+        }
+    }
+}
+
+/// Outcome of [step]
+#[derive(Debug)]
+pub enum Progress<O> {
+    /// Bundles of round `round` are written, protocol waits for bundles of other parties
+    Waiting { round: u16 },
+    /// Protocol is completed
+    Finished(O),
+}
+
+/// Carries out the protocol as far as bundles in the directory allow
+///
+/// Writes bundles of every round the party enters, and feeds the state machine with bundles
+/// of other parties once all of them are there. Returns [Progress::Waiting] when bundles of
+/// some party are missing: state machine is then between rounds, and can be checkpointed.
+pub fn step<SM>(
+    state: &mut SM,
+    dir: &BundleDir,
+) -> std::result::Result<Progress<SM::Output>, StepError<SM::Err>>
+where
+    SM: StateMachine,
+    SM::MessageBody: Serialize + DeserializeOwned,
+    SM::Err: std::error::Error + 'static,
+{
+    loop {
+        if state.wants_to_proceed() {
+            state.proceed().map_err(StepError::Protocol)?;
+        }
+        let round = state.current_round();
+        let outgoing = std::mem::take(state.message_queue());
+        if !outgoing.is_empty() {
+            dir.write_round(round, &outgoing)?;
+        }
+
+        if state.is_finished() {
+            return match state.pick_output() {
+                Some(output) => output.map(Progress::Finished).map_err(StepError::Protocol),
+                None => Err(StepError::Transport(Error::NoOutput)),
+            };
+        }
+        if state.wants_to_proceed() {
+            continue;
+        }
+
+        let messages = match dir.read_round(round)? {
+            Some(messages) => messages,
+            None => return Ok(Progress::Waiting { round }),
+        };
+        for msg in messages {
+            state.handle_incoming(msg).map_err(StepError::Protocol)?;
+        }
+        if state.current_round() == round && !state.wants_to_proceed() && !state.is_finished() {
+            return Err(StepError::Transport(Error::RoundIncomplete { round }));
+        }
+    }
+}
+
+type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Error of bundle directory
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Error {
+    #[error("party index {0} is out of range")]
+    InvalidPartyIndex(u16),
+    #[error("i/o error at {path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("serialize bundle: {0}")]
+    Serialize(#[source] serde_json::Error),
+    #[error("sign bundle: {0}")]
+    Sign(#[source] crate::identity::Error),
+    #[error("bundle {path} is corrupted")]
+    Corrupted { path: PathBuf },
+    #[error("bundle {path} has unsupported version {version}")]
+    UnsupportedVersion { path: PathBuf, version: u16 },
+    #[error("bundle {path} has invalid signature")]
+    InvalidSignature { path: PathBuf },
+    #[error("bundle {path} belongs to another session, round, sender or receiver")]
+    MisplacedBundle { path: PathBuf },
+    #[error("bundle {path} was already written with different messages")]
+    Equivocation { path: PathBuf },
+    #[error("bundles of round {round} are received, but the protocol didn't proceed")]
+    RoundIncomplete { round: u16 },
+    #[error("protocol is finished, but didn't return output")]
+    NoOutput,
+}
+
+/// Error of [step]
+#[derive(Debug, Error)]
+pub enum StepError<E: std::error::Error + 'static> {
+    #[error("bundle: {0}")]
+    Transport(#[from] Error),
+    #[error("protocol: {0}")]
+    Protocol(#[source] E),
+}
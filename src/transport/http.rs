@@ -0,0 +1,329 @@
+//! Client of HTTP/SSE rooms
+//!
+//! Parties of a protocol join a room on a relay server (e.g. [relay](crate::relay) or
+//! `gg20_sm_manager` example):
+//! * `POST rooms/{room}/issue_unique_idx` returns `{"unique_idx": N}`, a unique number in the room
+//! * `POST rooms/{room}/broadcast` takes a message and forwards it to every subscriber
+//! * `GET rooms/{room}/subscribe` is an SSE stream of messages sent to the room
+//!
+//! Every message is delivered to every party, P2P messages addressed to someone else are
+//! filtered out on the client side. So relay sees all the messages — use
+//! [secure channels](crate::secure_channel) to protect P2P messages.
+//!
+//! Client survives connection drops: subscription reconnects with exponential [Backoff] and
+//! resumes from the last received event by sending its id in `Last-Event-ID` header, events
+//! received twice are skipped. Broadcasts are retried carrying the same `Idempotency-Key`
+//! header, so relay stores the message once even if it's been delivered several times.
+
+use std::convert::TryFrom;
+use std::time::Duration;
+
+use futures::stream::BoxStream;
+use futures::{future, sink, stream, Stream, StreamExt, TryStreamExt};
+use round_based::Msg;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use super::{Error, Incoming, Outgoing, Result, Transport};
+
+/// Header carrying a unique id of broadcast message
+pub const IDEMPOTENCY_KEY: &str = "Idempotency-Key";
+
+/// Retry policy
+///
+/// Delay before `k`-th retry is `initial_delay * 2^(k-1)`, but not more than `max_delay`.
+#[derive(Debug, Clone, Copy)]
+pub struct Backoff {
+    /// Delay before the first retry
+    pub initial_delay: Duration,
+    /// Upper bound of the delay
+    pub max_delay: Duration,
+    /// Number of retries after which client gives up, `None` means retry forever
+    pub max_retries: Option<u32>,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(10),
+            max_retries: Some(20),
+        }
+    }
+}
+
+impl Backoff {
+    /// Waits before `retry`-th retry, returns `error` if retries are exhausted
+    async fn wait(&self, retry: u32, error: Error) -> Result<()> {
+        if self.max_retries.map(|max| retry > max).unwrap_or(false) {
+            return Err(error);
+        }
+        let delay = 2u32
+            .checked_pow(retry.saturating_sub(1))
+            .and_then(|factor| self.initial_delay.checked_mul(factor))
+            .map(|delay| delay.min(self.max_delay))
+            .unwrap_or(self.max_delay);
+        tokio::time::sleep(delay).await;
+        Ok(())
+    }
+}
+
+/// Client of a single room
+#[derive(Clone)]
+pub struct RoomClient {
+    http_client: surf::Client,
+    token: Option<String>,
+    parties: Option<u16>,
+    backoff: Backoff,
+}
+
+impl RoomClient {
+    /// Constructs a client of room `room_id` at relay server `address`
+    pub fn new(address: surf::Url, room_id: &str) -> Result<Self> {
+        let base_url = address
+            .join(&format!("rooms/{}/", room_id))
+            .map_err(|e| Error::Http(e.to_string()))?;
+        let config = surf::Config::new().set_base_url(base_url).set_timeout(None);
+        Ok(Self {
+            http_client: surf::Client::try_from(config).map_err(|e| Error::Http(e.to_string()))?,
+            token: None,
+            parties: None,
+            backoff: Backoff::default(),
+        })
+    }
+
+    /// Sends `token` in `Authorization: Bearer` header of every request
+    pub fn with_bearer_token(self, token: impl Into<String>) -> Self {
+        Self {
+            token: Some(token.into()),
+            ..self
+        }
+    }
+
+    /// Declares that `n` parties are going to join the room
+    ///
+    /// Relay limits the room to `n` parties, and rejects requests if room was created for
+    /// different number of parties.
+    pub fn with_parties(self, n: u16) -> Self {
+        Self {
+            parties: Some(n),
+            ..self
+        }
+    }
+
+    /// Sets retry policy of broadcasts and subscriptions
+    pub fn with_backoff(self, backoff: Backoff) -> Self {
+        Self { backoff, ..self }
+    }
+
+    fn request(&self, method: surf::http::Method, endpoint: &str) -> surf::RequestBuilder {
+        let endpoint = match self.parties {
+            Some(n) => format!("{}?parties={}", endpoint, n),
+            None => endpoint.to_owned(),
+        };
+        let request = self.http_client.request(method, endpoint);
+        match &self.token {
+            Some(token) => request.header("Authorization", format!("Bearer {}", token)),
+            None => request,
+        }
+    }
+
+    /// Sends a request built by `build`, retries on connection errors and `5xx` responses
+    async fn send(
+        &self,
+        what: &str,
+        build: impl Fn() -> surf::RequestBuilder,
+    ) -> Result<surf::Response> {
+        let mut retry = 0;
+        loop {
+            let error = match build().await {
+                Ok(response) if response.status().is_success() => return Ok(response),
+                Ok(response) if !response.status().is_server_error() => {
+                    return Err(Error::Http(format!(
+                        "{}: server responded {}",
+                        what,
+                        response.status()
+                    )))
+                }
+                Ok(response) => format!("{}: server responded {}", what, response.status()),
+                Err(e) => format!("{}: {}", what, e),
+            };
+            retry += 1;
+            self.backoff.wait(retry, Error::Http(error)).await?;
+        }
+    }
+
+    /// Obtains a number that's unique within the room
+    ///
+    /// Unlike other requests, it's not retried: a retry might issue another number.
+    pub async fn issue_index(&self) -> Result<u16> {
+        let response = self
+            .request(surf::http::Method::Post, "issue_unique_idx")
+            .recv_json::<IssuedUniqueIdx>()
+            .await
+            .map_err(http_error)?;
+        Ok(response.unique_idx)
+    }
+
+    /// Sends `message` to every subscriber of the room
+    pub async fn broadcast(&self, message: &str) -> Result<()> {
+        let key = hex::encode(rand::random::<[u8; 16]>());
+        self.send("broadcast", || {
+            self.request(surf::http::Method::Post, "broadcast")
+                .header(IDEMPOTENCY_KEY, key.as_str())
+                .body(message)
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Subscribes to messages sent to the room
+    ///
+    /// Lost connection is re-established, stream yields an error only when retries are
+    /// exhausted.
+    pub async fn subscribe(&self) -> Result<impl Stream<Item = Result<String>> + Send> {
+        let subscription = Subscription {
+            events: self.connect(None).await?,
+            client: self.clone(),
+            last_event_id: None,
+            retry: 0,
+        };
+        Ok(stream::unfold(Some(subscription), |subscription| async {
+            let mut subscription = subscription?;
+            match subscription.next().await {
+                Ok(msg) => Some((Ok(msg), Some(subscription))),
+                Err(e) => Some((Err(e), None)),
+            }
+        }))
+    }
+
+    async fn connect(&self, last_event_id: Option<u64>) -> Result<Events> {
+        let response = self
+            .send("subscribe", || {
+                let request = self.request(surf::http::Method::Get, "subscribe");
+                match last_event_id {
+                    Some(id) => request.header("Last-Event-ID", id.to_string()),
+                    None => request,
+                }
+            })
+            .await?;
+        Ok(async_sse::decode(response).boxed())
+    }
+}
+
+type Events = BoxStream<'static, std::result::Result<async_sse::Event, surf::Error>>;
+
+struct Subscription {
+    client: RoomClient,
+    events: Events,
+    last_event_id: Option<u64>,
+    /// Number of reconnections since the last received message
+    retry: u32,
+}
+
+impl Subscription {
+    async fn next(&mut self) -> Result<String> {
+        loop {
+            let error = match self.events.next().await {
+                Some(Ok(async_sse::Event::Message(msg))) => {
+                    let id = msg.id().as_ref().and_then(|id| id.parse::<u64>().ok());
+                    if let (Some(id), Some(last_id)) = (id, self.last_event_id) {
+                        if id <= last_id {
+                            // Already received before reconnection
+                            continue;
+                        }
+                    }
+                    self.last_event_id = id.or(self.last_event_id);
+                    self.retry = 0;
+                    return String::from_utf8(msg.into_bytes())
+                        .map_err(|_| Error::Http("SSE message is not valid UTF-8 string".into()));
+                }
+                // Ignore other types of events
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => http_error(e),
+                None => Error::Http("subscription is closed by server".into()),
+            };
+            self.retry += 1;
+            self.client.backoff.wait(self.retry, error).await?;
+            self.events = self.client.connect(self.last_event_id).await?;
+        }
+    }
+}
+
+/// Party connected to a room, see [join]
+pub struct Http {
+    i: u16,
+    client: RoomClient,
+    subscription: futures::stream::BoxStream<'static, Result<String>>,
+}
+
+/// Joins room `room_id` at relay server `address`
+///
+/// Party index is issued by the server: parties are indexed in order they joined the room.
+pub async fn join(address: surf::Url, room_id: &str) -> Result<Http> {
+    join_with(RoomClient::new(address, room_id)?).await
+}
+
+/// Joins a room using given client, e.g. one carrying a bearer token
+pub async fn join_with(client: RoomClient) -> Result<Http> {
+    // Subscribe before obtaining index, so we don't miss messages sent by parties that joined
+    // the room earlier
+    let subscription = client.subscribe().await?.boxed();
+    let i = client.issue_index().await?;
+    Ok(Http {
+        i,
+        client,
+        subscription,
+    })
+}
+
+/// Joins a room as party `i` instead of obtaining an index from the server
+///
+/// Use it to keep the same party index in all rooms of a computation. Server doesn't check that
+/// nobody else uses index `i`.
+pub async fn join_as(client: RoomClient, i: u16) -> Result<Http> {
+    let subscription = client.subscribe().await?.boxed();
+    Ok(Http {
+        i,
+        client,
+        subscription,
+    })
+}
+
+impl<M> Transport<M> for Http
+where
+    M: Serialize + DeserializeOwned + Send + 'static,
+{
+    fn party_index(&self) -> u16 {
+        self.i
+    }
+
+    fn split(self) -> (Incoming<M>, Outgoing<M>) {
+        let i = self.i;
+        let incoming = self
+            .subscription
+            .and_then(|msg| {
+                future::ready(serde_json::from_str::<Msg<M>>(&msg).map_err(Error::Deserialize))
+            })
+            .try_filter(move |msg| {
+                future::ready(msg.sender != i && msg.receiver.map(|r| r == i).unwrap_or(true))
+            })
+            .boxed();
+
+        let outgoing = sink::unfold(self.client, |client, msg: Msg<M>| async move {
+            let serialized = serde_json::to_string(&msg).map_err(Error::Serialize)?;
+            client.broadcast(&serialized).await?;
+            Ok(client)
+        });
+        (incoming, Box::pin(outgoing))
+    }
+}
+
+fn http_error(e: surf::Error) -> Error {
+    Error::Http(e.to_string())
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct IssuedUniqueIdx {
+    unique_idx: u16,
+}
@@ -0,0 +1,66 @@
+//! In-process transport
+
+use futures::channel::mpsc;
+use futures::{future, sink, StreamExt};
+use round_based::Msg;
+
+use super::{Error, Incoming, Outgoing, Transport};
+
+/// Connection of a party to in-process network, see [network]
+pub struct Memory<M> {
+    i: u16,
+    incoming: mpsc::UnboundedReceiver<Msg<M>>,
+    parties: Vec<mpsc::UnboundedSender<Msg<M>>>,
+}
+
+/// Constructs network of `n` parties connected by in-process channels
+///
+/// `i`-th element of returned vector is the connection of party `i+1`.
+pub fn network<M>(n: u16) -> Vec<Memory<M>> {
+    let (parties, incoming): (Vec<_>, Vec<_>) = (0..n).map(|_| mpsc::unbounded()).unzip();
+    (1..)
+        .zip(incoming)
+        .map(|(i, incoming)| Memory {
+            i,
+            incoming,
+            parties: parties.clone(),
+        })
+        .collect()
+}
+
+impl<M> Transport<M> for Memory<M>
+where
+    M: Clone + Send + 'static,
+{
+    fn party_index(&self) -> u16 {
+        self.i
+    }
+
+    fn split(self) -> (Incoming<M>, Outgoing<M>) {
+        let incoming = self.incoming.map(Ok).boxed();
+        let outgoing = sink::unfold(self.parties, |parties, msg: Msg<M>| {
+            let result = deliver(&parties, msg);
+            future::ready(result.map(|()| parties))
+        });
+        (incoming, Box::pin(outgoing))
+    }
+}
+
+fn deliver<M: Clone>(parties: &[mpsc::UnboundedSender<Msg<M>>], msg: Msg<M>) -> Result<(), Error> {
+    let send = |j: u16, msg: Msg<M>| {
+        parties[usize::from(j - 1)]
+            .unbounded_send(msg)
+            .map_err(|_| Error::ConnectionClosed { party: j })
+    };
+    match msg.receiver {
+        Some(j) if j == 0 || usize::from(j) > parties.len() => {
+            Err(Error::UnknownParty { party: j })
+        }
+        Some(j) => send(j, msg),
+        None => (1..)
+            .zip(parties)
+            .map(|(j, _)| j)
+            .filter(|&j| j != msg.sender)
+            .try_for_each(|j| send(j, msg.clone())),
+    }
+}
@@ -0,0 +1,131 @@
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+//! # Networking for `round_based` protocols
+//!
+//! State machines of this crate are transport-agnostic: `AsyncProtocol` executes them over any
+//! stream of incoming messages and sink of outgoing messages. This module provides ready-to-use
+//! transports, all implementing [Transport]:
+//! * [memory] — in-process channels, for tests and for running several parties in one process
+//! * [tcp] — full mesh of TCP connections between parties, messages are sent in length-prefixed
+//!   frames
+//! * [http] (requires `transport-http` feature) — client of HTTP/SSE rooms served by
+//!   `gg20_sm_manager` example
+//!
+//! [mux] runs many protocol executions concurrently over any of them. Parties that are kept
+//! offline exchange [bundle] files instead.
+//!
+//! Transports don't authenticate parties, use [identity](crate::identity) and
+//! [secure channels](crate::secure_channel) on top of them.
+//!
+//! ## Example
+//! ```no_run
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! use futures::StreamExt;
+//! use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::Keygen;
+//! use multi_party_ecdsa::transport::{tcp, Transport};
+//! use round_based::AsyncProtocol;
+//!
+//! let addresses = vec![
+//!     "10.0.0.1:7001".parse()?,
+//!     "10.0.0.2:7001".parse()?,
+//!     "10.0.0.3:7001".parse()?,
+//! ];
+//! let listener = tokio::net::TcpListener::bind("0.0.0.0:7001").await?;
+//! let transport = tcp::connect(1, listener, &addresses).await?;
+//!
+//! let i = Transport::<()>::party_index(&transport);
+//! let (incoming, outgoing) = transport.split();
+//! let local_key = AsyncProtocol::new(Keygen::new(i, 1, 3)?, incoming.fuse(), outgoing)
+//!     .run()
+//!     .await?;
+//! # Ok(()) }
+//! ```
+
+use std::pin::Pin;
+
+use futures::stream::BoxStream;
+use futures::Sink;
+use round_based::Msg;
+use thiserror::Error;
+
+pub mod bundle;
+pub mod memory;
+pub mod mux;
+pub mod tcp;
+
+#[cfg(feature = "transport-http")]
+pub mod http;
+
+#[cfg(test)]
+mod test;
+
+/// Stream of incoming messages
+pub type Incoming<M> = BoxStream<'static, Result<Msg<M>>>;
+/// Sink of outgoing messages
+pub type Outgoing<M> = Pin<Box<dyn Sink<Msg<M>, Error = Error> + Send>>;
+
+/// Connection of a party to other parties of the protocol
+///
+/// Incoming stream only yields messages addressed to this party (broadcast messages and P2P
+/// messages with `receiver` set to [party_index](Self::party_index)), and never yields messages
+/// sent by this party.
+pub trait Transport<M> {
+    /// Index of this party in the protocol
+    fn party_index(&self) -> u16;
+    /// Splits connection into stream of incoming messages and sink of outgoing messages
+    fn split(self) -> (Incoming<M>, Outgoing<M>);
+}
+
+type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Transport error
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Error {
+    #[error("i/o error: {0}")]
+    Io(#[source] std::io::Error),
+    #[error("serialize message: {0}")]
+    Serialize(#[source] serde_json::Error),
+    #[error("deserialize message: {0}")]
+    Deserialize(#[source] serde_json::Error),
+    #[error("party index {0} is out of range")]
+    InvalidPartyIndex(u16),
+    #[error("party {party} is not connected")]
+    UnknownParty { party: u16 },
+    #[error("party {party} connected twice")]
+    DuplicatedConnection { party: u16 },
+    #[error("connection to party {party} is closed")]
+    ConnectionClosed { party: u16 },
+    #[error("frame of {len} bytes exceeds the limit")]
+    FrameTooLarge { len: usize },
+    #[error("party {peer} sent a message on behalf of party {sender}")]
+    ForgedSender { peer: u16, sender: u16 },
+    #[error("party {sender} sent a message addressed to party {receiver}")]
+    MisaddressedMessage { sender: u16, receiver: u16 },
+    #[error("http: {0}")]
+    Http(String),
+    #[error("session {0} is already open")]
+    SessionExists(String),
+    #[error("session {session} is cancelled by party {party}")]
+    SessionCancelled { session: String, party: u16 },
+    #[error("session {session} received more messages than it can buffer")]
+    SessionOverflow { session: String },
+    #[error("multiplexer is closed")]
+    MuxClosed,
+    #[error("identity: {0}")]
+    Identity(#[source] crate::identity::Error),
+}
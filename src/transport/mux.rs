@@ -0,0 +1,504 @@
+//! Many protocol executions over one connection
+//!
+//! Running every protocol instance in its own room (or over its own set of TCP connections) gets
+//! expensive once parties sign hundreds of messages in parallel. [Mux] runs any number of
+//! sessions over a single [Transport]: every message is wrapped into a [Frame] tagged with
+//! session id, and is routed to the session with that id on the receiving side. Every
+//! [Session] is a [Transport] itself, so `Keygen`, `OfflineStage` or any other state machine is
+//! executed over it as usual, and sessions may run different protocols at the same time.
+//!
+//! Peers may open a session in any order: messages of a session that isn't opened locally yet
+//! are buffered until it is. Each session has its own bounded buffer. A session that doesn't
+//! keep up with its messages is aborted with [Error::SessionOverflow] rather than stalling other
+//! sessions. At most [Config::max_pending_sessions] sessions may be waiting to be opened locally,
+//! messages of further sessions are dropped, so a peer flooding us with sessions nobody opens
+//! doesn't bring the connection down. Outgoing messages of all sessions share a bounded queue,
+//! so sessions are suspended while the connection is busy.
+//!
+//! A session is closed once its incoming stream is dropped. [Mux::cancel] aborts a session at
+//! all parties: their incoming streams yield [Error::SessionCancelled].
+//!
+//! Session ids must be unique for the lifetime of a mux, and all parties must agree on them
+//! (e.g. derive them from the message being signed). Sessions can share the same
+//! [ChannelKeys](crate::secure_channel::ChannelKeys) to encrypt P2P messages.
+//!
+//! ## Authentication
+//! Mux constructed by [new] doesn't authenticate anything, it's as trustworthy as the
+//! underlying transport: whoever can write to it may inject messages into any session, open
+//! sessions or cancel them on behalf of any party. [new_authenticated] signs every outgoing frame
+//! (messages and cancellations alike) with party's [identity key](crate::identity), and drops
+//! incoming frames that aren't signed by their sender according to the roster. Signature binds
+//! frame to the mux `context`, to its sender and receiver, and to the session, so frames can't be
+//! moved to another mux or session. A frame replayed within the same session reaches the state
+//! machine twice, and is rejected by it as a duplicate.
+//!
+//! ## Example
+//! ```no_run
+//! # async fn run(local_key: multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey<curv::elliptic::curves::Secp256k1>, transport: multi_party_ecdsa::transport::tcp::Tcp) -> Result<(), Box<dyn std::error::Error>> {
+//! use futures::StreamExt;
+//! use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::sign::OfflineStage;
+//! use multi_party_ecdsa::transport::mux::{self, Config};
+//! use multi_party_ecdsa::transport::Transport;
+//! use round_based::AsyncProtocol;
+//!
+//! let (mux, driver) = mux::new(transport, Config::default());
+//! tokio::spawn(driver);
+//!
+//! // Carry out a hundred offline stages in parallel over the same connection
+//! let i = mux.party_index();
+//! let stages = (0..100).map(|k| {
+//!     let session = mux.session(format!("offline-{}", k));
+//!     let local_key = local_key.clone();
+//!     async move {
+//!         let (incoming, outgoing) = session?.split();
+//!         let offline_stage = OfflineStage::new(i, vec![1, 2], local_key)?;
+//!         let completed = AsyncProtocol::new(offline_stage, incoming.fuse(), outgoing)
+//!             .run()
+//!             .await?;
+//!         Ok::<_, Box<dyn std::error::Error>>(completed)
+//!     }
+//! });
+//! let completed = futures::future::try_join_all(stages).await?;
+//! # Ok(()) }
+//! ```
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+
+use futures::channel::mpsc;
+use futures::{future, SinkExt, Stream, StreamExt};
+use round_based::Msg;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use super::{Error, Incoming, Outgoing, Result, Transport};
+use crate::identity::{IdentityKey, Roster, SignedMessage};
+
+/// Message of underlying transport
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Frame {
+    session: String,
+    body: FrameBody,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum FrameBody {
+    Message(serde_json::Value),
+    Cancel,
+}
+
+/// Limits of [Mux]
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// How many received messages a session may hold before it's aborted
+    pub session_buffer: usize,
+    /// How many sessions opened by peers, but not opened locally, may be waited for
+    pub max_pending_sessions: usize,
+    /// Capacity of outgoing queue shared by all sessions
+    pub outgoing_buffer: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            session_buffer: 1000,
+            max_pending_sessions: 1000,
+            outgoing_buffer: 100,
+        }
+    }
+}
+
+/// Multiplexer of sessions, see [module level documentation](self)
+#[derive(Clone)]
+pub struct Mux {
+    i: u16,
+    registry: Arc<Registry>,
+    outgoing: mpsc::Sender<Msg<Frame>>,
+}
+
+/// Wraps `transport` into multiplexer
+///
+/// Returns the multiplexer and a future that drives it: it must be polled (e.g. spawned) for
+/// sessions to make progress. The future resolves once every handle to the multiplexer and its
+/// sessions is dropped, or with an error if the underlying transport fails. Open sessions yield
+/// [Error::MuxClosed] after that.
+pub fn new<T>(transport: T, config: Config) -> (Mux, impl Future<Output = Result<()>> + Send)
+where
+    T: Transport<Frame>,
+{
+    let i = transport.party_index();
+    let (incoming, outgoing) = transport.split();
+    drive(i, incoming, outgoing, config)
+}
+
+/// Wraps `transport` into multiplexer that authenticates frames with identity keys
+///
+/// Outgoing frames are signed by `identity`, which must be the key of this party in `roster`.
+/// Incoming frames whose signature doesn't match the roster key of the sender are dropped.
+/// `context` must be the same at all parties, and unique for every connection (e.g. room id
+/// along with the date), so frames can't be replayed across connections.
+///
+/// Otherwise the same as [new].
+pub fn new_authenticated<T>(
+    transport: T,
+    identity: Arc<IdentityKey>,
+    roster: Arc<Roster>,
+    context: Vec<u8>,
+    config: Config,
+) -> (Mux, impl Future<Output = Result<()>> + Send)
+where
+    T: Transport<SignedMessage>,
+{
+    let i = transport.party_index();
+    let (incoming, outgoing) = transport.split();
+    let verify_context = context.clone();
+    let incoming = incoming
+        .filter_map(move |msg| {
+            // Forged frame is dropped, so it can't bring the connection down
+            let msg = match msg {
+                Ok(msg) => roster.verify_msg(&verify_context, msg).ok().map(Ok),
+                Err(e) => Some(Err(e)),
+            };
+            future::ready(msg)
+        })
+        .boxed();
+    let outgoing = outgoing.with(move |msg: Msg<Frame>| {
+        future::ready(identity.sign_msg(&context, msg).map_err(Error::Identity))
+    });
+    drive(i, incoming, Box::pin(outgoing), config)
+}
+
+fn drive(
+    i: u16,
+    incoming: Incoming<Frame>,
+    outgoing: Outgoing<Frame>,
+    config: Config,
+) -> (Mux, impl Future<Output = Result<()>> + Send) {
+    let (outgoing_tx, outgoing_rx) = mpsc::channel(config.outgoing_buffer);
+    let registry = Arc::new(Registry {
+        config,
+        state: Mutex::new(State::default()),
+    });
+
+    let mux = Mux {
+        i,
+        registry: registry.clone(),
+        outgoing: outgoing_tx,
+    };
+    let driver = async move {
+        let send = outgoing_rx.map(Ok).forward(outgoing);
+        let receive = receive(&registry, incoming);
+        futures::pin_mut!(send, receive);
+        let result = future::select(send, receive).await.factor_first().0;
+        registry.shut_down();
+        result
+    };
+    (mux, driver)
+}
+
+async fn receive(registry: &Registry, mut incoming: Incoming<Frame>) -> Result<()> {
+    while let Some(msg) = incoming.next().await {
+        let Msg {
+            sender,
+            receiver,
+            body: Frame { session, body },
+        } = msg?;
+        let msg = match body {
+            FrameBody::Message(body) => Ok(Msg {
+                sender,
+                receiver,
+                body,
+            }),
+            FrameBody::Cancel => Err(Error::SessionCancelled {
+                session: session.clone(),
+                party: sender,
+            }),
+        };
+        registry.deliver(&session, msg);
+    }
+    Ok(())
+}
+
+impl Mux {
+    /// Index of this party
+    pub fn party_index(&self) -> u16 {
+        self.i
+    }
+
+    /// Opens a session with given id
+    ///
+    /// Returns [Error::SessionExists] if session with this id is already open or was recently
+    /// closed, and [Error::MuxClosed] if the driver is finished.
+    pub fn session<M>(&self, id: impl Into<String>) -> Result<Session<M>> {
+        let id = id.into();
+        let queue = self.registry.open(&id)?;
+        Ok(Session {
+            i: self.i,
+            registration: Registration {
+                id,
+                queue,
+                registry: self.registry.clone(),
+            },
+            outgoing: self.outgoing.clone(),
+            _message: PhantomData,
+        })
+    }
+
+    /// Aborts session at every party
+    ///
+    /// Incoming stream of this session yields [Error::SessionCancelled] locally and at other
+    /// parties. Sessions that aren't opened yet fail as soon as they're opened.
+    pub async fn cancel(&self, id: &str) -> Result<()> {
+        self.registry.deliver(
+            id,
+            Err(Error::SessionCancelled {
+                session: id.to_owned(),
+                party: self.i,
+            }),
+        );
+        self.outgoing
+            .clone()
+            .send(Msg {
+                sender: self.i,
+                receiver: None,
+                body: Frame {
+                    session: id.to_owned(),
+                    body: FrameBody::Cancel,
+                },
+            })
+            .await
+            .map_err(|_| Error::MuxClosed)
+    }
+}
+
+/// Session of [Mux]
+///
+/// Implements [Transport], split it to obtain incoming stream and outgoing sink of the session.
+pub struct Session<M> {
+    i: u16,
+    registration: Registration,
+    outgoing: mpsc::Sender<Msg<Frame>>,
+    _message: PhantomData<fn(M) -> M>,
+}
+
+impl<M> Session<M> {
+    /// Id of the session
+    pub fn id(&self) -> &str {
+        &self.registration.id
+    }
+}
+
+impl<M> Transport<M> for Session<M>
+where
+    M: Serialize + DeserializeOwned + Send + 'static,
+{
+    fn party_index(&self) -> u16 {
+        self.i
+    }
+
+    fn split(self) -> (Incoming<M>, Outgoing<M>) {
+        let session = self.registration.id.clone();
+        let incoming = self
+            .registration
+            .map(|msg| {
+                let msg = msg?;
+                Ok(Msg {
+                    sender: msg.sender,
+                    receiver: msg.receiver,
+                    body: serde_json::from_value(msg.body).map_err(Error::Deserialize)?,
+                })
+            })
+            .boxed();
+        let outgoing = self
+            .outgoing
+            .sink_map_err(|_| Error::MuxClosed)
+            .with(move |msg: Msg<M>| {
+                let (sender, receiver) = (msg.sender, msg.receiver);
+                let body = serde_json::to_value(msg.body).map(|body| Msg {
+                    sender,
+                    receiver,
+                    body: Frame {
+                        session: session.clone(),
+                        body: FrameBody::Message(body),
+                    },
+                });
+                future::ready(body.map_err(Error::Serialize))
+            });
+        (incoming, Box::pin(outgoing))
+    }
+}
+
+struct Registry {
+    config: Config,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    sessions: HashMap<String, Slot>,
+    /// Number of slots that aren't opened locally
+    pending: usize,
+    /// Recently closed sessions, messages addressed to them are ignored
+    closed: HashSet<String>,
+    closed_order: VecDeque<String>,
+    shut_down: bool,
+}
+
+struct Slot {
+    opened: bool,
+    queue: Arc<Mutex<Queue>>,
+}
+
+#[derive(Default)]
+struct Queue {
+    messages: VecDeque<Result<Msg<serde_json::Value>>>,
+    closed: bool,
+    waker: Option<Waker>,
+}
+
+impl Registry {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().expect("mux state is poisoned")
+    }
+
+    fn open(&self, id: &str) -> Result<Arc<Mutex<Queue>>> {
+        let mut state = self.state();
+        if state.shut_down {
+            return Err(Error::MuxClosed);
+        }
+        if state.closed.contains(id) {
+            return Err(Error::SessionExists(id.to_owned()));
+        }
+        match state.sessions.get_mut(id) {
+            Some(slot) if slot.opened => Err(Error::SessionExists(id.to_owned())),
+            Some(slot) => {
+                slot.opened = true;
+                let queue = slot.queue.clone();
+                state.pending -= 1;
+                Ok(queue)
+            }
+            None => {
+                let queue = Arc::new(Mutex::new(Queue::default()));
+                let slot = Slot {
+                    opened: true,
+                    queue: queue.clone(),
+                };
+                state.sessions.insert(id.to_owned(), slot);
+                Ok(queue)
+            }
+        }
+    }
+
+    /// Routes message to the session, creates a pending session if it's not open yet
+    ///
+    /// Message is dropped if session is closed, or if it's not open and there are already
+    /// [Config::max_pending_sessions] pending sessions.
+    fn deliver(&self, id: &str, msg: Result<Msg<serde_json::Value>>) {
+        let mut state = self.state();
+        if state.closed.contains(id) {
+            return;
+        }
+        if !state.sessions.contains_key(id) {
+            if state.pending >= self.config.max_pending_sessions {
+                return;
+            }
+            state.pending += 1;
+            let slot = Slot {
+                opened: false,
+                queue: Default::default(),
+            };
+            state.sessions.insert(id.to_owned(), slot);
+        }
+
+        let mut queue = lock(&state.sessions[id].queue);
+        if queue.closed {
+            return;
+        }
+        if msg.is_err() {
+            queue.close_with(msg);
+        } else if queue.messages.len() >= self.config.session_buffer {
+            queue.close_with(Err(Error::SessionOverflow {
+                session: id.to_owned(),
+            }));
+        } else {
+            queue.push(msg);
+        }
+    }
+
+    fn close(&self, id: &str) {
+        let mut state = self.state();
+        if let Some(slot) = state.sessions.remove(id) {
+            debug_assert!(slot.opened);
+            state.closed.insert(id.to_owned());
+            state.closed_order.push_back(id.to_owned());
+            if state.closed_order.len() > self.config.max_pending_sessions {
+                if let Some(oldest) = state.closed_order.pop_front() {
+                    state.closed.remove(&oldest);
+                }
+            }
+        }
+    }
+
+    fn shut_down(&self) {
+        let mut state = self.state();
+        state.shut_down = true;
+        for slot in state.sessions.values() {
+            let mut queue = lock(&slot.queue);
+            if !queue.closed {
+                queue.close_with(Err(Error::MuxClosed));
+            }
+        }
+    }
+}
+
+impl Queue {
+    fn push(&mut self, msg: Result<Msg<serde_json::Value>>) {
+        self.messages.push_back(msg);
+        if let Some(waker) = self.waker.take() {
+            waker.wake()
+        }
+    }
+
+    fn close_with(&mut self, error: Result<Msg<serde_json::Value>>) {
+        self.push(error);
+        self.closed = true;
+    }
+}
+
+fn lock(queue: &Mutex<Queue>) -> MutexGuard<'_, Queue> {
+    queue.lock().expect("session queue is poisoned")
+}
+
+/// Incoming messages of a session, unregisters the session when dropped
+struct Registration {
+    id: String,
+    queue: Arc<Mutex<Queue>>,
+    registry: Arc<Registry>,
+}
+
+impl Stream for Registration {
+    type Item = Result<Msg<serde_json::Value>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut queue = lock(&self.queue);
+        if let Some(msg) = queue.messages.pop_front() {
+            Poll::Ready(Some(msg))
+        } else if queue.closed {
+            Poll::Ready(None)
+        } else {
+            queue.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        self.registry.close(&self.id)
+    }
+}
@@ -0,0 +1,207 @@
+//! Full mesh of TCP connections
+//!
+//! Every pair of parties is connected directly: party `i` dials every party `j < i` and accepts
+//! connections from every party `j > i`. Dialing party introduces itself by sending its index
+//! (2 bytes, big-endian). Messages are serialized to JSON and sent in frames prefixed with
+//! 4 bytes big-endian length.
+
+use std::convert::TryFrom;
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use futures::{sink, stream, StreamExt};
+use round_based::Msg;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream};
+
+use super::{Error, Incoming, Outgoing, Result, Transport};
+
+/// Maximum size of a frame, larger frames are rejected without being read
+pub const MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+/// How many times a party is dialed before giving up
+const DIAL_ATTEMPTS: u32 = 50;
+/// Delay between dial attempts
+const DIAL_RETRY_DELAY: Duration = Duration::from_millis(200);
+
+/// Connection of a party to the mesh, see [connect]
+pub struct Tcp {
+    i: u16,
+    readers: Vec<(u16, OwnedReadHalf)>,
+    writers: Vec<Option<OwnedWriteHalf>>,
+}
+
+/// Connects party `i` to other parties
+///
+/// `addresses[j-1]` is the address party `j` listens at, `listener` must be bound to
+/// `addresses[i-1]` (it's taken as an argument, so it can be bound before addresses are
+/// published, e.g. to port 0). Returns once connections to all parties are established.
+pub async fn connect(i: u16, listener: TcpListener, addresses: &[SocketAddr]) -> Result<Tcp> {
+    let n = u16::try_from(addresses.len()).map_err(|_| Error::InvalidPartyIndex(i))?;
+    if i == 0 || i > n {
+        return Err(Error::InvalidPartyIndex(i));
+    }
+
+    let mut connections: Vec<Option<TcpStream>> = (0..n).map(|_| None).collect();
+    for j in 1..i {
+        let mut stream = dial(addresses[usize::from(j - 1)]).await?;
+        stream.write_u16(i).await.map_err(Error::Io)?;
+        connections[usize::from(j - 1)] = Some(stream);
+    }
+    for _ in i..n {
+        let (mut stream, _) = listener.accept().await.map_err(Error::Io)?;
+        let j = stream.read_u16().await.map_err(Error::Io)?;
+        if j <= i || j > n {
+            return Err(Error::InvalidPartyIndex(j));
+        }
+        let slot = &mut connections[usize::from(j - 1)];
+        if slot.is_some() {
+            return Err(Error::DuplicatedConnection { party: j });
+        }
+        *slot = Some(stream);
+    }
+
+    let mut readers = vec![];
+    let mut writers = vec![];
+    for (j, connection) in (1..).zip(connections) {
+        match connection {
+            Some(stream) => {
+                stream.set_nodelay(true).map_err(Error::Io)?;
+                let (reader, writer) = stream.into_split();
+                readers.push((j, reader));
+                writers.push(Some(writer));
+            }
+            None => writers.push(None),
+        }
+    }
+    Ok(Tcp {
+        i,
+        readers,
+        writers,
+    })
+}
+
+async fn dial(address: SocketAddr) -> Result<TcpStream> {
+    let mut attempt = 1;
+    loop {
+        match TcpStream::connect(address).await {
+            Ok(stream) => return Ok(stream),
+            Err(_) if attempt < DIAL_ATTEMPTS => {
+                attempt += 1;
+                tokio::time::sleep(DIAL_RETRY_DELAY).await;
+            }
+            Err(e) => return Err(Error::Io(e)),
+        }
+    }
+}
+
+impl<M> Transport<M> for Tcp
+where
+    M: Serialize + DeserializeOwned + Send + 'static,
+{
+    fn party_index(&self) -> u16 {
+        self.i
+    }
+
+    fn split(self) -> (Incoming<M>, Outgoing<M>) {
+        let i = self.i;
+        let incoming = stream::select_all(
+            self.readers
+                .into_iter()
+                .map(|(peer, reader)| receive_from(i, peer, reader).boxed()),
+        )
+        .boxed();
+
+        let outgoing = sink::unfold(self.writers, move |mut writers, msg: Msg<M>| async move {
+            let frame = serde_json::to_vec(&msg).map_err(Error::Serialize)?;
+            match msg.receiver {
+                Some(j) => {
+                    let writer = usize::from(j)
+                        .checked_sub(1)
+                        .and_then(|j| writers.get_mut(j))
+                        .and_then(Option::as_mut)
+                        .ok_or(Error::UnknownParty { party: j })?;
+                    write_frame(writer, &frame)
+                        .await
+                        .map_err(|_| Error::ConnectionClosed { party: j })?;
+                }
+                None => {
+                    for (j, writer) in (1..).zip(&mut writers) {
+                        if let Some(writer) = writer {
+                            write_frame(writer, &frame)
+                                .await
+                                .map_err(|_| Error::ConnectionClosed { party: j })?;
+                        }
+                    }
+                }
+            }
+            Ok(writers)
+        });
+        (incoming, Box::pin(outgoing))
+    }
+}
+
+/// Stream of messages received from party `peer`, ends when peer closes connection
+fn receive_from<M: DeserializeOwned>(
+    i: u16,
+    peer: u16,
+    reader: OwnedReadHalf,
+) -> impl futures::Stream<Item = Result<Msg<M>>> {
+    stream::unfold(Some(reader), move |reader| async move {
+        let mut reader = reader?;
+        let frame = match read_frame(&mut reader).await {
+            Ok(Some(frame)) => frame,
+            Ok(None) => return None,
+            Err(e) => return Some((Err(e), None)),
+        };
+        let msg = serde_json::from_slice::<Msg<M>>(&frame)
+            .map_err(Error::Deserialize)
+            .and_then(|msg| check_msg(i, peer, msg));
+        Some((msg, Some(reader)))
+    })
+}
+
+fn check_msg<M>(i: u16, peer: u16, msg: Msg<M>) -> Result<Msg<M>> {
+    if msg.sender != peer {
+        return Err(Error::ForgedSender {
+            peer,
+            sender: msg.sender,
+        });
+    }
+    match msg.receiver {
+        Some(receiver) if receiver != i => Err(Error::MisaddressedMessage {
+            sender: peer,
+            receiver,
+        }),
+        _ => Ok(msg),
+    }
+}
+
+async fn write_frame<W: AsyncWrite + Unpin>(writer: &mut W, frame: &[u8]) -> io::Result<()> {
+    let len = u32::try_from(frame.len())
+        .ok()
+        .filter(|&len| len as usize <= MAX_FRAME_SIZE)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "frame is too large"))?;
+    writer.write_u32(len).await?;
+    writer.write_all(frame).await?;
+    writer.flush().await
+}
+
+/// Reads a frame, returns `None` if connection is closed
+async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Option<Vec<u8>>> {
+    let len = match reader.read_u32().await {
+        Ok(len) => len as usize,
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(Error::Io(e)),
+    };
+    if len > MAX_FRAME_SIZE {
+        return Err(Error::FrameTooLarge { len });
+    }
+    let mut frame = vec![0u8; len];
+    reader.read_exact(&mut frame).await.map_err(Error::Io)?;
+    Ok(Some(frame))
+}
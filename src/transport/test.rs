@@ -0,0 +1,731 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use curv::elliptic::curves::secp256_k1::Secp256k1;
+use curv::BigInt;
+use futures::{SinkExt, StreamExt};
+use round_based::AsyncProtocol;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+
+use super::*;
+use crate::identity::{IdentityKey, Roster, SignedMessage};
+use crate::protocols::multi_party_ecdsa::gg_2020::party_i::verify;
+use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::checkpoint::{
+    Checkpoint, CheckpointKey, SentLogFile,
+};
+use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::{
+    Keygen, LocalKey, ProtocolMessage,
+};
+use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::sign::{OfflineStage, SignManual};
+
+async fn run_keygen<T>(t: u16, n: u16, transports: Vec<T>) -> Vec<LocalKey<Secp256k1>>
+where
+    T: Transport<ProtocolMessage>,
+{
+    let parties = transports
+        .into_iter()
+        .map(|transport| {
+            let i = transport.party_index();
+            let (incoming, outgoing) = transport.split();
+            tokio::spawn(async move {
+                AsyncProtocol::new(Keygen::new(i, t, n).unwrap(), incoming.fuse(), outgoing)
+                    .run()
+                    .await
+                    .map_err(|e| e.to_string())
+            })
+        })
+        .collect::<Vec<_>>();
+    let mut local_keys = vec![];
+    for party in parties {
+        local_keys.push(party.await.unwrap().unwrap());
+    }
+    assert!(local_keys
+        .iter()
+        .all(|k| k.public_key() == local_keys[0].public_key()));
+    local_keys
+}
+
+async fn tcp_mesh(n: u16) -> Vec<tcp::Tcp> {
+    let mut listeners = vec![];
+    for _ in 0..n {
+        listeners.push(TcpListener::bind("127.0.0.1:0").await.unwrap());
+    }
+    let addresses = listeners
+        .iter()
+        .map(|l| l.local_addr().unwrap())
+        .collect::<Vec<SocketAddr>>();
+    let connecting = (1..)
+        .zip(listeners)
+        .map(|(i, listener)| {
+            let addresses = addresses.clone();
+            tokio::spawn(async move { tcp::connect(i, listener, &addresses).await.unwrap() })
+        })
+        .collect::<Vec<_>>();
+    let mut transports = vec![];
+    for party in connecting {
+        transports.push(party.await.unwrap());
+    }
+    transports
+}
+
+#[tokio::test]
+async fn keygen_over_memory() {
+    run_keygen(1, 3, memory::network(3)).await;
+}
+
+#[tokio::test]
+async fn keygen_over_tcp() {
+    let transports = tcp_mesh(3).await;
+    run_keygen(1, 3, transports).await;
+}
+
+#[tokio::test]
+async fn memory_and_tcp_deliver_the_same_messages() {
+    async fn exchange<T: Transport<String>>(transports: Vec<T>) -> Vec<Vec<Msg<String>>> {
+        let (incoming, outgoing): (Vec<_>, Vec<_>) =
+            transports.into_iter().map(Transport::split).unzip();
+        for (i, mut outgoing) in (1..).zip(outgoing) {
+            outgoing
+                .send(Msg {
+                    sender: i,
+                    receiver: None,
+                    body: format!("broadcast from {}", i),
+                })
+                .await
+                .unwrap();
+            outgoing
+                .send(Msg {
+                    sender: i,
+                    receiver: Some(i % 3 + 1),
+                    body: format!("p2p from {}", i),
+                })
+                .await
+                .unwrap();
+        }
+        let mut received = vec![];
+        for incoming in incoming {
+            let mut msgs = incoming
+                .take(3)
+                .map(Result::unwrap)
+                .collect::<Vec<_>>()
+                .await;
+            msgs.sort_by_key(|m| (m.sender, m.receiver));
+            received.push(msgs);
+        }
+        received
+    }
+
+    let over_memory = exchange(memory::network(3)).await;
+    let over_tcp = exchange(tcp_mesh(3).await).await;
+    assert_eq!(over_memory, over_tcp);
+    assert_eq!(
+        over_memory[0],
+        vec![
+            Msg {
+                sender: 2,
+                receiver: None,
+                body: "broadcast from 2".to_owned()
+            },
+            Msg {
+                sender: 3,
+                receiver: None,
+                body: "broadcast from 3".to_owned()
+            },
+            Msg {
+                sender: 3,
+                receiver: Some(1),
+                body: "p2p from 3".to_owned()
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn tcp_rejects_forged_sender() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let addresses = vec![address, "127.0.0.1:1".parse().unwrap()];
+    let party1 = tokio::spawn(async move { tcp::connect(1, listener, &addresses).await });
+
+    // Party 2 introduces itself, but sends a message on behalf of party 3
+    let mut stream = TcpStream::connect(address).await.unwrap();
+    stream.write_u16(2).await.unwrap();
+    let frame = serde_json::to_vec(&Msg {
+        sender: 3,
+        receiver: None,
+        body: 42u32,
+    })
+    .unwrap();
+    stream.write_u32(frame.len() as u32).await.unwrap();
+    stream.write_all(&frame).await.unwrap();
+
+    let party1 = party1.await.unwrap().unwrap();
+    let (mut incoming, _outgoing) = Transport::<u32>::split(party1);
+    assert!(matches!(
+        incoming.next().await,
+        Some(Err(Error::ForgedSender { peer: 2, sender: 3 }))
+    ));
+}
+
+fn mux_network(n: u16, config: mux::Config) -> Vec<mux::Mux> {
+    memory::network(n)
+        .into_iter()
+        .map(|transport| {
+            let (mux, driver) = mux::new(transport, config.clone());
+            tokio::spawn(driver);
+            mux
+        })
+        .collect()
+}
+
+fn broadcast(i: u16, body: &str) -> Msg<String> {
+    Msg {
+        sender: i,
+        receiver: None,
+        body: body.to_owned(),
+    }
+}
+
+#[tokio::test]
+async fn concurrent_keygens_over_mux() {
+    let muxes = mux_network(3, mux::Config::default());
+    let sessions = |id: &str| {
+        muxes
+            .iter()
+            .map(|mux| mux.session::<ProtocolMessage>(id).unwrap())
+            .collect::<Vec<_>>()
+    };
+    let (a, b) = (sessions("keygen-a"), sessions("keygen-b"));
+    let (keys_a, keys_b) = futures::join!(run_keygen(1, 3, a), run_keygen(1, 3, b));
+    assert_ne!(keys_a[0].public_key(), keys_b[0].public_key());
+
+    assert!(matches!(
+        muxes[0].session::<ProtocolMessage>("keygen-a"),
+        Err(Error::SessionExists(_))
+    ));
+}
+
+#[tokio::test]
+async fn mux_buffers_sessions_until_opened_and_aborts_overflowing_ones() {
+    let config = mux::Config {
+        session_buffer: 2,
+        ..Default::default()
+    };
+    let muxes = mux_network(2, config);
+
+    let (_, mut outgoing) = muxes[0].session::<String>("early").unwrap().split();
+    for k in 0..2 {
+        outgoing.send(broadcast(1, &k.to_string())).await.unwrap();
+    }
+    let (_, mut overflowing) = muxes[0].session::<String>("overflowing").unwrap().split();
+    for k in 0..3 {
+        overflowing
+            .send(broadcast(1, &k.to_string()))
+            .await
+            .unwrap();
+    }
+    let (_, mut sync) = muxes[0].session::<String>("sync").unwrap().split();
+    sync.send(broadcast(1, "done")).await.unwrap();
+
+    // Messages are delivered in order, so once "sync" is received, other sessions are buffered
+    let (mut incoming, _) = muxes[1].session::<String>("sync").unwrap().split();
+    assert_eq!(incoming.next().await.unwrap().unwrap().body, "done");
+
+    let (incoming, _) = muxes[1].session::<String>("early").unwrap().split();
+    let received = incoming
+        .take(2)
+        .map(|m| m.unwrap().body)
+        .collect::<Vec<_>>();
+    assert_eq!(received.await, ["0", "1"]);
+
+    let (mut incoming, _) = muxes[1].session::<String>("overflowing").unwrap().split();
+    assert!(incoming.next().await.unwrap().is_ok());
+    assert!(incoming.next().await.unwrap().is_ok());
+    assert!(matches!(
+        incoming.next().await,
+        Some(Err(Error::SessionOverflow { .. }))
+    ));
+    assert!(incoming.next().await.is_none());
+}
+
+#[tokio::test]
+async fn mux_drops_sessions_beyond_pending_limit() {
+    let config = mux::Config {
+        max_pending_sessions: 1,
+        ..Default::default()
+    };
+    let muxes = mux_network(2, config);
+    let (mut sync_incoming, _) = muxes[1].session::<String>("sync").unwrap().split();
+
+    for session in &["pending", "dropped", "sync"] {
+        let (_, mut outgoing) = muxes[0].session::<String>(*session).unwrap().split();
+        outgoing.send(broadcast(1, session)).await.unwrap();
+    }
+    assert_eq!(sync_incoming.next().await.unwrap().unwrap().body, "sync");
+
+    let (mut incoming, _) = muxes[1].session::<String>("pending").unwrap().split();
+    assert_eq!(incoming.next().await.unwrap().unwrap().body, "pending");
+
+    // Connection is still up, but the message of session that didn't fit is lost
+    let (mut incoming, _) = muxes[1].session::<String>("dropped").unwrap().split();
+    let (_, mut outgoing) = muxes[0].session::<String>("after").unwrap().split();
+    outgoing.send(broadcast(1, "after")).await.unwrap();
+    let (mut after, _) = muxes[1].session::<String>("after").unwrap().split();
+    assert_eq!(after.next().await.unwrap().unwrap().body, "after");
+    assert!(futures::poll!(incoming.next()).is_pending());
+}
+
+#[tokio::test]
+async fn authenticated_mux_drops_forged_frames() {
+    let identities = (0..3).map(|_| IdentityKey::generate()).collect::<Vec<_>>();
+    let roster = Roster::new(identities.iter().map(IdentityKey::public_key).collect()).unwrap();
+    let roster = Arc::new(roster);
+    let mut transports = memory::network::<SignedMessage>(3);
+    // Party 3 writes frames to the transport directly
+    let (_, mut raw_outgoing) = transports.pop().unwrap().split();
+    let muxes = transports
+        .into_iter()
+        .zip(&identities)
+        .map(|(transport, identity)| {
+            let (mux, driver) = mux::new_authenticated(
+                transport,
+                Arc::new(identity.clone()),
+                roster.clone(),
+                b"mux".to_vec(),
+                mux::Config::default(),
+            );
+            tokio::spawn(driver);
+            mux
+        })
+        .collect::<Vec<_>>();
+    let (mut incoming, _) = muxes[0].session::<String>("s").unwrap().split();
+
+    let frame = |body: &str| Msg {
+        sender: 3,
+        receiver: None,
+        body: serde_json::from_value::<mux::Frame>(serde_json::json!({
+            "session": "s",
+            "body": { "message": body },
+        }))
+        .unwrap(),
+    };
+    let frames = vec![
+        IdentityKey::generate().sign_msg(b"mux", frame("impersonated")),
+        identities[2].sign_msg(b"another mux", frame("replayed")),
+        identities[2].sign_msg(b"mux", frame("genuine")),
+    ];
+    for frame in frames {
+        raw_outgoing.send(frame.unwrap()).await.unwrap();
+    }
+    assert_eq!(incoming.next().await.unwrap().unwrap().body, "genuine");
+
+    // Forged frames don't bring the connection down
+    let (_, mut outgoing) = muxes[1].session::<String>("s").unwrap().split();
+    outgoing.send(broadcast(2, "hello")).await.unwrap();
+    assert_eq!(incoming.next().await.unwrap().unwrap().body, "hello");
+}
+
+#[tokio::test]
+async fn mux_cancels_session_at_every_party() {
+    let muxes = mux_network(3, mux::Config::default());
+    let mut incoming = muxes
+        .iter()
+        .map(|mux| mux.session::<String>("signing").unwrap().split().0)
+        .collect::<Vec<_>>();
+
+    muxes[1].cancel("signing").await.unwrap();
+    for incoming in &mut incoming {
+        assert!(matches!(
+            incoming.next().await,
+            Some(Err(Error::SessionCancelled { party: 2, .. }))
+        ));
+        assert!(incoming.next().await.is_none());
+    }
+}
+
+fn run_over_bundles<SM, F>(session_id: &str, n: u16, mut new_party: F) -> Vec<SM::Output>
+where
+    SM: round_based::StateMachine + Checkpointed,
+    SM::MessageBody: serde::Serialize + serde::de::DeserializeOwned,
+    SM::Err: std::error::Error + 'static,
+    F: FnMut(u16) -> SM,
+{
+    use bundle::{BundleDir, Progress};
+
+    let dir = std::env::temp_dir().join(format!("bundle-test-{}", rand::random::<u64>()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let key = CheckpointKey::generate();
+    let sent_log = |i: u16| SentLogFile::new(dir.join(format!("{}-{}.sent", session_id, i)));
+    // Parties are restarted after every step, only checkpoints and sent logs survive
+    let mut checkpoints: Vec<Option<String>> = vec![None; usize::from(n)];
+    let mut outputs = (0..n).map(|_| None).collect::<Vec<_>>();
+    for _ in 0..20 {
+        for i in 1..=n {
+            let k = usize::from(i - 1);
+            if outputs[k].is_some() {
+                continue;
+            }
+            let mut party = match &checkpoints[k] {
+                Some(checkpoint) => SM::restore(
+                    &serde_json::from_str(checkpoint).unwrap(),
+                    &key,
+                    sent_log(i),
+                ),
+                None => {
+                    let mut party = new_party(i);
+                    party.persist_sent_log(sent_log(i));
+                    party
+                }
+            };
+            let bundles = BundleDir::new(&dir, session_id, i, n);
+            match bundle::step(&mut party, &bundles).unwrap() {
+                Progress::Waiting { .. } => {
+                    let checkpoint = party.checkpoint(&key);
+                    checkpoints[k] = Some(serde_json::to_string(&checkpoint).unwrap())
+                }
+                Progress::Finished(output) => outputs[k] = Some(output),
+            }
+        }
+        if outputs.iter().all(Option::is_some) {
+            std::fs::remove_dir_all(&dir).unwrap();
+            return outputs.into_iter().flatten().collect();
+        }
+    }
+    panic!("protocol didn't complete")
+}
+
+trait Checkpointed {
+    fn persist_sent_log(&mut self, store: SentLogFile);
+    fn checkpoint(&self, key: &CheckpointKey) -> Checkpoint;
+    fn restore(checkpoint: &Checkpoint, key: &CheckpointKey, store: SentLogFile) -> Self;
+}
+
+impl Checkpointed for Keygen {
+    fn persist_sent_log(&mut self, store: SentLogFile) {
+        Keygen::persist_sent_log(self, store).unwrap()
+    }
+    fn checkpoint(&self, key: &CheckpointKey) -> Checkpoint {
+        Keygen::checkpoint(self, key).unwrap()
+    }
+    fn restore(checkpoint: &Checkpoint, key: &CheckpointKey, store: SentLogFile) -> Self {
+        Keygen::restore(checkpoint, key, store).unwrap()
+    }
+}
+
+impl Checkpointed for OfflineStage {
+    fn persist_sent_log(&mut self, store: SentLogFile) {
+        OfflineStage::persist_sent_log(self, store).unwrap()
+    }
+    fn checkpoint(&self, key: &CheckpointKey) -> Checkpoint {
+        OfflineStage::checkpoint(self, key).unwrap()
+    }
+    fn restore(checkpoint: &Checkpoint, key: &CheckpointKey, store: SentLogFile) -> Self {
+        OfflineStage::restore(checkpoint, key, store).unwrap()
+    }
+}
+
+#[test]
+fn keygen_and_offline_stage_over_bundles() {
+    let local_keys = run_over_bundles("keygen", 3, |i| Keygen::new(i, 1, 3).unwrap());
+    assert!(local_keys
+        .iter()
+        .all(|k| k.public_key() == local_keys[0].public_key()));
+
+    let s_l = vec![1, 3];
+    let completed = run_over_bundles("offline", 2, |i| {
+        let local_key = local_keys[usize::from(s_l[usize::from(i - 1)] - 1)].clone();
+        OfflineStage::new(i, s_l.clone(), local_key).unwrap()
+    });
+
+    let message = BigInt::from(42);
+    let (signing, _) = SignManual::new(message.clone(), completed[0].clone()).unwrap();
+    let (_, partial2) = SignManual::new(message.clone(), completed[1].clone()).unwrap();
+    let signature = signing.complete(&[partial2]).unwrap();
+    verify(&signature, &local_keys[0].public_key(), &message).unwrap();
+}
+
+#[test]
+fn bundles_are_checked() {
+    use bundle::BundleDir;
+
+    let dir = std::env::temp_dir().join(format!("bundle-test-{}", rand::random::<u64>()));
+    let identities = (0..2)
+        .map(|_| crate::identity::IdentityKey::generate())
+        .collect::<Vec<_>>();
+    let roster =
+        crate::identity::Roster::new(identities.iter().map(|k| k.public_key()).collect()).unwrap();
+    let party = |i: u16, identity: usize| {
+        BundleDir::new(&dir, "session", i, 2)
+            .with_identity(identities[identity].clone(), roster.clone())
+    };
+    let msg = |body: &str| Msg {
+        sender: 1,
+        receiver: None,
+        body: body.to_owned(),
+    };
+
+    party(1, 0).write_round(1, &[msg("hello")]).unwrap();
+    // Writing the same messages again is fine, writing different ones is equivocation
+    party(1, 0).write_round(1, &[msg("hello")]).unwrap();
+    assert!(matches!(
+        party(1, 0).write_round(1, &[msg("bye")]),
+        Err(bundle::Error::Equivocation { .. })
+    ));
+    let received = party(2, 1).read_round::<String>(1).unwrap().unwrap();
+    assert_eq!(received, [msg("hello")]);
+    assert_eq!(party(2, 1).read_round::<String>(2).unwrap(), None);
+
+    // Bundle signed by a key that isn't party's one
+    party(1, 1).write_round(2, &[msg("hello")]).unwrap();
+    assert!(matches!(
+        party(2, 1).read_round::<String>(2),
+        Err(bundle::Error::InvalidSignature { .. })
+    ));
+
+    // Bundle from another session
+    BundleDir::new(&dir, "another-session", 1, 2)
+        .with_identity(identities[0].clone(), roster.clone())
+        .write_round(3, &[msg("hello")])
+        .unwrap();
+    assert!(matches!(
+        party(2, 1).read_round::<String>(3),
+        Err(bundle::Error::InvalidSignature { .. })
+    ));
+
+    // Corrupted bundle
+    let path = dir.join("round-1-from-1-to-2.bundle");
+    let bundle = std::fs::read_to_string(&path).unwrap();
+    std::fs::write(&path, bundle.replace("hello", "hellO")).unwrap();
+    assert!(matches!(
+        party(2, 1).read_round::<String>(1),
+        Err(bundle::Error::Corrupted { .. })
+    ));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Minimal relay serving a single HTTP/SSE room
+#[cfg(feature = "transport-http")]
+mod room {
+    use std::collections::{HashMap, HashSet};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::{TcpListener, TcpStream};
+
+    /// Faults injected by the server
+    #[derive(Default, Clone, Copy)]
+    pub struct Faults {
+        /// Subscription is dropped after sending this many events
+        pub drop_subscription_after: Option<usize>,
+        /// Every odd broadcast request is processed, but responded with `503`
+        pub lose_broadcast_responses: bool,
+    }
+
+    #[derive(Default)]
+    struct Room {
+        messages: Vec<String>,
+        keys: HashSet<String>,
+        issued: u16,
+        broadcast_requests: usize,
+        faults: Faults,
+    }
+
+    /// Starts serving, returns server address
+    pub async fn serve() -> surf::Url {
+        serve_with(Faults::default()).await
+    }
+
+    /// Starts serving a room that injects given faults, returns server address
+    pub async fn serve_with(faults: Faults) -> surf::Url {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let room = Arc::new(Mutex::new(Room {
+            faults,
+            ..Room::default()
+        }));
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                tokio::spawn(handle_connection(stream, room.clone()));
+            }
+        });
+        format!("http://{}/", address).parse().unwrap()
+    }
+
+    async fn handle_connection(stream: TcpStream, room: Arc<Mutex<Room>>) -> std::io::Result<()> {
+        let mut stream = BufReader::new(stream);
+        loop {
+            let mut request_line = String::new();
+            if stream.read_line(&mut request_line).await? == 0 {
+                return Ok(());
+            }
+            let mut headers = HashMap::new();
+            loop {
+                let mut header = String::new();
+                stream.read_line(&mut header).await?;
+                if header.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header.split_once(':') {
+                    headers.insert(name.to_ascii_lowercase(), value.trim().to_owned());
+                }
+            }
+            let content_length = headers
+                .get("content-length")
+                .map(|len| len.parse().unwrap())
+                .unwrap_or(0);
+            let mut body = vec![0u8; content_length];
+            stream.read_exact(&mut body).await?;
+
+            let path = request_line.split(' ').nth(1).unwrap_or_default();
+            if path.ends_with("/issue_unique_idx") {
+                let idx = {
+                    let mut room = room.lock().unwrap();
+                    room.issued += 1;
+                    room.issued
+                };
+                respond(
+                    &mut stream,
+                    "200 OK",
+                    &format!("{{\"unique_idx\":{}}}", idx),
+                )
+                .await?;
+            } else if path.ends_with("/broadcast") {
+                let lose_response = {
+                    let mut room = room.lock().unwrap();
+                    let key = headers.get("idempotency-key").cloned();
+                    if key.map(|key| room.keys.insert(key)).unwrap_or(true) {
+                        room.messages.push(String::from_utf8(body).unwrap());
+                    }
+                    room.broadcast_requests += 1;
+                    room.faults.lose_broadcast_responses && room.broadcast_requests % 2 == 1
+                };
+                if lose_response {
+                    respond(&mut stream, "503 Service Unavailable", "").await?;
+                } else {
+                    respond(&mut stream, "200 OK", "").await?;
+                }
+            } else if path.ends_with("/subscribe") {
+                let last_event_id = headers
+                    .get("last-event-id")
+                    .map(|id| id.parse::<usize>().unwrap());
+                return subscribe(stream, room, last_event_id).await;
+            } else {
+                respond(&mut stream, "404 Not Found", "").await?;
+            }
+        }
+    }
+
+    async fn respond(
+        stream: &mut BufReader<TcpStream>,
+        status: &str,
+        body: &str,
+    ) -> std::io::Result<()> {
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+        stream.write_all(response.as_bytes()).await
+    }
+
+    async fn subscribe(
+        mut stream: BufReader<TcpStream>,
+        room: Arc<Mutex<Room>>,
+        last_event_id: Option<usize>,
+    ) -> std::io::Result<()> {
+        stream
+            .write_all(
+                b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\
+                  Cache-Control: no-cache\r\nTransfer-Encoding: chunked\r\n\r\n",
+            )
+            .await?;
+        // Client doesn't resolve the response until it receives first bytes of the body
+        stream.write_all(b"3\r\n:\n\n\r\n").await?;
+        let drop_after = room.lock().unwrap().faults.drop_subscription_after;
+        let mut next_event = last_event_id.map(|id| id + 1).unwrap_or(0);
+        let mut sent = 0;
+        loop {
+            let new_messages = room.lock().unwrap().messages[next_event..].to_vec();
+            for message in new_messages {
+                if Some(sent) == drop_after {
+                    return Ok(());
+                }
+                let event = format!("id: {}\ndata: {}\n\n", next_event, message);
+                let chunk = format!("{:x}\r\n{}\r\n", event.len(), event);
+                stream.write_all(chunk.as_bytes()).await?;
+                next_event += 1;
+                sent += 1;
+            }
+            stream.flush().await?;
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+}
+
+#[cfg(feature = "transport-http")]
+#[tokio::test]
+async fn keygen_over_http() {
+    let address = room::serve().await;
+    let mut transports = vec![];
+    for _ in 0..3 {
+        transports.push(http::join(address.clone(), "keygen").await.unwrap());
+    }
+    let indexes = transports
+        .iter()
+        .map(Transport::<ProtocolMessage>::party_index)
+        .collect::<Vec<_>>();
+    assert_eq!(indexes, [1, 2, 3]);
+    run_keygen(1, 3, transports).await;
+}
+
+#[cfg(feature = "transport-http")]
+#[tokio::test]
+async fn keygen_over_http_survives_dropped_connections() {
+    let address = room::serve_with(room::Faults {
+        drop_subscription_after: Some(2),
+        lose_broadcast_responses: true,
+    })
+    .await;
+    let backoff = http::Backoff {
+        initial_delay: std::time::Duration::from_millis(1),
+        ..http::Backoff::default()
+    };
+    let mut transports = vec![];
+    for _ in 0..3 {
+        let client = http::RoomClient::new(address.clone(), "keygen")
+            .unwrap()
+            .with_backoff(backoff);
+        transports.push(http::join_with(client).await.unwrap());
+    }
+    // Keygen fails if any message is lost or delivered twice
+    run_keygen(1, 3, transports).await;
+}
+
+#[cfg(feature = "transport-http")]
+#[tokio::test]
+async fn http_subscription_gives_up_when_retries_are_exhausted() {
+    let address = room::serve_with(room::Faults {
+        drop_subscription_after: Some(0),
+        ..room::Faults::default()
+    })
+    .await;
+    let client = http::RoomClient::new(address, "room")
+        .unwrap()
+        .with_backoff(http::Backoff {
+            initial_delay: std::time::Duration::from_millis(1),
+            max_delay: std::time::Duration::from_millis(1),
+            max_retries: Some(3),
+        });
+    client.broadcast("message").await.unwrap();
+    let mut subscription = Box::pin(client.subscribe().await.unwrap());
+    assert!(matches!(
+        subscription.next().await,
+        Some(Err(Error::Http(_)))
+    ));
+    assert!(subscription.next().await.is_none());
+}
@@ -0,0 +1,185 @@
+#![allow(non_snake_case)]
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+//! ECDSA adaptor signatures
+//!
+//! A pre-signature of `message` under public key `X` is tied to adaptor point `Y = y * G`: it
+//! can be turned into a valid ECDSA signature only by someone who knows `y`, and once that
+//! signature is published anyone holding the pre-signature learns `y`. That's what cross-chain
+//! atomic swaps are built on.
+//!
+//! Pre-signature is `(R, R_Y, ŝ, π)` where `R = k * G`, `R_Y = k * Y`, `r = R_Y.x mod q`,
+//! `ŝ = k^-1 (m + r x)` and `π` proves that `log_G R = log_Y R_Y`. It's verified by checking `π`
+//! and `R = ŝ^-1 (m G + r X)`. Adapted signature is `(r, ŝ * y^-1)`, its nonce point is `R_Y`.
+//!
+//! Threshold protocols produce pre-signatures via
+//! [gg_2020 SignManual](crate::protocols::multi_party_ecdsa::gg_2020::state_machine::sign::AdaptorSignManual)
+//! and [lindell_2017 party one](crate::protocols::two_party_ecdsa::lindell_2017::party_one::Signature::compute_pre_signature).
+//! Nobody knows nonce `k` there, so parties build `π` jointly using [challenge] and [proof].
+
+use curv::arithmetic::traits::*;
+use curv::cryptographic_primitives::hashing::{Digest, DigestExt};
+use curv::cryptographic_primitives::proofs::sigma_ec_ddh::{ECDDHProof, ECDDHStatement};
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use curv::BigInt;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::utilities::verification::{self, SignatureRecid};
+use crate::Error::{self, InvalidSig};
+
+/// Pre-signature tied to adaptor point `Y`, see [module level documentation](self)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PreSignature {
+    /// Nonce point `R = k * G`
+    pub R: Point<Secp256k1>,
+    /// `R_Y = k * Y`, nonce point of adapted signature
+    pub R_Y: Point<Secp256k1>,
+    pub s_hat: Scalar<Secp256k1>,
+    /// Proof that `log_G R = log_Y R_Y`
+    pub proof: ECDDHProof<Secp256k1, Sha256>,
+}
+
+impl PreSignature {
+    /// `r` component of adapted signature
+    pub fn r(&self) -> Result<Scalar<Secp256k1>, Error> {
+        x_coord(&self.R_Y)
+    }
+}
+
+/// Verifies pre-signature of `message` under public key `pk` tied to adaptor point `Y`
+///
+/// `message` is a hash of signed data interpreted as a big-endian integer, same as in
+/// [verification::verify].
+pub fn verify_pre_signature(
+    pre_signature: &PreSignature,
+    pk: &Point<Secp256k1>,
+    Y: &Point<Secp256k1>,
+    message: &BigInt,
+) -> Result<(), Error> {
+    if pk.is_zero() || Y.is_zero() || pre_signature.R.is_zero() {
+        return Err(InvalidSig);
+    }
+    let r = pre_signature.r()?;
+    let s_hat_inv = pre_signature.s_hat.invert().ok_or(InvalidSig)?;
+
+    pre_signature
+        .proof
+        .verify(&statement(&pre_signature.R, Y, &pre_signature.R_Y))
+        .map_err(|_| InvalidSig)?;
+
+    let m = Scalar::<Secp256k1>::from(message);
+    let R = Point::generator() * (m * &s_hat_inv) + pk * (r * s_hat_inv);
+    if R != pre_signature.R {
+        return Err(InvalidSig);
+    }
+    Ok(())
+}
+
+/// Completes pre-signature into ECDSA signature using adaptor secret `y`
+///
+/// Resulting signature is low-S normalized. It's only valid if `y` matches adaptor point the
+/// pre-signature is tied to.
+pub fn adapt(pre_signature: &PreSignature, y: &Scalar<Secp256k1>) -> Result<SignatureRecid, Error> {
+    let r = pre_signature.r()?;
+    let y_inv = y.invert().ok_or(InvalidSig)?;
+    let (s, recid) = verification::normalize_s(&pre_signature.R_Y, &pre_signature.s_hat * y_inv)?;
+    Ok(SignatureRecid { r, s, recid })
+}
+
+/// Extracts adaptor secret `y` from pre-signature and signature adapted from it
+///
+/// Returns error if `signature` wasn't adapted from `pre_signature` with secret key of `Y`.
+pub fn extract_secret(
+    pre_signature: &PreSignature,
+    signature: &SignatureRecid,
+    Y: &Point<Secp256k1>,
+) -> Result<Scalar<Secp256k1>, Error> {
+    if signature.r != pre_signature.r()? {
+        return Err(InvalidSig);
+    }
+    let s_inv = signature.s.invert().ok_or(InvalidSig)?;
+    // Signature is normalized, so y is known up to a sign
+    let y = &pre_signature.s_hat * s_inv;
+    if &(Point::generator() * &y) == Y {
+        Ok(y)
+    } else if &(Point::generator() * -&y) == Y {
+        Ok(-y)
+    } else {
+        Err(InvalidSig)
+    }
+}
+
+/// Challenge of proof `π` for nonce points `R`, `R_Y` and commitments `a1 = ρ * G`, `a2 = ρ * Y`
+///
+/// Matches challenge of [ECDDHProof], so proof assembled by [proof] is verified as a regular one.
+pub fn challenge(
+    R: &Point<Secp256k1>,
+    Y: &Point<Secp256k1>,
+    R_Y: &Point<Secp256k1>,
+    a1: &Point<Secp256k1>,
+    a2: &Point<Secp256k1>,
+) -> Scalar<Secp256k1> {
+    Sha256::new()
+        .chain_point(&Point::<Secp256k1>::generator().to_point())
+        .chain_point(R)
+        .chain_point(Y)
+        .chain_point(R_Y)
+        .chain_point(a1)
+        .chain_point(a2)
+        .result_scalar()
+}
+
+/// Assembles proof `π` from commitments and response `z = ρ + e * k`
+pub fn proof(
+    a1: Point<Secp256k1>,
+    a2: Point<Secp256k1>,
+    z: Scalar<Secp256k1>,
+) -> ECDDHProof<Secp256k1, Sha256> {
+    ECDDHProof {
+        a1,
+        a2,
+        z,
+        hash_choice: Default::default(),
+    }
+}
+
+fn statement(
+    R: &Point<Secp256k1>,
+    Y: &Point<Secp256k1>,
+    R_Y: &Point<Secp256k1>,
+) -> ECDDHStatement<Secp256k1> {
+    ECDDHStatement {
+        g1: Point::generator().to_point(),
+        h1: R.clone(),
+        g2: Y.clone(),
+        h2: R_Y.clone(),
+    }
+}
+
+/// `R.x mod q`, must be non-zero
+fn x_coord(R: &Point<Secp256k1>) -> Result<Scalar<Secp256k1>, Error> {
+    let x = R.x_coord().ok_or(InvalidSig)?;
+    let r = Scalar::<Secp256k1>::from(&x.mod_floor(Scalar::<Secp256k1>::group_order()));
+    if r.is_zero() {
+        return Err(InvalidSig);
+    }
+    Ok(r)
+}
+
+#[cfg(test)]
+mod test;
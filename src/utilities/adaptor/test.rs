@@ -0,0 +1,92 @@
+#![allow(non_snake_case)]
+
+use curv::arithmetic::traits::*;
+use curv::cryptographic_primitives::hashing::{Digest, DigestExt};
+use curv::cryptographic_primitives::proofs::sigma_ec_ddh::{
+    ECDDHProof, ECDDHStatement, ECDDHWitness,
+};
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use curv::BigInt;
+use sha2::Sha256;
+
+use crate::utilities::adaptor::{adapt, extract_secret, verify_pre_signature, PreSignature};
+use crate::utilities::verification::{verify, Mode};
+
+fn pre_sign(x: &Scalar<Secp256k1>, Y: &Point<Secp256k1>, message: &BigInt) -> PreSignature {
+    let k = Scalar::<Secp256k1>::random();
+    let R = Point::generator() * &k;
+    let R_Y = Y * &k;
+    let proof = ECDDHProof::prove(
+        &ECDDHWitness { x: k.clone() },
+        &ECDDHStatement {
+            g1: Point::generator().to_point(),
+            h1: R.clone(),
+            g2: Y.clone(),
+            h2: R_Y.clone(),
+        },
+    );
+    let r = Scalar::<Secp256k1>::from(
+        &R_Y.x_coord()
+            .unwrap()
+            .mod_floor(Scalar::<Secp256k1>::group_order()),
+    );
+    let s_hat = k.invert().unwrap() * (Scalar::from(message) + r * x);
+    PreSignature {
+        R,
+        R_Y,
+        s_hat,
+        proof,
+    }
+}
+
+fn message() -> BigInt {
+    Sha256::new()
+        .chain_bigint(&BigInt::from_bytes(b"ZenGo"))
+        .result_bigint()
+}
+
+#[test]
+fn adapted_signature_is_valid_and_reveals_secret() {
+    let x = Scalar::<Secp256k1>::random();
+    let pk = Point::generator() * &x;
+    let y = Scalar::<Secp256k1>::random();
+    let Y = Point::generator() * &y;
+    let message = message();
+
+    let pre_signature = pre_sign(&x, &Y, &message);
+    verify_pre_signature(&pre_signature, &pk, &Y, &message).unwrap();
+
+    let signature = adapt(&pre_signature, &y).unwrap();
+    verify(&signature.r, &signature.s, &pk, &message, Mode::Strict).unwrap();
+
+    let extracted = extract_secret(&pre_signature, &signature, &Y).unwrap();
+    assert_eq!(extracted, y);
+}
+
+#[test]
+fn rejects_pre_signature_tied_to_another_point() {
+    let x = Scalar::<Secp256k1>::random();
+    let pk = Point::generator() * &x;
+    let Y = Point::generator() * Scalar::<Secp256k1>::random();
+    let message = message();
+
+    let mut pre_signature = pre_sign(&x, &Y, &message);
+    let other_Y = Point::generator() * Scalar::<Secp256k1>::random();
+    assert!(verify_pre_signature(&pre_signature, &pk, &other_Y, &message).is_err());
+
+    pre_signature.s_hat = &pre_signature.s_hat + Scalar::from(1);
+    assert!(verify_pre_signature(&pre_signature, &pk, &Y, &message).is_err());
+}
+
+#[test]
+fn extract_fails_on_unrelated_signature() {
+    let x = Scalar::<Secp256k1>::random();
+    let y = Scalar::<Secp256k1>::random();
+    let Y = Point::generator() * &y;
+    let message = message();
+
+    let pre_signature = pre_sign(&x, &Y, &message);
+    let mut signature = adapt(&pre_signature, &y).unwrap();
+    signature.s = signature.s * Scalar::from(2);
+    assert!(extract_secret(&pre_signature, &signature, &Y).is_err());
+}
@@ -1,3 +1,5 @@
+pub mod adaptor;
 pub mod mta;
+pub mod verification;
 pub mod zk_pdl;
 pub mod zk_pdl_with_slack;
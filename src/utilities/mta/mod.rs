@@ -17,7 +17,7 @@
 /// MtA is described in https://eprint.iacr.org/2019/114.pdf section 3
 use curv::arithmetic::traits::Samplable;
 use curv::cryptographic_primitives::proofs::sigma_dlog::DLogProof;
-use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Curve, Point, Scalar};
 use curv::BigInt;
 use paillier::traits::EncryptWithChosenRandomness;
 use paillier::{Add, Decrypt, Mul};
@@ -38,10 +38,11 @@ pub struct MessageA {
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct MessageB {
+#[serde(bound = "")]
+pub struct MessageB<E: Curve = Secp256k1> {
     pub c: BigInt, // paillier encryption
-    pub b_proof: DLogProof<Secp256k1, Sha256>,
-    pub beta_tag_proof: DLogProof<Secp256k1, Sha256>,
+    pub b_proof: DLogProof<E, Sha256>,
+    pub beta_tag_proof: DLogProof<E, Sha256>,
 }
 
 impl MessageA {
@@ -49,8 +50,8 @@ impl MessageA {
     /// - other parties' `h1,h2,N_tilde`s for range proofs.
     /// If range proofs are not needed (one example is identification of aborts where we
     /// only want to reconstruct a ciphertext), `dlog_statements` can be an empty slice.
-    pub fn a(
-        a: &Scalar<Secp256k1>,
+    pub fn a<E: Curve>(
+        a: &Scalar<E>,
         alice_ek: &EncryptionKey,
         dlog_statements: &[DLogStatement],
     ) -> (Self, BigInt) {
@@ -59,8 +60,8 @@ impl MessageA {
         (m_a, randomness)
     }
 
-    pub fn a_with_predefined_randomness(
-        a: &Scalar<Secp256k1>,
+    pub fn a_with_predefined_randomness<E: Curve>(
+        a: &Scalar<E>,
         alice_ek: &EncryptionKey,
         randomness: &BigInt,
         dlog_statements: &[DLogStatement],
@@ -76,7 +77,13 @@ impl MessageA {
         let alice_range_proofs = dlog_statements
             .iter()
             .map(|dlog_statement| {
-                AliceProof::generate(&a.to_bigint(), &c_a, alice_ek, dlog_statement, randomness)
+                AliceProof::generate::<E>(
+                    &a.to_bigint(),
+                    &c_a,
+                    alice_ek,
+                    dlog_statement,
+                    randomness,
+                )
             })
             .collect::<Vec<AliceProof>>();
 
@@ -87,13 +94,13 @@ impl MessageA {
     }
 }
 
-impl MessageB {
+impl<E: Curve> MessageB<E> {
     pub fn b(
-        b: &Scalar<Secp256k1>,
+        b: &Scalar<E>,
         alice_ek: &EncryptionKey,
         m_a: MessageA,
         dlog_statements: &[DLogStatement],
-    ) -> Result<(Self, Scalar<Secp256k1>, BigInt, BigInt), Error> {
+    ) -> Result<(Self, Scalar<E>, BigInt, BigInt), Error> {
         let beta_tag = BigInt::sample_below(&alice_ek.n);
         let randomness = BigInt::sample_below(&alice_ek.n);
         let (m_b, beta) = MessageB::b_with_predefined_randomness(
@@ -109,13 +116,13 @@ impl MessageB {
     }
 
     pub fn b_with_predefined_randomness(
-        b: &Scalar<Secp256k1>,
+        b: &Scalar<E>,
         alice_ek: &EncryptionKey,
         m_a: MessageA,
         randomness: &BigInt,
         beta_tag: &BigInt,
         dlog_statements: &[DLogStatement],
-    ) -> Result<(Self, Scalar<Secp256k1>), Error> {
+    ) -> Result<(Self, Scalar<E>), Error> {
         if m_a.range_proofs.len() != dlog_statements.len() {
             return Err(InvalidKey);
         }
@@ -124,12 +131,12 @@ impl MessageB {
             .range_proofs
             .iter()
             .zip(dlog_statements)
-            .map(|(proof, dlog_statement)| proof.verify(&m_a.c, alice_ek, dlog_statement))
+            .map(|(proof, dlog_statement)| proof.verify::<E>(&m_a.c, alice_ek, dlog_statement))
             .all(|x| x)
         {
             return Err(InvalidKey);
         };
-        let beta_tag_fe = Scalar::<Secp256k1>::from(beta_tag);
+        let beta_tag_fe = Scalar::<E>::from(beta_tag);
         let c_beta_tag = Paillier::encrypt_with_chosen_randomness(
             alice_ek,
             RawPlaintext::from(beta_tag),
@@ -143,7 +150,7 @@ impl MessageB {
             RawPlaintext::from(b_bn),
         );
         let c_b = Paillier::add(alice_ek, b_c_a, c_beta_tag);
-        let beta = Scalar::<Secp256k1>::zero() - &beta_tag_fe;
+        let beta = Scalar::<E>::zero() - &beta_tag_fe;
         let dlog_proof_b = DLogProof::prove(b);
         let dlog_proof_beta_tag = DLogProof::prove(&beta_tag_fe);
 
@@ -160,11 +167,11 @@ impl MessageB {
     pub fn verify_proofs_get_alpha(
         &self,
         dk: &DecryptionKey,
-        a: &Scalar<Secp256k1>,
-    ) -> Result<(Scalar<Secp256k1>, BigInt), Error> {
+        a: &Scalar<E>,
+    ) -> Result<(Scalar<E>, BigInt), Error> {
         let alice_share = Paillier::decrypt(dk, &RawCiphertext::from(self.c.clone()));
         let g = Point::generator();
-        let alpha = Scalar::<Secp256k1>::from(alice_share.0.as_ref());
+        let alpha = Scalar::<E>::from(alice_share.0.as_ref());
         let g_alpha = g * &alpha;
         let ba_btag = &self.b_proof.pk * a + &self.beta_tag_proof.pk;
         if DLogProof::verify(&self.b_proof).is_ok()
@@ -178,6 +185,12 @@ impl MessageB {
         }
     }
 
+    pub fn verify_b_against_public(public_gb: &Point<E>, mta_gb: &Point<E>) -> bool {
+        public_gb == mta_gb
+    }
+}
+
+impl MessageB<Secp256k1> {
     //  another version, supporting PartyPrivate therefore binding mta to gg18.
     //  with the regular version mta can be used in general
     pub fn verify_proofs_get_alpha_gg18(
@@ -200,13 +213,6 @@ impl MessageB {
             Err(InvalidKey)
         }
     }
-
-    pub fn verify_b_against_public(
-        public_gb: &Point<Secp256k1>,
-        mta_gb: &Point<Secp256k1>,
-    ) -> bool {
-        public_gb == mta_gb
-    }
 }
 
 pub mod range_proofs;
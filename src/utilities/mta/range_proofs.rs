@@ -11,7 +11,7 @@
 
 use curv::arithmetic::traits::*;
 use curv::cryptographic_primitives::hashing::{Digest, DigestExt};
-use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Curve, Point, Scalar};
 use curv::BigInt;
 use sha2::Sha256;
 
@@ -102,11 +102,22 @@ pub struct AliceProof {
 
 impl AliceProof {
     /// verify Alice's proof using the proof and public keys
-    pub fn verify(
+    pub fn verify<E: Curve>(
+        &self,
+        cipher: &BigInt,
+        alice_ek: &EncryptionKey,
+        dlog_statement: &DLogStatement,
+    ) -> bool {
+        self.verify_with_context::<E>(cipher, alice_ek, dlog_statement, &[])
+    }
+
+    /// Verifies Alice's proof bound to `context`, see [AliceProof::generate_with_context]
+    pub fn verify_with_context<E: Curve>(
         &self,
         cipher: &BigInt,
         alice_ek: &EncryptionKey,
         dlog_statement: &DLogStatement,
+        context: &[u8],
     ) -> bool {
         let N = &alice_ek.n;
         let NN = &alice_ek.nn;
@@ -115,7 +126,7 @@ impl AliceProof {
         let h2 = &dlog_statement.ni;
         let Gen = alice_ek.n.borrow() + 1;
 
-        if self.s1 > Scalar::<Secp256k1>::group_order().pow(3) {
+        if self.s1 > Scalar::<E>::group_order().pow(3) {
             return false;
         }
 
@@ -141,6 +152,7 @@ impl AliceProof {
         let u = (gs1 * BigInt::mod_pow(&self.s, N, NN) * cipher_e_inv) % NN;
 
         let e = Sha256::new()
+            .chain(context)
             .chain_bigint(N)
             .chain_bigint(&Gen)
             .chain_bigint(cipher)
@@ -156,23 +168,35 @@ impl AliceProof {
     }
     /// Create the proof using Alice's Paillier private keys and public ZKP setup.
     /// Requires randomness used for encrypting Alice's secret a.
-    /// It is assumed that secp256k1 curve is used.
-    pub fn generate(
+    /// Range of Alice's secret is bound by order of curve `E`.
+    pub fn generate<E: Curve>(
         a: &BigInt,
         cipher: &BigInt,
         alice_ek: &EncryptionKey,
         dlog_statement: &DLogStatement,
         r: &BigInt,
     ) -> Self {
-        let round1 = AliceZkpRound1::from(
-            alice_ek,
-            dlog_statement,
-            a,
-            Scalar::<Secp256k1>::group_order(),
-        );
+        Self::generate_with_context::<E>(a, cipher, alice_ek, dlog_statement, r, &[])
+    }
+
+    /// Creates the proof bound to `context`, so it's only valid in the same context
+    ///
+    /// Context is hashed first into Fiat-Shamir challenge, so it must be of fixed length (e.g. a
+    /// hash of session id, prover and verifier). Empty context gives the same proof as
+    /// [AliceProof::generate].
+    pub fn generate_with_context<E: Curve>(
+        a: &BigInt,
+        cipher: &BigInt,
+        alice_ek: &EncryptionKey,
+        dlog_statement: &DLogStatement,
+        r: &BigInt,
+        context: &[u8],
+    ) -> Self {
+        let round1 = AliceZkpRound1::from(alice_ek, dlog_statement, a, Scalar::<E>::group_order());
 
         let Gen = alice_ek.n.borrow() + 1;
         let e = Sha256::new()
+            .chain(context)
             .chain_bigint(&alice_ek.n)
             .chain_bigint(&Gen)
             .chain_bigint(cipher)
@@ -325,6 +349,18 @@ impl BobProof {
         alice_ek: &EncryptionKey,
         dlog_statement: &DLogStatement,
         check: Option<&BobCheck>,
+    ) -> bool {
+        self.verify_with_context(a_enc, mta_avc_out, alice_ek, dlog_statement, check, &[])
+    }
+
+    fn verify_with_context(
+        &self,
+        a_enc: &BigInt,
+        mta_avc_out: &BigInt,
+        alice_ek: &EncryptionKey,
+        dlog_statement: &DLogStatement,
+        check: Option<&BobCheck>,
+        context: &[u8],
     ) -> bool {
         let N = &alice_ek.n;
         let NN = &alice_ek.nn;
@@ -395,12 +431,12 @@ impl BobProof {
                 values_to_hash.push(&u_y_coor);
                 values_to_hash
                     .into_iter()
-                    .fold(Sha256::new(), |acc, b| acc.chain_bigint(b))
+                    .fold(Sha256::new().chain(context), |acc, b| acc.chain_bigint(b))
                     .result_bigint()
             }
             None => values_to_hash
                 .into_iter()
-                .fold(Sha256::new(), |acc, b| acc.chain_bigint(b))
+                .fold(Sha256::new().chain(context), |acc, b| acc.chain_bigint(b))
                 .result_bigint(),
         };
 
@@ -420,6 +456,30 @@ impl BobProof {
         dlog_statement: &DLogStatement,
         r: &Randomness,
         check: bool,
+    ) -> (BobProof, Option<Point<Secp256k1>>) {
+        Self::generate_with_context(
+            a_encrypted,
+            mta_encrypted,
+            b,
+            beta_prim,
+            alice_ek,
+            dlog_statement,
+            r,
+            check,
+            &[],
+        )
+    }
+
+    fn generate_with_context(
+        a_encrypted: &BigInt,
+        mta_encrypted: &BigInt,
+        b: &Scalar<Secp256k1>,
+        beta_prim: &BigInt,
+        alice_ek: &EncryptionKey,
+        dlog_statement: &DLogStatement,
+        r: &Randomness,
+        check: bool,
+        context: &[u8],
     ) -> (BobProof, Option<Point<Secp256k1>>) {
         let round1 = BobZkpRound1::from(
             alice_ek,
@@ -460,12 +520,12 @@ impl BobProof {
             values_to_hash.push(&u_y_coor);
             values_to_hash
                 .into_iter()
-                .fold(Sha256::new(), |acc, b| acc.chain_bigint(b))
+                .fold(Sha256::new().chain(context), |acc, b| acc.chain_bigint(b))
                 .result_bigint()
         } else {
             values_to_hash
                 .into_iter()
-                .fold(Sha256::new(), |acc, b| acc.chain_bigint(b))
+                .fold(Sha256::new().chain(context), |acc, b| acc.chain_bigint(b))
                 .result_bigint()
         };
 
@@ -496,6 +556,58 @@ pub struct BobProofExt {
 
 #[allow(clippy::too_many_arguments)]
 impl BobProofExt {
+    /// Creates the proof of MtAwc, binding `b` to its public counterpart $`B = g^b`$
+    pub fn generate(
+        a_encrypted: &BigInt,
+        mta_encrypted: &BigInt,
+        b: &Scalar<Secp256k1>,
+        beta_prim: &BigInt,
+        alice_ek: &EncryptionKey,
+        dlog_statement: &DLogStatement,
+        r: &Randomness,
+    ) -> BobProofExt {
+        Self::generate_with_context(
+            a_encrypted,
+            mta_encrypted,
+            b,
+            beta_prim,
+            alice_ek,
+            dlog_statement,
+            r,
+            &[],
+        )
+    }
+
+    /// Creates the proof bound to `context`, see [AliceProof::generate_with_context]
+    pub fn generate_with_context(
+        a_encrypted: &BigInt,
+        mta_encrypted: &BigInt,
+        b: &Scalar<Secp256k1>,
+        beta_prim: &BigInt,
+        alice_ek: &EncryptionKey,
+        dlog_statement: &DLogStatement,
+        r: &Randomness,
+        context: &[u8],
+    ) -> BobProofExt {
+        // proving a basic proof (with modified hash)
+        let (bob_proof, u) = BobProof::generate_with_context(
+            a_encrypted,
+            mta_encrypted,
+            b,
+            beta_prim,
+            alice_ek,
+            dlog_statement,
+            r,
+            true,
+            context,
+        );
+
+        BobProofExt {
+            proof: bob_proof,
+            u: u.expect("u is always returned when check is requested"),
+        }
+    }
+
     pub fn verify(
         &self,
         a_enc: &BigInt,
@@ -503,9 +615,22 @@ impl BobProofExt {
         alice_ek: &EncryptionKey,
         dlog_statement: &DLogStatement,
         X: &Point<Secp256k1>,
+    ) -> bool {
+        self.verify_with_context(a_enc, mta_avc_out, alice_ek, dlog_statement, X, &[])
+    }
+
+    /// Verifies the proof bound to `context`, see [BobProofExt::generate_with_context]
+    pub fn verify_with_context(
+        &self,
+        a_enc: &BigInt,
+        mta_avc_out: &BigInt,
+        alice_ek: &EncryptionKey,
+        dlog_statement: &DLogStatement,
+        X: &Point<Secp256k1>,
+        context: &[u8],
     ) -> bool {
         // check basic proof first
-        if !self.proof.verify(
+        if !self.proof.verify_with_context(
             a_enc,
             mta_avc_out,
             alice_ek,
@@ -514,6 +639,7 @@ impl BobProofExt {
                 u: self.u.clone(),
                 X: X.clone(),
             }),
+            context,
         ) {
             return false;
         }
@@ -562,33 +688,6 @@ pub(crate) mod tests {
     use paillier::traits::{Encrypt, EncryptWithChosenRandomness, KeyGeneration};
     use paillier::{Add, DecryptionKey, Mul, Paillier, RawCiphertext, RawPlaintext};
 
-    fn generate(
-        a_encrypted: &BigInt,
-        mta_encrypted: &BigInt,
-        b: &Scalar<Secp256k1>,
-        beta_prim: &BigInt,
-        alice_ek: &EncryptionKey,
-        dlog_statement: &DLogStatement,
-        r: &Randomness,
-    ) -> BobProofExt {
-        // proving a basic proof (with modified hash)
-        let (bob_proof, u) = BobProof::generate(
-            a_encrypted,
-            mta_encrypted,
-            b,
-            beta_prim,
-            alice_ek,
-            dlog_statement,
-            r,
-            true,
-        );
-
-        BobProofExt {
-            proof: bob_proof,
-            u: u.unwrap(),
-        }
-    }
-
     pub(crate) fn generate_init() -> (DLogStatement, EncryptionKey, DecryptionKey) {
         let (ek_tilde, dk_tilde) = Paillier::keypair().keys();
         let one = BigInt::one();
@@ -628,9 +727,9 @@ pub(crate) mod tests {
         .clone()
         .into_owned();
 
-        let alice_proof = AliceProof::generate(&a, &cipher, &ek, &dlog_statement, &r);
+        let alice_proof = AliceProof::generate::<Secp256k1>(&a, &cipher, &ek, &dlog_statement, &r);
 
-        assert!(alice_proof.verify(&cipher, &ek, &dlog_statement));
+        assert!(alice_proof.verify::<Secp256k1>(&cipher, &ek, &dlog_statement));
     }
 
     #[test]
@@ -688,7 +787,7 @@ pub(crate) mod tests {
                 // Bob follows MtAwc
                 let ec_gen = Point::generator();
                 let X = ec_gen * &b;
-                let bob_proof = generate(
+                let bob_proof = BobProofExt::generate(
                     &encrypted_a,
                     &mta_out.0.clone().into_owned(),
                     &b,
@@ -0,0 +1,185 @@
+#![allow(non_snake_case)]
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+//! ECDSA signature verification and public key recovery shared by all protocols in this crate
+//!
+//! GG18, GG20 and the two-party protocols output plain ECDSA signatures, so they all verify them
+//! through this module. Two verification modes are provided:
+//! * [Mode::Strict] rejects high-S signatures (`s > q/2`), as required by Bitcoin (BIP-62/BIP-146)
+//!   and Ethereum (EIP-2) consensus rules. Every signature produced by this library is normalized
+//!   to low-S, so it always passes strict verification.
+//! * [Mode::Lax] accepts both `s` and `q - s`, i.e. plain SEC1 ECDSA verification.
+//!
+//! In both modes `r` and `s` must be non-zero and the public key must not be the point at infinity.
+
+use std::cmp;
+
+use curv::arithmetic::traits::*;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Curve, Point, Scalar};
+use curv::BigInt;
+use serde::{Deserialize, Serialize};
+
+use crate::Error::{self, InvalidSig};
+
+/// ECDSA signature `(r, s)` along with recovery id
+///
+/// Recovery id bit 0 is the parity of y coordinate of the nonce point `R`, bit 1 is set if x
+/// coordinate of `R` is greater or equal to curve order (i.e. `r = R.x - q`).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct SignatureRecid<E: Curve = Secp256k1> {
+    pub r: Scalar<E>,
+    pub s: Scalar<E>,
+    pub recid: u8,
+}
+
+/// Verification mode, see [module level documentation](self)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    Strict,
+    Lax,
+}
+
+/// Verifies ECDSA signature `(r, s)` of `message` under public key `y`
+///
+/// `message` is a hash of signed data interpreted as a big-endian integer, it's reduced modulo
+/// curve order.
+pub fn verify<E: Curve>(
+    r: &Scalar<E>,
+    s: &Scalar<E>,
+    y: &Point<E>,
+    message: &BigInt,
+    mode: Mode,
+) -> Result<(), Error> {
+    if r.is_zero() || s.is_zero() || y.is_zero() {
+        return Err(InvalidSig);
+    }
+    if mode == Mode::Strict && is_high_s(s) {
+        return Err(InvalidSig);
+    }
+
+    let s_inv = s.invert().ok_or(InvalidSig)?;
+    let e = Scalar::<E>::from(message);
+    let u1 = e * &s_inv;
+    let u2 = r * &s_inv;
+
+    // u1 G + u2 y
+    let x = double_scalar_mul(&u1, &Point::generator().to_point(), &u2, y)
+        .x_coord()
+        .ok_or(InvalidSig)?;
+    if *r == Scalar::<E>::from(&x.mod_floor(Scalar::<E>::group_order())) {
+        Ok(())
+    } else {
+        Err(InvalidSig)
+    }
+}
+
+/// Recovers public key from signature and signed `message`
+///
+/// Returns an error if signature is malformed (zero `r` or `s`, recovery id greater than 3) or
+/// if there's no public key that matches given signature. Recovered key is not checked against
+/// any mode — call [verify] if a high-S signature must be rejected.
+pub fn recover_public_key<E: Curve>(
+    sig: &SignatureRecid<E>,
+    message: &BigInt,
+) -> Result<Point<E>, Error> {
+    if sig.r.is_zero() || sig.s.is_zero() || sig.recid > 3 {
+        return Err(InvalidSig);
+    }
+
+    // Restore the nonce point R from its x coordinate and y parity
+    let x = if sig.recid & 2 == 0 {
+        sig.r.to_bigint()
+    } else {
+        sig.r.to_bigint() + Scalar::<E>::group_order()
+    };
+    let field_size = Point::<E>::generator().to_bytes(true).len() - 1;
+    let x_bytes = x.to_bytes();
+    if x_bytes.len() > field_size {
+        return Err(InvalidSig);
+    }
+    let mut encoded = vec![0u8; field_size + 1];
+    encoded[0] = 2 | (sig.recid & 1);
+    encoded[1 + field_size - x_bytes.len()..].copy_from_slice(&x_bytes);
+    let nonce_point = Point::<E>::from_bytes(&encoded).map_err(|_| InvalidSig)?;
+
+    // y = r^-1 (s R - e G)
+    let r_inv = sig.r.invert().ok_or(InvalidSig)?;
+    let e = Scalar::<E>::from(message);
+    let u1 = &sig.s * &r_inv;
+    let u2 = -(e * &r_inv);
+    let y = double_scalar_mul(&u1, &nonce_point, &u2, &Point::generator().to_point());
+    if y.is_zero() {
+        return Err(InvalidSig);
+    }
+    Ok(y)
+}
+
+/// Brings `s` to low-S form and computes recovery id for signature with nonce point `R`
+///
+/// Returns `min(s, q - s)` and recovery id matching the returned value.
+pub fn normalize_s<E: Curve>(R: &Point<E>, s: Scalar<E>) -> Result<(Scalar<E>, u8), Error> {
+    let x = R.x_coord().ok_or(InvalidSig)?;
+    let y = R.y_coord().ok_or(InvalidSig)?;
+
+    let mut recid = if y.test_bit(0) { 1 } else { 0 };
+    if &x >= Scalar::<E>::group_order() {
+        recid |= 2;
+    }
+    if is_high_s(&s) {
+        Ok((-s, recid ^ 1))
+    } else {
+        Ok((s, recid))
+    }
+}
+
+/// Checks whether `s` is greater than half of the curve order
+pub fn is_high_s<E: Curve>(s: &Scalar<E>) -> bool {
+    let s = s.to_bigint();
+    s > Scalar::<E>::group_order() - &s
+}
+
+/// Computes `a * P + b * Q` with Shamir's trick
+///
+/// Both points are processed within a single double-and-add pass. It runs in variable time, so it
+/// must only be used with public inputs (e.g. in signature verification).
+pub fn double_scalar_mul<E: Curve>(
+    a: &Scalar<E>,
+    P: &Point<E>,
+    b: &Scalar<E>,
+    Q: &Point<E>,
+) -> Point<E> {
+    let a = a.to_bigint();
+    let b = b.to_bigint();
+    let P_plus_Q = P + Q;
+
+    let bits = cmp::max(a.bit_length(), b.bit_length());
+    let mut acc = Point::<E>::zero();
+    for i in (0..bits).rev() {
+        acc = &acc + &acc;
+        match (a.test_bit(i), b.test_bit(i)) {
+            (true, true) => acc = acc + &P_plus_Q,
+            (true, false) => acc = acc + P,
+            (false, true) => acc = acc + Q,
+            (false, false) => (),
+        }
+    }
+    acc
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,126 @@
+#![allow(non_snake_case)]
+
+use curv::arithmetic::traits::*;
+use curv::cryptographic_primitives::hashing::{Digest, DigestExt};
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use curv::BigInt;
+use sha2::Sha256;
+
+use crate::utilities::verification::{
+    double_scalar_mul, is_high_s, normalize_s, recover_public_key, verify, Mode, SignatureRecid,
+};
+
+fn sign(x: &Scalar<Secp256k1>, message: &BigInt) -> SignatureRecid {
+    let k = Scalar::<Secp256k1>::random();
+    let R = Point::generator() * &k;
+    let r = Scalar::<Secp256k1>::from(
+        &R.x_coord()
+            .unwrap()
+            .mod_floor(Scalar::<Secp256k1>::group_order()),
+    );
+    let s = k.invert().unwrap() * (Scalar::<Secp256k1>::from(message) + &r * x);
+    let (s, recid) = normalize_s(&R, s).unwrap();
+    SignatureRecid { r, s, recid }
+}
+
+fn message() -> BigInt {
+    Sha256::new()
+        .chain_bigint(&BigInt::from_bytes(b"ZenGo"))
+        .result_bigint()
+}
+
+#[test]
+fn test_verify_low_s() {
+    let x = Scalar::<Secp256k1>::random();
+    let y = Point::generator() * &x;
+    let message = message();
+
+    for _ in 0..10 {
+        let sig = sign(&x, &message);
+        assert!(!is_high_s(&sig.s));
+        verify(&sig.r, &sig.s, &y, &message, Mode::Strict).unwrap();
+        verify(&sig.r, &sig.s, &y, &message, Mode::Lax).unwrap();
+    }
+}
+
+#[test]
+fn test_verify_high_s() {
+    let x = Scalar::<Secp256k1>::random();
+    let y = Point::generator() * &x;
+    let message = message();
+
+    let sig = sign(&x, &message);
+    let high_s = -&sig.s;
+    assert!(is_high_s(&high_s));
+    assert!(verify(&sig.r, &high_s, &y, &message, Mode::Strict).is_err());
+    verify(&sig.r, &high_s, &y, &message, Mode::Lax).unwrap();
+}
+
+#[test]
+fn test_verify_rejects_malformed() {
+    let x = Scalar::<Secp256k1>::random();
+    let y = Point::generator() * &x;
+    let message = message();
+    let sig = sign(&x, &message);
+    let zero = Scalar::<Secp256k1>::zero();
+
+    for mode in [Mode::Strict, Mode::Lax] {
+        assert!(verify(&zero, &sig.s, &y, &message, mode).is_err());
+        assert!(verify(&sig.r, &zero, &y, &message, mode).is_err());
+        assert!(verify(&sig.r, &sig.s, &Point::zero(), &message, mode).is_err());
+        assert!(verify(&sig.r, &sig.s, &y, &(&message + BigInt::one()), mode).is_err());
+    }
+}
+
+#[test]
+fn test_recover_public_key() {
+    let x = Scalar::<Secp256k1>::random();
+    let y = Point::generator() * &x;
+    let message = message();
+
+    for _ in 0..10 {
+        let sig = sign(&x, &message);
+        assert_eq!(recover_public_key(&sig, &message).unwrap(), y);
+
+        let wrong_recid = SignatureRecid {
+            recid: sig.recid ^ 1,
+            ..sig.clone()
+        };
+        assert_ne!(recover_public_key(&wrong_recid, &message).unwrap(), y);
+    }
+}
+
+#[test]
+fn test_recover_rejects_malformed() {
+    let x = Scalar::<Secp256k1>::random();
+    let message = message();
+    let sig = sign(&x, &message);
+
+    let bad_recid = SignatureRecid {
+        recid: 4,
+        ..sig.clone()
+    };
+    assert!(recover_public_key(&bad_recid, &message).is_err());
+    let zero_r = SignatureRecid {
+        r: Scalar::zero(),
+        ..sig.clone()
+    };
+    assert!(recover_public_key(&zero_r, &message).is_err());
+    let zero_s = SignatureRecid {
+        s: Scalar::zero(),
+        ..sig
+    };
+    assert!(recover_public_key(&zero_s, &message).is_err());
+}
+
+#[test]
+fn test_double_scalar_mul() {
+    let a = Scalar::<Secp256k1>::random();
+    let b = Scalar::<Secp256k1>::random();
+    let P = Point::generator() * Scalar::<Secp256k1>::random();
+    let Q = Point::generator() * Scalar::<Secp256k1>::random();
+
+    assert_eq!(double_scalar_mul(&a, &P, &b, &Q), &P * &a + &Q * &b);
+    assert_eq!(double_scalar_mul(&a, &P, &Scalar::zero(), &Q), &P * &a);
+    assert!(double_scalar_mul(&Scalar::zero(), &P, &Scalar::zero(), &Q).is_zero());
+}